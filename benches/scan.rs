@@ -0,0 +1,106 @@
+//! Compares scanning carriers read through `File` with scanning them memory-mapped.
+//!
+//! Run with `cargo bench --features mmap`. Set `STEGANO_BENCH_MB` to change the size of the
+//! synthetic TIFF (default 512 MiB).
+
+use clap::Parser;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use stegano::cli::ShowMetaCmd;
+use stegano::mapped::MappedFile;
+use stegano::models::write_listing;
+use stegano::png::{encode_grayscale, insert_chunk, ChunkFilter};
+use stegano::tiff::TiffFile;
+
+/// Number of small chunks in the synthetic PNG.
+const PNG_CHUNKS: usize = 200_000;
+
+/// Runs `f` a few times and returns the fastest run.
+fn best_of<F: FnMut()>(runs: usize, mut f: F) -> Duration {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+/// Prints the timings of both readers and the speedup.
+fn report(name: &str, read: Duration, mapped: Duration) {
+    println!(
+        "{:<28} read: {:>10.2?}  mmap: {:>10.2?}  speedup: {:.1}x",
+        name,
+        read,
+        mapped,
+        read.as_secs_f64() / mapped.as_secs_f64().max(1e-9)
+    );
+}
+
+/// Writes a little-endian TIFF with one IFD up front and `size` bytes of strip data after it.
+fn write_tiff(path: &Path, size: usize) {
+    let mut file = File::create(path).unwrap();
+    file.write_all(&[
+        0x49, 0x49, 0x2A, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x03, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ])
+    .unwrap();
+    let block = vec![0u8; 1 << 20];
+    for _ in 0..size.div_ceil(block.len()) {
+        file.write_all(&block).unwrap();
+    }
+}
+
+/// Writes a PNG holding `PNG_CHUNKS` small ancillary chunks before IEND.
+fn write_png(path: &Path) {
+    let png = encode_grayscale(1, 1, &[0]).unwrap();
+    let (png, offset) = insert_chunk(&png, b"tEXt", b"k\0v", None).unwrap();
+    let chunk = png[offset..offset + 15].to_vec();
+    let mut out = png[..offset].to_vec();
+    for _ in 0..PNG_CHUNKS {
+        out.extend_from_slice(&chunk);
+    }
+    out.extend_from_slice(&png[offset + 15..]);
+    fs::write(path, out).unwrap();
+}
+
+fn main() {
+    let dir = std::env::temp_dir();
+    let mb: usize = std::env::var("STEGANO_BENCH_MB")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(512);
+
+    // The IFD chain sits in the first page, the strip data is never looked at.
+    let tiff = dir.join("stegano-bench-scan.tiff");
+    write_tiff(&tiff, mb << 20);
+    let read = best_of(3, || {
+        TiffFile::parse(fs::read(&tiff).unwrap()).unwrap();
+    });
+    let mapped = best_of(3, || {
+        TiffFile::parse_ifds(&MappedFile::open(&tiff).unwrap()).unwrap();
+    });
+    report(&format!("TIFF IFDs ({} MiB)", mb), read, mapped);
+    fs::remove_file(&tiff).unwrap();
+
+    // show-meta walks the chunk list by the declared lengths.
+    let png = dir.join("stegano-bench-scan.png");
+    write_png(&png);
+    let n = (PNG_CHUNKS + 3).to_string();
+    let path = png.to_string_lossy().into_owned();
+    let cmd = ShowMetaCmd::parse_from(["show-meta", "-i", &path, "-r", "-n", &n, "-e", &n]);
+    let filter = ChunkFilter::default();
+    let read = best_of(3, || {
+        let data = fs::read(&png).unwrap();
+        write_listing(&data, &cmd, &filter, &mut io::sink()).unwrap();
+    });
+    let mapped = best_of(3, || {
+        let data = MappedFile::open(&png).unwrap();
+        write_listing(&data, &cmd, &filter, &mut io::sink()).unwrap();
+    });
+    report(&format!("PNG chunks ({})", PNG_CHUNKS), read, mapped);
+    fs::remove_file(&png).unwrap();
+}
@@ -0,0 +1,158 @@
+//! Measures the throughput of embedding and extracting payloads, of the ciphers and of the
+//! Huffman coder, on carriers of the sizes photos and screenshots come in.
+//!
+//! Run with `cargo bench --bench throughput`, or `cargo bench --bench throughput -- cipher` for
+//! one group. Set `STEGANO_BENCH_SIZES` to a comma-separated list of square carrier sizes in
+//! pixels (default 256,1024,2048) and `STEGANO_BENCH_MB` to the payload size of the cipher runs
+//! (default 4 MiB). A 4K carrier is always run with the LSB method; `--features rayon` applies
+//! its bits in parallel. Criterion keeps the previous run of each group in `target/criterion`
+//! and reports the change against it.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::io::Cursor;
+use stegano::codec::{decode_bytes, encode_bytes, CodecOptions};
+use stegano::jpeg::huff::Huffman;
+use stegano::png::encode_rgb;
+use stegano::prng::Prng;
+use stegano::utils::{decrypt_with_mode, encrypt_stream_mode, CipherMode, Padding};
+
+/// The ciphers compared, as (algorithm, block mode): AES in each mode, XOR and ChaCha20.
+const CIPHERS: [(&str, &str); 6] = [
+    ("aes", "ecb"),
+    ("aes", "cbc"),
+    ("aes", "ctr"),
+    ("aes256", "ctr"),
+    ("chacha", "ctr"),
+    ("xor", "ecb"),
+];
+
+/// Returns `len` bytes of noise, which neither compresses nor repeats.
+fn noise(len: usize, seed: u64) -> Vec<u8> {
+    let mut rng = Prng::new(seed);
+    (0..len).map(|_| rng.next_u64() as u8).collect()
+}
+
+/// Reads a numeric setting from the environment, or returns `default`.
+fn setting(name: &str, default: Vec<usize>) -> Vec<usize> {
+    std::env::var(name)
+        .ok()
+        .map(|value| {
+            value
+                .split(',')
+                .filter_map(|size| size.parse().ok())
+                .collect()
+        })
+        .unwrap_or(default)
+}
+
+/// Embeds a payload with `method` and takes it back out, both measured against the carrier.
+fn embed_and_extract(c: &mut Criterion, label: &str, carrier: &[u8], payload: &[u8], method: &str) {
+    let opts = CodecOptions {
+        method: method.into(),
+        ..CodecOptions::default()
+    };
+    let stego = encode_bytes(carrier, payload, &opts).unwrap();
+    let mut group = c.benchmark_group(format!("{} ({})", method, label));
+    group.throughput(Throughput::Bytes(carrier.len() as u64));
+    group.sample_size(10);
+    group.bench_function("embed", |b| {
+        b.iter(|| encode_bytes(carrier, payload, &opts).unwrap())
+    });
+    group.bench_function("extract", |b| {
+        b.iter(|| decode_bytes(&stego, &opts).unwrap())
+    });
+    group.finish();
+}
+
+fn carriers(c: &mut Criterion) {
+    for side in setting("STEGANO_BENCH_SIZES", vec![256, 1024, 2048]) {
+        let carrier = encode_rgb(side, side, &noise(side * side * 3, side as u64)).unwrap();
+        let label = format!("{0}x{0}, {1} KiB", side, carrier.len() >> 10);
+        // A payload a tenth the size of the carrier, and a quarter of what the LSB method holds.
+        embed_and_extract(c, &label, &carrier, &noise(carrier.len() / 10, 1), "chunk");
+        embed_and_extract(c, &label, &carrier, &noise(side * side * 3 / 32, 2), "lsb");
+    }
+
+    // A 4K frame filled to two thirds of what the LSB method holds, where the scattered bit
+    // writes weigh the most; compare with `--features rayon`.
+    let (width, height) = (3840, 2160);
+    let carrier = encode_rgb(width, height, &noise(width * height * 3, 4)).unwrap();
+    let label = format!("{}x{}, {} KiB", width, height, carrier.len() >> 10);
+    embed_and_extract(c, &label, &carrier, &noise(width * height / 4, 5), "lsb");
+}
+
+fn ciphers(c: &mut Criterion) {
+    let mb = setting("STEGANO_BENCH_MB", vec![4])
+        .first()
+        .copied()
+        .unwrap_or(4);
+    let payload = noise(mb << 20, 3);
+    // One group each way, so that the report lines the ciphers up against each other.
+    let mut encrypt = c.benchmark_group(format!("cipher encrypt ({} MiB)", mb));
+    encrypt.throughput(Throughput::Bytes(payload.len() as u64));
+    encrypt.sample_size(10);
+    let mut encrypted = Vec::new();
+    for (algorithm, mode) in CIPHERS {
+        let mode = CipherMode::new(mode).unwrap();
+        let id = BenchmarkId::new(algorithm, mode.name());
+        let mut out = Vec::with_capacity(payload.len() + 16);
+        encrypt.bench_function(id, |b| {
+            b.iter(|| {
+                out.clear();
+                encrypt_stream_mode(
+                    algorithm,
+                    "key",
+                    &mode,
+                    Padding::Pkcs7,
+                    Cursor::new(&payload),
+                    &mut out,
+                )
+                .unwrap()
+            })
+        });
+        encrypted.push((algorithm, mode, out));
+    }
+    encrypt.finish();
+
+    let mut decrypt = c.benchmark_group(format!("cipher decrypt ({} MiB)", mb));
+    decrypt.throughput(Throughput::Bytes(payload.len() as u64));
+    decrypt.sample_size(10);
+    for (algorithm, mode, out) in &encrypted {
+        decrypt.bench_function(BenchmarkId::new(*algorithm, mode.name()), |b| {
+            b.iter(|| decrypt_with_mode(algorithm, "key", mode, Padding::Pkcs7, out).unwrap())
+        });
+    }
+    decrypt.finish();
+}
+
+fn huffman(c: &mut Criterion) {
+    // The entropy coding of a 2048x2048 greyscale JPEG, one 8x8 block at a time.
+    let mut rng = Prng::new(4);
+    let blocks: Vec<[i32; 64]> = (0..256 * 256)
+        .map(|_| {
+            std::array::from_fn(|k| (rng.below((64 >> (k / 8)).max(2)) as i32) - (32 >> (k / 8)))
+        })
+        .collect();
+    let mut out = Vec::new();
+    let encode = |out: &mut Vec<u8>| {
+        out.clear();
+        let mut huffman = Huffman::new(2048, 2048);
+        let mut prec = 0;
+        for block in &blocks {
+            huffman.huffman_block_encoder(out, block, prec, 0, 0);
+            prec = block[0];
+        }
+        huffman.flush_buffer(out);
+    };
+    encode(&mut out);
+    let mut group = c.benchmark_group("huffman");
+    group.throughput(Throughput::Bytes(out.len() as u64));
+    group.sample_size(10);
+    group.bench_function(format!("encode ({} blocks)", blocks.len()), |b| {
+        b.iter(|| encode(&mut out))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, carriers, ciphers, huffman);
+criterion_main!(benches);
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegano::flac::FlacFile;
+use stegano::strict::check_flac;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(flac) = FlacFile::parse(data) {
+        flac.stream_info();
+        let _ = flac.extract_payload();
+        let _ = flac.remove_payload();
+    }
+    let _ = check_flac(data);
+});
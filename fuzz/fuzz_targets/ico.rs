@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegano::ico::IcoFile;
+use stegano::strict::check_ico;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(ico) = IcoFile::parse(data.to_vec()) {
+        for entry in &ico.entries {
+            ico.entry_format(entry);
+        }
+        let _ = ico.extract_payload();
+    }
+    let _ = check_ico(data);
+});
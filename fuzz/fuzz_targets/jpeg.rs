@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegano::icc::extract_icc;
+use stegano::jpeg::markers::MarkerInventory;
+use stegano::jpeg::utils::parse_jpeg_headers;
+use stegano::strict::check_jpeg;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = parse_jpeg_headers(data, 0, 100, 100);
+    let _ = check_jpeg(data);
+    let _ = MarkerInventory::parse(data);
+    let _ = extract_icc(data, "jpeg");
+});
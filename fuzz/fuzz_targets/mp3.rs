@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegano::mp3::{describe_frame, Mp3File};
+use stegano::strict::check_mp3;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(mp3) = Mp3File::parse(data) {
+        if let Some(tag) = &mp3.tag {
+            for frame in &tag.frames {
+                describe_frame(&frame.id, &data[frame.body()..frame.end()]);
+            }
+        }
+        let _ = mp3.extract_payload();
+        let _ = mp3.remove_payload();
+    }
+    let _ = check_mp3(data);
+});
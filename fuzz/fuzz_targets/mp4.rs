@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegano::mp4::Mp4File;
+use stegano::strict::check_mp4;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(mp4) = Mp4File::parse(data) {
+        mp4.brands();
+        let _ = mp4.extract_payload();
+        let _ = mp4.remove_payload();
+    }
+    let _ = check_mp4(data);
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegano::apng::parse_animation;
+use stegano::icc::extract_icc;
+use stegano::png::{read_chunks, PngImage};
+use stegano::strict::check_png;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(chunks) = read_chunks(data) {
+        let _ = parse_animation(&chunks);
+    }
+    let _ = PngImage::decode(data);
+    let _ = check_png(data);
+    let _ = extract_icc(data, "png");
+});
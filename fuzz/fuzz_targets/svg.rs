@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegano::strict::check_svg;
+use stegano::svg::{SvgFile, SvgMethod};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(svg) = SvgFile::parse(data) {
+        for method in SvgMethod::ALL {
+            let _ = svg.capacity(method);
+            let _ = svg.extract_payload(method);
+            let _ = svg.remove_payload(method);
+        }
+    }
+    let _ = check_svg(data);
+});
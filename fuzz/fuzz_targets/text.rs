@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegano::strict::check_text;
+use stegano::text::{TextFile, TextMethod};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = TextFile::parse(data) {
+        for method in TextMethod::ALL {
+            let _ = text.extract_payload(method);
+            let _ = text.remove_payload(method);
+        }
+    }
+    let _ = check_text(data);
+});
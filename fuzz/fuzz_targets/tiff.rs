@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegano::strict::check_tiff;
+use stegano::tiff::TiffFile;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(tiff) = TiffFile::parse(data.to_vec()) {
+        let _ = tiff.extract_payload();
+        if let Ok((stego, offset)) = tiff.embed_in_tag(b"payload") {
+            assert_eq!(&stego[offset..offset + 4], b"payl");
+        }
+    }
+    let _ = check_tiff(data);
+});
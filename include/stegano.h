@@ -0,0 +1,112 @@
+#ifndef STEGANO_H
+#define STEGANO_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Embedding options. Any NULL field, or a NULL options pointer, falls back to the CLI
+ * defaults: "png", "auto", "aes", "key" and "stEg".
+ */
+typedef struct SteganoOptions {
+  /**
+   * The carrier type: "png", "tiff" or "ico".
+   */
+  const char *file_type;
+  /**
+   * The embedding method: "auto", "append", "precision", "lsb", "tag", "strip" or "slack".
+   */
+  const char *method;
+  /**
+   * The encryption algorithm: "aes" or "xor".
+   */
+  const char *algorithm;
+  /**
+   * The encryption key.
+   */
+  const char *key;
+  /**
+   * The PNG chunk holding the payload.
+   */
+  const char *chunk_name;
+} SteganoOptions;
+
+/**
+ * A byte buffer allocated by the library, released with `stegano_buffer_free`.
+ */
+typedef struct SteganoBuffer {
+  /**
+   * Pointer to the first byte, or NULL for an empty buffer.
+   */
+  uint8_t *data;
+  /**
+   * Number of bytes.
+   */
+  size_t len;
+} SteganoBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Encrypts `payload` and embeds it into `carrier`.
+ *
+ * On success, returns 0 and stores the stego file in `out`. On failure, returns -1 and the
+ * reason is available from `stegano_last_error`.
+ *
+ * # Safety
+ *
+ * `carrier` and `payload` must point to `carrier_len` and `payload_len` readable bytes (or
+ * be NULL when empty), `options` must follow `SteganoOptions` rules and `out` must be a
+ * valid pointer.
+ */
+int32_t stegano_encrypt_buffer(const uint8_t *carrier,
+                               size_t carrier_len,
+                               const uint8_t *payload,
+                               size_t payload_len,
+                               const struct SteganoOptions *options,
+                               struct SteganoBuffer *out);
+
+/**
+ * Extracts and decrypts the payload of `stego`.
+ *
+ * On success, returns 0, stores the payload in `payload_out` and, if `carrier_out` isn't
+ * NULL, the restored carrier in `carrier_out`. On failure, returns -1 and the reason is
+ * available from `stegano_last_error`.
+ *
+ * # Safety
+ *
+ * `stego` must point to `stego_len` readable bytes, `options` must follow `SteganoOptions`
+ * rules, `payload_out` must be a valid pointer and `carrier_out` valid or NULL.
+ */
+int32_t stegano_decrypt_buffer(const uint8_t *stego,
+                               size_t stego_len,
+                               const struct SteganoOptions *options,
+                               struct SteganoBuffer *payload_out,
+                               struct SteganoBuffer *carrier_out);
+
+/**
+ * Releases a buffer returned by the library. Freeing an empty buffer is a no-op.
+ *
+ * # Safety
+ *
+ * `buffer` must have been returned by this library and not freed before.
+ */
+void stegano_buffer_free(struct SteganoBuffer buffer);
+
+/**
+ * Returns the message of the last error raised on the calling thread, or NULL if none.
+ *
+ * The string is owned by the library and stays valid until the next failing call on the
+ * same thread.
+ */
+const char *stegano_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* STEGANO_H */
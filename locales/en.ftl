@@ -0,0 +1,167 @@
+# English messages of the stegano CLI, and the reference for translations.
+#
+# Each message is `id = text`, where `{ $name }` is replaced by an argument. Copy this file
+# to <lang>.ftl, such as de.ftl or pt-BR.ftl, and translate the text after each `=`; messages
+# left out fall back to English.
+
+## Labels in front of a value
+
+label-error = Error:
+label-warning = Warning:
+label-note = Note:
+label-payload = Payload:
+label-payload-header = Payload header:
+label-quality = Quality:
+label-dry-run = Dry run:
+label-output = Output:
+label-verbose = Verbose:
+label-hint = Hint:
+label-summary = Summary:
+label-misplaced = Misplaced:
+label-modified = Last modified:
+label-trailing = Trailing data:
+label-animation = Animation:
+label-disguised = Payload found disguised as
+label-removed = Removed:
+label-extracted = Extracted:
+label-owner-read = Owner ID read:
+label-shared-at = Shared at:
+label-connection = Connection:
+label-qr-code = QR code:
+
+## General
+
+cancelled = Cancelled, no partial output was left behind.
+interrupted = Interrupted, cleaning up (press Ctrl-C again to force)...
+unknown-command = Unknown command. Use 'help' for usage instructions.
+png-valid = It is a valid PNG file. Let's process it!
+
+## encrypt
+
+encrypt-written = Your payload has been encrypted and written successfully!
+encrypt-written-at = Your payload has been encrypted and written at offset { $offset } successfully!
+encrypt-appended = Your payload has been encrypted and appended successfully!
+encrypt-precision = Your payload has been encrypted into the gAMA, cHRM and pHYs values successfully!
+encrypt-lsb = Your payload has been encrypted and written to the pixels, { $bits } bit(s) per channel, successfully!
+encrypt-disguised = Your payload has been encrypted and disguised as { $disguise } successfully!
+encrypt-interleaved = Your payload has been encrypted and interleaved between the chunks successfully!
+encrypt-icc = Your payload has been encrypted and wrapped in the ICC profile successfully!
+encrypt-dct = Your payload has been encrypted into the DCT coefficients successfully!
+split-shares = Your payload has been encrypted and split into { $shares } shares, any { $needed } of which rebuild it, successfully!
+split-carriers = Your payload has been encrypted and split across { $carriers } carriers successfully!
+share-label = Share { $index }/{ $total } ({ $needed } needed)
+shard-label = Shard { $index }/{ $total }
+piece-written = { $label } ({ $bytes } bytes) written to { $path }
+piece-would-be-written = { $label } ({ $bytes } bytes) would be written to { $path }
+qr-payload-read = { $bytes } bytes read from the QR code in { $path }
+hint-plaintext = the hint is stored in plaintext. Anyone can read it with `stegano peek`, and it gives away that the file holds a payload.
+provenance-linked = Linked to parent carrier sha256 { $digest }
+dry-run-nothing-written = nothing was written.
+dry-run-output-size = { $path } would be { $bytes } bytes
+payload-at-offset = { $bytes } bytes at offset { $offset }
+payload-bytes = { $bytes } bytes
+verbose-no-offset = this method has no single injection offset to show.
+verbose-remote = remote inputs and outputs aren't read back to show.
+verbose-too-many = the { $bytes } inserted bytes are too many to show.
+descriptor-written = Recovery descriptor written to { $path }
+descriptor-would-be-written = Recovery descriptor would be written to { $path }
+
+## extract, remove and decrypt
+
+decrypted-secret = Your decrypted secret is:
+descriptor-read = Read { $bytes } bytes at offset { $offset } ({ $file_type } { $method }) from the descriptor
+restored-identical = The restored carrier is byte-identical to the original
+found-share = Found share { $index }/{ $total } ({ $needed } needed) in { $path }
+found-shard = Found shard { $index }/{ $total } in { $path }
+unwrapped-layer = Unwrapped layer { $layer } from a nested { $file_type } file
+pgp-written-as-is = the payload is an OpenPGP message; without --identity it is written as is, for `gpg --decrypt`.
+payload-without-header = legacy, age or OpenPGP format, without header
+qr-written = version { $version } ({ $size }x{ $size } modules) written to { $path }
+payload-file-written = The secret ({ $bytes } bytes) was written to { $path }
+burn-views-left = One view counted, the payload may be revealed { $views } more times.
+burn-wiped = That was the last view: the payload was wiped from { $path }.
+payload-wiped = { $bytes } payload bytes at offset { $offset } overwritten with random data { $passes } time(s).
+input-shredded = { $path } overwritten with random data { $passes } time(s) and removed.
+carrier-restored = The carrier without its payload was written to { $path }
+decrypt-deprecated = `decrypt` is deprecated: use `extract` to reveal the payload and `remove` to restore the carrier.
+
+## show-meta, peek and the other subcommands
+
+bit-plane-written = Bit plane { $plane } of channel { $channel } written to { $path }
+summary-counts = { $chunks } chunks, { $unknown } unknown types, { $crc_errors } CRC errors
+summary-truncated = , the last one truncated
+summary-capped = , stopped after { $chunks }
+page-position = Page { $page } of { $pages }: chunks { $first } to { $last } of { $total }
+page-empty = Page { $page } is past the last one, page { $pages }
+trailing-found = { $bytes } bytes follow { $terminator } at offset { $offset }, with { $entropy } bits of entropy per byte
+trailing-high-entropy = They look encrypted or compressed, as appended payloads are.
+trailing-none = No data follows the end of { $path }
+trailing-written = { $bytes } bytes after { $terminator } written to { $path }
+polyglot-entry = Found ZIP entry { $name } at offset { $offset }
+polyglot-written = Your payload has been encrypted into a PNG+ZIP polyglot successfully!
+share-send-command = Send this command to the recipient, and the key separately:
+serve-listening = Serving { $dir } on http://{ $address } with { $queued } queued payload(s)
+serve-answered = Answered { $requests } requests
+fetch-downloaded = Downloaded { $bytes } bytes of { $file_type } from { $url }
+soak-start = Soaking { $iterations } iterations with seed { $seed }
+soak-memory = Resident memory: { $start } -> { $end } bytes
+soak-open-files = Open files: { $start } -> { $end }
+soak-interrupted = Interrupted after { $done } of { $iterations } iterations, replay with --seed { $seed }
+soak-failed = { $failures } of { $round_trips } round trips failed, replay with --seed { $seed }
+soak-succeeded = All { $round_trips } round trips succeeded!
+peek-share = Found a { $bytes }-byte share in { $path }
+peek-share-info = It is share { $index }/{ $total } of a payload split with --shamir, any { $needed } of them rebuild it with `stegano decrypt --join`
+peek-found = Found { $bytes } bytes of encrypted payload in { $path }
+peek-shard-info = It is shard { $index }/{ $total } of a split payload, gather every part with `stegano decrypt --join`
+peek-hint-first-shard = No hint here, hints are stored in the first shard.
+peek-no-hint = No hint stored.
+config-saved = Saved { $path }
+config-empty = No defaults set in { $path }, the built-in ones apply. Available keys:
+rekey-written = Your payload has been re-encrypted with the new key and written to { $path } successfully!
+rekey-patched = Your payload has been re-encrypted with the new key, patching { $bytes } bytes of { $path } at offset { $offset } successfully!
+rekey-rewritten = Your payload has been re-encrypted with the new key and { $path } rewritten successfully!
+chunk-inserted = The chunk has been inserted at offset { $offset } and written to { $path } successfully!
+chunk-removed = { $chunk_type } chunk of { $bytes } bytes at offset { $offset }
+chunks-removed = { $count } chunk(s) removed and written to { $path } successfully!
+chunks-wiped = { $count } chunk(s) overwritten with random data { $passes } time(s) in the input.
+chunk-extracted = { $chunk_type } chunk #{ $index } of { $bytes } bytes at offset { $offset } to { $path }
+icc-injected = The ICC profile of { $bytes } bytes has been injected and written to { $path } successfully!
+icc-extracted = ICC profile of { $bytes } bytes to { $path }
+watermark-embedded = The watermark of { $owner } has been embedded into { $path } successfully!
+watermark-present = The watermark of { $owner } is present
+vault-stored = { $name } ({ $bytes } bytes) has been stored in the vault of { $path } successfully!
+vault-replaced = { $name } ({ $bytes } bytes) has been replaced in the vault of { $path } successfully!
+vault-empty = The vault of { $path } is empty.
+vault-written = { $name } ({ $bytes } bytes) has been written to { $path } successfully!
+vault-removed = { $name } ({ $bytes } bytes) has been removed from the vault of { $path } successfully!
+meta-set = { $count } metadata entr(y/ies) set and written to { $path } successfully!
+meta-empty = { $path } has no text entries or timestamp.
+meta-removed = { $count } metadata entr(y/ies) removed and written to { $path } successfully!
+carrier-enlarged = The carrier was enlarged to { $width }x{ $height } to fit the payload
+carrier-generated = A { $width }x{ $height } { $style } carrier (seed { $seed }, { $capacity } bytes safe with --method lsb) has been written to { $path } successfully!
+
+## gui
+
+gui-start = Drop a carrier onto the window to start.
+gui-drop-here = Drop a carrier here: PNG, JPEG, MP3, FLAC, SVG, text...
+gui-drop-another = Drop another file to replace it.
+gui-carrier-type = { $path } holds a { $file_type } file.
+gui-carrier-unknown = The type of { $path } is unknown, only the append method will work.
+gui-unreadable = { $path } can't be read: { $error }
+gui-type = Type: { $file_type }
+gui-type-unknown = Type: unknown
+gui-mode-hide = Hide a payload
+gui-mode-reveal = Reveal a payload
+gui-key = Key
+gui-method = Method
+gui-algorithm = Algorithm
+gui-save-as = Save as
+gui-payload = Payload
+gui-revealed-payload = Revealed payload
+gui-hide = Hide
+gui-reveal = Reveal
+gui-cancel = Cancel
+gui-working = Working... { $seconds } s
+gui-hidden = The payload has been hidden in { $path } successfully!
+gui-revealed = { $bytes } bytes have been revealed successfully!
+gui-cancelled = Cancelled, nothing was written.
@@ -0,0 +1,163 @@
+# Messages en français de la CLI stegano, traduits de en.ftl.
+
+## Libellés devant une valeur
+
+label-error = Erreur :
+label-warning = Attention :
+label-note = Remarque :
+label-payload = Charge utile :
+label-payload-header = En-tête de la charge utile :
+label-quality = Qualité :
+label-dry-run = Essai à blanc :
+label-output = Sortie :
+label-verbose = Détails :
+label-hint = Indice :
+label-summary = Résumé :
+label-misplaced = Mal placé :
+label-modified = Dernière modification :
+label-trailing = Données en fin de fichier :
+label-animation = Animation :
+label-disguised = Charge utile trouvée, déguisée en
+label-removed = Supprimé :
+label-extracted = Extrait :
+label-owner-read = Identifiant du propriétaire lu :
+label-shared-at = Partagé à l'adresse :
+label-connection = Connexion :
+label-qr-code = Code QR :
+
+## Général
+
+cancelled = Annulé, aucune sortie partielle n'a été laissée.
+interrupted = Interrompu, nettoyage en cours (appuyez à nouveau sur Ctrl-C pour forcer)...
+unknown-command = Commande inconnue. Utilisez 'help' pour l'aide.
+png-valid = C'est un fichier PNG valide. Traitons-le !
+
+## encrypt
+
+encrypt-written = Votre charge utile a été chiffrée et écrite avec succès !
+encrypt-written-at = Votre charge utile a été chiffrée et écrite à la position { $offset } avec succès !
+encrypt-appended = Votre charge utile a été chiffrée et ajoutée à la fin avec succès !
+encrypt-precision = Votre charge utile a été chiffrée dans les valeurs gAMA, cHRM et pHYs avec succès !
+encrypt-lsb = Votre charge utile a été chiffrée et écrite dans les pixels, { $bits } bit(s) par canal, avec succès !
+encrypt-disguised = Votre charge utile a été chiffrée et déguisée en { $disguise } avec succès !
+encrypt-interleaved = Votre charge utile a été chiffrée et intercalée entre les blocs avec succès !
+encrypt-icc = Votre charge utile a été chiffrée et enveloppée dans le profil ICC avec succès !
+encrypt-dct = Votre charge utile a été chiffrée dans les coefficients DCT avec succès !
+split-shares = Votre charge utile a été chiffrée et partagée en { $shares } parts, dont { $needed } suffisent à la reconstituer, avec succès !
+split-carriers = Votre charge utile a été chiffrée et répartie sur { $carriers } porteurs avec succès !
+share-label = Part { $index }/{ $total } ({ $needed } nécessaires)
+shard-label = Fragment { $index }/{ $total }
+piece-written = { $label } ({ $bytes } octets) écrit dans { $path }
+piece-would-be-written = { $label } ({ $bytes } octets) serait écrit dans { $path }
+qr-payload-read = { $bytes } octets lus dans le code QR de { $path }
+hint-plaintext = l'indice est stocké en clair. N'importe qui peut le lire avec `stegano peek`, et il révèle que le fichier contient une charge utile.
+provenance-linked = Lié au porteur parent sha256 { $digest }
+dry-run-nothing-written = rien n'a été écrit.
+dry-run-output-size = { $path } ferait { $bytes } octets
+payload-at-offset = { $bytes } octets à la position { $offset }
+payload-bytes = { $bytes } octets
+verbose-no-offset = cette méthode n'a pas de position d'injection unique à afficher.
+verbose-remote = les entrées et sorties distantes ne sont pas relues pour être affichées.
+verbose-too-many = les { $bytes } octets insérés sont trop nombreux pour être affichés.
+descriptor-written = Descripteur de récupération écrit dans { $path }
+descriptor-would-be-written = Le descripteur de récupération serait écrit dans { $path }
+
+## extract, remove et decrypt
+
+decrypted-secret = Votre secret déchiffré est :
+descriptor-read = { $bytes } octets lus à la position { $offset } ({ $file_type } { $method }) d'après le descripteur
+restored-identical = Le porteur restauré est identique à l'original, octet pour octet
+found-share = Part { $index }/{ $total } ({ $needed } nécessaires) trouvée dans { $path }
+found-shard = Fragment { $index }/{ $total } trouvé dans { $path }
+unwrapped-layer = Couche { $layer } extraite d'un fichier { $file_type } imbriqué
+pgp-written-as-is = la charge utile est un message OpenPGP ; sans --identity elle est écrite telle quelle, pour `gpg --decrypt`.
+payload-without-header = format ancien, age ou OpenPGP, sans en-tête
+qr-written = version { $version } ({ $size }x{ $size } modules) écrit dans { $path }
+payload-file-written = Le secret ({ $bytes } octets) a été écrit dans { $path }
+burn-views-left = Une lecture comptée, la charge utile peut encore être révélée { $views } fois.
+burn-wiped = C'était la dernière lecture : la charge utile a été effacée de { $path }.
+payload-wiped = { $bytes } octets de charge utile à la position { $offset } écrasés par des données aléatoires { $passes } fois.
+input-shredded = { $path } écrasé par des données aléatoires { $passes } fois et supprimé.
+carrier-restored = Le porteur sans sa charge utile a été écrit dans { $path }
+decrypt-deprecated = `decrypt` est obsolète : utilisez `extract` pour révéler la charge utile et `remove` pour restaurer le porteur.
+
+## show-meta, peek et les autres sous-commandes
+
+bit-plane-written = Plan de bits { $plane } du canal { $channel } écrit dans { $path }
+summary-counts = { $chunks } chunks, { $unknown } types inconnus, { $crc_errors } erreurs de CRC
+summary-truncated = , le dernier tronqué
+summary-capped = , arrêté après { $chunks }
+page-position = Page { $page } sur { $pages } : chunks { $first } à { $last } sur { $total }
+page-empty = La page { $page } est au-delà de la dernière, la page { $pages }
+trailing-found = { $bytes } octets suivent { $terminator } à la position { $offset }, avec { $entropy } bits d'entropie par octet
+trailing-high-entropy = Ils semblent chiffrés ou compressés, comme les charges utiles ajoutées en fin de fichier.
+trailing-none = Aucune donnée ne suit la fin de { $path }
+trailing-written = { $bytes } octets après { $terminator } écrits dans { $path }
+polyglot-entry = Entrée ZIP { $name } trouvée à la position { $offset }
+polyglot-written = Votre charge utile a été chiffrée dans un polyglotte PNG+ZIP avec succès !
+share-send-command = Envoyez cette commande au destinataire, et la clé séparément :
+serve-listening = Service de { $dir } sur http://{ $address } avec { $queued } charge(s) utile(s) en attente
+serve-answered = { $requests } requêtes traitées
+fetch-downloaded = { $bytes } octets de { $file_type } téléchargés depuis { $url }
+soak-start = Endurance sur { $iterations } itérations avec la graine { $seed }
+soak-memory = Mémoire résidente : { $start } -> { $end } octets
+soak-open-files = Fichiers ouverts : { $start } -> { $end }
+soak-interrupted = Interrompu après { $done } itérations sur { $iterations }, rejouez avec --seed { $seed }
+soak-failed = { $failures } allers-retours sur { $round_trips } ont échoué, rejouez avec --seed { $seed }
+soak-succeeded = Les { $round_trips } allers-retours ont tous réussi !
+peek-share = Part de { $bytes } octets trouvée dans { $path }
+peek-share-info = C'est la part { $index }/{ $total } d'une charge utile partagée avec --shamir, { $needed } d'entre elles la reconstituent avec `stegano decrypt --join`
+peek-found = { $bytes } octets de charge utile chiffrée trouvés dans { $path }
+peek-shard-info = C'est le fragment { $index }/{ $total } d'une charge utile répartie, rassemblez toutes les parties avec `stegano decrypt --join`
+peek-hint-first-shard = Pas d'indice ici, les indices sont stockés dans le premier fragment.
+peek-no-hint = Aucun indice stocké.
+config-saved = { $path } enregistré
+config-empty = Aucune valeur par défaut dans { $path }, celles intégrées s'appliquent. Clés disponibles :
+rekey-written = Votre charge utile a été rechiffrée avec la nouvelle clé et écrite dans { $path } avec succès !
+rekey-patched = Votre charge utile a été rechiffrée avec la nouvelle clé, en modifiant { $bytes } octets de { $path } à la position { $offset } avec succès !
+rekey-rewritten = Votre charge utile a été rechiffrée avec la nouvelle clé et { $path } réécrit avec succès !
+chunk-inserted = Le bloc a été inséré à la position { $offset } et écrit dans { $path } avec succès !
+chunk-removed = bloc { $chunk_type } de { $bytes } octets à la position { $offset }
+chunks-removed = { $count } bloc(s) supprimé(s) et écrit(s) dans { $path } avec succès !
+chunks-wiped = { $count } bloc(s) écrasé(s) par des données aléatoires { $passes } fois dans l'entrée.
+chunk-extracted = bloc { $chunk_type } n°{ $index } de { $bytes } octets à la position { $offset } vers { $path }
+icc-injected = Le profil ICC de { $bytes } octets a été injecté et écrit dans { $path } avec succès !
+icc-extracted = profil ICC de { $bytes } octets vers { $path }
+watermark-embedded = Le filigrane de { $owner } a été incrusté dans { $path } avec succès !
+watermark-present = Le filigrane de { $owner } est présent
+vault-stored = { $name } ({ $bytes } octets) a été stocké dans le coffre de { $path } avec succès !
+vault-replaced = { $name } ({ $bytes } octets) a été remplacé dans le coffre de { $path } avec succès !
+vault-empty = Le coffre de { $path } est vide.
+vault-written = { $name } ({ $bytes } octets) a été écrit dans { $path } avec succès !
+vault-removed = { $name } ({ $bytes } octets) a été supprimé du coffre de { $path } avec succès !
+meta-set = { $count } métadonnée(s) définie(s) et écrite(s) dans { $path } avec succès !
+meta-empty = { $path } n'a ni entrées de texte ni horodatage.
+meta-removed = { $count } métadonnée(s) supprimée(s) et écrite(s) dans { $path } avec succès !
+carrier-enlarged = Le porteur a été agrandi à { $width }x{ $height } pour contenir la charge utile
+carrier-generated = Un porteur { $style } de { $width }x{ $height } (graine { $seed }, { $capacity } octets sûrs avec --method lsb) a été écrit dans { $path } avec succès !
+
+## gui
+
+gui-start = Déposez un porteur sur la fenêtre pour commencer.
+gui-drop-here = Déposez un porteur ici : PNG, JPEG, MP3, FLAC, SVG, texte...
+gui-drop-another = Déposez un autre fichier pour le remplacer.
+gui-carrier-type = { $path } contient un fichier { $file_type }.
+gui-carrier-unknown = Le type de { $path } est inconnu, seule la méthode append fonctionnera.
+gui-unreadable = { $path } est illisible : { $error }
+gui-type = Type : { $file_type }
+gui-type-unknown = Type : inconnu
+gui-mode-hide = Cacher une charge utile
+gui-mode-reveal = Révéler une charge utile
+gui-key = Clé
+gui-method = Méthode
+gui-algorithm = Algorithme
+gui-save-as = Enregistrer sous
+gui-payload = Charge utile
+gui-revealed-payload = Charge utile révélée
+gui-hide = Cacher
+gui-reveal = Révéler
+gui-cancel = Annuler
+gui-working = En cours... { $seconds } s
+gui-hidden = La charge utile a été cachée dans { $path } avec succès !
+gui-revealed = { $bytes } octets ont été révélés avec succès !
+gui-cancelled = Annulé, rien n'a été écrit.
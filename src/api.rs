@@ -0,0 +1,228 @@
+use crate::codec::{decode_bytes, embed_encrypted, encode_bytes, CodecOptions};
+use crate::png::{insert_chunk, read_chunks};
+use std::io::{self, ErrorKind};
+
+/// Tells whether a method takes its payload back out of the carrier, rather than overwriting
+/// bits of it. Only those can restore the carrier. The icc method takes it out too, but
+/// compresses the PNG profile again, which may not give the original bytes, and the SVG
+/// attributes method loses the original attribute order.
+pub fn restores_carrier(method: &str) -> bool {
+    !matches!(
+        method.to_lowercase().as_str(),
+        "precision" | "lsb" | "robust" | "icc" | "attributes"
+    )
+}
+
+/// Returns the position of the first byte where two buffers differ, if they do.
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter()
+        .zip(b)
+        .position(|(x, y)| x != y)
+        .or((a.len() != b.len()).then(|| a.len().min(b.len())))
+}
+
+/// Builds the error returned when a carrier isn't restored byte for byte.
+fn mismatch(what: &str, position: usize) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("{} differs from the original at byte {}", what, position),
+    )
+}
+
+/// Checks that a payload survives a round trip through a carrier, and that decoding gives the
+/// carrier back byte for byte.
+///
+/// The payload is embedded with `encode_bytes` and taken back out with `decode_bytes`. The
+/// payload must come back unchanged, and for the methods that restore their carrier, the
+/// restored carrier must equal the original. The pixel methods, which overwrite bits of the
+/// image, are only checked for the payload.
+///
+/// # Arguments
+///
+/// * `carrier` - The bytes of the carrier file.
+/// * `payload` - The secret to hide.
+/// * `opts` - The carrier type, method, algorithm and key.
+///
+/// # Returns
+///
+/// A `Result` that is an `InvalidData` error naming the first byte that differs, or the
+/// error of the embedding or extraction.
+///
+/// # Examples
+///
+/// Every method that restores its carrier, over a small carrier of each type:
+///
+/// ```
+/// use stegano::api::roundtrip_check;
+/// use stegano::codec::CodecOptions;
+/// use stegano::png::encode_grayscale;
+///
+/// let png = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let tiff = vec![
+///     0x49, 0x49, 0x2A, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x03, 0x00,
+///     0x01, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+/// ];
+/// let ico = vec![
+///     0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x10, 0x10, 0x00, 0x00, 0x01, 0x00, 0x20, 0x00,
+///     0x04, 0x00, 0x00, 0x00, 0x16, 0x00, 0x00, 0x00, 1, 2, 3, 4,
+/// ];
+/// let mp4 = b"\0\0\0\x10ftypisom\0\0\x02\0\0\0\0\x0Cmdat\x01\x02\x03\x04".to_vec();
+/// let mp3 = [0xFF, 0xFB, 0x90, 0x00].to_vec();
+/// let mut flac = b"fLaC\0\0\0\x22".to_vec();
+/// flac.extend_from_slice(&[0; 34]);
+/// flac.extend_from_slice(&[0x81, 0, 1, 0]);
+/// flac.extend_from_slice(&[0; 256]);
+/// let svg = format!("<svg>\n{}</svg>\n", "<g/>\n".repeat(128)).into_bytes();
+/// let text = "The quick brown fox\n".repeat(128).into_bytes();
+/// let golden = [
+///     ("png", "chunk", &png),
+///     ("png", "append", &png),
+///     ("tiff", "tag", &tiff),
+///     ("tiff", "strip", &tiff),
+///     ("ico", "slack", &ico),
+///     ("mp4", "free", &mp4),
+///     ("mp4", "uuid", &mp4),
+///     ("mp3", "priv", &mp3),
+///     ("mp3", "geob", &mp3),
+///     ("flac", "application", &flac),
+///     ("flac", "padding", &flac),
+///     ("svg", "metadata", &svg),
+///     ("svg", "whitespace", &svg),
+///     ("text", "zerowidth", &text),
+///     ("text", "whitespace", &text),
+///     ("png", "disguise", &png),
+///     ("png", "interleave", &png),
+/// ];
+/// for (file_type, method, carrier) in golden {
+///     for algorithm in ["aes", "chacha", "xor"] {
+///         let opts = CodecOptions {
+///             file_type: file_type.into(),
+///             method: method.into(),
+///             algorithm: algorithm.into(),
+///             ..CodecOptions::default()
+///         };
+///         roundtrip_check(carrier, b"hello", &opts).unwrap();
+///     }
+/// }
+/// ```
+pub fn roundtrip_check(carrier: &[u8], payload: &[u8], opts: &CodecOptions) -> io::Result<()> {
+    let stego = encode_bytes(carrier, payload, opts)?;
+    let decoded = decode_bytes(&stego, opts)?;
+    if let Some(position) = first_difference(payload, &decoded.payload) {
+        return Err(mismatch("The decoded payload", position));
+    }
+    if !restores_carrier(&opts.method) {
+        return Ok(());
+    }
+    match first_difference(carrier, &decoded.carrier) {
+        Some(position) => Err(mismatch("The restored carrier", position)),
+        None => Ok(()),
+    }
+}
+
+/// Checks that a carrier restored from a stego file is the one the payload was embedded in.
+///
+/// The original carrier is usually gone by the time a payload is extracted, so the restored
+/// one is checked the other way round: embedding the extracted bytes into it again must give
+/// the stego file back, byte for byte. This is what `decrypt --exact` runs before writing.
+///
+/// # Arguments
+///
+/// * `stego` - The bytes of the stego file.
+/// * `restored` - The carrier restored from it.
+/// * `encrypted` - The bytes extracted from it, as they were embedded.
+/// * `opts` - The carrier type and method the payload was embedded with.
+///
+/// # Returns
+///
+/// A `Result` that is an `InvalidData` error naming the first byte that differs, or an
+/// `InvalidInput` error for the methods that can't restore their carrier.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::api::verify_restored;
+/// use stegano::codec::{encode_bytes, extract_encrypted, CodecOptions};
+/// use stegano::png::encode_grayscale;
+///
+/// let carrier = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let opts = CodecOptions::default();
+/// let stego = encode_bytes(&carrier, b"hello", &opts).unwrap();
+/// let (encrypted, restored) = extract_encrypted(&stego, &opts).unwrap();
+/// assert!(verify_restored(&stego, &restored, &encrypted, &opts).is_ok());
+///
+/// let mut damaged = restored.clone();
+/// damaged.push(0);
+/// assert!(verify_restored(&stego, &damaged, &encrypted, &opts).is_err());
+/// ```
+pub fn verify_restored(
+    stego: &[u8],
+    restored: &[u8],
+    encrypted: &[u8],
+    opts: &CodecOptions,
+) -> io::Result<()> {
+    if !restores_carrier(&opts.method) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The {} method doesn't restore the carrier byte for byte",
+                opts.method
+            ),
+        ));
+    }
+    let file_type = opts.file_type.to_lowercase();
+    let method = opts.method.to_lowercase();
+    let candidates = match (file_type.as_str(), method.as_str()) {
+        // The chunk goes back where it was found, which isn't always before IEND.
+        ("png", "auto" | "chunk" | "apng") => {
+            let name: [u8; 4] = opts.chunk_name.as_bytes().try_into().map_err(|_| {
+                io::Error::new(ErrorKind::InvalidInput, "Chunk names are four letters")
+            })?;
+            let offset = read_chunks(stego)?
+                .into_iter()
+                .find(|chunk| chunk.r#type == name && chunk.data == encrypted)
+                .map(|chunk| chunk.offset);
+            vec![insert_chunk(restored, &name, encrypted, offset)?.0]
+        }
+        // Any of the methods may have been used; the restored carrier must match one of them.
+        (file_type @ ("tiff" | "mp4" | "mp3" | "flac"), "auto") => {
+            let methods: &[&str] = match file_type {
+                "tiff" => &["tag", "strip"],
+                "mp4" => &["free", "skip", "uuid"],
+                "mp3" => &["priv", "geob"],
+                _ => &["application", "padding"],
+            };
+            methods
+                .iter()
+                .filter_map(|method| {
+                    let opts = CodecOptions {
+                        method: method.to_string(),
+                        ..opts.clone()
+                    };
+                    embed_encrypted(restored, encrypted, &opts).ok()
+                })
+                .collect()
+        }
+        // The template is found on extraction, so any of them may have been used.
+        ("png", "disguise") => ["icc", "xmp", "makernote"]
+            .iter()
+            .filter_map(|disguise| {
+                let opts = CodecOptions {
+                    disguise: disguise.to_string(),
+                    ..opts.clone()
+                };
+                embed_encrypted(restored, encrypted, &opts).ok()
+            })
+            .collect(),
+        _ => vec![embed_encrypted(restored, encrypted, opts)?],
+    };
+    if candidates.iter().any(|candidate| candidate == stego) {
+        return Ok(());
+    }
+    let position = candidates
+        .iter()
+        .filter_map(|candidate| first_difference(stego, candidate))
+        .max()
+        .unwrap_or(0);
+    Err(mismatch("Re-embedding into the restored carrier", position))
+}
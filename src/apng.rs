@@ -0,0 +1,250 @@
+use crate::chunk_type::ChunkType;
+use crate::png::RawChunk;
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+/// The animation of an APNG file, from its `acTL` and `fcTL` chunks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Animation {
+    /// Number of frames announced by `acTL`.
+    pub num_frames: u32,
+    /// Number of times the animation plays, 0 for forever.
+    pub num_plays: u32,
+    /// The frames, in file order.
+    pub frames: Vec<Frame>,
+}
+
+/// A frame of an APNG animation, from its `fcTL` chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Offset of the `fcTL` chunk, starting at its length field.
+    pub offset: usize,
+    /// Sequence number of the `fcTL` chunk.
+    pub sequence: u32,
+    /// Width of the frame region in pixels.
+    pub width: u32,
+    /// Height of the frame region in pixels.
+    pub height: u32,
+    /// Horizontal position of the frame region.
+    pub x_offset: u32,
+    /// Vertical position of the frame region.
+    pub y_offset: u32,
+    /// Numerator of the delay before the next frame, in seconds.
+    pub delay_num: u16,
+    /// Denominator of the delay, 0 meaning 100.
+    pub delay_den: u16,
+    /// How the region is disposed of after the frame: 0 none, 1 background, 2 previous.
+    pub dispose_op: u8,
+    /// How the frame is drawn: 0 source, 1 over.
+    pub blend_op: u8,
+    /// Number of `IDAT` or `fdAT` chunks holding the frame.
+    pub data_chunks: usize,
+}
+
+impl Frame {
+    /// Parses the data of an `fcTL` chunk.
+    fn parse(offset: usize, data: &[u8]) -> io::Result<Frame> {
+        if data.len() != 26 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Malformed fcTL chunk at offset {}", offset),
+            ));
+        }
+        let u32_at = |at: usize| u32::from_be_bytes(data[at..at + 4].try_into().unwrap());
+        let u16_at = |at: usize| u16::from_be_bytes(data[at..at + 2].try_into().unwrap());
+        Ok(Frame {
+            offset,
+            sequence: u32_at(0),
+            width: u32_at(4),
+            height: u32_at(8),
+            x_offset: u32_at(12),
+            y_offset: u32_at(16),
+            delay_num: u16_at(20),
+            delay_den: u16_at(22),
+            dispose_op: data[24],
+            blend_op: data[25],
+            data_chunks: 0,
+        })
+    }
+
+    /// Returns the delay before the next frame in milliseconds.
+    pub fn delay_ms(&self) -> u64 {
+        let den = if self.delay_den == 0 {
+            100
+        } else {
+            self.delay_den
+        };
+        self.delay_num as u64 * 1000 / den as u64
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dispose = match self.dispose_op {
+            0 => "none",
+            1 => "background",
+            2 => "previous",
+            _ => "unknown",
+        };
+        let blend = match self.blend_op {
+            0 => "source",
+            1 => "over",
+            _ => "unknown",
+        };
+        write!(
+            f,
+            "{}x{} at ({}, {}), delay {} ms, dispose {}, blend {}",
+            self.width,
+            self.height,
+            self.x_offset,
+            self.y_offset,
+            self.delay_ms(),
+            dispose,
+            blend
+        )
+    }
+}
+
+impl Animation {
+    /// Returns the duration of one play of the animation in milliseconds.
+    pub fn duration_ms(&self) -> u64 {
+        self.frames.iter().map(Frame::delay_ms).sum()
+    }
+}
+
+impl fmt::Display for Animation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} frames", self.num_frames)?;
+        if self.frames.len() != self.num_frames as usize {
+            write!(f, " ({} found)", self.frames.len())?;
+        }
+        match self.num_plays {
+            0 => write!(f, ", plays forever")?,
+            1 => write!(f, ", plays once")?,
+            plays => write!(f, ", plays {} times", plays)?,
+        }
+        write!(f, ", {} ms per play", self.duration_ms())
+    }
+}
+
+/// Reads the animation of a PNG file from its chunks.
+///
+/// # Arguments
+///
+/// * `chunks` - The chunks of the file, as `read_chunks` returns them.
+///
+/// # Returns
+///
+/// A `Result` containing the animation, `None` for a still PNG without `acTL`, or an
+/// `InvalidData` error for malformed animation chunks.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::apng::parse_animation;
+/// use stegano::png::{encode_grayscale, read_chunks};
+///
+/// let png = encode_grayscale(2, 2, &[0, 255, 255, 0]).unwrap();
+/// assert_eq!(parse_animation(&read_chunks(&png).unwrap()).unwrap(), None);
+/// ```
+pub fn parse_animation(chunks: &[RawChunk]) -> io::Result<Option<Animation>> {
+    let Some(actl) = chunks.iter().find(|chunk| &chunk.r#type == b"acTL") else {
+        return Ok(None);
+    };
+    if actl.data.len() != 8 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Malformed acTL chunk",
+        ));
+    }
+    let mut frames: Vec<Frame> = Vec::new();
+    for chunk in chunks {
+        match chunk.r#type {
+            ChunkType::Fctl => frames.push(Frame::parse(chunk.offset, &chunk.data)?),
+            // The default image is the first frame only when an fcTL comes before it.
+            ChunkType::Idat | ChunkType::Fdat => {
+                if let Some(frame) = frames.last_mut() {
+                    frame.data_chunks += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(Some(Animation {
+        num_frames: u32::from_be_bytes(actl.data[0..4].try_into().unwrap()),
+        num_plays: u32::from_be_bytes(actl.data[4..8].try_into().unwrap()),
+        frames,
+    }))
+}
+
+/// Describes an APNG chunk in one line for `show-meta`.
+///
+/// # Returns
+///
+/// The description of an `acTL`, `fcTL` or `fdAT` chunk, or `None` for other chunks and
+/// malformed ones.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::apng::describe_chunk;
+///
+/// let actl = [0, 0, 0, 12, 0, 0, 0, 0];
+/// assert_eq!(describe_chunk(b"acTL", &actl).unwrap(), "animation of 12 frames, plays forever");
+/// assert_eq!(describe_chunk(b"fdAT", &[0, 0, 0, 5, 1, 2]).unwrap(), "frame data, sequence 5");
+/// assert_eq!(describe_chunk(b"IDAT", &[]), None);
+/// ```
+pub fn describe_chunk(r#type: &[u8; 4], data: &[u8]) -> Option<String> {
+    let u32_at = |at: usize| Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?));
+    match r#type {
+        b"acTL" if data.len() == 8 => {
+            let plays = match u32_at(4)? {
+                0 => "plays forever".to_string(),
+                1 => "plays once".to_string(),
+                plays => format!("plays {} times", plays),
+            };
+            Some(format!("animation of {} frames, {}", u32_at(0)?, plays))
+        }
+        b"fcTL" => {
+            let frame = Frame::parse(0, data).ok()?;
+            Some(format!(
+                "frame control, sequence {}: {}",
+                frame.sequence, frame
+            ))
+        }
+        b"fdAT" => Some(format!("frame data, sequence {}", u32_at(0)?)),
+        _ => None,
+    }
+}
+
+/// Finds where a chunk goes between the first and the second frame of an animation.
+///
+/// Players skip unknown ancillary chunks between frames, and the chunk takes no sequence
+/// number, so the animation plays as before.
+///
+/// # Arguments
+///
+/// * `chunks` - The chunks of the file, as `read_chunks` returns them.
+///
+/// # Returns
+///
+/// A `Result` containing the offset of the `fcTL` chunk of the second frame, or an
+/// `InvalidInput` error if the file isn't an animation of at least two frames.
+pub fn between_frames_offset(chunks: &[RawChunk]) -> io::Result<usize> {
+    let animation = parse_animation(chunks)?.ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidInput,
+            "Not an animated PNG: the apng method needs an acTL chunk",
+        )
+    })?;
+    animation
+        .frames
+        .get(1)
+        .map(|frame| frame.offset)
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                "The animation has a single frame, there is nothing to embed between",
+            )
+        })
+}
@@ -0,0 +1,261 @@
+use crate::io_ext::{read_exact_at, read_vec_at, stream_len};
+use sha2::{Digest, Sha256};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+
+/// Magic marker closing a payload appended after the end of a carrier.
+pub const APPEND_MAGIC: &[u8; 8] = b"STEGANO\x01";
+
+/// Magic marker closing a payload frame that also links to its parent carrier.
+pub const LINKED_MAGIC: &[u8; 8] = b"STEGANO\x02";
+
+/// Size of the trailer following the payload: a big-endian u64 length and the magic.
+pub const TRAILER_LEN: u64 = 16;
+
+/// Size of the SHA-256 digest of the parent carrier stored by linked frames.
+pub const DIGEST_LEN: u64 = 32;
+
+/// How far back from the end of the file the magic is searched for, so that data appended
+/// by other tools after the payload doesn't hide it.
+const SCAN_WINDOW: u64 = 1 << 20;
+
+/// Location of a payload frame appended to a carrier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppendFrame {
+    /// Offset of the payload, which is also where the frame starts.
+    pub offset: u64,
+    /// Length of the payload.
+    pub len: u64,
+    /// SHA-256 digest of the carrier the frame was appended to, for linked frames.
+    pub parent: Option<[u8; 32]>,
+    /// Offset right after the frame trailer.
+    pub end: u64,
+}
+
+/// Writes a carrier followed by an appended payload frame.
+///
+/// The frame is laid out as `payload | length (u64, big-endian) | APPEND_MAGIC`, which works for
+/// any format whose readers stop at their own terminator (PNG IEND, JPEG EOI, ZIP EOCD, ...).
+///
+/// # Arguments
+///
+/// * `carrier` - The source of the carrier bytes, copied verbatim.
+/// * `payload` - The source of the bytes to hide.
+/// * `len` - The number of bytes to read from `payload`.
+/// * `writer` - The destination of the new file.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use stegano::append::{extract_appended, write_appended};
+///
+/// let mut out = Vec::new();
+/// write_appended(&b"carrier"[..], &b"secret"[..], 6, &mut out).unwrap();
+/// assert!(out.starts_with(b"carrier"));
+/// assert_eq!(extract_appended(&mut Cursor::new(out)).unwrap(), b"secret");
+/// ```
+pub fn write_appended<C: Read, P: Read, W: Write>(
+    mut carrier: C,
+    payload: P,
+    len: u64,
+    mut writer: W,
+) -> io::Result<()> {
+    io::copy(&mut carrier, &mut writer)?;
+    copy_payload(payload, len, &mut writer)?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(APPEND_MAGIC)
+}
+
+/// Writes a carrier followed by a payload frame linked to the carrier's SHA-256 digest.
+///
+/// The frame is laid out as `payload | parent digest | length | LINKED_MAGIC`. Appending to a
+/// carrier that already holds frames builds a chain of custody that `provenance_chain` can
+/// verify edit by edit.
+///
+/// # Arguments
+///
+/// * `carrier` - The source of the carrier bytes, copied verbatim and hashed on the way.
+/// * `payload` - The source of the bytes to hide.
+/// * `len` - The number of bytes to read from `payload`.
+/// * `writer` - The destination of the new file.
+///
+/// # Returns
+///
+/// A `Result` containing the digest of the parent carrier.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use stegano::append::{find_frame_before, write_appended_linked};
+///
+/// let mut out = Vec::new();
+/// let parent = write_appended_linked(&b"carrier"[..], &b"secret"[..], 6, &mut out).unwrap();
+/// let len = out.len() as u64;
+/// let frame = find_frame_before(&mut Cursor::new(out), len).unwrap().unwrap();
+/// assert_eq!(frame.parent, Some(parent));
+/// assert_eq!(frame.offset, 7);
+/// ```
+pub fn write_appended_linked<C: Read, P: Read, W: Write>(
+    mut carrier: C,
+    payload: P,
+    len: u64,
+    mut writer: W,
+) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = carrier.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n])?;
+    }
+    let digest: [u8; 32] = hasher.finalize().into();
+    copy_payload(payload, len, &mut writer)?;
+    writer.write_all(&digest)?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(LINKED_MAGIC)?;
+    Ok(digest)
+}
+
+/// Copies exactly `len` payload bytes to the writer.
+fn copy_payload<P: Read, W: Write>(payload: P, len: u64, writer: &mut W) -> io::Result<()> {
+    let copied = io::copy(&mut payload.take(len), writer)?;
+    if copied != len {
+        return Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "Payload shorter than announced",
+        ));
+    }
+    Ok(())
+}
+
+/// Computes the SHA-256 digest of the first `len` bytes of a stream.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use stegano::append::sha256_prefix;
+///
+/// let digest = sha256_prefix(&mut Cursor::new(b"abc-ignored".to_vec()), 3).unwrap();
+/// assert_eq!(digest[..4], [0xBA, 0x78, 0x16, 0xBF]);
+/// ```
+pub fn sha256_prefix<R: Read + Seek>(reader: &mut R, len: u64) -> io::Result<[u8; 32]> {
+    let position = reader.stream_position()?;
+    reader.seek(SeekFrom::Start(0))?;
+    let mut hasher = Sha256::new();
+    let mut limited = reader.by_ref().take(len);
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = limited.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    reader.seek(SeekFrom::Start(position))?;
+    Ok(hasher.finalize().into())
+}
+
+/// Locates the last payload frame ending at or before `end`, scanning backwards for the magic.
+///
+/// # Arguments
+///
+/// * `reader` - A readable and seekable input.
+/// * `end` - The offset the search starts from, usually the file length.
+///
+/// # Returns
+///
+/// A `Result` containing the frame, or `None` if no frame was found.
+pub fn find_frame_before<R: Read + Seek>(
+    reader: &mut R,
+    end: u64,
+) -> io::Result<Option<AppendFrame>> {
+    if end < TRAILER_LEN {
+        return Ok(None);
+    }
+    let window_start = end.saturating_sub(SCAN_WINDOW);
+    let window = read_vec_at(reader, window_start, (end - window_start) as usize)?;
+    let magic_len = APPEND_MAGIC.len();
+    for pos in (8..=window.len() - magic_len).rev() {
+        let magic = &window[pos..pos + magic_len];
+        let linked = magic == LINKED_MAGIC;
+        if magic != APPEND_MAGIC && !linked {
+            continue;
+        }
+        let len = u64::from_be_bytes(window[pos - 8..pos].try_into().unwrap());
+        let frame_end = window_start + (pos + magic_len) as u64;
+        let digest_len = if linked { DIGEST_LEN } else { 0 };
+        let Some(offset) = (frame_end - TRAILER_LEN)
+            .checked_sub(digest_len)
+            .and_then(|payload_end| payload_end.checked_sub(len))
+        else {
+            continue;
+        };
+        let parent = if linked {
+            let mut digest = [0u8; 32];
+            read_exact_at(reader, offset + len, &mut digest)?;
+            Some(digest)
+        } else {
+            None
+        };
+        return Ok(Some(AppendFrame {
+            offset,
+            len,
+            parent,
+            end: frame_end,
+        }));
+    }
+    Ok(None)
+}
+
+/// Locates an appended payload by scanning backwards from the end of the file for the magic.
+///
+/// # Returns
+///
+/// A `Result` containing the offset and length of the payload, or `None` if no frame was found.
+pub fn find_appended<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(u64, u64)>> {
+    let file_len = stream_len(reader)?;
+    Ok(find_frame_before(reader, file_len)?.map(|frame| (frame.offset, frame.len)))
+}
+
+/// Extracts a payload appended after the end of a carrier.
+///
+/// # Returns
+///
+/// A `Result` containing the payload bytes, or an `io::Error` if no payload was found.
+pub fn extract_appended<R: Read + Seek>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let (offset, len) = find_appended(reader)?
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "No appended payload found"))?;
+    read_vec_at(reader, offset, len as usize)
+}
+
+/// Writes the carrier without its appended payload frame, restoring the original file.
+///
+/// Any bytes found after the frame are preserved.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use stegano::append::{strip_appended, write_appended};
+///
+/// let mut stego = Vec::new();
+/// write_appended(&b"carrier"[..], &b"secret"[..], 6, &mut stego).unwrap();
+/// let mut clean = Vec::new();
+/// strip_appended(&mut Cursor::new(stego), &mut clean).unwrap();
+/// assert_eq!(clean, b"carrier");
+/// ```
+pub fn strip_appended<R: Read + Seek, W: Write>(reader: &mut R, mut writer: W) -> io::Result<()> {
+    let file_len = stream_len(reader)?;
+    let frame = find_frame_before(reader, file_len)?
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "No appended payload found"))?;
+    reader.seek(SeekFrom::Start(0))?;
+    io::copy(&mut reader.by_ref().take(frame.offset), &mut writer)?;
+    reader.seek(SeekFrom::Start(frame.end))?;
+    io::copy(reader, &mut writer)?;
+    Ok(())
+}
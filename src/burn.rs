@@ -0,0 +1,107 @@
+use crate::codec::{embed_encrypted, extract_encrypted, CodecOptions};
+use crate::header::{open_payload, payload_tag, PayloadHeader, TAG_LEN};
+use crate::hint::split_hint;
+use crate::rekey::{put_back, scrub};
+use std::io::{self, ErrorKind};
+
+/// A stego file after `decrypt --burn` counted one view of its payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Burned {
+    /// The payload as it was extracted, before the view was counted, to be decrypted.
+    pub embedded: Vec<u8>,
+    /// The file to write back: the payload with one view less, or the carrier without it once
+    /// no view is left.
+    pub stego: Vec<u8>,
+    /// The number of views left, 0 once the payload is wiped.
+    pub views: u32,
+}
+
+/// Counts one view of a payload embedded with `encrypt --views`, for `decrypt --burn`.
+///
+/// The view counter lives in the payload header, so the header is signed again with one view
+/// less and, as its tag changes, the payload tag is computed again; the ciphertext stays as it
+/// was, and the payload is put back where it was found, as `rekey::rekey_bytes` does. Once the
+/// last view is counted, the payload is taken out of the carrier, or overwritten with noise for
+/// the LSB and robust methods.
+///
+/// This is experimental and only as strong as the file handling around it: anyone can copy
+/// the file before reading it, so it keeps honest readers from reading twice, not attackers.
+///
+/// # Arguments
+///
+/// * `stego` - The bytes of the stego file.
+/// * `opts` - The options the payload was embedded with, including the key.
+///
+/// # Returns
+///
+/// A `Result` containing the payload and the file to write back, an `InvalidInput` error if
+/// the payload has no view counter, or the error of opening it, such as a wrong key.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::burn::burn_bytes;
+/// use stegano::codec::{extract_encrypted, CodecOptions};
+/// use stegano::header::{open_payload, payload_tag, PayloadHeader};
+/// use stegano::png::{encode_grayscale, read_chunks};
+/// use stegano::utils::encrypt_stream;
+///
+/// let header = PayloadHeader::new("xor", 5, 0).with_views(2);
+/// let mut embedded = header.to_bytes("key").unwrap();
+/// encrypt_stream("xor", "key", &b"hello"[..], &mut embedded).unwrap();
+/// let tag = payload_tag("key", &embedded[..]).unwrap();
+/// embedded.extend_from_slice(&tag);
+/// let carrier = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let opts = CodecOptions { key: "key".to_string(), ..CodecOptions::default() };
+/// let stego = stegano::codec::embed_encrypted(&carrier, &embedded, &opts).unwrap();
+///
+/// let first = burn_bytes(&stego, &opts).unwrap();
+/// assert_eq!(first.views, 1);
+/// let (counted, _) = extract_encrypted(&first.stego, &opts).unwrap();
+/// let (header, payload) = open_payload(&counted, "xor", "key").unwrap();
+/// assert_eq!((header.unwrap().views, payload.as_slice()), (1, &b"hello"[..]));
+///
+/// let last = burn_bytes(&first.stego, &opts).unwrap();
+/// assert_eq!(last.views, 0);
+/// assert_eq!(open_payload(&last.embedded, "xor", "key").unwrap().1, b"hello");
+/// assert!(read_chunks(&last.stego).unwrap().iter().all(|chunk| &chunk.r#type != b"stEg"));
+/// ```
+pub fn burn_bytes(stego: &[u8], opts: &CodecOptions) -> io::Result<Burned> {
+    let (encrypted, carrier) = extract_encrypted(stego, opts)?;
+    let (header, _) = open_payload(&encrypted, &opts.algorithm, &opts.key)?;
+    let Some(mut header) = header.filter(|header| header.views > 0) else {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The payload has no view counter to burn, embed it with `encrypt --views`",
+        ));
+    };
+    header.views -= 1;
+    if header.views == 0 {
+        // An empty payload over the noise tells readers there is nothing left, where the noise
+        // alone would read as a payload of garbage.
+        let mut wiped = scrub(&carrier, &encrypted, &[], opts)?;
+        if matches!(opts.method.to_lowercase().as_str(), "lsb" | "robust") {
+            wiped = embed_encrypted(&wiped, &[], opts)?;
+        }
+        return Ok(Burned {
+            embedded: encrypted,
+            stego: wiped,
+            views: 0,
+        });
+    }
+    // The new header is as long as the old one, so it and the tags are overwritten in place.
+    let body = split_hint(&encrypted).1;
+    let start = encrypted.len() - body.len();
+    let header_len = body.len() - PayloadHeader::parse(body)?.1.len();
+    let mut counted = encrypted.clone();
+    counted[start..start + header_len].copy_from_slice(&header.to_bytes(&opts.key)?);
+    let end = counted.len() - TAG_LEN;
+    let tag = payload_tag(&opts.key, &counted[start..end])?;
+    counted[end..].copy_from_slice(&tag);
+    let stego = put_back(stego, &carrier, &encrypted, &counted, opts, opts)?;
+    Ok(Burned {
+        embedded: encrypted,
+        stego,
+        views: header.views,
+    })
+}
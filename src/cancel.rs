@@ -0,0 +1,583 @@
+use crate::storage::{backend, is_remote};
+use std::fmt;
+use std::fs::{self, File, FileTimes};
+use std::io::{self, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Temp files to remove if the process is forced to exit.
+static TEMP_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Records a temp file so that a forced exit still removes it.
+pub fn register_temp_file(path: &Path) {
+    if let Ok(mut files) = TEMP_FILES.lock() {
+        files.push(path.to_path_buf());
+    }
+}
+
+/// Forgets a temp file recorded with `register_temp_file`, once it is removed or renamed.
+pub fn unregister_temp_file(path: &Path) {
+    if let Ok(mut files) = TEMP_FILES.lock() {
+        files.retain(|file| file != path);
+    }
+}
+
+/// Removes every registered temp file.
+fn remove_temp_files() {
+    if let Ok(mut files) = TEMP_FILES.lock() {
+        for path in files.drain(..) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// The error carried by `io::Error`s raised when an operation is cancelled.
+///
+/// `ErrorKind::Interrupted` can't be used since `io::copy` and friends retry on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Operation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Checks whether an error was raised by a cancelled operation.
+pub fn is_cancelled(err: &io::Error) -> bool {
+    err.get_ref().is_some_and(|inner| inner.is::<Cancelled>())
+}
+
+/// A cheap, cloneable handle to cancel an operation cooperatively.
+///
+/// Long-running operations check the token between steps and while reading their input,
+/// and return a `Cancelled` error once it is cancelled. Outputs written through `OutputFile`
+/// are then discarded, so nothing partial is left behind. Clones share the same state, so a
+/// server can keep one clone per request and cancel it from another thread.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use stegano::cancel::{is_cancelled, CancellationToken};
+///
+/// let token = CancellationToken::new();
+/// let mut reader = token.reader(&b"payload"[..]);
+/// token.clone().cancel();
+/// let err = reader.read(&mut [0; 4]).unwrap_err();
+/// assert!(is_cancelled(&err));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+impl CancellationToken {
+    /// Creates a token that isn't cancelled.
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Cancels the operations holding this token or one of its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true once `cancel` has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns a `Cancelled` error if the token is cancelled.
+    pub fn check(&self) -> io::Result<()> {
+        if self.is_cancelled() {
+            return Err(io::Error::other(Cancelled));
+        }
+        Ok(())
+    }
+
+    /// Wraps a reader so that reads fail once the token is cancelled.
+    pub fn reader<R: Read>(&self, inner: R) -> CancellableReader<R> {
+        CancellableReader {
+            inner,
+            token: self.clone(),
+        }
+    }
+}
+
+/// A reader that stops with a `Cancelled` error once its token is cancelled.
+#[derive(Debug)]
+pub struct CancellableReader<R> {
+    inner: R,
+    token: CancellationToken,
+}
+
+impl<R: Read> Read for CancellableReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.token.check()?;
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for CancellableReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+/// Installs a Ctrl-C (and SIGTERM) handler tied to a fresh token.
+///
+/// The first signal cancels the token, calls `on_interrupt` so the caller can say so, and
+/// lets the running operation clean up. A second one removes the registered temp files and
+/// exits right away with status 130.
+///
+/// # Returns
+///
+/// A `Result` containing the token, or an error if a handler is already installed.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn install_signal_handler<F>(on_interrupt: F) -> io::Result<CancellationToken>
+where
+    F: Fn() + Send + 'static,
+{
+    let token = CancellationToken::new();
+    let handler_token = token.clone();
+    ctrlc::set_handler(move || {
+        if handler_token.is_cancelled() {
+            remove_temp_files();
+            std::process::exit(130);
+        }
+        handler_token.cancel();
+        on_interrupt();
+    })
+    .map_err(|err| io::Error::other(err.to_string()))?;
+    Ok(token)
+}
+
+/// A file that only replaces its target when committed.
+///
+/// Data is written to a hidden temp file next to the target, which is synced to disk and
+/// renamed over it by `commit`, taking the permissions of the file it replaces. If the
+/// `OutputFile` is dropped first, because of an error or a cancellation, the temp file is
+/// removed and the target is left as it was, which also makes in-place edits safe. Targets
+/// that aren't regular files, such as `/dev/null`, are written directly, and remote targets
+/// such as `s3://bucket/key` are held in memory and stored by `commit`.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use stegano::cancel::{CancellationToken, OutputFile};
+///
+/// let path = std::env::temp_dir().join("stegano-output-file-doc.bin");
+/// let token = CancellationToken::new();
+///
+/// let mut output = OutputFile::create(&path, &token).unwrap();
+/// output.write_all(b"partial").unwrap();
+/// drop(output);
+/// assert!(!path.exists());
+///
+/// let mut output = OutputFile::create(&path, &token).unwrap();
+/// output.write_all(b"complete").unwrap();
+/// output.commit().unwrap();
+/// assert_eq!(std::fs::read(&path).unwrap(), b"complete");
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct OutputFile {
+    /// Where the data goes, or `None` for a dry run.
+    writer: Option<Sink>,
+    target: PathBuf,
+    /// The temp file, or `None` when writing directly to a special file.
+    temp: Option<PathBuf>,
+    /// The permissions of the file being replaced, if any.
+    permissions: Option<fs::Permissions>,
+    token: CancellationToken,
+    /// The number of bytes written so far.
+    written: u64,
+}
+
+/// Where the data of an `OutputFile` goes.
+#[derive(Debug)]
+enum Sink {
+    /// The temp file, or the target itself when it is a special file.
+    File(BufWriter<File>),
+    /// The data for a remote target, stored in one go by `commit`.
+    Remote(Vec<u8>),
+}
+
+impl OutputFile {
+    /// Starts writing a file that will replace `target` on `commit`.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The path of the final file.
+    /// * `token` - Makes `commit` fail once cancelled.
+    pub fn create<P: AsRef<Path>>(target: P, token: &CancellationToken) -> io::Result<Self> {
+        let target = target.as_ref().to_path_buf();
+        if target.to_str().is_some_and(is_remote) {
+            return Ok(OutputFile {
+                writer: Some(Sink::Remote(Vec::new())),
+                target,
+                temp: None,
+                permissions: None,
+                token: token.clone(),
+                written: 0,
+            });
+        }
+        let metadata = fs::metadata(&target).ok();
+        if metadata.as_ref().is_some_and(|meta| !meta.is_file()) {
+            return Ok(OutputFile {
+                writer: Some(Sink::File(BufWriter::new(File::create(&target)?))),
+                target,
+                temp: None,
+                permissions: None,
+                token: token.clone(),
+                written: 0,
+            });
+        }
+        let name = target
+            .file_name()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "Output is not a file path"))?
+            .to_string_lossy();
+        let temp = target.with_file_name(format!(".{}.{}.part", name, std::process::id()));
+        let file = File::create(&temp)?;
+        register_temp_file(&temp);
+        Ok(OutputFile {
+            writer: Some(Sink::File(BufWriter::new(file))),
+            target,
+            temp: Some(temp),
+            permissions: metadata.map(|meta| meta.permissions()),
+            token: token.clone(),
+            written: 0,
+        })
+    }
+
+    /// Starts a dry run for `target`, which counts the data written and discards it, so that
+    /// nothing is created on disk and `commit` leaves the target as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use stegano::cancel::{CancellationToken, OutputFile};
+    ///
+    /// let path = std::env::temp_dir().join("stegano-dry-run-doc.bin");
+    /// let mut output = OutputFile::discard(&path, &CancellationToken::new());
+    /// output.write_all(b"never written").unwrap();
+    /// assert_eq!(output.written(), 13);
+    /// output.commit().unwrap();
+    /// assert!(!path.exists());
+    /// ```
+    pub fn discard<P: AsRef<Path>>(target: P, token: &CancellationToken) -> Self {
+        OutputFile {
+            writer: None,
+            target: target.as_ref().to_path_buf(),
+            temp: None,
+            permissions: None,
+            token: token.clone(),
+            written: 0,
+        }
+    }
+
+    /// Returns the number of bytes written so far, which is the size of the file once
+    /// committed.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Flushes the data, syncs it to disk and moves it to the target, or stores it at a
+    /// remote target, unless the token was cancelled.
+    pub fn commit(mut self) -> io::Result<()> {
+        self.token.check()?;
+        let writer = match &mut self.writer {
+            Some(Sink::File(writer)) => writer,
+            Some(Sink::Remote(data)) => {
+                let location = self.target.to_string_lossy();
+                return backend(&location)?.store(&location, data);
+            }
+            None => return Ok(()),
+        };
+        writer.flush()?;
+        if let Some(temp) = self.temp.take() {
+            let file = writer.get_ref();
+            let mut renamed = file.sync_all();
+            if let (Ok(()), Some(permissions)) = (&renamed, self.permissions.take()) {
+                renamed = file.set_permissions(permissions);
+            }
+            if renamed.is_ok() {
+                renamed = fs::rename(&temp, &self.target);
+            }
+            if renamed.is_err() {
+                let _ = fs::remove_file(&temp);
+            }
+            unregister_temp_file(&temp);
+            renamed?;
+            sync_parent(&self.target);
+        }
+        Ok(())
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = match &mut self.writer {
+            Some(Sink::File(writer)) => writer.write(buf)?,
+            Some(Sink::Remote(data)) => {
+                data.extend_from_slice(buf);
+                buf.len()
+            }
+            None => buf.len(),
+        };
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Some(Sink::File(writer)) => writer.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Drop for OutputFile {
+    fn drop(&mut self) {
+        if let Some(temp) = self.temp.take() {
+            let _ = fs::remove_file(&temp);
+            unregister_temp_file(&temp);
+        }
+    }
+}
+
+/// Syncs the directory of a renamed file so that the rename itself survives a crash, where
+/// the platform allows it.
+fn sync_parent(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// Keeps the modification time of a file edited in place, for `--in-place --keep-mtime`.
+///
+/// The time is read when the guard is created and written back when it is dropped, whether
+/// the edit succeeded or not, so tools that go by modification times don't see a change.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+/// use stegano::cancel::{write_output, CancellationToken, KeepModified};
+///
+/// let path = std::env::temp_dir().join("stegano-keep-modified-doc.bin");
+/// let token = CancellationToken::new();
+/// write_output(&path, b"before", &token).unwrap();
+/// let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+/// std::fs::File::options().write(true).open(&path).unwrap().set_modified(old).unwrap();
+///
+/// {
+///     let _keep = KeepModified::new(&path).unwrap();
+///     write_output(&path, b"after", &token).unwrap();
+/// }
+/// assert_eq!(std::fs::metadata(&path).unwrap().modified().unwrap(), old);
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct KeepModified {
+    path: PathBuf,
+    modified: SystemTime,
+}
+
+impl KeepModified {
+    /// Records the modification time of `path`.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let modified = fs::metadata(&path)?.modified()?;
+        Ok(KeepModified { path, modified })
+    }
+}
+
+impl Drop for KeepModified {
+    fn drop(&mut self) {
+        if let Ok(file) = File::options().write(true).open(&self.path) {
+            let _ = file.set_modified(self.modified);
+        }
+    }
+}
+
+/// Gives a written file the access and modification times of the file it was made from, for
+/// `--preserve-times`.
+///
+/// The times of the source are read when the guard is created, before the source is read and
+/// its access time moves. When the guard is dropped, they are written back to the source, and
+/// to the target if it was written in the meantime, so that a dry run or a failed edit leaves
+/// an existing file alone.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+/// use stegano::cancel::{write_output, CancellationToken, KeepTimes};
+///
+/// let source = std::env::temp_dir().join("stegano-keep-times-source.bin");
+/// let target = std::env::temp_dir().join("stegano-keep-times-target.bin");
+/// let token = CancellationToken::new();
+/// write_output(&source, b"carrier", &token).unwrap();
+/// let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+/// let times = std::fs::FileTimes::new().set_accessed(old).set_modified(old);
+/// std::fs::File::options().write(true).open(&source).unwrap().set_times(times).unwrap();
+///
+/// {
+///     let _keep = KeepTimes::new(&source, &target).unwrap();
+///     write_output(&target, b"stego", &token).unwrap();
+/// }
+/// let metadata = std::fs::metadata(&target).unwrap();
+/// assert_eq!(metadata.modified().unwrap(), old);
+/// assert_eq!(metadata.accessed().unwrap(), old);
+/// std::fs::remove_file(&source).unwrap();
+/// std::fs::remove_file(&target).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct KeepTimes {
+    source: PathBuf,
+    target: PathBuf,
+    accessed: SystemTime,
+    modified: SystemTime,
+    before: Option<SystemTime>,
+}
+
+impl KeepTimes {
+    /// Records the times of `source`, and the modification time `target` has so far, if any.
+    pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(source: P, target: Q) -> io::Result<Self> {
+        let source = source.as_ref().to_path_buf();
+        let metadata = fs::metadata(&source)?;
+        let target = target.as_ref().to_path_buf();
+        let before = fs::metadata(&target).and_then(|m| m.modified()).ok();
+        Ok(KeepTimes {
+            source,
+            accessed: metadata.accessed()?,
+            modified: metadata.modified()?,
+            target,
+            before,
+        })
+    }
+}
+
+impl Drop for KeepTimes {
+    fn drop(&mut self) {
+        let written = fs::metadata(&self.target)
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| Some(modified) != self.before);
+        let times = FileTimes::new()
+            .set_accessed(self.accessed)
+            .set_modified(self.modified);
+        for path in [&self.source, &self.target] {
+            if path == &self.target && !written {
+                continue;
+            }
+            if let Ok(file) = File::options().write(true).open(path) {
+                let _ = file.set_times(times);
+            }
+        }
+    }
+}
+
+/// Writes a whole file through an `OutputFile`, so that it is either complete or untouched.
+pub fn write_output<P: AsRef<Path>>(
+    target: P,
+    data: &[u8],
+    token: &CancellationToken,
+) -> io::Result<()> {
+    let mut output = OutputFile::create(target, token)?;
+    output.write_all(data)?;
+    output.commit()
+}
+
+/// Updates `target`, which holds `original`, to hold `data`, writing only the bytes that
+/// changed when both have the same size.
+///
+/// Re-encrypting a payload in place usually keeps the file size, and pushing a carrier of
+/// several gigabytes through memory to change a few hundred bytes of it is wasteful. The file
+/// is copied next to itself by `fs::copy`, which leaves the copying to the kernel and shares
+/// the blocks on file systems that can, the bytes from the first to the last difference are
+/// written over the copy, and the copy is synced and renamed over the file. As with
+/// `write_output`, an interrupted patch leaves the file as it was. When the sizes differ, or
+/// the file no longer has the size of `original`, it is rewritten whole with `write_output`
+/// instead.
+///
+/// # Returns
+///
+/// A `Result` containing the range of bytes patched, empty if nothing changed, or `None` if
+/// the file was rewritten whole.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::cancel::{patch_output, CancellationToken};
+///
+/// let path = std::env::temp_dir().join("stegano-patch-doc.bin");
+/// let token = CancellationToken::new();
+/// std::fs::write(&path, b"carrier with an old payload").unwrap();
+/// let patched = patch_output(&path, b"carrier with an old payload", b"carrier with a new payload", &token);
+/// assert_eq!(patched.unwrap(), None);
+/// let patched = patch_output(&path, b"carrier with a new payload", b"carrier with a NEW payload", &token);
+/// assert_eq!(patched.unwrap(), Some(15..18));
+/// assert_eq!(std::fs::read(&path).unwrap(), b"carrier with a NEW payload");
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn patch_output<P: AsRef<Path>>(
+    target: P,
+    original: &[u8],
+    data: &[u8],
+    token: &CancellationToken,
+) -> io::Result<Option<Range<usize>>> {
+    let target = target.as_ref();
+    let same_size = original.len() == data.len()
+        && fs::metadata(target).is_ok_and(|meta| meta.is_file() && meta.len() == data.len() as u64);
+    if !same_size {
+        write_output(target, data, token)?;
+        return Ok(None);
+    }
+    let differs = |(a, b): (&u8, &u8)| a != b;
+    let Some(start) = original.iter().zip(data).position(differs) else {
+        return Ok(Some(0..0));
+    };
+    let end = data.len()
+        - original
+            .iter()
+            .rev()
+            .zip(data.iter().rev())
+            .position(differs)
+            .unwrap_or(0);
+    token.check()?;
+    let mut output = OutputFile::create(target, token)?;
+    if let (Some(temp), Some(Sink::File(writer))) = (&output.temp, &mut output.writer) {
+        fs::copy(target, temp)?;
+        writer.seek(SeekFrom::Start(start as u64))?;
+        writer.write_all(&data[start..end])?;
+    }
+    output.commit()?;
+    Ok(Some(start..end))
+}
@@ -0,0 +1,592 @@
+use std::collections::HashSet;
+use std::fmt;
+
+/// A PNG chunk type: one of the types registered in the PNG specification and its extensions
+/// (APNG, PNGEXT), which stegano knows the name and placement rules of, or any other.
+///
+/// The case of each of the four letters is a property bit: an uppercase first letter makes the
+/// chunk critical, a lowercase second letter private, and a lowercase fourth letter safe to
+/// copy by editors that don't know the chunk. The third letter is reserved and uppercase.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::chunk_type::ChunkType;
+///
+/// assert_eq!(ChunkType::from(*b"gAMA"), ChunkType::Gama);
+/// assert_eq!(ChunkType::Gama.name(), Some("Gamma"));
+/// assert_eq!(ChunkType::from(*b"stEg"), ChunkType::Other(*b"stEg"));
+/// assert_eq!(ChunkType::from(*b"stEg").to_string(), "stEg");
+/// assert_eq!(<[u8; 4]>::from(ChunkType::Iend), *b"IEND");
+///
+/// for name in stegano::png::REGISTERED_CHUNKS {
+///     assert!(ChunkType::from(*name).is_registered());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChunkType {
+    /// `IHDR`, the image header.
+    Ihdr,
+    /// `PLTE`, the palette.
+    Plte,
+    /// `IDAT`, the image data.
+    Idat,
+    /// `IEND`, the image trailer.
+    Iend,
+    /// `acTL`, the APNG animation control.
+    Actl,
+    /// `bKGD`, the background colour.
+    Bkgd,
+    /// `cHRM`, the primary chromaticities and white point.
+    Chrm,
+    /// `cICP`, the coding-independent code points.
+    Cicp,
+    /// `cLLI`, the content light level.
+    Clli,
+    /// `dSIG`, a digital signature.
+    Dsig,
+    /// `eXIf`, Exif metadata.
+    Exif,
+    /// `fcTL`, an APNG frame control.
+    Fctl,
+    /// `fdAT`, APNG frame data.
+    Fdat,
+    /// `fRAc`, fractal image parameters.
+    Frac,
+    /// `gAMA`, the image gamma.
+    Gama,
+    /// `gIFg`, a GIF graphic control extension.
+    Gifg,
+    /// `gIFt`, a GIF plain text extension.
+    Gift,
+    /// `gIFx`, a GIF application extension.
+    Gifx,
+    /// `hIST`, the palette histogram.
+    Hist,
+    /// `iCCP`, an embedded ICC profile.
+    Iccp,
+    /// `iTXt`, international text.
+    Itxt,
+    /// `mDCV`, the mastering display colour volume.
+    Mdcv,
+    /// `oFFs`, the image offset.
+    Offs,
+    /// `pCAL`, the pixel calibration.
+    Pcal,
+    /// `pHYs`, the physical pixel dimensions.
+    Phys,
+    /// `sBIT`, the significant bits.
+    Sbit,
+    /// `sCAL`, the physical scale.
+    Scal,
+    /// `sPLT`, a suggested palette.
+    Splt,
+    /// `sRGB`, the standard RGB colour space.
+    Srgb,
+    /// `sTER`, the stereo image indicator.
+    Ster,
+    /// `tEXt`, Latin-1 text.
+    Text,
+    /// `tIME`, the last modification time.
+    Time,
+    /// `tRNS`, the transparency.
+    Trns,
+    /// `zTXt`, compressed text.
+    Ztxt,
+    /// Any other type, such as the private `stEg` chunk of a payload.
+    Other([u8; 4]),
+}
+
+/// Where a chunk type may appear among the other chunks of a PNG file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// The first chunk of the file, `IHDR`.
+    First,
+    /// The last chunk of the file, `IEND`.
+    Last,
+    /// Before `PLTE` and `IDAT`, such as the colour space chunks.
+    BeforePlte,
+    /// Before the first `IDAT`.
+    BeforeIdat,
+    /// After `PLTE`, if there is one, and before `IDAT`.
+    BetweenPlteAndIdat,
+    /// After the first `IDAT`, like the APNG frame data.
+    AfterIdat,
+    /// Anywhere between `IHDR` and `IEND`.
+    Anywhere,
+}
+
+/// The registered chunk types, with their bytes, names, placement and whether a file may hold
+/// several.
+const KNOWN: [(ChunkType, &[u8; 4], &str, Placement, bool); 34] = [
+    (
+        ChunkType::Ihdr,
+        b"IHDR",
+        "Image header",
+        Placement::First,
+        false,
+    ),
+    (
+        ChunkType::Plte,
+        b"PLTE",
+        "Palette",
+        Placement::BeforeIdat,
+        false,
+    ),
+    (
+        ChunkType::Idat,
+        b"IDAT",
+        "Image data",
+        Placement::Anywhere,
+        true,
+    ),
+    (
+        ChunkType::Iend,
+        b"IEND",
+        "Image trailer",
+        Placement::Last,
+        false,
+    ),
+    (
+        ChunkType::Actl,
+        b"acTL",
+        "Animation control",
+        Placement::BeforeIdat,
+        false,
+    ),
+    (
+        ChunkType::Bkgd,
+        b"bKGD",
+        "Background colour",
+        Placement::BetweenPlteAndIdat,
+        false,
+    ),
+    (
+        ChunkType::Chrm,
+        b"cHRM",
+        "Primary chromaticities",
+        Placement::BeforePlte,
+        false,
+    ),
+    (
+        ChunkType::Cicp,
+        b"cICP",
+        "Coding-independent code points",
+        Placement::BeforePlte,
+        false,
+    ),
+    (
+        ChunkType::Clli,
+        b"cLLI",
+        "Content light level",
+        Placement::BeforePlte,
+        false,
+    ),
+    (
+        ChunkType::Dsig,
+        b"dSIG",
+        "Digital signature",
+        Placement::Anywhere,
+        true,
+    ),
+    (
+        ChunkType::Exif,
+        b"eXIf",
+        "Exif metadata",
+        Placement::BeforeIdat,
+        false,
+    ),
+    (
+        ChunkType::Fctl,
+        b"fcTL",
+        "Frame control",
+        Placement::Anywhere,
+        true,
+    ),
+    (
+        ChunkType::Fdat,
+        b"fdAT",
+        "Frame data",
+        Placement::AfterIdat,
+        true,
+    ),
+    (
+        ChunkType::Frac,
+        b"fRAc",
+        "Fractal image parameters",
+        Placement::Anywhere,
+        true,
+    ),
+    (
+        ChunkType::Gama,
+        b"gAMA",
+        "Gamma",
+        Placement::BeforePlte,
+        false,
+    ),
+    (
+        ChunkType::Gifg,
+        b"gIFg",
+        "GIF graphic control",
+        Placement::Anywhere,
+        true,
+    ),
+    (
+        ChunkType::Gift,
+        b"gIFt",
+        "GIF plain text",
+        Placement::Anywhere,
+        true,
+    ),
+    (
+        ChunkType::Gifx,
+        b"gIFx",
+        "GIF application extension",
+        Placement::Anywhere,
+        true,
+    ),
+    (
+        ChunkType::Hist,
+        b"hIST",
+        "Palette histogram",
+        Placement::BetweenPlteAndIdat,
+        false,
+    ),
+    (
+        ChunkType::Iccp,
+        b"iCCP",
+        "ICC profile",
+        Placement::BeforePlte,
+        false,
+    ),
+    (
+        ChunkType::Itxt,
+        b"iTXt",
+        "International text",
+        Placement::Anywhere,
+        true,
+    ),
+    (
+        ChunkType::Mdcv,
+        b"mDCV",
+        "Mastering display colour volume",
+        Placement::BeforePlte,
+        false,
+    ),
+    (
+        ChunkType::Offs,
+        b"oFFs",
+        "Image offset",
+        Placement::BeforeIdat,
+        false,
+    ),
+    (
+        ChunkType::Pcal,
+        b"pCAL",
+        "Pixel calibration",
+        Placement::BeforeIdat,
+        false,
+    ),
+    (
+        ChunkType::Phys,
+        b"pHYs",
+        "Physical pixel dimensions",
+        Placement::BeforeIdat,
+        false,
+    ),
+    (
+        ChunkType::Sbit,
+        b"sBIT",
+        "Significant bits",
+        Placement::BeforePlte,
+        false,
+    ),
+    (
+        ChunkType::Scal,
+        b"sCAL",
+        "Physical scale",
+        Placement::BeforeIdat,
+        false,
+    ),
+    (
+        ChunkType::Splt,
+        b"sPLT",
+        "Suggested palette",
+        Placement::BeforeIdat,
+        true,
+    ),
+    (
+        ChunkType::Srgb,
+        b"sRGB",
+        "Standard RGB colour space",
+        Placement::BeforePlte,
+        false,
+    ),
+    (
+        ChunkType::Ster,
+        b"sTER",
+        "Stereo image",
+        Placement::BeforeIdat,
+        false,
+    ),
+    (ChunkType::Text, b"tEXt", "Text", Placement::Anywhere, true),
+    (
+        ChunkType::Time,
+        b"tIME",
+        "Last modification time",
+        Placement::Anywhere,
+        false,
+    ),
+    (
+        ChunkType::Trns,
+        b"tRNS",
+        "Transparency",
+        Placement::BetweenPlteAndIdat,
+        false,
+    ),
+    (
+        ChunkType::Ztxt,
+        b"zTXt",
+        "Compressed text",
+        Placement::Anywhere,
+        true,
+    ),
+];
+
+impl ChunkType {
+    /// Returns the entry of a registered type in `KNOWN`.
+    fn known(
+        self,
+    ) -> Option<&'static (ChunkType, &'static [u8; 4], &'static str, Placement, bool)> {
+        KNOWN.iter().find(|entry| entry.0 == self)
+    }
+
+    /// Returns the four bytes of the type, as written in the file.
+    pub fn bytes(self) -> [u8; 4] {
+        match self {
+            ChunkType::Other(bytes) => bytes,
+            known => {
+                *known
+                    .known()
+                    .expect("every variant but Other is in KNOWN")
+                    .1
+            }
+        }
+    }
+
+    /// Returns the name of a registered type, such as "Image header" for `IHDR`.
+    pub fn name(self) -> Option<&'static str> {
+        self.known().map(|entry| entry.2)
+    }
+
+    /// Returns whether the type is registered in the PNG specification or its extensions.
+    pub fn is_registered(self) -> bool {
+        !matches!(self, ChunkType::Other(_))
+    }
+
+    /// Returns whether decoders must understand the chunk to display the image, from the case
+    /// of the first letter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::chunk_type::ChunkType;
+    ///
+    /// assert!(ChunkType::Idat.is_critical());
+    /// assert!(!ChunkType::from(*b"stEg").is_critical());
+    /// assert!(ChunkType::from(*b"StEg").is_critical());
+    /// ```
+    pub fn is_critical(self) -> bool {
+        self.bytes()[0].is_ascii_uppercase()
+    }
+
+    /// Returns whether the type is public, reserved for registered chunks, from the case of the
+    /// second letter.
+    pub fn is_public(self) -> bool {
+        self.bytes()[1].is_ascii_uppercase()
+    }
+
+    /// Returns whether editors that don't know the chunk may copy it into a modified image,
+    /// from the case of the fourth letter.
+    pub fn is_safe_to_copy(self) -> bool {
+        self.bytes()[3].is_ascii_lowercase()
+    }
+
+    /// Returns where chunks of this type may appear; unregistered types go anywhere.
+    pub fn placement(self) -> Placement {
+        self.known().map_or(Placement::Anywhere, |entry| entry.3)
+    }
+
+    /// Returns whether a file may hold several chunks of this type; unregistered types may
+    /// repeat.
+    pub fn is_repeatable(self) -> bool {
+        self.known().is_none_or(|entry| entry.4)
+    }
+
+    /// Describes the type for `show-meta`: its name, or "unknown", and its property bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::chunk_type::ChunkType;
+    ///
+    /// assert_eq!(ChunkType::Idat.describe(), "Image data (critical, public)");
+    /// assert_eq!(
+    ///     ChunkType::from(*b"stEg").describe(),
+    ///     "unknown (ancillary, private, safe to copy)"
+    /// );
+    /// ```
+    pub fn describe(self) -> String {
+        let mut bits = vec![
+            if self.is_critical() {
+                "critical"
+            } else {
+                "ancillary"
+            },
+            if self.is_public() {
+                "public"
+            } else {
+                "private"
+            },
+        ];
+        if self.is_safe_to_copy() {
+            bits.push("safe to copy");
+        }
+        format!("{} ({})", self.name().unwrap_or("unknown"), bits.join(", "))
+    }
+}
+
+impl From<[u8; 4]> for ChunkType {
+    fn from(bytes: [u8; 4]) -> ChunkType {
+        KNOWN
+            .iter()
+            .find(|entry| *entry.1 == bytes)
+            .map_or(ChunkType::Other(bytes), |entry| entry.0)
+    }
+}
+
+impl From<ChunkType> for [u8; 4] {
+    fn from(r#type: ChunkType) -> [u8; 4] {
+        r#type.bytes()
+    }
+}
+
+/// Compares a type with the four letters of a chunk, as a chunk walk reads them.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::chunk_type::ChunkType;
+///
+/// assert!(ChunkType::Iend == *b"IEND");
+/// assert!(*b"stEg" == ChunkType::Other(*b"stEg"));
+/// assert!(ChunkType::Idat != *b"IDAX");
+/// ```
+impl PartialEq<[u8; 4]> for ChunkType {
+    fn eq(&self, bytes: &[u8; 4]) -> bool {
+        self.bytes() == *bytes
+    }
+}
+
+impl PartialEq<ChunkType> for [u8; 4] {
+    fn eq(&self, r#type: &ChunkType) -> bool {
+        *r#type == *self
+    }
+}
+
+impl fmt::Display for ChunkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.bytes()))
+    }
+}
+
+/// A chunk out of the place the PNG specification gives its type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderViolation {
+    /// The index of the chunk in the file, counted from 0.
+    pub index: usize,
+    /// The type of the chunk.
+    pub r#type: ChunkType,
+    /// What is wrong with its place, such as "comes after IDAT".
+    pub problem: &'static str,
+}
+
+impl fmt::Display for OrderViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (chunk #{}) {}",
+            self.r#type, self.index, self.problem
+        )
+    }
+}
+
+/// Checks the chunk types of a PNG file, in file order, against the placement rules of the
+/// registered types: `IHDR` first and `IEND` last, colour space chunks before `PLTE`, the
+/// chunks describing the pixels before `IDAT`, consecutive `IDAT` chunks, and no second chunk
+/// of a type that may appear once.
+///
+/// Unregistered types may go anywhere, so payload chunks never count as violations.
+///
+/// # Returns
+///
+/// The violations, in file order; empty for a well-ordered file.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::chunk_type::{check_order, ChunkType};
+///
+/// let types = |names: &[&[u8; 4]]| -> Vec<ChunkType> {
+///     names.iter().map(|name| ChunkType::from(**name)).collect()
+/// };
+/// assert!(check_order(&types(&[b"IHDR", b"gAMA", b"IDAT", b"stEg", b"IEND"])).is_empty());
+///
+/// let violations = check_order(&types(&[b"IHDR", b"IDAT", b"gAMA", b"IDAT", b"IEND"]));
+/// let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+/// assert_eq!(
+///     messages,
+///     ["gAMA (chunk #2) comes after IDAT", "IDAT (chunk #3) isn't next to the other IDAT chunks"]
+/// );
+///
+/// let violations = check_order(&types(&[b"IHDR", b"tRNS", b"PLTE", b"tIME", b"tIME", b"IDAT", b"IEND"]));
+/// assert_eq!(violations[0].to_string(), "tRNS (chunk #1) comes before PLTE");
+/// assert_eq!(violations[1].to_string(), "tIME (chunk #4) appears more than once");
+/// ```
+pub fn check_order(types: &[ChunkType]) -> Vec<OrderViolation> {
+    let plte = types.iter().position(|r#type| *r#type == ChunkType::Plte);
+    let first_idat = types.iter().position(|r#type| *r#type == ChunkType::Idat);
+    let mut violations = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, &r#type) in types.iter().enumerate() {
+        let repeated = !seen.insert(r#type);
+        let after_plte = plte.is_some_and(|plte| plte < index);
+        let after_idat = first_idat.is_some_and(|idat| idat < index);
+        let problem = match r#type.placement() {
+            Placement::First if index != 0 => Some("isn't the first chunk"),
+            Placement::Last if index + 1 != types.len() => Some("isn't the last chunk"),
+            Placement::BeforePlte if after_idat => Some("comes after IDAT"),
+            Placement::BeforePlte if after_plte => Some("comes after PLTE"),
+            Placement::BeforeIdat | Placement::BetweenPlteAndIdat if after_idat => {
+                Some("comes after IDAT")
+            }
+            Placement::BetweenPlteAndIdat if plte.is_some_and(|plte| plte > index) => {
+                Some("comes before PLTE")
+            }
+            Placement::AfterIdat if !after_idat => Some("comes before IDAT"),
+            _ if r#type == ChunkType::Idat && after_idat && types[index - 1] != r#type => {
+                Some("isn't next to the other IDAT chunks")
+            }
+            _ if repeated && !r#type.is_repeatable() => Some("appears more than once"),
+            _ => None,
+        };
+        if let Some(problem) = problem {
+            violations.push(OrderViolation {
+                index,
+                r#type,
+                problem,
+            });
+        }
+    }
+    violations
+}
@@ -1,5 +1,20 @@
+use crate::cancel::CancellationToken;
+use crate::codec::CodecOptions;
+use crate::encoding::{parse_payload_encoding, PayloadEncoding};
+use crate::header::parse_expiry;
+use crate::lsb::{parse_channels, Channels};
+use crate::meta::{parse_meta_time, parse_text_entry};
+use crate::png::{
+    parse_chunk_position, parse_chunk_type, parse_offset_spec, ChunkPosition, OffsetSpec,
+};
+use crate::quality::{parse_max_distortion, MaxDistortion};
+use crate::shamir::{parse_threshold, Threshold};
+use crate::spill::parse_size;
+use crate::utils::{parse_hex_range, DEFAULT_BLOCK_MODE};
+use crate::watermark::DEFAULT_STRENGTH;
 use clap::builder::styling::{AnsiColor, Effects, Styles};
 use clap::{Parser, Subcommand};
+use std::ops::Range;
 
 fn styles() -> Styles {
     Styles::styled()
@@ -23,91 +38,593 @@ pub struct Cli {
     /// Subcommands for encryption and decryption.
     #[command(subcommand)]
     pub command: Option<SteganoCommands>,
+
+    /// Sets how errors are reported on stderr: human, or json for a structured object with
+    /// the error category and exit code.
+    #[arg(long = "error-format", global = true, default_value = "human", value_parser = ["human", "json"])]
+    pub error_format: String,
+
+    /// Sets when human output is colored: auto (on terminals, unless NO_COLOR is set), always
+    /// or never.
+    #[arg(long = "color", global = true, default_value = "auto", value_parser = ["auto", "always", "never"])]
+    pub color: String,
+
+    /// Sets the language of the messages, such as fr or fr_FR.UTF-8 (default is LC_ALL,
+    /// LC_MESSAGES or LANG, then en). STEGANO_LOCALE_DIR adds catalogs of other languages.
+    #[arg(long = "lang", global = true)]
+    pub lang: Option<String>,
 }
 
 /// Represents available subcommands for the stegano CLI.
 #[derive(Subcommand, Debug)]
 pub enum SteganoCommands {
     /// Subcommand for encryption.
-    Encrypt(EncryptCmd),
+    Encrypt(Box<EncryptCmd>),
+
+    /// Subcommand for revealing an embedded payload, leaving the input untouched.
+    Extract(DecryptCmd),
 
-    /// Subcommand for decryption.
+    /// Subcommand for writing the carrier without its payload, aka restoring the original file.
+    #[command(alias = "restore")]
+    Remove(DecryptCmd),
+
+    /// Subcommand for decryption, which extracts and removes in one go (deprecated, use
+    /// extract or remove).
     Decrypt(DecryptCmd),
 
     /// Subcommand for showing metadata.
     ShowMeta(ShowMetaCmd),
+
+    /// Subcommand for showing the chain of custody of a carrier.
+    Provenance(ProvenanceCmd),
+
+    /// Subcommand for building or unpacking a PNG+ZIP polyglot.
+    Polyglot(PolyglotCmd),
+
+    /// Subcommand for uploading a stego file to a file host.
+    #[cfg(feature = "net")]
+    Share(ShareCmd),
+
+    /// Subcommand for serving covers over HTTP with queued payloads embedded on the fly.
+    #[cfg(feature = "net")]
+    Serve(ServeCmd),
+
+    /// Subcommand for downloading a stego file and extracting its payload.
+    #[cfg(feature = "net")]
+    Fetch(FetchCmd),
+
+    /// Subcommand for running a long embed/extract stability test.
+    Soak(SoakCmd),
+
+    /// Subcommand for comparing two PNG files chunk by chunk.
+    Diff(DiffCmd),
+
+    /// Subcommand for inspecting an embedded payload without the key.
+    Peek(PeekCmd),
+
+    /// Subcommand for printing or changing the defaults of the configuration file.
+    Config(ConfigCmd),
+
+    /// Subcommand for re-encrypting an embedded payload with a new key.
+    Rekey(RekeyCmd),
+
+    /// Subcommand for embedding or verifying an ownership watermark.
+    Watermark(WatermarkCmd),
+
+    /// Subcommand for inserting a raw chunk into a PNG file, without encryption.
+    InjectChunk(InjectChunkCmd),
+
+    /// Subcommand for removing chunks from a PNG file by type or index.
+    RemoveChunk(RemoveChunkCmd),
+
+    /// Subcommand for writing the raw data of PNG chunks to files.
+    ExtractChunk(ExtractChunkCmd),
+
+    /// Subcommand for reading or replacing the ICC profile of a PNG or JPEG file.
+    Icc(IccCmd),
+
+    /// Subcommand for storing files in an encrypted vault inside a PNG file.
+    Vault(VaultCmd),
+
+    /// Subcommand for editing the standard text and timestamp metadata of a PNG file.
+    Meta(MetaCmd),
+
+    /// Subcommand for synthesizing a PNG carrier when no suitable one is at hand.
+    GenerateCarrier(GenerateCarrierCmd),
+
+    /// Subcommand for opening a window to hide or reveal a payload without the command line.
+    #[cfg(feature = "gui")]
+    Gui(GuiCmd),
 }
 
 /// Subcommand for encryption.
 #[derive(Parser, Debug)]
 pub struct EncryptCmd {
-    /// Sets the input file for injecting the payload.
-    #[arg(short = 'i', long = "input")]
+    /// Sets the input file for injecting the payload, or an http(s):// or s3:// URL to read
+    /// it from (http feature).
+    #[arg(
+        short = 'i',
+        long = "input",
+        required_unless_present = "split_across",
+        default_value_t = String::new(),
+        hide_default_value = true
+    )]
     pub input: String,
 
-    /// Sets the output file for generating a new file with the injected payload.
+    /// Sets the output file for generating a new file with the injected payload, or an
+    /// http(s):// or s3:// URL to upload it to (http feature).
     #[arg(short = 'o', long = "output", default_value_t = String::from("output.png"))]
     pub output: String,
 
-    /// Sets the key for payload encryption.
-    #[arg(short = 'k', long = "key", default_value_t = String::from("key"))]
+    /// Rewrites the input instead of writing --output: the new file is synced to disk and
+    /// renamed over it, keeping its permissions.
+    #[arg(long = "in-place", default_value_t = false, conflicts_with_all = ["output", "split_across"])]
+    pub in_place: bool,
+
+    /// Keeps the modification time of the input rewritten with --in-place.
+    #[arg(long = "keep-mtime", default_value_t = false, requires = "in_place")]
+    pub keep_mtime: bool,
+
+    /// Gives the output the access and modification times of the carrier, so that nothing
+    /// dates it as newer than the files around it, and puts back the access time of the
+    /// carrier, which reading it moves.
+    #[arg(long = "preserve-times", default_value_t = false)]
+    pub preserve_times: bool,
+
+    /// Refuses to write a PNG output that doesn't keep every ancillary chunk of the carrier
+    /// (gAMA, pHYs, iCCP, text...) byte for byte and in order.
+    #[arg(long = "preserve-metadata", default_value_t = false)]
+    pub preserve_metadata: bool,
+
+    /// Runs every check and prints what would be written, from the chunk type, offset and
+    /// size to the size of the output, without writing anything.
+    #[arg(long = "dry-run", default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Sets the key for payload encryption, or STEGANO_KEY if it is set.
+    #[arg(
+        short = 'k',
+        long = "key",
+        env = "STEGANO_KEY",
+        hide_env_values = true,
+        default_value_t = String::from("key")
+    )]
     pub key: String,
 
+    /// Reads the key from this open file descriptor instead of -k, such as a pipe or a
+    /// systemd credential, so that it never shows in the process arguments.
+    #[arg(long = "key-fd", value_name = "FD")]
+    pub key_fd: Option<u32>,
+
+    /// Reads the key from the OS keychain entry of this name, or stores the -k or --key-fd key
+    /// there when one is given (needs the keychain feature).
+    #[arg(long = "key-id")]
+    pub key_id: Option<String>,
+
     /// Suppresses output messages.
     #[arg(short = 's', long = "suppress", default_value_t = false)]
     pub suppress: bool,
 
-    /// Sets the offset.
-    #[arg(short = 'f', long = "offset", default_value_t = 9999999999)]
-    pub offset: usize,
+    /// Sets where the payload chunk goes in a PNG: auto (before IEND), end-N (N bytes before
+    /// the end of the file), after:TYPE, before:TYPE, or an absolute offset on a chunk boundary.
+    #[arg(short = 'f', long = "offset", default_value = "auto", value_parser = parse_offset_spec)]
+    pub offset: OffsetSpec,
 
     /// Sets the payload.
     #[arg(short = 'p', long = "payload", default_value_t = String::from("hello"))]
     pub payload: String,
 
-    /// Sets the type.
-    #[arg(short = 't', long = "type", default_value_t = String::from("PNG"))]
+    /// Sets the type, or auto to tell it from the magic bytes.
+    #[arg(short = 't', long = "type", default_value_t = String::from("auto"))]
     pub r#type: String,
 
-    /// Sets the algorithm.
+    /// Sets the algorithm: aes (AES-128), aes192, aes256, chacha (ChaCha20), xor, age to embed
+    /// a standard age file, encrypted with the key as passphrase or to --recipient, or pgp to
+    /// embed an OpenPGP message encrypted to --recipient.
     #[arg(short = 'a', long = "algo", default_value_t = String::from("aes"))]
     pub algorithm: String,
+
+    /// Encrypts the payload to this age public key (age1...) or OpenPGP certificate file
+    /// (key.asc) instead of the key, implying --algo age or pgp. Can be repeated; the holder of
+    /// any of the secret keys can decrypt it, with stegano or with `age -d` or `gpg --decrypt`.
+    #[arg(long = "recipient", value_name = "RECIPIENT")]
+    pub recipients: Vec<String>,
+
+    /// Sets the block mode of AES payloads: ctr, cbc, or ecb as before. The random IV is
+    /// stored in the payload header; the precision method has no header and stays in ecb.
+    #[arg(long = "block-mode", default_value_t = String::from(DEFAULT_BLOCK_MODE))]
+    pub block_mode: String,
+
+    /// Sets the embedding method (auto, append, precision|lsb|robust|apng|disguise|interleave|icc for PNG, icc for JPEG, tag|strip for TIFF, slack for ICO, free|skip|uuid for MP4, priv|geob for MP3, application|padding for FLAC, metadata|whitespace|attributes for SVG, zerowidth|whitespace for text).
+    #[arg(short = 'm', long = "method", default_value_t = String::from("auto"))]
+    pub method: String,
+
+    /// Embeds with the robust method, the same as `-m robust`: a small payload in the DCT
+    /// coefficients of the image that survives JPEG re-compression.
+    #[arg(long = "robust", default_value_t = false, conflicts_with = "method")]
+    pub robust: bool,
+
+    /// Disguises the payload as standard PNG metadata, implying `-m disguise`: an ICC profile
+    /// (icc, the default of the method), an XMP packet (xmp) or EXIF maker note (makernote).
+    #[arg(long = "disguise", value_parser = ["icc", "xmp", "makernote"], conflicts_with = "robust")]
+    pub disguise: Option<String>,
+
+    /// Sets the order the lsb method writes the payload bits in, recorded with its length:
+    /// scatter (derived from the key), row-major, column-major, zigzag or hilbert.
+    #[arg(
+        long = "traversal",
+        default_value = "scatter",
+        value_parser = ["scatter", "row-major", "column-major", "zigzag", "hilbert"]
+    )]
+    pub traversal: String,
+
+    /// Sets the channels whose samples the lsb method writes to, as letters among r, g, b and
+    /// a: rgb (the default), rg, b for blue only, a for alpha only... `decrypt` needs the same.
+    #[arg(long = "channels", default_value = "rgb", value_parser = parse_channels)]
+    pub channels: Channels,
+
+    /// Sets how many low bits of each sample the lsb method takes, from 1 to 4, recorded with
+    /// the length: more bits hold more, but lower the PSNR. Without it, the fewest that fit.
+    #[arg(long = "bits-per-channel", value_parser = clap::value_parser!(u8).range(1..=4))]
+    pub bits_per_channel: Option<u8>,
+
+    /// Sets the name of the PNG chunk holding the payload.
+    #[arg(short = 'c', long = "chunk-name", default_value_t = String::from("stEg"))]
+    pub chunk_name: String,
+
+    /// Reads the payload from a file instead of the command line.
+    #[arg(long = "payload-file")]
+    pub payload_file: Option<String>,
+
+    /// Reads the payload from a QR code in a PNG image, such as a photo of one shown by
+    /// `decrypt --qr` on an air-gapped machine.
+    #[arg(long = "payload-qr", conflicts_with_all = ["payload", "payload_file"])]
+    pub payload_qr: Option<String>,
+
+    /// Decodes the text payload given with -p or --payload-qr from base64 or hex, for binary
+    /// data that can't go through the shell as is (raw, base64 or hex).
+    #[arg(
+        long = "payload-encoding",
+        default_value = "raw",
+        value_parser = parse_payload_encoding,
+        conflicts_with = "payload_file"
+    )]
+    pub payload_encoding: PayloadEncoding,
+
+    /// Caps the memory used by payload transforms (e.g. 512M), spilling to a temp file beyond it.
+    #[arg(long = "max-memory", value_parser = parse_size)]
+    pub max_memory: Option<usize>,
+
+    /// Records the digest of the input carrier in the new frame (append method only).
+    #[arg(long = "provenance", default_value_t = false)]
+    pub provenance: bool,
+
+    /// Prints the result as JSON instead of human readable messages.
+    #[arg(long = "json", default_value_t = false)]
+    pub json: bool,
+
+    /// Sets the output format: auto (human on a terminal, JSON otherwise), human or json.
+    #[arg(long = "format", default_value_t = String::from("auto"))]
+    pub format: String,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+
+    /// Shows the bytes around the injection offset before and after embedding, with the
+    /// inserted bytes highlighted.
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        default_value_t = false,
+        conflicts_with_all = ["in_place", "dry_run", "split_across"]
+    )]
+    pub verbose: bool,
+
+    /// Shards the encrypted payload across these carriers instead of --input, writing one
+    /// output per carrier named after --output (output.1.png, output.2.png, ...).
+    #[arg(long = "split-across", num_args = 1.., conflicts_with = "input")]
+    pub split_across: Vec<String>,
+
+    /// Splits the payload into Shamir shares instead of plain shards: with 3-of-5, each of the
+    /// five carriers of --split-across gets a share and any three of them rebuild the payload.
+    #[arg(long = "shamir", value_name = "K-of-N", value_parser = parse_threshold, requires = "split_across")]
+    pub shamir: Option<Threshold>,
+
+    /// Stores a hint in plaintext next to the payload, such as "ask Bob for the phrase".
+    /// Anyone can read it with `stegano peek`, and it reveals that the file holds a payload.
+    #[arg(long = "hint")]
+    pub hint: Option<String>,
+
+    /// Also writes a recovery descriptor to this file: the location and digest of the payload,
+    /// sealed with the key. `decrypt --descriptor` reads the payload from it without scanning.
+    #[arg(long = "descriptor")]
+    pub descriptor: Option<String>,
+
+    /// Records the MIME type of the payload, such as "text/plain", in the payload header.
+    /// The header is plaintext, readable by anyone with `stegano peek`.
+    #[arg(long = "mime-type")]
+    pub mime_type: Option<String>,
+
+    /// Nests the stego file given with --payload-file: its MIME type (PNG, TIFF, ICO, MP4,
+    /// MP3, FLAC or SVG) is recorded in the payload header, so that `decrypt --depth` unwraps it
+    /// in turn.
+    #[arg(
+        long = "nest",
+        default_value_t = false,
+        requires = "payload_file",
+        conflicts_with = "mime_type"
+    )]
+    pub nest: bool,
+
+    /// Records the filename of the payload in the payload header, in plaintext.
+    #[arg(long = "filename")]
+    pub filename: Option<String>,
+
+    /// Records an expiry in the signed payload header, as YYYY-MM-DD (the end of that day, UTC)
+    /// or YYYY-MM-DDTHH:MM:SSZ. `decrypt` refuses to reveal the payload after it.
+    #[arg(long = "expires", value_parser = parse_expiry)]
+    pub expires: Option<u64>,
+
+    /// Records in the signed payload header how many times `decrypt --burn` may reveal the
+    /// payload before wiping it from the carrier (experimental).
+    #[arg(long = "views", value_parser = clap::value_parser!(u32).range(1..))]
+    pub views: Option<u32>,
+
+    /// Aborts without writing the output if the lsb or robust methods distort the image
+    /// beyond a lowest PSNR (e.g. 40dB) or SSIM (e.g. ssim:0.99).
+    #[arg(long = "max-distortion", value_parser = parse_max_distortion)]
+    pub max_distortion: Option<MaxDistortion>,
+
+    /// Writes the output of the lsb and robust methods without interlacing. By default an
+    /// interlaced (Adam7) carrier stays interlaced.
+    #[arg(long = "deinterlace", default_value_t = false)]
+    pub deinterlace: bool,
+
+    /// Rejects carriers that don't follow the specification of their format, such as PNG
+    /// chunks with a bad CRC or data after IEND, instead of embedding into them.
+    #[arg(long = "strict", default_value_t = false)]
+    pub strict: bool,
+}
+
+impl EncryptCmd {
+    /// Returns the options `codec` embeds the payload with.
+    pub fn codec_options(&self, cancel: &CancellationToken) -> CodecOptions {
+        CodecOptions {
+            file_type: self.r#type.clone(),
+            method: self.method.clone(),
+            algorithm: self.algorithm.clone(),
+            key: self.key.clone(),
+            chunk_name: self.chunk_name.clone(),
+            offset: self.offset,
+            channels: self.channels.name(),
+            disguise: self.disguise.clone().unwrap_or_else(|| String::from("icc")),
+            traversal: self.traversal.clone(),
+            bits_per_channel: self.bits_per_channel.map(usize::from),
+            cancel: cancel.clone(),
+        }
+    }
+}
+
+/// What a `DecryptCmd` does with the payload it finds, after the subcommand it was given to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DecryptMode {
+    /// `decrypt`: reveals the secret and writes the carrier without it.
+    #[default]
+    Both,
+    /// `extract`: reveals the secret and writes nothing.
+    Extract,
+    /// `remove`: writes the carrier without the payload and reveals nothing.
+    Remove,
+}
+
+impl DecryptMode {
+    /// Returns whether the secret is printed.
+    pub fn reveals(self) -> bool {
+        self != DecryptMode::Remove
+    }
+
+    /// Returns whether the carrier is written without the payload.
+    pub fn restores(self) -> bool {
+        self != DecryptMode::Extract
+    }
 }
 
-/// Subcommand for decryption.
+/// Subcommand for extraction, removal and decryption.
 #[derive(Parser, Debug)]
 pub struct DecryptCmd {
-    /// Sets the input file for decrypting and extracting the payload.
-    #[arg(short = 'i', long = "input")]
+    /// The subcommand the options were given to.
+    #[arg(skip)]
+    pub mode: DecryptMode,
+
+    /// Sets the input file for decrypting and extracting the payload, or an http(s):// or
+    /// s3:// URL to read it from (http feature).
+    #[arg(
+        short = 'i',
+        long = "input",
+        required_unless_present = "join",
+        default_value_t = String::new(),
+        hide_default_value = true
+    )]
     pub input: String,
 
-    /// Sets the output file for generating a new file with no payload, aka restoring the original file.
+    /// Sets the output file for generating a new file with no payload, aka restoring the
+    /// original file (remove and decrypt).
     #[arg(short = 'o', long = "output", default_value_t = String::from("output.png"))]
     pub output: String,
 
-    /// Sets the key for payload encryption.
-    #[arg(short = 'k', long = "key", default_value_t = String::from("key"))]
+    /// Rewrites the input without the payload instead of writing --output: the new file is
+    /// synced to disk and renamed over it, keeping its permissions.
+    #[arg(long = "in-place", default_value_t = false, conflicts_with_all = ["output", "join"])]
+    pub in_place: bool,
+
+    /// Keeps the modification time of the input rewritten with --in-place.
+    #[arg(long = "keep-mtime", default_value_t = false, requires = "in_place")]
+    pub keep_mtime: bool,
+
+    /// Sets the key for payload encryption, or STEGANO_KEY if it is set.
+    #[arg(
+        short = 'k',
+        long = "key",
+        env = "STEGANO_KEY",
+        hide_env_values = true,
+        default_value_t = String::from("key")
+    )]
     pub key: String,
 
+    /// Reads the key from this open file descriptor instead of -k, such as a pipe or a
+    /// systemd credential, so that it never shows in the process arguments.
+    #[arg(long = "key-fd", value_name = "FD")]
+    pub key_fd: Option<u32>,
+
+    /// Reads the key from the OS keychain entry of this name, or stores the -k or --key-fd key
+    /// there when one is given (needs the keychain feature).
+    #[arg(long = "key-id")]
+    pub key_id: Option<String>,
+
     /// Suppresses output messages.
     #[arg(short = 's', long = "suppress", default_value_t = false)]
     pub suppress: bool,
 
-    /// Sets the offset.
-    #[arg(short = 'f', long = "offset", default_value_t = 9999999999)]
-    pub offset: usize,
+    /// Sets where to look for the payload chunk in a PNG: auto (by chunk name anywhere),
+    /// end-N, after:TYPE, before:TYPE, or the absolute offset `encrypt` reported.
+    #[arg(short = 'f', long = "offset", default_value = "auto", value_parser = parse_offset_spec)]
+    pub offset: OffsetSpec,
 
-    /// Sets the payload.
-    #[arg(short = 'p', long = "payload", default_value_t = String::from("hello"))]
-    pub payload: String,
+    /// Writes the secret to this file, byte for byte, instead of printing it on stdout.
+    #[arg(
+        long = "output-payload-file",
+        value_name = "PATH",
+        conflicts_with = "qr"
+    )]
+    pub output_payload_file: Option<String>,
 
-    /// Sets the type.
-    #[arg(short = 't', long = "type", default_value_t = String::from("PNG"))]
+    /// Sets the type, or auto to tell it from the magic bytes.
+    #[arg(short = 't', long = "type", default_value_t = String::from("auto"))]
     pub r#type: String,
 
     /// Sets the algorithm.
     #[arg(short = 'a', long = "algo", default_value_t = String::from("aes"))]
     pub algorithm: String,
+
+    /// Opens a payload encrypted to age recipients with this age identity file, as written by
+    /// `age-keygen`, or one encrypted to OpenPGP recipients with this secret key, unlocked
+    /// with -k if protected. Can be repeated. Without it, OpenPGP payloads are written as is.
+    #[arg(long = "identity", value_name = "FILE")]
+    pub identities: Vec<String>,
+
+    /// Sets the method the payload was embedded with (auto, append, precision, lsb, robust, apng, disguise, interleave, icc, or metadata, whitespace, attributes for SVG, or zerowidth, whitespace for text).
+    #[arg(short = 'm', long = "method", default_value_t = String::from("auto"))]
+    pub method: String,
+
+    /// Reads a payload embedded with `--robust`, the same as `-m robust`.
+    #[arg(long = "robust", default_value_t = false, conflicts_with = "method")]
+    pub robust: bool,
+
+    /// Sets the channels the lsb payload was embedded in, as given to `encrypt --channels`.
+    #[arg(long = "channels", default_value = "rgb", value_parser = parse_channels)]
+    pub channels: Channels,
+
+    /// Sets the name of the PNG chunk holding the payload.
+    #[arg(short = 'c', long = "chunk-name", default_value_t = String::from("stEg"))]
+    pub chunk_name: String,
+
+    /// Sets the output format: auto (human on a terminal, the raw payload otherwise), human or raw.
+    #[arg(long = "format", default_value_t = String::from("auto"))]
+    pub format: String,
+
+    /// Writes raw binary payloads even when stdout is a terminal.
+    #[arg(long = "force-binary", default_value_t = false)]
+    pub force_binary: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+
+    /// Gathers a payload sharded with --split-across from these files, in any order, instead
+    /// of --input. With --shamir shares, any K of the N carriers are enough. The restored
+    /// carriers are named after --output (output.1.png, ...).
+    #[arg(long = "join", num_args = 1.., conflicts_with = "input")]
+    pub join: Vec<String>,
+
+    /// Reads the payload at the location recorded by `encrypt --descriptor`, which works even
+    /// when the structures used to find it are damaged. Nothing is restored.
+    #[arg(long = "descriptor", conflicts_with = "join")]
+    pub descriptor: Option<String>,
+
+    /// Reveals payloads past their expiry anyway, with a warning.
+    #[arg(long = "ignore-expiry", default_value_t = false)]
+    pub ignore_expiry: bool,
+
+    /// Reveals a payload embedded with `encrypt --views` and rewrites the input with one view
+    /// less, or without the payload once no view is left (experimental).
+    #[arg(
+        long = "burn",
+        default_value_t = false,
+        conflicts_with_all = ["output", "in_place", "join", "descriptor", "exact"]
+    )]
+    pub burn: bool,
+
+    /// Overwrites the payload bytes in the input with random data once it is revealed, for
+    /// the methods that store it in one piece, such as chunk and append.
+    #[arg(long = "wipe", default_value_t = false, conflicts_with_all = ["join", "burn"])]
+    pub wipe: bool,
+
+    /// Overwrites the whole input with random data and removes it once the output is written.
+    #[arg(
+        long = "shred-input",
+        default_value_t = false,
+        conflicts_with_all = ["join", "burn", "in_place"]
+    )]
+    pub shred_input: bool,
+
+    /// Sets how many times --wipe and --shred-input overwrite the bytes.
+    #[arg(long = "passes", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub passes: u32,
+
+    /// Unwraps up to this many layers: while the payload header records a PNG, TIFF, ICO, MP4,
+    /// MP3, FLAC or SVG MIME type, as `encrypt --nest` does, the payload is decrypted again as a
+    /// stego file of that type, with the same method, algorithm, key and chunk name.
+    #[arg(long = "depth", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub depth: u32,
+
+    /// Shows the secret as a QR code instead of printing it, for carrying it off an air-gapped
+    /// machine: in the terminal, or written to this PNG file.
+    #[arg(long = "qr", num_args = 0..=1, default_missing_value = "-", value_name = "PNG")]
+    pub qr: Option<String>,
+
+    /// Prints the secret encoded in base64 or hex, safe for terminals and text pipes, instead
+    /// of as is (raw, base64 or hex). `--qr` shows the encoded text too.
+    #[arg(long = "output-encoding", default_value = "raw", value_parser = parse_payload_encoding)]
+    pub output_encoding: PayloadEncoding,
+
+    /// Rejects stego files that don't follow the specification of their format before
+    /// reading anything from them. Payloads appended after the end of the file fail the check.
+    #[arg(long = "strict", default_value_t = false)]
+    pub strict: bool,
+
+    /// Guarantees the carrier is restored byte for byte: the payload is embedded again into
+    /// the restored carrier, and nothing is written unless that gives the stego file back.
+    /// The pixel methods overwrite the carrier and are rejected.
+    #[arg(long = "exact", default_value_t = false, conflicts_with_all = ["offset", "join", "descriptor"])]
+    pub exact: bool,
+}
+
+impl DecryptCmd {
+    /// Returns the options `codec` extracts the payload with.
+    pub fn codec_options(&self, cancel: &CancellationToken) -> CodecOptions {
+        CodecOptions {
+            file_type: self.r#type.clone(),
+            method: self.method.clone(),
+            algorithm: self.algorithm.clone(),
+            key: self.key.clone(),
+            chunk_name: self.chunk_name.clone(),
+            offset: self.offset,
+            channels: self.channels.name(),
+            cancel: cancel.clone(),
+            ..CodecOptions::default()
+        }
+    }
 }
 
 /// Subcommand for showing metadata.
@@ -133,11 +650,985 @@ pub struct ShowMetaCmd {
     #[arg(short = 'r', long = "suppress", default_value_t = false)]
     pub suppress: bool,
 
-    /// Sets the type.
-    #[arg(short = 't', long = "type", default_value_t = String::from("PNG"))]
+    /// Sets the type, or auto to tell it from the magic bytes.
+    #[arg(short = 't', long = "type", default_value_t = String::from("auto"))]
     pub r#type: String,
 
-    /// Read from start or end of file.
+    /// Lists the last chunks instead, with --start counting back from the end.
     #[arg(short = 'z', long = "read-end", default_value_t = false)]
     pub read_end: bool,
+
+    /// Extracts the given bit plane (0 = least significant) of a PNG image instead.
+    #[arg(long = "bit-plane")]
+    pub bit_plane: Option<u8>,
+
+    /// Sets the channel the bit plane is taken from (r, g, b or a).
+    #[arg(long = "channel", default_value_t = String::from("r"))]
+    pub channel: String,
+
+    /// Sets the output file for the bit plane image.
+    #[arg(short = 'o', long = "output", default_value_t = String::from("plane.png"))]
+    pub output: String,
+
+    /// Prints the metadata as JSON instead of human readable messages.
+    #[arg(long = "json", default_value_t = false)]
+    pub json: bool,
+
+    /// Sets the output format: auto (human on a terminal, JSON otherwise), human or json.
+    #[arg(long = "format", default_value_t = String::from("auto"))]
+    pub format: String,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+
+    /// Lists only the PNG chunks of these types (e.g. tEXt,iTXt).
+    #[arg(long = "filter", value_delimiter = ',')]
+    pub filter: Vec<String>,
+
+    /// Lists only the PNG chunks whose data contains this pattern: hex bytes after 0x
+    /// (e.g. 0x89504e47) or ASCII text.
+    #[arg(long = "grep")]
+    pub grep: Option<String>,
+
+    /// Dumps at most this many bytes of each chunk.
+    #[arg(long = "hex-limit")]
+    pub hex_limit: Option<usize>,
+
+    /// Dumps only this byte range of each chunk's data (e.g. 0..64, 0x100.., ..32).
+    #[arg(long = "hex-range", value_parser = parse_hex_range)]
+    pub hex_range: Option<Range<usize>>,
+
+    /// Prints the chunk headers without their hexdumps.
+    #[arg(long = "no-hex", default_value_t = false, conflicts_with_all = ["hex_limit", "hex_range"])]
+    pub no_hex: bool,
+
+    /// Checks the file against the specification of its format first, and fails on the first
+    /// violation, such as a PNG chunk with a bad CRC or a JPEG segment longer than the file.
+    #[arg(long = "strict", default_value_t = false)]
+    pub strict: bool,
+
+    /// Writes the bytes after IEND (PNG) or EOI (JPEG) to this file instead of listing the
+    /// metadata.
+    #[arg(long = "extract-trailing")]
+    pub extract_trailing: Option<String>,
+
+    /// Lists this page of the PNG chunks (counted from 1) instead of the chunks picked by
+    /// --start, --end and --nb-chunks.
+    #[arg(long = "page", value_parser = clap::value_parser!(u64).range(1..),
+          conflicts_with_all = ["start_chunk", "end_chunk", "nb_chunks", "read_end"])]
+    pub page: Option<u64>,
+
+    /// Sets the number of chunks on a page.
+    #[arg(long = "per-page", default_value_t = 50, value_parser = clap::value_parser!(u64).range(1..))]
+    pub per_page: u64,
+}
+
+/// Subcommand for showing the chain of custody of a carrier.
+#[derive(Parser, Debug)]
+pub struct ProvenanceCmd {
+    /// Sets the image input file.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for building or unpacking a PNG+ZIP polyglot.
+#[derive(Parser, Debug)]
+pub struct PolyglotCmd {
+    /// Sets the PNG carrier, or the polyglot to unpack with --extract.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the output file: the polyglot, or the restored PNG with --extract.
+    #[arg(short = 'o', long = "output", default_value_t = String::from("output.png"))]
+    pub output: String,
+
+    /// Sets the key for payload encryption.
+    #[arg(short = 'k', long = "key", default_value_t = String::from("key"))]
+    pub key: String,
+
+    /// Sets the payload.
+    #[arg(short = 'p', long = "payload", default_value_t = String::from("hello"))]
+    pub payload: String,
+
+    /// Reads the payload from a file instead of the command line.
+    #[arg(long = "payload-file")]
+    pub payload_file: Option<String>,
+
+    /// Sets the algorithm.
+    #[arg(short = 'a', long = "algo", default_value_t = String::from("aes"))]
+    pub algorithm: String,
+
+    /// Sets the name of the ZIP entry holding the encrypted payload.
+    #[arg(short = 'n', long = "name", default_value_t = String::from("secret.bin"))]
+    pub name: String,
+
+    /// Extracts and decrypts the payload instead of building a polyglot.
+    #[arg(short = 'x', long = "extract", default_value_t = false)]
+    pub extract: bool,
+
+    /// Caps the memory used by payload transforms (e.g. 512M), spilling to a temp file beyond it.
+    #[arg(long = "max-memory", value_parser = parse_size)]
+    pub max_memory: Option<usize>,
+
+    /// Sets the output format of --extract: auto (human on a terminal, the raw payload otherwise), human or raw.
+    #[arg(long = "format", default_value_t = String::from("auto"))]
+    pub format: String,
+
+    /// Writes raw binary payloads even when stdout is a terminal.
+    #[arg(long = "force-binary", default_value_t = false)]
+    pub force_binary: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for uploading a stego file to a file host.
+#[cfg(feature = "net")]
+#[derive(Parser, Debug)]
+pub struct ShareCmd {
+    /// Sets the stego file to upload.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the file host (0x0.st, custom).
+    #[arg(long = "provider", default_value_t = String::from("0x0.st"))]
+    pub provider: String,
+
+    /// Sets the upload URL of the custom provider.
+    #[arg(long = "url")]
+    pub url: Option<String>,
+
+    /// Sets the form field the file is uploaded in.
+    #[arg(long = "field", default_value_t = String::from("file"))]
+    pub field: String,
+
+    /// Sets the type, used in the printed decrypt command.
+    #[arg(short = 't', long = "type", default_value_t = String::from("PNG"))]
+    pub r#type: String,
+
+    /// Sets the method the payload was embedded with, used in the printed decrypt command.
+    #[arg(short = 'm', long = "method", default_value_t = String::from("auto"))]
+    pub method: String,
+
+    /// Sets the algorithm, used in the printed decrypt command.
+    #[arg(short = 'a', long = "algo", default_value_t = String::from("aes"))]
+    pub algorithm: String,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for serving covers over HTTP with queued payloads embedded on the fly.
+#[cfg(feature = "net")]
+#[derive(Parser, Debug)]
+pub struct ServeCmd {
+    /// Sets the directory of cover files to serve.
+    #[arg(long = "dir")]
+    pub dir: String,
+
+    /// Sets the address to listen on.
+    #[arg(long = "bind", default_value_t = String::from("127.0.0.1:8080"))]
+    pub bind: String,
+
+    /// Queues a text payload; repeat to queue several, each going into the next carrier
+    /// requested.
+    #[arg(short = 'p', long = "payload")]
+    pub payload: Vec<String>,
+
+    /// Queues the contents of a file as a payload, after the -p ones; repeat to queue several.
+    #[arg(long = "payload-file")]
+    pub payload_file: Vec<String>,
+
+    /// Sets the embedding method, which every served carrier type must support.
+    #[arg(short = 'm', long = "method", default_value_t = String::from("auto"))]
+    pub method: String,
+
+    /// Sets the algorithm to encrypt the payloads with.
+    #[arg(short = 'a', long = "algo", default_value_t = String::from("aes"))]
+    pub algorithm: String,
+
+    /// Sets the key for payload encryption, or STEGANO_KEY if it is set.
+    #[arg(
+        short = 'k',
+        long = "key",
+        env = "STEGANO_KEY",
+        hide_env_values = true,
+        default_value_t = String::from("key")
+    )]
+    pub key: String,
+
+    /// Stops after answering this many requests.
+    #[arg(long = "max-requests")]
+    pub max_requests: Option<u64>,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for downloading a stego file and extracting its payload.
+#[cfg(feature = "net")]
+#[derive(Parser, Debug)]
+pub struct FetchCmd {
+    /// Sets the URL of the stego file.
+    pub url: String,
+
+    /// Sets the type, or auto to take it from the URL or the magic bytes.
+    #[arg(short = 't', long = "type", default_value_t = String::from("auto"))]
+    pub r#type: String,
+
+    /// Sets the method the payload was embedded with.
+    #[arg(short = 'm', long = "method", default_value_t = String::from("auto"))]
+    pub method: String,
+
+    /// Sets the algorithm the payload was encrypted with.
+    #[arg(short = 'a', long = "algo", default_value_t = String::from("aes"))]
+    pub algorithm: String,
+
+    /// Sets the key for payload decryption, or STEGANO_KEY if it is set.
+    #[arg(
+        short = 'k',
+        long = "key",
+        env = "STEGANO_KEY",
+        hide_env_values = true,
+        default_value_t = String::from("key")
+    )]
+    pub key: String,
+
+    /// Writes the downloaded stego file here.
+    #[arg(long = "save")]
+    pub save: Option<String>,
+
+    /// Writes the carrier, with the payload removed, here.
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<String>,
+
+    /// Sets the output format: auto (human on a terminal, the raw payload otherwise), human or raw.
+    #[arg(long = "format", default_value_t = String::from("auto"))]
+    pub format: String,
+
+    /// Writes raw binary payloads even when stdout is a terminal.
+    #[arg(long = "force-binary", default_value_t = false)]
+    pub force_binary: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for running a long embed/extract stability test.
+#[derive(Parser, Debug)]
+pub struct SoakCmd {
+    /// Sets the number of iterations.
+    #[arg(long = "iterations", default_value_t = 1000)]
+    pub iterations: u64,
+
+    /// Sets the seed of the random generator, to replay a failing run.
+    #[arg(long = "seed")]
+    pub seed: Option<u64>,
+
+    /// Suppresses the progress output.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for comparing two PNG files.
+#[derive(Parser, Debug)]
+pub struct DiffCmd {
+    /// Sets the first file, usually the original.
+    #[arg(short = 'a', long = "original")]
+    pub original: String,
+
+    /// Sets the second file, usually the modified one.
+    #[arg(short = 'b', long = "modified")]
+    pub modified: String,
+
+    /// Also compares the decoded pixels.
+    #[arg(long = "pixels", default_value_t = false)]
+    pub pixels: bool,
+
+    /// Follows each added, removed or modified chunk with a hexdump of its bytes in both
+    /// files, the removed bytes in red and the inserted ones in green.
+    #[arg(long = "hex", default_value_t = false)]
+    pub hex: bool,
+
+    /// Prints the differences as JSON.
+    #[arg(long = "json", default_value_t = false)]
+    pub json: bool,
+
+    /// Sets the output format: auto (human on a terminal, JSON otherwise), human or json.
+    #[arg(long = "format", default_value_t = String::from("auto"))]
+    pub format: String,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for inspecting an embedded payload without the key.
+#[derive(Parser, Debug)]
+pub struct PeekCmd {
+    /// Sets the stego file.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the type, or auto to tell it from the magic bytes.
+    #[arg(short = 't', long = "type", default_value_t = String::from("auto"))]
+    pub r#type: String,
+
+    /// Sets the method the payload was embedded with (auto, append, precision).
+    #[arg(short = 'm', long = "method", default_value_t = String::from("auto"))]
+    pub method: String,
+
+    /// Sets the name of the PNG chunk holding the payload.
+    #[arg(short = 'c', long = "chunk-name", default_value_t = String::from("stEg"))]
+    pub chunk_name: String,
+
+    /// Prints only the hint stored with --hint, failing if there is none.
+    #[arg(long = "hint-only", default_value_t = false)]
+    pub hint_only: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for printing or changing the defaults of the configuration file.
+#[derive(Parser, Debug)]
+pub struct ConfigCmd {
+    /// Sets a default, as KEY=VALUE (repeatable).
+    #[arg(long = "set")]
+    pub set: Vec<String>,
+
+    /// Removes a default, so the built-in one applies again (repeatable).
+    #[arg(long = "unset")]
+    pub unset: Vec<String>,
+
+    /// Prints the path of the configuration file only.
+    #[arg(long = "path", default_value_t = false)]
+    pub path: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for re-encrypting an embedded payload with a new key.
+#[derive(Parser, Debug)]
+pub struct RekeyCmd {
+    /// Sets the stego file.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the output file for the re-keyed stego file.
+    #[arg(short = 'o', long = "output", default_value_t = String::from("output.png"))]
+    pub output: String,
+
+    /// Patches the input instead of writing --output: when the re-keyed file keeps its size,
+    /// only the bytes that changed are written over it, otherwise it is rewritten whole.
+    #[arg(long = "in-place", default_value_t = false, conflicts_with = "output")]
+    pub in_place: bool,
+
+    /// Sets the key the payload is encrypted with.
+    #[arg(short = 'k', long = "key", default_value_t = String::from("key"))]
+    pub key: String,
+
+    /// Sets the algorithm the payload is encrypted with.
+    #[arg(short = 'a', long = "algo", default_value_t = String::from("aes"))]
+    pub algorithm: String,
+
+    /// Sets the key to re-encrypt the payload with.
+    #[arg(long = "new-key")]
+    pub new_key: String,
+
+    /// Sets the algorithm to re-encrypt the payload with (default is the current one).
+    #[arg(long = "new-algo")]
+    pub new_algorithm: Option<String>,
+
+    /// Sets the type, or auto to tell it from the magic bytes.
+    #[arg(short = 't', long = "type", default_value_t = String::from("auto"))]
+    pub r#type: String,
+
+    /// Sets the method the payload was embedded with (auto, append, precision, lsb, robust, apng, disguise, interleave, icc, or metadata, whitespace, attributes for SVG, or zerowidth, whitespace for text).
+    #[arg(short = 'm', long = "method", default_value_t = String::from("auto"))]
+    pub method: String,
+
+    /// Sets the name of the PNG chunk holding the payload.
+    #[arg(short = 'c', long = "chunk-name", default_value_t = String::from("stEg"))]
+    pub chunk_name: String,
+
+    /// Sets the channels the lsb payload was embedded in, as given to `encrypt --channels`;
+    /// the new payload goes in the same ones.
+    #[arg(long = "channels", default_value = "rgb", value_parser = parse_channels)]
+    pub channels: Channels,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for embedding or verifying an ownership watermark.
+#[derive(Parser, Debug)]
+pub struct WatermarkCmd {
+    /// Embeds or verifies.
+    #[command(subcommand)]
+    pub action: WatermarkAction,
+}
+
+impl WatermarkCmd {
+    /// Tells whether the action was given `--explain`.
+    pub fn explain(&self) -> bool {
+        match &self.action {
+            WatermarkAction::Embed(cmd) => cmd.explain,
+            WatermarkAction::Verify(cmd) => cmd.explain,
+        }
+    }
+}
+
+/// Actions of the `watermark` subcommand.
+#[derive(Subcommand, Debug)]
+pub enum WatermarkAction {
+    /// Embeds an owner ID as a watermark spread over the whole image.
+    Embed(WatermarkEmbedCmd),
+
+    /// Reads the owner ID of a watermark and checks its presence, without the original.
+    Verify(WatermarkVerifyCmd),
+}
+
+/// Action for embedding a watermark.
+#[derive(Parser, Debug)]
+pub struct WatermarkEmbedCmd {
+    /// Sets the image to watermark.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the output file for the watermarked image.
+    #[arg(short = 'o', long = "output", default_value_t = String::from("output.png"))]
+    pub output: String,
+
+    /// Sets the owner ID, up to 16 bytes.
+    #[arg(long = "owner")]
+    pub owner: String,
+
+    /// Sets the key the watermark pattern is derived from.
+    #[arg(short = 'k', long = "key", default_value_t = String::from("key"))]
+    pub key: String,
+
+    /// Sets the amplitude of the pattern in luma levels: higher survives more, but shows more.
+    #[arg(long = "strength", default_value_t = DEFAULT_STRENGTH)]
+    pub strength: f64,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Action for verifying a watermark.
+#[derive(Parser, Debug)]
+pub struct WatermarkVerifyCmd {
+    /// Sets the image to check.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the key the watermark was embedded with.
+    #[arg(short = 'k', long = "key", default_value_t = String::from("key"))]
+    pub key: String,
+
+    /// Checks for this owner ID, even if the ID can't be read back in full.
+    #[arg(long = "owner")]
+    pub owner: Option<String>,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for inserting a raw chunk into a PNG file.
+#[derive(Parser, Debug)]
+pub struct InjectChunkCmd {
+    /// Sets the PNG file.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the output file.
+    #[arg(short = 'o', long = "output", default_value_t = String::from("output.png"))]
+    pub output: String,
+
+    /// Sets the chunk type, four ASCII letters such as teXt.
+    #[arg(long = "type", value_parser = parse_chunk_type)]
+    pub chunk_type: [u8; 4],
+
+    /// Sets the file holding the chunk data, written as is.
+    #[arg(long = "data-file")]
+    pub data_file: String,
+
+    /// Sets where the chunk goes: after:TYPE (after the last chunk of that type),
+    /// before:TYPE (before the first one) or index:N (IHDR being 0).
+    #[arg(long = "position", default_value = "before:IEND", value_parser = parse_chunk_position)]
+    pub position: ChunkPosition,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for removing chunks from a PNG file.
+#[derive(Parser, Debug)]
+pub struct RemoveChunkCmd {
+    /// Sets the PNG file.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the output file.
+    #[arg(short = 'o', long = "output", default_value_t = String::from("output.png"))]
+    pub output: String,
+
+    /// Removes every chunk of this type.
+    #[arg(long = "type", value_parser = parse_chunk_type, required_unless_present = "index", conflicts_with = "index")]
+    pub chunk_type: Option<[u8; 4]>,
+
+    /// Removes the chunk at this index, IHDR being 0.
+    #[arg(long = "index")]
+    pub index: Option<usize>,
+
+    /// Allows removing critical chunks other than IHDR and IEND, such as PLTE or IDAT.
+    #[arg(long = "force", default_value_t = false)]
+    pub force: bool,
+
+    /// Overwrites the removed chunks in the input with random data before writing the output.
+    #[arg(long = "wipe", default_value_t = false)]
+    pub wipe: bool,
+
+    /// Overwrites the whole input with random data and removes it once the output is written.
+    #[arg(long = "shred-input", default_value_t = false)]
+    pub shred_input: bool,
+
+    /// Sets how many times --wipe and --shred-input overwrite the bytes.
+    #[arg(long = "passes", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub passes: u32,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for writing the raw data of PNG chunks to files.
+#[derive(Parser, Debug)]
+pub struct ExtractChunkCmd {
+    /// Sets the PNG file.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the output file; with --all, out.bin gives out.1.bin, out.2.bin and so on.
+    #[arg(short = 'o', long = "output", default_value_t = String::from("chunk.bin"))]
+    pub output: String,
+
+    /// Extracts the first chunk of this type.
+    #[arg(long = "type", value_parser = parse_chunk_type, required_unless_present = "index", conflicts_with = "index")]
+    pub chunk_type: Option<[u8; 4]>,
+
+    /// Extracts the chunk at this index, IHDR being 0.
+    #[arg(long = "index")]
+    pub index: Option<usize>,
+
+    /// Extracts every chunk of the type, each to its own file.
+    #[arg(long = "all", default_value_t = false, conflicts_with = "index")]
+    pub all: bool,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for reading or replacing the ICC profile of a PNG or JPEG file.
+#[derive(Parser, Debug)]
+pub struct IccCmd {
+    /// Sets the PNG or JPEG file.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the type (PNG or JPEG), or auto to tell it from the magic bytes.
+    #[arg(short = 't', long = "type", default_value_t = String::from("auto"))]
+    pub r#type: String,
+
+    /// Writes the profile of the input to this file, reassembled from all its APP2 segments
+    /// in a JPEG.
+    #[arg(
+        long = "extract-icc",
+        required_unless_present = "inject_icc",
+        conflicts_with = "inject_icc"
+    )]
+    pub extract_icc: Option<String>,
+
+    /// Replaces the profile of the input with the one in this file, written to --output.
+    #[arg(long = "inject-icc", requires = "output")]
+    pub inject_icc: Option<String>,
+
+    /// Sets the output file for --inject-icc.
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<String>,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for storing files in an encrypted vault inside a PNG file.
+#[derive(Parser, Debug)]
+pub struct VaultCmd {
+    /// Stores, lists, reads or removes files.
+    #[command(subcommand)]
+    pub action: VaultAction,
+}
+
+impl VaultCmd {
+    /// Tells whether the action was given `--explain`.
+    pub fn explain(&self) -> bool {
+        match &self.action {
+            VaultAction::Put(cmd) => cmd.explain,
+            VaultAction::Ls(cmd) => cmd.explain,
+            VaultAction::Get(cmd) => cmd.explain,
+            VaultAction::Rm(cmd) => cmd.explain,
+        }
+    }
+}
+
+/// Actions of the `vault` subcommand.
+#[derive(Subcommand, Debug)]
+pub enum VaultAction {
+    /// Stores a file in the vault, replacing any file of the same name.
+    Put(VaultPutCmd),
+
+    /// Lists the files in the vault.
+    #[command(alias = "list")]
+    Ls(VaultLsCmd),
+
+    /// Reads a file back from the vault.
+    Get(VaultGetCmd),
+
+    /// Removes a file from the vault.
+    Rm(VaultRmCmd),
+}
+
+/// Action for storing a file in a vault.
+#[derive(Parser, Debug)]
+pub struct VaultPutCmd {
+    /// Sets the PNG file holding the vault.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the output file (default is the input, rewritten in place).
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<String>,
+
+    /// Sets the file to store.
+    #[arg(long = "file")]
+    pub file: String,
+
+    /// Sets the name to store the file under (default is the name of --file).
+    #[arg(long = "name")]
+    pub name: Option<String>,
+
+    /// Sets the key of the vault.
+    #[arg(short = 'k', long = "key", default_value_t = String::from("key"))]
+    pub key: String,
+
+    /// Sets the algorithm.
+    #[arg(short = 'a', long = "algo", default_value_t = String::from("aes"))]
+    pub algorithm: String,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Action for listing the files in a vault.
+#[derive(Parser, Debug)]
+pub struct VaultLsCmd {
+    /// Sets the PNG file holding the vault.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the key of the vault.
+    #[arg(short = 'k', long = "key", default_value_t = String::from("key"))]
+    pub key: String,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Action for reading a file back from a vault.
+#[derive(Parser, Debug)]
+pub struct VaultGetCmd {
+    /// Sets the PNG file holding the vault.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the name of the file to read.
+    #[arg(long = "name")]
+    pub name: String,
+
+    /// Sets the file to write it to (default is stdout).
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<String>,
+
+    /// Sets the key of the vault.
+    #[arg(short = 'k', long = "key", default_value_t = String::from("key"))]
+    pub key: String,
+
+    /// Writes binary files to a terminal anyway.
+    #[arg(long = "force-binary", default_value_t = false)]
+    pub force_binary: bool,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Action for removing a file from a vault.
+#[derive(Parser, Debug)]
+pub struct VaultRmCmd {
+    /// Sets the PNG file holding the vault.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the output file (default is the input, rewritten in place).
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<String>,
+
+    /// Sets the name of the file to remove.
+    #[arg(long = "name")]
+    pub name: String,
+
+    /// Sets the key of the vault.
+    #[arg(short = 'k', long = "key", default_value_t = String::from("key"))]
+    pub key: String,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for editing the standard text and timestamp metadata of a PNG file.
+#[derive(Parser, Debug)]
+pub struct MetaCmd {
+    /// Sets, reads or removes tEXt entries and the tIME timestamp.
+    #[command(subcommand)]
+    pub action: MetaAction,
+}
+
+impl MetaCmd {
+    /// Tells whether the action was given `--explain`.
+    pub fn explain(&self) -> bool {
+        match &self.action {
+            MetaAction::Set(cmd) => cmd.explain,
+            MetaAction::Get(cmd) => cmd.explain,
+            MetaAction::Remove(cmd) => cmd.explain,
+        }
+    }
+}
+
+/// Actions of the `meta` subcommand.
+#[derive(Subcommand, Debug)]
+pub enum MetaAction {
+    /// Sets tEXt entries and the tIME timestamp, replacing those already there.
+    Set(MetaSetCmd),
+
+    /// Prints the tEXt entries and the tIME timestamp.
+    Get(MetaGetCmd),
+
+    /// Removes tEXt entries or the tIME timestamp.
+    #[command(alias = "rm")]
+    Remove(MetaRemoveCmd),
+}
+
+/// Action for setting the metadata of a PNG file.
+#[derive(Parser, Debug)]
+pub struct MetaSetCmd {
+    /// Sets the PNG file to edit.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the output file (default is the input, rewritten in place).
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<String>,
+
+    /// Sets a tEXt entry, such as Author=Alice, replacing any entry of the same keyword. Can
+    /// be repeated.
+    #[arg(
+        long = "text",
+        value_name = "KEY=VALUE",
+        value_parser = parse_text_entry,
+        required_unless_present = "time"
+    )]
+    pub text: Vec<(String, String)>,
+
+    /// Sets the tIME timestamp: now, a YYYY-MM-DD date or a YYYY-MM-DDTHH:MM:SSZ time.
+    #[arg(long = "time", value_parser = parse_meta_time)]
+    pub time: Option<u64>,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Action for printing the metadata of a PNG file.
+#[derive(Parser, Debug)]
+pub struct MetaGetCmd {
+    /// Sets the PNG file to read.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Prints only the text of the tEXt entry with this keyword.
+    #[arg(long = "key")]
+    pub key: Option<String>,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Action for removing metadata from a PNG file.
+#[derive(Parser, Debug)]
+pub struct MetaRemoveCmd {
+    /// Sets the PNG file to edit.
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// Sets the output file (default is the input, rewritten in place).
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<String>,
+
+    /// Removes the tEXt entries with this keyword. Can be repeated.
+    #[arg(long = "key", required_unless_present_any = ["all_text", "time"])]
+    pub keys: Vec<String>,
+
+    /// Removes every tEXt entry.
+    #[arg(long = "all-text", default_value_t = false, conflicts_with = "keys")]
+    pub all_text: bool,
+
+    /// Removes the tIME timestamp.
+    #[arg(long = "time", default_value_t = false)]
+    pub time: bool,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for synthesizing a PNG carrier.
+#[derive(Parser, Debug)]
+pub struct GenerateCarrierCmd {
+    /// Sets the width of the image, in pixels.
+    #[arg(long = "width", default_value_t = 1920)]
+    pub width: usize,
+
+    /// Sets the height of the image, in pixels.
+    #[arg(long = "height", default_value_t = 1080)]
+    pub height: usize,
+
+    /// Sets the look of the image: noise, gradient or perlin.
+    #[arg(long = "style", default_value_t = String::from("perlin"), value_parser = ["noise", "gradient", "perlin"])]
+    pub style: String,
+
+    /// Grows the image, keeping its aspect ratio, until it safely holds this file.
+    #[arg(long = "fit-payload")]
+    pub fit_payload: Option<String>,
+
+    /// Sets the seed of the random generator, to generate the same image again.
+    #[arg(long = "seed")]
+    pub seed: Option<u64>,
+
+    /// Sets the output file for the image.
+    #[arg(short = 'o', long = "output", default_value_t = String::from("output.png"))]
+    pub output: String,
+
+    /// Suppresses output messages.
+    #[arg(short = 's', long = "suppress", default_value_t = false)]
+    pub suppress: bool,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+}
+
+/// Subcommand for opening a window to hide or reveal a payload without the command line.
+#[cfg(feature = "gui")]
+#[derive(Parser, Debug)]
+pub struct GuiCmd {
+    /// Sets the carrier the window opens with, instead of dropping one onto it.
+    #[arg(short = 'i', long = "input")]
+    pub input: Option<String>,
+
+    /// Sets the algorithm selected for encryption.
+    #[arg(short = 'a', long = "algo", default_value_t = String::from("aes"), value_parser = ["aes", "aes192", "aes256", "chacha", "xor"])]
+    pub algorithm: String,
+
+    /// Fills in the key, or STEGANO_KEY if it is set.
+    #[arg(short = 'k', long = "key", env = "STEGANO_KEY", hide_env_values = true)]
+    pub key: Option<String>,
+
+    /// Prints what will be done and why before doing it.
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
 }
@@ -0,0 +1,286 @@
+use crate::append::{extract_appended, strip_appended, write_appended};
+use crate::cancel::CancellationToken;
+use crate::envelope::{age_encrypt, is_age_algorithm};
+use crate::format::{find_format, resolve_file_type, unsupported};
+use crate::header::{
+    open_payload, open_precision, payload_tag, precision_tag, unix_now, PayloadHeader,
+};
+use crate::png::OffsetSpec;
+use crate::polyglot::PNG_SIGNATURE;
+use crate::svg::SvgFile;
+use crate::utils::{encrypt_stream_mode, CipherMode, Padding, DEFAULT_BLOCK_MODE};
+use std::io::{self, Cursor};
+
+/// Options shared by `encode_bytes` and `decode_bytes`, mirroring the encrypt/decrypt flags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodecOptions {
+    /// The carrier type: png, jpeg, tiff, ico, mp4, mp3, flac, svg, text, one added with
+    /// `format::register_format`, or auto to detect it from the magic bytes.
+    pub file_type: String,
+    /// The embedding method: auto, append, precision|lsb|robust|apng|disguise|interleave|icc for
+    /// PNG, icc for JPEG, tag|strip for TIFF, slack for ICO, free|skip|uuid for MP4, priv|geob
+    /// for MP3, application|padding for FLAC, metadata|whitespace|attributes for SVG,
+    /// zerowidth|whitespace for text.
+    pub method: String,
+    /// The encryption algorithm: aes, aes192, aes256, chacha, xor, or age with the key as passphrase.
+    pub algorithm: String,
+    /// The encryption key.
+    pub key: String,
+    /// The PNG chunk holding the payload.
+    pub chunk_name: String,
+    /// Where the chunk method puts the payload chunk, and where it looks for it: after a chunk
+    /// type, before one, or at an offset. `Auto` puts it before `IEND` and finds it by name.
+    pub offset: OffsetSpec,
+    /// The metadata the disguise method dresses the payload as: icc, xmp or makernote.
+    /// Extraction finds it by itself.
+    pub disguise: String,
+    /// The order the lsb method writes the payload bits in: scatter, row-major, column-major,
+    /// zigzag or hilbert. Extraction reads it from the payload.
+    pub traversal: String,
+    /// The channels whose samples the lsb method uses: letters among r, g, b and a. The same
+    /// channels must be given to extract the payload.
+    pub channels: String,
+    /// The low bits of each sample the lsb method takes, from 1 to 4, or `None` for the fewest
+    /// the payload fits in. Extraction reads it from the payload.
+    pub bits_per_channel: Option<usize>,
+    /// Stops the operation early when cancelled from another thread.
+    pub cancel: CancellationToken,
+}
+
+impl Default for CodecOptions {
+    fn default() -> Self {
+        CodecOptions {
+            file_type: String::from("png"),
+            method: String::from("auto"),
+            algorithm: String::from("aes"),
+            key: String::from("key"),
+            chunk_name: String::from("stEg"),
+            offset: OffsetSpec::Auto,
+            disguise: String::from("icc"),
+            traversal: String::from("scatter"),
+            channels: String::from("rgb"),
+            bits_per_channel: None,
+            cancel: CancellationToken::new(),
+        }
+    }
+}
+
+/// The result of `decode_bytes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    /// The decrypted payload, without the AES padding.
+    pub payload: Vec<u8>,
+    /// The carrier with the payload removed.
+    pub carrier: Vec<u8>,
+}
+
+/// The carriers that can be nested as payloads, by the MIME type recorded in the payload
+/// header and the matching `--type`.
+const NESTED_CARRIERS: [(&str, &str); 7] = [
+    ("image/png", "png"),
+    ("image/tiff", "tiff"),
+    ("image/vnd.microsoft.icon", "ico"),
+    ("video/mp4", "mp4"),
+    ("audio/mpeg", "mp3"),
+    ("audio/flac", "flac"),
+    ("image/svg+xml", "svg"),
+];
+
+/// Recognizes a carrier from its magic bytes, for payloads nested with `encrypt --nest`.
+///
+/// # Returns
+///
+/// The MIME type of a PNG, TIFF, ICO, MP4, tagged MP3, FLAC or SVG file, or `None` for
+/// anything else.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::codec::{carrier_mime_type, nested_carrier_type};
+/// use stegano::png::encode_grayscale;
+///
+/// let png = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// assert_eq!(carrier_mime_type(&png), Some("image/png"));
+/// assert_eq!(carrier_mime_type(b"hello"), None);
+/// assert_eq!(nested_carrier_type("image/png"), Some("png"));
+/// assert_eq!(nested_carrier_type("text/plain"), None);
+/// ```
+pub fn carrier_mime_type(data: &[u8]) -> Option<&'static str> {
+    let index = if data.starts_with(PNG_SIGNATURE) {
+        0
+    } else if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        1
+    } else if data.starts_with(&[0, 0, 1, 0]) {
+        2
+    } else if data.get(4..8) == Some(b"ftyp") {
+        3
+    } else if data.starts_with(b"ID3") {
+        4
+    } else if data.starts_with(b"fLaC") {
+        5
+    } else if data.trim_ascii_start().starts_with(b"<") && SvgFile::parse(data).is_ok() {
+        6
+    } else {
+        return None;
+    };
+    Some(NESTED_CARRIERS[index].0)
+}
+
+/// Returns the carrier type of a nested payload from the MIME type in its header, or `None`
+/// if the payload isn't a carrier.
+pub fn nested_carrier_type(mime_type: &str) -> Option<&'static str> {
+    NESTED_CARRIERS
+        .iter()
+        .find(|(mime, _)| mime.eq_ignore_ascii_case(mime_type))
+        .map(|(_, file_type)| *file_type)
+}
+
+/// Encrypts a payload and embeds it into a carrier, entirely in memory.
+///
+/// This is the I/O-free counterpart of the `encrypt` subcommand, usable where there is no
+/// file system, such as in the browser. The ciphertext is preceded by a `PayloadHeader`,
+/// except with the precision method, which has no room for it and only gets a check byte, and
+/// with the age algorithm, whose files stand on their own.
+///
+/// # Arguments
+///
+/// * `carrier` - The bytes of the carrier file.
+/// * `payload` - The secret to hide.
+/// * `opts` - The carrier type, method, algorithm and key.
+///
+/// # Returns
+///
+/// A `Result` containing the bytes of the stego file.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::codec::{decode_bytes, encode_bytes, CodecOptions};
+/// use stegano::png::encode_grayscale;
+///
+/// let carrier = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let opts = CodecOptions::default();
+/// let stego = encode_bytes(&carrier, b"hello", &opts).unwrap();
+/// let decoded = decode_bytes(&stego, &opts).unwrap();
+/// assert_eq!(decoded.payload, b"hello");
+/// assert_eq!(decoded.carrier, carrier);
+/// ```
+pub fn encode_bytes(carrier: &[u8], payload: &[u8], opts: &CodecOptions) -> io::Result<Vec<u8>> {
+    let mut encrypted = Vec::new();
+    if is_age_algorithm(&opts.algorithm) {
+        age_encrypt(&[], &opts.key, opts.cancel.reader(payload), &mut encrypted)?;
+        opts.cancel.check()?;
+        return embed_encrypted(carrier, &encrypted, opts);
+    }
+    let mut mode = CipherMode::Ecb;
+    let mut padding = Padding::Zeros;
+    // The precision method holds a few bytes, too few for a header.
+    let sealed = !opts.method.eq_ignore_ascii_case("precision");
+    if sealed {
+        let header = PayloadHeader::new(&opts.algorithm, payload.len() as u64, unix_now())
+            .with_mode(DEFAULT_BLOCK_MODE)?;
+        mode = header.mode;
+        padding = header.padding;
+        encrypted = header.to_bytes(&opts.key)?;
+    }
+    encrypt_stream_mode(
+        &opts.algorithm,
+        &opts.key,
+        &mode,
+        padding,
+        opts.cancel.reader(payload),
+        &mut encrypted,
+    )?;
+    if sealed {
+        let tag = payload_tag(&opts.key, &encrypted[..])?;
+        encrypted.extend_from_slice(&tag);
+    } else {
+        let check = precision_tag(&opts.key, &encrypted);
+        encrypted.extend_from_slice(&check);
+    }
+    opts.cancel.check()?;
+    embed_encrypted(carrier, &encrypted, opts)
+}
+
+/// Embeds an already encrypted payload into a carrier with the method of `opts`.
+///
+/// This is the embedding half of `encode_bytes`, for callers that produce the encrypted bytes
+/// themselves, such as the shards of `encrypt --split-across`.
+///
+/// # Arguments
+///
+/// * `carrier` - The bytes of the carrier file.
+/// * `encrypted` - The bytes to embed, as they are.
+/// * `opts` - The carrier type and method, and the key for the LSB, robust and interleave
+///   methods.
+///
+/// # Returns
+///
+/// A `Result` containing the bytes of the stego file.
+pub fn embed_encrypted(
+    carrier: &[u8],
+    encrypted: &[u8],
+    opts: &CodecOptions,
+) -> io::Result<Vec<u8>> {
+    let method = opts.method.to_lowercase();
+    if method == "append" {
+        let mut out = Vec::with_capacity(carrier.len() + encrypted.len() + 16);
+        write_appended(carrier, encrypted, encrypted.len() as u64, &mut out)?;
+        return Ok(out);
+    }
+    let file_type = resolve_file_type(&opts.file_type, carrier)?;
+    find_format(&file_type)
+        .ok_or_else(|| unsupported(&file_type, &method))?
+        .embed(carrier, encrypted, opts)
+}
+
+/// Extracts and decrypts a payload from a stego file, entirely in memory.
+///
+/// # Arguments
+///
+/// * `stego` - The bytes of the stego file.
+/// * `opts` - The options the payload was embedded with.
+///
+/// # Returns
+///
+/// A `Result` containing the payload and the restored carrier.
+pub fn decode_bytes(stego: &[u8], opts: &CodecOptions) -> io::Result<Decoded> {
+    let (encrypted, carrier) = extract_encrypted(stego, opts)?;
+    opts.cancel.check()?;
+    if !opts.method.eq_ignore_ascii_case("precision") {
+        let (_, payload) = open_payload(&encrypted, &opts.algorithm, &opts.key)?;
+        return Ok(Decoded { payload, carrier });
+    }
+    let payload = open_precision(&encrypted, &opts.algorithm, &opts.key)?;
+    Ok(Decoded { payload, carrier })
+}
+
+/// Extracts a payload embedded with the method of `opts`, without decrypting it.
+///
+/// This is the extraction half of `decode_bytes`.
+///
+/// # Arguments
+///
+/// * `stego` - The bytes of the stego file.
+/// * `opts` - The carrier type and method, and the key for the LSB, robust and interleave
+///   methods.
+///
+/// # Returns
+///
+/// A `Result` containing the embedded bytes and the restored carrier. The precision, LSB and
+/// robust methods can't restore the overwritten bits, so their carrier is the stego file
+/// itself.
+pub fn extract_encrypted(stego: &[u8], opts: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let method = opts.method.to_lowercase();
+    if method == "append" {
+        let mut reader = Cursor::new(stego);
+        let encrypted = extract_appended(&mut reader)?;
+        let mut carrier = Vec::new();
+        strip_appended(&mut reader, &mut carrier)?;
+        return Ok((encrypted, carrier));
+    }
+    let file_type = resolve_file_type(&opts.file_type, stego)?;
+    find_format(&file_type)
+        .ok_or_else(|| unsupported(&file_type, &method))?
+        .extract(stego, opts)
+}
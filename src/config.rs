@@ -0,0 +1,417 @@
+use crate::cancel::{write_output, CancellationToken};
+use crate::cli::{MetaAction, SteganoCommands, VaultAction, WatermarkAction};
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use std::fmt;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// Keys accepted in the configuration file, with what they set.
+pub const CONFIG_KEYS: [(&str, &str); 5] = [
+    ("algorithm", "the encryption algorithm, aes or xor (--algo)"),
+    (
+        "chunk_name",
+        "the PNG chunk holding the payload (--chunk-name)",
+    ),
+    (
+        "output_dir",
+        "the directory outputs are written to when --output is a relative path",
+    ),
+    ("suppress", "true to suppress output messages (--suppress)"),
+    (
+        "json",
+        "true for JSON output, false for human output, where supported (--format)",
+    ),
+];
+
+/// Defaults read from the configuration file, overridden by command-line flags.
+///
+/// The file is a flat TOML document of `key = value` lines with `#` comments, where values
+/// are strings or booleans; see `CONFIG_KEYS` for the keys.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::config::Config;
+///
+/// let config = Config::parse("# Defaults\nalgorithm = \"xor\"\njson = true\n").unwrap();
+/// assert_eq!(config.algorithm.as_deref(), Some("xor"));
+/// assert_eq!(config.json, Some(true));
+/// assert_eq!(Config::parse(&config.to_string()).unwrap(), config);
+/// assert!(Config::parse("colour = \"red\"").is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    /// The encryption algorithm.
+    pub algorithm: Option<String>,
+    /// The PNG chunk holding the payload.
+    pub chunk_name: Option<String>,
+    /// The directory relative outputs are written to.
+    pub output_dir: Option<String>,
+    /// Whether to suppress output messages.
+    pub suppress: Option<bool>,
+    /// Whether to print JSON where supported.
+    pub json: Option<bool>,
+}
+
+/// Builds an `InvalidData` error for a line of the configuration file.
+fn invalid(line: usize, message: impl fmt::Display) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("Config line {}: {}", line, message),
+    )
+}
+
+/// Parses a TOML basic string, with its quotes.
+fn parse_string(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                '"' => out.push('"'),
+                '\\' => out.push('\\'),
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                _ => return None,
+            },
+            '"' => return None,
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// Formats a TOML basic string.
+fn format_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+/// Removes a `#` comment that isn't inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+impl Config {
+    /// Returns the path of the configuration file: `$STEGANO_CONFIG` if set, otherwise
+    /// `stegano/config.toml` under `$XDG_CONFIG_HOME` or `~/.config`.
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("STEGANO_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(base.join("stegano").join("config.toml"))
+    }
+
+    /// Loads the configuration file, or the empty configuration if there is none.
+    pub fn load() -> io::Result<Config> {
+        match Config::path() {
+            Some(path) => Config::load_from(&path),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// Loads a configuration file, or the empty configuration if it doesn't exist.
+    pub fn load_from(path: &Path) -> io::Result<Config> {
+        match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err))),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Parses the text of a configuration file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the configuration, or an `InvalidData` error naming the line of
+    /// the first unknown key or malformed value.
+    pub fn parse(text: &str) -> io::Result<Config> {
+        let mut config = Config::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid(i + 1, "expected `key = value`"))?;
+            let value = value.trim();
+            let parsed = match value {
+                "true" | "false" => Some(value.to_string()),
+                _ => parse_string(value),
+            }
+            .ok_or_else(|| invalid(i + 1, format!("malformed value {}", value)))?;
+            config
+                .set(key.trim(), &parsed)
+                .map_err(|err| invalid(i + 1, err))?;
+        }
+        Ok(config)
+    }
+
+    /// Sets a key from its text value.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or an `InvalidInput` error for unknown keys and values.
+    pub fn set(&mut self, key: &str, value: &str) -> io::Result<()> {
+        let boolean = || match value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} must be true or false, got {:?}", key, value),
+            )),
+        };
+        match key {
+            "algorithm" => {
+                let algorithm = value.to_lowercase();
+                if !matches!(
+                    algorithm.as_str(),
+                    "aes" | "aes192" | "aes256" | "chacha" | "xor" | "age"
+                ) {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "algorithm must be aes, aes192, aes256, chacha, xor or age, got {:?}",
+                            value
+                        ),
+                    ));
+                }
+                self.algorithm = Some(algorithm);
+            }
+            "chunk_name" => self.chunk_name = Some(value.to_string()),
+            "output_dir" => self.output_dir = Some(value.to_string()),
+            "suppress" => self.suppress = Some(boolean()?),
+            "json" => self.json = Some(boolean()?),
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Unknown key {:?}, expected one of {}",
+                        key,
+                        CONFIG_KEYS.map(|(key, _)| key).join(", ")
+                    ),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes a key, so that the built-in default applies again.
+    pub fn unset(&mut self, key: &str) -> io::Result<()> {
+        match key {
+            "algorithm" => self.algorithm = None,
+            "chunk_name" => self.chunk_name = None,
+            "output_dir" => self.output_dir = None,
+            "suppress" => self.suppress = None,
+            "json" => self.json = None,
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown key {:?}", key),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the keys that are set, with their values formatted as TOML.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let strings = [
+            ("algorithm", &self.algorithm),
+            ("chunk_name", &self.chunk_name),
+            ("output_dir", &self.output_dir),
+        ];
+        let booleans = [("suppress", self.suppress), ("json", self.json)];
+        strings
+            .into_iter()
+            .filter_map(|(key, value)| value.as_deref().map(|v| (key, format_string(v))))
+            .chain(
+                booleans
+                    .into_iter()
+                    .filter_map(|(key, value)| value.map(|v| (key, v.to_string()))),
+            )
+            .collect()
+    }
+
+    /// Writes the configuration to a file, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        write_output(path, self.to_string().as_bytes(), &CancellationToken::new())
+    }
+
+    /// Fills the options of a parsed command that weren't given on the command line.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The parsed subcommand.
+    /// * `matches` - The matches of the subcommand, telling which flags were given.
+    pub fn apply(&self, command: &mut SteganoCommands, matches: &ArgMatches) {
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        let output = |path: &mut String| {
+            if let Some(dir) = &self.output_dir {
+                if !given("output") && Path::new(path.as_str()).is_relative() {
+                    *path = Path::new(dir).join(&*path).to_string_lossy().into_owned();
+                }
+            }
+        };
+        let format = |format: &mut String| {
+            if let Some(json) = self.json {
+                if !given("format") && !given("json") {
+                    *format = String::from(if json { "json" } else { "human" });
+                }
+            }
+        };
+        let fill = |id: &str, field: &mut String, value: &Option<String>| {
+            if let (Some(value), false) = (value, given(id)) {
+                *field = value.clone();
+            }
+        };
+        let suppress = |flag: &mut bool| {
+            *flag |= !given("suppress") && self.suppress == Some(true);
+        };
+        match command {
+            SteganoCommands::Encrypt(cmd) => {
+                fill("algorithm", &mut cmd.algorithm, &self.algorithm);
+                fill("chunk_name", &mut cmd.chunk_name, &self.chunk_name);
+                output(&mut cmd.output);
+                format(&mut cmd.format);
+                suppress(&mut cmd.suppress);
+            }
+            SteganoCommands::Extract(cmd)
+            | SteganoCommands::Remove(cmd)
+            | SteganoCommands::Decrypt(cmd) => {
+                fill("algorithm", &mut cmd.algorithm, &self.algorithm);
+                fill("chunk_name", &mut cmd.chunk_name, &self.chunk_name);
+                output(&mut cmd.output);
+                suppress(&mut cmd.suppress);
+            }
+            SteganoCommands::ShowMeta(cmd) => {
+                output(&mut cmd.output);
+                format(&mut cmd.format);
+                suppress(&mut cmd.suppress);
+            }
+            SteganoCommands::Polyglot(cmd) => {
+                fill("algorithm", &mut cmd.algorithm, &self.algorithm);
+                output(&mut cmd.output);
+            }
+            SteganoCommands::Rekey(cmd) => {
+                fill("algorithm", &mut cmd.algorithm, &self.algorithm);
+                fill("chunk_name", &mut cmd.chunk_name, &self.chunk_name);
+                output(&mut cmd.output);
+                suppress(&mut cmd.suppress);
+            }
+            SteganoCommands::Watermark(cmd) => {
+                // The flags are those of the action.
+                let given = |id: &str| {
+                    matches
+                        .subcommand()
+                        .and_then(|(_, sub)| sub.value_source(id))
+                        == Some(ValueSource::CommandLine)
+                };
+                if let WatermarkAction::Embed(embed) = &mut cmd.action {
+                    if let Some(dir) = &self.output_dir {
+                        if !given("output") && Path::new(&embed.output).is_relative() {
+                            embed.output = Path::new(dir)
+                                .join(&embed.output)
+                                .to_string_lossy()
+                                .into_owned();
+                        }
+                    }
+                    embed.suppress |= !given("suppress") && self.suppress == Some(true);
+                }
+            }
+            SteganoCommands::Vault(cmd) => {
+                // The flags are those of the action, and outputs default to the input.
+                let given = |id: &str| {
+                    matches
+                        .subcommand()
+                        .and_then(|(_, sub)| sub.value_source(id))
+                        == Some(ValueSource::CommandLine)
+                };
+                let suppress = || !given("suppress") && self.suppress == Some(true);
+                match &mut cmd.action {
+                    VaultAction::Put(put) => {
+                        if let (Some(algorithm), false) = (&self.algorithm, given("algorithm")) {
+                            put.algorithm = algorithm.clone();
+                        }
+                        put.suppress |= suppress();
+                    }
+                    VaultAction::Get(get) => get.suppress |= suppress(),
+                    VaultAction::Rm(rm) => rm.suppress |= suppress(),
+                    VaultAction::Ls(_) => {}
+                }
+            }
+            SteganoCommands::Meta(cmd) => {
+                // The flags are those of the action, and outputs default to the input.
+                let given = |id: &str| {
+                    matches
+                        .subcommand()
+                        .and_then(|(_, sub)| sub.value_source(id))
+                        == Some(ValueSource::CommandLine)
+                };
+                let suppress = || !given("suppress") && self.suppress == Some(true);
+                match &mut cmd.action {
+                    MetaAction::Set(set) => set.suppress |= suppress(),
+                    MetaAction::Remove(remove) => remove.suppress |= suppress(),
+                    MetaAction::Get(_) => {}
+                }
+            }
+            SteganoCommands::InjectChunk(cmd) => {
+                output(&mut cmd.output);
+                suppress(&mut cmd.suppress);
+            }
+            SteganoCommands::ExtractChunk(cmd) => {
+                output(&mut cmd.output);
+                suppress(&mut cmd.suppress);
+            }
+            SteganoCommands::RemoveChunk(cmd) => {
+                output(&mut cmd.output);
+                suppress(&mut cmd.suppress);
+            }
+            SteganoCommands::Peek(cmd) => fill("chunk_name", &mut cmd.chunk_name, &self.chunk_name),
+            SteganoCommands::Soak(cmd) => suppress(&mut cmd.suppress),
+            SteganoCommands::GenerateCarrier(cmd) => {
+                output(&mut cmd.output);
+                suppress(&mut cmd.suppress);
+            }
+            SteganoCommands::Diff(cmd) => format(&mut cmd.format),
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for Config {
+    /// Formats the configuration as the TOML file it is read from.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in self.entries() {
+            writeln!(f, "{} = {}", key, value)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,340 @@
+use crate::header::{PayloadHeader, TAG_LEN};
+use crate::png::encode_rgb;
+use crate::prng::Prng;
+use std::f64::consts::PI;
+use std::io::{self, ErrorKind};
+
+/// Largest number of pixels of a generated carrier, about 400 MiB of samples.
+pub const MAX_COVER_PIXELS: usize = 1 << 27;
+
+/// Share of the LSB capacity a payload may fill for the carrier to count as safe. At a
+/// quarter, about one colour sample in sixteen changes, lost in the grain of every style.
+const SAFE_FRACTION: usize = 4;
+
+/// Amplitude of the sensor-like grain added to the smooth styles, in levels. Anything above a
+/// few levels leaves the least significant bits as random as those of a photo.
+const GRAIN: u64 = 6;
+
+/// Number of octaves summed by the perlin style.
+const OCTAVES: usize = 5;
+
+/// The look of a generated carrier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverStyle {
+    /// Every sample drawn at random, like static.
+    Noise,
+    /// A linear blend of two colours at a random angle, with grain.
+    Gradient,
+    /// Fractal Perlin noise over a three-colour palette, like clouds or marble, with grain.
+    Perlin,
+}
+
+impl CoverStyle {
+    /// Parses a style name: noise, gradient or perlin.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::cover::CoverStyle;
+    ///
+    /// assert_eq!(CoverStyle::new("Perlin").unwrap(), CoverStyle::Perlin);
+    /// assert!(CoverStyle::new("plaid").is_err());
+    /// ```
+    pub fn new(name: &str) -> io::Result<CoverStyle> {
+        match name.to_lowercase().as_str() {
+            "noise" => Ok(CoverStyle::Noise),
+            "gradient" => Ok(CoverStyle::Gradient),
+            "perlin" => Ok(CoverStyle::Perlin),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unknown carrier style {:?}, use noise, gradient or perlin",
+                    name
+                ),
+            )),
+        }
+    }
+
+    /// Returns the name of the style, as given on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CoverStyle::Noise => "noise",
+            CoverStyle::Gradient => "gradient",
+            CoverStyle::Perlin => "perlin",
+        }
+    }
+}
+
+/// Returns the number of payload bytes an RGB carrier of this size holds safely with the LSB
+/// method: a quarter of its LSB capacity.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::cover::{generate_cover, safe_capacity, CoverStyle};
+/// use stegano::lsb::lsb_capacity;
+/// use stegano::png::PngImage;
+///
+/// let png = generate_cover(64, 48, CoverStyle::Noise, 1).unwrap();
+/// let capacity = lsb_capacity(&PngImage::decode(&png).unwrap());
+/// assert_eq!(safe_capacity(64, 48), capacity / 4);
+/// ```
+pub fn safe_capacity(width: usize, height: usize) -> usize {
+    // The LSB method keeps 4 bytes for the payload length.
+    (width * height * 3 / 8).saturating_sub(4) / SAFE_FRACTION
+}
+
+/// Returns the largest size a payload can take once encrypted: AES-256 in CBC mode, with the
+/// payload header, a whole block of padding and the payload tag, but no hint or file name.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::cover::sealed_len;
+///
+/// assert!(sealed_len(100) > 100);
+/// assert_eq!(sealed_len(16) - sealed_len(15), 16);
+/// ```
+pub fn sealed_len(payload_len: usize) -> usize {
+    let header = PayloadHeader::new("aes256", payload_len as u64, 0)
+        .with_mode("cbc")
+        .and_then(|header| header.to_bytes(""))
+        .map_or(0, |bytes| bytes.len());
+    header + (payload_len / 16 + 1) * 16 + TAG_LEN
+}
+
+/// Grows a carrier size, keeping its aspect ratio, until it safely holds a payload.
+///
+/// # Arguments
+///
+/// * `width` - The width asked for, in pixels.
+/// * `height` - The height asked for, in pixels.
+/// * `payload_len` - The size of the payload, before encryption.
+///
+/// # Returns
+///
+/// The size asked for if it is large enough, a larger one otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::cover::{fit_dimensions, safe_capacity, sealed_len};
+///
+/// assert_eq!(fit_dimensions(1920, 1080, 1000), (1920, 1080));
+/// let (width, height) = fit_dimensions(16, 9, 1000);
+/// assert!(safe_capacity(width, height) >= sealed_len(1000));
+/// assert_eq!(width * 9, height * 16);
+/// ```
+pub fn fit_dimensions(width: usize, height: usize, payload_len: usize) -> (usize, usize) {
+    let needed = sealed_len(payload_len);
+    let (width, height) = (width.max(1), height.max(1));
+    if safe_capacity(width, height) >= needed {
+        return (width, height);
+    }
+    // Each step grows both sides by the same factor, so the ratio stays exact.
+    let divisor = gcd(width, height);
+    let (step_x, step_y) = (width / divisor, height / divisor);
+    let mut factor = divisor;
+    while safe_capacity(step_x * factor, step_y * factor) < needed {
+        factor += (factor / 8).max(1);
+    }
+    (step_x * factor, step_y * factor)
+}
+
+/// Returns the greatest common divisor of two numbers.
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Synthesizes a plausible RGB carrier.
+///
+/// The same seed always gives the same image. The smooth styles get a few levels of random
+/// grain, so that the least significant bits look like those of a photo rather than of a
+/// rendering, where LSB embedding would stand out.
+///
+/// # Arguments
+///
+/// * `width` - Width in pixels.
+/// * `height` - Height in pixels.
+/// * `style` - The look of the image.
+/// * `seed` - The seed of the random generator.
+///
+/// # Returns
+///
+/// A `Result` containing the PNG file, or an `InvalidInput` error if the size is empty or
+/// larger than `MAX_COVER_PIXELS`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::cover::{generate_cover, CoverStyle};
+/// use stegano::png::PngImage;
+///
+/// for style in [CoverStyle::Noise, CoverStyle::Gradient, CoverStyle::Perlin] {
+///     let png = generate_cover(40, 30, style, 7).unwrap();
+///     assert_eq!(png, generate_cover(40, 30, style, 7).unwrap());
+///     let image = PngImage::decode(&png).unwrap();
+///     assert_eq!((image.width, image.height, image.color_type), (40, 30, 2));
+///     // The least significant bits are about half ones.
+///     let ones = image.samples.iter().filter(|&&sample| sample & 1 == 1).count();
+///     assert!((ones as f64 / image.samples.len() as f64 - 0.5).abs() < 0.1);
+/// }
+/// assert!(generate_cover(0, 30, CoverStyle::Noise, 7).is_err());
+/// ```
+pub fn generate_cover(
+    width: usize,
+    height: usize,
+    style: CoverStyle,
+    seed: u64,
+) -> io::Result<Vec<u8>> {
+    let pixels = width.saturating_mul(height);
+    if pixels == 0 || pixels > MAX_COVER_PIXELS {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Carriers are 1 to {} pixels, {}x{} isn't",
+                MAX_COVER_PIXELS, width, height
+            ),
+        ));
+    }
+    let mut rng = Prng::new(seed);
+    let mut samples = vec![0u8; pixels * 3];
+    match style {
+        CoverStyle::Noise => rng.fill(&mut samples),
+        CoverStyle::Gradient => {
+            let from = random_colour(&mut rng);
+            let to = random_colour(&mut rng);
+            let angle = rng.below(360) as f64 * PI / 180.0;
+            let (dx, dy) = (angle.cos(), angle.sin());
+            // Projections of the corners, so that the blend spans the whole image.
+            let (w, h) = (dx * width as f64, dy * height as f64);
+            let corners = [0.0, w, h, w + h];
+            let low = corners.iter().copied().fold(f64::MAX, f64::min);
+            let high = corners.iter().copied().fold(f64::MIN, f64::max);
+            for (i, pixel) in samples.chunks_exact_mut(3).enumerate() {
+                let (x, y) = ((i % width) as f64, (i / width) as f64);
+                let t = (dx * x + dy * y - low) / (high - low).max(1.0);
+                for (c, sample) in pixel.iter_mut().enumerate() {
+                    *sample = grain(&mut rng, lerp(from[c] as f64, to[c] as f64, t));
+                }
+            }
+        }
+        CoverStyle::Perlin => {
+            let noise = Perlin::new(&mut rng);
+            let palette = [
+                random_colour(&mut rng),
+                random_colour(&mut rng),
+                random_colour(&mut rng),
+            ];
+            // The largest features span about a quarter of the longer side.
+            let scale = 4.0 / width.max(height) as f64;
+            for (i, pixel) in samples.chunks_exact_mut(3).enumerate() {
+                let (x, y) = ((i % width) as f64, (i / width) as f64);
+                let t = (noise.fractal(x * scale, y * scale) * 0.5 + 0.5).clamp(0.0, 1.0);
+                let (a, b, t) = if t < 0.5 {
+                    (palette[0], palette[1], t * 2.0)
+                } else {
+                    (palette[1], palette[2], t * 2.0 - 1.0)
+                };
+                for (c, sample) in pixel.iter_mut().enumerate() {
+                    *sample = grain(&mut rng, lerp(a[c] as f64, b[c] as f64, t));
+                }
+            }
+        }
+    }
+    encode_rgb(width, height, &samples)
+}
+
+/// Draws a random colour.
+fn random_colour(rng: &mut Prng) -> [u8; 3] {
+    let mut colour = [0; 3];
+    rng.fill(&mut colour);
+    colour
+}
+
+/// Blends two values, `t` going from 0 (`a`) to 1 (`b`).
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// Adds up to `GRAIN` levels of random grain to a sample value, reflecting at the ends of the
+/// range so that dark and bright areas stay grainy.
+fn grain(rng: &mut Prng, value: f64) -> u8 {
+    let value = value.round() as i64 + rng.below(2 * GRAIN + 1) as i64 - GRAIN as i64;
+    match value {
+        v if v < 0 => (-v).min(255) as u8,
+        v if v > 255 => (510 - v).max(0) as u8,
+        v => v as u8,
+    }
+}
+
+/// Two-dimensional Perlin gradient noise over a shuffled permutation table.
+struct Perlin {
+    permutation: Vec<usize>,
+}
+
+impl Perlin {
+    /// Shuffles the permutation table with the generator.
+    fn new(rng: &mut Prng) -> Self {
+        let mut permutation: Vec<usize> = (0..256).collect();
+        for i in (1..256).rev() {
+            permutation.swap(i, rng.below(i as u64 + 1) as usize);
+        }
+        permutation.extend_from_within(..);
+        Perlin { permutation }
+    }
+
+    /// Returns the noise at a point, between about -1 and 1.
+    fn noise(&self, x: f64, y: f64) -> f64 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (xi, yi) = (x0 as i64 as usize & 255, y0 as i64 as usize & 255);
+        let p = &self.permutation;
+        let hash = |dx: usize, dy: usize| p[p[xi + dx] + yi + dy];
+        let fade = |t: f64| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let (u, v) = (fade(fx), fade(fy));
+        let top = lerp(
+            gradient(hash(0, 0), fx, fy),
+            gradient(hash(1, 0), fx - 1.0, fy),
+            u,
+        );
+        let bottom = lerp(
+            gradient(hash(0, 1), fx, fy - 1.0),
+            gradient(hash(1, 1), fx - 1.0, fy - 1.0),
+            u,
+        );
+        lerp(top, bottom, v)
+    }
+
+    /// Sums `OCTAVES` octaves of noise, each twice as fine and half as strong as the last.
+    fn fractal(&self, x: f64, y: f64) -> f64 {
+        let (mut sum, mut amplitude, mut frequency, mut total) = (0.0, 1.0, 1.0, 0.0);
+        for _ in 0..OCTAVES {
+            sum += self.noise(x * frequency, y * frequency) * amplitude;
+            total += amplitude;
+            amplitude /= 2.0;
+            frequency *= 2.0;
+        }
+        // Perlin noise rarely gets past ±0.7, so the sum is stretched to use the palette.
+        sum / total * 1.4
+    }
+}
+
+/// Returns the dot product of one of eight gradient directions, picked by `hash`, with the
+/// offset of a point from a lattice corner.
+fn gradient(hash: usize, x: f64, y: f64) -> f64 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
@@ -0,0 +1,248 @@
+use crate::error::SteganoError;
+use crate::json::JsonValue;
+use crate::provenance::digest_hex;
+use crate::utils::{decrypt_with_mode, encrypt_stream_mode, strip_padding, CipherMode, Padding};
+use sha2::{Digest, Sha256};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+
+/// Schema of descriptor files.
+pub const DESCRIPTOR_SCHEMA: &str = "stegano.descriptor/1";
+
+/// Where an embedded payload lives in a stego file, kept apart from it for recovery.
+///
+/// A descriptor records the byte range of the embedded payload and its digest, so that
+/// `decrypt --descriptor` can read it directly even when the structures normally used to find
+/// it, such as the chunk list, the IFD or the append trailer, are damaged. It never holds the
+/// key, and is sealed with the payload's own algorithm and key, so it reveals nothing on its
+/// own but the algorithm.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use stegano::descriptor::{sha256_range, Descriptor};
+///
+/// let stego = b"header|embedded bytes|trailer".to_vec();
+/// let descriptor = Descriptor {
+///     file_type: "png".to_string(),
+///     method: "append".to_string(),
+///     algorithm: "aes".to_string(),
+///     chunk_name: "stEg".to_string(),
+///     offset: 7,
+///     length: 14,
+///     sha256: sha256_range(&mut Cursor::new(&stego), 7, 14).unwrap(),
+/// };
+/// let sealed = descriptor.seal("key").unwrap();
+/// assert!(!sealed.contains("append"));
+///
+/// let opened = Descriptor::open(&sealed, "key").unwrap();
+/// assert_eq!(opened, descriptor);
+/// assert_eq!(opened.read_payload(&mut Cursor::new(&stego)).unwrap(), b"embedded bytes");
+/// assert!(Descriptor::open(&sealed, "wrong key").is_err());
+///
+/// // ChaCha20 descriptors store the nonce they were sealed with.
+/// let chacha = Descriptor { algorithm: "chacha".to_string(), ..descriptor };
+/// let sealed = chacha.seal("key").unwrap();
+/// assert!(sealed.contains("\"nonce\""));
+/// assert_ne!(sealed, chacha.seal("key").unwrap());
+/// assert_eq!(Descriptor::open(&sealed, "key").unwrap(), chacha);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Descriptor {
+    /// The carrier type.
+    pub file_type: String,
+    /// The embedding method.
+    pub method: String,
+    /// The algorithm of the payload, which also seals the descriptor.
+    pub algorithm: String,
+    /// The PNG chunk holding the payload, for the chunk method.
+    pub chunk_name: String,
+    /// The offset of the first embedded byte in the stego file.
+    pub offset: u64,
+    /// The number of embedded bytes.
+    pub length: u64,
+    /// The SHA-256 digest of the embedded bytes.
+    pub sha256: [u8; 32],
+}
+
+/// Builds the error returned for descriptors that can't be read.
+fn unreadable(reason: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("Can't open the descriptor: {}", reason),
+    )
+}
+
+/// Decodes a hexadecimal string.
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 == 1 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+impl Descriptor {
+    /// Encrypts the descriptor into the JSON text of a descriptor file, with its algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to seal it with, usually the key of the payload.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the JSON document.
+    pub fn seal(&self, key: &str) -> io::Result<String> {
+        let inner = JsonValue::object([
+            ("type", JsonValue::from(self.file_type.as_str())),
+            ("method", JsonValue::from(self.method.as_str())),
+            ("chunk_name", JsonValue::from(self.chunk_name.as_str())),
+            ("offset", JsonValue::from(self.offset)),
+            ("length", JsonValue::from(self.length)),
+            ("sha256", JsonValue::from(digest_hex(&self.sha256))),
+        ])
+        .to_string();
+        // ChaCha20 can't run without a nonce, which is drawn afresh and stored in the clear.
+        let mode = if self.algorithm.eq_ignore_ascii_case("chacha") {
+            CipherMode::new("ctr")?
+        } else {
+            CipherMode::Ecb
+        };
+        let mut sealed = Vec::new();
+        encrypt_stream_mode(
+            &self.algorithm,
+            key,
+            &mode,
+            Padding::Zeros,
+            inner.as_bytes(),
+            &mut sealed,
+        )?;
+        let nonce = mode
+            .iv()
+            .map(|iv| ("nonce", JsonValue::from(digest_hex(iv))));
+        Ok(JsonValue::object(
+            [
+                ("schema", JsonValue::from(DESCRIPTOR_SCHEMA)),
+                ("algorithm", JsonValue::from(self.algorithm.to_lowercase())),
+            ]
+            .into_iter()
+            .chain(nonce)
+            .chain([("sealed", JsonValue::from(digest_hex(&sealed)))]),
+        )
+        .to_string())
+    }
+
+    /// Decrypts a descriptor file written by `seal`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The JSON text of the descriptor file.
+    /// * `key` - The key it was sealed with.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the descriptor, or an `InvalidData` error if the file is malformed
+    /// or the key is wrong.
+    pub fn open(text: &str, key: &str) -> io::Result<Descriptor> {
+        let outer = JsonValue::parse(text)?;
+        if outer.get("schema").and_then(JsonValue::as_str) != Some(DESCRIPTOR_SCHEMA) {
+            return Err(unreadable("not a stegano descriptor"));
+        }
+        let algorithm = outer
+            .get("algorithm")
+            .and_then(JsonValue::as_str)
+            .ok_or_else(|| unreadable("missing algorithm"))?;
+        let sealed = outer
+            .get("sealed")
+            .and_then(JsonValue::as_str)
+            .and_then(from_hex)
+            .ok_or_else(|| unreadable("missing sealed data"))?;
+        let mode = match outer.get("nonce").and_then(JsonValue::as_str) {
+            Some(nonce) => CipherMode::Ctr(
+                from_hex(nonce)
+                    .and_then(|iv| iv.try_into().ok())
+                    .ok_or_else(|| unreadable("malformed nonce"))?,
+            ),
+            None => CipherMode::Ecb,
+        };
+        let mut inner = decrypt_with_mode(algorithm, key, &mode, Padding::Zeros, &sealed)
+            .map_err(unreadable)?;
+        strip_padding(algorithm, &mut inner);
+        let inner = std::str::from_utf8(&inner)
+            .ok()
+            .and_then(|text| JsonValue::parse(text).ok())
+            .ok_or_else(|| {
+                io::Error::from(SteganoError::WrongKey(
+                    "Can't open the descriptor: wrong key?".to_string(),
+                ))
+            })?;
+        let string = |key: &str| {
+            inner
+                .get(key)
+                .and_then(JsonValue::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| unreadable(&format!("missing {}", key)))
+        };
+        let number = |key: &str| {
+            inner
+                .get(key)
+                .and_then(JsonValue::as_u64)
+                .ok_or_else(|| unreadable(&format!("missing {}", key)))
+        };
+        let sha256 = from_hex(&string("sha256")?)
+            .and_then(|digest| digest.try_into().ok())
+            .ok_or_else(|| unreadable("malformed sha256"))?;
+        Ok(Descriptor {
+            file_type: string("type")?,
+            method: string("method")?,
+            algorithm: algorithm.to_string(),
+            chunk_name: string("chunk_name")?,
+            offset: number("offset")?,
+            length: number("length")?,
+            sha256,
+        })
+    }
+
+    /// Reads the embedded bytes from a stego file and checks them against the digest.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the embedded bytes, or an `InvalidData` error if the file is too
+    /// short or the bytes at the recorded offset changed.
+    pub fn read_payload<R: Read + Seek>(&self, reader: &mut R) -> io::Result<Vec<u8>> {
+        reader.seek(SeekFrom::Start(self.offset))?;
+        let mut payload = Vec::new();
+        reader.take(self.length).read_to_end(&mut payload)?;
+        if payload.len() as u64 != self.length || Sha256::digest(&payload).as_slice() != self.sha256
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "The {} bytes at offset {} don't match the descriptor, the payload was altered or moved",
+                    self.length, self.offset
+                ),
+            ));
+        }
+        Ok(payload)
+    }
+}
+
+/// Computes the SHA-256 digest of `len` bytes of a stream starting at `offset`.
+///
+/// # Returns
+///
+/// A `Result` containing the digest, or an `UnexpectedEof` error if the stream is too short.
+pub fn sha256_range<R: Read + Seek>(reader: &mut R, offset: u64, len: u64) -> io::Result<[u8; 32]> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut hasher = Sha256::new();
+    let copied = io::copy(&mut reader.take(len), &mut hasher)?;
+    if copied != len {
+        return Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "The stream ends before the range",
+        ));
+    }
+    Ok(hasher.finalize().into())
+}
@@ -0,0 +1,498 @@
+use crate::chunk_type::ChunkType;
+use crate::outln;
+use crate::output::OutputWriter;
+use crate::png::{read_chunks, PngImage, RawChunk};
+use crate::style::{colors_enabled, Stream, Style};
+use crate::utils::{HexFormatter, HEX_LINE};
+use std::io::{self, ErrorKind, Write};
+use std::ops::Range;
+
+/// Bytes of context shown on each side of a change by `write_hex_diff`, two dump lines.
+pub const HEX_CONTEXT: usize = 2 * HEX_LINE;
+
+/// Changed runs longer than this many bytes only show their first and last dump lines.
+const HEX_ELIDE: usize = 4 * HEX_LINE;
+
+/// How a chunk differs between two PNG files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkChange {
+    /// Same data at the same offset.
+    Unchanged,
+    /// Same data at another offset, because something before it changed.
+    Moved,
+    /// Different data.
+    Modified,
+    /// Only in the second file.
+    Added,
+    /// Only in the first file.
+    Removed,
+}
+
+impl ChunkChange {
+    /// Returns the lowercase name of the change, as used in the JSON output.
+    pub fn name(self) -> &'static str {
+        match self {
+            ChunkChange::Unchanged => "unchanged",
+            ChunkChange::Moved => "moved",
+            ChunkChange::Modified => "modified",
+            ChunkChange::Added => "added",
+            ChunkChange::Removed => "removed",
+        }
+    }
+}
+
+/// The difference between a chunk of the first file and its counterpart in the second.
+///
+/// Chunks are paired by type and rank: the second `IDAT` of one file is compared with the
+/// second `IDAT` of the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkDiff {
+    /// The four-letter chunk type.
+    pub r#type: ChunkType,
+    /// What changed.
+    pub change: ChunkChange,
+    /// The offset and data size of the chunk in the first file.
+    pub a: Option<(usize, usize)>,
+    /// The offset and data size of the chunk in the second file.
+    pub b: Option<(usize, usize)>,
+    /// The number of data bytes that differ, counting the length difference.
+    pub bytes_changed: usize,
+    /// The position in the data of the first differing byte.
+    pub first_difference: Option<usize>,
+}
+
+/// Compares two PNG files chunk by chunk.
+///
+/// # Arguments
+///
+/// * `a` - The bytes of the first file, usually the original.
+/// * `b` - The bytes of the second file, usually the modified one.
+///
+/// # Returns
+///
+/// A `Result` containing one `ChunkDiff` per chunk, in the order of the first file followed by
+/// the chunks added in the second one.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::diff::{diff_chunks, ChunkChange};
+/// use stegano::png::{encode_grayscale, insert_chunk};
+///
+/// let original = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let (modified, _) = insert_chunk(&original, b"stEg", b"payload", None).unwrap();
+/// let diffs = diff_chunks(&original, &modified).unwrap();
+/// let added: Vec<_> = diffs.iter().filter(|d| d.change == ChunkChange::Added).collect();
+/// assert_eq!(added.len(), 1);
+/// assert_eq!(&added[0].r#type, b"stEg");
+/// assert!(diffs.iter().any(|d| &d.r#type == b"IEND" && d.change == ChunkChange::Moved));
+/// ```
+pub fn diff_chunks(a: &[u8], b: &[u8]) -> io::Result<Vec<ChunkDiff>> {
+    let chunks_a = read_chunks(a)?;
+    let chunks_b = read_chunks(b)?;
+    let mut paired = vec![false; chunks_b.len()];
+    let mut diffs = Vec::new();
+    for (i, chunk) in chunks_a.iter().enumerate() {
+        let rank = chunks_a[..i]
+            .iter()
+            .filter(|other| other.r#type == chunk.r#type)
+            .count();
+        let counterpart = chunks_b
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| other.r#type == chunk.r#type)
+            .nth(rank);
+        let Some((j, other)) = counterpart else {
+            diffs.push(ChunkDiff {
+                r#type: chunk.r#type,
+                change: ChunkChange::Removed,
+                a: Some((chunk.offset, chunk.data.len())),
+                b: None,
+                bytes_changed: chunk.data.len(),
+                first_difference: None,
+            });
+            continue;
+        };
+        paired[j] = true;
+        diffs.push(compare(chunk, other));
+    }
+    for (chunk, _) in chunks_b.iter().zip(paired).filter(|(_, paired)| !paired) {
+        diffs.push(ChunkDiff {
+            r#type: chunk.r#type,
+            change: ChunkChange::Added,
+            a: None,
+            b: Some((chunk.offset, chunk.data.len())),
+            bytes_changed: chunk.data.len(),
+            first_difference: None,
+        });
+    }
+    Ok(diffs)
+}
+
+/// Compares two chunks of the same type.
+fn compare(a: &RawChunk, b: &RawChunk) -> ChunkDiff {
+    let common = a.data.len().min(b.data.len());
+    let differing = (0..common).filter(|&i| a.data[i] != b.data[i]);
+    let first_difference = differing
+        .clone()
+        .next()
+        .or_else(|| (a.data.len() != b.data.len()).then_some(common));
+    let bytes_changed = differing.count() + a.data.len().abs_diff(b.data.len());
+    let change = if bytes_changed > 0 {
+        ChunkChange::Modified
+    } else if a.offset != b.offset {
+        ChunkChange::Moved
+    } else {
+        ChunkChange::Unchanged
+    };
+    ChunkDiff {
+        r#type: a.r#type,
+        change,
+        a: Some((a.offset, a.data.len())),
+        b: Some((b.offset, b.data.len())),
+        bytes_changed,
+        first_difference,
+    }
+}
+
+/// The difference between the decoded samples of two images of the same size.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PixelDiff {
+    /// The width of the images, in pixels.
+    pub width: usize,
+    /// The height of the images, in pixels.
+    pub height: usize,
+    /// The number of pixels with at least one differing sample.
+    pub pixels_changed: usize,
+    /// The number of differing samples.
+    pub samples_changed: usize,
+    /// The number of differing samples where only the least significant bit changed, the mark
+    /// of LSB embedding.
+    pub lsb_only: usize,
+    /// The largest difference between two samples.
+    pub max_delta: u16,
+    /// The smallest rectangle holding every changed pixel, as left, top, right and bottom
+    /// coordinates, all inclusive.
+    pub bounds: Option<(usize, usize, usize, usize)>,
+}
+
+/// Compares the pixels of two PNG images.
+///
+/// # Arguments
+///
+/// * `a` - The bytes of the first image.
+/// * `b` - The bytes of the second image.
+///
+/// # Returns
+///
+/// A `Result` containing the `PixelDiff`, or an `InvalidInput` error if the images differ in
+/// size, colour type or bit depth, where samples can't be compared one to one.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::diff::diff_pixels;
+/// use stegano::png::encode_grayscale;
+///
+/// let a = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let b = encode_grayscale(2, 2, &[0, 65, 128, 200]).unwrap();
+/// let diff = diff_pixels(&a, &b).unwrap();
+/// assert_eq!(diff.pixels_changed, 2);
+/// assert_eq!(diff.lsb_only, 1);
+/// assert_eq!(diff.max_delta, 55);
+/// assert_eq!(diff.bounds, Some((1, 0, 1, 1)));
+/// ```
+pub fn diff_pixels(a: &[u8], b: &[u8]) -> io::Result<PixelDiff> {
+    let image_a = PngImage::decode(a)?;
+    let image_b = PngImage::decode(b)?;
+    let layout = |image: &PngImage| (image.width, image.height, image.color_type, image.bit_depth);
+    if layout(&image_a) != layout(&image_b) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The pixels can't be compared: {}x{} colour type {} depth {} vs {}x{} colour type {} depth {}",
+                image_a.width,
+                image_a.height,
+                image_a.color_type,
+                image_a.bit_depth,
+                image_b.width,
+                image_b.height,
+                image_b.color_type,
+                image_b.bit_depth
+            ),
+        ));
+    }
+    let channels = image_a.channels();
+    let mut diff = PixelDiff {
+        width: image_a.width,
+        height: image_a.height,
+        ..PixelDiff::default()
+    };
+    let pixels = image_a
+        .samples
+        .chunks_exact(channels)
+        .zip(image_b.samples.chunks_exact(channels));
+    for (index, (pixel_a, pixel_b)) in pixels.enumerate() {
+        let mut changed = false;
+        for (&sample_a, &sample_b) in pixel_a.iter().zip(pixel_b) {
+            if sample_a == sample_b {
+                continue;
+            }
+            changed = true;
+            diff.samples_changed += 1;
+            if sample_a ^ sample_b == 1 {
+                diff.lsb_only += 1;
+            }
+            diff.max_delta = diff.max_delta.max(sample_a.abs_diff(sample_b));
+        }
+        if changed {
+            diff.pixels_changed += 1;
+            let (x, y) = (index % diff.width, index / diff.width);
+            diff.bounds = Some(match diff.bounds {
+                Some((left, top, right, bottom)) => {
+                    (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                }
+                None => (x, y, x, y),
+            });
+        }
+    }
+    Ok(diff)
+}
+
+/// Formats an offset and size pair for the human output.
+fn location(place: Option<(usize, usize)>) -> String {
+    place.map_or_else(
+        || "-".to_string(),
+        |(offset, size)| format!("offset {}, {} bytes", offset, size),
+    )
+}
+
+/// Returns the bytes of a chunk, from its length field to its CRC, and their offset, or
+/// nothing for a chunk missing from the file.
+fn chunk_bytes(file: &[u8], place: Option<(usize, usize)>) -> (&[u8], u64) {
+    place.map_or((&[], 0), |(offset, size)| {
+        (&file[offset..offset + size + 12], offset as u64)
+    })
+}
+
+/// Prints the differences between two files in color.
+///
+/// # Arguments
+///
+/// * `chunks` - The chunk differences returned by `diff_chunks`.
+/// * `pixels` - The pixel differences returned by `diff_pixels`, if requested.
+/// * `files` - The bytes of both files, to follow each added, removed or modified chunk with a
+///   hexdump of its changes, if requested.
+pub fn print_diff(chunks: &[ChunkDiff], pixels: Option<&PixelDiff>, files: Option<(&[u8], &[u8])>) {
+    let unchanged = chunks
+        .iter()
+        .filter(|diff| diff.change == ChunkChange::Unchanged)
+        .count();
+    for diff in chunks {
+        let name = diff.r#type;
+        match diff.change {
+            ChunkChange::Unchanged => {}
+            ChunkChange::Moved => outln!(
+                "  {} moved: {} -> {}",
+                name,
+                location(diff.a),
+                location(diff.b)
+            ),
+            ChunkChange::Modified => outln!(
+                "{} {} -> {}, {} bytes differ from data byte {}",
+                Style::Yellow.paint(format!("~ {} modified:", name)),
+                location(diff.a),
+                location(diff.b),
+                diff.bytes_changed,
+                diff.first_difference.unwrap_or(0)
+            ),
+            ChunkChange::Added => {
+                let added = Style::Green.paint(format!("+ {} added:", name));
+                outln!("{} {}", added, location(diff.b))
+            }
+            ChunkChange::Removed => {
+                let removed = Style::Red.paint(format!("- {} removed:", name));
+                outln!("{} {}", removed, location(diff.a))
+            }
+        }
+        let shown = !matches!(diff.change, ChunkChange::Unchanged | ChunkChange::Moved);
+        if let Some((a, b)) = files.filter(|_| shown) {
+            let (before, before_offset) = chunk_bytes(a, diff.a);
+            let (after, after_offset) = chunk_bytes(b, diff.b);
+            // A closed stdout, such as a pager quit early, isn't worth a panic.
+            let _ = write_hex_diff(
+                &mut OutputWriter::default(),
+                before,
+                before_offset,
+                after,
+                after_offset,
+                colors_enabled(Stream::Stdout),
+            );
+        }
+    }
+    if unchanged == chunks.len() {
+        outln!("{}", Style::Green.paint("The chunks are identical."));
+    } else {
+        outln!("{} of {} chunks unchanged", unchanged, chunks.len());
+    }
+    let Some(pixels) = pixels else {
+        return;
+    };
+    match pixels.bounds {
+        None => outln!("{}", Style::Green.paint("The pixels are identical.")),
+        Some((left, top, right, bottom)) => {
+            outln!(
+                "{} {} of {} changed in ({}, {})-({}, {}), {} samples, largest delta {}",
+                Style::Yellow.paint("~ pixels:"),
+                pixels.pixels_changed,
+                pixels.width * pixels.height,
+                left,
+                top,
+                right,
+                bottom,
+                pixels.samples_changed,
+                pixels.max_delta
+            );
+            if pixels.lsb_only == pixels.samples_changed {
+                outln!("  only least significant bits changed, as LSB embedding does");
+            } else if pixels.lsb_only > 0 {
+                outln!(
+                    "  {} of the changed samples differ in the least significant bit only",
+                    pixels.lsb_only
+                );
+            }
+        }
+    }
+}
+
+/// Finds the bytes that differ between two windows, trimming their common prefix and suffix.
+///
+/// # Arguments
+///
+/// * `before` - The bytes before the change.
+/// * `after` - The bytes after the change.
+///
+/// # Returns
+///
+/// The range of `before` that was removed and the range of `after` that was inserted in its
+/// place, both empty if the windows are identical.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::diff::changed_span;
+///
+/// assert_eq!(changed_span(b"abcdef", b"abcXYZdef"), (3..3, 3..6));
+/// assert_eq!(changed_span(b"abcdef", b"abXf"), (2..5, 2..3));
+/// assert_eq!(changed_span(b"same", b"same"), (4..4, 4..4));
+/// ```
+pub fn changed_span(before: &[u8], after: &[u8]) -> (Range<usize>, Range<usize>) {
+    let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (prefix..before.len() - suffix, prefix..after.len() - suffix)
+}
+
+/// Writes a before and after hexdump of a window of bytes, with the removed bytes in red and
+/// the inserted ones in green.
+///
+/// Only `HEX_CONTEXT` bytes are shown around the change, and long changes are cut down to their
+/// first and last lines. An empty window is left out, such as the before side of an added
+/// chunk.
+///
+/// # Arguments
+///
+/// * `writer` - Where the dumps are written.
+/// * `before` - The bytes before the change.
+/// * `before_offset` - The offset of `before` in its file.
+/// * `after` - The bytes after the change.
+/// * `after_offset` - The offset of `after` in its file.
+/// * `color` - Whether to paint the dumps with ANSI escapes.
+///
+/// # Returns
+///
+/// An `io::Result` reporting errors of the writer.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::diff::write_hex_diff;
+///
+/// let mut out = Vec::new();
+/// write_hex_diff(&mut out, b"IDAT....IEND", 1000, b"IDATstEg....IEND", 1000, false).unwrap();
+/// let out = String::from_utf8(out).unwrap();
+/// assert!(out.contains("+ after: 4 bytes inserted at offset 1004"));
+/// assert!(out.contains("00001000 | 49 44 41 54 73 74 45 67"));
+/// ```
+pub fn write_hex_diff<W: Write>(
+    writer: &mut W,
+    before: &[u8],
+    before_offset: u64,
+    after: &[u8],
+    after_offset: u64,
+    color: bool,
+) -> io::Result<()> {
+    let (removed, inserted) = changed_span(before, after);
+    if removed.is_empty() && inserted.is_empty() {
+        return writeln!(writer, "The bytes are identical.");
+    }
+    let sides = [
+        (
+            before,
+            before_offset,
+            removed,
+            Style::Red,
+            "- before:",
+            "removed",
+        ),
+        (
+            after,
+            after_offset,
+            inserted,
+            Style::Green,
+            "+ after:",
+            "inserted",
+        ),
+    ];
+    for (data, offset, changed, style, label, verb) in sides {
+        if data.is_empty() {
+            continue;
+        }
+        writeln!(
+            writer,
+            "{} {} bytes {} at offset {}",
+            style.paint_if(color, label),
+            changed.len(),
+            verb,
+            offset + changed.start as u64
+        )?;
+        let start = changed.start.saturating_sub(HEX_CONTEXT);
+        let end = (changed.end + HEX_CONTEXT).min(data.len());
+        let dump = |writer: &mut W, piece: Range<usize>| {
+            HexFormatter::new(offset + piece.start as u64)
+                .color(color)
+                .highlight(
+                    changed.start.saturating_sub(piece.start)..changed.end - piece.start,
+                    style,
+                )
+                .write(writer, &data[piece])
+        };
+        if changed.len() > HEX_ELIDE {
+            dump(writer, start..changed.start + HEX_LINE)?;
+            writeln!(
+                writer,
+                "... {} {} bytes not shown",
+                changed.len() - 2 * HEX_LINE,
+                verb
+            )?;
+            dump(writer, changed.end - HEX_LINE..end)?;
+        } else {
+            dump(writer, start..end)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,568 @@
+use crate::chunk_type::ChunkType;
+use crate::encoding::PayloadEncoding;
+use crate::png::{insert_chunk, read_chunks, remove_chunk_at, ChunkPosition, RawChunk};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+use std::io::{self, ErrorKind, Read, Write};
+
+/// Name of the profile in the `iCCP` chunk.
+pub(crate) const ICC_PROFILE_NAME: &[u8] = b"ICC Profile";
+
+/// Keyword of the `iTXt` chunk holding an XMP packet.
+const XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+/// Start of a baseline JFIF file, so that the XMP thumbnail decodes to something JPEG-like.
+const JPEG_HEAD: &[u8] = &[
+    0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F', 0x00, 0x01, 0x02, 0x01, 0x00, 0x48,
+    0x00, 0x48, 0x00, 0x00,
+];
+
+/// End of a JPEG file.
+const JPEG_TAIL: &[u8] = &[0xFF, 0xD9];
+
+/// Camera make and model of the EXIF template.
+const CAMERA_MAKE: &str = "Canon";
+const CAMERA_MODEL: &str = "Canon EOS R6";
+
+/// Canon model ID of the EOS R6.
+const CANON_MODEL_ID: u32 = 0x8000_0453;
+
+/// Canon maker note tag of the colour data block, which holds the payload.
+const CANON_COLOR_DATA: u16 = 0x4001;
+
+/// EXIF tags used by the maker note template.
+const TAG_MAKE: u16 = 0x010F;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_MAKER_NOTE: u16 = 0x927C;
+
+/// TIFF field types used by the maker note template.
+const ASCII: u16 = 2;
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const UNDEFINED: u16 = 7;
+
+/// A template of standard metadata the payload is disguised as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disguise {
+    /// A display ICC profile in an `iCCP` chunk, the payload being its tone curve.
+    Icc,
+    /// An XMP packet in an `iTXt` chunk, the payload being its base64 JPEG thumbnail.
+    Xmp,
+    /// EXIF data in an `eXIf` chunk, the payload being the colour data of a Canon maker note.
+    MakerNote,
+}
+
+impl Disguise {
+    /// Parses a template name: icc, xmp or makernote.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::disguise::Disguise;
+    ///
+    /// assert_eq!(Disguise::new("XMP").unwrap(), Disguise::Xmp);
+    /// assert!(Disguise::new("gps").is_err());
+    /// ```
+    pub fn new(name: &str) -> io::Result<Disguise> {
+        match name.to_lowercase().as_str() {
+            "icc" => Ok(Disguise::Icc),
+            "xmp" => Ok(Disguise::Xmp),
+            "makernote" => Ok(Disguise::MakerNote),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown disguise {:?}, use icc, xmp or makernote", name),
+            )),
+        }
+    }
+
+    /// Returns the name of the template, as given on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Disguise::Icc => "icc",
+            Disguise::Xmp => "xmp",
+            Disguise::MakerNote => "makernote",
+        }
+    }
+
+    /// Returns what the payload looks like, for messages.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Disguise::Icc => "an ICC profile",
+            Disguise::Xmp => "an XMP thumbnail",
+            Disguise::MakerNote => "an EXIF maker note",
+        }
+    }
+
+    /// Returns the type of the PNG chunk holding the template.
+    pub fn chunk_type(&self) -> [u8; 4] {
+        match self {
+            Disguise::Icc => *b"iCCP",
+            Disguise::Xmp => *b"iTXt",
+            Disguise::MakerNote => *b"eXIf",
+        }
+    }
+}
+
+/// Draws a plausible date and time from the payload: a year from 2019 to 2024, a day of the
+/// month up to the 28th. The same payload always gets the same date, so re-embedding it gives
+/// the same file.
+fn stamp(payload: &[u8]) -> [u16; 6] {
+    let digest = Sha256::digest(payload);
+    let pick =
+        |i: usize, bound: u16| u16::from_be_bytes([digest[2 * i], digest[2 * i + 1]]) % bound;
+    [
+        2019 + pick(0, 6),
+        1 + pick(1, 12),
+        1 + pick(2, 28),
+        pick(3, 24),
+        pick(4, 60),
+        pick(5, 60),
+    ]
+}
+
+/// Tells whether a chunk is an `iTXt` chunk holding an XMP packet.
+fn is_xmp(chunk: &RawChunk) -> bool {
+    &chunk.r#type == b"iTXt"
+        && chunk.data.starts_with(XMP_KEYWORD)
+        && chunk.data.get(XMP_KEYWORD.len()) == Some(&0)
+}
+
+/// Builds an `InvalidInput` error for carriers whose metadata the template would contradict.
+fn conflict(disguise: Disguise, chunk: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "The carrier already has {}, pick another --disguise than {}",
+            chunk,
+            disguise.name()
+        ),
+    )
+}
+
+/// Builds the ICC tag element of an `XYZ ` type.
+fn xyz(values: [f64; 3]) -> Vec<u8> {
+    let mut element = b"XYZ \0\0\0\0".to_vec();
+    for value in values {
+        element.extend_from_slice(&((value * 65536.0).round() as i32).to_be_bytes());
+    }
+    element
+}
+
+/// Builds the ICC tag element of an `mluc` type holding one English string.
+fn mluc(text: &str) -> Vec<u8> {
+    let mut element = b"mluc\0\0\0\0".to_vec();
+    element.extend_from_slice(&1u32.to_be_bytes());
+    element.extend_from_slice(&12u32.to_be_bytes());
+    element.extend_from_slice(b"enUS");
+    let utf16: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    element.extend_from_slice(&(utf16.len() as u32).to_be_bytes());
+    element.extend_from_slice(&28u32.to_be_bytes());
+    element.extend_from_slice(&utf16);
+    element
+}
+
+/// Builds a version 4 display profile with sRGB primaries, whose three tone curves share one
+/// `curv` element made of the payload.
+///
+/// The curve holds half as many entries as the payload has bytes, rounded up, but its tag
+/// size is exact, so that odd lengths come back whole.
+pub(crate) fn icc_profile(payload: &[u8]) -> Vec<u8> {
+    let mut curve = b"curv\0\0\0\0".to_vec();
+    curve.extend_from_slice(&(payload.len().div_ceil(2) as u32).to_be_bytes());
+    curve.extend_from_slice(payload);
+    let tags: [(&[u8; 4], Vec<u8>); 6] = [
+        (b"desc", mluc("Display")),
+        (b"cprt", mluc("No copyright, use freely")),
+        (b"wtpt", xyz([0.9642, 1.0, 0.8249])),
+        (b"rXYZ", xyz([0.4361, 0.2225, 0.0139])),
+        (b"gXYZ", xyz([0.3851, 0.7169, 0.0971])),
+        (b"bXYZ", xyz([0.1431, 0.0606, 0.7141])),
+    ];
+    // Each element starts on a 4-byte boundary after the tag table.
+    let table_end = 128 + 4 + (tags.len() + 3) * 12;
+    let mut elements = Vec::new();
+    let mut entries = Vec::new();
+    for (signature, element) in &tags {
+        entries.push((**signature, table_end + elements.len(), element.len()));
+        elements.extend_from_slice(element);
+        elements.resize(elements.len().next_multiple_of(4), 0);
+    }
+    let curve_offset = table_end + elements.len();
+    for signature in [b"rTRC", b"gTRC", b"bTRC"] {
+        entries.push((*signature, curve_offset, curve.len()));
+    }
+    elements.extend_from_slice(&curve);
+    elements.resize(elements.len().next_multiple_of(4), 0);
+
+    let mut profile = Vec::with_capacity(table_end + elements.len());
+    profile.extend_from_slice(&((table_end + elements.len()) as u32).to_be_bytes());
+    profile.extend_from_slice(b"lcms");
+    profile.extend_from_slice(&0x0430_0000u32.to_be_bytes());
+    profile.extend_from_slice(b"mntrRGB XYZ ");
+    for field in stamp(payload) {
+        profile.extend_from_slice(&field.to_be_bytes());
+    }
+    profile.extend_from_slice(b"acspAPPL");
+    profile.resize(68, 0);
+    profile.extend_from_slice(&xyz([0.9642, 1.0, 0.8249])[8..]);
+    profile.extend_from_slice(b"lcms");
+    profile.resize(128, 0);
+    profile.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+    for (signature, offset, size) in entries {
+        profile.extend_from_slice(&signature);
+        profile.extend_from_slice(&(offset as u32).to_be_bytes());
+        profile.extend_from_slice(&(size as u32).to_be_bytes());
+    }
+    profile.extend_from_slice(&elements);
+    profile
+}
+
+/// Reads the payload back from the tone curve of a profile built by `icc_profile`.
+fn icc_payload(profile: &[u8]) -> Option<Vec<u8>> {
+    let u32_at = |at: usize| -> Option<usize> {
+        Some(u32::from_be_bytes(profile.get(at..at + 4)?.try_into().ok()?) as usize)
+    };
+    if profile.get(36..40) != Some(b"acsp") {
+        return None;
+    }
+    let count = u32_at(128)?;
+    let (offset, size) = (0..count.min(64)).find_map(|i| {
+        let entry = 132 + i * 12;
+        (profile.get(entry..entry + 4)? == b"rTRC")
+            .then(|| Some((u32_at(entry + 4)?, u32_at(entry + 8)?)))?
+    })?;
+    let element = profile.get(offset..offset.checked_add(size)?)?;
+    if !element.starts_with(b"curv") || element.len() < 12 {
+        return None;
+    }
+    Some(element[12..].to_vec())
+}
+
+/// Builds an XMP packet whose JPEG thumbnail is the payload, encoded in base64 and wrapped
+/// every 76 characters the way Adobe applications do.
+fn xmp_packet(payload: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let [year, month, day, hour, minute, second] = stamp(payload);
+    let date = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year, month, day, hour, minute, second
+    );
+    let mut jpeg = JPEG_HEAD.to_vec();
+    jpeg.extend_from_slice(payload);
+    jpeg.extend_from_slice(JPEG_TAIL);
+    let base64 = PayloadEncoding::Base64.encode(&jpeg);
+    let image = base64
+        .chunks(76)
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect::<Vec<_>>()
+        .join("&#xA;");
+    // Thumbnails are at most 256 pixels on their longer side.
+    let scale = 256.0 / width.max(height).max(1) as f64;
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\" x:xmptk=\"Adobe XMP Core 7.1-c000 79.9ccc4de, 2022/03/14-11:26:06        \">\n\
+         \x20<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n\
+         \x20 <rdf:Description rdf:about=\"\"\n\
+         \x20   xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"\n\
+         \x20   xmlns:xmpGImg=\"http://ns.adobe.com/xap/1.0/g/img/\"\n\
+         \x20  xmp:CreatorTool=\"Adobe Photoshop 23.3 (Windows)\"\n\
+         \x20  xmp:CreateDate=\"{date}\"\n\
+         \x20  xmp:ModifyDate=\"{date}\"\n\
+         \x20  xmp:MetadataDate=\"{date}\">\n\
+         \x20  <xmp:Thumbnails>\n\
+         \x20   <rdf:Alt>\n\
+         \x20    <rdf:li rdf:parseType=\"Resource\">\n\
+         \x20     <xmpGImg:width>{}</xmpGImg:width>\n\
+         \x20     <xmpGImg:height>{}</xmpGImg:height>\n\
+         \x20     <xmpGImg:format>JPEG</xmpGImg:format>\n\
+         \x20     <xmpGImg:image>{image}</xmpGImg:image>\n\
+         \x20    </rdf:li>\n\
+         \x20   </rdf:Alt>\n\
+         \x20  </xmp:Thumbnails>\n\
+         \x20 </rdf:Description>\n\
+         \x20</rdf:RDF>\n\
+         </x:xmpmeta>\n\
+         <?xpacket end=\"w\"?>",
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    )
+    .into_bytes()
+}
+
+/// Reads the payload back from the thumbnail of a packet built by `xmp_packet`.
+fn xmp_payload(packet: &[u8]) -> Option<Vec<u8>> {
+    let text = std::str::from_utf8(packet).ok()?;
+    let start = text.find("<xmpGImg:image>")? + "<xmpGImg:image>".len();
+    let end = start + text[start..].find("</xmpGImg:image>")?;
+    let jpeg = PayloadEncoding::Base64
+        .decode(&text[start..end].replace("&#xA;", ""))
+        .ok()?;
+    let payload = jpeg.strip_prefix(JPEG_HEAD)?.strip_suffix(JPEG_TAIL)?;
+    Some(payload.to_vec())
+}
+
+/// An entry of a little-endian TIFF IFD being built: tag, type, count and value bytes.
+type Field = (u16, u16, u32, Vec<u8>);
+
+/// Returns the size of an IFD and of the values that don't fit in its entries.
+fn ifd_len(fields: &[Field]) -> usize {
+    let values: usize = fields
+        .iter()
+        .filter(|(_, _, _, value)| value.len() > 4)
+        .map(|(_, _, _, value)| value.len().next_multiple_of(2))
+        .sum();
+    2 + fields.len() * 12 + 4 + values
+}
+
+/// Lays out a little-endian IFD at the end of the TIFF data, followed by the values that don't
+/// fit in its entries.
+fn write_ifd(out: &mut Vec<u8>, fields: &[Field]) {
+    let mut values_at = out.len() + 2 + fields.len() * 12 + 4;
+    let mut values = Vec::new();
+    out.extend_from_slice(&(fields.len() as u16).to_le_bytes());
+    for (tag, field_type, count, value) in fields {
+        out.extend_from_slice(&tag.to_le_bytes());
+        out.extend_from_slice(&field_type.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        if value.len() <= 4 {
+            let mut inline = [0; 4];
+            inline[..value.len()].copy_from_slice(value);
+            out.extend_from_slice(&inline);
+        } else {
+            out.extend_from_slice(&(values_at as u32).to_le_bytes());
+            values.extend_from_slice(value);
+            values.resize(values.len().next_multiple_of(2), 0);
+            values_at += value.len().next_multiple_of(2);
+        }
+    }
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&values);
+}
+
+/// Builds an ASCII field.
+fn ascii(tag: u16, text: &str) -> Field {
+    let mut value = text.as_bytes().to_vec();
+    value.push(0);
+    (tag, ASCII, value.len() as u32, value)
+}
+
+/// Builds little-endian EXIF data of a Canon camera, whose maker note holds the payload as
+/// its colour data block.
+fn exif_data(payload: &[u8]) -> Vec<u8> {
+    let [year, month, day, hour, minute, second] = stamp(payload);
+    let date = format!(
+        "{:04}:{:02}:{:02} {:02}:{:02}:{:02}",
+        year, month, day, hour, minute, second
+    );
+    let maker_note = |base: usize| {
+        let fields: [Field; 3] = [
+            ascii(0x0006, CAMERA_MODEL),
+            (0x0010, LONG, 1, CANON_MODEL_ID.to_le_bytes().to_vec()),
+            (
+                CANON_COLOR_DATA,
+                UNDEFINED,
+                payload.len() as u32,
+                payload.to_vec(),
+            ),
+        ];
+        let mut out = vec![0; base];
+        write_ifd(&mut out, &fields);
+        out.split_off(base)
+    };
+    let ifd0 = |exif_at: usize| -> [Field; 4] {
+        [
+            ascii(TAG_MAKE, CAMERA_MAKE),
+            ascii(TAG_MODEL, CAMERA_MODEL),
+            (0x0112, SHORT, 1, 1u16.to_le_bytes().to_vec()),
+            (
+                TAG_EXIF_IFD,
+                LONG,
+                1,
+                (exif_at as u32).to_le_bytes().to_vec(),
+            ),
+        ]
+    };
+    let exif_at = 8 + ifd_len(&ifd0(0));
+    let exif = |note_at: usize, note_len: usize| -> [Field; 4] {
+        [
+            (0x9000, UNDEFINED, 4, b"0232".to_vec()),
+            ascii(0x9003, &date),
+            // The maker note is written after the IFD, so only its place is reserved here.
+            (
+                TAG_MAKER_NOTE,
+                UNDEFINED,
+                note_len as u32,
+                (note_at as u32).to_le_bytes().to_vec(),
+            ),
+            (0xA001, SHORT, 1, 1u16.to_le_bytes().to_vec()),
+        ]
+    };
+    let note_at = exif_at + ifd_len(&exif(0, 0));
+    let note = maker_note(note_at);
+
+    let mut out = b"II*\0".to_vec();
+    out.extend_from_slice(&8u32.to_le_bytes());
+    write_ifd(&mut out, &ifd0(exif_at));
+    write_ifd(&mut out, &exif(note_at, note.len()));
+    out.extend_from_slice(&note);
+    out
+}
+
+/// Finds an entry of a little-endian IFD, returning its count and value or offset field.
+fn find_entry(data: &[u8], ifd: usize, tag: u16) -> Option<(usize, usize)> {
+    let u16_at = |at: usize| Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?));
+    let u32_at =
+        |at: usize| Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?) as usize);
+    let count = u16_at(ifd)? as usize;
+    (0..count).find_map(|i| {
+        let entry = ifd + 2 + i * 12;
+        (u16_at(entry)? == tag).then(|| Some((u32_at(entry + 4)?, u32_at(entry + 8)?)))?
+    })
+}
+
+/// Reads the payload back from the maker note of EXIF data built by `exif_data`.
+fn exif_payload(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(b"II*\0") {
+        return None;
+    }
+    let (_, exif_at) = find_entry(data, 8, TAG_EXIF_IFD)?;
+    let (_, note_at) = find_entry(data, exif_at, TAG_MAKER_NOTE)?;
+    let (len, at) = find_entry(data, note_at, CANON_COLOR_DATA)?;
+    Some(data.get(at..at.checked_add(len)?)?.to_vec())
+}
+
+/// Builds the chunk data of a template holding a payload.
+fn chunk_data(disguise: Disguise, payload: &[u8], width: u32, height: u32) -> io::Result<Vec<u8>> {
+    Ok(match disguise {
+        Disguise::Icc => {
+            let mut data = ICC_PROFILE_NAME.to_vec();
+            data.extend_from_slice(&[0, 0]);
+            let mut encoder = ZlibEncoder::new(data, Compression::default());
+            encoder.write_all(&icc_profile(payload))?;
+            encoder.finish()?
+        }
+        Disguise::Xmp => {
+            let mut data = XMP_KEYWORD.to_vec();
+            // No compression, no language tag, no translated keyword.
+            data.extend_from_slice(&[0, 0, 0, 0, 0]);
+            data.extend_from_slice(&xmp_packet(payload, width, height));
+            data
+        }
+        Disguise::MakerNote => exif_data(payload),
+    })
+}
+
+/// Reads the payload back from a chunk, if it is a template built by `chunk_data`.
+fn chunk_payload(chunk: &RawChunk) -> Option<(Disguise, Vec<u8>)> {
+    match chunk.r#type {
+        ChunkType::Iccp => {
+            let name_end = chunk.data.iter().position(|&byte| byte == 0)?;
+            let mut profile = Vec::new();
+            ZlibDecoder::new(chunk.data.get(name_end + 2..)?)
+                .read_to_end(&mut profile)
+                .ok()?;
+            Some((Disguise::Icc, icc_payload(&profile)?))
+        }
+        ChunkType::Itxt if is_xmp(chunk) => {
+            let packet = chunk.data.get(XMP_KEYWORD.len() + 5..)?;
+            Some((Disguise::Xmp, xmp_payload(packet)?))
+        }
+        ChunkType::Exif => Some((Disguise::MakerNote, exif_payload(&chunk.data)?)),
+        _ => None,
+    }
+}
+
+/// Hides a payload in a PNG file disguised as standard metadata, so that a cursory look at the
+/// file's metadata finds nothing unusual.
+///
+/// The template goes right after `IHDR`, where encoders put colour profiles and EXIF data:
+///
+/// * `Icc` - An `iCCP` chunk holding a display profile with sRGB primaries, whose red, green
+///   and blue tone curves share one table: the payload.
+/// * `Xmp` - An `iTXt` chunk holding an XMP packet from an image editor, whose JPEG thumbnail
+///   is the payload, in base64.
+/// * `MakerNote` - An `eXIf` chunk holding the EXIF data of a camera, whose maker note has the
+///   payload as its colour data block.
+///
+/// The dates in the templates are drawn from the payload, so that files don't all share one.
+/// This hides the payload from people and tools that list metadata, not from steganalysis: the
+/// "curve" or "thumbnail" doesn't decode to anything sensible.
+///
+/// # Arguments
+///
+/// * `carrier` - The bytes of the PNG file.
+/// * `payload` - The bytes to hide.
+/// * `disguise` - The template to use.
+///
+/// # Returns
+///
+/// A `Result` containing the stego file, or an `InvalidInput` error if the carrier already has
+/// the metadata of the template, or an `sRGB` chunk for `Icc`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::disguise::{embed_disguised, extract_disguised, Disguise};
+/// use stegano::png::{encode_grayscale, read_chunks};
+///
+/// let carrier = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// for disguise in [Disguise::Icc, Disguise::Xmp, Disguise::MakerNote] {
+///     let stego = embed_disguised(&carrier, b"odd-length secret", disguise).unwrap();
+///     assert_eq!(read_chunks(&stego).unwrap()[1].r#type, disguise.chunk_type());
+///     let (payload, restored, found) = extract_disguised(&stego).unwrap();
+///     assert_eq!(payload, b"odd-length secret");
+///     assert_eq!((restored, found), (carrier.clone(), disguise));
+///     // Only one profile, packet or EXIF block per file.
+///     assert!(embed_disguised(&stego, b"more", disguise).is_err());
+/// }
+/// ```
+pub fn embed_disguised(carrier: &[u8], payload: &[u8], disguise: Disguise) -> io::Result<Vec<u8>> {
+    let chunks = read_chunks(carrier)?;
+    let ihdr = chunks
+        .first()
+        .filter(|chunk| &chunk.r#type == b"IHDR" && chunk.data.len() == 13)
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Missing IHDR chunk"))?;
+    let width = u32::from_be_bytes(ihdr.data[0..4].try_into().unwrap());
+    let height = u32::from_be_bytes(ihdr.data[4..8].try_into().unwrap());
+    for chunk in &chunks {
+        match (disguise, chunk.r#type) {
+            (Disguise::Icc, ChunkType::Iccp) => return Err(conflict(disguise, "an ICC profile")),
+            (Disguise::Icc, ChunkType::Srgb) => return Err(conflict(disguise, "an sRGB chunk")),
+            (Disguise::Xmp, _) if is_xmp(chunk) => return Err(conflict(disguise, "an XMP packet")),
+            (Disguise::MakerNote, ChunkType::Exif) => return Err(conflict(disguise, "EXIF data")),
+            _ => {}
+        }
+    }
+    let data = chunk_data(disguise, payload, width, height)?;
+    let offset = ChunkPosition::After(*b"IHDR").offset(&chunks)?;
+    Ok(insert_chunk(carrier, &disguise.chunk_type(), &data, Some(offset))?.0)
+}
+
+/// Finds a payload hidden by `embed_disguised`, whatever its template, and removes it.
+///
+/// # Arguments
+///
+/// * `stego` - The bytes of the stego file.
+///
+/// # Returns
+///
+/// A `Result` containing the payload, the carrier without the template chunk and the template
+/// found, or a `NotFound` error if no chunk holds one.
+pub fn extract_disguised(stego: &[u8]) -> io::Result<(Vec<u8>, Vec<u8>, Disguise)> {
+    let (chunk, disguise, payload) = read_chunks(stego)?
+        .into_iter()
+        .find_map(|chunk| {
+            let (disguise, payload) = chunk_payload(&chunk)?;
+            Some((chunk, disguise, payload))
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                "No ICC profile, XMP thumbnail or EXIF maker note holds a payload",
+            )
+        })?;
+    let (carrier, _) = remove_chunk_at(stego, &chunk.r#type.bytes(), chunk.offset)?;
+    Ok((payload, carrier, disguise))
+}
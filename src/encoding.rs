@@ -0,0 +1,166 @@
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+/// The standard base64 alphabet, by value.
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// How binary payloads are written as text on the command line and on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadEncoding {
+    /// The bytes as they are.
+    Raw,
+    /// Standard base64 with padding.
+    Base64,
+    /// Lowercase hexadecimal, two digits per byte.
+    Hex,
+}
+
+impl fmt::Display for PayloadEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PayloadEncoding::Raw => "raw",
+            PayloadEncoding::Base64 => "base64",
+            PayloadEncoding::Hex => "hex",
+        })
+    }
+}
+
+impl PayloadEncoding {
+    /// Encodes bytes as text, or leaves them as they are for `Raw`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::encoding::PayloadEncoding;
+    ///
+    /// assert_eq!(PayloadEncoding::Base64.encode(b"\x00\xFFhi"), b"AP9oaQ==");
+    /// assert_eq!(PayloadEncoding::Hex.encode(b"\x00\xFFhi"), b"00ff6869");
+    /// assert_eq!(PayloadEncoding::Raw.encode(b"\x00\xFFhi"), b"\x00\xFFhi");
+    /// ```
+    pub fn encode(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            PayloadEncoding::Raw => data.to_vec(),
+            PayloadEncoding::Hex => data
+                .iter()
+                .flat_map(|byte| format!("{:02x}", byte).into_bytes())
+                .collect(),
+            PayloadEncoding::Base64 => {
+                let mut text = Vec::with_capacity(data.len().div_ceil(3) * 4);
+                for group in data.chunks(3) {
+                    let bits = group.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+                        bits | ((byte as u32) << (16 - 8 * i))
+                    });
+                    for i in 0..4 {
+                        text.push(if i <= group.len() {
+                            BASE64[((bits >> (18 - 6 * i)) & 0x3F) as usize]
+                        } else {
+                            b'='
+                        });
+                    }
+                }
+                text
+            }
+        }
+    }
+
+    /// Decodes text back to bytes, or leaves it as it is for `Raw`.
+    ///
+    /// Whitespace is skipped, so that wrapped base64 and spaced out hex pass through the
+    /// shell unchanged. Base64 padding is optional, and hex digits may be of either case.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes, or an `InvalidInput` error naming the encoding if the
+    /// text isn't valid in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::encoding::PayloadEncoding;
+    ///
+    /// assert_eq!(PayloadEncoding::Base64.decode("AP9o\naQ").unwrap(), b"\x00\xFFhi");
+    /// assert_eq!(PayloadEncoding::Hex.decode("00 FF 68 69").unwrap(), b"\x00\xFFhi");
+    /// assert!(PayloadEncoding::Hex.decode("0g").is_err());
+    /// assert!(PayloadEncoding::Hex.decode("+f").is_err());
+    /// assert!(PayloadEncoding::Base64.decode("A").is_err());
+    /// ```
+    pub fn decode(self, text: &str) -> io::Result<Vec<u8>> {
+        if self == PayloadEncoding::Raw {
+            return Ok(text.as_bytes().to_vec());
+        }
+        let digits: Vec<u8> = text
+            .bytes()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .collect();
+        let invalid = || {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("The payload isn't valid {}", self),
+            )
+        };
+        match self {
+            PayloadEncoding::Hex => {
+                if !digits.len().is_multiple_of(2) {
+                    return Err(invalid());
+                }
+                if !digits.iter().all(u8::is_ascii_hexdigit) {
+                    return Err(invalid());
+                }
+                Ok(digits
+                    .chunks(2)
+                    .map(|pair| (hex_value(pair[0]) << 4) | hex_value(pair[1]))
+                    .collect())
+            }
+            _ => {
+                let padding = digits.iter().rev().take_while(|&&c| c == b'=').count();
+                if padding > 2 || (padding > 0 && !digits.len().is_multiple_of(4)) {
+                    return Err(invalid());
+                }
+                let digits = &digits[..digits.len() - padding];
+                if digits.len() % 4 == 1 {
+                    return Err(invalid());
+                }
+                let mut data = Vec::with_capacity(digits.len() * 3 / 4);
+                for group in digits.chunks(4) {
+                    let mut bits = 0u32;
+                    for (i, &c) in group.iter().enumerate() {
+                        let value = BASE64.iter().position(|&d| d == c).ok_or_else(invalid)?;
+                        bits |= (value as u32) << (18 - 6 * i);
+                    }
+                    data.extend_from_slice(&bits.to_be_bytes()[1..group.len()]);
+                }
+                Ok(data)
+            }
+        }
+    }
+}
+
+/// Returns the value of a hexadecimal digit.
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        _ => digit.to_ascii_lowercase() - b'a' + 10,
+    }
+}
+
+/// Parses the value of `--payload-encoding` and `--output-encoding`: raw, base64 or hex.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::encoding::{parse_payload_encoding, PayloadEncoding};
+///
+/// assert_eq!(parse_payload_encoding("Base64").unwrap(), PayloadEncoding::Base64);
+/// assert!(parse_payload_encoding("base32").is_err());
+/// ```
+pub fn parse_payload_encoding(value: &str) -> Result<PayloadEncoding, String> {
+    match value.to_lowercase().as_str() {
+        "raw" => Ok(PayloadEncoding::Raw),
+        "base64" => Ok(PayloadEncoding::Base64),
+        "hex" => Ok(PayloadEncoding::Hex),
+        _ => Err(format!(
+            "invalid encoding '{}', expected raw, base64 or hex",
+            value
+        )),
+    }
+}
@@ -0,0 +1,384 @@
+#[cfg(any(feature = "age", feature = "pgp"))]
+use crate::error::SteganoError;
+use std::io::{self, ErrorKind, Read, Write};
+
+/// The line opening every age file, which tells age payloads from `PayloadHeader` ones.
+pub const AGE_MAGIC: &[u8; 22] = b"age-encryption.org/v1\n";
+
+/// Tells whether embedded bytes, after any hint, are an age file.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::envelope::is_age;
+///
+/// assert!(is_age(b"age-encryption.org/v1\n-> scrypt ..."));
+/// assert!(!is_age(b"STpl"));
+/// ```
+pub fn is_age(data: &[u8]) -> bool {
+    data.starts_with(AGE_MAGIC)
+}
+
+/// Tells whether `--algo` names the age envelope.
+pub fn is_age_algorithm(algorithm: &str) -> bool {
+    algorithm.eq_ignore_ascii_case("age")
+}
+
+/// Tells whether `--algo` names the OpenPGP envelope.
+pub fn is_pgp_algorithm(algorithm: &str) -> bool {
+    matches!(algorithm.to_lowercase().as_str(), "pgp" | "openpgp")
+}
+
+/// Tells whether embedded bytes, after any hint, open like a binary OpenPGP message encrypted
+/// to public keys: a version 3 public-key encrypted session key packet, for RSA, Elgamal, ECDH,
+/// X25519 or X448 keys.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::envelope::is_pgp;
+///
+/// let mut packet = vec![0xC1, 0x5E, 3];
+/// packet.extend_from_slice(&[0xAB; 8]);
+/// packet.push(18);
+/// assert!(is_pgp(&packet));
+/// assert!(!is_pgp(b"STpl"));
+/// ```
+pub fn is_pgp(data: &[u8]) -> bool {
+    // Packet tag 1, in the current format or the legacy one with a one or two byte length.
+    let body = match data {
+        [0xC1, len, body @ ..] if *len < 192 => body,
+        [0xC1, len, _, body @ ..] if (192..224).contains(len) => body,
+        [0x84, _, body @ ..] => body,
+        [0x85, _, _, body @ ..] => body,
+        _ => return false,
+    };
+    matches!(
+        body,
+        [3, _, _, _, _, _, _, _, _, 1 | 2 | 16 | 18 | 25 | 26, ..]
+    )
+}
+
+/// Encrypts a stream into an age file, for `--algo age`.
+///
+/// The file is a standard age v1 file, without a `PayloadHeader` in front of it: once
+/// extracted, `age -d` decrypts it as well as stegano does. It is encrypted to the X25519
+/// recipients given, `age1...` public keys, or with the passphrase when there are none, which
+/// age stretches with scrypt.
+///
+/// # Arguments
+///
+/// * `recipients` - The `age1...` public keys to encrypt to, if any.
+/// * `passphrase` - The passphrase used when there are no recipients.
+/// * `reader` - The plaintext.
+/// * `writer` - Where the age file is written.
+///
+/// # Returns
+///
+/// A `Result` containing the number of bytes written, an `InvalidInput` error for a malformed
+/// recipient, or an `Unsupported` error without the `age` feature.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use stegano::envelope::{age_decrypt, age_encrypt, is_age};
+///
+/// let mut encrypted = Vec::new();
+/// let result = age_encrypt(&[], "passphrase", &b"hello"[..], &mut encrypted);
+/// if cfg!(feature = "age") {
+///     assert_eq!(result.unwrap(), encrypted.len() as u64);
+///     assert!(is_age(&encrypted));
+///     assert_eq!(age_decrypt(&encrypted, &[], "passphrase").unwrap(), b"hello");
+///     assert!(age_decrypt(&encrypted, &[], "wrong").is_err());
+/// } else {
+///     assert_eq!(result.unwrap_err().kind(), ErrorKind::Unsupported);
+/// }
+/// ```
+pub fn age_encrypt<R: Read, W: Write>(
+    recipients: &[String],
+    passphrase: &str,
+    reader: R,
+    writer: W,
+) -> io::Result<u64> {
+    #[cfg(feature = "age")]
+    {
+        use age::secrecy::SecretString;
+        let encryptor = if recipients.is_empty() {
+            age::Encryptor::with_user_passphrase(SecretString::from(passphrase.to_string()))
+        } else {
+            let recipients = recipients
+                .iter()
+                .map(|recipient| {
+                    recipient.parse::<age::x25519::Recipient>().map_err(|err| {
+                        SteganoError::Usage(format!(
+                            "Invalid age recipient {:?}: {}, expected an age1... public key",
+                            recipient, err
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            age::Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))
+                .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err.to_string()))?
+        };
+        let mut counted = Counted {
+            inner: writer,
+            written: 0,
+        };
+        let mut stream = encryptor.wrap_output(&mut counted)?;
+        io::copy(&mut { reader }, &mut stream)?;
+        stream.finish()?;
+        Ok(counted.written)
+    }
+    #[cfg(not(feature = "age"))]
+    {
+        let _ = (recipients, passphrase, reader, writer);
+        Err(unsupported("age"))
+    }
+}
+
+/// Decrypts an age file embedded with `--algo age`.
+///
+/// Files encrypted with a passphrase are opened with `passphrase`, those encrypted to
+/// recipients with the identity files given, as written by `age-keygen`.
+///
+/// # Arguments
+///
+/// * `data` - The age file.
+/// * `identities` - The paths of the identity files, for files encrypted to recipients.
+/// * `passphrase` - The passphrase, for files encrypted with one.
+///
+/// # Returns
+///
+/// A `Result` containing the plaintext, a `WrongKey` error if neither the passphrase nor the
+/// identities open the file, an `InvalidData` error if it is damaged, or an `Unsupported`
+/// error without the `age` feature.
+pub fn age_decrypt(data: &[u8], identities: &[String], passphrase: &str) -> io::Result<Vec<u8>> {
+    #[cfg(feature = "age")]
+    {
+        use age::secrecy::SecretString;
+        use age::DecryptError;
+        let decryptor = age::Decryptor::new_buffered(data).map_err(|err| {
+            SteganoError::Format(format!("The payload isn't a valid age file: {}", err))
+        })?;
+        let mut keys: Vec<Box<dyn age::Identity>> = Vec::new();
+        if decryptor.is_scrypt() {
+            let passphrase = SecretString::from(passphrase.to_string());
+            keys.push(Box::new(age::scrypt::Identity::new(passphrase)));
+        } else if identities.is_empty() {
+            return Err(SteganoError::WrongKey(
+                "The payload is an age file encrypted to recipients, pass the identity file \
+                 of one of them with --identity"
+                    .into(),
+            )
+            .into());
+        } else {
+            for path in identities {
+                let file = age::IdentityFile::from_file(path.clone())?;
+                keys.extend(file.into_identities().map_err(|err| {
+                    SteganoError::Usage(format!("Invalid age identity file {}: {}", path, err))
+                })?);
+            }
+        }
+        let mut reader = decryptor
+            .decrypt(keys.iter().map(|key| key.as_ref()))
+            .map_err(|err| -> io::Error {
+                match err {
+                    DecryptError::DecryptionFailed | DecryptError::NoMatchingKeys => {
+                        SteganoError::WrongKey(format!(
+                            "Incorrect key or identity: {}",
+                            err.to_string().trim_end_matches('.')
+                        ))
+                        .into()
+                    }
+                    err => SteganoError::Format(format!("The age file is damaged: {}", err)).into(),
+                }
+            })?;
+        let mut plaintext = Vec::new();
+        reader.read_to_end(&mut plaintext).map_err(|err| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("The age file is damaged: {}", err),
+            )
+        })?;
+        Ok(plaintext)
+    }
+    #[cfg(not(feature = "age"))]
+    {
+        let _ = (data, identities, passphrase);
+        Err(unsupported("age"))
+    }
+}
+
+/// Encrypts a stream into a binary OpenPGP message, for `--algo pgp`.
+///
+/// The message holds the payload as a literal data packet, encrypted with AES-256 in a SEIPD
+/// v1 packet, whose session key is encrypted to the encryption subkey of each certificate, or
+/// to its primary key if it has none. `gpg --decrypt` opens it once extracted.
+///
+/// # Arguments
+///
+/// * `recipients` - The paths of the OpenPGP certificates to encrypt to, armored or binary.
+/// * `reader` - The plaintext.
+/// * `writer` - Where the message is written.
+///
+/// # Returns
+///
+/// A `Result` containing the number of bytes written, an `InvalidInput` error without
+/// recipients or for a certificate that can't encrypt, or an `Unsupported` error without the
+/// `pgp` feature.
+pub fn pgp_encrypt<R: Read, W: Write>(
+    recipients: &[String],
+    reader: R,
+    writer: W,
+) -> io::Result<u64> {
+    #[cfg(feature = "pgp")]
+    {
+        use pgp::composed::{Deserializable, MessageBuilder, SignedPublicKey};
+        use pgp::crypto::sym::SymmetricKeyAlgorithm;
+        use pgp::types::KeyDetails;
+        if recipients.is_empty() {
+            return Err(SteganoError::Usage(
+                "OpenPGP payloads are encrypted to certificates, give one with --recipient".into(),
+            )
+            .into());
+        }
+        let mut certificates = Vec::with_capacity(recipients.len());
+        for path in recipients {
+            let (certificate, _) = SignedPublicKey::from_reader_single(std::fs::File::open(path)?)
+                .map_err(|err| pgp_error(format!("Invalid OpenPGP certificate {}", path), err))?;
+            certificates.push(certificate);
+        }
+        let mut rng = rand::thread_rng();
+        let mut builder = MessageBuilder::from_reader("", reader)
+            .seipd_v1(&mut rng, SymmetricKeyAlgorithm::AES256);
+        for (path, certificate) in recipients.iter().zip(&certificates) {
+            let subkey = certificate.public_subkeys.iter().find(|subkey| {
+                subkey.key.algorithm().can_encrypt()
+                    && subkey.signatures.iter().any(|signature| {
+                        let flags = signature.key_flags();
+                        flags.encrypt_comms() || flags.encrypt_storage()
+                    })
+            });
+            let encrypted = match subkey {
+                Some(subkey) => builder.encrypt_to_key(&mut rng, subkey),
+                None if certificate.primary_key.algorithm().can_encrypt() => {
+                    builder.encrypt_to_key(&mut rng, &certificate.primary_key)
+                }
+                None => {
+                    return Err(SteganoError::Usage(format!(
+                        "The OpenPGP certificate {} has no key that can encrypt",
+                        path
+                    ))
+                    .into())
+                }
+            };
+            encrypted.map_err(|err| pgp_error(format!("Can't encrypt to {}", path), err))?;
+        }
+        let mut counted = Counted {
+            inner: writer,
+            written: 0,
+        };
+        builder
+            .to_writer(&mut rng, &mut counted)
+            .map_err(|err| pgp_error("OpenPGP encryption failed", err))?;
+        Ok(counted.written)
+    }
+    #[cfg(not(feature = "pgp"))]
+    {
+        let _ = (recipients, reader, writer);
+        Err(unsupported("pgp"))
+    }
+}
+
+/// Decrypts an OpenPGP message embedded with `--algo pgp`.
+///
+/// # Arguments
+///
+/// * `data` - The binary OpenPGP message.
+/// * `secret_keys` - The paths of the secret keys to try, armored or binary.
+/// * `passphrase` - The passphrase of secret keys protected by one; unprotected keys need none.
+///
+/// # Returns
+///
+/// A `Result` containing the plaintext, a `WrongKey` error if none of the keys opens the
+/// message, or an `Unsupported` error without the `pgp` feature.
+pub fn pgp_decrypt(data: &[u8], secret_keys: &[String], passphrase: &str) -> io::Result<Vec<u8>> {
+    #[cfg(feature = "pgp")]
+    {
+        use pgp::composed::{Deserializable, Message, SignedSecretKey};
+        use pgp::types::Password;
+        let mut keys = Vec::with_capacity(secret_keys.len());
+        for path in secret_keys {
+            let (key, _) = SignedSecretKey::from_reader_single(std::fs::File::open(path)?)
+                .map_err(|err| pgp_error(format!("Invalid OpenPGP secret key {}", path), err))?;
+            keys.push(key);
+        }
+        let passwords = [Password::empty(), Password::from(passphrase)];
+        let message = Message::from_bytes(data)
+            .map_err(|err| pgp_error("The payload isn't a valid OpenPGP message", err))?;
+        let mut message = message
+            .decrypt_with_keys(passwords.iter().collect(), keys.iter().collect())
+            .map_err(|err| -> io::Error {
+                SteganoError::WrongKey(format!(
+                    "None of the secret keys opens the OpenPGP message: {}",
+                    err
+                ))
+                .into()
+            })?;
+        if message.is_compressed() {
+            message = message
+                .decompress()
+                .map_err(|err| pgp_error("The OpenPGP message is damaged", err))?;
+        }
+        message.as_data_vec().map_err(|err| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("The OpenPGP message is damaged: {}", err),
+            )
+        })
+    }
+    #[cfg(not(feature = "pgp"))]
+    {
+        let _ = (data, secret_keys, passphrase);
+        Err(unsupported("pgp"))
+    }
+}
+
+/// Wraps an error of the OpenPGP implementation, such as a malformed certificate.
+#[cfg(feature = "pgp")]
+fn pgp_error(context: impl std::fmt::Display, err: pgp::errors::Error) -> io::Error {
+    SteganoError::Format(format!("{}: {}", context, err)).into()
+}
+
+/// A writer counting the bytes written through it.
+#[cfg(any(feature = "age", feature = "pgp"))]
+struct Counted<W> {
+    inner: W,
+    written: u64,
+}
+
+#[cfg(any(feature = "age", feature = "pgp"))]
+impl<W: Write> Write for Counted<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Builds the error returned by builds without the `age` or `pgp` feature.
+#[cfg(not(all(feature = "age", feature = "pgp")))]
+fn unsupported(feature: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::Unsupported,
+        format!(
+            "--algo {} needs a format this build doesn't include; rebuild with --features {}",
+            feature, feature
+        ),
+    )
+}
@@ -0,0 +1,172 @@
+use crate::cancel::Cancelled;
+use crate::json::JsonValue;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+/// Schema of the error objects printed with `--error-format json`.
+pub const ERROR_SCHEMA: &str = "stegano.error/1";
+
+/// The category of a failed command, which decides its exit code.
+///
+/// Library functions keep returning `io::Error`s; the few that can tell a category their
+/// `ErrorKind` doesn't, such as a wrong key, carry a `SteganoError` inside, the same way
+/// cancelled operations carry `Cancelled`. `classify` recovers the category of any error.
+///
+/// | Exit code | Category            | Meaning                                              |
+/// |-----------|---------------------|------------------------------------------------------|
+/// | 0         |                     | Success                                              |
+/// | 1         | `error`             | Any other failure                                    |
+/// | 2         | `usage`             | Invalid arguments or options that don't fit together |
+/// | 3         | `io`                | A file couldn't be read or written                   |
+/// | 4         | `format`            | The carrier is malformed or not of the given type    |
+/// | 5         | `payload_not_found` | The carrier holds no payload where it was looked for |
+/// | 6         | `wrong_key`         | The key doesn't open the data                        |
+/// | 7         | `expired`           | The payload is past its expiry                       |
+/// | 130       | `cancelled`         | Interrupted with Ctrl-C                              |
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{self, ErrorKind};
+/// use stegano::error::SteganoError;
+///
+/// let err = io::Error::new(ErrorKind::NotFound, "No \"stEg\" chunk found");
+/// let error = SteganoError::classify(&err);
+/// assert_eq!(error.exit_code(), 5);
+/// assert_eq!(error.category(), "payload_not_found");
+///
+/// let err: io::Error = SteganoError::WrongKey("wrong key?".to_string()).into();
+/// assert_eq!(SteganoError::classify(&err).exit_code(), 6);
+/// assert!(SteganoError::classify(&err).to_json().to_string().contains("\"wrong_key\""));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SteganoError {
+    /// Invalid arguments, or options that don't fit together.
+    Usage(String),
+    /// A file couldn't be read or written.
+    Io(String),
+    /// The carrier is malformed or not of the given type.
+    Format(String),
+    /// The carrier holds no payload where it was looked for.
+    PayloadNotFound(String),
+    /// The key doesn't open the data. Only detected where the data carries a check.
+    WrongKey(String),
+    /// The payload is past its expiry.
+    Expired(String),
+    /// The operation was cancelled.
+    Cancelled,
+    /// Any other failure.
+    Other(String),
+}
+
+impl SteganoError {
+    /// Returns the exit code of the category.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SteganoError::Other(_) => 1,
+            SteganoError::Usage(_) => 2,
+            SteganoError::Io(_) => 3,
+            SteganoError::Format(_) => 4,
+            SteganoError::PayloadNotFound(_) => 5,
+            SteganoError::WrongKey(_) => 6,
+            SteganoError::Expired(_) => 7,
+            SteganoError::Cancelled => 130,
+        }
+    }
+
+    /// Returns the name of the category, as used in the JSON output.
+    pub fn category(&self) -> &'static str {
+        match self {
+            SteganoError::Other(_) => "error",
+            SteganoError::Usage(_) => "usage",
+            SteganoError::Io(_) => "io",
+            SteganoError::Format(_) => "format",
+            SteganoError::PayloadNotFound(_) => "payload_not_found",
+            SteganoError::WrongKey(_) => "wrong_key",
+            SteganoError::Expired(_) => "expired",
+            SteganoError::Cancelled => "cancelled",
+        }
+    }
+
+    /// Sorts any error into a category.
+    ///
+    /// A `SteganoError`, on its own or inside an `io::Error`, keeps its category. Other
+    /// `io::Error`s are sorted by origin and kind: errors from the operating system are `io`,
+    /// `NotFound` means no payload was found, `InvalidData` and `UnexpectedEof` a malformed
+    /// carrier and `InvalidInput` a usage error. Command-line parsing errors are usage errors.
+    pub fn classify(err: &(dyn Error + 'static)) -> SteganoError {
+        if let Some(error) = err.downcast_ref::<SteganoError>() {
+            return error.clone();
+        }
+        if err.is::<Cancelled>() {
+            return SteganoError::Cancelled;
+        }
+        if err.is::<clap::Error>() {
+            let message = err.to_string();
+            let message = message.strip_prefix("error: ").unwrap_or(&message);
+            return SteganoError::Usage(message.trim_end().to_string());
+        }
+        let Some(io_err) = err.downcast_ref::<io::Error>() else {
+            return SteganoError::Other(err.to_string());
+        };
+        if let Some(inner) = io_err.get_ref() {
+            if inner.is::<SteganoError>() || inner.is::<Cancelled>() {
+                return SteganoError::classify(inner);
+            }
+        }
+        let message = io_err.to_string();
+        if io_err.raw_os_error().is_some() {
+            return SteganoError::Io(message);
+        }
+        match io_err.kind() {
+            ErrorKind::NotFound => SteganoError::PayloadNotFound(message),
+            ErrorKind::InvalidData | ErrorKind::UnexpectedEof => SteganoError::Format(message),
+            ErrorKind::InvalidInput => SteganoError::Usage(message),
+            ErrorKind::PermissionDenied | ErrorKind::AlreadyExists | ErrorKind::WriteZero => {
+                SteganoError::Io(message)
+            }
+            _ => SteganoError::Other(message),
+        }
+    }
+
+    /// Builds the error object printed on stderr with `--error-format json`.
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("schema", JsonValue::from(ERROR_SCHEMA)),
+            ("error", JsonValue::from(self.category())),
+            ("exit_code", JsonValue::from(self.exit_code() as u64)),
+            ("message", JsonValue::from(self.to_string())),
+        ])
+    }
+}
+
+impl fmt::Display for SteganoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SteganoError::Usage(message)
+            | SteganoError::Io(message)
+            | SteganoError::Format(message)
+            | SteganoError::PayloadNotFound(message)
+            | SteganoError::WrongKey(message)
+            | SteganoError::Expired(message)
+            | SteganoError::Other(message) => write!(f, "{}", message),
+            SteganoError::Cancelled => write!(f, "{}", Cancelled),
+        }
+    }
+}
+
+impl Error for SteganoError {}
+
+impl From<SteganoError> for io::Error {
+    /// Wraps the error in an `io::Error` of the matching kind, so `classify` finds it again.
+    fn from(error: SteganoError) -> io::Error {
+        let kind = match error {
+            SteganoError::Usage(_) => ErrorKind::InvalidInput,
+            SteganoError::Format(_) | SteganoError::WrongKey(_) => ErrorKind::InvalidData,
+            SteganoError::PayloadNotFound(_) => ErrorKind::NotFound,
+            _ => ErrorKind::Other,
+        };
+        io::Error::new(kind, error)
+    }
+}
@@ -0,0 +1,2041 @@
+use crate::chunk_type::ChunkType;
+#[cfg(feature = "gui")]
+use crate::cli::GuiCmd;
+use crate::cli::{
+    ConfigCmd, DecryptCmd, DecryptMode, DiffCmd, EncryptCmd, ExtractChunkCmd, GenerateCarrierCmd,
+    IccCmd, InjectChunkCmd, MetaAction, MetaCmd, PeekCmd, PolyglotCmd, ProvenanceCmd, RekeyCmd,
+    RemoveChunkCmd, ShowMetaCmd, SoakCmd, VaultAction, VaultCmd, WatermarkAction, WatermarkCmd,
+};
+#[cfg(feature = "net")]
+use crate::cli::{FetchCmd, ServeCmd, ShareCmd};
+use crate::config::Config;
+use crate::cover::{safe_capacity, MAX_COVER_PIXELS};
+use crate::encoding::PayloadEncoding;
+use crate::header::PRECISION_TAG_LEN;
+use crate::lsb::Channels;
+use crate::png::{ChunkPosition, OffsetSpec};
+use crate::precision::PRECISION_CAPACITY;
+use crate::shamir::SHARE_HEADER_LEN;
+use crate::shard::{shard_path, SHARD_HEADER_LEN};
+use crate::storage::is_remote;
+use crate::style::Style;
+use crate::utils::is_aes;
+use crate::vault::{VAULT_FILE_CHUNK, VAULT_INDEX_CHUNK};
+use crate::watermark::{MAX_OWNER_LEN, WATERMARK_THRESHOLD};
+use std::fmt;
+
+/// A plain-language plan of what a subcommand is about to do, printed by `--explain`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+    /// What the subcommand does, in one line.
+    pub summary: String,
+    /// Labelled steps, such as where the payload goes and how it is protected.
+    pub steps: Vec<(&'static str, String)>,
+    /// Problems found in the options, which usually mean the command will fail or mislead.
+    pub warnings: Vec<String>,
+}
+
+impl Plan {
+    /// Starts a plan with its summary line.
+    fn new(summary: impl Into<String>) -> Self {
+        Plan {
+            summary: summary.into(),
+            ..Plan::default()
+        }
+    }
+
+    /// Adds a labelled step.
+    fn step(mut self, label: &'static str, text: impl Into<String>) -> Self {
+        self.steps.push((label, text.into()));
+        self
+    }
+
+    /// Adds a warning when `condition` holds.
+    fn warn_if(mut self, condition: bool, text: impl Into<String>) -> Self {
+        if condition {
+            self.warnings.push(text.into());
+        }
+        self
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", Style::BoldCyan.paint("Plan:"), self.summary)?;
+        let width = self
+            .steps
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0);
+        for (label, text) in &self.steps {
+            writeln!(
+                f,
+                "  {:<width$}  {}",
+                format!("{}:", label),
+                text,
+                width = width + 1
+            )?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "  {} {}", Style::Yellow.paint("Warning:"), warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes where a type/method combination puts the payload and what would destroy it.
+///
+/// # Returns
+///
+/// The placement and the risks, or `None` if the combination isn't supported.
+fn placement(file_type: &str, method: &str, chunk_name: &str) -> Option<(String, String)> {
+    let (place, risks) = match (method, file_type) {
+        ("append", _) => (
+            "after the end of the file, followed by its length and a magic trailer; readers of \
+             the format stop before it"
+                .to_string(),
+            "anything that re-saves the file or trims trailing data, as many upload pipelines \
+             and optimizers do",
+        ),
+        ("auto" | "chunk", "png") => (
+            format!(
+                "in a private ancillary \"{}\" chunk inserted before IEND; PNG readers skip \
+                 chunks they don't know",
+                chunk_name
+            ),
+            "optimizers and editors that drop unknown chunks (oxipng --strip, pngcrush -rem \
+             alla) or re-save the image",
+        ),
+        ("apng", "png") => (
+            format!(
+                "in a private ancillary \"{}\" chunk inserted between the first two frames of \
+                 the animation; players skip it and the frame sequence numbers are unchanged",
+                chunk_name
+            ),
+            "optimizers and editors that drop unknown chunks or rebuild the animation, and \
+             converters to GIF, WebP or still PNG",
+        ),
+        ("precision", "png") => (
+            "in the low four bits of the gAMA, cHRM and pHYs values, moved by at most 8/100000; \
+             missing chunks are added with sRGB defaults"
+                .to_string(),
+            "any tool that drops or rewrites these chunks, including colour management and \
+             most re-encodes",
+        ),
+        ("lsb", "png") => (
+            "in the least significant bit of colour samples at positions drawn from the key, \
+             spread over the whole image, or the two, three or four low bits when one doesn't \
+             hold the payload"
+                .to_string(),
+            "lossy conversion (JPEG, WebP), resizing, colour or bit depth changes and filters; \
+             lossless re-compression keeps it",
+        ),
+        ("robust", "png") => (
+            "in mid-frequency DCT coefficients of 8x8 luma blocks drawn from the key, each bit \
+             repeated over five blocks"
+                .to_string(),
+            "resizing, cropping, rotation and JPEG re-compression below about quality 50; \
+             milder re-compression and noise are survived once converted back to PNG",
+        ),
+        ("disguise", "png") => (
+            "right after IHDR, dressed as standard metadata picked with --disguise: the tone \
+             curve of an ICC profile (iCCP), the thumbnail of an XMP packet (iTXt) or the colour \
+             data of a camera maker note (eXIf); metadata viewers list it as such, but it doesn't \
+             decode to a sensible curve or image"
+                .to_string(),
+            "tools that strip metadata (oxipng --strip, exiftool -all=), and colour management \
+             that converts or drops the profile",
+        ),
+        ("interleave", "png") => (
+            "split into 2 to 8 fragment chunks named --chunk-name, each numbered, scattered \
+             between the chunks of the carrier at places drawn from the key (never between two \
+             IDAT chunks)"
+                .to_string(),
+            "tools that strip unknown chunks (oxipng --strip, pngcrush -rem alla); reordering \
+             the chunks is harmless, but losing one fragment loses the payload",
+        ),
+        ("icc", "png" | "jpeg" | "jpg") | ("auto", "jpeg" | "jpg") => (
+            "after the tag elements of the ICC profile (iCCP in a PNG, APP2 segments in a JPEG), \
+             with the profile size grown to cover it; colour management only reads what the tag \
+             table points to, and a carrier without a profile gets a neutral sRGB-like one"
+                .to_string(),
+            "tools that strip metadata or convert the colours to another profile; copying the \
+             profile elsewhere carries the payload along",
+        ),
+        ("auto" | "tag", "tiff") => (
+            "in private tag 65000 of the first IFD, which is rewritten at the end of the file"
+                .to_string(),
+            "editors that rewrite the IFDs and drop unknown tags",
+        ),
+        ("strip", "tiff") => (
+            "in an unreferenced strip at the end of the file; no IFD points to it".to_string(),
+            "any re-save, since writers only keep the referenced strips",
+        ),
+        ("auto" | "slack", "ico") => (
+            "in the slack space after the last image; icon readers only read the images listed \
+             in the directory"
+                .to_string(),
+            "any re-save of the icon",
+        ),
+        ("auto" | "application", "flac") => (
+            "in an APPLICATION metadata block with the \"stEg\" application ID, added after the \
+             last metadata block; players skip applications they don't know"
+                .to_string(),
+            "tag editors that rewrite the metadata and drop unknown blocks, and re-encoding",
+        ),
+        ("padding", "flac") => (
+            "in the PADDING block when it is zeroed and large enough, keeping the size of the \
+             file, or in a new PADDING block after the last metadata block"
+                .to_string(),
+            "any tag edit, since editors take their room from the padding, and re-encoding",
+        ),
+        ("auto" | "priv" | "geob", "mp3") => (
+            "in a PRIV frame owned by \"stegano\", or a GEOB object described as \"stegano\", \
+             after the last frame of the ID3v2 tag, which grows to hold it; a file without a \
+             tag gets one, and players skip frames they don't show"
+                .to_string(),
+            "tag editors that drop private frames or objects, and re-encoding the audio",
+        ),
+        ("auto" | "free" | "skip" | "uuid", "mp4") => (
+            "in a free, skip or uuid box appended after the last top-level box; players skip \
+             those boxes, and the sample offsets of the video stay valid"
+                .to_string(),
+            "remuxing or re-encoding the video, and tools that move moov to the front (faststart)",
+        ),
+        ("auto" | "metadata", "svg") => (
+            "in base64 in a metadata element with the id \"stegano\", right after the root svg \
+             tag; renderers don't draw metadata"
+                .to_string(),
+            "optimizers such as SVGO that drop metadata, and editors that rewrite the document",
+        ),
+        ("whitespace", "svg") => (
+            "as spaces and tabs at the end of the lines, 8 per line for each byte of the length \
+             and the payload; whitespace between tags has no meaning in XML"
+                .to_string(),
+            "minifiers, formatters and editors that trim trailing whitespace",
+        ),
+        ("auto" | "zerowidth", "text") => (
+            "as zero-width characters right after the first visible character, four per byte of \
+             the length and the payload; they take no room when the text is displayed"
+                .to_string(),
+            "tools that normalize or sanitize Unicode, retyping the text, and some paste targets",
+        ),
+        ("whitespace", "text") => (
+            "as spaces and tabs at the end of the lines, 8 per line for each byte of the length \
+             and the payload; trailing whitespace doesn't show"
+                .to_string(),
+            "editors and formatters that trim trailing whitespace, and reflowing the text",
+        ),
+        ("attributes", "svg") => (
+            "in the order of the attributes of the elements, each element with n attributes \
+             holding log2(n!) bits; attribute order has no meaning in XML, and the original \
+             order isn't restored"
+                .to_string(),
+            "optimizers and editors that sort or rewrite attributes",
+        ),
+        _ => return None,
+    };
+    Some((place, risks.to_string()))
+}
+
+/// Describes how an algorithm protects the payload.
+fn protection(algorithm: &str) -> Option<&'static str> {
+    match algorithm {
+        "aes" => Some(
+            "AES-128 on independent 16-byte blocks, with the key cut or zero-padded to 16 bytes \
+             and the payload padded to a whole block with PKCS#7, or zeros without a header",
+        ),
+        "aes192" => Some(
+            "AES-192 on independent 16-byte blocks, with a 24-byte key taken from the SHA-256 of \
+             the key and the payload padded to a whole block with PKCS#7, or zeros without a header",
+        ),
+        "aes256" => Some(
+            "AES-256 on independent 16-byte blocks, with a 32-byte key taken from the SHA-256 of \
+             the key and the payload padded to a whole block with PKCS#7, or zeros without a header",
+        ),
+        "chacha" => Some(
+            "ChaCha20, a stream cipher, with a 32-byte key taken from the SHA-256 of the key \
+             and a random nonce stored in the header; the payload isn't padded",
+        ),
+        "xor" => Some(
+            "XOR with the repeating key, which only obfuscates: a known piece of plaintext \
+             reveals the key",
+        ),
+        "age" => Some(
+            "a standard age file instead of a header and ciphertext: ChaCha20-Poly1305 under a \
+             random file key, wrapped with scrypt from the key or with X25519 for each \
+             --recipient, so `age -d` opens the extracted payload too",
+        ),
+        "pgp" | "openpgp" => Some(
+            "a binary OpenPGP message instead of a header and ciphertext: AES-256 in a SEIPD v1 \
+             packet, its session key encrypted to the encryption key of each --recipient \
+             certificate, so `gpg --decrypt` opens the extracted payload too",
+        ),
+        _ => None,
+    }
+}
+
+/// Describes what `--strict` checks in a file of this type.
+fn strict_checks(file_type: &str) -> &'static str {
+    match file_type {
+        "jpeg" | "jpg" => {
+            "the marker structure first: SOI, segment lengths that fit, EOI at the end"
+        }
+        "tiff" => "the IFD chain first: word-aligned IFDs listing their tags in ascending order",
+        "ico" => "the icon directory first: one plane per image, no overlapping images",
+        "mp4" => "the top-level boxes first: ftyp at the start, exactly one moov",
+        "mp3" => "the ID3v2 tag first: synchsafe sizes, zero padding, a frame sync after it",
+        "flac" => {
+            "the metadata blocks first: STREAMINFO first and once, zeroed padding, a frame sync \
+             after the last block"
+        }
+        "svg" => "the tags first: balanced start and end tags under one svg root in its namespace",
+        "text" => "the text first: UTF-8 without NUL or control characters or a stray BOM",
+        _ => {
+            "the file against the PNG specification first: chunk CRCs, names and order, \
+              the IHDR fields, and nothing after IEND"
+        }
+    }
+}
+
+/// Adds a step saying how an input or output named by a URL is read or written.
+fn remote(plan: Plan, input: &str, output: &str) -> Plan {
+    let text = match (is_remote(input), is_remote(output)) {
+        (false, false) => return plan,
+        (true, false) => format!(
+            "{} is read as it downloads, without saving it to disk",
+            input
+        ),
+        (false, true) => format!(
+            "{} is uploaded once complete, nothing is written to disk",
+            output
+        ),
+        (true, true) => format!(
+            "{} is read as it downloads and {} uploaded once complete, nothing is written to disk",
+            input, output
+        ),
+    };
+    plan.step("Remote", text)
+}
+
+/// Builds the plan of the `encrypt` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before embedding.
+///
+/// # Examples
+///
+/// ```
+/// use clap::Parser;
+/// use stegano::cli::{Cli, SteganoCommands};
+/// use stegano::explain::explain_encrypt;
+///
+/// let cli = Cli::parse_from(["stegano", "encrypt", "-i", "in.png", "-t", "png", "-m", "precision"]);
+/// let Some(SteganoCommands::Encrypt(cmd)) = cli.command else { unreachable!() };
+/// let plan = explain_encrypt(&cmd);
+/// assert!(plan.steps.iter().any(|(label, text)| *label == "Where" && text.contains("gAMA")));
+/// assert!(plan.warnings.iter().any(|w| w.contains("--algo xor")));
+/// ```
+pub fn explain_encrypt(cmd: &EncryptCmd) -> Plan {
+    let file_type = cmd.r#type.to_lowercase();
+    let method = cmd.method.to_lowercase();
+    let algorithm = cmd.algorithm.to_lowercase();
+    // An invalid encoding is reported when the command runs; the plan counts the text.
+    let payload_len = cmd
+        .payload_encoding
+        .decode(&cmd.payload)
+        .map_or(cmd.payload.len(), |data| data.len());
+    let payload = match (&cmd.payload_file, &cmd.payload_qr, cmd.payload_encoding) {
+        (Some(path), _, _) => format!("the contents of {}", path),
+        (None, Some(path), PayloadEncoding::Raw) => {
+            format!("the text of the QR code in {}", path)
+        }
+        (None, Some(path), encoding) => {
+            format!("the {} text of the QR code in {}, decoded", encoding, path)
+        }
+        (None, None, PayloadEncoding::Raw) => {
+            format!("the text given with -p ({} bytes)", payload_len)
+        }
+        (None, None, encoding) => format!(
+            "the {} given with -p, decoded ({} bytes)",
+            encoding, payload_len
+        ),
+    };
+    let mut plan = if cmd.in_place {
+        Plan::new(format!(
+            "hide {} in {}, rewriting it in place through a synced temp file",
+            payload, cmd.input
+        ))
+    } else if cmd.split_across.is_empty() {
+        Plan::new(format!(
+            "hide {} in a copy of {} written to {}; the input is left untouched",
+            payload, cmd.input, cmd.output
+        ))
+    } else {
+        let parts = cmd.split_across.len();
+        Plan::new(format!(
+            "split {} across copies of {} written to {} to {}; the inputs are left untouched",
+            payload,
+            cmd.split_across.join(", "),
+            shard_path(&cmd.output, 0),
+            shard_path(&cmd.output, parts - 1)
+        ))
+        .step(
+            "Split",
+            match cmd.shamir {
+                Some(threshold) => format!(
+                    "the encrypted payload is turned into {} Shamir shares as long as itself, any \
+                     {} of which rebuild it while fewer reveal nothing; each has a header of {} bytes \
+                     holding its number, the threshold and a digest of the whole payload",
+                    threshold.shares, threshold.needed, SHARE_HEADER_LEN
+                ),
+                None => format!(
+                    "the encrypted payload is cut into {} shards, each with a {}-byte header \
+                     holding its number, the total and a digest of the whole payload",
+                    parts, SHARD_HEADER_LEN
+                ),
+            },
+        )
+        .warn_if(
+            cmd.shamir
+                .is_some_and(|threshold| threshold.shares as usize != parts),
+            "--shamir needs as many carriers in --split-across as it has shares",
+        )
+    };
+
+    if cmd.strict {
+        plan = plan.step("Strict", format!("checks {}", strict_checks(&file_type)));
+    }
+    match placement(&file_type, &method, &cmd.chunk_name) {
+        Some((place, risks)) => {
+            let place = if file_type == "png" && matches!(method.as_str(), "auto" | "chunk") {
+                match cmd.offset {
+                    OffsetSpec::Auto => place,
+                    OffsetSpec::Absolute(offset) => place.replace(
+                        "before IEND",
+                        &format!("at offset {}, which must be a chunk boundary", offset),
+                    ),
+                    OffsetSpec::FromEnd(back) => place.replace(
+                        "before IEND",
+                        &format!(
+                            "{} bytes before the end of the file, which must be a chunk boundary",
+                            back
+                        ),
+                    ),
+                    OffsetSpec::After(r#type) => place.replace(
+                        "before IEND",
+                        &format!("after the last {} chunk", String::from_utf8_lossy(&r#type)),
+                    ),
+                    OffsetSpec::Before(r#type) => place.replace(
+                        "before IEND",
+                        &format!(
+                            "before the first {} chunk",
+                            String::from_utf8_lossy(&r#type)
+                        ),
+                    ),
+                }
+            } else {
+                place
+            };
+            plan = plan.step("Where", place).step("Destroyed by", risks);
+            if let (true, Some(bits)) = (method == "lsb", cmd.bits_per_channel) {
+                plan = plan.step(
+                    "Bits",
+                    format!(
+                        "{} low bits of each sample, recorded with the length, instead of the \
+                         fewest the payload fits in; each bit more holds as much again but takes \
+                         about 6 dB off the PSNR of the samples it changes",
+                        bits
+                    ),
+                );
+            }
+            if method == "lsb" && cmd.channels != Channels::default() {
+                plan = plan.step(
+                    "Channels",
+                    format!(
+                        "only the {} samples carry bits, which shrinks the capacity; decrypt \
+                         needs --channels {} too",
+                        cmd.channels.name(),
+                        cmd.channels.name()
+                    ),
+                );
+            }
+            if method == "lsb" && cmd.traversal != "scatter" {
+                plan = plan.step(
+                    "Traversal",
+                    format!(
+                        "the payload bits follow the {} order over the pixels, after the length \
+                         scattered with the key, which records it",
+                        cmd.traversal
+                    ),
+                );
+            }
+        }
+        None => {
+            plan = plan
+                .step("Where", "nowhere: the command will fail")
+                .warn_if(
+                    true,
+                    format!(
+                        "method {:?} isn't supported for {:?} files",
+                        cmd.method, cmd.r#type
+                    ),
+                )
+        }
+    }
+    plan = match protection(&algorithm) {
+        Some(text) => plan.step("Protection", text),
+        None => plan.warn_if(true, format!("unsupported algorithm {:?}", cmd.algorithm)),
+    };
+    if is_aes(&algorithm) && method != "precision" {
+        plan = match cmd.block_mode.to_lowercase().as_str() {
+            "ctr" => plan.step(
+                "Mode",
+                "CTR instead: a counter from a random IV is encrypted and XORed with the \
+                 payload, which isn't padded; the IV is stored in the header",
+            ),
+            "cbc" => plan.step(
+                "Mode",
+                "CBC instead: each block is chained with the one before, from a random IV \
+                 stored in the header",
+            ),
+            "ecb" => plan.warn_if(
+                true,
+                "--block-mode ecb encrypts identical 16-byte blocks to identical ciphertext",
+            ),
+            _ => plan.warn_if(true, format!("unsupported block mode {:?}", cmd.block_mode)),
+        };
+    }
+    if method == "lsb" {
+        plan = plan.step(
+            "Key",
+            "also seeds the pixel order, so the bits can't be gathered without it",
+        );
+    }
+    if method == "robust" {
+        plan = plan.step(
+            "Key",
+            "also seeds the block order and the quantization dither, so the bits can't be \
+             gathered without it",
+        );
+    }
+    if method == "interleave" {
+        plan = plan.step(
+            "Key",
+            "also seeds the number and places of the fragments; they are gathered by their \
+             sequence numbers, so extracting them doesn't need it",
+        );
+    }
+    if method == "lsb" || method == "robust" {
+        plan = plan.step(
+            "Quality",
+            match cmd.max_distortion {
+                Some(max) => format!(
+                    "the PSNR and SSIM of the result are reported, and nothing is written below {}",
+                    max
+                ),
+                None => "the PSNR and SSIM of the result are reported".to_string(),
+            },
+        );
+    } else if cmd.max_distortion.is_some() {
+        plan = plan.warn_if(
+            true,
+            "--max-distortion only applies to the lsb and robust methods",
+        );
+    }
+    if method == "lsb" || method == "robust" {
+        plan = plan.step(
+            "Interlacing",
+            if cmd.deinterlace {
+                "the output is written without interlacing, whatever the carrier"
+            } else {
+                "kept: an interlaced (Adam7) carrier gives an interlaced output, the payload \
+                 being placed in the de-interlaced pixels"
+            },
+        );
+    } else {
+        plan = plan.warn_if(
+            cmd.deinterlace,
+            "--deinterlace only applies to the lsb and robust methods",
+        );
+    }
+    if let Some(hint) = &cmd.hint {
+        plan = plan.step(
+            "Hint",
+            format!("{:?} is stored unencrypted in front of the payload", hint),
+        );
+    }
+    if method != "precision" {
+        let mut fields = vec!["version", "algorithm", "payload length", "creation time"];
+        if cmd.nest {
+            fields.push("MIME type of the nested stego file, which `decrypt --depth` unwraps");
+        } else if cmd.mime_type.is_some() {
+            fields.push("MIME type");
+        }
+        if cmd.filename.is_some() {
+            fields.push("filename");
+        }
+        if cmd.expires.is_some() {
+            fields.push("expiry, after which decrypt refuses to reveal the payload");
+        }
+        if cmd.views.is_some() {
+            fields.push("number of views `decrypt --burn` counts down before wiping the payload");
+        }
+        plan = plan.step(
+            "Header",
+            format!(
+                "a plaintext header signed with the key, in front of the ciphertext, records the {}",
+                fields.join(", ")
+            ),
+        );
+    }
+    if let Some(path) = &cmd.descriptor {
+        plan = plan.step(
+            "Descriptor",
+            format!(
+                "the offset, length and SHA-256 of the payload are sealed with the key into {}",
+                path
+            ),
+        );
+    }
+    if cmd.provenance {
+        plan = plan.step(
+            "Provenance",
+            "the SHA-256 of the input is recorded, so `stegano provenance` can verify the chain",
+        );
+    }
+    if let Some(limit) = cmd.max_memory {
+        plan = plan.step(
+            "Memory",
+            format!(
+                "the encrypted payload spills to a temp file beyond {} bytes",
+                limit
+            ),
+        );
+    }
+    if let Some(id) = &cmd.key_id {
+        plan = plan.step(
+            "Keychain",
+            format!(
+                "the key is read from the OS keychain entry {:?}, or stored there if -k is given",
+                id
+            ),
+        );
+    }
+    if cmd.preserve_times {
+        plan = plan.step(
+            "Timestamps",
+            "the output gets the access and modification times the carrier had before it was \
+             read, and the carrier gets its access time back",
+        );
+    }
+    if cmd.preserve_metadata {
+        plan = plan
+            .step(
+                "Metadata",
+                "every ancillary chunk of the carrier (gAMA, pHYs, iCCP, text...) must come out \
+                 byte for byte and in order, or nothing is written",
+            )
+            .warn_if(
+                !cmd.r#type.eq_ignore_ascii_case("png") || method == "precision",
+                "--preserve-metadata only applies to PNG carriers, and not to the precision \
+                 method, which rewrites gAMA, cHRM and pHYs",
+            );
+    }
+    if cmd.dry_run {
+        plan = plan.step(
+            "Dry run",
+            "every check runs and the output is described, chunk, offset and size, but nothing \
+             is written",
+        );
+    }
+    if cmd.verbose {
+        plan = plan.step(
+            "Verbose",
+            "reads back the bytes around the injection offset from the carrier and the output \
+             and dumps both, the inserted bytes highlighted; pixel methods have no such offset",
+        );
+    }
+    plan = remote(plan, &cmd.input, &cmd.output);
+
+    let source = if cmd.split_across.is_empty() {
+        format!("-i {}", cmd.output)
+    } else {
+        let shards: Vec<String> = (0..cmd.split_across.len())
+            .map(|index| shard_path(&cmd.output, index))
+            .collect();
+        format!("--join {}", shards.join(" "))
+    };
+    let key = match &cmd.key_id {
+        Some(id) => format!("--key-id {}", id),
+        None => "-k <key>".to_string(),
+    };
+    let mut extract = format!(
+        "stegano decrypt {} -t {} -m {} -a {} {}",
+        source, cmd.r#type, cmd.method, cmd.algorithm, key
+    );
+    if file_type == "png"
+        && matches!(method.as_str(), "auto" | "chunk" | "apng")
+        && cmd.chunk_name != "stEg"
+    {
+        extract.push_str(&format!(" -c {}", cmd.chunk_name));
+    }
+    plan.step("Extract with", extract)
+        .warn_if(
+            cmd.hint.is_some(),
+            "the hint is stored in plaintext: anyone can read it with `stegano peek`, and it \
+             gives away that the file holds a payload",
+        )
+        .warn_if(
+            cmd.mime_type.is_some() || cmd.filename.is_some(),
+            "the MIME type and filename are stored in plaintext, readable with `stegano peek`",
+        )
+        .warn_if(
+            method == "precision"
+                && (cmd.mime_type.is_some() || cmd.filename.is_some() || cmd.expires.is_some()),
+            "the precision method has no room for a payload header, MIME type, filename or expiry",
+        )
+        .warn_if(
+            cmd.hint.is_some() && matches!(method.as_str(), "precision" | "lsb" | "robust"),
+            "hints aren't supported by the precision, LSB and robust methods",
+        )
+        .warn_if(
+            method == "precision" && !cmd.split_across.is_empty(),
+            format!(
+                "each shard carries a {}-byte header, more than the precision method holds",
+                SHARD_HEADER_LEN
+            ),
+        )
+        .warn_if(
+            cmd.descriptor.is_some()
+                && (!cmd.split_across.is_empty()
+                    || matches!(method.as_str(), "precision" | "lsb" | "robust")),
+            "descriptors need a single carrier and a method that stores the payload in one piece",
+        )
+        .warn_if(
+            cmd.provenance && !cmd.split_across.is_empty(),
+            "--provenance can't be combined with --split-across",
+        )
+        .warn_if(
+            cmd.provenance && method != "append",
+            "--provenance only works with --method append",
+        )
+        .warn_if(
+            method == "precision"
+                && (algorithm != "xor" || payload_len > PRECISION_CAPACITY - PRECISION_TAG_LEN),
+            format!(
+                "the precision method holds {} bytes, a check byte included, so only --algo xor \
+                 with a payload of at most {} bytes fits",
+                PRECISION_CAPACITY,
+                PRECISION_CAPACITY - PRECISION_TAG_LEN
+            ),
+        )
+        .warn_if(
+            cmd.key == "key",
+            "the default key \"key\" is in use, set your own with -k",
+        )
+}
+
+/// Builds the plan of the `extract`, `remove` and `decrypt` subcommands.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before extracting.
+pub fn explain_decrypt(cmd: &DecryptCmd) -> Plan {
+    if let Some(path) = &cmd.descriptor {
+        return Plan::new(format!(
+            "read the payload of {} at the location recorded in {} and print it",
+            cmd.input, path
+        ))
+        .step(
+            "Opens",
+            format!("{} with the key, which also tells the algorithm", path),
+        )
+        .step(
+            "Checks",
+            "the SHA-256 of the bytes read, no chunk, IFD or trailer is parsed",
+        )
+        .step("Writes", "nothing: the carrier isn't restored");
+    }
+    let file_type = cmd.r#type.to_lowercase();
+    let method = cmd.method.to_lowercase();
+    let ending = match cmd.mode {
+        DecryptMode::Remove => "check it against the key, without printing it",
+        _ => "print it",
+    };
+    let mut plan = if cmd.join.is_empty() {
+        Plan::new(format!(
+            "extract and decrypt the payload of {} and {}",
+            cmd.input, ending
+        ))
+    } else {
+        Plan::new(format!(
+            "gather the shards in {}, join them in order, decrypt the result and {}",
+            cmd.join.join(", "),
+            ending
+        ))
+        .step(
+            "Checks",
+            "that no shard is missing, or that there are enough --shamir shares, and that they \
+             all come from the same payload",
+        )
+    };
+    if cmd.strict {
+        plan = plan.step("Strict", format!("checks {}", strict_checks(&file_type)));
+    }
+    plan = remote(plan, &cmd.input, &cmd.output);
+    if cmd.exact {
+        plan = plan.warn_if(
+            matches!(method.as_str(), "precision" | "lsb" | "robust"),
+            format!(
+                "--exact is refused: the {} method overwrites the carrier",
+                cmd.method
+            ),
+        );
+        plan = plan.step(
+            "Verifies",
+            "that embedding the payload again into the restored carrier gives the stego file              back byte for byte, before writing anything",
+        );
+    }
+    if cmd.offset != OffsetSpec::Auto && file_type == "png" {
+        plan = plan.step(
+            "Offset",
+            format!(
+                "only a {:?} chunk starting at {} is read; the command fails if there is none",
+                cmd.chunk_name, cmd.offset
+            ),
+        );
+    }
+    plan = match placement(&file_type, &method, &cmd.chunk_name) {
+        Some((place, _)) => plan.step("Looks", place),
+        None => plan.warn_if(
+            true,
+            format!(
+                "method {:?} isn't supported for {:?} files",
+                cmd.method, cmd.r#type
+            ),
+        ),
+    };
+    plan = if method == "precision" {
+        match protection(&cmd.algorithm.to_lowercase()) {
+            Some(text) => plan
+                .step(
+                    "Checks",
+                    "the check byte of the payload against the key, and fails with \
+                     \"incorrect key or corrupted payload\" if it doesn't match",
+                )
+                .step("Decrypts", text)
+                .warn_if(
+                    true,
+                    "a check byte lets one wrong key in 256 through, which then decrypts to \
+                     garbage",
+                ),
+            None => plan.warn_if(true, format!("unsupported algorithm {:?}", cmd.algorithm)),
+        }
+    } else {
+        plan.step(
+            "Checks",
+            "the tags of the payload header and ciphertext against the key, and fails with \
+             \"incorrect key or corrupted payload\" if either doesn't match",
+        )
+        .step(
+            "Decrypts",
+            "with the algorithm named in the payload header, trimmed to the payload length it \
+             records; payloads written before the header are decrypted with -a instead, and age \
+             files with the key as passphrase or with the --identity files; OpenPGP messages \
+             are decrypted with the --identity secret keys, or written as is without them",
+        )
+        .step(
+            "Expiry",
+            if cmd.ignore_expiry {
+                "payloads past the expiry in their header are revealed anyway, with a warning"
+            } else {
+                "payloads past the expiry in their header are refused"
+            },
+        )
+    };
+    if cmd.burn {
+        plan = plan.step(
+            "Burn",
+            "the view counter of the payload header goes down by one, and at zero the payload \
+             is wiped",
+        );
+    }
+    if cmd.wipe {
+        plan = plan
+            .step(
+                "Wipes",
+                format!(
+                    "the payload bytes in {} with random data, {} time(s), once the payload \
+                     opens and before anything is written",
+                    cmd.input, cmd.passes
+                ),
+            )
+            .warn_if(
+                matches!(method.as_str(), "precision" | "lsb" | "robust"),
+                format!(
+                    "--wipe is refused: {} payloads are spread over the pixels, use \
+                     --shred-input instead",
+                    cmd.method
+                ),
+            );
+    }
+    if cmd.shred_input {
+        plan = plan.step(
+            "Shreds",
+            format!(
+                "{} with random data, {} time(s), then removes it, once the output is written",
+                cmd.input, cmd.passes
+            ),
+        );
+    }
+    if cmd.wipe || cmd.shred_input {
+        plan = plan.warn_if(
+            true,
+            "copy-on-write file systems, journals and SSDs may keep the old bytes elsewhere on \
+             the disk",
+        );
+    }
+    if cmd.depth > 1 {
+        plan = plan.step(
+            "Layers",
+            format!(
+                "up to {} in all: while the header of a payload records a PNG, TIFF or ICO MIME \
+                 type, as `encrypt --nest` does, it is decrypted again as a stego file of that \
+                 type, with -m, then auto, then append",
+                cmd.depth
+            ),
+        );
+    }
+    if cmd.output_encoding != PayloadEncoding::Raw {
+        plan = plan.step(
+            "Encodes",
+            format!(
+                "the secret in {}, so that binary payloads print safely",
+                cmd.output_encoding
+            ),
+        );
+    }
+    if let Some(path) = &cmd.output_payload_file {
+        plan = plan.step(
+            "Saves",
+            format!(
+                "the secret to {}, byte for byte, and prints nothing of it on stdout",
+                path
+            ),
+        );
+    }
+    if let Some(target) = &cmd.qr {
+        plan = plan.step(
+            "Shows",
+            if target == "-" {
+                "the secret as a QR code of Unicode half blocks on stdout, instead of its text"
+                    .to_string()
+            } else {
+                format!(
+                    "the secret as a QR code written to {}, instead of its text",
+                    target
+                )
+            },
+        );
+    }
+    let output = if cmd.burn {
+        format!(
+            "{} over itself, with the payload counted or wiped, through a synced temp file",
+            cmd.input
+        )
+    } else if matches!(method.as_str(), "precision" | "lsb" | "robust") {
+        "nothing: the overwritten bits of the carrier can't be restored".to_string()
+    } else if !cmd.mode.restores() {
+        format!(
+            "nothing: {} is left as it is, use remove to restore the carrier",
+            cmd.input
+        )
+    } else if !cmd.join.is_empty() {
+        format!(
+            "the carrier of each shard without its payload to {}, {} and so on",
+            shard_path(&cmd.output, 0),
+            shard_path(&cmd.output, 1)
+        )
+    } else if cmd.in_place {
+        format!(
+            "the carrier without the payload over {} itself, through a synced temp file",
+            cmd.input
+        )
+    } else {
+        format!("the carrier without the payload to {}", cmd.output)
+    };
+    plan.step("Writes", output).step(
+        "Wrong key",
+        if method == "lsb" {
+            "reads the wrong pixels, so no payload is found"
+        } else if method == "robust" {
+            "reads the wrong blocks, so the CRC fails and no payload is found"
+        } else {
+            "is refused, since the payload header is signed with the key; precision and \
+             legacy payloads carry no checksum and give garbage instead"
+        },
+    )
+}
+
+/// Builds the plan of the `show-meta` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before reading.
+pub fn explain_show_meta(cmd: &ShowMetaCmd) -> Plan {
+    if let Some(plane) = cmd.bit_plane {
+        return Plan::new(format!(
+            "decode {} and write bit plane {} of channel {} to {} in black and white",
+            cmd.input, plane, cmd.channel, cmd.output
+        ))
+        .step(
+            "Why",
+            "natural images look structured in their high planes and noisy in plane 0; \
+             sequentially embedded data shows up as a noisy band in the low planes",
+        )
+        .step("Modifies", "nothing but the output image");
+    }
+    if let Some(path) = &cmd.extract_trailing {
+        let terminator = match cmd.r#type.to_lowercase().as_str() {
+            "jpeg" | "jpg" => "the EOI marker",
+            _ => "the IEND chunk",
+        };
+        return Plan::new(format!(
+            "write the bytes after {} of {} to {}",
+            terminator, cmd.input, path
+        ))
+        .step(
+            "Why",
+            "readers stop at the terminator of an image, so many tools hide payloads by \
+             appending them after it",
+        )
+        .step("Modifies", "nothing but the output file");
+    }
+    let what = match cmd.r#type.to_lowercase().as_str() {
+        "jpeg" => "JPEG segments",
+        "tiff" => "TIFF header and IFD entries",
+        "ico" => "ICO directory entries",
+        "mp4" => "MP4 box tree",
+        "mp3" => "ID3v2 frames",
+        "flac" => "FLAC metadata blocks",
+        "svg" => "SVG root element and method capacities",
+        "text" => "text size and method capacities",
+        _ => "PNG chunks",
+    };
+    let mut plan = Plan::new(format!("read {} and print its {}", cmd.input, what));
+    if !cmd.filter.is_empty() {
+        plan = plan.step(
+            "Filter",
+            format!("only the chunks of type {}", cmd.filter.join(", ")),
+        );
+    }
+    if let Some(pattern) = &cmd.grep {
+        plan = plan.step(
+            "Grep",
+            format!(
+                "only the chunks whose data contains {}, with the offsets of the matches",
+                match pattern.strip_prefix("0x") {
+                    Some(hex) => format!("the bytes {}", hex),
+                    None => format!("the text {:?}", pattern),
+                }
+            ),
+        );
+    }
+    if let Some(page) = cmd.page {
+        plan = plan.step(
+            "Page",
+            format!(
+                "chunks {} to {} of the listing, walked by their declared lengths",
+                (page - 1).saturating_mul(cmd.per_page) + 1,
+                page.saturating_mul(cmd.per_page)
+            ),
+        );
+    }
+    if cmd.strict {
+        plan = plan.step(
+            "Strict",
+            format!("checks {}", strict_checks(&cmd.r#type.to_lowercase())),
+        );
+    }
+    if cmd.no_hex {
+        plan = plan.step("Hexdumps", "left out, only the chunk headers are printed");
+    } else if cmd.hex_limit.is_some() || cmd.hex_range.is_some() {
+        let range = match cmd.hex_range.clone() {
+            None => "the data of each chunk".to_string(),
+            Some(range) if range.end == usize::MAX => {
+                format!("the data of each chunk from byte {}", range.start)
+            }
+            Some(range) => format!("bytes {} to {} of each chunk", range.start, range.end),
+        };
+        let limit = cmd
+            .hex_limit
+            .map_or(String::new(), |limit| format!(", at most {} bytes", limit));
+        plan = plan.step("Hexdumps", format!("{}{}", range, limit));
+    }
+    plan.step(
+        "Output",
+        if cmd.json {
+            "one JSON document on stdout"
+        } else {
+            "hex dumps and headers on stdout"
+        },
+    )
+    .step("Modifies", "nothing")
+}
+
+/// Builds the plan of the `provenance` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before verifying.
+pub fn explain_provenance(cmd: &ProvenanceCmd) -> Plan {
+    Plan::new(format!(
+        "walk the appended frames of {} from the last one back to the original carrier",
+        cmd.input
+    ))
+    .step(
+        "Checks",
+        "that the SHA-256 recorded in each frame matches the bytes before it",
+    )
+    .step(
+        "Means",
+        "a mismatch says the file was modified between two embeddings",
+    )
+    .step("Modifies", "nothing")
+}
+
+/// Builds the plan of the `polyglot` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before building or unpacking.
+pub fn explain_polyglot(cmd: &PolyglotCmd) -> Plan {
+    let protection = protection(&cmd.algorithm.to_lowercase());
+    if cmd.extract {
+        return Plan::new(format!(
+            "read the ZIP at the end of {}, check the CRC of its entry and decrypt it",
+            cmd.input
+        ))
+        .step("Writes", format!("the PNG part alone to {}", cmd.output))
+        .warn_if(
+            protection.is_none(),
+            format!("unsupported algorithm {:?}", cmd.algorithm),
+        );
+    }
+    let mut plan = Plan::new(format!(
+        "write {}: the PNG {} followed by a ZIP whose entry {:?} holds the encrypted payload",
+        cmd.output, cmd.input, cmd.name
+    ))
+    .step(
+        "Why",
+        "image viewers show the PNG and archive tools list the entry, from the same file",
+    );
+    plan = match protection {
+        Some(text) => plan.step("Protection", text),
+        None => plan.warn_if(true, format!("unsupported algorithm {:?}", cmd.algorithm)),
+    };
+    plan.step(
+        "Destroyed by",
+        "any re-save of the image; some upload filters also reject polyglots",
+    )
+    .step(
+        "Extract with",
+        format!(
+            "stegano polyglot -x -i {} -a {} -k <key>, or unzip and decrypt",
+            cmd.output, cmd.algorithm
+        ),
+    )
+    .warn_if(
+        cmd.key == "key",
+        "the default key \"key\" is in use, set your own with -k",
+    )
+}
+
+/// Builds the plan of the `share` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before uploading.
+#[cfg(feature = "net")]
+pub fn explain_share(cmd: &ShareCmd) -> Plan {
+    let target = cmd.url.as_deref().unwrap_or(&cmd.provider);
+    Plan::new(format!("upload {} as is to {}", cmd.input, target))
+        .step(
+            "Exposure",
+            "anyone with the link can download the file; the key is never sent",
+        )
+        .step(
+            "Prints",
+            "the link and the decrypt command to send, without the key",
+        )
+        .warn_if(
+            cmd.provider.eq_ignore_ascii_case("custom") && cmd.url.is_none(),
+            "--provider custom needs --url",
+        )
+}
+
+/// Builds the plan of the `serve` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before listening.
+#[cfg(feature = "net")]
+pub fn explain_serve(cmd: &ServeCmd) -> Plan {
+    let queued = cmd.payload.len() + cmd.payload_file.len();
+    let plan = Plan::new(format!(
+        "serve the files of {} over plain HTTP on {}",
+        cmd.dir, cmd.bind
+    ))
+    .step(
+        "Embeds",
+        format!(
+            "the {} queued payload(s), one per GET of a carrier, with -m {} and -a {}; the \
+             files on disk stay untouched",
+            queued, cmd.method, cmd.algorithm
+        ),
+    )
+    .step(
+        "Then",
+        "serves the covers as they are, like any static server",
+    )
+    .step(
+        "Exposure",
+        "anyone reaching the address can download the files, and the payloads go to the \
+         first clients that ask",
+    );
+    plan.warn_if(
+        queued == 0,
+        "no payload is queued, give one with -p or --payload-file",
+    )
+    .warn_if(
+        !cmd.bind.starts_with("127.") && !cmd.bind.starts_with("localhost"),
+        format!("{} is reachable from other machines", cmd.bind),
+    )
+    .warn_if(
+        cmd.key == "key",
+        "the default key \"key\" is in use, set your own with -k",
+    )
+}
+
+/// Builds the plan of the `fetch` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before downloading.
+#[cfg(feature = "net")]
+pub fn explain_fetch(cmd: &FetchCmd) -> Plan {
+    let file_type = if cmd.r#type.eq_ignore_ascii_case("auto") {
+        "taken from the URL extension, or else the magic bytes".to_string()
+    } else {
+        cmd.r#type.to_lowercase()
+    };
+    let mut plan = Plan::new(format!(
+        "download {} and extract its payload in memory",
+        cmd.url
+    ))
+    .step("Type", file_type)
+    .step("Limit", "downloads over 256 MiB are refused");
+    if let Some(save) = &cmd.save {
+        plan = plan.step("Saves", format!("the stego file to {}", save));
+    }
+    if let Some(output) = &cmd.output {
+        plan = plan.step("Restores", format!("the carrier to {}", output));
+    }
+    plan.step("Reveals", "the decrypted payload on stdout")
+        .warn_if(
+            cmd.url.starts_with("http://"),
+            "the download isn't encrypted, only the payload is",
+        )
+}
+
+/// Builds the plan of the `soak` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before soaking.
+pub fn explain_soak(cmd: &SoakCmd) -> Plan {
+    let seed = match cmd.seed {
+        Some(seed) => format!("{}, so the run is reproducible", seed),
+        None => "taken from the clock and printed, to replay failures".to_string(),
+    };
+    Plan::new(format!(
+        "run {} iterations of random embed/extract round trips",
+        cmd.iterations
+    ))
+    .step(
+        "Each",
+        "hides random payloads in synthetic PNG, TIFF and ICO carriers with every method, plus \
+         a polyglot, a provenance chain and a round trip through a temp file",
+    )
+    .step("Seed", seed)
+    .step(
+        "Fails on",
+        "mismatches, panics, open file descriptors left behind and resident memory growing \
+         by more than 64 MiB",
+    )
+    .step(
+        "Modifies",
+        "nothing but short-lived files in the temp directory",
+    )
+}
+
+/// Builds the plan of the `diff` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before comparing.
+pub fn explain_diff(cmd: &DiffCmd) -> Plan {
+    let mut plan = Plan::new(format!(
+        "compare {} with {} chunk by chunk",
+        cmd.original, cmd.modified
+    ))
+    .step(
+        "Pairs",
+        "chunks by type and rank, so the second IDAT is compared with the second IDAT",
+    )
+    .step(
+        "Reports",
+        "added, removed and modified chunks with their offsets and the number of differing \
+         bytes; chunks shifted by an earlier change are listed as moved",
+    );
+    if cmd.pixels {
+        plan = plan.step(
+            "Pixels",
+            "decodes both images and counts the changed pixels and samples, their bounding box \
+             and how many only differ in the least significant bit",
+        );
+    }
+    if cmd.hex {
+        plan = plan.step(
+            "Hexdumps",
+            "each added, removed or modified chunk, from its length field to its CRC, with the \
+             bytes that differ highlighted",
+        );
+    }
+    plan.step("Modifies", "nothing")
+}
+
+/// Builds the plan of the `peek` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before inspecting.
+pub fn explain_peek(cmd: &PeekCmd) -> Plan {
+    let method = cmd.method.to_lowercase();
+    let mut plan = Plan::new(format!(
+        "look for a payload in {} without decrypting it",
+        cmd.input
+    ));
+    plan = match placement(&cmd.r#type.to_lowercase(), &method, &cmd.chunk_name) {
+        Some((place, _)) => plan.step("Looks", place),
+        None => plan.warn_if(
+            true,
+            format!(
+                "method {:?} isn't supported for {:?} files",
+                cmd.method, cmd.r#type
+            ),
+        ),
+    };
+    plan.step(
+        "Prints",
+        if cmd.hint_only {
+            "only the plaintext hint stored with --hint"
+        } else {
+            "the size of the encrypted payload, its shard number if it was split, its header \
+             and the plaintext hint if one was stored"
+        },
+    )
+    .step("Modifies", "nothing")
+    .warn_if(
+        matches!(method.as_str(), "lsb" | "robust"),
+        "the LSB and robust methods scatter the bits with the key, so they can't be inspected \
+         without it",
+    )
+}
+
+/// Builds the plan of the `config` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before reading or changing the configuration.
+pub fn explain_config(cmd: &ConfigCmd) -> Plan {
+    let path = Config::path().map_or_else(
+        || "the configuration file".to_string(),
+        |path| path.display().to_string(),
+    );
+    let plan = if cmd.set.is_empty() && cmd.unset.is_empty() {
+        Plan::new(format!("print the defaults set in {}", path)).step("Modifies", "nothing")
+    } else {
+        Plan::new(format!("change the defaults in {}", path))
+            .step(
+                "Sets",
+                if cmd.set.is_empty() {
+                    "nothing".to_string()
+                } else {
+                    cmd.set.join(", ")
+                },
+            )
+            .step(
+                "Removes",
+                if cmd.unset.is_empty() {
+                    "nothing".to_string()
+                } else {
+                    cmd.unset.join(", ")
+                },
+            )
+            .step(
+                "Modifies",
+                "the configuration file, rewritten whole; comments aren't kept",
+            )
+    };
+    plan.step(
+        "Applies",
+        "to every later command, for the flags not given on the command line",
+    )
+}
+
+/// Builds the plan of the `rekey` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The `Plan` to print before re-encrypting.
+pub fn explain_rekey(cmd: &RekeyCmd) -> Plan {
+    let file_type = cmd.r#type.to_lowercase();
+    let method = cmd.method.to_lowercase();
+    let new_algorithm = cmd
+        .new_algorithm
+        .as_deref()
+        .unwrap_or(&cmd.algorithm)
+        .to_lowercase();
+    let mut plan = Plan::new(if cmd.input == cmd.output {
+        format!(
+            "re-encrypt the payload of {} with the new key, replacing the file",
+            cmd.input
+        )
+    } else {
+        format!(
+            "re-encrypt the payload of {} with the new key and write the result to {}; the \
+             input is left untouched",
+            cmd.input, cmd.output
+        )
+    });
+    plan = match placement(&file_type, &method, &cmd.chunk_name) {
+        Some(_) if method == "lsb" => plan.step(
+            "Where",
+            "in the pixels the new key chooses, in the same image",
+        ),
+        Some(_) if method == "robust" => plan.step(
+            "Where",
+            "in the blocks the new key chooses, in the same image",
+        ),
+        Some(_) => plan.step(
+            "Where",
+            "where it already is: the same chunk and offset, tag, strip, slack or frame",
+        ),
+        None => plan.warn_if(
+            true,
+            format!(
+                "method {:?} isn't supported for {:?} files",
+                cmd.method, cmd.r#type
+            ),
+        ),
+    };
+    plan = match protection(&new_algorithm) {
+        Some(text) => plan.step("Protection", text),
+        None => plan.warn_if(true, format!("unsupported algorithm {:?}", new_algorithm)),
+    };
+    if method == "lsb" {
+        plan = plan.step(
+            "Old key",
+            "the pixels it chose are overwritten with noise, so it no longer reads the payload",
+        );
+    }
+    if method == "robust" {
+        plan = plan.step(
+            "Old key",
+            "its blocks are overwritten with noise, so it no longer reads the payload",
+        );
+    }
+    if cmd.in_place {
+        plan = plan.step(
+            "Writes",
+            "only the bytes that changed if the file keeps its size, otherwise the whole file",
+        );
+    }
+    plan.step(
+        "Keeps",
+        "the plaintext hint and the provenance link, if any",
+    )
+    .step(
+        "Extract with",
+        format!(
+            "stegano decrypt -i {} -t {} -m {} -a {} -k <new key>",
+            cmd.output, cmd.r#type, cmd.method, new_algorithm
+        ),
+    )
+    .warn_if(
+        true,
+        "legacy payloads carry no signed header, and precision payloads only a check byte, so \
+         a wrong old key may re-encrypt garbage: keep the input until the new key has been \
+         checked",
+    )
+    .warn_if(
+        cmd.input == cmd.output,
+        "the output replaces the input, the payload is lost if the old key is wrong",
+    )
+}
+
+/// Builds the plan of the `watermark` subcommand.
+///
+/// # Arguments
+///
+/// * `cmd` - The parsed options.
+///
+/// # Returns
+///
+/// The plan.
+pub fn explain_watermark(cmd: &WatermarkCmd) -> Plan {
+    match &cmd.action {
+        WatermarkAction::Embed(embed) => Plan::new(format!(
+            "watermark a copy of {} with the owner ID {:?}, written to {}",
+            embed.input, embed.owner, embed.output
+        ))
+        .step(
+            "Where",
+            "in two patterns of 2x2 pixel cells over the whole image, drawn from the key: one \
+             spreads the bits of the owner ID, the other proves it",
+        )
+        .step(
+            "Changes",
+            format!("the luma of every cell by up to {} levels", embed.strength),
+        )
+        .step(
+            "Survives",
+            "noise and JPEG re-compression once converted back to PNG, not resizing or cropping",
+        )
+        .step(
+            "Verify with",
+            format!(
+                "stegano watermark verify -i {} -k <key> --owner {:?}",
+                embed.output, embed.owner
+            ),
+        )
+        .warn_if(
+            embed.owner.is_empty() || embed.owner.len() > MAX_OWNER_LEN,
+            format!("owner IDs are 1 to {} bytes long", MAX_OWNER_LEN),
+        )
+        .warn_if(
+            true,
+            "the owner ID isn't encrypted: anyone with the key can read it",
+        ),
+        WatermarkAction::Verify(verify) => {
+            Plan::new(format!(
+                "look for a watermark in {} without the original",
+                verify.input
+            ))
+            .step(
+                "Reads",
+                "the owner ID from the correlation of the high-passed image with the pattern of \
+             each bit",
+            )
+            .step(
+                "Scores",
+                format!(
+                    "the correlation with the proof pattern of {}, in standard deviations above \
+                 chance; {} or more counts as present",
+                    verify.owner.as_deref().map_or(
+                        "the owner ID read".to_string(),
+                        |owner| format!("{:?}", owner)
+                    ),
+                    WATERMARK_THRESHOLD
+                ),
+            )
+            .step("Modifies", "nothing")
+        }
+    }
+}
+
+/// Describes a position of `inject-chunk --position`.
+fn describe_position(position: &ChunkPosition) -> String {
+    match position {
+        ChunkPosition::After(r#type) => format!(
+            "right after the last {:?} chunk",
+            String::from_utf8_lossy(r#type)
+        ),
+        ChunkPosition::Before(r#type) => format!(
+            "right before the first {:?} chunk",
+            String::from_utf8_lossy(r#type)
+        ),
+        ChunkPosition::Index(index) => format!("at index {}, IHDR being 0", index),
+    }
+}
+
+/// Explains what `inject-chunk` is about to do.
+///
+/// # Returns
+///
+/// The `Plan` to print before inserting the chunk.
+pub fn explain_inject_chunk(cmd: &InjectChunkCmd) -> Plan {
+    let chunk_type = ChunkType::from(cmd.chunk_type);
+    let r#type = chunk_type.to_string();
+    Plan::new(format!(
+        "insert a chunk of type {:?} holding the bytes of {} into a copy of {}, written to {}",
+        r#type, cmd.data_file, cmd.input, cmd.output
+    ))
+    .step("Where", describe_position(&cmd.position))
+    .step(
+        "Writes",
+        "the data as is, with its length and CRC: nothing is encrypted or checked",
+    )
+    .warn_if(
+        chunk_type.is_critical(),
+        format!(
+            "{:?} is a critical chunk type, which decoders must understand: an unknown or \
+             misplaced one makes the file unreadable",
+            r#type
+        ),
+    )
+    .warn_if(
+        chunk_type.is_public() && !chunk_type.is_critical(),
+        "public chunk types are reserved for registered chunks, use a lowercase second letter",
+    )
+}
+
+/// Explains what `remove-chunk` is about to do.
+///
+/// # Returns
+///
+/// The `Plan` to print before removing the chunks.
+pub fn explain_remove_chunk(cmd: &RemoveChunkCmd) -> Plan {
+    let selection = match (&cmd.chunk_type, cmd.index) {
+        (Some(r#type), _) => format!("every {:?} chunk", String::from_utf8_lossy(r#type)),
+        (None, Some(index)) => format!("the chunk at index {}", index),
+        (None, None) => "nothing".to_string(),
+    };
+    let mut plan = Plan::new(format!(
+        "remove {} from a copy of {}, written to {}",
+        selection, cmd.input, cmd.output
+    ))
+    .step(
+        "Keeps",
+        "the other chunks byte for byte, and any data after IEND",
+    )
+    .step(
+        "Refuses",
+        if cmd.force {
+            "IHDR and IEND, without which the file isn't a PNG"
+        } else {
+            "critical chunks (IHDR, PLTE, IDAT, IEND...), unless --force is given"
+        },
+    )
+    .warn_if(
+        cmd.force,
+        "removing PLTE or IDAT chunks leaves an image that can't be decoded",
+    );
+    if cmd.wipe {
+        plan = plan.step(
+            "Wipes",
+            format!(
+                "the removed chunks in {} with random data, {} time(s), before the output is \
+                 written",
+                cmd.input, cmd.passes
+            ),
+        );
+    }
+    if cmd.shred_input {
+        plan = plan.step(
+            "Shreds",
+            format!(
+                "{} with random data, {} time(s), then removes it, once the output is written",
+                cmd.input, cmd.passes
+            ),
+        );
+    }
+    plan.warn_if(
+        cmd.wipe || cmd.shred_input,
+        "copy-on-write file systems, journals and SSDs may keep the old bytes elsewhere on the \
+         disk",
+    )
+}
+
+/// Explains what `extract-chunk` is about to do.
+///
+/// # Returns
+///
+/// The `Plan` to print before writing the chunk data.
+pub fn explain_extract_chunk(cmd: &ExtractChunkCmd) -> Plan {
+    let (selection, files) = match (&cmd.chunk_type, cmd.index) {
+        (Some(r#type), _) if cmd.all => (
+            format!("every {:?} chunk", String::from_utf8_lossy(r#type)),
+            format!(
+                "one per chunk, numbered from 1 after {}: {}, {} and so on",
+                cmd.output,
+                shard_path(&cmd.output, 0),
+                shard_path(&cmd.output, 1)
+            ),
+        ),
+        (Some(r#type), _) => (
+            format!("the first {:?} chunk", String::from_utf8_lossy(r#type)),
+            cmd.output.clone(),
+        ),
+        (None, index) => (
+            format!("the chunk at index {}", index.unwrap_or_default()),
+            cmd.output.clone(),
+        ),
+    };
+    Plan::new(format!(
+        "write the data of {} in {} to files",
+        selection, cmd.input
+    ))
+    .step("Writes", files)
+    .step(
+        "Contents",
+        "the chunk data as stored, without its length, type and CRC; compressed chunks \
+             such as iCCP or zTXt stay compressed",
+    )
+    .step("Modifies", "nothing: the input is only read")
+}
+
+/// Explains what `icc` is about to do.
+///
+/// # Returns
+///
+/// The `Plan` to print before reading or replacing the profile.
+pub fn explain_icc(cmd: &IccCmd) -> Plan {
+    let jpeg = matches!(cmd.r#type.to_lowercase().as_str(), "jpeg" | "jpg");
+    match (&cmd.inject_icc, &cmd.output) {
+        (Some(path), Some(output)) => Plan::new(format!(
+            "replace the ICC profile of {} with the one in {}, written to {}",
+            cmd.input, path, output
+        ))
+        .step(
+            "Writes",
+            if jpeg {
+                "the profile in APP2 segments of at most 65519 bytes each, where the old ones \
+                 were or after the JFIF and Exif segments"
+            } else {
+                "the profile compressed in an iCCP chunk right after IHDR, dropping any sRGB \
+                 chunk, which can't be used with a profile"
+            },
+        )
+        .step(
+            "Checks",
+            "only the acsp signature of the profile header; the profile isn't validated",
+        ),
+        _ => Plan::new(format!(
+            "write the ICC profile of {} to {}",
+            cmd.input,
+            cmd.extract_icc.as_deref().unwrap_or_default()
+        ))
+        .step(
+            "Reads",
+            if jpeg {
+                "every APP2 ICC_PROFILE segment before the first scan, put back in order by \
+                 their sequence numbers; a missing one fails"
+            } else {
+                "the first iCCP chunk, decompressed"
+            },
+        )
+        .step("Modifies", "nothing: the input is only read"),
+    }
+}
+
+/// Explains what `vault` is about to do.
+///
+/// # Returns
+///
+/// The `Plan` to print before touching the vault.
+pub fn explain_vault(cmd: &VaultCmd) -> Plan {
+    let index = String::from_utf8_lossy(VAULT_INDEX_CHUNK);
+    let file = String::from_utf8_lossy(VAULT_FILE_CHUNK);
+    match &cmd.action {
+        VaultAction::Put(put) => {
+            let output = put.output.as_deref().unwrap_or(&put.input);
+            Plan::new(format!(
+                "store {} in the vault of {} as {:?}, written to {}",
+                put.file,
+                put.input,
+                put.name.as_deref().unwrap_or(&put.file),
+                output
+            ))
+            .step(
+                "Where",
+                format!(
+                    "in a {:?} chunk of its own before IEND, encrypted with {} behind a signed \
+                     header; a file of the same name is replaced",
+                    file,
+                    put.algorithm.to_lowercase()
+                ),
+            )
+            .step(
+                "Index",
+                format!(
+                    "the {:?} chunk listing the names, sizes and digests is decrypted, updated \
+                     and encrypted again, or created if the carrier has no vault yet",
+                    index
+                ),
+            )
+            .warn_if(
+                output == put.input,
+                "the input is rewritten in place, through a temp file renamed over it",
+            )
+        }
+        VaultAction::Ls(ls) => Plan::new(format!("list the files in the vault of {}", ls.input))
+            .step(
+                "Reads",
+                format!("the {:?} chunk only, decrypted with the key", index),
+            )
+            .step("Modifies", "nothing"),
+        VaultAction::Get(get) => Plan::new(format!(
+            "read {:?} from the vault of {} and write it to {}",
+            get.name,
+            get.input,
+            get.output.as_deref().unwrap_or("stdout")
+        ))
+        .step(
+            "Finds",
+            format!(
+                "the file in the {:?} index, then decrypts its {:?} chunk",
+                index, file
+            ),
+        )
+        .step(
+            "Checks",
+            "the contents against the SHA-256 recorded in the index",
+        ),
+        VaultAction::Rm(rm) => {
+            let output = rm.output.as_deref().unwrap_or(&rm.input);
+            Plan::new(format!(
+                "remove {:?} from the vault of {}, written to {}",
+                rm.name, rm.input, output
+            ))
+            .step(
+                "Removes",
+                format!(
+                    "its {:?} chunk, and its entry from the {:?} index, which is encrypted again",
+                    file, index
+                ),
+            )
+            .warn_if(
+                output == rm.input,
+                "the input is rewritten in place, through a temp file renamed over it",
+            )
+        }
+    }
+}
+
+/// Explains what `meta` is about to do.
+///
+/// # Returns
+///
+/// The `Plan` to print before touching the metadata.
+pub fn explain_meta(cmd: &MetaCmd) -> Plan {
+    match &cmd.action {
+        MetaAction::Set(set) => {
+            let output = set.output.as_deref().unwrap_or(&set.input);
+            let mut changes: Vec<String> = set
+                .text
+                .iter()
+                .map(|(key, _)| format!("{:?}", key))
+                .collect();
+            if set.time.is_some() {
+                changes.push("the timestamp".into());
+            }
+            Plan::new(format!(
+                "set {} in {}, written to {}",
+                changes.join(", "),
+                set.input,
+                output
+            ))
+            .step(
+                "Writes",
+                "a tEXt chunk per entry, in Latin-1, and a tIME chunk in UTC for the timestamp, \
+                 all before IEND",
+            )
+            .step(
+                "Replaces",
+                "the tEXt chunks of the same keywords and the tIME chunk, if any; iTXt, zTXt \
+                 and every other chunk are kept as they are",
+            )
+            .warn_if(
+                output == set.input,
+                "the input is rewritten in place, through a temp file renamed over it",
+            )
+        }
+        MetaAction::Get(get) => Plan::new(match &get.key {
+            Some(key) => format!("print the {:?} text entry of {}", key, get.input),
+            None => format!("print the text entries and timestamp of {}", get.input),
+        })
+        .step(
+            "Reads",
+            "the tEXt and tIME chunks, decoded from Latin-1 and UTC",
+        )
+        .step("Modifies", "nothing: the input is only read"),
+        MetaAction::Remove(remove) => {
+            let output = remove.output.as_deref().unwrap_or(&remove.input);
+            let mut changes: Vec<String> = if remove.all_text {
+                vec!["every text entry".into()]
+            } else {
+                remove.keys.iter().map(|key| format!("{:?}", key)).collect()
+            };
+            if remove.time {
+                changes.push("the timestamp".into());
+            }
+            Plan::new(format!(
+                "remove {} from {}, written to {}",
+                changes.join(", "),
+                remove.input,
+                output
+            ))
+            .step(
+                "Removes",
+                "the matching tEXt chunks and the tIME chunk; it fails if none of them is there",
+            )
+            .warn_if(
+                output == remove.input,
+                "the input is rewritten in place, through a temp file renamed over it",
+            )
+        }
+    }
+}
+
+/// Explains what `generate-carrier` is about to do.
+///
+/// # Returns
+///
+/// The `Plan` to print before generating the image.
+pub fn explain_generate_carrier(cmd: &GenerateCarrierCmd) -> Plan {
+    let look = match cmd.style.as_str() {
+        "noise" => "every sample drawn at random, like static",
+        "gradient" => "a blend of two random colours at a random angle, with grain",
+        _ => "fractal Perlin noise over three random colours, like clouds, with grain",
+    };
+    let seed = match cmd.seed {
+        Some(seed) => format!("{}, so the same image comes out every time", seed),
+        None => "taken from the clock and printed, to generate the image again".to_string(),
+    };
+    let size = match &cmd.fit_payload {
+        Some(path) => format!(
+            "{}x{}, grown with the same aspect ratio until the encrypted {} fills at most a \
+             quarter of the LSB capacity",
+            cmd.width, cmd.height, path
+        ),
+        None => format!(
+            "{}x{}, safely holding {} bytes with --method lsb",
+            cmd.width,
+            cmd.height,
+            safe_capacity(cmd.width, cmd.height)
+        ),
+    };
+    Plan::new(format!(
+        "synthesize a {} PNG carrier, written to {}",
+        cmd.style, cmd.output
+    ))
+    .step("Looks like", look)
+    .step("Size", size)
+    .step("Seed", seed)
+    .step(
+        "Then",
+        format!(
+            "stegano encrypt -i {} -m lsb -k <key> --payload-file <file>",
+            cmd.output
+        ),
+    )
+    .warn_if(
+        !(1..=MAX_COVER_PIXELS).contains(&cmd.width.saturating_mul(cmd.height)),
+        format!("carriers are 1 to {} pixels", MAX_COVER_PIXELS),
+    )
+    .warn_if(
+        true,
+        "a synthetic image with no camera metadata draws attention of its own: it hides the \
+         payload, not the fact that something was sent",
+    )
+}
+
+/// Explains what `gui` is about to do.
+///
+/// # Returns
+///
+/// The `Plan` to print before opening the window.
+#[cfg(feature = "gui")]
+pub fn explain_gui(cmd: &GuiCmd) -> Plan {
+    Plan::new("open a window to hide a payload in a carrier, or reveal one")
+        .step(
+            "Carrier",
+            match &cmd.input {
+                Some(input) => format!("{}, or any file dropped onto the window", input),
+                None => "any file dropped onto the window".to_string(),
+            },
+        )
+        .step(
+            "Detects",
+            "the carrier type from its magic bytes, as with -t auto",
+        )
+        .step(
+            "Hides",
+            format!(
+                "the typed payload with -a {} and the chosen method, into <name>-stego next to \
+                 the carrier unless another path is given",
+                cmd.algorithm
+            ),
+        )
+        .warn_if(
+            cmd.key.is_none(),
+            "no key is filled in, type one into the window before hiding or revealing",
+        )
+}
@@ -0,0 +1,205 @@
+use crate::codec::{decode_bytes, encode_bytes, CodecOptions};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+use std::slice;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records the error message returned by `stegano_last_error`.
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// A byte buffer allocated by the library, released with `stegano_buffer_free`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SteganoBuffer {
+    /// Pointer to the first byte, or NULL for an empty buffer.
+    pub data: *mut u8,
+    /// Number of bytes.
+    pub len: usize,
+}
+
+impl SteganoBuffer {
+    /// Hands a vector over to the caller.
+    fn from_vec(data: Vec<u8>) -> Self {
+        let mut data = data.into_boxed_slice();
+        let len = data.len();
+        let ptr = data.as_mut_ptr();
+        std::mem::forget(data);
+        SteganoBuffer { data: ptr, len }
+    }
+}
+
+/// Embedding options. Any NULL field, or a NULL options pointer, falls back to the CLI
+/// defaults: "png", "auto", "aes", "key" and "stEg".
+#[repr(C)]
+#[derive(Debug)]
+pub struct SteganoOptions {
+    /// The carrier type: "png", "tiff", "ico", "mp4", "mp3", "flac", "svg" or "text".
+    pub file_type: *const c_char,
+    /// The embedding method: "auto", "append", "precision", "lsb", "robust", "tag", "strip" or "slack".
+    pub method: *const c_char,
+    /// The encryption algorithm: "aes", "aes192", "aes256", "chacha", "xor", or "age" with the `age` feature.
+    pub algorithm: *const c_char,
+    /// The encryption key.
+    pub key: *const c_char,
+    /// The PNG chunk holding the payload.
+    pub chunk_name: *const c_char,
+}
+
+/// Converts C options into codec options.
+///
+/// # Safety
+///
+/// `options` must be NULL or point to a valid `SteganoOptions` whose non-NULL fields are
+/// NUL-terminated strings.
+unsafe fn codec_options(options: *const SteganoOptions) -> Result<CodecOptions, String> {
+    let mut opts = CodecOptions::default();
+    let Some(options) = options.as_ref() else {
+        return Ok(opts);
+    };
+    for (field, target) in [
+        (options.file_type, &mut opts.file_type),
+        (options.method, &mut opts.method),
+        (options.algorithm, &mut opts.algorithm),
+        (options.key, &mut opts.key),
+        (options.chunk_name, &mut opts.chunk_name),
+    ] {
+        if !field.is_null() {
+            *target = CStr::from_ptr(field)
+                .to_str()
+                .map_err(|_| "Options must be valid UTF-8".to_string())?
+                .to_string();
+        }
+    }
+    Ok(opts)
+}
+
+/// Borrows a C buffer as a slice, accepting NULL for empty buffers.
+///
+/// # Safety
+///
+/// `data` must be NULL or point to `len` readable bytes.
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], String> {
+    match (data.is_null(), len) {
+        (true, 0) => Ok(&[]),
+        (true, _) => Err("NULL buffer with a non-zero length".to_string()),
+        (false, _) => Ok(slice::from_raw_parts(data, len)),
+    }
+}
+
+/// Encrypts `payload` and embeds it into `carrier`.
+///
+/// On success, returns 0 and stores the stego file in `out`. On failure, returns -1 and the
+/// reason is available from `stegano_last_error`.
+///
+/// # Safety
+///
+/// `carrier` and `payload` must point to `carrier_len` and `payload_len` readable bytes (or
+/// be NULL when empty), `options` must follow `SteganoOptions` rules and `out` must be a
+/// valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn stegano_encrypt_buffer(
+    carrier: *const u8,
+    carrier_len: usize,
+    payload: *const u8,
+    payload_len: usize,
+    options: *const SteganoOptions,
+    out: *mut SteganoBuffer,
+) -> i32 {
+    let result = (|| {
+        if out.is_null() {
+            return Err("NULL output buffer".to_string());
+        }
+        let opts = codec_options(options)?;
+        encode_bytes(
+            input(carrier, carrier_len)?,
+            input(payload, payload_len)?,
+            &opts,
+        )
+        .map_err(|err| err.to_string())
+    })();
+    match result {
+        Ok(stego) => {
+            *out = SteganoBuffer::from_vec(stego);
+            0
+        }
+        Err(message) => {
+            set_last_error(message);
+            -1
+        }
+    }
+}
+
+/// Extracts and decrypts the payload of `stego`.
+///
+/// On success, returns 0, stores the payload in `payload_out` and, if `carrier_out` isn't
+/// NULL, the restored carrier in `carrier_out`. On failure, returns -1 and the reason is
+/// available from `stegano_last_error`.
+///
+/// # Safety
+///
+/// `stego` must point to `stego_len` readable bytes, `options` must follow `SteganoOptions`
+/// rules, `payload_out` must be a valid pointer and `carrier_out` valid or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn stegano_decrypt_buffer(
+    stego: *const u8,
+    stego_len: usize,
+    options: *const SteganoOptions,
+    payload_out: *mut SteganoBuffer,
+    carrier_out: *mut SteganoBuffer,
+) -> i32 {
+    let result = (|| {
+        if payload_out.is_null() {
+            return Err("NULL output buffer".to_string());
+        }
+        let opts = codec_options(options)?;
+        decode_bytes(input(stego, stego_len)?, &opts).map_err(|err| err.to_string())
+    })();
+    match result {
+        Ok(decoded) => {
+            *payload_out = SteganoBuffer::from_vec(decoded.payload);
+            if let Some(carrier_out) = carrier_out.as_mut() {
+                *carrier_out = SteganoBuffer::from_vec(decoded.carrier);
+            }
+            0
+        }
+        Err(message) => {
+            set_last_error(message);
+            -1
+        }
+    }
+}
+
+/// Releases a buffer returned by the library. Freeing an empty buffer is a no-op.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by this library and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn stegano_buffer_free(buffer: SteganoBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+/// Returns the message of the last error raised on the calling thread, or NULL if none.
+///
+/// The string is owned by the library and stays valid until the next failing call on the
+/// same thread.
+#[no_mangle]
+pub extern "C" fn stegano_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
@@ -0,0 +1,483 @@
+use crate::outln;
+use crate::storage::map_input;
+use crate::style::Style;
+use std::error::Error;
+use std::io::{self, ErrorKind, Read, Write};
+
+/// The application ID of the `APPLICATION` blocks holding a payload.
+pub const APPLICATION_ID: &[u8; 4] = b"stEg";
+
+/// Magic marker opening a payload stored in a `PADDING` block.
+pub const PADDING_MAGIC: &[u8; 4] = b"STGF";
+
+/// The length of the frame in front of a payload in a `PADDING` block: the magic, whether the
+/// block was added for the payload, and the payload length.
+const PADDING_FRAME_LEN: usize = 9;
+
+/// The largest length a metadata block can declare, in its 24-bit field.
+const MAX_BLOCK_LEN: u64 = (1 << 24) - 1;
+
+/// The `PADDING` block type.
+const PADDING: u8 = 1;
+
+/// The `APPLICATION` block type.
+const APPLICATION: u8 = 2;
+
+/// A metadata block of a FLAC file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlacBlock {
+    /// The block type, such as 0 for `STREAMINFO` or 4 for `VORBIS_COMMENT`.
+    pub kind: u8,
+    /// Whether the header flags the block as the last one before the audio frames.
+    pub last: bool,
+    /// The offset of the 4-byte block header in the file.
+    pub offset: usize,
+    /// The length of the block data, without its header.
+    pub len: usize,
+}
+
+impl FlacBlock {
+    /// Returns the offset of the block data.
+    pub fn body(&self) -> usize {
+        self.offset + 4
+    }
+
+    /// Returns the end offset of the block.
+    pub fn end(&self) -> usize {
+        self.body() + self.len
+    }
+
+    /// Returns the name of the block type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::flac::FlacBlock;
+    ///
+    /// let block = FlacBlock { kind: 4, last: false, offset: 42, len: 40 };
+    /// assert_eq!(block.kind_name(), "VORBIS_COMMENT");
+    /// ```
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            0 => "STREAMINFO",
+            1 => "PADDING",
+            2 => "APPLICATION",
+            3 => "SEEKTABLE",
+            4 => "VORBIS_COMMENT",
+            5 => "CUESHEET",
+            6 => "PICTURE",
+            _ => "reserved",
+        }
+    }
+}
+
+/// The kind of block a payload is hidden in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadBlock {
+    /// An `APPLICATION` block with the `APPLICATION_ID` application ID.
+    Application,
+    /// A `PADDING` block: the existing one when it is large enough, a new one otherwise.
+    Padding,
+}
+
+impl PayloadBlock {
+    /// Parses an embedding method: auto or application, or padding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::flac::PayloadBlock;
+    ///
+    /// assert_eq!(PayloadBlock::new("auto").unwrap(), PayloadBlock::Application);
+    /// assert_eq!(PayloadBlock::new("Padding").unwrap(), PayloadBlock::Padding);
+    /// assert!(PayloadBlock::new("picture").is_err());
+    /// ```
+    pub fn new(method: &str) -> io::Result<PayloadBlock> {
+        match method.to_lowercase().as_str() {
+            "auto" | "application" => Ok(PayloadBlock::Application),
+            "padding" => Ok(PayloadBlock::Padding),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unsupported method {:?} for FLAC files, use application or padding",
+                    method
+                ),
+            )),
+        }
+    }
+}
+
+/// Returns the header of a metadata block.
+fn block_header(kind: u8, last: bool, len: u64) -> [u8; 4] {
+    let len = (len as u32).to_be_bytes();
+    [kind | if last { 0x80 } else { 0 }, len[1], len[2], len[3]]
+}
+
+/// Represents the metadata blocks of a FLAC file, over its bytes.
+///
+/// The bytes are borrowed, so long recordings can be parsed from a `MappedFile` without
+/// copying them.
+#[derive(Debug, Clone)]
+pub struct FlacFile<'a> {
+    /// The metadata blocks, from `STREAMINFO` to the one flagged as last.
+    pub blocks: Vec<FlacBlock>,
+    /// Raw bytes of the file.
+    pub data: &'a [u8],
+}
+
+impl<'a> FlacFile<'a> {
+    /// Parses the metadata blocks of a FLAC file.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The raw bytes of the FLAC file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed `FlacFile`, or an `InvalidData` error if the file
+    /// doesn't start with `fLaC` or a block runs past the end of the file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::flac::FlacFile;
+    ///
+    /// let mut flac = b"fLaC\0\0\0\x22".to_vec();
+    /// flac.extend_from_slice(&[0; 34]);
+    /// flac.extend_from_slice(&[0x81, 0, 0, 8]);
+    /// flac.extend_from_slice(&[0; 8]);
+    /// flac.extend_from_slice(&[0xFF, 0xF8, 0x69, 0x08]);
+    /// let file = FlacFile::parse(&flac).unwrap();
+    /// let kinds: Vec<&str> = file.blocks.iter().map(|b| b.kind_name()).collect();
+    /// assert_eq!(kinds, ["STREAMINFO", "PADDING"]);
+    /// assert_eq!(file.audio_offset(), 54);
+    ///
+    /// // The padding block declares more bytes than the file holds.
+    /// assert!(FlacFile::parse(&flac[..48]).is_err());
+    /// ```
+    pub fn parse(data: &'a [u8]) -> io::Result<FlacFile<'a>> {
+        let invalid = |msg: String| io::Error::new(ErrorKind::InvalidData, msg);
+        if !data.starts_with(b"fLaC") {
+            return Err(invalid(
+                "Not a FLAC file: it doesn't start with fLaC".into(),
+            ));
+        }
+        let mut blocks = Vec::new();
+        let mut pos = 4;
+        loop {
+            let Some(head) = data.get(pos..pos + 4) else {
+                return Err(invalid(format!("Truncated block header at offset {}", pos)));
+            };
+            let len = u32::from_be_bytes([0, head[1], head[2], head[3]]) as usize;
+            let block = FlacBlock {
+                kind: head[0] & 0x7F,
+                last: head[0] & 0x80 != 0,
+                offset: pos,
+                len,
+            };
+            if block.end() > data.len() {
+                return Err(invalid(format!(
+                    "The {} block at offset {} declares {} bytes, past the end of the file",
+                    block.kind_name(),
+                    pos,
+                    len
+                )));
+            }
+            pos = block.end();
+            let last = block.last;
+            blocks.push(block);
+            if last {
+                return Ok(FlacFile { blocks, data });
+            }
+        }
+    }
+
+    /// Returns the offset right after the metadata blocks, where the audio frames begin.
+    pub fn audio_offset(&self) -> usize {
+        self.blocks.last().map_or(4, FlacBlock::end)
+    }
+
+    /// Returns the sample rate, channel count, bits per sample and total number of samples
+    /// from the `STREAMINFO` block, if it is the first block as it must be.
+    pub fn stream_info(&self) -> Option<(u32, u8, u8, u64)> {
+        let block = self.blocks.first().filter(|b| b.kind == 0 && b.len >= 18)?;
+        let info = &self.data[block.body() + 10..block.body() + 18];
+        let packed = u64::from_be_bytes(info.try_into().unwrap());
+        Some((
+            (packed >> 44) as u32,
+            ((packed >> 41) & 0x7) as u8 + 1,
+            ((packed >> 36) & 0x1F) as u8 + 1,
+            packed & 0xF_FFFF_FFFF,
+        ))
+    }
+
+    /// Hides a payload in an `APPLICATION` block or a `PADDING` block.
+    ///
+    /// An `APPLICATION` block is added after the last metadata block, where players skip it.
+    /// A `PADDING` payload goes into the existing padding when it is zeroed and large
+    /// enough, leaving the size of the file unchanged as tag editors do, or into a new
+    /// padding block. Either way the audio frames are copied as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The bytes to hide.
+    /// * `kind` - The block to hide them in.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the new FLAC file, or an `InvalidInput` error if
+    /// the payload doesn't fit in a block.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::flac::{FlacFile, PayloadBlock};
+    ///
+    /// let mut flac = b"fLaC\0\0\0\x22".to_vec();
+    /// flac.extend_from_slice(&[0; 34]);
+    /// flac.extend_from_slice(&[0x81, 0, 0, 32]);
+    /// flac.extend_from_slice(&[0; 32]);
+    /// flac.extend_from_slice(&[0xFF, 0xF8, 0x69, 0x08]);
+    /// for (kind, payload) in [
+    ///     (PayloadBlock::Application, &b"secret"[..]),
+    ///     (PayloadBlock::Padding, b"fits in the padding"),
+    ///     (PayloadBlock::Padding, b"is too long for the padding"),
+    /// ] {
+    ///     let stego = FlacFile::parse(&flac).unwrap().embed_in_block(payload, kind).unwrap();
+    ///     let file = FlacFile::parse(&stego).unwrap();
+    ///     assert_eq!(file.extract_payload().unwrap(), payload);
+    ///     assert_eq!(file.remove_payload().unwrap(), flac);
+    /// }
+    /// ```
+    pub fn embed_in_block(&self, payload: &[u8], kind: PayloadBlock) -> io::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(self.data.len() + payload.len() + 16);
+        self.write_block_embedded(payload, payload.len() as u64, kind, &mut out)?;
+        Ok(out)
+    }
+
+    /// Streams the file with a payload block to `writer`.
+    ///
+    /// This is the streaming counterpart of `embed_in_block`, used when the payload is too
+    /// big to be held in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The source of the bytes to hide.
+    /// * `len` - The number of bytes to read from `payload`.
+    /// * `kind` - The block to hide them in.
+    /// * `writer` - The destination of the new FLAC file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the offset of the payload in the new file.
+    pub fn write_block_embedded<R: Read, W: Write>(
+        &self,
+        payload: R,
+        len: u64,
+        kind: PayloadBlock,
+        mut writer: W,
+    ) -> io::Result<u64> {
+        let len32 = u32::try_from(len)
+            .ok()
+            .filter(|&len| len as u64 + 9 <= MAX_BLOCK_LEN);
+        let Some(len32) = len32 else {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Payload too large for a FLAC metadata block, which holds up to 16 MiB",
+            ));
+        };
+        // The existing padding is reused when the payload fits and it holds nothing else.
+        let padding = self.blocks.iter().find(|b| {
+            kind == PayloadBlock::Padding
+                && b.kind == PADDING
+                && b.len >= PADDING_FRAME_LEN + len as usize
+                && self.data[b.body()..b.end()].iter().all(|&byte| byte == 0)
+        });
+        let (offset, tail) = match (kind, padding) {
+            (PayloadBlock::Padding, Some(block)) => {
+                writer.write_all(&self.data[..block.body()])?;
+                writer.write_all(PADDING_MAGIC)?;
+                writer.write_all(&[0])?;
+                writer.write_all(&len32.to_be_bytes())?;
+                (
+                    block.body() + PADDING_FRAME_LEN,
+                    block.body() + PADDING_FRAME_LEN + len as usize,
+                )
+            }
+            _ => {
+                let audio = self.audio_offset();
+                let last = self.blocks.last().map_or(4, |b| b.offset);
+                writer.write_all(&self.data[..last])?;
+                writer.write_all(&[self.data[last] & 0x7F])?;
+                writer.write_all(&self.data[last + 1..audio])?;
+                let mut header = Vec::with_capacity(13);
+                if kind == PayloadBlock::Application {
+                    header.extend_from_slice(&block_header(APPLICATION, true, 4 + len));
+                    header.extend_from_slice(APPLICATION_ID);
+                } else {
+                    header.extend_from_slice(&block_header(
+                        PADDING,
+                        true,
+                        PADDING_FRAME_LEN as u64 + len,
+                    ));
+                    header.extend_from_slice(PADDING_MAGIC);
+                    header.push(1);
+                    header.extend_from_slice(&len32.to_be_bytes());
+                }
+                writer.write_all(&header)?;
+                (audio + header.len(), audio)
+            }
+        };
+        let copied = io::copy(&mut payload.take(len), &mut writer)?;
+        if copied != len {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Payload shorter than announced",
+            ));
+        }
+        writer.write_all(&self.data[tail..])?;
+        Ok(offset as u64)
+    }
+
+    /// Returns the last block holding a payload, and the range of the payload in the file.
+    pub fn payload_block(&self) -> Option<(&FlacBlock, usize, usize)> {
+        self.blocks.iter().rev().find_map(|b| {
+            let body = &self.data[b.body()..b.end()];
+            match b.kind {
+                APPLICATION if body.starts_with(APPLICATION_ID) => Some((b, b.body() + 4, b.end())),
+                PADDING if body.starts_with(PADDING_MAGIC) && body.len() >= PADDING_FRAME_LEN => {
+                    let len = u32::from_be_bytes(body[5..9].try_into().unwrap()) as usize;
+                    let start = b.body() + PADDING_FRAME_LEN;
+                    (len <= b.end() - start).then_some((b, start, start + len))
+                }
+                _ => None,
+            }
+        })
+    }
+
+    /// Extracts a payload hidden in an `APPLICATION` or `PADDING` block.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the payload bytes, or a `NotFound` error if no payload was found.
+    pub fn extract_payload(&self) -> io::Result<Vec<u8>> {
+        let (_, start, end) = self.payload_block().ok_or_else(not_found)?;
+        Ok(self.data[start..end].to_vec())
+    }
+
+    /// Removes a payload, restoring the original file.
+    ///
+    /// A block added for the payload is removed, and the block before it flagged as the last
+    /// one again; a payload written into existing padding is zeroed.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the cleaned file, or a `NotFound` error if no
+    /// payload was found.
+    pub fn remove_payload(&self) -> io::Result<Vec<u8>> {
+        let (block, _, end) = self.payload_block().ok_or_else(not_found)?;
+        if block.kind == PADDING && self.data[block.body() + 4] == 0 {
+            let mut out = self.data.to_vec();
+            out[block.body()..end].fill(0);
+            return Ok(out);
+        }
+        let mut out = Vec::with_capacity(self.data.len());
+        out.extend_from_slice(&self.data[..block.offset]);
+        if block.last {
+            if let Some(previous) = self.blocks.iter().rev().find(|b| b.end() == block.offset) {
+                out[previous.offset] |= 0x80;
+            }
+        }
+        out.extend_from_slice(&self.data[block.end()..]);
+        Ok(out)
+    }
+}
+
+/// Builds the error returned when a file holds no payload block.
+fn not_found() -> io::Error {
+    io::Error::new(ErrorKind::NotFound, "No payload found in the FLAC file")
+}
+
+/// Describes the content of a block in a few words.
+fn describe_block(file: &FlacFile, block: &FlacBlock) -> String {
+    let body = &file.data[block.body()..block.end()];
+    let be32 = |bytes: &[u8]| u32::from_be_bytes(bytes.try_into().unwrap()) as usize;
+    match block.kind {
+        0 => match file.stream_info() {
+            Some((rate, channels, bits, samples)) => format!(
+                "{} Hz, {} channels, {} bits, {} samples",
+                rate, channels, bits, samples
+            ),
+            None => String::new(),
+        },
+        APPLICATION if body.len() >= 4 => {
+            format!("application {:?}", String::from_utf8_lossy(&body[..4]))
+        }
+        3 => format!("{} seek points", block.len / 18),
+        // The vendor string and comment count are little-endian, unlike the rest of FLAC.
+        4 if body.len() >= 4 => {
+            let vendor_len = u32::from_le_bytes(body[..4].try_into().unwrap()) as usize;
+            match body.get(4..4 + vendor_len) {
+                Some(vendor) => format!("vendor {:?}", String::from_utf8_lossy(vendor)),
+                None => String::new(),
+            }
+        }
+        6 if body.len() >= 8 => match body.get(8..8 + be32(&body[4..8])) {
+            Some(mime) => String::from_utf8_lossy(mime).into_owned(),
+            None => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+/// Reads a FLAC file and prints its metadata blocks.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice representing the path to the FLAC file.
+/// * `nb_blocks` - The maximum number of blocks to print.
+/// * `suppress` - A boolean to suppress print statements.
+///
+/// # Returns
+///
+/// A `Result` that is an error if the file could not be read or parsed.
+pub fn read_flac_blocks(
+    file_path: &str,
+    nb_blocks: usize,
+    suppress: bool,
+) -> Result<(), Box<dyn Error>> {
+    let data = map_input(file_path)?;
+    let file = FlacFile::parse(&data)?;
+    if suppress {
+        return Ok(());
+    }
+
+    outln!("It is a valid FLAC file. Let's process it! \n");
+    outln!("{}", Style::Green.paint("---- Blocks ----"));
+    outln!(
+        "{}",
+        Style::Grey.paint(format!(
+            "{:<10} {:<14} {:>9}  {}",
+            "Offset", "Type", "Length", "Content"
+        ))
+    );
+    let payload = file.payload_block().map(|(block, _, _)| block.offset);
+    for block in file.blocks.iter().take(nb_blocks) {
+        let mark = if payload == Some(block.offset) {
+            Style::Yellow.paint(" (stegano payload)").to_string()
+        } else {
+            String::new()
+        };
+        outln!(
+            "{:<10} {:<14} {:>9}  {}{}",
+            block.offset,
+            block.kind_name(),
+            block.len,
+            describe_block(&file, block),
+            mark
+        );
+    }
+    outln!("{}", Style::Green.paint("----- End ------"));
+    outln!("Audio frames: {} bytes", data.len() - file.audio_offset());
+    Ok(())
+}
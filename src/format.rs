@@ -0,0 +1,958 @@
+use crate::apng::between_frames_offset;
+use crate::codec::CodecOptions;
+use crate::disguise::{embed_disguised, extract_disguised, Disguise};
+use crate::flac::{FlacFile, PayloadBlock};
+use crate::icc::{embed_in_icc, extract_from_icc, jpeg_icc_capacity};
+use crate::ico::IcoFile;
+use crate::interleave::{embed_interleaved, extract_interleaved};
+use crate::jpeg::markers::MarkerInventory;
+use crate::lsb::{
+    embed_lsb_with, extract_lsb_in, lsb_channels_capacity, Channels, LsbOptions, Traversal,
+    MAX_LSB_BITS,
+};
+use crate::mp3::{Mp3File, PayloadFrame};
+use crate::mp4::{Mp4File, PayloadBox};
+use crate::png::{
+    insert_chunk, read_chunks, remove_chunk, remove_chunk_at, validate_chunk_name, PngImage,
+};
+use crate::polyglot::PNG_SIGNATURE;
+use crate::precision::{embed_precision, extract_precision, PRECISION_CAPACITY};
+use crate::robust::{embed_robust, extract_robust, robust_capacity};
+use crate::svg::{SvgFile, SvgMethod};
+use crate::text::{TextFile, TextMethod};
+use crate::tiff::TiffFile;
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// How many leading bytes of a carrier are read to detect its type.
+pub const SNIFF_LEN: usize = 4096;
+
+/// The formats registered with `register_format`, looked up before the built-in ones.
+static FORMATS: RwLock<Vec<Arc<dyn CarrierFormat>>> = RwLock::new(Vec::new());
+
+/// A structural unit of a carrier, such as a PNG chunk or a JPEG marker segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// The name of the segment, such as `IHDR` or `APP1/Exif`.
+    pub name: String,
+    /// The offset of the segment in the file.
+    pub offset: usize,
+    /// The length of the segment, with its framing.
+    pub len: usize,
+}
+
+/// A carrier format: how to recognize its files, walk their structure, and hide a payload
+/// in them.
+///
+/// PNG, JPEG, TIFF, ICO, MP4, MP3, FLAC, SVG and text are built in. Other crates add formats
+/// with `register_format`, after which `encode_bytes` and `decode_bytes` pick them up, by name
+/// or detected with a file type of `auto`. A registered format named like a built-in one,
+/// `png` say, replaces it for every method.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::codec::{decode_bytes, encode_bytes, CodecOptions};
+/// use stegano::format::{detect_file_type, register_format, CarrierFormat, Segment};
+/// use std::io;
+///
+/// /// Appends the payload after a "RAW!" magic and a length byte.
+/// struct RawFormat;
+///
+/// impl CarrierFormat for RawFormat {
+///     fn name(&self) -> &'static str {
+///         "raw"
+///     }
+///
+///     fn detect(&self, data: &[u8]) -> bool {
+///         data.starts_with(b"RAW!")
+///     }
+///
+///     fn segments(&self, data: &[u8]) -> io::Result<Vec<Segment>> {
+///         let name = String::from("magic");
+///         Ok(vec![Segment { name, offset: 0, len: data.len().min(4) }])
+///     }
+///
+///     fn capacity(&self, _: &[u8], _: &CodecOptions) -> io::Result<Option<usize>> {
+///         Ok(Some(255))
+///     }
+///
+///     fn embed(&self, carrier: &[u8], encrypted: &[u8], _: &CodecOptions) -> io::Result<Vec<u8>> {
+///         let mut stego = carrier.to_vec();
+///         stego.extend_from_slice(encrypted);
+///         stego.push(encrypted.len() as u8);
+///         Ok(stego)
+///     }
+///
+///     fn extract(&self, stego: &[u8], _: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)> {
+///         let len = *stego.last().unwrap() as usize;
+///         let start = stego.len() - 1 - len;
+///         Ok((stego[start..stego.len() - 1].to_vec(), stego[..start].to_vec()))
+///     }
+/// }
+///
+/// register_format(RawFormat);
+/// assert_eq!(detect_file_type(b"RAW!cover"), Some("raw"));
+/// let opts = CodecOptions { file_type: String::from("auto"), ..CodecOptions::default() };
+/// let stego = encode_bytes(b"RAW!cover", b"hello", &opts).unwrap();
+/// assert_eq!(decode_bytes(&stego, &opts).unwrap().payload, b"hello");
+/// ```
+pub trait CarrierFormat: Send + Sync {
+    /// Returns the `--type` of the format, in lowercase.
+    fn name(&self) -> &'static str;
+
+    /// Returns the other names the format answers to, such as `jpg` for JPEG.
+    fn aliases(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// Tells whether `data`, the first bytes of a file, belongs to the format.
+    fn detect(&self, data: &[u8]) -> bool;
+
+    /// Splits a file into its segments, in file order.
+    fn segments(&self, data: &[u8]) -> io::Result<Vec<Segment>>;
+
+    /// Returns how many encrypted bytes the method of `opts` can hide in a carrier, or `None`
+    /// when only the size limits of the format bound it.
+    fn capacity(&self, data: &[u8], opts: &CodecOptions) -> io::Result<Option<usize>>;
+
+    /// Embeds an encrypted payload into a carrier with the method of `opts`.
+    fn embed(&self, carrier: &[u8], encrypted: &[u8], opts: &CodecOptions) -> io::Result<Vec<u8>>;
+
+    /// Embeds an encrypted payload of `len` bytes read from `encrypted`, writing the stego file
+    /// to `out`, as `encrypt` does with payloads it may have spilled to disk.
+    ///
+    /// Formats that can copy the payload through without holding it override this; the
+    /// default reads it whole and calls `embed`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the offset of the payload in the stego file when it is stored
+    /// there as is, which `encrypt --descriptor` records, or `None`.
+    fn embed_stream(
+        &self,
+        carrier: &[u8],
+        encrypted: &mut dyn Read,
+        len: u64,
+        out: &mut dyn Write,
+        opts: &CodecOptions,
+    ) -> io::Result<Option<u64>> {
+        let mut payload = Vec::new();
+        encrypted.take(len).read_to_end(&mut payload)?;
+        out.write_all(&self.embed(carrier, &payload, opts)?)?;
+        Ok(None)
+    }
+
+    /// Takes back an encrypted payload embedded with the method of `opts`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the embedded bytes and the restored carrier.
+    fn extract(&self, stego: &[u8], opts: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)>;
+}
+
+/// Builds an error for unsupported type/method combinations.
+pub(crate) fn unsupported(file_type: &str, method: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidInput,
+        format!("Unsupported method {:?} for {:?} files", method, file_type),
+    )
+}
+
+/// Adds a carrier format, which takes precedence over a built-in format of the same name.
+pub fn register_format<F: CarrierFormat + 'static>(format: F) {
+    FORMATS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Arc::new(format));
+}
+
+/// Returns the registered formats, the latest first, followed by the built-in ones.
+pub fn formats() -> Vec<Arc<dyn CarrierFormat>> {
+    let mut formats = FORMATS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    formats.reverse();
+    formats.push(Arc::new(PngFormat));
+    formats.push(Arc::new(JpegFormat));
+    formats.push(Arc::new(TiffFormat));
+    formats.push(Arc::new(IcoFormat));
+    formats.push(Arc::new(Mp4Format));
+    formats.push(Arc::new(Mp3Format));
+    formats.push(Arc::new(FlacFormat));
+    formats.push(Arc::new(SvgFormat));
+    formats.push(Arc::new(TextFormat));
+    formats
+}
+
+/// Looks a format up by its name or one of its aliases, ignoring case.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::format::find_format;
+///
+/// assert_eq!(find_format("PNG").map(|format| format.name()), Some("png"));
+/// assert_eq!(find_format("jpg").map(|format| format.name()), Some("jpeg"));
+/// assert_eq!(find_format("tiff").map(|format| format.name()), Some("tiff"));
+/// assert!(find_format("gif").is_none());
+/// ```
+pub fn find_format(file_type: &str) -> Option<Arc<dyn CarrierFormat>> {
+    formats().into_iter().find(|format| {
+        format.name().eq_ignore_ascii_case(file_type)
+            || format
+                .aliases()
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(file_type))
+    })
+}
+
+/// Finds the format whose magic bytes start `data`.
+pub fn detect_format(data: &[u8]) -> Option<Arc<dyn CarrierFormat>> {
+    formats().into_iter().find(|format| format.detect(data))
+}
+
+/// Tells the `--type` of a carrier from its first bytes, `SNIFF_LEN` of which are enough.
+///
+/// The registered and built-in formats are tried in turn. Text has no magic bytes, so
+/// anything else that reads as UTF-8 is a text carrier.
+///
+/// # Returns
+///
+/// The carrier type, or `None` for binary data of no known format.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::format::detect_file_type;
+/// use stegano::png::encode_grayscale;
+///
+/// let png = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// assert_eq!(detect_file_type(&png), Some("png"));
+/// assert_eq!(detect_file_type(&[0xFF, 0xD8, 0xFF, 0xE0]), Some("jpeg"));
+/// assert_eq!(detect_file_type(b"fLaC\0\0\0\x22"), Some("flac"));
+/// assert_eq!(detect_file_type(b"<?xml version=\"1.0\"?>\n<svg"), Some("svg"));
+/// assert_eq!(detect_file_type("Dear reader,\n".as_bytes()), Some("text"));
+/// assert_eq!(detect_file_type(&[0, 159, 146, 150]), None);
+/// ```
+pub fn detect_file_type(data: &[u8]) -> Option<&'static str> {
+    if let Some(format) = detect_format(data) {
+        return Some(format.name());
+    }
+    TextFormat::is_text(data).then_some("text")
+}
+
+/// Resolves a file type of `auto` from the first bytes of a carrier, and lowercases the
+/// others.
+///
+/// # Returns
+///
+/// A `Result` containing the carrier type, or an `InvalidInput` error if `auto` can't tell it.
+pub fn resolve_file_type(file_type: &str, data: &[u8]) -> io::Result<String> {
+    if !file_type.eq_ignore_ascii_case("auto") {
+        return Ok(file_type.to_lowercase());
+    }
+    let prefix = &data[..data.len().min(SNIFF_LEN)];
+    detect_file_type(prefix).map(String::from).ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidInput,
+            "The carrier type can't be told from its first bytes, set it explicitly",
+        )
+    })
+}
+
+/// Returns the carrier type a file name suggests by its extension, or `None` for other
+/// extensions.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use stegano::format::extension_type;
+///
+/// assert_eq!(extension_type(Path::new("covers/cat.PNG")), Some("png"));
+/// assert_eq!(extension_type(Path::new("photo.jpg")), Some("jpeg"));
+/// assert_eq!(extension_type(Path::new("archive.zip")), None);
+/// ```
+pub fn extension_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "png" | "apng" => Some("png"),
+        "jpg" | "jpeg" => Some("jpeg"),
+        "tif" | "tiff" => Some("tiff"),
+        "ico" => Some("ico"),
+        "mp4" | "m4v" | "m4a" | "mov" => Some("mp4"),
+        "mp3" => Some("mp3"),
+        "flac" => Some("flac"),
+        "svg" => Some("svg"),
+        "txt" | "md" => Some("text"),
+        _ => None,
+    }
+}
+
+/// Tells whether a carrier given as `claimed`, by `--type` or its extension, can be handled
+/// as the type detected from its content. Aliases such as `jpg` name their format, and SVG
+/// files are text too.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::format::same_file_type;
+///
+/// assert!(same_file_type("JPG", "jpeg"));
+/// assert!(same_file_type("text", "svg"));
+/// assert!(!same_file_type("png", "jpeg"));
+/// ```
+pub fn same_file_type(claimed: &str, detected: &str) -> bool {
+    let canonical = |file_type: &str| {
+        find_format(file_type)
+            .map(|format| format.name().to_string())
+            .unwrap_or_else(|| file_type.to_lowercase())
+    };
+    let (claimed, detected) = (canonical(claimed), canonical(detected));
+    claimed == detected || matches!((&*claimed, &*detected), ("text" | "svg", "text" | "svg"))
+}
+
+/// PNG, with the chunk, apng, precision, lsb, robust, disguise, interleave and icc methods.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PngFormat;
+
+impl CarrierFormat for PngFormat {
+    fn name(&self) -> &'static str {
+        "png"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        data.starts_with(PNG_SIGNATURE)
+    }
+
+    fn segments(&self, data: &[u8]) -> io::Result<Vec<Segment>> {
+        let chunks = read_chunks(data)?;
+        Ok(chunks
+            .into_iter()
+            .map(|chunk| Segment {
+                name: chunk.r#type.to_string(),
+                offset: chunk.offset,
+                // Length, type and CRC.
+                len: chunk.data.len() + 12,
+            })
+            .collect())
+    }
+
+    fn capacity(&self, data: &[u8], opts: &CodecOptions) -> io::Result<Option<usize>> {
+        match opts.method.to_lowercase().as_str() {
+            "precision" => Ok(Some(PRECISION_CAPACITY)),
+            "lsb" => {
+                let image = PngImage::decode(data)?;
+                Ok(Some(lsb_channels_capacity(
+                    &image,
+                    opts.bits_per_channel.unwrap_or(MAX_LSB_BITS),
+                    Channels::new(&opts.channels)?,
+                )))
+            }
+            "robust" => Ok(Some(robust_capacity(&PngImage::decode(data)?))),
+            "auto" | "chunk" | "apng" | "disguise" | "interleave" | "icc" => Ok(None),
+            method => Err(unsupported(self.name(), method)),
+        }
+    }
+
+    fn embed(&self, carrier: &[u8], encrypted: &[u8], opts: &CodecOptions) -> io::Result<Vec<u8>> {
+        match opts.method.to_lowercase().as_str() {
+            "auto" | "chunk" | "apng" => Ok(PngFormat::insert(carrier, encrypted, opts)?.0),
+            "precision" => embed_precision(carrier, encrypted),
+            "lsb" => {
+                let lsb = LsbOptions {
+                    traversal: Traversal::new(&opts.traversal)?,
+                    channels: Channels::new(&opts.channels)?,
+                    bits: opts.bits_per_channel,
+                };
+                embed_lsb_with(carrier, encrypted, &opts.key, lsb)
+            }
+            "robust" => embed_robust(carrier, encrypted, &opts.key),
+            "disguise" => embed_disguised(carrier, encrypted, Disguise::new(&opts.disguise)?),
+            "interleave" => embed_interleaved(carrier, encrypted, &opts.chunk_name, &opts.key),
+            "icc" => embed_in_icc(carrier, encrypted, "png"),
+            method => Err(unsupported(self.name(), method)),
+        }
+    }
+
+    fn embed_stream(
+        &self,
+        carrier: &[u8],
+        encrypted: &mut dyn Read,
+        len: u64,
+        out: &mut dyn Write,
+        opts: &CodecOptions,
+    ) -> io::Result<Option<u64>> {
+        let mut payload = Vec::new();
+        encrypted.take(len).read_to_end(&mut payload)?;
+        if !matches!(
+            opts.method.to_lowercase().as_str(),
+            "auto" | "chunk" | "apng"
+        ) {
+            out.write_all(&self.embed(carrier, &payload, opts)?)?;
+            return Ok(None);
+        }
+        let (stego, offset) = PngFormat::insert(carrier, &payload, opts)?;
+        out.write_all(&stego)?;
+        // The chunk data follows its length and type.
+        Ok(Some(offset as u64 + 8))
+    }
+
+    fn extract(&self, stego: &[u8], opts: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)> {
+        let extracted = match opts.method.to_lowercase().as_str() {
+            "auto" | "chunk" | "apng" => {
+                let name: [u8; 4] = opts.chunk_name.as_bytes().try_into().map_err(|_| {
+                    io::Error::new(ErrorKind::InvalidInput, "Chunk names are four letters")
+                })?;
+                let (carrier, chunk) = match opts.offset.resolve(stego, &read_chunks(stego)?)? {
+                    Some(offset) => remove_chunk_at(stego, &name, offset)?,
+                    None => remove_chunk(stego, &name)?,
+                };
+                (chunk.data, carrier)
+            }
+            // The original low-order bits are lost, so the carrier is returned as is.
+            "precision" => (extract_precision(stego)?, stego.to_vec()),
+            "lsb" => (
+                extract_lsb_in(stego, &opts.key, Channels::new(&opts.channels)?)?,
+                stego.to_vec(),
+            ),
+            "robust" => (extract_robust(stego, &opts.key)?, stego.to_vec()),
+            "disguise" => {
+                let (payload, carrier, _) = extract_disguised(stego)?;
+                (payload, carrier)
+            }
+            "interleave" => extract_interleaved(stego, &opts.chunk_name)?,
+            "icc" => extract_from_icc(stego, "png")?,
+            method => return Err(unsupported(self.name(), method)),
+        };
+        Ok(extracted)
+    }
+}
+
+impl PngFormat {
+    /// Inserts the payload chunk of the chunk and apng methods, at the offset of `opts` or
+    /// between the frames.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the stego file and the offset of the chunk in it.
+    fn insert(
+        carrier: &[u8],
+        encrypted: &[u8],
+        opts: &CodecOptions,
+    ) -> io::Result<(Vec<u8>, usize)> {
+        let chunks = read_chunks(carrier)?;
+        let name = validate_chunk_name(&opts.chunk_name, &chunks)?;
+        let offset = if opts.method.eq_ignore_ascii_case("apng") {
+            Some(between_frames_offset(&chunks)?)
+        } else {
+            opts.offset.resolve(carrier, &chunks)?
+        };
+        insert_chunk(carrier, &name, encrypted, offset)
+    }
+}
+
+/// JPEG, whose payloads are wrapped in the ICC profile, split over APP2 segments.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JpegFormat;
+
+impl CarrierFormat for JpegFormat {
+    fn name(&self) -> &'static str {
+        "jpeg"
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["jpg"]
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        data.starts_with(&[0xFF, 0xD8, 0xFF])
+    }
+
+    fn segments(&self, data: &[u8]) -> io::Result<Vec<Segment>> {
+        let inventory = MarkerInventory::parse(data)?;
+        Ok(inventory
+            .segments
+            .into_iter()
+            .map(|segment| Segment {
+                name: segment.class,
+                offset: segment.offset,
+                // The marker, then the counted length and the entropy-coded data after it.
+                len: 2 + segment.length + segment.scan_len,
+            })
+            .collect())
+    }
+
+    fn capacity(&self, data: &[u8], opts: &CodecOptions) -> io::Result<Option<usize>> {
+        match opts.method.to_lowercase().as_str() {
+            "auto" | "icc" => Ok(Some(jpeg_icc_capacity(data)?)),
+            method => Err(unsupported(self.name(), method)),
+        }
+    }
+
+    fn embed(&self, carrier: &[u8], encrypted: &[u8], opts: &CodecOptions) -> io::Result<Vec<u8>> {
+        match opts.method.to_lowercase().as_str() {
+            "auto" | "icc" => embed_in_icc(carrier, encrypted, "jpeg"),
+            method => Err(unsupported(self.name(), method)),
+        }
+    }
+
+    fn extract(&self, stego: &[u8], opts: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)> {
+        match opts.method.to_lowercase().as_str() {
+            "auto" | "icc" => extract_from_icc(stego, "jpeg"),
+            method => Err(unsupported(self.name(), method)),
+        }
+    }
+}
+
+/// TIFF, with the payload in a private tag of the first IFD or in a strip appended to the file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TiffFormat;
+
+impl CarrierFormat for TiffFormat {
+    fn name(&self) -> &'static str {
+        "tiff"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        data.starts_with(b"II*\0") || data.starts_with(b"MM\0*")
+    }
+
+    fn segments(&self, data: &[u8]) -> io::Result<Vec<Segment>> {
+        let (_, ifds) = TiffFile::parse_ifds(data)?;
+        let header = Segment {
+            name: String::from("header"),
+            offset: 0,
+            len: 8,
+        };
+        Ok(std::iter::once(header)
+            .chain(ifds.iter().enumerate().map(|(index, ifd)| Segment {
+                name: format!("IFD #{}", index),
+                offset: ifd.offset as usize,
+                // The entry count, the entries and the offset of the next IFD.
+                len: 2 + ifd.entries.len() * 12 + 4,
+            }))
+            .collect())
+    }
+
+    fn capacity(&self, _: &[u8], opts: &CodecOptions) -> io::Result<Option<usize>> {
+        match opts.method.to_lowercase().as_str() {
+            "auto" | "tag" | "strip" => Ok(None),
+            method => Err(unsupported(self.name(), method)),
+        }
+    }
+
+    fn embed(&self, carrier: &[u8], encrypted: &[u8], opts: &CodecOptions) -> io::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(carrier.len() + encrypted.len() + 32);
+        self.embed_stream(
+            carrier,
+            &mut &encrypted[..],
+            encrypted.len() as u64,
+            &mut out,
+            opts,
+        )?;
+        Ok(out)
+    }
+
+    fn embed_stream(
+        &self,
+        carrier: &[u8],
+        encrypted: &mut dyn Read,
+        len: u64,
+        out: &mut dyn Write,
+        opts: &CodecOptions,
+    ) -> io::Result<Option<u64>> {
+        let method = opts.method.to_lowercase();
+        if !matches!(method.as_str(), "auto" | "tag" | "strip") {
+            return Err(unsupported(self.name(), &method));
+        }
+        let tiff = TiffFile::parse(carrier.to_vec())?;
+        if method == "strip" {
+            return tiff.write_strip_embedded(encrypted, len, out).map(Some);
+        }
+        // The tag method rewrites the first IFD, so the payload is needed whole.
+        let mut payload = Vec::new();
+        encrypted.take(len).read_to_end(&mut payload)?;
+        let (stego, offset) = tiff.embed_in_tag(&payload)?;
+        out.write_all(&stego)?;
+        Ok(Some(offset as u64))
+    }
+
+    fn extract(&self, stego: &[u8], opts: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)> {
+        match opts.method.to_lowercase().as_str() {
+            "auto" | "tag" | "strip" => {
+                let tiff = TiffFile::parse(stego.to_vec())?;
+                Ok((tiff.extract_payload()?, tiff.remove_payload()?))
+            }
+            method => Err(unsupported(self.name(), method)),
+        }
+    }
+}
+
+/// ICO, with the payload in the slack space after the last image.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IcoFormat;
+
+impl CarrierFormat for IcoFormat {
+    fn name(&self) -> &'static str {
+        "ico"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        data.starts_with(&[0, 0, 1, 0])
+    }
+
+    fn segments(&self, data: &[u8]) -> io::Result<Vec<Segment>> {
+        let ico = IcoFile::parse(data.to_vec())?;
+        let directory = Segment {
+            name: String::from("directory"),
+            offset: 0,
+            len: 6 + ico.entries.len() * 16,
+        };
+        Ok(std::iter::once(directory)
+            .chain(
+                ico.entries
+                    .iter()
+                    .enumerate()
+                    .map(|(index, entry)| Segment {
+                        name: format!("image #{}", index),
+                        offset: entry.offset as usize,
+                        len: entry.size as usize,
+                    }),
+            )
+            .collect())
+    }
+
+    fn capacity(&self, _: &[u8], opts: &CodecOptions) -> io::Result<Option<usize>> {
+        match opts.method.to_lowercase().as_str() {
+            "auto" | "slack" => Ok(None),
+            method => Err(unsupported(self.name(), method)),
+        }
+    }
+
+    fn embed(&self, carrier: &[u8], encrypted: &[u8], opts: &CodecOptions) -> io::Result<Vec<u8>> {
+        match opts.method.to_lowercase().as_str() {
+            "auto" | "slack" => Ok(IcoFile::parse(carrier.to_vec())?.embed_in_slack(encrypted)),
+            method => Err(unsupported(self.name(), method)),
+        }
+    }
+
+    fn embed_stream(
+        &self,
+        carrier: &[u8],
+        encrypted: &mut dyn Read,
+        len: u64,
+        out: &mut dyn Write,
+        opts: &CodecOptions,
+    ) -> io::Result<Option<u64>> {
+        match opts.method.to_lowercase().as_str() {
+            "auto" | "slack" => IcoFile::parse(carrier.to_vec())?
+                .write_slack_embedded(encrypted, len, out)
+                .map(Some),
+            method => Err(unsupported(self.name(), method)),
+        }
+    }
+
+    fn extract(&self, stego: &[u8], opts: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)> {
+        match opts.method.to_lowercase().as_str() {
+            "auto" | "slack" => {
+                let ico = IcoFile::parse(stego.to_vec())?;
+                Ok((ico.extract_payload()?, ico.remove_payload()?))
+            }
+            method => Err(unsupported(self.name(), method)),
+        }
+    }
+}
+
+/// MP4 and QuickTime, with the payload in a `free`, `skip` or `uuid` box.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mp4Format;
+
+impl CarrierFormat for Mp4Format {
+    fn name(&self) -> &'static str {
+        "mp4"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        data.get(4..8) == Some(b"ftyp")
+    }
+
+    fn segments(&self, data: &[u8]) -> io::Result<Vec<Segment>> {
+        Ok(Mp4File::parse(data)?
+            .boxes
+            .iter()
+            .map(|mp4_box| Segment {
+                name: mp4_box.type_name(),
+                offset: mp4_box.offset,
+                len: mp4_box.size,
+            })
+            .collect())
+    }
+
+    fn capacity(&self, _: &[u8], opts: &CodecOptions) -> io::Result<Option<usize>> {
+        PayloadBox::new(&opts.method)?;
+        Ok(None)
+    }
+
+    fn embed(&self, carrier: &[u8], encrypted: &[u8], opts: &CodecOptions) -> io::Result<Vec<u8>> {
+        Mp4File::parse(carrier)?.embed_in_box(encrypted, PayloadBox::new(&opts.method)?)
+    }
+
+    fn embed_stream(
+        &self,
+        carrier: &[u8],
+        encrypted: &mut dyn Read,
+        len: u64,
+        out: &mut dyn Write,
+        opts: &CodecOptions,
+    ) -> io::Result<Option<u64>> {
+        let kind = PayloadBox::new(&opts.method)?;
+        Mp4File::parse(carrier)?
+            .write_box_embedded(encrypted, len, kind, out)
+            .map(Some)
+    }
+
+    fn extract(&self, stego: &[u8], opts: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)> {
+        PayloadBox::new(&opts.method)?;
+        let mp4 = Mp4File::parse(stego)?;
+        Ok((mp4.extract_payload()?, mp4.remove_payload()?))
+    }
+}
+
+/// MP3, with the payload in a `PRIV` or `GEOB` frame of the ID3v2 tag.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mp3Format;
+
+impl CarrierFormat for Mp3Format {
+    fn name(&self) -> &'static str {
+        "mp3"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        data.starts_with(b"ID3")
+    }
+
+    fn segments(&self, data: &[u8]) -> io::Result<Vec<Segment>> {
+        let mp3 = Mp3File::parse(data)?;
+        let mut segments = Vec::new();
+        let mut audio = 0;
+        if let Some(tag) = &mp3.tag {
+            segments.push(Segment {
+                name: String::from("ID3v2 header"),
+                offset: 0,
+                len: 10,
+            });
+            segments.extend(tag.frames.iter().map(|frame| Segment {
+                name: String::from_utf8_lossy(&frame.id).into_owned(),
+                offset: frame.offset,
+                len: frame.end() - frame.offset,
+            }));
+            audio = tag.end();
+        }
+        segments.push(Segment {
+            name: String::from("audio"),
+            offset: audio,
+            len: data.len().saturating_sub(audio),
+        });
+        Ok(segments)
+    }
+
+    fn capacity(&self, _: &[u8], opts: &CodecOptions) -> io::Result<Option<usize>> {
+        PayloadFrame::new(&opts.method)?;
+        Ok(None)
+    }
+
+    fn embed(&self, carrier: &[u8], encrypted: &[u8], opts: &CodecOptions) -> io::Result<Vec<u8>> {
+        Mp3File::parse(carrier)?.embed_in_frame(encrypted, PayloadFrame::new(&opts.method)?)
+    }
+
+    fn embed_stream(
+        &self,
+        carrier: &[u8],
+        encrypted: &mut dyn Read,
+        len: u64,
+        out: &mut dyn Write,
+        opts: &CodecOptions,
+    ) -> io::Result<Option<u64>> {
+        let kind = PayloadFrame::new(&opts.method)?;
+        Mp3File::parse(carrier)?
+            .write_frame_embedded(encrypted, len, kind, out)
+            .map(Some)
+    }
+
+    fn extract(&self, stego: &[u8], opts: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)> {
+        PayloadFrame::new(&opts.method)?;
+        let mp3 = Mp3File::parse(stego)?;
+        Ok((mp3.extract_payload()?, mp3.remove_payload()?))
+    }
+}
+
+/// FLAC, with the payload in an `APPLICATION` or `PADDING` metadata block.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlacFormat;
+
+impl CarrierFormat for FlacFormat {
+    fn name(&self) -> &'static str {
+        "flac"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        data.starts_with(b"fLaC")
+    }
+
+    fn segments(&self, data: &[u8]) -> io::Result<Vec<Segment>> {
+        let flac = FlacFile::parse(data)?;
+        let marker = Segment {
+            name: String::from("fLaC"),
+            offset: 0,
+            len: 4,
+        };
+        Ok(std::iter::once(marker)
+            .chain(flac.blocks.iter().map(|block| Segment {
+                name: block.kind_name().to_string(),
+                offset: block.offset,
+                len: block.end() - block.offset,
+            }))
+            .collect())
+    }
+
+    fn capacity(&self, _: &[u8], opts: &CodecOptions) -> io::Result<Option<usize>> {
+        PayloadBlock::new(&opts.method)?;
+        Ok(None)
+    }
+
+    fn embed(&self, carrier: &[u8], encrypted: &[u8], opts: &CodecOptions) -> io::Result<Vec<u8>> {
+        FlacFile::parse(carrier)?.embed_in_block(encrypted, PayloadBlock::new(&opts.method)?)
+    }
+
+    fn embed_stream(
+        &self,
+        carrier: &[u8],
+        encrypted: &mut dyn Read,
+        len: u64,
+        out: &mut dyn Write,
+        opts: &CodecOptions,
+    ) -> io::Result<Option<u64>> {
+        let kind = PayloadBlock::new(&opts.method)?;
+        FlacFile::parse(carrier)?
+            .write_block_embedded(encrypted, len, kind, out)
+            .map(Some)
+    }
+
+    fn extract(&self, stego: &[u8], opts: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)> {
+        PayloadBlock::new(&opts.method)?;
+        let flac = FlacFile::parse(stego)?;
+        Ok((flac.extract_payload()?, flac.remove_payload()?))
+    }
+}
+
+/// SVG, with the payload in a `metadata` element, in the whitespace between tags, or in the
+/// order of the attributes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SvgFormat;
+
+impl CarrierFormat for SvgFormat {
+    fn name(&self) -> &'static str {
+        "svg"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        // The prefix of a larger SVG file doesn't parse on its own.
+        let head = data.trim_ascii_start();
+        head.starts_with(b"<") && head.windows(4).any(|window| window == b"<svg")
+    }
+
+    fn segments(&self, data: &[u8]) -> io::Result<Vec<Segment>> {
+        let svg = SvgFile::parse(data)?;
+        Ok(svg
+            .tags
+            .iter()
+            .map(|tag| Segment {
+                name: String::from_utf8_lossy(&data[tag.name.clone()]).into_owned(),
+                offset: tag.offset,
+                len: tag.end - tag.offset,
+            })
+            .collect())
+    }
+
+    fn capacity(&self, data: &[u8], opts: &CodecOptions) -> io::Result<Option<usize>> {
+        Ok(SvgFile::parse(data)?.capacity(SvgMethod::new(&opts.method)?))
+    }
+
+    fn embed(&self, carrier: &[u8], encrypted: &[u8], opts: &CodecOptions) -> io::Result<Vec<u8>> {
+        SvgFile::parse(carrier)?.embed(encrypted, SvgMethod::new(&opts.method)?)
+    }
+
+    // The attributes method can't restore the original order, so the carrier is the stego
+    // file itself.
+    fn extract(&self, stego: &[u8], opts: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)> {
+        let svg = SvgFile::parse(stego)?;
+        let method = SvgMethod::new(&opts.method)?;
+        Ok((svg.extract_payload(method)?, svg.remove_payload(method)?))
+    }
+}
+
+/// Plain UTF-8 text, with the payload in zero-width characters or trailing whitespace.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextFormat;
+
+impl TextFormat {
+    /// Tells whether `data`, the first bytes of a file, reads as text: UTF-8 without NUL
+    /// bytes, where the prefix may end in the middle of a character.
+    fn is_text(data: &[u8]) -> bool {
+        let text = match std::str::from_utf8(data) {
+            Ok(_) => data,
+            Err(err) if err.error_len().is_none() => &data[..err.valid_up_to()],
+            Err(_) => return false,
+        };
+        !text.is_empty() && !text.contains(&0)
+    }
+}
+
+impl CarrierFormat for TextFormat {
+    fn name(&self) -> &'static str {
+        "text"
+    }
+
+    // Text has no magic bytes: `detect_file_type` falls back to it once every other format
+    // has been tried.
+    fn detect(&self, _: &[u8]) -> bool {
+        false
+    }
+
+    fn segments(&self, data: &[u8]) -> io::Result<Vec<Segment>> {
+        let text = TextFile::parse(data)?;
+        let mut offset = 0;
+        Ok(text
+            .text
+            .split_inclusive('\n')
+            .enumerate()
+            .map(|(index, line)| {
+                let segment = Segment {
+                    name: format!("line {}", index + 1),
+                    offset,
+                    len: line.len(),
+                };
+                offset += line.len();
+                segment
+            })
+            .collect())
+    }
+
+    fn capacity(&self, data: &[u8], opts: &CodecOptions) -> io::Result<Option<usize>> {
+        Ok(TextFile::parse(data)?.capacity(TextMethod::new(&opts.method)?))
+    }
+
+    fn embed(&self, carrier: &[u8], encrypted: &[u8], opts: &CodecOptions) -> io::Result<Vec<u8>> {
+        TextFile::parse(carrier)?.embed(encrypted, TextMethod::new(&opts.method)?)
+    }
+
+    fn extract(&self, stego: &[u8], opts: &CodecOptions) -> io::Result<(Vec<u8>, Vec<u8>)> {
+        let text = TextFile::parse(stego)?;
+        let method = TextMethod::new(&opts.method)?;
+        Ok((text.extract_payload(method)?, text.remove_payload(method)?))
+    }
+}
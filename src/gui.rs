@@ -0,0 +1,342 @@
+use crate::cancel::{is_cancelled, write_output, CancellationToken};
+use crate::codec::{decode_bytes, encode_bytes, CodecOptions};
+use crate::format::{detect_file_type, SNIFF_LEN};
+use crate::tr;
+use eframe::egui;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The methods offered by the window, the ones that work on the most carrier types.
+const METHODS: [&str; 4] = ["auto", "append", "lsb", "robust"];
+
+/// The algorithms offered by the window.
+const ALGORITHMS: [&str; 5] = ["aes", "aes192", "aes256", "chacha", "xor"];
+
+/// What the window opens with, taken from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct GuiOptions {
+    /// The carrier to load before anything is dropped.
+    pub carrier: Option<PathBuf>,
+    /// The key to fill in.
+    pub key: String,
+    /// The algorithm to select.
+    pub algorithm: String,
+}
+
+/// Whether the window hides a payload or reads one back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Hide,
+    Reveal,
+}
+
+/// What a finished job hands back to the window.
+enum Outcome {
+    /// The stego file was written to this path.
+    Written(PathBuf),
+    /// The payload read from the carrier.
+    Revealed(Vec<u8>),
+}
+
+/// A job running on its own thread, so the window stays responsive and can cancel it.
+struct Job {
+    started: Instant,
+    token: CancellationToken,
+    done: Receiver<io::Result<Outcome>>,
+}
+
+/// The state of the window.
+struct App {
+    mode: Mode,
+    carrier: Option<PathBuf>,
+    carrier_type: Option<&'static str>,
+    output: String,
+    payload: String,
+    key: String,
+    method: String,
+    algorithm: String,
+    job: Option<Job>,
+    status: Result<String, String>,
+}
+
+/// Returns where a stego file is written by default: next to the carrier, with `-stego`
+/// added to its name.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use stegano::gui::stego_path;
+///
+/// assert_eq!(stego_path(Path::new("pics/cat.png")), PathBuf::from("pics/cat-stego.png"));
+/// assert_eq!(stego_path(Path::new("notes")), PathBuf::from("notes-stego"));
+/// ```
+pub fn stego_path(carrier: &Path) -> PathBuf {
+    let stem = carrier
+        .file_stem()
+        .map_or_else(Default::default, |stem| stem.to_string_lossy());
+    let name = match carrier.extension() {
+        Some(ext) => format!("{}-stego.{}", stem, ext.to_string_lossy()),
+        None => format!("{}-stego", stem),
+    };
+    carrier.with_file_name(name)
+}
+
+/// Opens the window and blocks until it is closed.
+///
+/// The window takes a carrier dropped onto it, a payload typed into a text box and a key, and
+/// hides the payload with `codec::encode_bytes` or reads it back with `codec::decode_bytes`,
+/// telling the carrier type from its magic bytes as the CLI does with `-t auto`.
+///
+/// # Returns
+///
+/// A `Result` that is an error if no window can be opened, such as without a display.
+pub fn run(options: GuiOptions) -> io::Result<()> {
+    let mut app = App {
+        mode: Mode::Hide,
+        carrier: None,
+        carrier_type: None,
+        output: String::new(),
+        payload: String::new(),
+        key: options.key,
+        method: METHODS[0].to_string(),
+        algorithm: options.algorithm,
+        job: None,
+        status: Ok(tr!("gui-start")),
+    };
+    if let Some(carrier) = options.carrier {
+        app.load(carrier);
+    }
+    let native = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("stegano")
+            .with_inner_size([520.0, 560.0])
+            .with_drag_and_drop(true),
+        ..Default::default()
+    };
+    eframe::run_native("stegano", native, Box::new(|_| Ok(Box::new(app))))
+        .map_err(|err| io::Error::other(format!("The window failed to open: {}", err)))
+}
+
+impl App {
+    /// Makes `path` the carrier, detecting its type and proposing an output next to it.
+    fn load(&mut self, path: PathBuf) {
+        let mut prefix = Vec::new();
+        let read =
+            File::open(&path).and_then(|file| file.take(SNIFF_LEN as u64).read_to_end(&mut prefix));
+        self.carrier_type = detect_file_type(&prefix);
+        self.output = stego_path(&path).to_string_lossy().into_owned();
+        self.status = match read {
+            Ok(_) => Ok(match self.carrier_type {
+                Some(file_type) => tr!(
+                    "gui-carrier-type",
+                    path = path.display(),
+                    file_type = file_type
+                ),
+                None => tr!("gui-carrier-unknown", path = path.display()),
+            }),
+            Err(err) => Err(tr!("gui-unreadable", path = path.display(), error = err)),
+        };
+        self.carrier = Some(path);
+    }
+
+    /// Starts hiding or revealing on a worker thread.
+    fn start(&mut self, ctx: &egui::Context) {
+        let Some(carrier) = self.carrier.clone() else {
+            return;
+        };
+        let token = CancellationToken::new();
+        let opts = CodecOptions {
+            file_type: String::from("auto"),
+            method: self.method.clone(),
+            algorithm: self.algorithm.clone(),
+            key: self.key.clone(),
+            cancel: token.clone(),
+            ..Default::default()
+        };
+        let mode = self.mode;
+        let payload = self.payload.clone().into_bytes();
+        let output = PathBuf::from(&self.output);
+        let (sender, done) = mpsc::channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let outcome = std::fs::read(&carrier).and_then(|data| match mode {
+                Mode::Hide => {
+                    let stego = encode_bytes(&data, &payload, &opts)?;
+                    write_output(&output, &stego, &opts.cancel)?;
+                    Ok(Outcome::Written(output))
+                }
+                Mode::Reveal => Ok(Outcome::Revealed(decode_bytes(&data, &opts)?.payload)),
+            });
+            let _ = sender.send(outcome);
+            ctx.request_repaint();
+        });
+        self.job = Some(Job {
+            started: Instant::now(),
+            token,
+            done,
+        });
+    }
+
+    /// Picks up the result of the running job once it is done.
+    fn poll(&mut self) {
+        let Some(job) = &self.job else {
+            return;
+        };
+        let outcome = match job.done.try_recv() {
+            Ok(outcome) => outcome,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err(io::Error::other("The worker thread panicked")),
+        };
+        self.job = None;
+        self.status = match outcome {
+            Ok(Outcome::Written(path)) => Ok(tr!("gui-hidden", path = path.display())),
+            Ok(Outcome::Revealed(payload)) => {
+                self.payload = String::from_utf8_lossy(&payload).into_owned();
+                Ok(tr!("gui-revealed", bytes = payload.len()))
+            }
+            Err(err) if is_cancelled(&err) => Err(tr!("gui-cancelled")),
+            Err(err) => Err(err.to_string()),
+        };
+    }
+
+    /// Draws the drop zone, which shows the carrier and lights up while a file is dragged over.
+    fn drop_zone(&mut self, ui: &mut egui::Ui) {
+        let hovering = ui.ctx().input(|input| !input.raw.hovered_files.is_empty());
+        let stroke = if hovering {
+            ui.visuals().selection.stroke
+        } else {
+            ui.visuals().widgets.noninteractive.bg_stroke
+        };
+        egui::Frame::group(ui.style())
+            .stroke(stroke)
+            .show(ui, |ui| {
+                ui.set_min_size(egui::vec2(ui.available_width(), 64.0));
+                ui.vertical_centered(|ui| match &self.carrier {
+                    Some(path) => {
+                        ui.strong(path.display().to_string());
+                        ui.label(match self.carrier_type {
+                            Some(file_type) => tr!("gui-type", file_type = file_type),
+                            None => tr!("gui-type-unknown"),
+                        });
+                        ui.weak(tr!("gui-drop-another"));
+                    }
+                    None => {
+                        ui.add_space(16.0);
+                        ui.label(tr!("gui-drop-here"));
+                    }
+                });
+            });
+    }
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll();
+        let dropped = ctx.input(|input| {
+            input
+                .raw
+                .dropped_files
+                .iter()
+                .find_map(|file| file.path.clone())
+        });
+        if let (Some(path), None) = (dropped, &self.job) {
+            self.load(path);
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.add_enabled_ui(self.job.is_none(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.mode, Mode::Hide, tr!("gui-mode-hide"));
+                    ui.selectable_value(&mut self.mode, Mode::Reveal, tr!("gui-mode-reveal"));
+                });
+                ui.separator();
+                self.drop_zone(ui);
+                ui.add_space(8.0);
+                egui::Grid::new("settings").num_columns(2).show(ui, |ui| {
+                    ui.label(tr!("gui-key"));
+                    ui.add(egui::TextEdit::singleline(&mut self.key).password(true));
+                    ui.end_row();
+                    ui.label(tr!("gui-method"));
+                    egui::ComboBox::from_id_salt("method")
+                        .selected_text(self.method.as_str())
+                        .show_ui(ui, |ui| {
+                            for method in METHODS {
+                                ui.selectable_value(&mut self.method, method.to_string(), method);
+                            }
+                        });
+                    ui.end_row();
+                    if self.mode == Mode::Hide {
+                        ui.label(tr!("gui-algorithm"));
+                        egui::ComboBox::from_id_salt("algorithm")
+                            .selected_text(self.algorithm.as_str())
+                            .show_ui(ui, |ui| {
+                                for algorithm in ALGORITHMS {
+                                    ui.selectable_value(
+                                        &mut self.algorithm,
+                                        algorithm.to_string(),
+                                        algorithm,
+                                    );
+                                }
+                            });
+                        ui.end_row();
+                        ui.label(tr!("gui-save-as"));
+                        ui.text_edit_singleline(&mut self.output);
+                        ui.end_row();
+                    }
+                });
+                ui.add_space(8.0);
+                ui.label(match self.mode {
+                    Mode::Hide => tr!("gui-payload"),
+                    Mode::Reveal => tr!("gui-revealed-payload"),
+                });
+                egui::ScrollArea::vertical()
+                    .max_height(180.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut self.payload)
+                                .desired_rows(8)
+                                .desired_width(f32::INFINITY)
+                                .interactive(self.mode == Mode::Hide),
+                        );
+                    });
+            });
+            ui.add_space(8.0);
+            ui.horizontal(|ui| match &self.job {
+                Some(job) => {
+                    ui.spinner();
+                    ui.label(tr!(
+                        "gui-working",
+                        seconds = format!("{:.1}", job.started.elapsed().as_secs_f32())
+                    ));
+                    if ui.button(tr!("gui-cancel")).clicked() {
+                        job.token.cancel();
+                    }
+                }
+                None => {
+                    let ready = self.carrier.is_some()
+                        && !self.key.is_empty()
+                        && (self.mode == Mode::Reveal || !self.payload.is_empty());
+                    let label = match self.mode {
+                        Mode::Hide => tr!("gui-hide"),
+                        Mode::Reveal => tr!("gui-reveal"),
+                    };
+                    if ui.add_enabled(ready, egui::Button::new(label)).clicked() {
+                        self.start(ctx);
+                    }
+                }
+            });
+            ui.add_space(4.0);
+            match &self.status {
+                Ok(note) => ui.label(note),
+                Err(err) => ui.colored_label(ui.visuals().error_fg_color, err),
+            };
+        });
+        if self.job.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
+}
@@ -0,0 +1,755 @@
+use crate::envelope::{age_decrypt, is_age};
+use crate::error::SteganoError;
+use crate::hint::split_hint;
+use crate::json::{parse_rfc3339, rfc3339};
+use crate::utils::{decrypt_with, decrypt_with_mode, is_aes, strip_padding, CipherMode, Padding};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Magic bytes opening a payload header.
+pub const HEADER_MAGIC: &[u8; 4] = b"STpl";
+
+/// The newest header version this release reads, and the one it writes for payloads with a
+/// view counter.
+pub const HEADER_VERSION: u8 = 6;
+
+/// The header version written for payloads without a view counter, 4 bytes shorter than
+/// `HEADER_VERSION` so that small carriers keep their capacity.
+pub const PLAIN_HEADER_VERSION: u8 = 5;
+
+/// Key derivation id for keys used as they are, cut or zero-padded to the cipher key size.
+pub const KDF_NONE: u8 = 0;
+
+/// Key derivation id for keys hashed with SHA-256 and cut to the cipher key size, as AES-192
+/// and AES-256 do.
+pub const KDF_SHA256: u8 = 1;
+
+/// Number of bytes of the shortest header, of version 1 without MIME type and filename.
+pub const MIN_HEADER_LEN: usize = 30;
+
+/// Number of bytes of the authentication tag closing headers from version 2 on, and of the
+/// one closing payloads from version 3 on.
+pub const TAG_LEN: usize = 16;
+
+/// Number of bytes of the check closing precision payloads, out of the few the method holds.
+pub const PRECISION_TAG_LEN: usize = 1;
+
+/// The plaintext header written in front of the ciphertext of an embedded payload.
+///
+/// It tells readers how to decrypt what follows and how long the plaintext is, so AES padding
+/// is trimmed exactly instead of guessed. Version 6 is laid out as follows, with integers in
+/// big-endian order, and followed by the ciphertext and the payload tag:
+///
+/// | Bytes  | Field                                                    |
+/// |--------|----------------------------------------------------------|
+/// | 4      | `STpl`                                                   |
+/// | 1      | Format version                                           |
+/// | 1      | Algorithm id: 1 AES-128, 2 XOR, 3 AES-192, 4 AES-256     |
+/// | 1      | Key derivation id: 0 for the key as it is, 1 for SHA-256 |
+/// | 4      | Key derivation iterations                                |
+/// | 8      | Plaintext length                                         |
+/// | 8      | Creation time, in seconds since the Unix epoch           |
+/// | 8      | Expiry time, in seconds since the Unix epoch, 0 for none |
+/// | 1 + 16 | Block mode: 0 ECB, 1 CBC, 2 CTR; then the IV unless ECB  |
+/// | 1      | Padding of ECB and CBC: 0 for zeros, 1 for PKCS#7        |
+/// | 4      | Views left before the payload is wiped, 0 for no limit   |
+/// | 1 + n  | MIME type length and MIME type, in UTF-8                 |
+/// | 2 + n  | Filename length and filename, in UTF-8                   |
+/// | 16     | HMAC-SHA256 of the bytes above with the key, truncated   |
+///
+/// The IV follows the block mode unless it is ECB. The payload tag is computed by
+/// `payload_tag` over the header and the ciphertext. Version 5 has no view counter, version 4
+/// has no padding byte either, its payloads being zero-padded, version 3 has no block mode
+/// either, its payloads being in ECB, version 2 has no payload tag either, and version 1 has
+/// neither the expiry time nor the header tag. The header isn't encrypted:
+/// anyone can read it with `stegano peek`, MIME type and filename included. But the tags bind
+/// it and the ciphertext to the key, so `open_payload` tells a wrong key or a damaged payload
+/// from a secret, instead of decrypting garbage. Readers refuse versions newer than
+/// `HEADER_VERSION` rather than misread them.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::header::PayloadHeader;
+///
+/// let mut header = PayloadHeader::new("aes", 5, 1_700_000_000);
+/// header.mime_type = Some("text/plain".to_string());
+/// let mut embedded = header.to_bytes("key").unwrap();
+/// embedded.extend_from_slice(b"ciphertext");
+///
+/// let (parsed, ciphertext) = PayloadHeader::parse(&embedded).unwrap();
+/// assert_eq!(parsed, header);
+/// assert_eq!(ciphertext, b"ciphertext");
+/// assert!(PayloadHeader::parse(b"ciphertext").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadHeader {
+    /// The format version.
+    pub version: u8,
+    /// The encryption algorithm: aes, aes192, aes256, chacha or xor.
+    pub algorithm: String,
+    /// The key derivation id: `KDF_SHA256` for AES-192, AES-256 and ChaCha20, `KDF_NONE` otherwise.
+    pub kdf: u8,
+    /// The key derivation iterations, 0 without derivation.
+    pub kdf_iterations: u32,
+    /// The block mode of AES payloads and its IV, ECB up to version 3.
+    pub mode: CipherMode,
+    /// The padding of AES payloads in ECB and CBC, zeros up to version 4.
+    pub padding: Padding,
+    /// The length of the plaintext, without padding.
+    pub payload_len: u64,
+    /// The creation time, in seconds since the Unix epoch, or 0 if unknown.
+    pub created: u64,
+    /// The time after which the payload shouldn't be revealed, in seconds since the Unix
+    /// epoch, or 0 if it never expires.
+    pub expires: u64,
+    /// The number of times `decrypt --burn` may still reveal the payload before wiping it, or
+    /// 0 if there is no limit.
+    pub views: u32,
+    /// The MIME type of the plaintext, if given.
+    pub mime_type: Option<String>,
+    /// The original filename of the plaintext, if given.
+    pub filename: Option<String>,
+}
+
+/// Builds the error returned for malformed headers.
+fn malformed(reason: impl fmt::Display) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("Malformed payload header: {}", reason),
+    )
+}
+
+/// Returns the id of an algorithm in the header.
+fn algorithm_id(algorithm: &str) -> io::Result<u8> {
+    match algorithm.to_lowercase().as_str() {
+        "aes" => Ok(1),
+        "xor" => Ok(2),
+        "aes192" => Ok(3),
+        "aes256" => Ok(4),
+        "chacha" => Ok(5),
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "Unsupported algorithm!",
+        )),
+    }
+}
+
+/// Returns the id of a block mode in the header.
+fn mode_id(mode: &CipherMode) -> u8 {
+    match mode {
+        CipherMode::Ecb => 0,
+        CipherMode::Cbc(_) => 1,
+        CipherMode::Ctr(_) => 2,
+    }
+}
+
+/// Returns the key derivation id an algorithm uses, see `utils::derive_aes_key`.
+pub fn kdf_for(algorithm: &str) -> u8 {
+    match algorithm.to_lowercase().as_str() {
+        "aes192" | "aes256" | "chacha" => KDF_SHA256,
+        _ => KDF_NONE,
+    }
+}
+
+/// Computes the HMAC-SHA256 of a stream, truncated to `TAG_LEN` bytes.
+fn hmac<R: Read>(key: &str, mut message: R) -> io::Result<[u8; TAG_LEN]> {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block[..32].copy_from_slice(&Sha256::digest(key.as_bytes()));
+    } else {
+        block[..key.len()].copy_from_slice(key.as_bytes());
+    }
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+    let mut inner = Sha256::new().chain_update(pad(0x36));
+    let mut buffer = [0u8; 8192];
+    loop {
+        match message.read(&mut buffer)? {
+            0 => break,
+            read => inner.update(&buffer[..read]),
+        }
+    }
+    let outer = Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner.finalize())
+        .finalize();
+    Ok(outer[..TAG_LEN].try_into().unwrap())
+}
+
+/// Computes the HMAC-SHA256 of a message, truncated to `TAG_LEN` bytes.
+fn tag(key: &str, message: &[u8]) -> [u8; TAG_LEN] {
+    hmac(key, message).expect("reading a slice doesn't fail")
+}
+
+/// Computes the tag closing a payload from header version 3 on.
+///
+/// The tag is the HMAC-SHA256 of the header, its own tag included, and of the ciphertext,
+/// with the key, truncated to `TAG_LEN` bytes. Writers append it once the ciphertext is
+/// written; it is read from a stream so that payloads spilled to disk needn't be loaded.
+///
+/// # Arguments
+///
+/// * `key` - The key of the payload.
+/// * `sealed` - The header followed by the ciphertext, without any hint.
+///
+/// # Returns
+///
+/// A `Result` containing the tag, or the error of reading `sealed`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::header::{open_payload, payload_tag, PayloadHeader};
+/// use stegano::utils::encrypt_stream;
+///
+/// let mut embedded = PayloadHeader::new("xor", 5, 0).to_bytes("key").unwrap();
+/// encrypt_stream("xor", "key", &b"hello"[..], &mut embedded).unwrap();
+/// let tag = payload_tag("key", &embedded[..]).unwrap();
+/// embedded.extend_from_slice(&tag);
+/// assert_eq!(open_payload(&embedded, "xor", "key").unwrap().1, b"hello");
+///
+/// // A flipped ciphertext bit is caught, where XOR alone would decrypt it to garbage.
+/// let len = embedded.len();
+/// embedded[len - 17] ^= 1;
+/// assert!(open_payload(&embedded, "xor", "key").is_err());
+/// ```
+pub fn payload_tag<R: Read>(key: &str, sealed: R) -> io::Result<[u8; TAG_LEN]> {
+    hmac(key, sealed)
+}
+
+/// Computes the check byte closing a precision payload, which has no room for a header or a
+/// whole payload tag.
+///
+/// It is the first byte of the payload tag of the ciphertext alone, so a wrong key is caught
+/// 255 times out of 256 rather than every time.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::header::{open_precision, precision_tag};
+/// use stegano::utils::encrypt_with;
+///
+/// let mut embedded = encrypt_with("xor", "key", "wm42").unwrap();
+/// embedded.extend_from_slice(&precision_tag("key", &embedded));
+/// assert_eq!(open_precision(&embedded, "xor", "key").unwrap(), b"wm42");
+/// assert!(open_precision(&embedded, "xor", "not the key").is_err());
+/// ```
+pub fn precision_tag(key: &str, ciphertext: &[u8]) -> [u8; PRECISION_TAG_LEN] {
+    let mut check = [0; PRECISION_TAG_LEN];
+    check.copy_from_slice(&tag(key, ciphertext)[..PRECISION_TAG_LEN]);
+    check
+}
+
+/// Checks a precision payload against its check byte and decrypts it.
+///
+/// # Arguments
+///
+/// * `embedded` - The bytes extracted from the carrier.
+/// * `algorithm` - The algorithm the payload was encrypted with.
+/// * `key` - The decryption key.
+///
+/// # Returns
+///
+/// A `Result` containing the plaintext, or a `WrongKey` error if the check byte doesn't match.
+pub fn open_precision(embedded: &[u8], algorithm: &str, key: &str) -> io::Result<Vec<u8>> {
+    let body = split_hint(embedded).1;
+    let split = body
+        .len()
+        .checked_sub(PRECISION_TAG_LEN)
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "The precision payload is empty"))?;
+    if body[split..] != precision_tag(key, &body[..split]) {
+        return Err(SteganoError::WrongKey(
+            "Incorrect key or corrupted payload: the precision payload doesn't match its check \
+             byte"
+                .into(),
+        )
+        .into());
+    }
+    let mut plaintext = decrypt_with(algorithm, key, &body[..split])
+        .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+    strip_padding(algorithm, &mut plaintext);
+    Ok(plaintext)
+}
+
+/// Parses the value of `--expires`: a `YYYY-MM-DD` date, expiring once that day is over in
+/// UTC, or an RFC 3339 UTC timestamp such as `2025-12-31T18:00:00Z`.
+///
+/// # Returns
+///
+/// A `Result` containing the expiry time in seconds since the Unix epoch.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::header::parse_expiry;
+///
+/// assert_eq!(parse_expiry("1970-01-01").unwrap(), 86_400);
+/// assert_eq!(parse_expiry("1970-01-01T00:01:00Z").unwrap(), 60);
+/// assert!(parse_expiry("31/12/2025").is_err());
+/// ```
+pub fn parse_expiry(value: &str) -> Result<u64, String> {
+    let error = || {
+        format!(
+            "invalid expiry {:?}, expected YYYY-MM-DD or YYYY-MM-DDTHH:MM:SSZ",
+            value
+        )
+    };
+    let time = parse_rfc3339(value).ok_or_else(error)?;
+    // A bare date covers the whole day.
+    Ok(if value.len() == 10 {
+        time + 86_400
+    } else {
+        time
+    })
+}
+
+/// Returns the current time in seconds since the Unix epoch, or 0 where there is no clock.
+pub fn unix_now() -> u64 {
+    // The standard clock panics in the browser.
+    #[cfg(target_arch = "wasm32")]
+    return 0;
+    #[cfg(not(target_arch = "wasm32"))]
+    std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+impl PayloadHeader {
+    /// Creates a header of `PLAIN_HEADER_VERSION`, with the key derivation of the algorithm, in
+    /// ECB with PKCS#7 padding, and without MIME type, filename or view counter. `with_mode`
+    /// picks another block mode, and `with_views` sets a view counter.
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - The encryption algorithm of the payload.
+    /// * `payload_len` - The length of the plaintext.
+    /// * `created` - The creation time, in seconds since the Unix epoch.
+    pub fn new(algorithm: &str, payload_len: u64, created: u64) -> PayloadHeader {
+        PayloadHeader {
+            version: PLAIN_HEADER_VERSION,
+            algorithm: algorithm.to_lowercase(),
+            kdf: kdf_for(algorithm),
+            kdf_iterations: 0,
+            mode: CipherMode::Ecb,
+            padding: Padding::Pkcs7,
+            payload_len,
+            created,
+            expires: 0,
+            views: 0,
+            mime_type: None,
+            filename: None,
+        }
+    }
+
+    /// Sets the block mode of an AES payload by name, drawing a fresh IV.
+    ///
+    /// XOR payloads have no blocks: they keep ECB, which means none to them. ChaCha20 payloads
+    /// always take CTR, whose IV holds their nonce, whatever the mode asked for.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the header, or an `InvalidInput` error for unknown modes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::header::PayloadHeader;
+    ///
+    /// let header = PayloadHeader::new("aes", 5, 0).with_mode("ctr").unwrap();
+    /// assert_eq!(header.mode.name(), "ctr");
+    /// let header = PayloadHeader::new("xor", 5, 0).with_mode("ctr").unwrap();
+    /// assert_eq!(header.mode.name(), "ecb");
+    /// let header = PayloadHeader::new("chacha", 5, 0).with_mode("ecb").unwrap();
+    /// assert_eq!(header.mode.name(), "ctr");
+    /// ```
+    pub fn with_mode(mut self, mode: &str) -> io::Result<PayloadHeader> {
+        let mode = CipherMode::new(mode)?;
+        if self.algorithm == "chacha" {
+            self.mode = CipherMode::new("ctr")?;
+        } else if is_aes(&self.algorithm) {
+            self.mode = mode;
+        }
+        Ok(self)
+    }
+
+    /// Sets the number of times `decrypt --burn` may reveal the payload, moving the header to
+    /// `HEADER_VERSION`, which records it, or back to `PLAIN_HEADER_VERSION` for 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::header::{PayloadHeader, HEADER_VERSION, PLAIN_HEADER_VERSION};
+    ///
+    /// let plain = PayloadHeader::new("aes", 5, 0);
+    /// let counted = plain.clone().with_views(3);
+    /// assert_eq!((plain.version, counted.version), (PLAIN_HEADER_VERSION, HEADER_VERSION));
+    /// assert_eq!(counted.to_bytes("key").unwrap().len(), plain.to_bytes("key").unwrap().len() + 4);
+    /// assert_eq!(PayloadHeader::parse(&counted.to_bytes("key").unwrap()).unwrap().0.views, 3);
+    /// ```
+    pub fn with_views(mut self, views: u32) -> PayloadHeader {
+        self.views = views;
+        self.version = if views == 0 {
+            PLAIN_HEADER_VERSION
+        } else {
+            HEADER_VERSION
+        };
+        self
+    }
+
+    /// Serializes the header in the layout of its version.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the payload, which signs headers from version 2 on.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes, or an `InvalidInput` error for unknown algorithms, MIME
+    /// types over 255 bytes, filenames over 65535 bytes, expiry times in version 1, block
+    /// modes before version 4, PKCS#7 padding before version 5 and view counters before
+    /// version 6.
+    pub fn to_bytes(&self, key: &str) -> io::Result<Vec<u8>> {
+        if self.version < 2 && self.expires != 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Version 1 headers can't record an expiry time",
+            ));
+        }
+        if self.version < 4 && self.mode != CipherMode::Ecb {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Headers before version 4 can't record a block mode",
+            ));
+        }
+        if self.version < 5 && self.padding != Padding::Zeros {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Headers before version 5 can't record a padding scheme",
+            ));
+        }
+        if self.version < 6 && self.views != 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Headers before version 6 can't record a view counter",
+            ));
+        }
+        let mime = self.mime_type.as_deref().unwrap_or("").as_bytes();
+        let name = self.filename.as_deref().unwrap_or("").as_bytes();
+        let mime_len = u8::try_from(mime.len()).map_err(|_| {
+            io::Error::new(ErrorKind::InvalidInput, "MIME types are at most 255 bytes")
+        })?;
+        let name_len = u16::try_from(name.len()).map_err(|_| {
+            io::Error::new(ErrorKind::InvalidInput, "Filenames are at most 65535 bytes")
+        })?;
+        let mut bytes = Vec::with_capacity(MIN_HEADER_LEN + 28 + mime.len() + name.len());
+        bytes.extend_from_slice(HEADER_MAGIC);
+        bytes.push(self.version);
+        bytes.push(algorithm_id(&self.algorithm)?);
+        bytes.push(self.kdf);
+        bytes.extend_from_slice(&self.kdf_iterations.to_be_bytes());
+        bytes.extend_from_slice(&self.payload_len.to_be_bytes());
+        bytes.extend_from_slice(&self.created.to_be_bytes());
+        if self.version >= 2 {
+            bytes.extend_from_slice(&self.expires.to_be_bytes());
+        }
+        if self.version >= 4 {
+            bytes.push(mode_id(&self.mode));
+            bytes.extend_from_slice(self.mode.iv().map_or(&[][..], |iv| &iv[..]));
+        }
+        if self.version >= 5 {
+            bytes.push(match self.padding {
+                Padding::Zeros => 0,
+                Padding::Pkcs7 => 1,
+            });
+        }
+        if self.version >= 6 {
+            bytes.extend_from_slice(&self.views.to_be_bytes());
+        }
+        bytes.push(mime_len);
+        bytes.extend_from_slice(mime);
+        bytes.extend_from_slice(&name_len.to_be_bytes());
+        bytes.extend_from_slice(name);
+        if self.version >= 2 {
+            let tag = tag(key, &bytes);
+            bytes.extend_from_slice(&tag);
+        }
+        Ok(bytes)
+    }
+
+    /// Parses the header at the start of an embedded payload, after any hint, without checking
+    /// its tag.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the header and the ciphertext that follows it, or an
+    /// `InvalidData` error if there is no header, it is malformed, or it was written by a newer
+    /// release.
+    pub fn parse(data: &[u8]) -> io::Result<(PayloadHeader, &[u8])> {
+        PayloadHeader::parse_tagged(data).map(|(header, _, ciphertext)| (header, ciphertext))
+    }
+
+    /// Parses a header, returning it with its bytes before the tag and the ciphertext.
+    fn parse_tagged(data: &[u8]) -> io::Result<(PayloadHeader, &[u8], &[u8])> {
+        if !data.starts_with(HEADER_MAGIC) {
+            return Err(malformed("no header found"));
+        }
+        if data.len() < MIN_HEADER_LEN {
+            return Err(malformed("truncated"));
+        }
+        let version = data[4];
+        if version == 0 || version > HEADER_VERSION {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "The payload uses format version {}, this release reads up to version {}; \
+                     upgrade stegano to read it",
+                    version, HEADER_VERSION
+                ),
+            ));
+        }
+        let algorithm = match data[5] {
+            1 => "aes",
+            2 => "xor",
+            3 => "aes192",
+            4 => "aes256",
+            5 => "chacha",
+            id => return Err(malformed(format!("unknown algorithm id {}", id))),
+        };
+        let u64_at = |pos: usize| u64::from_be_bytes(data[pos..pos + 8].try_into().unwrap());
+        let text = |pos: usize, len: usize| -> io::Result<Option<String>> {
+            let bytes = data
+                .get(pos..pos + len)
+                .ok_or_else(|| malformed("truncated"))?;
+            let text = std::str::from_utf8(bytes).map_err(|_| malformed("text isn't UTF-8"))?;
+            Ok((!text.is_empty()).then(|| text.to_string()))
+        };
+        let (expires, mode_pos) = if version >= 2 {
+            let expires = data.get(27..35).ok_or_else(|| malformed("truncated"))?;
+            (u64::from_be_bytes(expires.try_into().unwrap()), 35)
+        } else {
+            (0, 27)
+        };
+        let (mode, padding_pos) = if version >= 4 {
+            let id = *data.get(mode_pos).ok_or_else(|| malformed("truncated"))?;
+            let iv = || -> io::Result<[u8; 16]> {
+                let iv = data
+                    .get(mode_pos + 1..mode_pos + 17)
+                    .ok_or_else(|| malformed("truncated"))?;
+                Ok(iv.try_into().unwrap())
+            };
+            match id {
+                0 => (CipherMode::Ecb, mode_pos + 1),
+                1 => (CipherMode::Cbc(iv()?), mode_pos + 17),
+                2 => (CipherMode::Ctr(iv()?), mode_pos + 17),
+                id => return Err(malformed(format!("unknown block mode id {}", id))),
+            }
+        } else {
+            (CipherMode::Ecb, mode_pos)
+        };
+        let (padding, views_pos) = if version >= 5 {
+            match data.get(padding_pos) {
+                Some(0) => (Padding::Zeros, padding_pos + 1),
+                Some(1) => (Padding::Pkcs7, padding_pos + 1),
+                Some(id) => return Err(malformed(format!("unknown padding id {}", id))),
+                None => return Err(malformed("truncated")),
+            }
+        } else {
+            (Padding::Zeros, padding_pos)
+        };
+        let (views, mime_pos) = if version >= 6 {
+            let views = data
+                .get(views_pos..views_pos + 4)
+                .ok_or_else(|| malformed("truncated"))?;
+            (u32::from_be_bytes(views.try_into().unwrap()), views_pos + 4)
+        } else {
+            (0, views_pos)
+        };
+        let mime_len = *data.get(mime_pos).ok_or_else(|| malformed("truncated"))? as usize;
+        let mime_type = text(mime_pos + 1, mime_len)?;
+        let name_pos = mime_pos + 1 + mime_len;
+        let name_len = data
+            .get(name_pos..name_pos + 2)
+            .map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+            .ok_or_else(|| malformed("truncated"))?;
+        let filename = text(name_pos + 2, name_len)?;
+        let header = PayloadHeader {
+            version,
+            algorithm: algorithm.to_string(),
+            kdf: data[6],
+            kdf_iterations: u32::from_be_bytes(data[7..11].try_into().unwrap()),
+            mode,
+            padding,
+            payload_len: u64_at(11),
+            created: u64_at(19),
+            expires,
+            views,
+            mime_type,
+            filename,
+        };
+        if header.kdf != kdf_for(algorithm) {
+            return Err(malformed(format!(
+                "key derivation id {} doesn't go with {}",
+                header.kdf, algorithm
+            )));
+        }
+        let end = name_pos + 2 + name_len;
+        let tag_len = if version >= 2 { TAG_LEN } else { 0 };
+        if data.len() < end + tag_len {
+            return Err(malformed("truncated"));
+        }
+        Ok((header, &data[..end], &data[end + tag_len..]))
+    }
+
+    /// Tells whether the payload is past its expiry time.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, in seconds since the Unix epoch.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires != 0 && now >= self.expires
+    }
+}
+
+impl fmt::Display for PayloadHeader {
+    /// Summarizes the header on one line, as printed by `decrypt` and `peek`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "format {}, {}", self.version, self.algorithm)?;
+        if self.mode != CipherMode::Ecb {
+            write!(f, "-{}", self.mode.name())?;
+        }
+        write!(f, ", {} bytes", self.payload_len)?;
+        if self.created > 0 {
+            let created = UNIX_EPOCH + Duration::from_secs(self.created);
+            write!(f, ", created {}", rfc3339(created))?;
+        }
+        if self.expires > 0 {
+            let expires = UNIX_EPOCH + Duration::from_secs(self.expires);
+            write!(f, ", expires {}", rfc3339(expires))?;
+        }
+        if self.views > 0 {
+            write!(f, ", views left: {}", self.views)?;
+        }
+        if let Some(mime_type) = &self.mime_type {
+            write!(f, ", {}", mime_type)?;
+        }
+        if let Some(filename) = &self.filename {
+            write!(f, ", named {:?}", filename)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decrypts an embedded payload: skips the hint, reads the header and decrypts the ciphertext
+/// with the algorithm it names, trimmed to the plaintext length.
+///
+/// The tag of headers from version 2 on is checked first: a mismatch means a wrong key or an
+/// altered header. From version 3 on, the payload tag is checked next: the key is right by
+/// then, so a mismatch means the ciphertext was damaged. Nothing is decrypted unless both
+/// match. Expiry isn't enforced here, callers decide with `is_expired`.
+///
+/// Payloads written before the header existed start right with the ciphertext. They are
+/// recognized by the missing magic and decrypted the old way, with `algorithm` and the AES
+/// padding stripped, so older stego files keep opening. Payloads embedded with `--algo age`
+/// are age files, opened with the key as their passphrase; see `envelope::age_decrypt` for
+/// those encrypted to recipients.
+///
+/// # Arguments
+///
+/// * `embedded` - The bytes extracted from the carrier.
+/// * `algorithm` - The algorithm of legacy payloads, ignored when there is a header.
+/// * `key` - The decryption key.
+///
+/// # Returns
+///
+/// A `Result` containing the header, `None` for legacy and age payloads, and the plaintext, a
+/// `WrongKey` error if the header tag doesn't match, or an `InvalidData` error if the payload
+/// tag doesn't.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::header::{open_payload, payload_tag, PayloadHeader};
+/// use stegano::utils::{encrypt_stream, encrypt_stream_mode};
+///
+/// let sealed = PayloadHeader::new("aes", 5, 0);
+/// let mut embedded = sealed.to_bytes("key").unwrap();
+/// encrypt_stream_mode("aes", "key", &sealed.mode, sealed.padding, &b"hello"[..], &mut embedded)
+///     .unwrap();
+/// let tag = payload_tag("key", &embedded[..]).unwrap();
+/// embedded.extend_from_slice(&tag);
+/// let (header, plaintext) = open_payload(&embedded, "xor", "key").unwrap();
+/// assert_eq!(header.unwrap().algorithm, "aes");
+/// assert_eq!(plaintext, b"hello");
+/// assert!(open_payload(&embedded, "aes", "wrong key").is_err());
+///
+/// let mut legacy = Vec::new();
+/// encrypt_stream("aes", "key", &b"hello"[..], &mut legacy).unwrap();
+/// let (header, plaintext) = open_payload(&legacy, "aes", "key").unwrap();
+/// assert!(header.is_none());
+/// assert_eq!(plaintext, b"hello");
+/// ```
+pub fn open_payload(
+    embedded: &[u8],
+    algorithm: &str,
+    key: &str,
+) -> io::Result<(Option<PayloadHeader>, Vec<u8>)> {
+    let body = split_hint(embedded).1;
+    // Age files carry no header, their own format authenticates them.
+    if is_age(body) {
+        return Ok((None, age_decrypt(body, &[], key)?));
+    }
+    if !is_current(body) {
+        let mut plaintext = decrypt_with(algorithm, key, body)
+            .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+        strip_padding(algorithm, &mut plaintext);
+        return Ok((None, plaintext));
+    }
+    let (header, signed, mut ciphertext) = PayloadHeader::parse_tagged(body)?;
+    if header.version >= 2 && body[signed.len()..signed.len() + TAG_LEN] != tag(key, signed) {
+        return Err(SteganoError::WrongKey(
+            "Incorrect key or corrupted payload: the payload header doesn't match the key".into(),
+        )
+        .into());
+    }
+    if header.version >= 3 {
+        let split = body
+            .len()
+            .checked_sub(TAG_LEN)
+            .filter(|&split| split >= signed.len() + TAG_LEN)
+            .ok_or_else(|| malformed("the payload tag is missing"))?;
+        if body[split..] != tag(key, &body[..split]) {
+            return Err(SteganoError::Format(
+                "Incorrect key or corrupted payload: the ciphertext doesn't match its \
+                 authentication tag"
+                    .into(),
+            )
+            .into());
+        }
+        ciphertext = &ciphertext[..ciphertext.len() - TAG_LEN];
+    }
+    let mut plaintext = decrypt_with_mode(
+        &header.algorithm,
+        key,
+        &header.mode,
+        header.padding,
+        ciphertext,
+    )
+    .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+    let len = usize::try_from(header.payload_len)
+        .ok()
+        .filter(|&len| len <= plaintext.len())
+        .ok_or_else(|| malformed("the ciphertext is shorter than the payload length"))?;
+    plaintext.truncate(len);
+    Ok((Some(header), plaintext))
+}
+
+/// Tells whether an embedded payload, after its hint, starts with a header rather than being
+/// a legacy payload of bare ciphertext.
+///
+/// A legacy ciphertext starting with the magic by chance, one in four billion, is taken for a
+/// header and fails to parse rather than decrypting to garbage.
+pub fn is_current(body: &[u8]) -> bool {
+    body.starts_with(HEADER_MAGIC)
+}
@@ -0,0 +1,92 @@
+/// Returns the side of the smallest Hilbert curve covering a `width` by `height` grid, a power
+/// of two.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::hilbert::curve_side;
+///
+/// assert_eq!(curve_side(1, 1), 1);
+/// assert_eq!(curve_side(5, 3), 8);
+/// assert_eq!(curve_side(16, 16), 16);
+/// ```
+pub fn curve_side(width: u32, height: u32) -> u32 {
+    width.max(height).max(1).next_power_of_two()
+}
+
+/// Returns the distance along the Hilbert curve of side `side` of the point `(x, y)`.
+///
+/// The curve starts at `(0, 0)`, ends at `(side - 1, 0)` and moves one cell at a time, so
+/// points close along the curve are close in the grid. Points of a grid smaller than the
+/// curve are ordered by their distance, skipping the cells outside it.
+///
+/// # Arguments
+///
+/// * `side` - The side of the curve, a power of two.
+/// * `x`, `y` - The point, both below `side`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::hilbert::{distance, point};
+///
+/// assert_eq!(distance(2, 0, 0), 0);
+/// assert_eq!(distance(2, 0, 1), 1);
+/// assert_eq!(distance(2, 1, 1), 2);
+/// assert_eq!(distance(2, 1, 0), 3);
+/// for d in 0..64 {
+///     let (x, y) = point(8, d);
+///     assert_eq!(distance(8, x, y), d);
+/// }
+/// ```
+pub fn distance(side: u32, x: u32, y: u32) -> u64 {
+    let (mut x, mut y) = (x, y);
+    let mut d = 0u64;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = u32::from(x & s > 0);
+        let ry = u32::from(y & s > 0);
+        d += u64::from(s) * u64::from(s) * u64::from((3 * rx) ^ ry);
+        rotate(side, &mut x, &mut y, rx, ry);
+        s /= 2;
+    }
+    d
+}
+
+/// Returns the point at distance `d` along the Hilbert curve of side `side`, the inverse of
+/// `distance`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::hilbert::point;
+///
+/// let path: Vec<_> = (0..4).map(|d| point(2, d)).collect();
+/// assert_eq!(path, [(0, 0), (0, 1), (1, 1), (1, 0)]);
+/// ```
+pub fn point(side: u32, d: u64) -> (u32, u32) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < side {
+        let rx = (1 & (t / 2)) as u32;
+        let ry = (1 & (t ^ u64::from(rx))) as u32;
+        rotate(s, &mut x, &mut y, rx, ry);
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
+/// Rotates and flips a quadrant so that the sub-curve in it runs the right way.
+fn rotate(side: u32, x: &mut u32, y: &mut u32, rx: u32, ry: u32) {
+    if ry == 0 {
+        if rx == 1 {
+            *x = side - 1 - *x;
+            *y = side - 1 - *y;
+        }
+        std::mem::swap(x, y);
+    }
+}
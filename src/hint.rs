@@ -0,0 +1,71 @@
+use std::io::{self, ErrorKind};
+
+/// Magic bytes opening a hint frame.
+pub const HINT_MAGIC: &[u8; 4] = b"STht";
+
+/// Longest hint accepted, in bytes.
+pub const MAX_HINT_LEN: usize = 1024;
+
+/// Builds the plaintext frame stored in front of the encrypted payload to carry a hint.
+///
+/// The frame is `STht`, the length of the hint as a 16-bit big-endian integer and the hint in
+/// UTF-8. It is never encrypted: anyone can read it with `stegano peek`, and its magic bytes
+/// tell that the file carries a payload, so hints trade stealth for a recovery breadcrumb.
+///
+/// # Arguments
+///
+/// * `hint` - A reminder of where to find the key, such as "ask Bob for the phrase".
+///
+/// # Returns
+///
+/// A `Result` containing the frame, or an `InvalidInput` error if the hint is empty or longer
+/// than `MAX_HINT_LEN` bytes.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::hint::{hint_frame, split_hint};
+///
+/// let mut embedded = hint_frame("ask Bob for the phrase").unwrap();
+/// embedded.extend_from_slice(b"ciphertext");
+/// let (hint, encrypted) = split_hint(&embedded);
+/// assert_eq!(hint, Some("ask Bob for the phrase"));
+/// assert_eq!(encrypted, b"ciphertext");
+/// assert_eq!(split_hint(b"ciphertext"), (None, &b"ciphertext"[..]));
+/// ```
+pub fn hint_frame(hint: &str) -> io::Result<Vec<u8>> {
+    if hint.is_empty() || hint.len() > MAX_HINT_LEN {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Hints must be 1 to {} bytes long", MAX_HINT_LEN),
+        ));
+    }
+    let mut frame = Vec::with_capacity(6 + hint.len());
+    frame.extend_from_slice(HINT_MAGIC);
+    frame.extend_from_slice(&(hint.len() as u16).to_be_bytes());
+    frame.extend_from_slice(hint.as_bytes());
+    Ok(frame)
+}
+
+/// Separates the hint frame, if any, from the encrypted payload that follows it.
+///
+/// Payloads embedded without a hint are returned as they are. A ciphertext that happens to
+/// start with the magic bytes and a valid frame would be misread, which has a chance below one
+/// in four billion.
+///
+/// # Returns
+///
+/// The hint, if there is one, and the encrypted payload.
+pub fn split_hint(data: &[u8]) -> (Option<&str>, &[u8]) {
+    if data.len() < 6 || &data[..4] != HINT_MAGIC {
+        return (None, data);
+    }
+    let len = u16::from_be_bytes([data[4], data[5]]) as usize;
+    match data
+        .get(6..6 + len)
+        .and_then(|hint| std::str::from_utf8(hint).ok())
+    {
+        Some(hint) if len > 0 && len <= MAX_HINT_LEN => (Some(hint), &data[6 + len..]),
+        _ => (None, data),
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// The language messages fall back to when a catalog lacks them.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// The environment variable naming a directory of extra `<lang>.ftl` catalogs.
+pub const LOCALE_DIR_ENV: &str = "STEGANO_LOCALE_DIR";
+
+/// The catalogs built into the binary, by language tag.
+const BUILTIN: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+];
+
+/// The catalog selected with `set_language`, or `None` for English.
+static ACTIVE: RwLock<Option<Arc<Catalog>>> = RwLock::new(None);
+
+/// The English catalog, parsed on first use.
+static ENGLISH: RwLock<Option<Arc<Catalog>>> = RwLock::new(None);
+
+/// The messages of one language, read from a catalog in a subset of the Fluent syntax.
+///
+/// Each message is an `id = text` line, where `{ $name }` stands for an argument. Indented
+/// lines continue the message above on a new line, and lines starting with `#` are comments.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::i18n::Catalog;
+///
+/// let catalog = Catalog::parse(
+///     "fr",
+///     "# Greetings\nhello = Bonjour { $name } !\nlines = one\n    two\n",
+/// )
+/// .unwrap();
+/// assert_eq!(catalog.format("hello", &[("name", &"Ada")]).unwrap(), "Bonjour Ada !");
+/// assert_eq!(catalog.format("lines", &[]).unwrap(), "one\ntwo");
+/// assert!(catalog.format("missing", &[]).is_none());
+/// assert!(Catalog::parse("fr", "no equals sign").is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    /// The language tag, such as `fr` or `pt-BR`.
+    pub language: String,
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Parses the text of a catalog.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the catalog, or an `InvalidData` error naming the first line that
+    /// is neither a message, a continuation nor a comment.
+    pub fn parse(language: &str, text: &str) -> io::Result<Catalog> {
+        let mut messages: HashMap<String, String> = HashMap::new();
+        let mut last: Option<String> = None;
+        for (number, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                if let Some(value) = last.as_ref().and_then(|id| messages.get_mut(id)) {
+                    if !value.is_empty() {
+                        value.push('\n');
+                    }
+                    value.push_str(trimmed);
+                    continue;
+                }
+            }
+            let (id, value) = trimmed
+                .split_once('=')
+                .filter(|(id, _)| is_message_id(id.trim()))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Line {} of the {} catalog is not an `id = text` message: {:?}",
+                            number + 1,
+                            language,
+                            trimmed
+                        ),
+                    )
+                })?;
+            let id = id.trim().to_string();
+            messages.insert(id.clone(), value.trim().to_string());
+            last = Some(id);
+        }
+        Ok(Catalog {
+            language: language.to_string(),
+            messages,
+        })
+    }
+
+    /// Returns the ids of the messages, sorted.
+    pub fn ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.messages.keys().map(String::as_str).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Formats a message, replacing each `{ $name }` with the argument of that name.
+    ///
+    /// Placeables naming no argument are kept as they are, so a mistake in a translation
+    /// shows up in the output rather than silently dropping text.
+    ///
+    /// # Returns
+    ///
+    /// The formatted message, or `None` if the catalog lacks it.
+    pub fn format(&self, id: &str, args: &[(&str, &dyn fmt::Display)]) -> Option<String> {
+        let template = self.messages.get(id)?;
+        let mut formatted = String::with_capacity(template.len());
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            formatted.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+            let placeable = &rest[start..start + end + 1];
+            let name = placeable[1..placeable.len() - 1].trim();
+            match name
+                .strip_prefix('$')
+                .and_then(|name| args.iter().find(|(arg, _)| *arg == name))
+            {
+                Some((_, value)) => formatted.push_str(&value.to_string()),
+                None => formatted.push_str(placeable),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        formatted.push_str(rest);
+        Some(formatted)
+    }
+}
+
+/// Tells whether `id` is a valid message id: a letter followed by letters, digits, `-` or `_`.
+fn is_message_id(id: &str) -> bool {
+    id.starts_with(|c: char| c.is_ascii_alphabetic())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Returns the language tags of the catalogs built into the binary.
+pub fn languages() -> Vec<&'static str> {
+    BUILTIN.iter().map(|(language, _)| *language).collect()
+}
+
+/// Lists the catalogs a language setting may refer to, the most specific first.
+///
+/// POSIX locales such as `fr_CA.UTF-8@euro` are turned into language tags, and `C` and
+/// `POSIX` mean English.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::i18n::candidates;
+///
+/// assert_eq!(candidates("fr_CA.UTF-8"), ["fr-CA", "fr"]);
+/// assert_eq!(candidates("pt-BR"), ["pt-BR", "pt"]);
+/// assert_eq!(candidates("de"), ["de"]);
+/// assert_eq!(candidates("C.UTF-8"), ["en"]);
+/// ```
+pub fn candidates(setting: &str) -> Vec<String> {
+    let tag = setting
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-");
+    if tag.is_empty() || tag == "C" || tag == "POSIX" {
+        return vec![DEFAULT_LANGUAGE.to_string()];
+    }
+    let mut candidates = vec![tag.clone()];
+    if let Some((language, _)) = tag.split_once('-') {
+        candidates.push(language.to_string());
+    }
+    candidates
+}
+
+/// Returns the language asked for by the environment: the first of `LC_ALL`, `LC_MESSAGES`
+/// and `LANG` that is set, as gettext reads them.
+pub fn environment_language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Loads the catalog of a language tag, from `STEGANO_LOCALE_DIR` if it has one, or else
+/// from the ones built into the binary.
+///
+/// # Returns
+///
+/// A `Result` containing the catalog, or `None` if there is none for the tag.
+pub fn load_catalog(language: &str) -> io::Result<Option<Catalog>> {
+    if let Some(dir) = std::env::var_os(LOCALE_DIR_ENV) {
+        let path = PathBuf::from(dir).join(format!("{}.ftl", language));
+        match std::fs::read_to_string(&path) {
+            Ok(text) => return Catalog::parse(language, &text).map(Some),
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+    }
+    BUILTIN
+        .iter()
+        .find(|(builtin, _)| builtin.eq_ignore_ascii_case(language))
+        .map(|(builtin, text)| Catalog::parse(builtin, text))
+        .transpose()
+}
+
+/// Selects the language of the messages of this process, from `--lang` or the environment.
+///
+/// The most specific catalog found is used, and messages it lacks fall back to English. A
+/// language with no catalog at all selects English.
+///
+/// # Arguments
+///
+/// * `setting` - A language tag or POSIX locale, such as `fr` or `fr_FR.UTF-8`.
+///
+/// # Returns
+///
+/// A `Result` containing the tag of the catalog selected, or an error if it can't be read.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::i18n::{message, set_language};
+///
+/// assert_eq!(set_language("fr_FR.UTF-8").unwrap(), "fr");
+/// assert_eq!(message("dry-run-nothing-written", &[]), "rien n'a été écrit.");
+/// assert_eq!(set_language("xx").unwrap(), "en");
+/// assert_eq!(message("dry-run-nothing-written", &[]), "nothing was written.");
+/// ```
+pub fn set_language(setting: &str) -> io::Result<String> {
+    let mut selected = None;
+    for candidate in candidates(setting) {
+        if let Some(catalog) = load_catalog(&candidate)? {
+            selected = Some(catalog);
+            break;
+        }
+    }
+    let language = selected
+        .as_ref()
+        .map_or(DEFAULT_LANGUAGE.to_string(), |catalog| {
+            catalog.language.clone()
+        });
+    *ACTIVE.write().unwrap_or_else(PoisonError::into_inner) = selected.map(Arc::new);
+    Ok(language)
+}
+
+/// Returns the English catalog, loading it on first use.
+fn english() -> Arc<Catalog> {
+    if let Some(catalog) = ENGLISH
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+    {
+        return catalog.clone();
+    }
+    let catalog = Arc::new(
+        load_catalog(DEFAULT_LANGUAGE)
+            .ok()
+            .flatten()
+            .unwrap_or_default(),
+    );
+    *ENGLISH.write().unwrap_or_else(PoisonError::into_inner) = Some(catalog.clone());
+    catalog
+}
+
+/// Formats a message in the selected language, falling back to English, then to the id
+/// itself.
+///
+/// The `tr!` macro calls it with named arguments.
+pub fn message(id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let active = ACTIVE
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    active
+        .and_then(|catalog| catalog.format(id, args))
+        .or_else(|| english().format(id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Formats a message of the catalog in the selected language.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::tr;
+///
+/// assert_eq!(
+///     tr!("split-carriers", carriers = 3),
+///     "Your payload has been encrypted and split across 3 carriers successfully!"
+/// );
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($id:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::message(
+            $id,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    };
+}
@@ -0,0 +1,515 @@
+use crate::chunk_type::ChunkType;
+use crate::disguise::{icc_profile, ICC_PROFILE_NAME};
+use crate::jpeg::markers::MarkerInventory;
+use crate::png::{insert_chunk, read_chunks, remove_chunks};
+use crate::shard::list_numbers;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{self, ErrorKind, Read, Write};
+
+/// The identifier opening each APP2 segment of a JPEG profile, before its sequence number and
+/// segment count.
+const JPEG_ICC_ID: &[u8] = b"ICC_PROFILE\0";
+
+/// Most profile bytes one APP2 segment holds: 65535, less the length field and the 14 bytes
+/// of identifier, sequence number and count.
+pub const MAX_SEGMENT_DATA: usize = 65519;
+
+/// The tone curve of the profile given to carriers that have none: a single entry, gamma 2.2
+/// as an 8.8 fixed-point number, which renders like sRGB.
+const NEUTRAL_CURVE: [u8; 2] = [0x02, 0x33];
+
+/// Builds an `InvalidInput` error for carrier types without ICC profiles.
+fn unsupported(file_type: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "ICC profiles are only handled in PNG and JPEG files, not {:?}",
+            file_type
+        ),
+    )
+}
+
+/// Builds the `NotFound` error of files without a profile.
+fn no_profile() -> io::Error {
+    io::Error::new(ErrorKind::NotFound, "No ICC profile found")
+}
+
+/// Reads the ICC profile embedded in a PNG or JPEG file.
+///
+/// A PNG profile is decompressed from its `iCCP` chunk. A JPEG profile is reassembled from its
+/// APP2 segments by their sequence numbers, since a profile longer than 65519 bytes is split
+/// over several of them.
+///
+/// # Arguments
+///
+/// * `data` - The bytes of the image.
+/// * `file_type` - `png`, `jpeg` or `jpg`.
+///
+/// # Returns
+///
+/// A `Result` containing the profile as it was embedded, a `NotFound` error if there is none,
+/// or an `InvalidData` error naming the missing segments of a JPEG profile.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::icc::{extract_icc, inject_icc};
+/// use stegano::png::encode_grayscale;
+///
+/// let mut profile = vec![0; 132];
+/// profile[..4].copy_from_slice(&132u32.to_be_bytes());
+/// profile[36..40].copy_from_slice(b"acsp");
+///
+/// let png = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// assert!(extract_icc(&png, "png").is_err());
+/// let tagged = inject_icc(&png, &profile, "png").unwrap();
+/// assert_eq!(extract_icc(&tagged, "png").unwrap(), profile);
+///
+/// let jpeg = [0xFF, 0xD8, 0xFF, 0xD9];
+/// let tagged = inject_icc(&jpeg, &profile, "jpeg").unwrap();
+/// assert_eq!(extract_icc(&tagged, "jpeg").unwrap(), profile);
+/// ```
+pub fn extract_icc(data: &[u8], file_type: &str) -> io::Result<Vec<u8>> {
+    match file_type.to_lowercase().as_str() {
+        "png" => extract_icc_png(data),
+        "jpeg" | "jpg" => extract_icc_jpeg(data),
+        _ => Err(unsupported(file_type)),
+    }
+}
+
+/// Embeds an ICC profile into a PNG or JPEG file, replacing the one it has.
+///
+/// In a PNG, the `iCCP` chunk goes right after `IHDR`, keeping the profile name of the old
+/// one, and any `sRGB` chunk is dropped, since the two can't be used together. In a JPEG, the
+/// APP2 segments go where the old ones were, or after the leading JFIF and Exif segments, each
+/// holding at most 65519 bytes of the profile.
+///
+/// # Arguments
+///
+/// * `data` - The bytes of the image.
+/// * `profile` - The ICC profile, which must at least have the `acsp` signature of its header.
+/// * `file_type` - `png`, `jpeg` or `jpg`.
+///
+/// # Returns
+///
+/// A `Result` containing the new image, or an `InvalidInput` error if `profile` isn't an ICC
+/// profile or is too long for 255 APP2 segments.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::icc::{extract_icc, inject_icc, MAX_SEGMENT_DATA};
+/// use stegano::jpeg::markers::MarkerInventory;
+///
+/// let mut profile = vec![7; MAX_SEGMENT_DATA + 100];
+/// profile[..4].copy_from_slice(&(MAX_SEGMENT_DATA as u32 + 100).to_be_bytes());
+/// profile[36..40].copy_from_slice(b"acsp");
+///
+/// let jpeg = [0xFF, 0xD8, 0xFF, 0xD9];
+/// let tagged = inject_icc(&jpeg, &profile, "jpeg").unwrap();
+/// let classes: Vec<String> = MarkerInventory::parse(&tagged)
+///     .unwrap()
+///     .segments
+///     .into_iter()
+///     .map(|segment| segment.class)
+///     .collect();
+/// assert_eq!(classes, ["SOI", "APP2/ICC 1 of 2", "APP2/ICC 2 of 2", "EOI"]);
+/// assert_eq!(extract_icc(&tagged, "jpeg").unwrap(), profile);
+///
+/// assert!(inject_icc(&jpeg, b"not a profile", "jpeg").is_err());
+/// ```
+pub fn inject_icc(data: &[u8], profile: &[u8], file_type: &str) -> io::Result<Vec<u8>> {
+    if profile.get(36..40) != Some(b"acsp") {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "Not an ICC profile: the acsp signature is missing from its header",
+        ));
+    }
+    replace_icc(data, Some(profile), file_type)
+}
+
+/// Replaces the profile of an image, or removes it when `profile` is `None`.
+fn replace_icc(data: &[u8], profile: Option<&[u8]>, file_type: &str) -> io::Result<Vec<u8>> {
+    match file_type.to_lowercase().as_str() {
+        "png" => replace_icc_png(data, profile),
+        "jpeg" | "jpg" => replace_icc_jpeg(data, profile),
+        _ => Err(unsupported(file_type)),
+    }
+}
+
+/// Decompresses the profile of the first `iCCP` chunk.
+fn extract_icc_png(data: &[u8]) -> io::Result<Vec<u8>> {
+    let chunks = read_chunks(data)?;
+    let chunk = chunks
+        .iter()
+        .find(|chunk| &chunk.r#type == b"iCCP")
+        .ok_or_else(no_profile)?;
+    let invalid = |msg: &str| io::Error::new(ErrorKind::InvalidData, msg.to_string());
+    let name_end = chunk
+        .data
+        .iter()
+        .position(|&byte| byte == 0)
+        .ok_or_else(|| invalid("The iCCP chunk has no profile name"))?;
+    if chunk.data.get(name_end + 1) != Some(&0) {
+        return Err(invalid("The iCCP chunk uses an unknown compression method"));
+    }
+    let mut profile = Vec::new();
+    ZlibDecoder::new(&chunk.data[name_end + 2..]).read_to_end(&mut profile)?;
+    Ok(profile)
+}
+
+/// Swaps the `iCCP` chunk of a PNG file, dropping `sRGB`.
+fn replace_icc_png(data: &[u8], profile: Option<&[u8]>) -> io::Result<Vec<u8>> {
+    let chunks = read_chunks(data)?;
+    let name = chunks
+        .iter()
+        .find(|chunk| &chunk.r#type == b"iCCP")
+        .and_then(|chunk| chunk.data.split(|&byte| byte == 0).next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or(ICC_PROFILE_NAME)
+        .to_vec();
+    let (stripped, _) = remove_chunks(data, |_, chunk| {
+        matches!(chunk.r#type, ChunkType::Iccp | ChunkType::Srgb)
+    })?;
+    let Some(profile) = profile else {
+        return Ok(stripped);
+    };
+    let mut chunk_data = name;
+    // The name is followed by its terminator and compression method 0, zlib.
+    chunk_data.extend_from_slice(&[0, 0]);
+    let mut encoder = ZlibEncoder::new(chunk_data, Compression::default());
+    encoder.write_all(profile)?;
+    let after_ihdr = read_chunks(&stripped)?
+        .get(1)
+        .map(|chunk| chunk.offset)
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Missing IEND chunk"))?;
+    Ok(insert_chunk(&stripped, b"iCCP", &encoder.finish()?, Some(after_ihdr))?.0)
+}
+
+/// The APP2 segments of a JPEG profile, as the byte range of each segment with its sequence
+/// number, segment count and profile bytes.
+type IccSegments<'a> = Vec<(usize, usize, u8, u8, &'a [u8])>;
+
+/// Lists the APP2 segments holding a profile, in the headers before the first scan.
+fn icc_segments(data: &[u8]) -> io::Result<IccSegments<'_>> {
+    let inventory = MarkerInventory::parse(data)?;
+    let mut segments = Vec::new();
+    for segment in inventory
+        .segments
+        .iter()
+        .take_while(|segment| segment.marker != 0xDA)
+    {
+        let end = segment.offset + 2 + segment.length;
+        let body = &data[(segment.offset + 4).min(end)..end];
+        if segment.marker != 0xE2 || !body.starts_with(JPEG_ICC_ID) {
+            continue;
+        }
+        let (Some(&seq), Some(&count)) = (body.get(12), body.get(13)) else {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("The ICC segment at offset {} is truncated", segment.offset),
+            ));
+        };
+        segments.push((segment.offset, end, seq, count, &body[14..]));
+    }
+    Ok(segments)
+}
+
+/// Reassembles the profile of a JPEG file from its APP2 segments.
+fn extract_icc_jpeg(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut segments = icc_segments(data)?;
+    let Some(&(_, _, _, count, _)) = segments.first() else {
+        return Err(no_profile());
+    };
+    let invalid = |msg: String| io::Error::new(ErrorKind::InvalidData, msg);
+    if segments
+        .iter()
+        .any(|&(_, _, seq, total, _)| total != count || seq == 0 || seq > count)
+    {
+        return Err(invalid(
+            "The ICC segments don't agree on their count, or are numbered past it".into(),
+        ));
+    }
+    segments.sort_by_key(|&(_, _, seq, _, _)| seq);
+    segments.dedup_by_key(|&mut (_, _, seq, _, _)| seq);
+    let missing: Vec<u16> = (0..count as u16)
+        .filter(|&index| {
+            segments
+                .binary_search_by_key(&(index as u8 + 1), |&(_, _, seq, _, _)| seq)
+                .is_err()
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(invalid(format!(
+            "Missing ICC segments {} out of {}",
+            list_numbers(&missing),
+            count
+        )));
+    }
+    Ok(segments
+        .iter()
+        .flat_map(|&(_, _, _, _, bytes)| bytes.iter().copied())
+        .collect())
+}
+
+/// Swaps the APP2 profile segments of a JPEG file.
+fn replace_icc_jpeg(data: &[u8], profile: Option<&[u8]>) -> io::Result<Vec<u8>> {
+    let segments = icc_segments(data)?;
+    let mut out = Vec::with_capacity(data.len() + profile.map_or(0, <[u8]>::len) + 64);
+    // The new segments go where the first old one was, so nothing before it moves.
+    let at = match segments.first() {
+        Some(&(offset, ..)) => offset,
+        None => {
+            let inventory = MarkerInventory::parse(data)?;
+            inventory
+                .segments
+                .iter()
+                .skip(1)
+                .find(|segment| !matches!(segment.marker, 0xE0 | 0xE1))
+                .map_or(data.len(), |segment| segment.offset)
+        }
+    };
+    let mut cursor = 0;
+    for &(start, end, ..) in &segments {
+        out.extend_from_slice(&data[cursor..start]);
+        cursor = end;
+    }
+    out.extend_from_slice(&data[cursor..]);
+    let Some(profile) = profile else {
+        return Ok(out);
+    };
+    let pieces: Vec<&[u8]> = profile.chunks(MAX_SEGMENT_DATA).collect();
+    if pieces.len() > 255 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The profile is {} bytes long, more than 255 APP2 segments hold",
+                profile.len()
+            ),
+        ));
+    }
+    let mut inserted = Vec::with_capacity(profile.len() + pieces.len() * 18);
+    for (index, piece) in pieces.iter().enumerate() {
+        inserted.extend_from_slice(&[0xFF, 0xE2]);
+        inserted.extend_from_slice(&((2 + 14 + piece.len()) as u16).to_be_bytes());
+        inserted.extend_from_slice(JPEG_ICC_ID);
+        inserted.extend_from_slice(&[index as u8 + 1, pieces.len() as u8]);
+        inserted.extend_from_slice(piece);
+    }
+    out.splice(at..at, inserted);
+    Ok(out)
+}
+
+/// Returns where the tag elements of a profile end, after checking its header.
+fn tags_end(profile: &[u8]) -> io::Result<usize> {
+    let invalid = |msg: String| io::Error::new(ErrorKind::InvalidData, msg);
+    let u32_at = |at: usize| -> Option<usize> {
+        Some(u32::from_be_bytes(profile.get(at..at + 4)?.try_into().ok()?) as usize)
+    };
+    if profile.get(36..40) != Some(b"acsp") {
+        return Err(invalid(
+            "Not an ICC profile: the acsp signature is missing from its header".into(),
+        ));
+    }
+    if u32_at(0) != Some(profile.len()) {
+        return Err(invalid(format!(
+            "The ICC profile declares {} bytes but holds {}",
+            u32_at(0).unwrap_or_default(),
+            profile.len()
+        )));
+    }
+    let count = u32_at(128).ok_or_else(|| invalid("The ICC profile has no tag table".into()))?;
+    let mut end = 132;
+    for index in 0..count {
+        let entry = 132 + index * 12;
+        let (Some(offset), Some(size)) = (u32_at(entry + 4), u32_at(entry + 8)) else {
+            return Err(invalid("The ICC tag table is truncated".into()));
+        };
+        end = end.max(entry + 12).max(offset.saturating_add(size));
+    }
+    if end > profile.len() {
+        return Err(invalid(
+            "An ICC tag element ends past the end of the profile".into(),
+        ));
+    }
+    Ok(end)
+}
+
+/// Appends a payload to an ICC profile, after its tag elements.
+///
+/// The payload is followed by its length as a 32-bit big-endian integer, and the size in the
+/// profile header is updated to cover both. Colour management only reads the elements the tag
+/// table points to, so the profile renders as before. A profile ID (the MD5 in bytes 84 to
+/// 99), when set, no longer matches, and is kept as it was so that the profile comes back
+/// byte for byte.
+///
+/// # Arguments
+///
+/// * `profile` - The ICC profile.
+/// * `payload` - The bytes to hide.
+///
+/// # Returns
+///
+/// A `Result` containing the wrapped profile, or an `InvalidData` error if `profile` isn't a
+/// well-formed ICC profile.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::icc::{unwrap_payload, wrap_payload};
+///
+/// let mut profile = vec![0; 132];
+/// profile[..4].copy_from_slice(&132u32.to_be_bytes());
+/// profile[36..40].copy_from_slice(b"acsp");
+///
+/// let wrapped = wrap_payload(&profile, b"secret").unwrap();
+/// assert_eq!(wrapped.len(), 132 + 6 + 4);
+/// assert_eq!(&wrapped[..4], &142u32.to_be_bytes());
+/// assert_eq!(unwrap_payload(&wrapped).unwrap(), (b"secret".to_vec(), profile.clone()));
+/// assert!(unwrap_payload(&profile).is_err());
+/// ```
+pub fn wrap_payload(profile: &[u8], payload: &[u8]) -> io::Result<Vec<u8>> {
+    tags_end(profile)?;
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|len| profile.len() as u64 + *len as u64 + 4 <= u32::MAX as u64)
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                "The payload is too large for a profile",
+            )
+        })?;
+    let mut wrapped = Vec::with_capacity(profile.len() + payload.len() + 4);
+    wrapped.extend_from_slice(profile);
+    wrapped.extend_from_slice(payload);
+    wrapped.extend_from_slice(&len.to_be_bytes());
+    let size = (wrapped.len() as u32).to_be_bytes();
+    wrapped[..4].copy_from_slice(&size);
+    Ok(wrapped)
+}
+
+/// Takes back the payload appended by `wrap_payload`.
+///
+/// # Arguments
+///
+/// * `profile` - The wrapped ICC profile.
+///
+/// # Returns
+///
+/// A `Result` containing the payload and the profile as it was before, a `NotFound` error if
+/// nothing follows the tag elements, or an `InvalidData` error if the payload length doesn't
+/// fit.
+pub fn unwrap_payload(profile: &[u8]) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let end = tags_end(profile)?;
+    // Profiles may pad their last element to 4 bytes, which is too short for a length.
+    if profile.len() < end + 4 {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            "Nothing follows the tag elements of the ICC profile",
+        ));
+    }
+    let len_at = profile.len() - 4;
+    let len = u32::from_be_bytes(profile[len_at..].try_into().unwrap()) as usize;
+    let start = len_at
+        .checked_sub(len)
+        .filter(|&start| start >= end)
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "The {} bytes after the ICC tag elements don't hold a payload",
+                    profile.len() - end
+                ),
+            )
+        })?;
+    let mut original = profile[..start].to_vec();
+    original[..4].copy_from_slice(&(start as u32).to_be_bytes());
+    Ok((profile[start..len_at].to_vec(), original))
+}
+
+/// Hides a payload after the tag elements of the ICC profile of a PNG or JPEG file.
+///
+/// A carrier without a profile is given a small display profile with sRGB primaries and a
+/// gamma of 2.2 first, which extraction removes again.
+///
+/// # Arguments
+///
+/// * `carrier` - The bytes of the image.
+/// * `payload` - The bytes to hide.
+/// * `file_type` - `png`, `jpeg` or `jpg`.
+///
+/// # Returns
+///
+/// A `Result` containing the stego file.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::icc::{embed_in_icc, extract_from_icc, extract_icc};
+/// use stegano::png::encode_grayscale;
+///
+/// let png = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let stego = embed_in_icc(&png, b"secret", "png").unwrap();
+/// assert_eq!(&extract_icc(&stego, "png").unwrap()[36..40], b"acsp");
+/// let (payload, cleaned) = extract_from_icc(&stego, "png").unwrap();
+/// assert_eq!(payload, b"secret");
+/// assert_eq!(cleaned, png);
+///
+/// let jpeg = [0xFF, 0xD8, 0xFF, 0xD9];
+/// let stego = embed_in_icc(&jpeg, b"secret", "jpeg").unwrap();
+/// assert_eq!(extract_from_icc(&stego, "jpeg").unwrap(), (b"secret".to_vec(), jpeg.to_vec()));
+/// ```
+pub fn embed_in_icc(carrier: &[u8], payload: &[u8], file_type: &str) -> io::Result<Vec<u8>> {
+    let profile = match extract_icc(carrier, file_type) {
+        Ok(profile) => profile,
+        Err(err) if err.kind() == ErrorKind::NotFound => icc_profile(&NEUTRAL_CURVE),
+        Err(err) => return Err(err),
+    };
+    replace_icc(carrier, Some(&wrap_payload(&profile, payload)?), file_type)
+}
+
+/// Takes back a payload hidden by `embed_in_icc`.
+///
+/// # Arguments
+///
+/// * `stego` - The bytes of the stego file.
+/// * `file_type` - `png`, `jpeg` or `jpg`.
+///
+/// # Returns
+///
+/// A `Result` containing the payload and the image with its original profile, or none if the
+/// carrier had none. A PNG profile is compressed again, which may not give the bytes of the
+/// original `iCCP` chunk.
+pub fn extract_from_icc(stego: &[u8], file_type: &str) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let (payload, profile) = unwrap_payload(&extract_icc(stego, file_type)?)?;
+    let restored = if profile == icc_profile(&NEUTRAL_CURVE) {
+        None
+    } else {
+        Some(&profile[..])
+    };
+    Ok((payload, replace_icc(stego, restored, file_type)?))
+}
+
+/// Returns the number of payload bytes `embed_in_icc` can hide in a JPEG file, whose
+/// profile, payload and length together must fit in 255 APP2 segments.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::icc::{embed_in_icc, jpeg_icc_capacity};
+///
+/// let jpeg = [0xFF, 0xD8, 0xFF, 0xD9];
+/// let capacity = jpeg_icc_capacity(&jpeg).unwrap();
+/// assert!(embed_in_icc(&jpeg, &vec![0; capacity], "jpeg").is_ok());
+/// assert!(embed_in_icc(&jpeg, &vec![0; capacity + 1], "jpeg").is_err());
+/// ```
+pub fn jpeg_icc_capacity(carrier: &[u8]) -> io::Result<usize> {
+    let profile_len = match extract_icc(carrier, "jpeg") {
+        Ok(profile) => profile.len(),
+        Err(err) if err.kind() == ErrorKind::NotFound => icc_profile(&NEUTRAL_CURVE).len(),
+        Err(err) => return Err(err),
+    };
+    Ok((255 * MAX_SEGMENT_DATA).saturating_sub(profile_len + 4))
+}
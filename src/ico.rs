@@ -0,0 +1,305 @@
+use crate::outln;
+use crate::storage::read_input;
+use crate::style::Style;
+use crate::utils::print_hex;
+use std::error::Error;
+use std::io::{self, ErrorKind, Read, Write};
+
+// ANSI escape codes for text color
+/// Magic marker written in front of a payload stored in the slack space of an ICO file.
+pub const SLACK_MAGIC: &[u8; 4] = b"STGI";
+
+/// The PNG signature, used to tell PNG entries apart from BMP ones.
+const PNG_SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Represents a 16-byte entry of the icon directory.
+#[derive(Debug, Clone)]
+pub struct IconDirEntry {
+    /// Width of the image in pixels, 0 meaning 256.
+    pub width: u8,
+    /// Height of the image in pixels, 0 meaning 256.
+    pub height: u8,
+    /// Number of colors in the palette, 0 if no palette is used.
+    pub colors: u8,
+    /// Color planes for icons, hotspot X coordinate for cursors.
+    pub planes: u16,
+    /// Bits per pixel for icons, hotspot Y coordinate for cursors.
+    pub bpp: u16,
+    /// Size of the image data in bytes.
+    pub size: u32,
+    /// Offset of the image data in the file.
+    pub offset: u32,
+}
+
+impl IconDirEntry {
+    /// Returns the end offset of the image data.
+    pub fn end(&self) -> usize {
+        self.offset as usize + self.size as usize
+    }
+}
+
+/// Represents a parsed ICO or CUR file together with its raw bytes.
+#[derive(Debug, Clone)]
+pub struct IcoFile {
+    /// Resource type: 1 for icons, 2 for cursors.
+    pub kind: u16,
+    /// Entries of the icon directory.
+    pub entries: Vec<IconDirEntry>,
+    /// Raw bytes of the file.
+    pub data: Vec<u8>,
+}
+
+impl IcoFile {
+    /// Parses an ICO file from its raw bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The raw bytes of the ICO file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed `IcoFile`, or an `io::Error` if the data is not a valid ICO.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::ico::IcoFile;
+    ///
+    /// // An icon directory with a single 16x16 entry pointing at 4 bytes of image data.
+    /// let mut data: Vec<u8> = vec![
+    ///     0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x10, 0x10, 0x00, 0x00, 0x01, 0x00, 0x20, 0x00,
+    ///     0x04, 0x00, 0x00, 0x00, 0x16, 0x00, 0x00, 0x00,
+    /// ];
+    /// data.extend_from_slice(&[1, 2, 3, 4]);
+    /// let ico = IcoFile::parse(data).unwrap();
+    /// assert_eq!(ico.entries.len(), 1);
+    /// assert_eq!(ico.entries[0].width, 16);
+    /// ```
+    pub fn parse(data: Vec<u8>) -> io::Result<IcoFile> {
+        let invalid = || io::Error::new(ErrorKind::InvalidData, "Not a valid ICO file!");
+        if data.len() < 6 || data[0..2] != [0, 0] {
+            return Err(invalid());
+        }
+        let kind = u16::from_le_bytes([data[2], data[3]]);
+        if kind != 1 && kind != 2 {
+            return Err(invalid());
+        }
+        let count = u16::from_le_bytes([data[4], data[5]]) as usize;
+        if data.len() < 6 + count * 16 {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Truncated ICO directory",
+            ));
+        }
+
+        let mut entries = Vec::with_capacity(count);
+        for i in 0..count {
+            let e = &data[6 + i * 16..6 + (i + 1) * 16];
+            let entry = IconDirEntry {
+                width: e[0],
+                height: e[1],
+                colors: e[2],
+                planes: u16::from_le_bytes([e[4], e[5]]),
+                bpp: u16::from_le_bytes([e[6], e[7]]),
+                size: u32::from_le_bytes([e[8], e[9], e[10], e[11]]),
+                offset: u32::from_le_bytes([e[12], e[13], e[14], e[15]]),
+            };
+            if entry.end() > data.len() {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "ICO image data out of bounds",
+                ));
+            }
+            entries.push(entry);
+        }
+
+        Ok(IcoFile {
+            kind,
+            entries,
+            data,
+        })
+    }
+
+    /// Returns the format of the image wrapped by an entry, either "PNG" or "BMP".
+    pub fn entry_format(&self, entry: &IconDirEntry) -> &'static str {
+        if self.data[entry.offset as usize..entry.end()].starts_with(&PNG_SIGNATURE) {
+            "PNG"
+        } else {
+            "BMP"
+        }
+    }
+
+    /// Returns the offset right after the last image, where the slack space begins.
+    pub fn slack_offset(&self) -> usize {
+        self.entries
+            .iter()
+            .map(IconDirEntry::end)
+            .max()
+            .unwrap_or(6 + self.entries.len() * 16)
+    }
+
+    /// Hides a payload in the slack space after the last image entry.
+    ///
+    /// The payload is prefixed with `SLACK_MAGIC` and its little-endian length. Icon readers
+    /// only follow the directory offsets, so the extra bytes are never looked at.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The bytes to hide.
+    ///
+    /// # Returns
+    ///
+    /// The bytes of the new ICO file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::ico::IcoFile;
+    ///
+    /// let mut data: Vec<u8> = vec![
+    ///     0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x10, 0x10, 0x00, 0x00, 0x01, 0x00, 0x20, 0x00,
+    ///     0x04, 0x00, 0x00, 0x00, 0x16, 0x00, 0x00, 0x00,
+    /// ];
+    /// data.extend_from_slice(&[1, 2, 3, 4]);
+    /// let ico = IcoFile::parse(data.clone()).unwrap();
+    /// let stego = IcoFile::parse(ico.embed_in_slack(b"secret")).unwrap();
+    /// assert_eq!(stego.extract_payload().unwrap(), b"secret");
+    /// assert_eq!(stego.remove_payload().unwrap(), data);
+    /// ```
+    pub fn embed_in_slack(&self, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.data.len() + payload.len() + 8);
+        self.write_slack_embedded(payload, payload.len() as u64, &mut out)
+            .expect("Writing to a Vec never fails");
+        out
+    }
+
+    /// Streams the file with a payload in its slack space to `writer`.
+    ///
+    /// This is the streaming counterpart of `embed_in_slack`, used when the payload is too big
+    /// to be held in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The source of the bytes to hide.
+    /// * `len` - The number of bytes to read from `payload`.
+    /// * `writer` - The destination of the new ICO file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the offset of the payload in the new file.
+    pub fn write_slack_embedded<R: Read, W: Write>(
+        &self,
+        payload: R,
+        len: u64,
+        mut writer: W,
+    ) -> io::Result<u64> {
+        let len32 = u32::try_from(len)
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "Payload too large for ICO"))?;
+        let slack = self.slack_offset();
+        writer.write_all(&self.data[..slack])?;
+        writer.write_all(SLACK_MAGIC)?;
+        writer.write_all(&len32.to_le_bytes())?;
+        let copied = io::copy(&mut payload.take(len), &mut writer)?;
+        if copied != len {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Payload shorter than announced",
+            ));
+        }
+        writer.write_all(&self.data[slack..])?;
+        // The slack frame opens with its magic and length.
+        Ok(slack as u64 + 8)
+    }
+
+    /// Returns the range of the payload frame (magic, length and payload) in the slack space.
+    fn payload_frame(&self) -> Option<(usize, usize)> {
+        let slack = self.slack_offset();
+        let header = self.data.get(slack..slack + 8)?;
+        if &header[..4] != SLACK_MAGIC {
+            return None;
+        }
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        (slack + 8 + len <= self.data.len()).then_some((slack, slack + 8 + len))
+    }
+
+    /// Extracts a payload hidden in the slack space.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the payload bytes, or an `io::Error` if no payload was found.
+    pub fn extract_payload(&self) -> io::Result<Vec<u8>> {
+        let (start, end) = self.payload_frame().ok_or_else(|| {
+            io::Error::new(ErrorKind::NotFound, "No payload found in the ICO file")
+        })?;
+        Ok(self.data[start + 8..end].to_vec())
+    }
+
+    /// Removes a payload hidden in the slack space, restoring the original file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the cleaned file, or an `io::Error` if no payload was found.
+    pub fn remove_payload(&self) -> io::Result<Vec<u8>> {
+        let (start, end) = self.payload_frame().ok_or_else(|| {
+            io::Error::new(ErrorKind::NotFound, "No payload found in the ICO file")
+        })?;
+        Ok([&self.data[..start], &self.data[end..]].concat())
+    }
+}
+
+/// Reads an ICO file and prints its icon directory.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice representing the path to the ICO file.
+/// * `nb_entries` - The maximum number of directory entries to print.
+/// * `suppress` - A boolean to suppress print statements.
+///
+/// # Returns
+///
+/// A `Result` containing the parsed `IcoFile` or an error if the file could not be read or parsed.
+pub fn read_ico_headers(
+    file_path: &str,
+    nb_entries: usize,
+    suppress: bool,
+) -> Result<IcoFile, Box<dyn Error>> {
+    let data = read_input(file_path)?;
+    let ico = IcoFile::parse(data)?;
+    if suppress {
+        return Ok(ico);
+    }
+
+    outln!("It is a valid ICO file. Let's process it! \n");
+    outln!("{}", Style::Green.paint("---- Header ----"));
+    print_hex(&ico.data[..6], 0);
+    outln!("Type: {}", if ico.kind == 1 { "Icon" } else { "Cursor" });
+    outln!("Images: {}", ico.entries.len());
+    outln!("{}", Style::Green.paint("----- End ------"));
+    outln!();
+
+    for (i, entry) in ico.entries.iter().take(nb_entries).enumerate() {
+        let dimension = |v: u8| if v == 0 { 256 } else { v as u16 };
+        outln!("{}", Style::Green.paint(format!("---- Image #{} ----", i)));
+        outln!(
+            "{}",
+            Style::Yellow.paint(format!("Format: {}", ico.entry_format(entry)))
+        );
+        outln!(
+            "Dimensions: {}x{}",
+            dimension(entry.width),
+            dimension(entry.height)
+        );
+        outln!("Colors: {}", entry.colors);
+        outln!("Bits per pixel: {}", entry.bpp);
+        outln!("Offset: {}", entry.offset);
+        outln!("Size: {}", entry.size);
+        outln!("{}", Style::Green.paint("------ End ------"));
+        outln!();
+    }
+
+    let slack = ico.data.len() - ico.slack_offset();
+    if slack > 0 {
+        outln!("Slack space after the last image: {} bytes", slack);
+    }
+    Ok(ico)
+}
@@ -0,0 +1,175 @@
+use crate::png::{read_chunks, remove_chunks, validate_chunk_name, write_chunk};
+use crate::prng::Prng;
+use sha2::{Digest, Sha256};
+use std::io::{self, ErrorKind};
+
+/// Number of bytes in front of each fragment: its sequence number and the fragment count, as
+/// 16-bit big-endian integers.
+pub const FRAGMENT_HEADER_LEN: usize = 4;
+
+/// Most fragments a payload is split into.
+const MAX_FRAGMENTS: u64 = 8;
+
+/// Seeds the generator placing the fragments, from the key.
+fn placement_rng(key: &str) -> Prng {
+    let digest = Sha256::digest(format!("interleave:{}", key).as_bytes());
+    Prng::new(u64::from_le_bytes(digest[..8].try_into().unwrap()))
+}
+
+/// Splits a payload into chunks of one type scattered between the chunks of a PNG file.
+///
+/// The payload is cut into 2 to 8 fragments, each stored in its own chunk with its sequence
+/// number and the fragment count. Both the count and the place of each fragment are drawn from
+/// the key: any chunk boundary between `IHDR` and `IEND` will do, except between two `IDAT`
+/// chunks, which must stay together. Scanners looking for one unknown chunk before `IEND`
+/// find nothing, and since fragments are put back in order by their sequence numbers, tools
+/// that reorder ancillary chunks don't break the payload.
+///
+/// # Arguments
+///
+/// * `carrier` - The bytes of the PNG file.
+/// * `payload` - The bytes to hide.
+/// * `chunk_name` - The type of the fragment chunks, which the carrier must not hold already.
+/// * `key` - The key the placement is drawn from.
+///
+/// # Returns
+///
+/// A `Result` containing the stego file.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::interleave::{embed_interleaved, extract_interleaved};
+/// use stegano::png::{encode_grayscale, read_chunks};
+///
+/// let carrier = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let stego = embed_interleaved(&carrier, b"a secret in several parts", "stEg", "key").unwrap();
+/// let fragments = read_chunks(&stego).unwrap().iter().filter(|c| &c.r#type == b"stEg").count();
+/// assert!(fragments >= 2);
+/// let (payload, restored) = extract_interleaved(&stego, "stEg").unwrap();
+/// assert_eq!(payload, b"a secret in several parts");
+/// assert_eq!(restored, carrier);
+/// ```
+pub fn embed_interleaved(
+    carrier: &[u8],
+    payload: &[u8],
+    chunk_name: &str,
+    key: &str,
+) -> io::Result<Vec<u8>> {
+    let chunks = read_chunks(carrier)?;
+    let name = validate_chunk_name(chunk_name, &chunks)?;
+    if chunks.len() < 2 || &chunks[chunks.len() - 1].r#type != b"IEND" {
+        return Err(io::Error::new(ErrorKind::InvalidData, "Missing IEND chunk"));
+    }
+    // Boundaries are numbered by the chunk they come before.
+    let boundaries: Vec<usize> = (1..chunks.len())
+        .filter(|&i| !(&chunks[i - 1].r#type == b"IDAT" && &chunks[i].r#type == b"IDAT"))
+        .collect();
+    let mut rng = placement_rng(key);
+    let count = (2 + rng.below(MAX_FRAGMENTS - 1)).min(payload.len().max(1) as u64) as usize;
+    let size = payload.len().div_ceil(count).max(1);
+    let mut fragments: Vec<(usize, Vec<u8>)> = (0..count)
+        .map(|seq| {
+            let mut data = Vec::with_capacity(FRAGMENT_HEADER_LEN + size);
+            data.extend_from_slice(&(seq as u16).to_be_bytes());
+            data.extend_from_slice(&(count as u16).to_be_bytes());
+            let start = (seq * size).min(payload.len());
+            data.extend_from_slice(&payload[start..(start + size).min(payload.len())]);
+            let boundary = boundaries[rng.below(boundaries.len() as u64) as usize];
+            (boundary, data)
+        })
+        .collect();
+    // Fragments sharing a boundary go in drawing order, so the file order stays shuffled.
+    fragments.sort_by_key(|(boundary, _)| *boundary);
+
+    let mut out = Vec::with_capacity(carrier.len() + payload.len() + count * 16);
+    out.extend_from_slice(&carrier[..chunks[0].offset]);
+    let mut pending = fragments.iter().peekable();
+    let mut end = 0;
+    for (index, chunk) in chunks.iter().enumerate() {
+        while let Some((_, data)) = pending.next_if(|(boundary, _)| *boundary == index) {
+            write_chunk(&mut out, &name, data)?;
+        }
+        end = chunk.offset + 12 + chunk.data.len();
+        out.extend_from_slice(&carrier[chunk.offset..end]);
+    }
+    out.extend_from_slice(&carrier[end..]);
+    Ok(out)
+}
+
+/// Gathers the fragments written by `embed_interleaved`, in whatever order the chunks are.
+///
+/// # Arguments
+///
+/// * `stego` - The bytes of the stego file.
+/// * `chunk_name` - The type of the fragment chunks.
+///
+/// # Returns
+///
+/// A `Result` containing the payload and the carrier without the fragment chunks, a
+/// `NotFound` error if there are none, or an `InvalidData` error naming the missing fragments.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::interleave::{embed_interleaved, extract_interleaved};
+/// use stegano::png::{encode_grayscale, read_chunks, remove_chunk};
+///
+/// let carrier = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let stego = embed_interleaved(&carrier, b"a secret in several parts", "stEg", "key").unwrap();
+/// let (damaged, _) = remove_chunk(&stego, b"stEg").unwrap();
+/// let error = extract_interleaved(&damaged, "stEg").unwrap_err();
+/// assert!(error.to_string().starts_with("Missing fragments"));
+/// ```
+pub fn extract_interleaved(stego: &[u8], chunk_name: &str) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let name: [u8; 4] = chunk_name
+        .as_bytes()
+        .try_into()
+        .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "Chunk names are four letters"))?;
+    let (carrier, fragments) = remove_chunks(stego, |_, chunk| chunk.r#type == name)?;
+    if fragments.is_empty() {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("No {:?} fragments found", chunk_name),
+        ));
+    }
+    let invalid = |msg: String| io::Error::new(ErrorKind::InvalidData, msg);
+    let header = |data: &[u8]| -> Option<(usize, usize)> {
+        let seq = u16::from_be_bytes(data.get(0..2)?.try_into().ok()?) as usize;
+        let count = u16::from_be_bytes(data.get(2..4)?.try_into().ok()?) as usize;
+        Some((seq, count))
+    };
+    let mut parts = Vec::with_capacity(fragments.len());
+    for fragment in fragments {
+        let (seq, count) = header(&fragment.data)
+            .ok_or_else(|| invalid(format!("A {:?} fragment is truncated", chunk_name)))?;
+        parts.push((seq, count, fragment.data));
+    }
+    let count = parts[0].1;
+    if parts
+        .iter()
+        .any(|(seq, total, _)| *total != count || *seq >= count)
+    {
+        return Err(invalid(
+            "The fragments don't agree on their count, they come from different payloads".into(),
+        ));
+    }
+    parts.sort_by_key(|(seq, _, _)| *seq);
+    parts.dedup_by_key(|(seq, _, _)| *seq);
+    let missing: Vec<String> = (0..count)
+        .filter(|seq| parts.binary_search_by_key(seq, |(seq, _, _)| *seq).is_err())
+        .map(|seq| (seq + 1).to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Err(invalid(format!(
+            "Missing fragments {} out of {}",
+            missing.join(", "),
+            count
+        )));
+    }
+    let payload = parts
+        .iter()
+        .flat_map(|(_, _, data)| data[FRAGMENT_HEADER_LEN..].iter().copied())
+        .collect();
+    Ok((payload, carrier))
+}
@@ -0,0 +1,116 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Reads a big-endian 16-bit unsigned integer from a reader.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::io_ext::read_u16_be;
+///
+/// let mut reader: &[u8] = &[0xFF, 0xD8];
+/// assert_eq!(read_u16_be(&mut reader).unwrap(), 0xFFD8);
+/// ```
+pub fn read_u16_be<R: Read + ?Sized>(reader: &mut R) -> io::Result<u16> {
+    let mut bytes = [0u8; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_be_bytes(bytes))
+}
+
+/// Reads a big-endian 32-bit unsigned integer from a reader.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::io_ext::read_u32_be;
+///
+/// let mut reader: &[u8] = b"IEND";
+/// assert_eq!(read_u32_be(&mut reader).unwrap(), 0x49454E44);
+/// ```
+pub fn read_u32_be<R: Read + ?Sized>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+/// Reads a big-endian 64-bit unsigned integer from a reader.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::io_ext::read_u64_be;
+///
+/// let mut reader: &[u8] = &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+/// assert_eq!(read_u64_be(&mut reader).unwrap(), 0x8950_4E47_0D0A_1A0A);
+/// ```
+pub fn read_u64_be<R: Read + ?Sized>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+/// Returns the total length of a seekable stream, leaving the cursor where it was.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{Cursor, Seek, SeekFrom};
+/// use stegano::io_ext::stream_len;
+///
+/// let mut cursor = Cursor::new(vec![0u8; 42]);
+/// cursor.seek(SeekFrom::Start(7)).unwrap();
+/// assert_eq!(stream_len(&mut cursor).unwrap(), 42);
+/// assert_eq!(cursor.position(), 7);
+/// ```
+pub fn stream_len<S: Seek + ?Sized>(stream: &mut S) -> io::Result<u64> {
+    let position = stream.stream_position()?;
+    let len = stream.seek(SeekFrom::End(0))?;
+    stream.seek(SeekFrom::Start(position))?;
+    Ok(len)
+}
+
+/// Fills `buf` with the bytes found at `offset`, leaving the cursor where it was.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use stegano::io_ext::read_exact_at;
+///
+/// let mut cursor = Cursor::new(b"hello world".to_vec());
+/// let mut buf = [0u8; 5];
+/// read_exact_at(&mut cursor, 6, &mut buf).unwrap();
+/// assert_eq!(&buf, b"world");
+/// assert_eq!(cursor.position(), 0);
+/// ```
+pub fn read_exact_at<R: Read + Seek + ?Sized>(
+    reader: &mut R,
+    offset: u64,
+    buf: &mut [u8],
+) -> io::Result<()> {
+    let position = reader.stream_position()?;
+    reader.seek(SeekFrom::Start(offset))?;
+    let result = reader.read_exact(buf);
+    reader.seek(SeekFrom::Start(position))?;
+    result
+}
+
+/// Reads `len` bytes found at `offset` into a new vector, leaving the cursor where it was.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use stegano::io_ext::read_vec_at;
+///
+/// let mut cursor = Cursor::new(b"hello world".to_vec());
+/// assert_eq!(read_vec_at(&mut cursor, 0, 5).unwrap(), b"hello");
+/// ```
+pub fn read_vec_at<R: Read + Seek + ?Sized>(
+    reader: &mut R,
+    offset: u64,
+    len: usize,
+) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    read_exact_at(reader, offset, &mut buf)?;
+    Ok(buf)
+}
@@ -0,0 +1,428 @@
+use crate::jpeg::markers::MarkerInventory;
+use crate::style::Style;
+use std::io::{self, ErrorKind, Write};
+
+/// The Huffman tables of Annex K of the JPEG standard, as (name, is AC, code counts per
+/// length, symbols), which most encoders write unless they optimize their tables.
+const ANNEX_K_TABLES: [(&str, bool, [u8; 16], &[u8]); 4] = [
+    (
+        "Annex K luminance DC",
+        false,
+        [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0],
+        &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+    ),
+    (
+        "Annex K chrominance DC",
+        false,
+        [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0],
+        &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+    ),
+    (
+        "Annex K luminance AC",
+        true,
+        [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7D],
+        &[
+            0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51,
+            0x61, 0x07, 0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xA1, 0x08, 0x23, 0x42, 0xB1, 0xC1,
+            0x15, 0x52, 0xD1, 0xF0, 0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0A, 0x16, 0x17, 0x18,
+            0x19, 0x1A, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2A, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39,
+            0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57,
+            0x58, 0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x73, 0x74, 0x75,
+            0x76, 0x77, 0x78, 0x79, 0x7A, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x8A, 0x92,
+            0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7,
+            0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3,
+            0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8,
+            0xD9, 0xDA, 0xE1, 0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF1, 0xF2,
+            0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9, 0xFA,
+        ],
+    ),
+    (
+        "Annex K chrominance AC",
+        true,
+        [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77],
+        &[
+            0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07,
+            0x61, 0x71, 0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xA1, 0xB1, 0xC1, 0x09,
+            0x23, 0x33, 0x52, 0xF0, 0x15, 0x62, 0x72, 0xD1, 0x0A, 0x16, 0x24, 0x34, 0xE1, 0x25,
+            0xF1, 0x17, 0x18, 0x19, 0x1A, 0x26, 0x27, 0x28, 0x29, 0x2A, 0x35, 0x36, 0x37, 0x38,
+            0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x53, 0x54, 0x55, 0x56,
+            0x57, 0x58, 0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x73, 0x74,
+            0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+            0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5,
+            0xA6, 0xA7, 0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA,
+            0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6,
+            0xD7, 0xD8, 0xD9, 0xDA, 0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF2,
+            0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9, 0xFA,
+        ],
+    ),
+];
+
+/// A Huffman table read from a DHT segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HuffmanTable {
+    /// Whether the table codes AC coefficients rather than DC differences.
+    pub ac: bool,
+    /// The table slot, from 0 to 3, that scans refer to.
+    pub id: u8,
+    /// The number of codes of each length, from 1 to 16 bits.
+    pub counts: [u8; 16],
+    /// The symbols, in the order of their codes.
+    pub symbols: Vec<u8>,
+}
+
+impl HuffmanTable {
+    /// Reads every table of a DHT segment, after its length field.
+    ///
+    /// The code counts are checked against the bytes of the segment before any symbol is
+    /// read, so a table declaring more symbols than the segment holds is an error.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the tables, or an `InvalidData` error if the segment is cut short,
+    /// declares more than 256 symbols in a table or a class or slot the standard doesn't have.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::jpeg::codes::HuffmanTable;
+    ///
+    /// // A DC table in slot 1 with two codes of 2 bits.
+    /// let body = [0x01, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 5];
+    /// let tables = HuffmanTable::parse_segment(&body).unwrap();
+    /// assert_eq!((tables[0].ac, tables[0].id, &tables[0].symbols[..]), (false, 1, &[4, 5][..]));
+    /// assert!(HuffmanTable::parse_segment(&body[..18]).is_err());
+    /// ```
+    pub fn parse_segment(body: &[u8]) -> io::Result<Vec<HuffmanTable>> {
+        let invalid = |msg: String| io::Error::new(ErrorKind::InvalidData, msg);
+        let mut tables = Vec::new();
+        let mut rest = body;
+        while let Some((&info, after)) = rest.split_first() {
+            let (class, id) = (info >> 4, info & 0x0F);
+            if class > 1 || id > 3 {
+                return Err(invalid(format!(
+                    "Invalid DHT table: class {} and slot {}",
+                    class, id
+                )));
+            }
+            let counts: [u8; 16] = after
+                .get(..16)
+                .and_then(|counts| counts.try_into().ok())
+                .ok_or_else(|| invalid(format!("DHT table {} is cut short in its counts", id)))?;
+            let total: usize = counts.iter().map(|&count| usize::from(count)).sum();
+            if total > 256 {
+                return Err(invalid(format!(
+                    "DHT table {} declares {} symbols, more than the 256 there are",
+                    id, total
+                )));
+            }
+            let symbols = after.get(16..16 + total).ok_or_else(|| {
+                invalid(format!(
+                    "DHT table {} declares {} symbols but the segment holds {}",
+                    id,
+                    total,
+                    after.len() - 16
+                ))
+            })?;
+            tables.push(HuffmanTable {
+                ac: class == 1,
+                id,
+                counts,
+                symbols: symbols.to_vec(),
+            });
+            rest = &after[16 + total..];
+        }
+        Ok(tables)
+    }
+
+    /// Returns the class and slot of the table, such as "DC 0".
+    pub fn label(&self) -> String {
+        format!("{} {}", if self.ac { "AC" } else { "DC" }, self.id)
+    }
+
+    /// Tells whether the code lengths can make a prefix code, by the Kraft inequality.
+    ///
+    /// JPEG also reserves the code of all ones of each length, so the lengths must leave room
+    /// for at least one more code: the sum of `2^-length` has to stay below 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::jpeg::codes::HuffmanTable;
+    ///
+    /// let table = |counts: [u8; 16]| HuffmanTable { ac: false, id: 0, counts, symbols: vec![] };
+    /// let mut counts = [0; 16];
+    /// counts[1] = 3;
+    /// assert!(table(counts).is_prefix_code());
+    /// // Four codes of 2 bits use up every code, the reserved one included.
+    /// counts[1] = 4;
+    /// assert!(!table(counts).is_prefix_code());
+    /// ```
+    pub fn is_prefix_code(&self) -> bool {
+        let used: u32 = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(index, &count)| u32::from(count) << (15 - index))
+            .sum();
+        used < 1 << 16
+    }
+
+    /// Returns the name of the Annex K table this one is, or `None` for custom or optimized
+    /// tables.
+    pub fn standard(&self) -> Option<&'static str> {
+        ANNEX_K_TABLES
+            .iter()
+            .find(|(_, ac, counts, symbols)| {
+                *ac == self.ac && *counts == self.counts && *symbols == &self.symbols[..]
+            })
+            .map(|(name, ..)| *name)
+    }
+
+    /// Lists what is wrong with the table: codes that aren't a prefix code, no codes at all,
+    /// repeated symbols and symbols no coefficient can have.
+    ///
+    /// # Arguments
+    ///
+    /// * `progressive` - Whether the file is progressive, whose AC tables also code runs of
+    ///   empty blocks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::jpeg::codes::HuffmanTable;
+    ///
+    /// let mut counts = [0; 16];
+    /// counts[1] = 2;
+    /// let table = HuffmanTable { ac: false, id: 0, counts, symbols: vec![3, 3] };
+    /// assert_eq!(table.problems(false), ["symbol 3 is coded twice"]);
+    /// let table = HuffmanTable { ac: true, id: 0, counts, symbols: vec![0x00, 0x20] };
+    /// assert_eq!(table.problems(false), ["symbol 0x20 is no AC run and size"]);
+    /// assert!(table.problems(true).is_empty());
+    /// ```
+    pub fn problems(&self, progressive: bool) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.symbols.is_empty() {
+            problems.push("no codes".to_string());
+        }
+        if !self.is_prefix_code() {
+            problems.push(
+                "the code lengths break the Kraft inequality, so the codes can't be decoded"
+                    .to_string(),
+            );
+        }
+        let mut seen = [false; 256];
+        for &symbol in &self.symbols {
+            if std::mem::replace(&mut seen[usize::from(symbol)], true) {
+                problems.push(format!("symbol {} is coded twice", symbol));
+            }
+            // DC differences take at most 16 bits; AC symbols pair a run of zeros with a size
+            // from 1 to 15, except for the end of block (0x00) and the run of 16 zeros (0xF0),
+            // and in progressive files the runs of end of blocks (0x10 to 0xE0).
+            if !self.ac && symbol > 16 {
+                problems.push(format!("symbol {} is no DC difference size", symbol));
+            }
+            if self.ac && !progressive && symbol & 0x0F == 0 && symbol != 0x00 && symbol != 0xF0 {
+                problems.push(format!("symbol {:#04x} is no AC run and size", symbol));
+            }
+        }
+        problems
+    }
+}
+
+/// The Huffman tables of a JPEG file, with what is wrong with them and with the scans using
+/// them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HuffmanReport {
+    /// Each table with the offset of the DHT segment defining it, in file order.
+    pub tables: Vec<(usize, HuffmanTable)>,
+    /// The problems of the scans, such as a reference to a table that isn't defined.
+    pub scan_problems: Vec<String>,
+    /// Whether the file is arithmetic coded, and so needs no Huffman tables.
+    pub arithmetic: bool,
+    /// Whether the file is progressive.
+    pub progressive: bool,
+}
+
+impl HuffmanReport {
+    /// Reads the DHT segments of a JPEG file and checks each SOS against the tables defined
+    /// before it.
+    ///
+    /// A scan needs the DC table of each of its components when it codes DC coefficients for
+    /// the first time (`Ss` = 0 and `Ah` = 0), and the AC table when it codes AC ones
+    /// (`Se` > 0). Refinement scans of DC coefficients and arithmetic-coded files need none.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The whole JPEG file.
+    /// * `inventory` - Its markers, as `MarkerInventory::parse` returns them.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the report, or an `InvalidData` error if a DHT or SOS segment
+    /// is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::jpeg::codes::HuffmanReport;
+    /// use stegano::jpeg::markers::MarkerInventory;
+    ///
+    /// let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xC4, 0x00, 0x15, 0x00];
+    /// jpeg.extend_from_slice(&[0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    /// // One component, DC table 0 and AC table 1, Ss 0, Se 63, Ah/Al 0.
+    /// jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x08, 1, 1, 0x01, 0, 63, 0, 0xFF, 0xD9]);
+    ///
+    /// let report = HuffmanReport::analyze(&jpeg, &MarkerInventory::parse(&jpeg).unwrap()).unwrap();
+    /// assert_eq!(report.tables[0].1.label(), "DC 0");
+    /// assert_eq!(
+    ///     report.scan_problems,
+    ///     ["the SOS at offset 25 uses AC table 1, which isn't defined before it"]
+    /// );
+    /// ```
+    pub fn analyze(data: &[u8], inventory: &MarkerInventory) -> io::Result<HuffmanReport> {
+        let mut report = HuffmanReport {
+            progressive: inventory
+                .segments
+                .iter()
+                .any(|segment| matches!(segment.marker, 0xC2 | 0xC6 | 0xCA | 0xCE)),
+            ..HuffmanReport::default()
+        };
+        let mut defined = [[false; 4]; 2];
+        for segment in &inventory.segments {
+            let body = &data[(segment.offset + 4).min(segment.offset + 2 + segment.length)
+                ..segment.offset + 2 + segment.length];
+            match segment.marker {
+                0xC9..=0xCB | 0xCD..=0xCF => report.arithmetic = true,
+                0xC4 => {
+                    for table in HuffmanTable::parse_segment(body)? {
+                        defined[usize::from(table.ac)][usize::from(table.id)] = true;
+                        report.tables.push((segment.offset, table));
+                    }
+                }
+                0xDA if !report.arithmetic => {
+                    report
+                        .scan_problems
+                        .extend(scan_problems(body, segment.offset, &defined)?);
+                }
+                _ => {}
+            }
+        }
+        Ok(report)
+    }
+
+    /// Tells whether there is anything to flag: a broken table or scan, or tables other than
+    /// those of Annex K in a sequential file. Progressive encoders always build their own
+    /// tables, so those aren't flagged.
+    pub fn has_anomalies(&self) -> bool {
+        !self.scan_problems.is_empty()
+            || self.tables.iter().any(|(_, table)| {
+                (!self.progressive && table.standard().is_none())
+                    || !table.problems(self.progressive).is_empty()
+            })
+    }
+
+    /// Writes what `show-meta -t jpeg` tells of the Huffman tables: each table with its
+    /// number of codes and whether it is standard, then the problems found.
+    ///
+    /// Optimized tables, as `jpegtran -optimize` writes, are custom without being broken; in
+    /// sequential files they are flagged all the same, since a tool rewriting the
+    /// entropy-coded data to hide a payload rebuilds them too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::jpeg::codes::{HuffmanReport, HuffmanTable};
+    ///
+    /// let mut counts = [0; 16];
+    /// counts[1] = 4;
+    /// let table = HuffmanTable { ac: false, id: 0, counts, symbols: vec![0, 1, 2, 3] };
+    /// let report = HuffmanReport { tables: vec![(20, table)], ..Default::default() };
+    /// let mut out = Vec::new();
+    /// report.write_report(&mut out).unwrap();
+    /// let out = String::from_utf8(out).unwrap();
+    /// assert!(out.contains("DC 0 at offset 20: 4 codes, custom"));
+    /// assert!(out.contains("the code lengths break the Kraft inequality"));
+    /// ```
+    pub fn write_report<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{}", Style::Green.paint("---- Huffman ----"))?;
+        if self.arithmetic {
+            writeln!(
+                out,
+                "The file is arithmetic coded and needs no Huffman tables."
+            )?;
+        }
+        for (offset, table) in &self.tables {
+            writeln!(
+                out,
+                "{} at offset {}: {} code{}, {}",
+                table.label(),
+                offset,
+                table.symbols.len(),
+                if table.symbols.len() == 1 { "" } else { "s" },
+                table.standard().unwrap_or("custom")
+            )?;
+            for problem in table.problems(self.progressive) {
+                writeln!(out, "{}", Style::Yellow.paint(format!("  {}", problem)))?;
+            }
+        }
+        for problem in &self.scan_problems {
+            writeln!(out, "{}", Style::Yellow.paint(problem))?;
+        }
+        if !self.progressive
+            && self
+                .tables
+                .iter()
+                .any(|(_, table)| table.standard().is_none())
+        {
+            writeln!(
+                out,
+                "{}",
+                Style::Yellow.paint(
+                    "Custom tables: optimized by the encoder, or rebuilt by a tool that rewrote the scan data."
+                )
+            )?;
+        }
+        writeln!(out, "{}", Style::Green.paint("------ End ------"))
+    }
+}
+
+/// Checks the tables an SOS segment refers to against those defined before it.
+fn scan_problems(body: &[u8], offset: usize, defined: &[[bool; 4]; 2]) -> io::Result<Vec<String>> {
+    let invalid = || {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("The SOS segment at offset {} is cut short", offset),
+        )
+    };
+    let count = usize::from(*body.first().ok_or_else(invalid)?);
+    let components = body.get(1..1 + 2 * count).ok_or_else(invalid)?;
+    let [ss, se, approximation] =
+        *<&[u8; 3]>::try_from(body.get(1 + 2 * count..4 + 2 * count).ok_or_else(invalid)?)
+            .map_err(|_| invalid())?;
+    let mut problems = Vec::new();
+    for component in components.chunks(2) {
+        let (dc, ac) = (component[1] >> 4, component[1] & 0x0F);
+        let needed = [
+            (false, dc, ss == 0 && approximation >> 4 == 0),
+            (true, ac, se > 0),
+        ];
+        for (is_ac, id, used) in needed {
+            if !used {
+                continue;
+            }
+            let class = if is_ac { "AC" } else { "DC" };
+            if id > 3 {
+                problems.push(format!(
+                    "the SOS at offset {} uses {} table {}, past the four slots there are",
+                    offset, class, id
+                ));
+            } else if !defined[usize::from(is_ac)][usize::from(id)] {
+                problems.push(format!(
+                    "the SOS at offset {} uses {} table {}, which isn't defined before it",
+                    offset, class, id
+                ));
+            }
+        }
+    }
+    Ok(problems)
+}
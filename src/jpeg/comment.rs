@@ -1,4 +1,5 @@
 use crate::jpeg::writer::JpegWriter;
+use std::io;
 
 /// Represents the header for a comment in a JPEG file.
 ///
@@ -44,6 +45,10 @@ impl CommentHeader {
     ///
     /// * `writer` - A mutable reference to a `JpegWriter` trait object.
     ///
+    /// # Returns
+    ///
+    /// A `Result` with the error of the writer, if any.
+    ///
     /// # Examples
     ///
     /// ```
@@ -51,13 +56,13 @@ impl CommentHeader {
     /// use std::io::BufWriter;
     /// use std::fs::File;
     ///
-    /// let output_file = File::create("temp.jpeg").unwrap();
+    /// let output_file = File::create(std::env::temp_dir().join("temp.jpeg")).unwrap();
     /// let mut writer = BufWriter::new(output_file);
     ///
     /// let comment = CommentHeader::new("This is a sample comment.");
-    /// comment.write(&mut writer);
+    /// comment.write(&mut writer).unwrap();
     /// ```
-    pub fn write(&self, writer: &mut dyn JpegWriter) {
+    pub fn write(&self, writer: &mut dyn JpegWriter) -> io::Result<()> {
         let length = self.comment.len();
         let com: Vec<u8> = vec![
             0xFF,
@@ -67,6 +72,6 @@ impl CommentHeader {
         ];
         let comment_bytes: Vec<u8> = self.comment.bytes().collect();
         let com = [&com[..], &comment_bytes[..]].concat();
-        writer.write_array(&com);
+        writer.write_array(&com)
     }
 }
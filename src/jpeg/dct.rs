@@ -75,12 +75,11 @@ impl DctStruct {
         for (i, row) in quantum.iter_mut().enumerate() {
             for (j, coeff) in row.iter_mut().enumerate() {
                 let index = i * 64 + j;
-                if index + 1 < bytes.len() {
-                    *coeff = u16::from_be_bytes(bytes[index..(index + 2)].try_into().unwrap());
-                } else {
-                    eprintln!("Invalid byte slice for DctStruct");
+                // The last coefficient has no second byte and stays zero.
+                let Some(pair) = bytes.get(index..index + 2) else {
                     break;
-                }
+                };
+                *coeff = u16::from_be_bytes(pair.try_into().unwrap());
             }
         }
 
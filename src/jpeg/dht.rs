@@ -1,5 +1,6 @@
 use crate::jpeg::huff::Huffman;
 use crate::jpeg::writer::JpegWriter;
+use std::io;
 
 /// Represents the header for a DHT (Define Huffman Table) segment in a JPEG file.
 ///
@@ -47,6 +48,10 @@ impl DhtHeader {
     ///
     /// * `writer` - A mutable reference to a `JpegWriter` trait object.
     ///
+    /// # Returns
+    ///
+    /// A `Result` with the error of the writer, if any.
+    ///
     /// # Examples
     ///
     /// ```
@@ -60,38 +65,26 @@ impl DhtHeader {
     /// let height = 480;
     /// let huffman_encoder = Huffman::new(width, height);
     /// let dht_header = DhtHeader::new(huffman_encoder);
-    /// let output_file = File::create("temp.jpeg").unwrap();
+    /// let output_file = File::create(std::env::temp_dir().join("temp.jpeg")).unwrap();
     /// let mut writer = BufWriter::new(output_file);
-    /// dht_header.write(&mut writer);
+    /// dht_header.write(&mut writer).unwrap();
+    ///
+    /// // The four tables: a class and id byte, 16 code counts, and a value per code.
+    /// let mut segment = Vec::new();
+    /// dht_header.write(&mut segment).unwrap();
+    /// assert_eq!(&segment[..2], [0xFF, 0xC4]);
+    /// assert_eq!(u16::from_be_bytes([segment[2], segment[3]]) as usize, segment.len() - 2);
     /// ```
-    pub fn write(&self, writer: &mut dyn JpegWriter) {
-        let mut dht: Vec<u8> = vec![0xFF, 0xC4];
-        let index = 4;
-        let mut old_index = 4;
-        for i in 0..4 {
-            let bytes = self.huf.bits[i][0];
-            dht.push(bytes as u8);
-            for j in 1..17 {
-                let temp = self.huf.bits[i][j];
-                dht.push(temp as u8);
-            }
-            let dht2: Vec<u8> = self.huf.val[i].iter().map(|&x| x as u8).collect();
-            dht.extend_from_slice(&dht2);
-            let mut dht3: Vec<u8> = vec![0xFF, 0xC4];
-            let end_index = old_index + 17 + bytes as usize;
-            dht3.extend_from_slice(&dht[old_index..end_index.min(index)]); // Ensure the range is within bounds
-            dht = dht3;
-            old_index = index;
+    pub fn write(&self, writer: &mut dyn JpegWriter) -> io::Result<()> {
+        let mut dht: Vec<u8> = vec![0xFF, 0xC4, 0, 0];
+        for (bits, val) in self.huf.bits.iter().zip(&self.huf.val) {
+            // The table class and id, then the number of codes of each length from 1 to 16.
+            dht.extend(bits.iter().take(17).map(|&count| count as u8));
+            let codes: i32 = bits.iter().skip(1).take(16).sum();
+            dht.extend(val.iter().take(codes as usize).map(|&value| value as u8));
         }
-
-        // Ensure the vector has enough capacity before updating elements
-        if dht.len() > 2 {
-            dht[2] = (((index - 2) >> 8) & 0xFF) as u8;
-        }
-        if dht.len() > 3 {
-            dht[3] = ((index - 2) & 0xFF) as u8;
-        }
-
-        writer.write_array(&dht);
+        let length = (dht.len() - 2) as u16;
+        dht[2..4].copy_from_slice(&length.to_be_bytes());
+        writer.write_array(&dht)
     }
 }
@@ -1,5 +1,6 @@
 use crate::jpeg::dct::DctStruct;
 use crate::jpeg::writer::JpegWriter;
+use std::io;
 
 /// Represents the header for a Quantization Table (DQT) in a JPEG file.
 ///
@@ -48,6 +49,10 @@ impl DqtHeader {
     ///
     /// * `writer` - A mutable reference to a `JpegWriter` trait object.
     ///
+    /// # Returns
+    ///
+    /// A `Result` with the error of the writer, if any.
+    ///
     /// # Examples
     ///
     /// ```
@@ -61,18 +66,18 @@ impl DqtHeader {
     /// let dct = DctStruct::new(&bytes).unwrap();
     ///
     /// let dqt_header = DqtHeader::new(dct);
-    /// let output_file = File::create("temp.jpeg").unwrap();
+    /// let output_file = File::create(std::env::temp_dir().join("temp.jpeg")).unwrap();
     /// let mut writer = BufWriter::new(output_file);
     ///
-    /// dqt_header.write(&mut writer);
+    /// dqt_header.write(&mut writer).unwrap();
     /// ```
-    pub fn write(&self, writer: &mut dyn JpegWriter) {
+    pub fn write(&self, writer: &mut dyn JpegWriter) -> io::Result<()> {
         let mut dqt: Vec<u8> = vec![0xFF, 0xDB, 0x00, 0x84];
         for i in 0..2 {
             dqt.push(i as u8);
             let temp_array = &self.dct.quantum[i];
             dqt.extend(temp_array.iter().map(|&x| x as u8));
         }
-        writer.write_array(&dqt);
+        writer.write_array(&dqt)
     }
 }
@@ -1,4 +1,5 @@
 use crate::jpeg::writer::JpegWriter;
+use std::io;
 
 /// Struct representing the header of a JPEG File Interchange Format (JFIF) file.
 ///
@@ -48,14 +49,12 @@ impl JfifHeader {
     pub fn new(data: &[u8]) -> Result<Self, &'static str> {
         // Check if the byte slice has the expected length
         if data.len() != 18 {
-            eprintln!("Warning: Invalid byte slice length for JFIF header. Continuing...");
             return Err("Invalid byte slice length for JFIF header");
         }
 
         // Validate the JFIF marker and other fields
         let expected_marker: [u8; 2] = [0x4A, 0x46]; // ASCII codes for "JF"
         if data[0..2] != expected_marker {
-            eprintln!("Warning: Invalid JFIF marker. Continuing...");
             return Err("Invalid JFIF marker");
         }
 
@@ -72,6 +71,10 @@ impl JfifHeader {
     ///
     /// * `writer` - A mutable reference to a `JpegWriter` trait object.
     ///
+    /// # Returns
+    ///
+    /// A `Result` with the error of the writer, if any.
+    ///
     /// # Examples
     ///
     /// ```
@@ -79,17 +82,17 @@ impl JfifHeader {
     /// use std::io::BufWriter;
     /// use std::fs::File;
     ///
-    /// let output_file = File::create("temp.jpeg").unwrap();
+    /// let output_file = File::create(std::env::temp_dir().join("temp.jpeg")).unwrap();
     ///
     /// let mut writer = BufWriter::new(output_file);
     /// let jfif_header = JfifHeader { version: 1 };
-    /// jfif_header.write(&mut writer);
+    /// jfif_header.write(&mut writer).unwrap();
     /// ```
-    pub fn write(&self, writer: &mut dyn JpegWriter) {
+    pub fn write(&self, writer: &mut dyn JpegWriter) -> io::Result<()> {
         let jfif: [u8; 18] = [
             0xFF, 0xE0, 0x00, 0x10, 0x4A, 0x46, 0x49, 0x46, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01,
             0x00, 0x01, 0x00, 0x00,
         ];
-        writer.write_array(&jfif);
+        writer.write_array(&jfif)
     }
 }
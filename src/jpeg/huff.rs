@@ -287,7 +287,7 @@ impl Huffman {
     ///     0, 0, 0, 0, 0, 0, 0, 0,
     ///     0, 0, 0, 0, 0, 0, 0, 0,
     /// ];
-
+    ///
     /// let predictor = 0;
     /// let dc_table_index = 0;
     /// let ac_table_index = 1;
@@ -428,15 +428,20 @@ impl Huffman {
         put_buffer <<= 24 - put_bits;
         put_buffer |= self.buffer_put_buffer;
 
+        // At most three bytes are complete, each followed by a stuffed zero if it is 0xFF;
+        // they go out in one write.
+        let mut bytes = [0u8; 6];
+        let mut len = 0;
         while put_bits >= 8 {
             let c = ((put_buffer >> 16) & 0xFF) as u8;
-            out_stream.write_all(&[c]).unwrap();
-            if c == 0xFF {
-                out_stream.write_all(&[0]).unwrap();
-            }
+            bytes[len] = c;
+            len += if c == 0xFF { 2 } else { 1 };
             put_buffer <<= 8;
             put_bits -= 8;
         }
+        if len > 0 {
+            out_stream.write_all(&bytes[..len]).unwrap();
+        }
         self.buffer_put_buffer = put_buffer;
         self.buffer_put_bits = put_bits;
     }
@@ -449,7 +454,7 @@ impl Huffman {
     /// # Arguments
     ///
     /// * `out_stream` - A mutable reference to a type implementing the `Write` trait,
-    ///                  where the encoded data will be written.
+    ///   where the encoded data will be written.
     ///
     /// # Examples
     ///
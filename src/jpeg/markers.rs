@@ -0,0 +1,317 @@
+use crate::outln;
+use crate::style::Style;
+use crate::tiff::TiffFile;
+use std::io::{self, ErrorKind};
+
+/// The Exif tag naming the maker of the camera.
+const TAG_MAKE: u16 = 0x010F;
+
+/// The Exif tag naming the model of the camera.
+const TAG_MODEL: u16 = 0x0110;
+
+/// A marker found in a JPEG file, with the segment it opens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkerSegment {
+    /// The second byte of the marker, such as `0xE1` for APP1.
+    pub marker: u8,
+    /// The offset of the marker's `0xFF` byte in the file.
+    pub offset: usize,
+    /// The declared length of the segment, which counts its two length bytes; 0 for the
+    /// standalone markers (SOI, EOI, RSTn and TEM).
+    pub length: usize,
+    /// The number of entropy-coded bytes following the segment, for SOS and RSTn.
+    pub scan_len: usize,
+    /// A short classification, such as "APP1/Exif" or "SOF2/Progressive".
+    pub class: String,
+}
+
+/// Every marker of a JPEG file, in file order, as `show-meta -t jpeg` lists them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkerInventory {
+    /// The markers, from SOI to EOI.
+    pub segments: Vec<MarkerSegment>,
+    /// The number of bytes after EOI, where appended payloads go.
+    pub trailing: usize,
+}
+
+impl MarkerInventory {
+    /// Walks the markers of a JPEG file, through the entropy-coded data of its scans.
+    ///
+    /// Fill bytes before markers and stuffed `0xFF 0x00` pairs in scans are skipped; the
+    /// restart markers inside a scan are listed like any other marker.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the inventory, or an `InvalidData` error if the file doesn't
+    /// start with SOI, has bytes where a marker should be, or holds a segment longer than the
+    /// file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::jpeg::markers::MarkerInventory;
+    ///
+    /// let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1, 0x00, 0x08];
+    /// jpeg.extend_from_slice(b"Exif\0\0");
+    /// jpeg.extend_from_slice(&[0xFF, 0xDD, 0x00, 0x04, 0x00, 0x01]);
+    /// jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02, 0x12, 0xFF, 0x00]);
+    /// jpeg.extend_from_slice(&[0xFF, 0xD0, 0x34, 0xFF, 0xD9, b'!']);
+    ///
+    /// let inventory = MarkerInventory::parse(&jpeg).unwrap();
+    /// let classes: Vec<&str> = inventory.segments.iter().map(|s| s.class.as_str()).collect();
+    /// assert_eq!(classes, ["SOI", "APP1/Exif", "DRI", "SOS", "RST0", "EOI"]);
+    /// assert_eq!(inventory.segments[3].scan_len, 3);
+    /// assert_eq!(inventory.trailing, 1);
+    ///
+    /// // The APP1 segment declares more bytes than the file holds.
+    /// assert!(MarkerInventory::parse(&jpeg[..8]).is_err());
+    /// ```
+    pub fn parse(data: &[u8]) -> io::Result<MarkerInventory> {
+        let invalid = |msg: String| io::Error::new(ErrorKind::InvalidData, msg);
+        if !data.starts_with(&[0xFF, 0xD8]) {
+            return Err(invalid("Not a JPEG file: it doesn't start with SOI".into()));
+        }
+        let mut segments = Vec::new();
+        let mut pos = 0;
+        while pos < data.len() {
+            if data[pos] != 0xFF {
+                return Err(invalid(format!("Expected a marker at offset {}", pos)));
+            }
+            // Fill bytes may precede a marker.
+            while data.get(pos + 1) == Some(&0xFF) {
+                pos += 1;
+            }
+            let Some(&marker) = data.get(pos + 1) else {
+                return Err(invalid(format!("Truncated marker at offset {}", pos)));
+            };
+            let offset = pos;
+            let length = if is_standalone(marker) {
+                0
+            } else {
+                let Some(&[high, low]) = data.get(pos + 2..pos + 4) else {
+                    return Err(invalid(format!("Truncated segment at offset {}", pos)));
+                };
+                let length = u16::from_be_bytes([high, low]) as usize;
+                if length < 2 || pos + 2 + length > data.len() {
+                    return Err(invalid(format!(
+                        "The FF{:02X} segment at offset {} declares {} bytes, past the end of the file",
+                        marker, pos, length
+                    )));
+                }
+                length
+            };
+            pos += 2 + length;
+            let scan_len = if marker == 0xDA || (0xD0..=0xD7).contains(&marker) {
+                let start = pos;
+                // In the entropy-coded data, 0xFF is only followed by stuffing or restart
+                // markers, which are listed on their own.
+                while pos < data.len() && (data[pos] != 0xFF || data.get(pos + 1) == Some(&0x00)) {
+                    pos += if data[pos] == 0xFF { 2 } else { 1 };
+                }
+                pos - start
+            } else {
+                0
+            };
+            let body = &data[(offset + 4).min(offset + 2 + length)..offset + 2 + length];
+            segments.push(MarkerSegment {
+                marker,
+                offset,
+                length,
+                scan_len,
+                class: classify(marker, body),
+            });
+            if marker == 0xD9 {
+                return Ok(MarkerInventory {
+                    segments,
+                    trailing: data.len() - pos,
+                });
+            }
+        }
+        Ok(MarkerInventory {
+            segments,
+            trailing: 0,
+        })
+    }
+
+    /// Tells whether the file has an APP14 Adobe segment, as Adobe software writes.
+    pub fn is_adobe(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| segment.class == "APP14/Adobe")
+    }
+
+    /// Returns the make and model of the camera named in the APP1 Exif segment, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The whole JPEG file the inventory was parsed from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::jpeg::markers::MarkerInventory;
+    ///
+    /// // A little-endian IFD with a Make entry pointing at "Acme\0".
+    /// let mut exif = b"Exif\0\0II*\0\x08\0\0\0\x01\0\x0F\x01\x02\0\x05\0\0\0\x1A\0\0\0\0\0\0\0".to_vec();
+    /// exif.extend_from_slice(b"Acme\0");
+    /// let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1, 0x00, exif.len() as u8 + 2];
+    /// jpeg.extend_from_slice(&exif);
+    /// jpeg.extend_from_slice(&[0xFF, 0xD9]);
+    ///
+    /// let inventory = MarkerInventory::parse(&jpeg).unwrap();
+    /// assert_eq!(inventory.camera(&jpeg).as_deref(), Some("Acme"));
+    /// ```
+    pub fn camera(&self, data: &[u8]) -> Option<String> {
+        let segment = self
+            .segments
+            .iter()
+            .find(|segment| segment.class == "APP1/Exif")?;
+        let tiff = data.get(segment.offset + 10..segment.offset + 2 + segment.length)?;
+        let (order, ifds) = TiffFile::parse_ifds(tiff).ok()?;
+        let text = |tag| {
+            let entry = ifds
+                .first()?
+                .entries
+                .iter()
+                .find(|entry| entry.tag == tag)?;
+            let value = String::from_utf8_lossy(entry.data(order, tiff).ok()?);
+            let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        match (text(TAG_MAKE), text(TAG_MODEL)) {
+            // Models often repeat the make, as in "Canon" and "Canon EOS 5D".
+            (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+            (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+            (make, model) => make.or(model),
+        }
+    }
+
+    /// Prints the inventory as a table, followed by a warning about a missing EOI.
+    ///
+    /// Bytes after EOI are reported by `trailing::Trailing::write_report`.
+    pub fn print(&self) {
+        outln!("{}", Style::Green.paint("---- Markers ----"));
+        outln!(
+            "{}",
+            Style::Grey.paint(format!(
+                "{:<10} {:<6} {:>7}  {}",
+                "Offset", "Marker", "Length", "Class"
+            ))
+        );
+        for segment in &self.segments {
+            let length = if segment.length == 0 {
+                "-".to_string()
+            } else {
+                segment.length.to_string()
+            };
+            let scan = if segment.scan_len > 0 {
+                format!(" + {} bytes of scan data", segment.scan_len)
+            } else {
+                String::new()
+            };
+            outln!(
+                "{:<10} FF{:02X}   {:>7}  {}{}",
+                segment.offset,
+                segment.marker,
+                length,
+                segment.class,
+                scan
+            );
+        }
+        outln!("{}", Style::Green.paint("------ End ------"));
+        if self
+            .segments
+            .last()
+            .is_none_or(|segment| segment.marker != 0xD9)
+        {
+            outln!(
+                "{}",
+                Style::Yellow.paint("The file ends without an EOI marker.")
+            );
+        }
+    }
+}
+
+/// Tells whether a marker stands alone, without a length or segment.
+fn is_standalone(marker: u8) -> bool {
+    matches!(marker, 0x01 | 0xD0..=0xD9)
+}
+
+/// Classifies a marker, naming the application segments after the identifier they open with.
+///
+/// # Arguments
+///
+/// * `marker` - The second byte of the marker.
+/// * `body` - The segment after its length field, empty for standalone markers.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::jpeg::markers::classify;
+///
+/// assert_eq!(classify(0xC2, &[]), "SOF2/Progressive");
+/// assert_eq!(classify(0xED, b"Photoshop 3.0\0"), "APP13/IPTC");
+/// assert_eq!(classify(0xE2, b"ICC_PROFILE\0\x01\x02"), "APP2/ICC 1 of 2");
+/// assert_eq!(classify(0xE7, b"stEg payload"), "APP7");
+/// assert_eq!(classify(0x4F, &[]), "unknown");
+/// ```
+pub fn classify(marker: u8, body: &[u8]) -> String {
+    let name = match marker {
+        0x01 => "TEM",
+        0xC0 => "SOF0/Baseline",
+        0xC1 => "SOF1/Extended sequential",
+        0xC2 => "SOF2/Progressive",
+        0xC3 => "SOF3/Lossless",
+        0xC4 => "DHT",
+        0xC5 => "SOF5/Differential sequential",
+        0xC6 => "SOF6/Differential progressive",
+        0xC7 => "SOF7/Differential lossless",
+        0xC8 => "JPG",
+        0xC9 => "SOF9/Arithmetic sequential",
+        0xCA => "SOF10/Arithmetic progressive",
+        0xCB => "SOF11/Arithmetic lossless",
+        0xCC => "DAC",
+        0xCD => "SOF13/Arithmetic differential sequential",
+        0xCE => "SOF14/Arithmetic differential progressive",
+        0xCF => "SOF15/Arithmetic differential lossless",
+        0xD0..=0xD7 => return format!("RST{}", marker - 0xD0),
+        0xD8 => "SOI",
+        0xD9 => "EOI",
+        0xDA => "SOS",
+        0xDB => "DQT",
+        0xDC => "DNL",
+        0xDD => "DRI",
+        0xDE => "DHP",
+        0xDF => "EXP",
+        0xE0..=0xEF => return classify_app(marker - 0xE0, body),
+        0xF0..=0xFD => return format!("JPG{}", marker - 0xF0),
+        0xFE => "COM",
+        _ => "unknown",
+    };
+    name.to_string()
+}
+
+/// Names an APPn segment after the identifier it opens with.
+fn classify_app(n: u8, body: &[u8]) -> String {
+    let app = format!("APP{}", n);
+    let kind = match (n, body) {
+        (0, b) if b.starts_with(b"JFIF\0") => "JFIF",
+        (0, b) if b.starts_with(b"JFXX\0") => "JFXX",
+        (1, b) if b.starts_with(b"Exif\0") => "Exif",
+        (1, b) if b.starts_with(b"http://ns.adobe.com/xap/1.0/\0") => "XMP",
+        (1, b) if b.starts_with(b"http://ns.adobe.com/xmp/extension/\0") => "XMP extension",
+        (2, b) if b.starts_with(b"ICC_PROFILE\0") => {
+            return match b.get(12..14) {
+                Some(&[seq, count]) => format!("{}/ICC {} of {}", app, seq, count),
+                _ => format!("{}/ICC", app),
+            };
+        }
+        (2, b) if b.starts_with(b"MPF\0") => "MPF",
+        (2, b) if b.starts_with(b"FPXR\0") => "FlashPix",
+        (12, b) if b.starts_with(b"Ducky") => "Ducky",
+        (13, b) if b.starts_with(b"Photoshop 3.0\0") => "IPTC",
+        (14, b) if b.starts_with(b"Adobe") => "Adobe",
+        _ => return app,
+    };
+    format!("{}/{}", app, kind)
+}
@@ -1,10 +1,13 @@
+pub mod codes;
 pub mod comment;
 pub mod dct;
 pub mod dht;
 pub mod dqt;
 pub mod header;
 pub mod huff;
+pub mod markers;
 pub mod obj;
+pub mod quant;
 pub mod sof;
 pub mod sos;
 pub mod utils;
@@ -0,0 +1,520 @@
+use crate::jpeg::markers::MarkerInventory;
+use crate::style::Style;
+use std::fmt;
+use std::io::{self, ErrorKind, Write};
+
+/// The position of each coefficient of the natural (row-major) order in the zigzag order
+/// DQT segments store tables in.
+const ZIGZAG_POSITION: [usize; 64] = [
+    0, 1, 5, 6, 14, 15, 27, 28, 2, 4, 7, 13, 16, 26, 29, 42, 3, 8, 12, 17, 25, 30, 41, 43, 9, 11,
+    18, 24, 31, 40, 44, 53, 10, 19, 23, 32, 39, 45, 52, 54, 20, 22, 33, 38, 46, 51, 55, 60, 21, 34,
+    37, 47, 50, 56, 59, 61, 35, 36, 48, 49, 57, 58, 62, 63,
+];
+
+/// The luminance table of Annex K of the JPEG standard, in natural order, which libjpeg scales
+/// for its quality levels.
+pub const ANNEX_K_LUMINANCE: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+/// The chrominance table of Annex K of the JPEG standard, in natural order.
+pub const ANNEX_K_CHROMINANCE: [u16; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99,
+    47, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+/// The tables of the presets of Adobe Photoshop, as (preset, luminance, chrominance) in
+/// natural order: the "Save for Web" ones, then those of "Save As".
+pub const PHOTOSHOP_TABLES: [(&str, [u16; 64], [u16; 64]); 9] = [
+    (
+        "Save for Web, Low",
+        [
+            20, 16, 25, 39, 50, 46, 62, 68, 16, 18, 23, 38, 38, 53, 65, 68, 25, 23, 31, 38, 53, 65,
+            68, 68, 39, 38, 38, 53, 65, 68, 68, 68, 50, 38, 53, 65, 68, 68, 68, 68, 46, 53, 65, 68,
+            68, 68, 68, 68, 62, 65, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68,
+        ],
+        [
+            21, 25, 32, 38, 54, 68, 68, 68, 25, 28, 24, 38, 54, 68, 68, 68, 32, 24, 32, 43, 66, 68,
+            68, 68, 38, 38, 43, 53, 68, 68, 68, 68, 54, 54, 66, 68, 68, 68, 68, 68, 68, 68, 68, 68,
+            68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68, 68,
+        ],
+    ),
+    (
+        "Save for Web, Medium",
+        [
+            16, 11, 11, 16, 23, 27, 31, 30, 11, 12, 12, 15, 20, 23, 23, 30, 11, 12, 13, 16, 23, 26,
+            35, 47, 16, 15, 16, 23, 26, 37, 47, 64, 23, 20, 23, 26, 39, 51, 64, 64, 27, 23, 26, 37,
+            51, 64, 64, 64, 31, 23, 35, 47, 64, 64, 64, 64, 30, 30, 47, 64, 64, 64, 64, 64,
+        ],
+        [
+            17, 15, 17, 21, 20, 26, 38, 48, 15, 19, 18, 17, 20, 26, 35, 43, 17, 18, 20, 22, 26, 30,
+            46, 53, 21, 17, 22, 28, 30, 39, 53, 64, 20, 20, 26, 30, 39, 48, 64, 64, 26, 26, 30, 39,
+            48, 63, 64, 64, 38, 35, 46, 53, 64, 64, 64, 64, 48, 43, 53, 64, 64, 64, 64, 64,
+        ],
+    ),
+    (
+        "Save for Web, High",
+        [
+            6, 4, 4, 6, 9, 11, 12, 16, 4, 5, 5, 6, 8, 10, 12, 12, 4, 5, 5, 6, 10, 12, 14, 19, 6, 6,
+            6, 11, 12, 15, 19, 28, 9, 8, 10, 12, 16, 20, 27, 31, 11, 10, 12, 15, 20, 27, 31, 31,
+            12, 12, 14, 19, 27, 31, 31, 31, 16, 12, 19, 28, 31, 31, 31, 31,
+        ],
+        [
+            7, 7, 13, 24, 26, 31, 31, 31, 7, 12, 16, 21, 31, 31, 31, 31, 13, 16, 17, 31, 31, 31,
+            31, 31, 24, 21, 31, 31, 31, 31, 31, 31, 26, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31,
+            31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31,
+        ],
+    ),
+    (
+        "Save for Web, Very High",
+        [
+            2, 2, 2, 2, 3, 4, 5, 6, 2, 2, 2, 2, 3, 4, 5, 6, 2, 2, 2, 2, 4, 5, 7, 9, 2, 2, 2, 4, 5,
+            7, 9, 12, 3, 3, 4, 5, 8, 10, 12, 12, 4, 4, 5, 7, 10, 12, 12, 12, 5, 5, 7, 9, 12, 12,
+            12, 12, 6, 6, 9, 12, 12, 12, 12, 12,
+        ],
+        [
+            3, 3, 5, 9, 13, 15, 15, 15, 3, 4, 6, 11, 14, 12, 12, 12, 5, 6, 9, 14, 12, 12, 12, 12,
+            9, 11, 14, 12, 12, 12, 12, 12, 13, 14, 12, 12, 12, 12, 12, 12, 15, 12, 12, 12, 12, 12,
+            12, 12, 15, 12, 12, 12, 12, 12, 12, 12, 15, 12, 12, 12, 12, 12, 12, 12,
+        ],
+    ),
+    (
+        "Save for Web, Maximum",
+        [
+            1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1,
+            1, 2, 2, 1, 1, 1, 1, 1, 2, 2, 3, 1, 1, 1, 1, 2, 2, 3, 3, 1, 1, 1, 2, 2, 3, 3, 3, 1, 1,
+            2, 2, 3, 3, 3, 3,
+        ],
+        [
+            1, 1, 1, 2, 2, 3, 3, 3, 1, 1, 1, 2, 3, 3, 3, 3, 1, 1, 1, 3, 3, 3, 3, 3, 2, 2, 3, 3, 3,
+            3, 3, 3, 2, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3,
+        ],
+    ),
+    (
+        "Save As, Low",
+        [
+            18, 14, 14, 21, 30, 35, 34, 17, 14, 16, 16, 19, 26, 23, 12, 12, 14, 16, 17, 21, 23, 12,
+            12, 12, 21, 19, 21, 23, 12, 12, 12, 12, 30, 26, 23, 12, 12, 12, 12, 12, 35, 23, 12, 12,
+            12, 12, 12, 12, 34, 12, 12, 12, 12, 12, 12, 12, 17, 12, 12, 12, 12, 12, 12, 12,
+        ],
+        [
+            20, 19, 22, 27, 20, 20, 17, 17, 19, 25, 23, 14, 14, 12, 12, 12, 22, 23, 14, 14, 12, 12,
+            12, 12, 27, 14, 14, 12, 12, 12, 12, 12, 20, 14, 12, 12, 12, 12, 12, 12, 20, 12, 12, 12,
+            12, 12, 12, 12, 17, 12, 12, 12, 12, 12, 12, 12, 17, 12, 12, 12, 12, 12, 12, 12,
+        ],
+    ),
+    (
+        "Save As, Medium",
+        [
+            12, 8, 8, 12, 17, 21, 24, 17, 8, 9, 9, 11, 15, 19, 12, 12, 8, 9, 10, 12, 19, 12, 12,
+            12, 12, 11, 12, 21, 12, 12, 12, 12, 17, 15, 19, 12, 12, 12, 12, 12, 21, 19, 12, 12, 12,
+            12, 12, 12, 24, 12, 12, 12, 12, 12, 12, 12, 17, 12, 12, 12, 12, 12, 12, 12,
+        ],
+        [
+            13, 11, 13, 16, 20, 20, 17, 17, 11, 14, 14, 14, 14, 12, 12, 12, 13, 14, 14, 14, 12, 12,
+            12, 12, 16, 14, 14, 12, 12, 12, 12, 12, 20, 14, 12, 12, 12, 12, 12, 12, 20, 12, 12, 12,
+            12, 12, 12, 12, 17, 12, 12, 12, 12, 12, 12, 12, 17, 12, 12, 12, 12, 12, 12, 12,
+        ],
+    ),
+    (
+        "Save As, High",
+        [
+            6, 4, 4, 6, 9, 11, 12, 16, 4, 5, 5, 6, 8, 10, 12, 12, 4, 5, 5, 6, 10, 12, 12, 12, 6, 6,
+            6, 11, 12, 12, 12, 12, 9, 8, 10, 12, 12, 12, 12, 12, 11, 10, 12, 12, 12, 12, 12, 12,
+            12, 12, 12, 12, 12, 12, 12, 12, 16, 12, 12, 12, 12, 12, 12, 12,
+        ],
+        [
+            7, 7, 13, 24, 20, 20, 17, 17, 7, 12, 16, 14, 14, 12, 12, 12, 13, 16, 14, 14, 12, 12,
+            12, 12, 24, 14, 14, 12, 12, 12, 12, 12, 20, 14, 12, 12, 12, 12, 12, 12, 20, 12, 12, 12,
+            12, 12, 12, 12, 17, 12, 12, 12, 12, 12, 12, 12, 17, 12, 12, 12, 12, 12, 12, 12,
+        ],
+    ),
+    (
+        "Save As, Maximum",
+        [
+            2, 2, 2, 2, 3, 4, 5, 6, 2, 2, 2, 2, 3, 4, 5, 6, 2, 2, 2, 2, 4, 5, 7, 9, 2, 2, 2, 4, 5,
+            7, 9, 12, 3, 3, 4, 5, 8, 10, 12, 12, 4, 4, 5, 7, 10, 12, 12, 12, 5, 5, 7, 9, 12, 12,
+            12, 12, 6, 6, 9, 12, 12, 12, 12, 12,
+        ],
+        [
+            3, 3, 5, 9, 13, 15, 15, 15, 3, 4, 6, 10, 14, 12, 12, 12, 5, 6, 9, 14, 12, 12, 12, 12,
+            9, 10, 14, 12, 12, 12, 12, 12, 13, 14, 12, 12, 12, 12, 12, 12, 15, 12, 12, 12, 12, 12,
+            12, 12, 15, 12, 12, 12, 12, 12, 12, 12, 15, 12, 12, 12, 12, 12, 12, 12,
+        ],
+    ),
+];
+
+/// What a JPEG file tells of its encoder besides its tables, for `QuantSource::identify`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EncoderHints {
+    /// Whether the file has an APP14 Adobe segment, as Adobe software writes.
+    pub adobe: bool,
+    /// The make and model of the camera named in the Exif data.
+    pub camera: Option<String>,
+}
+
+impl EncoderHints {
+    /// Gathers the hints of a JPEG file from its markers.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The whole JPEG file.
+    /// * `inventory` - Its markers, as `MarkerInventory::parse` returns them.
+    pub fn read(data: &[u8], inventory: &MarkerInventory) -> EncoderHints {
+        EncoderHints {
+            adobe: inventory.is_adobe(),
+            camera: inventory.camera(data),
+        }
+    }
+}
+
+/// A quantization table read from a DQT segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantTable {
+    /// The table slot, from 0 to 3, that frame components refer to.
+    pub id: u8,
+    /// Whether the values take 16 bits instead of 8.
+    pub wide: bool,
+    /// The 64 quantization steps, in natural (row-major) order.
+    pub values: [u16; 64],
+}
+
+impl QuantTable {
+    /// Reads every table of a DQT segment, after its length field.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the tables, or an `InvalidData` error if the segment is cut short
+    /// or declares a precision or slot the standard doesn't have.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::jpeg::quant::QuantTable;
+    ///
+    /// let mut body = vec![0x00];
+    /// body.extend(1..=64u8);
+    /// let tables = QuantTable::parse_segment(&body).unwrap();
+    /// // The zigzag order goes right first, then down and to the left.
+    /// assert_eq!((tables[0].id, tables[0].values[1], tables[0].values[8]), (0, 2, 3));
+    /// assert!(QuantTable::parse_segment(&body[..40]).is_err());
+    /// ```
+    pub fn parse_segment(body: &[u8]) -> io::Result<Vec<QuantTable>> {
+        let invalid = |msg: String| io::Error::new(ErrorKind::InvalidData, msg);
+        let mut tables = Vec::new();
+        let mut rest = body;
+        while let Some((&info, after)) = rest.split_first() {
+            let (wide, id) = (info >> 4, info & 0x0F);
+            if wide > 1 || id > 3 {
+                return Err(invalid(format!(
+                    "Invalid DQT table: precision {} and slot {}",
+                    wide, id
+                )));
+            }
+            let size = if wide == 1 { 128 } else { 64 };
+            let raw = after.get(..size).ok_or_else(|| {
+                invalid(format!(
+                    "DQT table {} is cut short at {} bytes",
+                    id,
+                    after.len()
+                ))
+            })?;
+            let mut values = [0; 64];
+            for (natural, value) in values.iter_mut().enumerate() {
+                let zigzag = ZIGZAG_POSITION[natural];
+                *value = if wide == 1 {
+                    u16::from_be_bytes([raw[2 * zigzag], raw[2 * zigzag + 1]])
+                } else {
+                    u16::from(raw[zigzag])
+                };
+            }
+            tables.push(QuantTable {
+                id,
+                wide: wide == 1,
+                values,
+            });
+            rest = &after[size..];
+        }
+        Ok(tables)
+    }
+
+    /// Reads the tables of every DQT segment of a JPEG file, in file order.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The whole JPEG file.
+    /// * `inventory` - Its markers, as `MarkerInventory::parse` returns them.
+    pub fn read_all(data: &[u8], inventory: &MarkerInventory) -> io::Result<Vec<QuantTable>> {
+        let mut tables = Vec::new();
+        for segment in &inventory.segments {
+            if segment.marker == 0xDB {
+                let body = &data[segment.offset + 4..segment.offset + 2 + segment.length];
+                tables.extend(QuantTable::parse_segment(body)?);
+            }
+        }
+        Ok(tables)
+    }
+}
+
+/// Scales a base table as libjpeg does for a quality from 1 to 100, with baseline values of
+/// at most 255.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::jpeg::quant::{libjpeg_table, ANNEX_K_LUMINANCE};
+///
+/// assert_eq!(libjpeg_table(&ANNEX_K_LUMINANCE, 50), ANNEX_K_LUMINANCE);
+/// assert_eq!(libjpeg_table(&ANNEX_K_LUMINANCE, 75)[..4], [8, 6, 5, 8]);
+/// assert_eq!(libjpeg_table(&ANNEX_K_LUMINANCE, 100), [1; 64]);
+/// ```
+pub fn libjpeg_table(base: &[u16; 64], quality: u8) -> [u16; 64] {
+    let quality = u32::from(quality.clamp(1, 100));
+    let scale = if quality < 50 {
+        5000 / quality
+    } else {
+        200 - 2 * quality
+    };
+    base.map(|value| ((u32::from(value) * scale + 50) / 100).clamp(1, 255) as u16)
+}
+
+/// What the quantization tables of a JPEG file tell of the encoder that wrote them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuantSource {
+    /// The Annex K tables as libjpeg scales them, as written by libjpeg, libjpeg-turbo and the
+    /// many tools built on them, at this quality.
+    Libjpeg {
+        /// The quality the tables were scaled for.
+        quality: u8,
+    },
+    /// The tables of a preset of Adobe Photoshop, from `PHOTOSHOP_TABLES`.
+    Photoshop {
+        /// The preset, such as "Save for Web, High".
+        preset: &'static str,
+        /// The closest libjpeg quality.
+        closest: u8,
+    },
+    /// Tables of other Adobe software, which writes an APP14 Adobe segment, with the libjpeg
+    /// quality whose tables are the closest.
+    Adobe {
+        /// The closest libjpeg quality.
+        closest: u8,
+    },
+    /// Tables of the camera named in the Exif data, since cameras scale tables of their own,
+    /// with the libjpeg quality whose tables are the closest.
+    Camera {
+        /// The make and model of the camera.
+        name: String,
+        /// The closest libjpeg quality.
+        closest: u8,
+    },
+    /// Tables of no encoder known here, with the libjpeg quality whose tables are the closest.
+    Unknown {
+        /// The closest libjpeg quality.
+        closest: u8,
+    },
+}
+
+impl QuantSource {
+    /// Tells which encoder likely wrote these tables, or `None` without a luminance table.
+    ///
+    /// The luminance table (slot 0) and, when there is one, the chrominance table (slot 1) are
+    /// compared with those libjpeg computes for each quality, then with `PHOTOSHOP_TABLES`.
+    /// Tables matching none of them are given the quality whose tables differ the least, in
+    /// the sum of the relative differences of the steps, and put down to Adobe software when
+    /// the file has an APP14 Adobe segment, or else to the camera its Exif data names.
+    ///
+    /// # Arguments
+    ///
+    /// * `tables` - The tables of the file, as `QuantTable::read_all` returns them.
+    /// * `hints` - What the other segments of the file tell of its encoder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::jpeg::quant::{
+    ///     libjpeg_table, EncoderHints, QuantSource, QuantTable, ANNEX_K_CHROMINANCE,
+    ///     ANNEX_K_LUMINANCE, PHOTOSHOP_TABLES,
+    /// };
+    ///
+    /// let table = |id, values| QuantTable { id, wide: false, values };
+    /// let none = EncoderHints::default();
+    /// let tables = [
+    ///     table(0, libjpeg_table(&ANNEX_K_LUMINANCE, 85)),
+    ///     table(1, libjpeg_table(&ANNEX_K_CHROMINANCE, 85)),
+    /// ];
+    /// assert_eq!(QuantSource::identify(&tables, &none), Some(QuantSource::Libjpeg { quality: 85 }));
+    ///
+    /// let (preset, luma, chroma) = PHOTOSHOP_TABLES[2];
+    /// let source = QuantSource::identify(&[table(0, luma), table(1, chroma)], &none).unwrap();
+    /// assert_eq!(source, QuantSource::Photoshop { preset, closest: 84 });
+    ///
+    /// let mut custom = libjpeg_table(&ANNEX_K_LUMINANCE, 90);
+    /// custom[0] = 1;
+    /// let adobe = EncoderHints { adobe: true, camera: None };
+    /// assert_eq!(
+    ///     QuantSource::identify(&[table(0, custom)], &adobe),
+    ///     Some(QuantSource::Adobe { closest: 90 })
+    /// );
+    /// let camera = EncoderHints { adobe: false, camera: Some("Acme X1".into()) };
+    /// assert_eq!(
+    ///     QuantSource::identify(&[table(0, custom)], &camera),
+    ///     Some(QuantSource::Camera { name: "Acme X1".into(), closest: 90 })
+    /// );
+    /// assert_eq!(QuantSource::identify(&[], &none), None);
+    /// ```
+    pub fn identify(tables: &[QuantTable], hints: &EncoderHints) -> Option<QuantSource> {
+        let find = |id| tables.iter().find(|table| table.id == id);
+        let luma = find(0)?;
+        let chroma = find(1);
+        let distance = |quality| {
+            let mut distance =
+                table_distance(&luma.values, &libjpeg_table(&ANNEX_K_LUMINANCE, quality));
+            if let Some(chroma) = chroma {
+                distance += table_distance(
+                    &chroma.values,
+                    &libjpeg_table(&ANNEX_K_CHROMINANCE, quality),
+                );
+            }
+            distance
+        };
+        // Low qualities clamp to the same tables; the highest of them is reported.
+        if let Some(quality) = (1..=100).rev().find(|&quality| distance(quality) == 0.0) {
+            return Some(QuantSource::Libjpeg { quality });
+        }
+        let closest = (1..=100)
+            .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or(100);
+        let photoshop = PHOTOSHOP_TABLES.iter().find(|(_, luminance, chrominance)| {
+            luma.values == *luminance && chroma.is_none_or(|chroma| chroma.values == *chrominance)
+        });
+        Some(match (photoshop, &hints.camera) {
+            (Some(&(preset, ..)), _) => QuantSource::Photoshop { preset, closest },
+            _ if hints.adobe => QuantSource::Adobe { closest },
+            (None, Some(name)) => QuantSource::Camera {
+                name: name.clone(),
+                closest,
+            },
+            (None, None) => QuantSource::Unknown { closest },
+        })
+    }
+
+    /// Returns the quality of the tables, exact for libjpeg and estimated for the others.
+    pub fn quality(&self) -> u8 {
+        match *self {
+            QuantSource::Libjpeg { quality } => quality,
+            QuantSource::Photoshop { closest, .. }
+            | QuantSource::Adobe { closest }
+            | QuantSource::Camera { closest, .. }
+            | QuantSource::Unknown { closest } => closest,
+        }
+    }
+
+    /// Returns a short name of the encoder.
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            QuantSource::Libjpeg { .. } => "libjpeg",
+            QuantSource::Photoshop { .. } => "photoshop",
+            QuantSource::Adobe { .. } => "adobe",
+            QuantSource::Camera { .. } => "camera",
+            QuantSource::Unknown { .. } => "unknown",
+        }
+    }
+
+    /// Tells whether the tables are exactly those of a known encoder setting.
+    pub fn is_exact(&self) -> bool {
+        matches!(
+            self,
+            QuantSource::Libjpeg { .. } | QuantSource::Photoshop { .. }
+        )
+    }
+}
+
+impl fmt::Display for QuantSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuantSource::Libjpeg { quality } => {
+                write!(f, "libjpeg (or a tool built on it) at quality {}", quality)
+            }
+            QuantSource::Photoshop { preset, closest } => write!(
+                f,
+                "Adobe Photoshop ({}), close to libjpeg quality {}",
+                preset, closest
+            ),
+            QuantSource::Adobe { closest } => write!(
+                f,
+                "Adobe software (APP14 Adobe segment, custom tables), close to libjpeg quality {}",
+                closest
+            ),
+            QuantSource::Camera { name, closest } => write!(
+                f,
+                "the camera named in the Exif data ({}), close to libjpeg quality {}",
+                name, closest
+            ),
+            QuantSource::Unknown { closest } => write!(
+                f,
+                "an unknown encoder, close to libjpeg quality {}",
+                closest
+            ),
+        }
+    }
+}
+
+/// Sums the differences of two tables, relative to the expected steps, so that the coarse
+/// high frequencies don't outweigh the fine low ones.
+fn table_distance(table: &[u16; 64], expected: &[u16; 64]) -> f64 {
+    table
+        .iter()
+        .zip(expected)
+        .map(|(&value, &expected)| {
+            (f64::from(value) - f64::from(expected)).abs() / f64::from(expected)
+        })
+        .sum()
+}
+
+/// Writes what `show-meta -t jpeg` tells of the quantization tables: each table as an 8x8
+/// grid in natural order, then the encoder they likely come from.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::jpeg::quant::{
+///     libjpeg_table, write_quant_report, EncoderHints, QuantTable, ANNEX_K_LUMINANCE,
+/// };
+///
+/// let tables = [QuantTable { id: 0, wide: false, values: libjpeg_table(&ANNEX_K_LUMINANCE, 75) }];
+/// let mut out = Vec::new();
+/// write_quant_report(&tables, &EncoderHints::default(), &mut out).unwrap();
+/// let out = String::from_utf8(out).unwrap();
+/// assert!(out.contains("Table 0 (8-bit)"));
+/// assert!(out.contains("   8   6   5   8  12  20  26  31"));
+/// assert!(out.contains("libjpeg (or a tool built on it) at quality 75"));
+/// ```
+pub fn write_quant_report<W: Write>(
+    tables: &[QuantTable],
+    hints: &EncoderHints,
+    out: &mut W,
+) -> io::Result<()> {
+    writeln!(out, "{}", Style::Green.paint("-- Quantization --"))?;
+    for table in tables {
+        writeln!(
+            out,
+            "Table {} ({}-bit)",
+            table.id,
+            if table.wide { 16 } else { 8 }
+        )?;
+        for row in table.values.chunks(8) {
+            let row: String = row.iter().map(|value| format!("{:>4}", value)).collect();
+            writeln!(out, "{}", row)?;
+        }
+    }
+    match QuantSource::identify(tables, hints) {
+        Some(source) => writeln!(out, "Likely encoder: {}", source)?,
+        None => writeln!(out, "No luminance table, the encoder can't be told.")?,
+    }
+    writeln!(out, "{}", Style::Green.paint("------ End ------"))
+}
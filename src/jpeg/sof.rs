@@ -1,5 +1,6 @@
 use crate::jpeg::obj::JpegObj;
 use crate::jpeg::writer::JpegWriter;
+use std::io;
 
 /// Represents the header for the Start of Frame (SOF) in a JPEG file.
 ///
@@ -47,6 +48,10 @@ impl SofHeader {
     ///
     /// * `writer` - A mutable reference to a `JpegWriter` trait object.
     ///
+    /// # Returns
+    ///
+    /// A `Result` with the error of the writer, if any.
+    ///
     /// # Examples
     ///
     /// ```
@@ -55,14 +60,14 @@ impl SofHeader {
     /// use std::io::BufWriter;
     /// use std::fs::File;
     ///
-    /// let output_file = File::create("temp.jpeg").unwrap();
+    /// let output_file = File::create(std::env::temp_dir().join("temp.jpeg")).unwrap();
     /// let mut writer = BufWriter::new(output_file);
     ///
     /// let jpeg_obj = JpegObj::default();
     /// let sof_header = SofHeader::new(jpeg_obj);
-    /// sof_header.write(&mut writer);
+    /// sof_header.write(&mut writer).unwrap();
     /// ```
-    pub fn write(&self, writer: &mut dyn JpegWriter) {
+    pub fn write(&self, writer: &mut dyn JpegWriter) -> io::Result<()> {
         let mut sof: Vec<u8> = vec![0xFF, 0xC0, 0x00, 17];
         sof.push(self.jpeg_obj.precision);
         sof.push((self.jpeg_obj.image_height >> 8) as u8);
@@ -75,6 +80,6 @@ impl SofHeader {
             sof.push((self.jpeg_obj.hsamp_factor[i] << 4) + self.jpeg_obj.vsamp_factor[i]);
             sof.push(self.jpeg_obj.qtable_number[i]);
         }
-        writer.write_array(&sof);
+        writer.write_array(&sof)
     }
 }
@@ -1,5 +1,6 @@
 use crate::jpeg::obj::JpegObj;
 use crate::jpeg::writer::JpegWriter;
+use std::io;
 
 /// Represents the Start of Scan (SOS) header in a JPEG file.
 ///
@@ -46,6 +47,10 @@ impl SosHeader {
     ///
     /// * `writer` - A mutable reference to a `JpegWriter` trait object.
     ///
+    /// # Returns
+    ///
+    /// A `Result` with the error of the writer, if any.
+    ///
     /// # Examples
     ///
     /// ```
@@ -54,14 +59,14 @@ impl SosHeader {
     /// use std::io::BufWriter;
     /// use std::fs::File;
     ///
-    /// let output_file = File::create("temp.jpeg").unwrap();
+    /// let output_file = File::create(std::env::temp_dir().join("temp.jpeg")).unwrap();
     /// let mut writer = BufWriter::new(output_file);
     ///
     /// let jpeg_obj = JpegObj::default();
     /// let sof_header = SosHeader::new(jpeg_obj);
-    /// sof_header.write(&mut writer);
+    /// sof_header.write(&mut writer).unwrap();
     /// ```
-    pub fn write(&self, writer: &mut dyn JpegWriter) {
+    pub fn write(&self, writer: &mut dyn JpegWriter) -> io::Result<()> {
         let mut sos: Vec<u8> = vec![0xFF, 0xDA, 0x00, 12];
         sos.push(self.jpeg_obj.number_of_components);
 
@@ -77,6 +82,6 @@ impl SosHeader {
         sos.push(self.jpeg_obj.ss);
         sos.push(self.jpeg_obj.se);
         sos.push((self.jpeg_obj.ah << 4) + self.jpeg_obj.al);
-        writer.write_array(&sos);
+        writer.write_array(&sos)
     }
 }
@@ -1,3 +1,4 @@
+use crate::io_ext::read_u16_be;
 use crate::jpeg::comment::CommentHeader;
 use crate::jpeg::dct::DctStruct;
 use crate::jpeg::dht::DhtHeader;
@@ -7,18 +8,15 @@ use crate::jpeg::huff::Huffman;
 use crate::jpeg::obj::JpegObj;
 use crate::jpeg::sof::SofHeader;
 use crate::jpeg::sos::SosHeader;
+use crate::outln;
+use crate::storage::map_input;
+use crate::style::Style;
 use std::error::Error;
-use std::fs::File;
 use std::io;
 use std::io::SeekFrom;
-use std::io::{BufReader, ErrorKind, Read, Seek};
+use std::io::{Cursor, ErrorKind, Read, Seek};
 
 // ANSI escape codes for text color
-const COLOR_RED: &str = "\x1b[91m";
-const COLOR_GREEN: &str = "\x1b[92m";
-const COLOR_YELLOW: &str = "\x1b[93m";
-const COLOR_RESET: &str = "\x1b[0m";
-
 type JpegHeadersResult = Result<
     (
         Option<JfifHeader>,
@@ -31,6 +29,38 @@ type JpegHeadersResult = Result<
     Box<dyn Error>,
 >;
 
+/// Reads the byte at `index` of a segment, failing instead of panicking when the segment is
+/// shorter than its fields say.
+fn byte_at(data: &[u8], index: usize, segment: &str) -> io::Result<u8> {
+    data.get(index).copied().ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Truncated {} segment: {} bytes, field at {}",
+                segment,
+                data.len(),
+                index
+            ),
+        )
+    })
+}
+
+/// Reads the length field of a marker segment and returns the number of bytes following it,
+/// failing when the declared length is shorter than `header`, the bytes it must at least
+/// cover.
+fn segment_len(reader: &mut dyn Read, segment: &str, header: usize) -> io::Result<usize> {
+    let declared = read_u16_be(reader)? as usize;
+    declared.checked_sub(header).ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{} segment length {} is shorter than its {}-byte header",
+                segment, declared, header
+            ),
+        )
+    })
+}
+
 /// Reads a 16-bit marker from the specified `Read` trait object.
 ///
 /// The `read_marker` function attempts to read a 16-bit marker from the provided `Read` trait object.
@@ -48,9 +78,8 @@ type JpegHeadersResult = Result<
 /// If the marker is successfully read, it is returned as a `u16` using big-endian byte order.
 /// If an error occurs during the read operation, the function returns an `io::Error`.
 ///
-/// If an unexpected end-of-file error occurs, a warning message is printed to stderr, and the
-/// function continues execution, returning a placeholder value of 0. You may choose to handle
-/// this case differently by modifying the returned value in the placeholder section.
+/// An unexpected end of file returns 0 instead, which `read_jpeg_headers` takes as the end of
+/// the headers.
 ///
 /// # Examples
 ///
@@ -71,15 +100,9 @@ type JpegHeadersResult = Result<
 /// }
 /// ```
 pub fn read_marker(reader: &mut dyn Read) -> io::Result<u16> {
-    let mut marker = [0u8; 2];
-
-    match reader.read_exact(&mut marker) {
-        Ok(_) => Ok(u16::from_be_bytes(marker)),
-        Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => {
-            // Print a message and continue with the loop
-            eprintln!("Warning: Unexpected end of file while reading marker. Continuing...");
-            Ok(0)
-        }
+    match read_u16_be(reader) {
+        Ok(marker) => Ok(marker),
+        Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => Ok(0),
         Err(e) => Err(e),
     }
 }
@@ -117,9 +140,10 @@ pub fn read_marker(reader: &mut dyn Read) -> io::Result<u16> {
 /// use stegano::jpeg::utils::read_jpeg_headers;
 /// use std::fs::File;
 ///
-/// let output_file = File::create("temp.jpeg").unwrap();
+/// let path = std::env::temp_dir().join("temp.jpeg");
+/// let output_file = File::create(&path).unwrap();
 ///
-/// match read_jpeg_headers("temp.jpeg", 0, 100, 10) {
+/// match read_jpeg_headers(path.to_str().unwrap(), 0, 100, 10) {
 ///     Ok((jfif, comment, dqt, sof, dht, sos)) => {
 ///         // Process the obtained headers as needed
 ///     }
@@ -134,8 +158,32 @@ pub fn read_jpeg_headers(
     end_chunk: usize,
     num_chunks: usize,
 ) -> JpegHeadersResult {
-    let file = File::open(file_path)?;
-    let mut reader = BufReader::new(file);
+    let file = map_input(file_path)?;
+    parse_jpeg_headers(&file, start_chunk, end_chunk, num_chunks)
+}
+
+/// Reads various JPEG headers from the bytes of a JPEG file, as `read_jpeg_headers` does for a
+/// path.
+///
+/// Declared segment lengths are checked against the data, so a truncated or crafted file is
+/// reported as an `InvalidData` or `UnexpectedEof` error instead of panicking.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::jpeg::utils::parse_jpeg_headers;
+///
+/// // A SOF segment declaring three components but holding one.
+/// let data = [0xFF, 0xC0, 0x00, 0x0B, 8, 0, 1, 0, 1, 3, 1, 0x11, 0];
+/// assert!(parse_jpeg_headers(&data, 0, 10, 10).is_err());
+/// ```
+pub fn parse_jpeg_headers(
+    data: &[u8],
+    start_chunk: usize,
+    end_chunk: usize,
+    num_chunks: usize,
+) -> JpegHeadersResult {
+    let mut reader = Cursor::new(data);
 
     let mut jfif_header = None;
     let mut dqt_header = None;
@@ -157,131 +205,122 @@ pub fn read_jpeg_headers(
         match marker {
             0xFFE0 => {
                 // JFIF Marker
-                let mut data_length_bytes = [0u8; 2];
-                reader.read_exact(&mut data_length_bytes)?;
-                let data_length = u16::from_be_bytes(data_length_bytes);
+                let data_length = read_u16_be(&mut reader)?;
                 let mut data = vec![0u8; data_length as usize + 2];
                 reader.read_exact(&mut data)?;
 
                 // Process data and store in the struct
-                jfif_header = Some(JfifHeader::new(&data).unwrap());
-                println!(
-                    "{}JFIF Header: {:?}{}",
-                    COLOR_GREEN,
-                    jfif_header.clone().unwrap(),
-                    COLOR_RESET
+                jfif_header = Some(
+                    JfifHeader::new(&data)
+                        .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?,
+                );
+                outln!(
+                    "{}",
+                    Style::Green.paint(format!("JFIF Header: {:?}", jfif_header.clone().unwrap()))
                 );
             }
             0xFFFE => {
                 // Comment Marker
-                let mut data_length_bytes = [0u8; 2];
-                reader.read_exact(&mut data_length_bytes)?;
-                let data_length = u16::from_be_bytes(data_length_bytes);
-                let mut data = vec![0u8; data_length as usize - 2];
+                let mut data = vec![0u8; segment_len(&mut reader, "COM", 2)?];
                 reader.read_exact(&mut data)?;
                 comment_data = Some(data);
-                println!(
-                    "{}Comment Header: {:?}{}",
-                    COLOR_YELLOW,
-                    comment_data.clone().unwrap(),
-                    COLOR_RESET
+                outln!(
+                    "{}",
+                    Style::Yellow.paint(format!(
+                        "Comment Header: {:?}",
+                        comment_data.clone().unwrap()
+                    ))
                 );
             }
             0xFFDB => {
                 // DQT Marker
-                let mut data_length_bytes = [0u8; 2];
-                reader.read_exact(&mut data_length_bytes)?;
-                let data_length = u16::from_be_bytes(data_length_bytes);
-                let mut data = vec![0u8; data_length as usize - 4];
+                let mut data = vec![0u8; segment_len(&mut reader, "DQT", 4)?];
                 reader.read_exact(&mut data)?;
 
                 // Process data and store in the struct
-                let dct_struct = DctStruct::new(&data).unwrap();
+                let dct_struct = DctStruct::new(&data)
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
                 dqt_header = Some(DqtHeader::new(dct_struct));
                 encountered_dqt = true;
-                println!(
-                    "{}DQT Header for Chunk#{}: {:?}{}",
-                    COLOR_GREEN,
-                    current_chunk,
-                    dqt_header.clone().unwrap(),
-                    COLOR_RESET
+                outln!(
+                    "{}",
+                    Style::Green.paint(format!(
+                        "DQT Header for Chunk#{}: {:?}",
+                        current_chunk,
+                        dqt_header.clone().unwrap()
+                    ))
                 );
             }
             0xFFC0 => {
                 // SOF Marker
-                let mut data_length_bytes = [0u8; 2];
-                reader.read_exact(&mut data_length_bytes)?;
-                let data_length = u16::from_be_bytes(data_length_bytes);
-                let mut data = vec![0u8; data_length as usize - 2];
+                let mut data = vec![0u8; segment_len(&mut reader, "SOF", 2)?];
                 reader.read_exact(&mut data)?;
 
                 // Process data and store in the struct
-                let jpeg_obj = process_sof_data(&data);
+                let jpeg_obj = process_sof_data(&data)?;
                 sof_header = Some(SofHeader::new(jpeg_obj));
                 image_width = sof_header.clone().unwrap().jpeg_obj.image_width;
                 image_height = sof_header.clone().unwrap().jpeg_obj.image_height;
-                println!(
-                    "{}SOF Header for Chunk#{}: {:?}{}",
-                    COLOR_YELLOW,
-                    current_chunk,
-                    sof_header.clone().unwrap(),
-                    COLOR_RESET
+                outln!(
+                    "{}",
+                    Style::Yellow.paint(format!(
+                        "SOF Header for Chunk#{}: {:?}",
+                        current_chunk,
+                        sof_header.clone().unwrap()
+                    ))
                 );
             }
             0xFFC4 => {
                 // DHT Marker
-                let mut data_length_bytes = [0u8; 2];
-                reader.read_exact(&mut data_length_bytes)?;
-                let data_length = u16::from_be_bytes(data_length_bytes);
-                let mut data = vec![0u8; data_length as usize - 2];
+                let mut data = vec![0u8; segment_len(&mut reader, "DHT", 2)?];
                 reader.read_exact(&mut data)?;
 
                 // Process data and store in the struct
-                let huf_struct = process_dht_data(&data);
+                let huf_struct = process_dht_data(&data)?;
                 dht_header = Some(DhtHeader::new(huf_struct));
-                println!(
-                    "{}Processing DHT Header for Chunk#{}: {}",
-                    COLOR_RED, current_chunk, COLOR_RESET
+                outln!(
+                    "{}",
+                    Style::Red.paint(format!(
+                        "Processing DHT Header for Chunk#{}: ",
+                        current_chunk
+                    ))
                 );
             }
             0xFFDA => {
                 // SOS Marker
-                let mut data_length_bytes = [0u8; 2];
-                reader.read_exact(&mut data_length_bytes)?;
-                let data_length = u16::from_be_bytes(data_length_bytes);
-                let mut data = vec![0u8; data_length as usize - 2];
+                let mut data = vec![0u8; segment_len(&mut reader, "SOS", 2)?];
                 reader.read_exact(&mut data)?;
 
                 // Process data and store in the struct
-                let jpeg_obj = process_sos_data(&data, image_height, image_width);
+                let jpeg_obj = process_sos_data(&data, image_height, image_width)?;
                 sos_header = Some(SosHeader::new(jpeg_obj));
-                println!(
-                    "{}SOS Header for Chunk#{}: {:?}{}",
-                    COLOR_GREEN,
-                    current_chunk,
-                    sos_header.clone().unwrap(),
-                    COLOR_RESET
+                outln!(
+                    "{}",
+                    Style::Green.paint(format!(
+                        "SOS Header for Chunk#{}: {:?}",
+                        current_chunk,
+                        sos_header.clone().unwrap()
+                    ))
                 );
             }
             0xFFD9 => {
                 // EOI Marker - End of Headers
-                println!(
-                    "{}End of Headers for Chunk {}{}",
-                    COLOR_RED, current_chunk, COLOR_RESET
+                outln!(
+                    "{}",
+                    Style::Red.paint(format!("End of Headers for Chunk {}", current_chunk))
                 );
                 break;
             }
             0 => {
                 // EOI Marker - End of Headers
-                println!(
-                    "{}End of Headers for Chunk {}{}",
-                    COLOR_RED, current_chunk, COLOR_RESET
+                outln!(
+                    "{}",
+                    Style::Red.paint(format!("End of Headers for Chunk {}", current_chunk))
                 );
 
                 break;
             }
             _ => {
-                // println!("{}Ignoring Marker {} for Chunk {}{}", COLOR_YELLOW, marker, current_chunk, COLOR_RESET);
                 // Ignore other markers
             }
         }
@@ -334,21 +373,27 @@ pub fn read_jpeg_headers(
 ///
 /// # Returns
 ///
-/// A `JpegObj` struct populated with the extracted information from the SOF data.
+/// A `Result` containing a `JpegObj` struct populated with the extracted information from the
+/// SOF data, or an `InvalidData` error if the segment is shorter than its component count says.
 ///
 /// # Examples
 ///
 /// ```
 /// use stegano::jpeg::utils::process_sof_data;
 ///
-/// let sof_data: [u8; 11] = [8, 0, 100, 200, 3, 1, 2, 3, 10, 20, 30];
-/// let jpeg_obj = process_sof_data(&sof_data);
+/// let sof_data: [u8; 15] = [8, 0, 100, 0, 200, 3, 1, 0x22, 0, 2, 0x11, 1, 3, 0x11, 1];
+/// let jpeg_obj = process_sof_data(&sof_data).unwrap();
+/// assert_eq!((jpeg_obj.image_width, jpeg_obj.comp_id), (200, vec![1, 2, 3]));
+///
+/// // Three components declared, one present.
+/// assert!(process_sof_data(&sof_data[..9]).is_err());
 /// ```
-pub fn process_sof_data(data: &[u8]) -> JpegObj {
-    let precision = data[0];
-    let image_height = u16::from_be_bytes([data[1], data[2]]);
-    let image_width = u16::from_be_bytes([data[3], data[4]]);
-    let number_of_components = data[5];
+pub fn process_sof_data(data: &[u8]) -> io::Result<JpegObj> {
+    let byte = |index| byte_at(data, index, "SOF");
+    let precision = byte(0)?;
+    let image_height = u16::from_be_bytes([byte(1)?, byte(2)?]);
+    let image_width = u16::from_be_bytes([byte(3)?, byte(4)?]);
+    let number_of_components = byte(5)?;
 
     let mut comp_id = Vec::with_capacity(number_of_components as usize);
     let mut hsamp_factor = Vec::with_capacity(number_of_components as usize);
@@ -358,12 +403,12 @@ pub fn process_sof_data(data: &[u8]) -> JpegObj {
     let mut index = 6;
 
     for _ in 0..number_of_components {
-        comp_id.push(data[index]);
+        comp_id.push(byte(index)?);
         index += 1;
-        hsamp_factor.push((data[index] >> 4) & 0xF);
-        vsamp_factor.push(data[index] & 0xF);
+        hsamp_factor.push((byte(index)? >> 4) & 0xF);
+        vsamp_factor.push(byte(index)? & 0xF);
         index += 1;
-        qtable_number.push(data[index]);
+        qtable_number.push(byte(index)?);
         index += 1;
     }
 
@@ -375,7 +420,7 @@ pub fn process_sof_data(data: &[u8]) -> JpegObj {
     let ah = 0x00; // Successive approximation bit position high
     let al = 0x00; // Successive approximation bit position low
 
-    JpegObj {
+    Ok(JpegObj {
         precision,
         image_height,
         image_width,
@@ -390,7 +435,7 @@ pub fn process_sof_data(data: &[u8]) -> JpegObj {
         se,
         ah,
         al,
-    }
+    })
 }
 
 /// Processes Define Huffman Table (DHT) data and populates a `Huffman` struct with the extracted information.
@@ -405,11 +450,15 @@ pub fn process_sof_data(data: &[u8]) -> JpegObj {
 ///
 /// # Returns
 ///
-/// A `Huffman` struct populated with the extracted information from the DHT data.
+/// A `Result` containing a `Huffman` struct populated with the extracted information from the
+/// DHT data, or an `InvalidData` error if it is shorter than its four-byte header.
 ///
 /// The `Huffman` struct represents a Huffman table used for entropy encoding in JPEG compression.
 /// It contains arrays for Huffman code lengths (`bits`) and the corresponding values (`val`).
 ///
+/// The tables are taken as they come: `codes::HuffmanTable::parse_segment` reads them with their
+/// counts checked against the segment, and `codes::HuffmanReport` checks them and the scans.
+///
 /// # Examples
 ///
 /// ```
@@ -420,11 +469,13 @@ pub fn process_sof_data(data: &[u8]) -> JpegObj {
 ///                           0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x10, 0x00, 0x02,
 ///                           0x01, 0x03, 0x03, 0x02, 0x04, 0x03, 0x05, 0x05, 0x04, 0x04, 0x04, 0x04, 0x00, 0x00];
 ///
-/// let huffman_table = process_dht_data(&dht_data);
+/// let huffman_table = process_dht_data(&dht_data).unwrap();
+/// assert!(process_dht_data(&dht_data[..3]).is_err());
 /// ```
-pub fn process_dht_data(data: &[u8]) -> Huffman {
-    let image_height = u16::from_be_bytes([data[0], data[1]]);
-    let image_width = u16::from_be_bytes([data[2], data[3]]);
+pub fn process_dht_data(data: &[u8]) -> io::Result<Huffman> {
+    let byte = |index| byte_at(data, index, "DHT");
+    let image_height = u16::from_be_bytes([byte(0)?, byte(1)?]);
+    let image_width = u16::from_be_bytes([byte(2)?, byte(3)?]);
 
     let mut huf_struct = Huffman::new(image_width as i32, image_height as i32);
 
@@ -438,10 +489,6 @@ pub fn process_dht_data(data: &[u8]) -> Huffman {
             index += 1;
         } else {
             // Handle the case where the index goes beyond the data length
-            // eprintln!(
-            //     "Warning: Index out of bounds when reading huf_struct.bits[{}].",
-            //     i
-            // );
             break;
         }
 
@@ -451,10 +498,6 @@ pub fn process_dht_data(data: &[u8]) -> Huffman {
                 index += 1;
             } else {
                 // Handle the case where the index goes beyond the data length
-                // eprintln!(
-                //     "Warning: Index out of bounds when reading huf_struct.bits[{}][{}].",
-                //     i, j
-                // );
                 break;
             }
         }
@@ -468,11 +511,6 @@ pub fn process_dht_data(data: &[u8]) -> Huffman {
                 .collect();
             huf_struct.val[i] = huf_vals;
             index += bytes;
-        } else {
-            // eprintln!(
-            //     "Warning: Index out of bounds when reading huf_vals for huf_struct.val[{}].",
-            //     i
-            // );
         }
 
         let mut dht3 = vec![0xFF, 0xC4];
@@ -481,7 +519,7 @@ pub fn process_dht_data(data: &[u8]) -> Huffman {
         huf_struct.bits[i][2] = ((index - 2) >> 8) as i32;
         huf_struct.bits[i][3] = (index - 2) as i32;
     }
-    huf_struct
+    Ok(huf_struct)
 }
 
 /// Processes Start of Scan (SOS) data and populates a `JpegObj` struct with the extracted information.
@@ -497,7 +535,8 @@ pub fn process_dht_data(data: &[u8]) -> Huffman {
 ///
 /// # Returns
 ///
-/// A `JpegObj` struct populated with the extracted information from the SOS data.
+/// A `Result` containing a `JpegObj` struct populated with the extracted information from the
+/// SOS data, or an `InvalidData` error if it is shorter than its six-byte header.
 ///
 /// The `JpegObj` struct represents a set of parameters related to a JPEG image. It includes
 /// information such as precision, image dimensions, number of components, component IDs, Huffman
@@ -509,11 +548,13 @@ pub fn process_dht_data(data: &[u8]) -> Huffman {
 /// use stegano::jpeg::utils::process_sos_data;
 ///
 /// let sos_data: [u8; 15] = [8, 0, 100, 200, 3, 1, 1, 0x11, 0x00, 0x2C, 0x00, 0x00, 0x00, 0x00, 0x00];
-/// let jpeg_obj = process_sos_data(&sos_data, 10, 10);
+/// let jpeg_obj = process_sos_data(&sos_data, 10, 10).unwrap();
+/// assert!(process_sos_data(&sos_data[..4], 10, 10).is_err());
 /// ```
-pub fn process_sos_data(data: &[u8], image_height: u16, image_width: u16) -> JpegObj {
-    let precision = data[0];
-    let number_of_components = data[5];
+pub fn process_sos_data(data: &[u8], image_height: u16, image_width: u16) -> io::Result<JpegObj> {
+    let byte = |index| byte_at(data, index, "SOS");
+    let precision = byte(0)?;
+    let number_of_components = byte(5)?;
 
     let mut comp_id = Vec::with_capacity(number_of_components as usize);
     let mut dc_table_number = Vec::with_capacity(number_of_components as usize);
@@ -525,7 +566,7 @@ pub fn process_sos_data(data: &[u8], image_height: u16, image_width: u16) -> Jpe
     let mut index = 6;
 
     for _ in 0..number_of_components {
-        if index + 1 < data.len() {
+        if index + 3 < data.len() {
             comp_id.push(data[index]);
             index += 1;
 
@@ -539,7 +580,6 @@ pub fn process_sos_data(data: &[u8], image_height: u16, image_width: u16) -> Jpe
 
             index += 2;
         } else {
-            // eprintln!("Warning: Index out of bounds when reading SOS component data.");
             break;
         }
     }
@@ -558,7 +598,7 @@ pub fn process_sos_data(data: &[u8], image_height: u16, image_width: u16) -> Jpe
     let ah = (ah_al >> 4) & 0xF;
     let al = ah_al & 0xF;
 
-    JpegObj {
+    Ok(JpegObj {
         precision,
         image_height,
         image_width,
@@ -573,5 +613,5 @@ pub fn process_sos_data(data: &[u8], image_height: u16, image_width: u16) -> Jpe
         se,
         ah,
         al,
-    }
+    })
 }
@@ -1,4 +1,4 @@
-use std::io::Write;
+use std::io::{self, ErrorKind, Write};
 
 /// Trait for writing JPEG markers and arrays to a writer.
 ///
@@ -10,14 +10,19 @@ pub trait JpegWriter {
     /// # Arguments
     ///
     /// * `data` - A reference to a byte slice containing the marker data.
-    fn write_marker(&mut self, data: &[u8]);
+    fn write_marker(&mut self, data: &[u8]) -> io::Result<()>;
 
     /// Writes a JPEG array to the writer.
     ///
     /// # Arguments
     ///
     /// * `data` - A reference to a byte slice containing the array data.
-    fn write_array(&mut self, data: &[u8]);
+    ///
+    /// # Returns
+    ///
+    /// A `Result` with the error of the writer, or an `InvalidInput` error if `data` is too
+    /// short to hold a marker and a length.
+    fn write_array(&mut self, data: &[u8]) -> io::Result<()>;
 }
 
 /// Implements the `JpegWriter` trait for any type that implements the `std::io::Write` trait.
@@ -30,8 +35,8 @@ impl<W: Write> JpegWriter for W {
     /// # Arguments
     ///
     /// * `data` - A reference to a byte slice containing the marker data.
-    fn write_marker(&mut self, data: &[u8]) {
-        self.write_all(data).expect("IO Error");
+    fn write_marker(&mut self, data: &[u8]) -> io::Result<()> {
+        self.write_all(data)
     }
 
     /// Writes a JPEG array to the writer.
@@ -39,15 +44,8 @@ impl<W: Write> JpegWriter for W {
     /// # Arguments
     ///
     /// * `data` - A reference to a byte slice containing the array data.
-    fn write_array(&mut self, data: &[u8]) {
-        if data.len() >= 4 {
-            let length = ((data[2] as usize) << 8) + (data[3] as usize) + 2;
-            self.write_all(&data[..length.min(data.len())])
-                .expect("IO Error");
-        } else {
-            // Handle the case where the slice is too short
-            eprintln!("Error: Data slice is too short in write_array.");
-        }
+    fn write_array(&mut self, data: &[u8]) -> io::Result<()> {
+        self.write_all(segment(data)?)
     }
 }
 
@@ -61,8 +59,8 @@ impl JpegWriter for dyn std::io::Write {
     /// # Arguments
     ///
     /// * `data` - A reference to a byte slice containing the marker data.
-    fn write_marker(&mut self, data: &[u8]) {
-        self.write_all(data).expect("IO Error");
+    fn write_marker(&mut self, data: &[u8]) -> io::Result<()> {
+        self.write_all(data)
     }
 
     /// Writes a JPEG array to the writer.
@@ -70,8 +68,24 @@ impl JpegWriter for dyn std::io::Write {
     /// # Arguments
     ///
     /// * `data` - A reference to a byte slice containing the array data.
-    fn write_array(&mut self, data: &[u8]) {
-        let length = ((data[2] as usize) << 8) + (data[3] as usize) + 2;
-        self.write_all(&data[..length]).expect("IO Error");
+    fn write_array(&mut self, data: &[u8]) -> io::Result<()> {
+        self.write_all(segment(data)?)
+    }
+}
+
+/// Returns the bytes of a segment up to the length it declares.
+///
+/// # Returns
+///
+/// A `Result` containing the bytes, or an `InvalidInput` error if the slice is too short to
+/// hold a marker and a length.
+fn segment(data: &[u8]) -> io::Result<&[u8]> {
+    if data.len() < 4 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The JPEG segment is too short to hold a marker and a length",
+        ));
     }
+    let length = ((data[2] as usize) << 8) + (data[3] as usize) + 2;
+    Ok(&data[..length.min(data.len())])
 }
@@ -0,0 +1,158 @@
+use std::io::{self, ErrorKind};
+
+/// The service name keys are stored under in the OS keychain.
+pub const SERVICE: &str = "stegano";
+
+/// The environment variable `encrypt` and `decrypt` read the key from when `-k` isn't given.
+pub const KEY_ENV: &str = "STEGANO_KEY";
+
+/// Reads the key stored under `id` in the OS keychain, for `--key-id`.
+///
+/// Keys live in the macOS Keychain, the Windows Credential Manager or the Secret Service on
+/// Linux, under the `stegano` service, when the `keychain` feature is enabled.
+///
+/// # Returns
+///
+/// A `Result` containing the key, an `InvalidInput` error if nothing is stored under `id`, or
+/// an `Unsupported` error without the `keychain` feature.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use stegano::keychain::fetch_key;
+///
+/// let result = fetch_key("stegano-doc-missing");
+/// if !cfg!(feature = "keychain") {
+///     assert_eq!(result.unwrap_err().kind(), ErrorKind::Unsupported);
+/// }
+/// ```
+pub fn fetch_key(id: &str) -> io::Result<String> {
+    #[cfg(feature = "keychain")]
+    {
+        entry(id)?.get_password().map_err(|err| match err {
+            keyring::Error::NoEntry => io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "No key is stored under {:?} in the keychain, store one with -k <key> --key-id {}",
+                    id, id
+                ),
+            ),
+            err => keychain_error(err),
+        })
+    }
+    #[cfg(not(feature = "keychain"))]
+    Err(unsupported(id))
+}
+
+/// Stores `key` under `id` in the OS keychain, replacing any key stored there before.
+///
+/// # Returns
+///
+/// A `Result` that is an `Unsupported` error without the `keychain` feature.
+pub fn store_key(id: &str, key: &str) -> io::Result<()> {
+    #[cfg(feature = "keychain")]
+    {
+        entry(id)?.set_password(key).map_err(keychain_error)
+    }
+    #[cfg(not(feature = "keychain"))]
+    {
+        let _ = key;
+        Err(unsupported(id))
+    }
+}
+
+/// Opens the keychain entry of `id`.
+#[cfg(feature = "keychain")]
+fn entry(id: &str) -> io::Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, id).map_err(keychain_error)
+}
+
+/// Turns a failure of the keychain backend, such as a locked or missing keychain, into an
+/// `io::Error`.
+#[cfg(feature = "keychain")]
+fn keychain_error(err: keyring::Error) -> io::Error {
+    let kind = match err {
+        keyring::Error::Invalid(..) | keyring::Error::TooLong(..) => ErrorKind::InvalidInput,
+        _ => ErrorKind::Other,
+    };
+    io::Error::new(kind, format!("The keychain failed: {}", err))
+}
+
+/// Builds the error returned by builds without the `keychain` feature.
+#[cfg(not(feature = "keychain"))]
+fn unsupported(id: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::Unsupported,
+        format!(
+            "--key-id {} needs the OS keychain, which this build doesn't include; rebuild with --features keychain",
+            id
+        ),
+    )
+}
+
+/// Reads a key from an open file descriptor, for `--key-fd`.
+///
+/// Orchestration systems hand secrets over this way, such as a pipe from a parent process or
+/// a systemd credential, without the key showing up in the process arguments. The descriptor
+/// is read to its end, and one trailing line break is dropped.
+///
+/// # Returns
+///
+/// A `Result` containing the key, an `InvalidInput` error if the descriptor isn't open or
+/// holds no UTF-8 key, or an `Unsupported` error on platforms without `/dev/fd`.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use stegano::keychain::read_key_fd;
+///
+/// // Nothing is open that far up.
+/// let err = read_key_fd(987_654).unwrap_err();
+/// assert!(matches!(err.kind(), ErrorKind::InvalidInput | ErrorKind::Unsupported));
+/// ```
+pub fn read_key_fd(fd: u32) -> io::Result<String> {
+    #[cfg(unix)]
+    {
+        use std::io::Read;
+
+        // Going through /dev/fd keeps the descriptor of the process open and needs no unsafe
+        // code to own it.
+        let mut file = std::fs::File::open(format!("/dev/fd/{}", fd)).map_err(|err| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Can't read the key from file descriptor {}: {}", fd, err),
+            )
+        })?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        let mut key = String::from_utf8(data).map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("The key read from file descriptor {} isn't UTF-8", fd),
+            )
+        })?;
+        if key.ends_with('\n') {
+            key.pop();
+            if key.ends_with('\r') {
+                key.pop();
+            }
+        }
+        if key.is_empty() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("File descriptor {} holds no key", fd),
+            ));
+        }
+        Ok(key)
+    }
+    #[cfg(not(unix))]
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        format!(
+            "--key-fd {} isn't supported on this platform, use STEGANO_KEY instead",
+            fd
+        ),
+    ))
+}
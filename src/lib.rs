@@ -18,88 +18,79 @@
 //! 1. Read and process 10 chunks from a png image:
 //!
 //!    ```bash
-//!    $ stegano show-meta -i image_file_name -n 10
+//!    $ stegano show-meta -i image_file_name -n 10 --hex-limit 40
 //!    It is a valid PNG file. Let's process it!
 //!
 //!    ---- Header ----
 //!    00000000 | 89 50 4E 47 0D 0A 1A 0A | .PNG....
-//!    ---- End ----
+//!    ----- End ------
+//!
+//!    ----- IHDR -----
+//!    Width: 16
+//!    Height: 16
+//!    Bit depth: 8
+//!    Colour type: 6 (truecolour with alpha)
+//!    Compression: 0 (deflate)
+//!    Filter: 0 (adaptive)
+//!    Interlace: 0 (none)
+//!    ----- End ------
 //!
 //!    ---- Chunk #0 ----
-//!    Offset: 13
-//!    Size: 68
-//!    CRC: fa130394
-//!    00000013 | 00 0A 36 08 06 00 00 00 AF 77 9F 68 00 00 00 01 73 52 47 42 | ..6......w.h....sRGB
-//!    00000033 | 00 AE CE 1C E9 00 00 20 00 49 44 41 54 78 5E EC DD 75 BC 6F | .........IDATx^..u.o
-//!    00000053 | 55 9D FF F1 CF 39 B7 83 1B 70 69 91 54 50 50 46 09 51 41 C5 | U....9...pi.TPPF.QA.
-//!    00000073 | 8E F9 19 94 98 E0 08 12 | ........
-//!    ------ End ------
+//!    Offset: 8
+//!    Type: IHDR
+//!    Name: Image header (critical, public)
+//!    Size: 13
+//!    CRC: 1ff3ff61
+//!    00000016 | 00 00 00 10 00 00 00 10 08 06 00 00 00 | .............
+//!    ------- End -------
 //!
 //!    ---- Chunk #1 ----
-//!    Offset: 98
-//!    Size: 70
-//!    CRC: 80000208
-//!    00000098 | 42 74 08 09 0B C5 E2 27 17 A5 E3 22 48 DE E4 C6 39 BF C7 DA | Bt.....'..."H...9...
-//!    00000118 | DF DA B1 F6 5E B9 BF 75 5E E7 9F E1 9E EF CA E7 5E FB 7B F9 | ....^..u^.......^.{.
-//!    00000138 | F1 7B FB 59 23 97 5E 7D E3 B8 F0 13 4D 60 7C 1C CE 68 98 0C | .{.Y#.^}....M`|..h..
-//!    00000158 | 84 00 02 08 20 80 00 02 08 20 | ..........
-//!    ------ End ------
+//!    Offset: 33
+//!    Type: sRGB
+//!    Name: Standard RGB colour space (ancillary, public)
+//!    Size: 1
+//!    CRC: aece1ce9
+//!    00000041 | 00 | .
+//!    ------- End -------
 //!
 //!    ---- Chunk #2 ----
-//!    Offset: 185
-//!    Size: 2
-//!    CRC: 20800002
-//!    00000185 | 02 08 | ..
-//!    ------ End ------
+//!    Offset: 46
+//!    Type: gAMA
+//!    Name: Gamma (ancillary, public)
+//!    Size: 4
+//!    CRC: bfc6105
+//!    00000054 | 00 00 B1 8F | ....
+//!    ------- End -------
 //!
 //!    ---- Chunk #3 ----
-//!    Offset: 204
-//!    Size: 8
-//!    CRC: 460800c6
-//!    00000204 | 32 32 32 54 FB E9 F5 66 | 222T...f
-//!    ------ End ------
+//!    Offset: 62
+//!    Type: pHYs
+//!    Name: Physical pixel dimensions (ancillary, public, safe to copy)
+//!    Size: 9
+//!    CRC: c76fa864
+//!    00000070 | 00 00 0E C3 00 00 0E C3 01 | .........
+//!    ------- End -------
 //!
 //!    ---- Chunk #4 ----
-//!    Offset: 229
-//!    Size: 2
-//!    CRC: 20800002
-//!    00000229 | 02 08 | ..
-//!    ------ End ------
+//!    Offset: 83
+//!    Type: IDAT
+//!    Name: Image data (critical, public)
+//!    Size: 295
+//!    CRC: 16f6155b
+//!    00000091 | 78 DA 15 CC C1 80 45 21 00 00 C0 87 F0 11 42 08 21 84 10 42 | x.....E!......B.!..B
+//!    00000111 | 08 21 84 10 42 08 21 84 10 42 C8 60 77 3A CC 75 BE EF 6B 7F | .!..B.!..B.`w:.u..k.
+//!    ... 255 more bytes of 295 not shown
+//!    ------- End -------
 //!
 //!    ---- Chunk #5 ----
-//!    Offset: 248
-//!    Size: 8
-//!    CRC: 20800002
-//!    00000248 | 00 02 08 20 80 00 02 08 | ........
-//!    ------ End ------
-//!
-//!    ---- Chunk #6 ----
-//!    Offset: 273
-//!    Size: 8
-//!    CRC: 20190601
-//!    00000273 | 95 00 60 24 4B C2 7F 91 | ..`$K...
-//!    ------ End ------
-//!
-//!    ---- Chunk #7 ----
-//!    Offset: 298
-//!    Size: 4
-//!    CRC: 40000104
-//!    00000298 | 00 01 04 10 | ....
-//!    ------ End ------
-//!
-//!    ---- Chunk #8 ----
-//!    Offset: 319
-//!    Size: 1
-//!    CRC: 4104000
-//!    00000319 | 01 | .
-//!    ------ End ------
-//!
-//!    ---- Chunk #9 ----
-//!    Offset: 337
-//!    Size: 16
-//!    CRC: 20820
-//!    00000337 | 23 E1 BF 08 88 A6 21 B8 5A D9 24 C4 E7 08 20 80 | #.....!.Z.$.....
-//!    ------ End ------
+//!    Offset: 390
+//!    Type: IEND
+//!    Name: Image trailer (critical, public)
+//!    Size: 0
+//!    CRC: ae426082
+//!    ------- End -------
+//!
+//!    Summary: 6 chunks, 0 unknown types, 0 CRC errors
 //!    ```
 //!
 //! 2. Process the png image in silent mode:
@@ -118,6 +109,22 @@
 //!    Processing DHT Header for Chunk#70:
 //!    Processing DHT Header for Chunk#71:
 //!    SOS Header for Chunk#72: SosHeader { jpeg_obj: JpegObj { precision: 3, image_height: 243, image_width: 207, number_of_components: 3, comp_id: [17], hsamp_factor: [3], vsamp_factor: [15], qtable_number: [0], dctable_number: [0], actable_number: [0], ss: 0, se: 0, ah: 0, al: 0 } }
+//!    ---- Markers ----
+//!    Offset     Marker  Length  Class
+//!    0          FFD8         -  SOI
+//!    2          FFE0        16  APP0/JFIF
+//!    20         FFE1       132  APP1/Exif
+//!    154        FFE1      2797  APP1/XMP
+//!    2953       FFED        56  APP13/IPTC
+//!    3011       FFC0        17  SOF0/Baseline
+//!    3030       FFC4        31  DHT
+//!    3462       FFDB        67  DQT
+//!    3600       FFDD         4  DRI
+//!    3606       FFDA        12  SOS + 1313 bytes of scan data
+//!    4933       FFD0         -  RST0 + 1429 bytes of scan data
+//!    ...
+//!    100959     FFD9         -  EOI
+//!    ------ End ------
 //!    ```
 //!
 //!    Sometimes the `JFIF` header doesn't exist, but it is still a valid jpeg file:
@@ -130,61 +137,44 @@
 //!    SOS Header for Chunk#5: SosHeader { jpeg_obj: JpegObj { precision: 3, image_height: 460, image_width: 460, number_of_components: 3, comp_id: [17], hsamp_factor: [3], vsamp_factor: [15], qtable_number: [0], dctable_number: [0], actable_number: [0], ss: 0, se: 0, ah: 0, al: 0 } }
 //!    ```
 //!
-//! 4. Read chunks at different positions:
+//! 4. Read chunks at different positions, counted from 0 after the signature:
 //!
 //!    ```bash
-//!    # Read 1 chunk starting from position 0
-//!    $ stegano show-meta -i image_file_name -s 0 -e 10 -n 1
-//!    It is a valid PNG file. Let's process it!
-//!
-//!    ---- Header ----
-//!    00000000 | 89 50 4E 47 0D 0A 1A 0A | .PNG....
-//!    ---- End ----
-//!
-//!    ---- Chunk #0 ----
-//!    Offset: 13
-//!    Size: 68
-//!    CRC: fa130394
-//!    00000013 | 00 0A 36 08 06 00 00 00 AF 77 9F 68 00 00 00 01 73 52 47 42 | ..6......w.h....sRGB
-//!    00000033 | 00 AE CE 1C E9 00 00 20 00 49 44 41 54 78 5E EC DD 75 BC 6F | .........IDATx^..u.o
-//!    00000053 | 55 9D FF F1 CF 39 B7 83 1B 70 69 91 54 50 50 46 09 51 41 C5 | U....9...pi.TPPF.QA.
-//!    00000073 | 8E F9 19 94 98 E0 08 12 | ........
-//!    ------ End ------
-//!
-//!    # Read 3 chunks starting from position 10000
-//!    $ stegano show-meta -i image_file_name -s 10000 -e 200000 -n 3
-//!    It is a valid PNG file. Let's process it!
-//!
-//!    ---- Header ----
-//!    00000000 | 89 50 4E 47 0D 0A 1A 0A | .PNG....
-//!    ---- End ----
-//!
-//!    ---- Chunk #10000 ----
-//!    Offset: 13
-//!    Size: 68
-//!    CRC: fa130394
-//!    00000013 | 00 0A 36 08 06 00 00 00 AF 77 9F 68 00 00 00 01 73 52 47 42 | ..6......w.h....sRGB
-//!    00000033 | 00 AE CE 1C E9 00 00 20 00 49 44 41 54 78 5E EC DD 75 BC 6F | .........IDATx^..u.o
-//!    00000053 | 55 9D FF F1 CF 39 B7 83 1B 70 69 91 54 50 50 46 09 51 41 C5 | U....9...pi.TPPF.QA.
-//!    00000073 | 8E F9 19 94 98 E0 08 12 | ........
-//!    ------ End ------
-//!
-//!    ---- Chunk #10001 ----
-//!    Offset: 98
-//!    Size: 70
-//!    CRC: 80000208
-//!    00000098 | 42 74 08 09 0B C5 E2 27 17 A5 E3 22 48 DE E4 C6 39 BF C7 DA | Bt.....'..."H...9...
-//!    00000118 | DF DA B1 F6 5E B9 BF 75 5E E7 9F E1 9E EF CA E7 5E FB 7B F9 | ....^..u^.......^.{.
-//!    00000138 | F1 7B FB 59 23 97 5E 7D E3 B8 F0 13 4D 60 7C 1C CE 68 98 0C | .{.Y#.^}....M`|..h..
-//!    00000158 | 84 00 02 08 20 80 00 02 08 20 | ..........
-//!    ------ End ------
+//!    # Read 2 chunks starting from chunk #3
+//!    $ stegano show-meta -i image_file_name -s 3 -n 2 --hex-limit 40
+//!    ---- Chunk #3 ----
+//!    Offset: 62
+//!    Type: pHYs
+//!    Name: Physical pixel dimensions (ancillary, public, safe to copy)
+//!    Size: 9
+//!    CRC: c76fa864
+//!    00000070 | 00 00 0E C3 00 00 0E C3 01 | .........
+//!    ------- End -------
 //!
-//!    ---- Chunk #10002 ----
-//!    Offset: 185
-//!    Size: 2
-//!    CRC: 20800002
-//!    00000185 | 02 08 | ..
-//!    ------ End ------
+//!    ---- Chunk #4 ----
+//!    Offset: 83
+//!    Type: IDAT
+//!    Name: Image data (critical, public)
+//!    Size: 295
+//!    CRC: 16f6155b
+//!    00000091 | 78 DA 15 CC C1 80 45 21 00 00 C0 87 F0 11 42 08 21 84 10 42 | x.....E!......B.!..B
+//!    00000111 | 08 21 84 10 42 08 21 84 10 42 C8 60 77 3A CC 75 BE EF 6B 7F | .!..B.!..B.`w:.u..k.
+//!    ... 255 more bytes of 295 not shown
+//!    ------- End -------
+//!
+//!    Summary: 6 chunks, 0 unknown types, 0 CRC errors
+//!
+//!    # Read the last chunk
+//!    $ stegano show-meta -i image_file_name -z -n 1
+//!    ---- Chunk #5 ----
+//!    Offset: 390
+//!    Type: IEND
+//!    Name: Image trailer (critical, public)
+//!    Size: 0
+//!    CRC: ae426082
+//!    ------- End -------
+//!
+//!    Summary: 6 chunks, 0 unknown types, 0 CRC errors
 //!    ```
 //!
 //! 5. Encrypt an inject data in an image:
@@ -201,11 +191,23 @@
 //!    Your decoded secret is: "hello"
 //!    ```
 //!
+//!    `decrypt` is deprecated: `extract` only reveals the secret and `remove` (or `restore`)
+//!    only writes the image without it.
+//!
+//!    ```bash
+//!    $ stegano extract -i input_image_file_name -k 'pass' -f 159028 -s
+//!    Your decoded secret is: "hello"
+//!    $ stegano remove -i input_image_file_name -k 'pass' -f 159028 -o output_image_file_name
+//!    The carrier without its payload was written to output_image_file_name
+//!    ```
+//!
 //! 7. if wrong key is provided:
 //!
 //!    ```bash
 //!    $ stegano decrypt -i input_image_file_name -k 'invalid' -f 159028 -o output_image_file_name
-//!    Your decoded secret is: "qji~s"
+//!    Error: Incorrect key or corrupted payload: the payload header doesn't match the key
+//!    $ echo $?
+//!    6
 //!    ```
 //!
 //! 8. if wrong key and wrong offset are provided:
@@ -255,24 +257,83 @@
 //! | Option                  | Description                                               |
 //! |-------------------------|-----------------------------------------------------------|
 //! | **Encryption Options**  |                                                           |
-//! | `-a` or `--algo`        | Sets the algorithm for encryption (default is "aes").        |
-//! | `-i` or `--input`       | Sets the input file for encryption.                        |
-//! | `-o` or `--output`      | Sets the output file for the encrypted payload (default is "output.png").|
-//! | `-k` or `--key`         | Sets the key for payload encryption (default is "key").    |
+//! | `-a` or `--algo`        | Sets the algorithm for encryption: aes (AES-128), aes192, aes256, chacha (ChaCha20), xor, age or pgp (default is "aes"). |
+//! | `--recipient`           | Encrypts the payload to an age public key (`age1...`) or an OpenPGP certificate file instead of the key, implying `--algo age` or `pgp` (`age` and `pgp` features). |
+//! | `--block-mode`          | Sets the AES block mode: ctr (default), cbc, or ecb; the random IV goes in the payload header. |
+//! | `-i` or `--input`       | Sets the input file for encryption, or an `http(s)://` or `s3://` URL (`http` feature). |
+//! | `-o` or `--output`      | Sets the output file for the encrypted payload, or a URL to upload it to (default is "output.png").|
+//! | `--in-place`            | Rewrites the input instead of writing `-o`, atomically and keeping its permissions. |
+//! | `--keep-mtime`          | Keeps the modification time of the input rewritten with `--in-place`. |
+//! | `--preserve-times`      | Gives the output the access and modification times of the carrier. |
+//! | `--preserve-metadata`   | Writes nothing unless every ancillary PNG chunk of the carrier (gAMA, pHYs, iCCP...) is kept byte for byte. |
+//! | `--dry-run`             | Runs every check and prints what would be written without writing anything. |
+//! | `-k` or `--key`         | Sets the key for payload encryption (default is "key", or `STEGANO_KEY` if it is set). |
+//! | `--key-fd`              | Reads the key from an open file descriptor, such as `0` for a pipe, instead of `-k`. |
+//! | `--key-id`              | Reads the key from the OS keychain entry of this name, or stores the `-k` key there (`keychain` feature). |
 //! | `-s` or `--suppress`    | Suppresses output messages.                                |
-//! | `-f` or `--offset`      | Sets the offset (default is 10).                           |
+//! | `-f` or `--offset`      | Sets where the payload chunk goes: `auto` (default, before IEND), `end-N`, `after:TYPE`, `before:TYPE` or an absolute offset. |
 //! | `-p` or `--payload`     | Sets the payload (default is "hello").                     |
-//! | `-t` or `--type`        | Sets the type (default is "PNG").                          |
+//! | `-t` or `--type`        | Sets the type, or "auto" to tell it from the magic bytes (default is "auto"). |
+//! | `-m` or `--method`      | Sets the embedding method (default is "auto").             |
+//! | `-c` or `--chunk-name`  | Sets the PNG chunk holding the payload (default is "stEg"). |
+//! | `--payload-file`        | Reads the payload from a file instead of `-p`.             |
+//! | `--payload-qr`          | Reads the payload from a QR code in a PNG image, such as a photo of one. |
+//! | `--payload-encoding`    | Decodes the `-p` or `--payload-qr` text from base64 or hex (default is "raw"). |
+//! | `--max-memory`          | Caps in-memory payload processing (e.g. "512M").           |
+//! | `--provenance`          | Links the new frame to the input carrier's digest (append method). |
+//! | `--json`                | Prints the result as JSON.                                 |
+//! | `--explain`             | Prints what will be done and why first (every subcommand). |
+//! | `--format`              | Sets the output format: auto (human on a terminal, JSON when piped), human or json. |
+//! | `--split-across`        | Shards the payload across several carriers instead of `-i`. |
+//! | `--shamir`              | Writes K-of-N Shamir shares to the `--split-across` carriers instead of shards. |
+//! | `--hint`                | Stores a plaintext hint next to the payload (readable by anyone). |
+//! | `--descriptor`          | Also writes a sealed recovery descriptor of the payload location to this file. |
+//! | `--mime-type`           | Records the MIME type of the payload in its plaintext header. |
+//! | `--nest`                | Records the type of a stego `--payload-file` so that `decrypt --depth` unwraps it. |
+//! | `--filename`            | Records the filename of the payload in its plaintext header. |
+//! | `--expires`             | Records an expiry (`YYYY-MM-DD` or RFC 3339) in the signed header; decrypt refuses the payload after it. |
+//! | `--views`               | Records how many times `decrypt --burn` may reveal the payload before wiping it (experimental). |
+//! | `--robust`              | Embeds with the robust method, the same as `-m robust`.    |
+//! | `--disguise`            | Disguises the payload as an ICC profile, XMP packet or EXIF maker note (icc, xmp or makernote), the same as `-m disguise`. |
+//! | `--traversal`           | Sets the order the lsb method writes the payload bits in: scatter (the default, drawn from the key), row-major, column-major, zigzag or hilbert. |
+//! | `--channels`            | Sets the channels the lsb method writes to, as letters among r, g, b and a (default is "rgb"); `decrypt` and `rekey` need the same. |
+//! | `--bits-per-channel`    | Sets how many low bits of each sample the lsb method takes, 1 to 4 (default is the fewest the payload fits in). |
+//! | `--max-distortion`      | Writes nothing if the lsb or robust methods drop the image below a PSNR (`40dB`) or SSIM (`ssim:0.99`). |
+//! | `--deinterlace`         | Writes the lsb and robust outputs without interlacing; interlaced carriers stay interlaced otherwise. |
+//! | `--strict`              | Rejects carriers that don't follow the specification of their format. |
 //! |                         |                                                           |
 //! | **Decryption Options**  |                                                           |
 //! | `-a` or `--algo`        | Sets the algorithm for decryption (default is "aes").        |
-//! | `-i` or `--input`       | Sets the input file for decryption.                        |
-//! | `-o` or `--output`      | Sets the output file for the decrypted payload (default is "output.png").|
-//! | `-k` or `--key`         | Sets the key for payload decryption (default is "key").    |
+//! | `--identity`            | Opens payloads encrypted to age or OpenPGP recipients with this age identity file or OpenPGP secret key. |
+//! | `-i` or `--input`       | Sets the input file for decryption, or an `http(s)://` or `s3://` URL (`http` feature). |
+//! | `-o` or `--output`      | Sets the output file for the carrier without the payload, for `remove` and `decrypt` (default is "output.png").|
+//! | `--in-place`            | Rewrites the input instead of writing `-o`, atomically and keeping its permissions. |
+//! | `--keep-mtime`          | Keeps the modification time of the input rewritten with `--in-place`. |
+//! | `-k` or `--key`         | Sets the key for payload decryption (default is "key", or `STEGANO_KEY` if it is set). |
+//! | `--key-fd`              | Reads the key from an open file descriptor, such as `0` for a pipe, instead of `-k`. |
+//! | `--key-id`              | Reads the key from the OS keychain entry of this name, or stores the `-k` key there (`keychain` feature). |
 //! | `-s` or `--suppress`    | Suppresses output messages.                                |
-//! | `-f` or `--offset`      | Sets the offset (default is 10).                           |
-//! | `-p` or `--payload`     | Sets the payload (default is "hello").                     |
-//! | `-t` or `--type`        | Sets the type (default is "PNG").                          |
+//! | `-f` or `--offset`      | Sets where to look for the payload chunk: `auto` (default, by chunk name), `end-N`, `after:TYPE`, `before:TYPE` or an absolute offset. |
+//! | `--output-payload-file` | Writes the secret to this file, byte for byte, instead of printing it. |
+//! | `-t` or `--type`        | Sets the type, or "auto" to tell it from the magic bytes (default is "auto"). |
+//! | `-m` or `--method`      | Sets the method the payload was embedded with (default is "auto"). |
+//! | `-c` or `--chunk-name`  | Sets the PNG chunk holding the payload (default is "stEg"). |
+//! | `--format`              | Sets the output format: auto (human on a terminal, the raw payload when piped), human or raw. |
+//! | `--force-binary`        | Writes raw binary payloads to a terminal anyway.           |
+//! | `--join`                | Gathers a payload sharded with `--split-across` from these files, or rebuilds it from enough `--shamir` shares. |
+//! | `--descriptor`          | Reads the payload at the location recorded in this descriptor, without scanning. |
+//! | `--ignore-expiry`       | Reveals payloads past their expiry anyway, with a warning. |
+//! | `--burn`                | Reveals a `--views` payload and rewrites the input with one view less, or wiped at zero. |
+//! | `--wipe`                | Overwrites the payload bytes in the input with random data once they decrypt. |
+//! | `--shred-input`         | Overwrites the whole input with random data and removes it once the output is written. |
+//! | `--passes`              | Sets how many times `--wipe` and `--shred-input` overwrite the bytes (default 1). |
+//! | `--depth`               | Unwraps up to this many payloads nested with `encrypt --nest` (default 1). |
+//! | `--qr`                  | Shows the secret as a QR code in the terminal, or written to the PNG file given. |
+//! | `--output-encoding`     | Prints the secret in base64 or hex (default is "raw").     |
+//! | `--strict`              | Rejects stego files that don't follow the specification of their format. |
+//! | `--exact`               | Writes the carrier only if it is restored byte for byte, checked by embedding the payload again. |
+//! | `--robust`              | Reads a payload embedded with `--robust`, the same as `-m robust`. |
+//! | `--channels`            | Sets the channels the lsb payload was embedded in (default is "rgb"). |
 //! |                         |                                                           |
 //! | **Metadata Options**    |                                                           |
 //! | `-i` or `--input`       | Sets the input image file for metadata extraction.         |
@@ -280,6 +341,159 @@
 //! | `-s` or `--start`       | Sets the index of the start chunk to read from (default 0). |
 //! | `-e` or `--end`         | Sets the index of the end chunk to stop reading at (default 100).|
 //! | `-r` or `--suppress`    | Suppresses output messages.                                |
+//! | `--bit-plane`           | Extracts a bit plane of a PNG image as a black-and-white PNG. |
+//! | `--channel`             | Sets the bit plane channel: r, g, b or a (default is "r"). |
+//! | `-o` or `--output`      | Sets the bit plane output file (default is "plane.png").   |
+//! | `--json`                | Prints the metadata as JSON.                               |
+//! | `--filter`              | Lists only the PNG chunks of these types (e.g. "tEXt,iTXt"). |
+//! | `--grep`                | Lists only the PNG chunks containing a pattern: hex after `0x` or ASCII text, with the match offsets. |
+//! | `--hex-limit`           | Dumps at most this many bytes of each chunk.               |
+//! | `--hex-range`           | Dumps only a byte range of each chunk (e.g. "0..64", "0x100.."). |
+//! | `--no-hex`              | Prints the chunk headers without their hexdumps.           |
+//! | `--strict`              | Fails on the first violation of the format's specification. |
+//! | `--extract-trailing`    | Writes the bytes after IEND (PNG) or EOI (JPEG) to a file.  |
+//! | `--page`                | Lists this page of the PNG chunks instead of `--start` to `--end`. |
+//! | `--per-page`            | Sets the number of chunks on a page (default is 50).       |
+//! |                         |                                                           |
+//! | **Polyglot Options**    |                                                           |
+//! | `-i` or `--input`       | Sets the PNG carrier, or the polyglot to unpack.           |
+//! | `-o` or `--output`      | Sets the polyglot, or the restored PNG (default is "output.png").|
+//! | `-n` or `--name`        | Sets the name of the ZIP entry (default is "secret.bin").  |
+//! | `-x` or `--extract`     | Extracts and decrypts the payload from a polyglot.         |
+//! |                         |                                                           |
+//! | **Soak Options**        |                                                           |
+//! | `--iterations`          | Sets the number of embed/extract iterations (default is 1000).|
+//! | `--seed`                | Sets the random seed, to replay a failing run (default is the clock).|
+//! | `-s` or `--suppress`    | Suppresses the progress output.                            |
+//! |                         |                                                           |
+//! | **Diff Options**        |                                                           |
+//! | `-a` or `--original`    | Sets the first PNG file, usually the original.             |
+//! | `-b` or `--modified`    | Sets the second PNG file, usually the modified one.        |
+//! | `--pixels`              | Also compares the decoded pixels.                          |
+//! | `--json`                | Prints the differences as JSON.                            |
+//! |                         |                                                           |
+//! | **Peek Options**        |                                                           |
+//! | `-i` or `--input`       | Sets the stego file to inspect without the key.            |
+//! | `-t`, `-m`, `-c`        | Set the type, method and chunk name, as for decryption.    |
+//! | `--hint-only`           | Prints only the plaintext hint, failing if there is none.  |
+//! |                         |                                                           |
+//! | **Config Options**      |                                                           |
+//! | `--set`                 | Sets a default as KEY=VALUE: algorithm, chunk_name, output_dir, suppress or json. |
+//! | `--unset`               | Removes a default, so the built-in one applies again.      |
+//! | `--path`                | Prints the path of the configuration file.                 |
+//! |                         |                                                           |
+//! | **Rekey Options**       |                                                           |
+//! | `-i` or `--input`       | Sets the stego file to re-encrypt.                         |
+//! | `-o` or `--output`      | Sets the re-keyed output file (default is "output.png").   |
+//! | `-k`, `-a`              | Set the current key and algorithm.                         |
+//! | `--new-key`             | Sets the key to re-encrypt with.                           |
+//! | `--new-algo`            | Sets the algorithm to re-encrypt with (default is the current one). |
+//! | `-t`, `-m`, `-c`        | Set the type, method and chunk name, as for decryption.    |
+//! | `--channels`            | Sets the channels of an lsb payload, kept for the new one (default is "rgb"). |
+//! |                         |                                                           |
+//! | **Watermark Options**   | `stegano watermark embed` and `stegano watermark verify`  |
+//! | `-i` or `--input`       | Sets the image to watermark, or to check.                  |
+//! | `-o` or `--output`      | Sets the watermarked output file (embed, default is "output.png"). |
+//! | `--owner`               | Sets the owner ID to embed (up to 16 bytes), or to look for on verify. |
+//! | `-k` or `--key`         | Sets the key drawing the watermark patterns (default is "key"). |
+//! | `--strength`            | Sets how many luma levels each cell may change (embed, default is 2). |
+//! |                         |                                                           |
+//! | **Inject Chunk Options** |                                                          |
+//! | `-i`, `-o`              | Set the PNG file and the output file (default is "output.png"). |
+//! | `--type`                | Sets the type of the new chunk, four ASCII letters.        |
+//! | `--data-file`           | Sets the file holding the chunk data, written as is.       |
+//! | `--position`            | Sets where the chunk goes: `after:TYPE`, `before:TYPE` or `index:N` (default is `before:IEND`). |
+//! |                         |                                                           |
+//! | **Remove Chunk Options** |                                                          |
+//! | `-i`, `-o`              | Set the PNG file and the output file (default is "output.png"). |
+//! | `--type`                | Removes every chunk of this type.                          |
+//! | `--index`               | Removes the chunk at this index, `IHDR` being 0.           |
+//! | `--force`               | Allows removing critical chunks other than `IHDR` and `IEND`. |
+//! | `--wipe`                | Overwrites the removed chunks in the input with random data. |
+//! | `--shred-input`         | Overwrites the whole input with random data and removes it once the output is written. |
+//! | `--passes`              | Sets how many times `--wipe` and `--shred-input` overwrite the bytes (default 1). |
+//! |                         |                                                           |
+//! | **Extract Chunk Options** |                                                         |
+//! | `-i` or `--input`       | Sets the PNG file.                                         |
+//! | `-o` or `--output`      | Sets the output file (default is "chunk.bin"); with `--all`, `out.bin` gives `out.1.bin`, `out.2.bin`... |
+//! | `--type`                | Extracts the first chunk of this type.                     |
+//! | `--index`               | Extracts the chunk at this index, `IHDR` being 0.          |
+//! | `--all`                 | Extracts every chunk of the type, each to its own file.    |
+//! |                         |                                                           |
+//! | **Vault Options**       | `stegano vault put`, `ls`, `get` and `rm`                 |
+//! | `-i` or `--input`       | Sets the PNG file holding the vault.                       |
+//! | `-o` or `--output`      | Sets the output file (put and rm, default is the input; get, default is stdout). |
+//! | `--file`                | Sets the file to store (put).                              |
+//! | `--name`                | Sets the name of the file in the vault (default is the name of `--file` on put). |
+//! | `-k`, `-a`              | Set the key of the vault and the algorithm of new files (default is "key" and "aes"). |
+//! |                         |                                                           |
+//! | **Meta Options**        | `stegano meta set`, `get` and `remove`                    |
+//! | `-i` or `--input`       | Sets the PNG file.                                         |
+//! | `-o` or `--output`      | Sets the output file (set and remove, default is the input). |
+//! | `--text`                | Sets a `tEXt` entry as `KEY=VALUE`, replacing any of the same keyword; can be repeated (set). |
+//! | `--time`                | Sets the `tIME` timestamp: now, a date or an RFC 3339 UTC time (set), or removes it (remove). |
+//! | `--key`                 | Prints only this entry (get), or removes the entries of this keyword; can be repeated (remove). |
+//! | `--all-text`            | Removes every `tEXt` entry (remove).                       |
+//! |                         |                                                           |
+//! | **Generate Carrier Options** |                                                      |
+//! | `--width`, `--height`   | Set the size of the image (default is 1920x1080).          |
+//! | `--style`               | Sets the look of the image: noise, gradient or perlin (default is "perlin"). |
+//! | `--fit-payload`         | Grows the image until it safely holds this file with `--method lsb`. |
+//! | `--seed`                | Sets the random seed, to generate the same image again (default is the clock). |
+//! | `-o` or `--output`      | Sets the output file (default is "output.png").            |
+//! |                         |                                                           |
+//! | **Global Options**      |                                                           |
+//! | `--error-format`        | Reports errors as human text or a JSON object on stderr (see Exit Codes). |
+//! | `--color`               | Colors human output: auto (on terminals, unless `NO_COLOR` is set), always or never. |
+//! | `--lang`                | Sets the language of the messages, such as `fr` (default is `LC_ALL`, `LC_MESSAGES` or `LANG`, then English). |
+//!
+//! # Methods
+//!
+//! | Type | Method      | Where the payload goes                            | Capacity                  | Carrier restored on decrypt |
+//! |------|-------------|---------------------------------------------------|---------------------------|-----------------------------|
+//! | PNG  | `auto`      | A private ancillary chunk (`--chunk-name`) before `IEND` | 2 GiB per chunk, larger payloads span consecutive chunks | Yes, byte for byte |
+//! | PNG  | `apng`      | A private ancillary chunk between the first two frames of an animated PNG, which keeps playing as before | 2 GiB per chunk, larger payloads span consecutive chunks | Yes, byte for byte |
+//! | PNG  | `precision` | Low-order bits of the `gAMA`, `cHRM` and `pHYs` values (experimental) | 4 bytes and a check byte, `--algo xor` only | No, values stay within 8/100000 of the original |
+//! | PNG  | `lsb`       | Least significant bits of colour samples, at positions drawn from a PRNG seeded by the key, or in the `--traversal` order | 1 bit per colour sample, or 2 to 4 when the payload needs them or `--bits-per-channel` asks, minus 4 bytes, up to 128 MiB | No, only the chosen low bits differ |
+//! | PNG  | `robust`    | Mid-frequency DCT coefficients of 8x8 luma blocks drawn from the key, each bit repeated over 5 blocks; survives JPEG re-compression down to about quality 50 | 1 byte per 40 blocks, minus 8 bytes (94 bytes at 512x512) | No, the image stays around 45 dB PSNR |
+//! | PNG  | `disguise`  | The tone curve of an ICC profile, the thumbnail of an XMP packet or a camera maker note (`--disguise`), right after `IHDR` | Up to 2 GiB | Yes, byte for byte |
+//! | PNG  | `interleave` | 2 to 8 numbered fragment chunks (`--chunk-name`) scattered between the chunks of the carrier at places drawn from the key, never between two `IDAT` | Up to 2 GiB | Yes, byte for byte |
+//! | PNG, JPEG | `icc` | After the tag elements of the ICC profile (`iCCP`, or APP2 segments in a JPEG); carriers without one get a neutral sRGB-like profile | Up to 4 GiB, 16 MiB in a JPEG | With its original profile, recompressed in a PNG |
+//! | TIFF | `auto`/`tag`| Private tag 65000 in the first IFD                | Up to 4 GiB               | As a clean TIFF |
+//! | TIFF | `strip`     | An unreferenced strip at the end of the file      | Up to 4 GiB               | As a clean TIFF |
+//! | ICO  | `auto`/`slack` | Slack space after the last image               | Up to 4 GiB               | Yes, byte for byte |
+//! | MP4  | `auto`/`free`, `skip`, `uuid` | A `free` or `skip` box, or a `uuid` box of its own, after the last top-level box; the sample offsets stay valid | Up to 16 EiB | Yes, byte for byte |
+//! | MP3  | `auto`/`priv`, `geob` | A `PRIV` frame or a `GEOB` object after the last frame of the ID3v2 tag, added if missing | Up to 256 MiB | Yes, byte for byte |
+//! | FLAC | `auto`/`application`, `padding` | An `APPLICATION` block added after the last metadata block, or the `PADDING` block when it is large enough (a new one otherwise) | Up to 16 MiB | Yes, byte for byte |
+//! | SVG  | `auto`/`metadata` | Base64 in a `metadata` element right after the root `svg` tag | Unlimited | Yes, byte for byte |
+//! | SVG  | `whitespace` | Spaces and tabs at the end of the lines, one byte per line | 1 byte per line, minus 4 bytes | Yes, if the lines had no trailing whitespace |
+//! | SVG  | `attributes` | The order of the attributes of each element, `log2(n!)` bits for `n` attributes | 15 bits per element of 8 attributes, minus 4 bytes | No, the attributes stay reordered |
+//! | Text | `auto`/`zerowidth` | Zero-width Unicode characters (U+200B, U+200C, U+200D, U+2060) right after the first visible character, four per byte | Unlimited | Yes, byte for byte |
+//! | Text | `whitespace` | Spaces and tabs at the end of the lines, one byte per line | 1 byte per line, minus 4 bytes | Yes, if the lines had no trailing whitespace |
+//! | Any  | `append`    | After the end of the file, with a magic trailer   | Unlimited                 | Yes, byte for byte |
+//!
+//! # Exit Codes
+//!
+//! Every failure exits with the code of its category. With `--error-format json`, the error is
+//! printed on stderr as an object such as
+//! `{"schema":"stegano.error/1","error":"payload_not_found","exit_code":5,"message":"No \"stEg\" chunk found"}`.
+//! A wrong key is detected where the data is checked: payload headers are signed with the key, and
+//! so are `--descriptor` files. From format version 3 on, the ciphertext is signed too, so a
+//! damaged payload fails with exit code 4 instead of decrypting to garbage. Precision payloads
+//! only have room for a check byte: a wrong key fails with exit code 6, except once in 256
+//! tries. Legacy payloads carry no checksum and decrypt to garbage.
+//!
+//! | Exit code | Category            | Meaning                                              |
+//! |-----------|---------------------|------------------------------------------------------|
+//! | 0         |                     | Success                                              |
+//! | 1         | `error`             | Any other failure                                    |
+//! | 2         | `usage`             | Invalid arguments or options that don't fit together |
+//! | 3         | `io`                | A file couldn't be read or written                   |
+//! | 4         | `format`            | The carrier is malformed or not of the given type    |
+//! | 5         | `payload_not_found` | The carrier holds no payload where it was looked for |
+//! | 6         | `wrong_key`         | The key doesn't open the data                        |
+//! | 7         | `expired`           | The payload is past its expiry                       |
+//! | 130       | `cancelled`         | Interrupted with Ctrl-C                              |
 //!
 //! # GitHub Repository
 //!
@@ -291,7 +505,77 @@
 //! please engage with the project on [GitHub](https://github.com/wiseaidev/stegano).
 //! Your contributions help improve this crate for the community.
 
+pub mod api;
+pub mod apng;
+pub mod append;
+pub mod burn;
+pub mod cancel;
+pub mod chunk_type;
 pub mod cli;
+pub mod codec;
+pub mod config;
+pub mod cover;
+pub mod descriptor;
+pub mod diff;
+pub mod disguise;
+pub mod encoding;
+pub mod envelope;
+pub mod error;
+pub mod explain;
+#[cfg(feature = "stegano-ffi")]
+pub mod ffi;
+pub mod flac;
+pub mod format;
+#[cfg(feature = "gui")]
+pub mod gui;
+pub mod header;
+pub mod hilbert;
+pub mod hint;
+pub mod i18n;
+pub mod icc;
+pub mod ico;
+pub mod interleave;
+pub mod io_ext;
 pub mod jpeg;
+pub mod json;
+pub mod keychain;
+pub mod lsb;
+pub mod mapped;
+pub mod meta;
 pub mod models;
+pub mod mp3;
+pub mod mp4;
+#[cfg(feature = "tokio")]
+pub mod nonblocking;
+pub mod output;
+pub mod png;
+pub mod polyglot;
+pub mod precision;
+pub mod prng;
+pub mod provenance;
+pub mod qr;
+pub mod quality;
+pub mod rekey;
+pub mod report;
+pub mod robust;
+#[cfg(feature = "net")]
+pub mod serve;
+pub mod shamir;
+pub mod shard;
+#[cfg(feature = "net")]
+pub mod share;
+pub mod soak;
+pub mod spill;
+pub mod storage;
+pub mod strict;
+pub mod style;
+pub mod svg;
+pub mod text;
+pub mod tiff;
+pub mod trailing;
 pub mod utils;
+pub mod vault;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watermark;
+pub mod wipe;
@@ -0,0 +1,710 @@
+use crate::hilbert::{curve_side, distance};
+use crate::png::{read_chunks, PngImage};
+use crate::prng::Prng;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::io::{self, ErrorKind};
+
+/// Number of bytes used by the payload length.
+const LENGTH_LEN: usize = 4;
+
+/// The low bits per sample a payload can be spread over, by the index recorded in the top two
+/// bits of the length. Three bits came last, so payloads written before them read the same.
+pub const LSB_PLANES: [usize; 4] = [1, 2, 4, 3];
+
+/// The most low bits per sample a payload is spread over.
+pub const MAX_LSB_BITS: usize = 4;
+
+/// The bits of the length word that hold the payload length, below the traversal.
+const LENGTH_MASK: u32 = (1 << 27) - 1;
+
+/// The shift of the traversal in the length word, three bits below the plane count.
+const TRAVERSAL_SHIFT: u32 = 27;
+
+/// The order the payload bits are written to the colour samples in, after the length.
+///
+/// The length is always scattered with the key, so that the traversal it records can be read
+/// back; the payload then follows the traversal over the samples left. Each order defeats
+/// other steganalysis heuristics: a keyed scatter leaves no region of changed bits for
+/// sequential analysis to find, while the fixed orders keep the changes together, where the
+/// Hilbert curve and the zigzag keep neighbouring bits in neighbouring pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Traversal {
+    /// Samples drawn in an order derived from the key, the default.
+    #[default]
+    Scatter,
+    /// Pixels left to right, then top to bottom.
+    RowMajor,
+    /// Pixels top to bottom, then left to right.
+    ColumnMajor,
+    /// Pixels along the anti-diagonals, alternating directions as JPEG coefficients are.
+    ZigZag,
+    /// Pixels along a Hilbert curve covering the image.
+    Hilbert,
+}
+
+/// The traversals, in the order of the values recorded in the length word.
+pub const TRAVERSALS: [Traversal; 5] = [
+    Traversal::Scatter,
+    Traversal::RowMajor,
+    Traversal::ColumnMajor,
+    Traversal::ZigZag,
+    Traversal::Hilbert,
+];
+
+impl Traversal {
+    /// Parses a traversal name: scatter, row-major, column-major, zigzag or hilbert.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::lsb::Traversal;
+    ///
+    /// assert_eq!(Traversal::new("Hilbert").unwrap(), Traversal::Hilbert);
+    /// assert_eq!(Traversal::new("zigzag").unwrap().name(), "zigzag");
+    /// assert!(Traversal::new("spiral").is_err());
+    /// ```
+    pub fn new(name: &str) -> io::Result<Traversal> {
+        TRAVERSALS
+            .into_iter()
+            .find(|traversal| traversal.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Unknown traversal {:?}, use scatter, row-major, column-major, zigzag or hilbert",
+                        name
+                    ),
+                )
+            })
+    }
+
+    /// Returns the name of the traversal, as given on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Traversal::Scatter => "scatter",
+            Traversal::RowMajor => "row-major",
+            Traversal::ColumnMajor => "column-major",
+            Traversal::ZigZag => "zigzag",
+            Traversal::Hilbert => "hilbert",
+        }
+    }
+
+    /// Returns the rank of a pixel in the traversal; pixels are visited by increasing rank.
+    fn rank(&self, x: u32, y: u32, width: u32, height: u32) -> u64 {
+        let (x, y) = (u64::from(x), u64::from(y));
+        match self {
+            Traversal::Scatter | Traversal::RowMajor => y * u64::from(width) + x,
+            Traversal::ColumnMajor => x * u64::from(height) + y,
+            Traversal::ZigZag => {
+                let diagonal = x + y;
+                diagonal << 32 | if diagonal % 2 == 0 { x } else { y }
+            }
+            Traversal::Hilbert => distance(curve_side(width, height), x as u32, y as u32),
+        }
+    }
+}
+
+/// Number of samples in the regions writes are grouped by, 128 KiB of 16-bit samples, which
+/// stays in the cache while its writes are applied.
+const REGION_LEN: usize = 1 << 16;
+
+/// Number of bits drawn, then written or read, at a time.
+const BATCH_BITS: usize = 1 << 20;
+
+/// The channels whose samples carry LSB bits, red, green and blue by default.
+///
+/// The colour channels of greyscale images all select the grey sample, and alpha is only
+/// written when asked for. The same channels must be given to extract the payload, as the key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Channels {
+    /// Whether red, green, blue and alpha are selected, in that order.
+    selected: [bool; 4],
+}
+
+impl Default for Channels {
+    fn default() -> Self {
+        Channels {
+            selected: [true, true, true, false],
+        }
+    }
+}
+
+impl Channels {
+    /// Parses the channels as letters among `r`, `g`, `b` and `a`, such as rgb, rg, b or a.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::lsb::Channels;
+    ///
+    /// assert_eq!(Channels::new("RGB").unwrap(), Channels::default());
+    /// assert_eq!(Channels::new("br").unwrap().name(), "rb");
+    /// assert!(Channels::new("").is_err());
+    /// assert!(Channels::new("bb").is_err());
+    /// assert!(Channels::new("rgbx").is_err());
+    /// ```
+    pub fn new(names: &str) -> io::Result<Channels> {
+        let invalid = || {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid channels {:?}, use letters among r, g, b and a, such as rgb, rg, b or a",
+                    names
+                ),
+            )
+        };
+        let mut selected = [false; 4];
+        for name in names.chars() {
+            let index = "rgba".find(name.to_ascii_lowercase()).ok_or_else(invalid)?;
+            if selected[index] {
+                return Err(invalid());
+            }
+            selected[index] = true;
+        }
+        if names.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Channels { selected })
+    }
+
+    /// Returns the channels as letters in `rgba` order, as given on the command line.
+    pub fn name(&self) -> String {
+        "rgba"
+            .chars()
+            .zip(self.selected)
+            .filter_map(|(name, selected)| selected.then_some(name))
+            .collect()
+    }
+
+    /// Returns, for each sample of a pixel of `image`, whether it carries bits.
+    fn samples(&self, image: &PngImage) -> Vec<bool> {
+        let mut samples = vec![false; image.channels()];
+        for (name, _) in "rgba"
+            .chars()
+            .zip(self.selected)
+            .filter(|&(_, selected)| selected)
+        {
+            if let Some(index) = image.channel_index(&name.to_string()) {
+                samples[index] = true;
+            }
+        }
+        samples
+    }
+
+    /// Returns the number of samples of `image` that carry bits.
+    fn count(&self, image: &PngImage) -> usize {
+        let per_pixel = self.samples(image).iter().filter(|&&used| used).count();
+        image.width * image.height * per_pixel
+    }
+}
+
+/// Parses `--channels` for clap.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::lsb::{parse_channels, Channels};
+///
+/// assert_eq!(parse_channels("b").unwrap(), Channels::new("b").unwrap());
+/// assert!(parse_channels("rgbq").is_err());
+/// ```
+pub fn parse_channels(value: &str) -> Result<Channels, String> {
+    Channels::new(value).map_err(|err| err.to_string())
+}
+
+/// Yields the indices of colour samples in an order derived from a key.
+///
+/// This is a Fisher-Yates shuffle drawn one position at a time, so each sample is used at most
+/// once and the embedder and extractor walk the same sequence.
+struct Scatter {
+    indices: Vec<u32>,
+    next: usize,
+    rng: Prng,
+    ordered: bool,
+}
+
+impl Scatter {
+    /// Lists the samples of the selected channels of an image and seeds the order from `key`.
+    fn new(image: &PngImage, key: &str, channels: Channels) -> Self {
+        let used = channels.samples(image);
+        let indices = (0..image.samples.len() as u32)
+            .filter(|&i| used[i as usize % used.len()])
+            .collect();
+        let digest = Sha256::digest(key.as_bytes());
+        let seed = u64::from_le_bytes(digest[..8].try_into().unwrap());
+        Scatter {
+            indices,
+            next: 0,
+            rng: Prng::new(seed),
+            ordered: false,
+        }
+    }
+
+    /// Visits the samples not used yet in the order of `traversal` from now on, the channels
+    /// of a pixel one after another.
+    fn follow(&mut self, traversal: Traversal, image: &PngImage) {
+        if traversal == Traversal::Scatter {
+            return;
+        }
+        let channels = image.channels();
+        let (width, height) = (image.width as u32, image.height as u32);
+        let mut ranked: Vec<(u64, u32)> = self.indices[self.next..]
+            .iter()
+            .map(|&index| {
+                let pixel = index as usize / channels;
+                let (x, y) = ((pixel % image.width) as u32, (pixel / image.width) as u32);
+                (traversal.rank(x, y, width, height), index)
+            })
+            .collect();
+        ranked.sort_unstable();
+        for (slot, (_, index)) in self.indices[self.next..].iter_mut().zip(ranked) {
+            *slot = index;
+        }
+        self.ordered = true;
+    }
+
+    /// Returns the next sample index, or `None` once every sample has been used.
+    fn next_index(&mut self) -> Option<usize> {
+        let remaining = self.indices.len() - self.next;
+        if remaining == 0 {
+            return None;
+        }
+        if !self.ordered {
+            let pick = self.next + self.rng.below(remaining as u64) as usize;
+            self.indices.swap(self.next, pick);
+        }
+        self.next += 1;
+        Some(self.indices[self.next - 1] as usize)
+    }
+}
+
+/// Sets the low bits of samples, each write packing the index of a sample and its new bits
+/// as `index << 8 | bits`; `mask` selects the bits that are replaced.
+///
+/// Writes scattered over a large image miss the cache one after another, so they are first
+/// grouped by region of `REGION_LEN` samples with a counting sort, then applied region by
+/// region, in parallel with the `rayon` feature. The sort is stable, so writes to the same
+/// sample keep their order.
+fn set_lsbs(samples: &mut [u16], writes: &[u64], mask: u16) {
+    let region = |write: u64| (write >> 8) as usize / REGION_LEN;
+    let mut starts = vec![0; samples.len().div_ceil(REGION_LEN) + 1];
+    for &write in writes {
+        starts[region(write) + 1] += 1;
+    }
+    for i in 1..starts.len() {
+        starts[i] += starts[i - 1];
+    }
+    let mut grouped = vec![0; writes.len()];
+    let mut cursors = starts.clone();
+    for &write in writes {
+        grouped[cursors[region(write)]] = write;
+        cursors[region(write)] += 1;
+    }
+    let apply = |(index, samples): (usize, &mut [u16])| {
+        for &write in &grouped[starts[index]..starts[index + 1]] {
+            let sample = &mut samples[(write >> 8) as usize % REGION_LEN];
+            *sample = *sample & !mask | write as u16 & mask;
+        }
+    };
+    #[cfg(feature = "rayon")]
+    samples
+        .par_chunks_mut(REGION_LEN)
+        .enumerate()
+        .for_each(apply);
+    #[cfg(not(feature = "rayon"))]
+    samples.chunks_mut(REGION_LEN).enumerate().for_each(apply);
+}
+
+/// Draws a position for each of `values`, the bits of one sample each, and writes them in
+/// batches of `BATCH_BITS`.
+fn write_values<I: Iterator<Item = u64>>(
+    samples: &mut [u16],
+    scatter: &mut Scatter,
+    mut values: I,
+    planes: usize,
+) {
+    let mask = (1u16 << planes) - 1;
+    let mut writes = Vec::with_capacity(BATCH_BITS);
+    loop {
+        writes.clear();
+        // The positions are drawn in order, the same sequence `extract_lsb` walks.
+        writes.extend(values.by_ref().take(BATCH_BITS).map(|value| {
+            let index = scatter.next_index().expect("Capacity was checked");
+            (index as u64) << 8 | value
+        }));
+        if writes.is_empty() {
+            break;
+        }
+        set_lsbs(samples, &writes, mask);
+    }
+}
+
+/// Splits bytes into values of `planes` bits, most significant first, the last one padded with
+/// zeros, one value per sample.
+fn bit_values(bytes: &[u8], planes: usize) -> impl Iterator<Item = u64> + '_ {
+    (0..(bytes.len() * 8).div_ceil(planes)).map(move |value| {
+        (value * planes..(value + 1) * planes).fold(0, |acc, bit| {
+            let set = bytes
+                .get(bit / 8)
+                .map_or(0, |byte| byte >> (7 - bit % 8) & 1);
+            acc << 1 | u64::from(set)
+        })
+    })
+}
+
+/// Reads `len` bytes from the `planes` low bits of the samples `scatter` yields next,
+/// gathering each batch in parallel with the `rayon` feature.
+///
+/// The samples are gathered in groups holding a whole number of bytes: 8 / `planes` samples
+/// for one byte, or 8 samples for three bytes at three bits each.
+///
+/// # Returns
+///
+/// The bytes, or `None` if the image runs out of samples first.
+fn read_bytes(
+    samples: &[u16],
+    scatter: &mut Scatter,
+    len: usize,
+    planes: usize,
+) -> Option<Vec<u8>> {
+    let mask = (1u16 << planes) - 1;
+    let group = if 8 % planes == 0 { 8 / planes } else { 8 };
+    let group_bytes = group * planes / 8;
+    // The last group may be short, its missing bits then read as zeros.
+    let gather = |positions: &[u32]| {
+        let bits = positions.iter().fold(0u32, |acc, &index| {
+            acc << planes | u32::from(samples[index as usize] & mask)
+        }) << ((group - positions.len()) * planes);
+        (0..group_bytes)
+            .rev()
+            .map(move |byte| (bits >> (byte * 8)) as u8)
+    };
+    let total = (len * 8).div_ceil(planes);
+    let mut bytes = Vec::with_capacity(len + group_bytes);
+    let mut positions = Vec::with_capacity(total.min(BATCH_BITS));
+    let mut read = 0;
+    while read < total {
+        positions.clear();
+        for _ in 0..(total - read).min(BATCH_BITS) {
+            positions.push(scatter.next_index()? as u32);
+        }
+        read += positions.len();
+        #[cfg(feature = "rayon")]
+        bytes.par_extend(positions.par_chunks(group).flat_map_iter(gather));
+        #[cfg(not(feature = "rayon"))]
+        bytes.extend(positions.chunks(group).flat_map(gather));
+    }
+    bytes.truncate(len);
+    Some(bytes)
+}
+
+/// Decodes a carrier, checking that its samples can hold LSB data.
+fn decode_carrier(data: &[u8]) -> io::Result<PngImage> {
+    let chunks = read_chunks(data)?;
+    let ihdr = chunks
+        .first()
+        .filter(|chunk| &chunk.r#type == b"IHDR" && chunk.data.len() == 13)
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Missing IHDR chunk"))?;
+    if ihdr.data[9] == 3 || ihdr.data[8] < 8 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The LSB method needs an 8 or 16-bit greyscale or truecolour PNG",
+        ));
+    }
+    PngImage::decode(data)
+}
+
+/// Returns the number of payload bytes an image can hold with the LSB method, one bit per
+/// sample.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::lsb::lsb_capacity;
+/// use stegano::png::{encode_grayscale, PngImage};
+///
+/// let png = encode_grayscale(8, 8, &[0; 64]).unwrap();
+/// assert_eq!(lsb_capacity(&PngImage::decode(&png).unwrap()), 4);
+/// ```
+pub fn lsb_capacity(image: &PngImage) -> usize {
+    lsb_planes_capacity(image, 1)
+}
+
+/// Returns the number of payload bytes an image can hold with the LSB method, spread over
+/// `planes` low bits of each sample. The length always takes one bit per sample.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::lsb::{lsb_planes_capacity, LSB_PLANES};
+/// use stegano::png::{encode_grayscale, PngImage};
+///
+/// let image = PngImage::decode(&encode_grayscale(8, 8, &[0; 64]).unwrap()).unwrap();
+/// assert_eq!(lsb_planes_capacity(&image, 2), 8);
+/// assert_eq!(lsb_planes_capacity(&image, LSB_PLANES[2]), 16);
+/// ```
+pub fn lsb_planes_capacity(image: &PngImage, planes: usize) -> usize {
+    lsb_channels_capacity(image, planes, Channels::default())
+}
+
+/// Returns the number of payload bytes an image can hold with the LSB method, spread over
+/// `planes` low bits of the samples of `channels` only.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::lsb::{lsb_channels_capacity, lsb_planes_capacity, Channels};
+/// use stegano::png::{encode_rgb, PngImage};
+///
+/// let image = PngImage::decode(&encode_rgb(8, 8, &[0; 192]).unwrap()).unwrap();
+/// assert_eq!(lsb_planes_capacity(&image, 1), 20);
+/// assert_eq!(lsb_channels_capacity(&image, 1, Channels::new("b").unwrap()), 4);
+/// // The image has no alpha channel.
+/// assert_eq!(lsb_channels_capacity(&image, 1, Channels::new("a").unwrap()), 0);
+/// ```
+pub fn lsb_channels_capacity(image: &PngImage, planes: usize, channels: Channels) -> usize {
+    let samples = channels.count(image).saturating_sub(LENGTH_LEN * 8);
+    (samples * planes / 8).min(LENGTH_MASK as usize)
+}
+
+/// Hides a payload in the least significant bits of pseudo-randomly chosen colour samples.
+///
+/// The positions are drawn from a generator seeded with the SHA-256 digest of `key`, so the
+/// bits are scattered over the whole image instead of filling it from the top left, which
+/// defeats sequential LSB analysis. Without the key the bits can't even be gathered, let alone
+/// decrypted. The payload is preceded by its length as a 32-bit big-endian integer. A payload
+/// too large for one bit per sample is spread over the two, three or four low bits of each
+/// sample instead, as recorded in the top two bits of the length. Positions refer to the
+/// de-interlaced pixel grid, and the image is written back interlaced with Adam7 if the carrier
+/// is, so that interlaced carriers keep their look while loading and the payload doesn't depend
+/// on the order the rows are stored in.
+///
+/// Only the colour channels carry bits here. `embed_lsb_with` walks the samples in another
+/// order, or writes the alpha samples too when its channels select them, as `--channels a`
+/// does.
+///
+/// # Arguments
+///
+/// * `carrier` - The bytes of an 8 or 16-bit, non-palette PNG.
+/// * `payload` - The bytes to hide.
+/// * `key` - The key the positions are derived from.
+///
+/// # Returns
+///
+/// A `Result` containing the bytes of the stego file, or an `InvalidInput` error if the
+/// carrier is unsuitable, or too small even with four bits per sample.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::lsb::{embed_lsb, extract_lsb};
+/// use stegano::png::{encode_grayscale, is_interlaced, PngImage};
+///
+/// let carrier = encode_grayscale(16, 16, &[128; 256]).unwrap();
+/// let stego = embed_lsb(&carrier, b"secret", "key").unwrap();
+/// assert_eq!(extract_lsb(&stego, "key").unwrap(), b"secret");
+/// assert_ne!(extract_lsb(&stego, "other key").ok(), Some(b"secret".to_vec()));
+///
+/// let image = PngImage::decode(&carrier).unwrap();
+/// let interlaced = image.replace_pixels_interlaced(&carrier).unwrap();
+/// let stego = embed_lsb(&interlaced, b"secret", "key").unwrap();
+/// assert!(is_interlaced(&stego).unwrap());
+/// assert_eq!(extract_lsb(&stego, "key").unwrap(), b"secret");
+///
+/// // 224 samples past the length hold 28 bytes at one bit each, 112 at four.
+/// let stego = embed_lsb(&carrier, &[7; 100], "key").unwrap();
+/// assert_eq!(extract_lsb(&stego, "key").unwrap(), vec![7; 100]);
+/// let err = embed_lsb(&carrier, &[7; 113], "key").unwrap_err();
+/// assert!(err.to_string().contains("needs 113 bytes but the carrier offers 112"));
+/// ```
+pub fn embed_lsb(carrier: &[u8], payload: &[u8], key: &str) -> io::Result<Vec<u8>> {
+    embed_lsb_with(carrier, payload, key, LsbOptions::default())
+}
+
+/// How `embed_lsb_with` lays a payload out in the samples, besides the key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LsbOptions {
+    /// The order the payload bits are written in.
+    pub traversal: Traversal,
+    /// The channels whose samples carry them.
+    pub channels: Channels,
+    /// The low bits of each sample they take, from 1 to `MAX_LSB_BITS`, or `None` for the
+    /// fewest the payload fits in.
+    pub bits: Option<usize>,
+}
+
+/// Hides a payload with the LSB method as `embed_lsb` does, in the samples of the channels of
+/// `opts` only, writing its bits in the order of its traversal over as many low bits of each
+/// sample as it asks for. The traversal and the bits go in the scattered length.
+///
+/// `extract_lsb_in` reads them back from the length, so it only needs the channels. More bits
+/// per sample hold a larger payload in fewer samples, at the cost of a visibly noisier image:
+/// each bit more roughly takes 6 dB off the PSNR of the samples it changes.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::lsb::{embed_lsb_with, extract_lsb, extract_lsb_in, lsb_layout, Channels, LsbOptions, TRAVERSALS};
+/// use stegano::png::{encode_grayscale, encode_rgb, PngImage};
+///
+/// let carrier = encode_grayscale(13, 7, &[128; 91]).unwrap();
+/// for traversal in TRAVERSALS {
+///     let opts = LsbOptions { traversal, ..LsbOptions::default() };
+///     let stego = embed_lsb_with(&carrier, b"secret", "key", opts).unwrap();
+///     assert_eq!(extract_lsb(&stego, "key").unwrap(), b"secret");
+///     assert_eq!(lsb_layout(&stego, "key", Channels::default()).unwrap().traversal, traversal);
+/// }
+///
+/// // Only the blue samples change.
+/// let carrier = encode_rgb(16, 16, &[128; 768]).unwrap();
+/// let blue = LsbOptions { channels: Channels::new("b").unwrap(), ..LsbOptions::default() };
+/// let stego = embed_lsb_with(&carrier, b"secret", "key", blue).unwrap();
+/// assert_eq!(extract_lsb_in(&stego, "key", blue.channels).unwrap(), b"secret");
+/// let samples = PngImage::decode(&stego).unwrap().samples;
+/// assert!(samples.iter().enumerate().all(|(i, &sample)| i % 3 == 2 || sample == 128));
+/// let err = embed_lsb_with(&carrier, &[0; 113], "key", blue).unwrap_err();
+/// assert!(err.to_string().contains("needs 113 bytes but the carrier offers 112"));
+///
+/// // Three bits per sample, asked for or the fewest that fit.
+/// for (bits, len) in [(Some(3), 5), (None, 200)] {
+///     let opts = LsbOptions { bits, ..LsbOptions::default() };
+///     let stego = embed_lsb_with(&carrier, &vec![0xA5; len], "key", opts).unwrap();
+///     assert_eq!(extract_lsb(&stego, "key").unwrap(), vec![0xA5; len]);
+///     assert_eq!(lsb_layout(&stego, "key", Channels::default()).unwrap().bits, 3);
+/// }
+/// ```
+pub fn embed_lsb_with(
+    carrier: &[u8],
+    payload: &[u8],
+    key: &str,
+    opts: LsbOptions,
+) -> io::Result<Vec<u8>> {
+    let LsbOptions {
+        traversal,
+        channels,
+        bits,
+    } = opts;
+    let mut image = decode_carrier(carrier)?;
+    if bits.is_some_and(|bits| !(1..=MAX_LSB_BITS).contains(&bits)) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("The LSB method takes 1 to {} bits per sample", MAX_LSB_BITS),
+        ));
+    }
+    let most = bits.unwrap_or(MAX_LSB_BITS);
+    let planes = (bits.unwrap_or(1)..=most)
+        .find(|&planes| payload.len() <= lsb_channels_capacity(&image, planes, channels))
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "The payload needs {} bytes but the carrier offers {} with the LSB method, over {} bits per sample",
+                    payload.len(),
+                    lsb_channels_capacity(&image, most, channels),
+                    most
+                ),
+            )
+        })?;
+    let level = LSB_PLANES
+        .iter()
+        .position(|&known| known == planes)
+        .unwrap();
+    let mut scatter = Scatter::new(&image, key, channels);
+    let header =
+        ((level as u32) << 30 | (traversal as u32) << TRAVERSAL_SHIFT | payload.len() as u32)
+            .to_be_bytes();
+    write_values(&mut image.samples, &mut scatter, bit_values(&header, 1), 1);
+    scatter.follow(traversal, &image);
+    write_values(
+        &mut image.samples,
+        &mut scatter,
+        bit_values(payload, planes),
+        planes,
+    );
+    image.replace_pixels(carrier)
+}
+
+/// Extracts a payload hidden by `embed_lsb`.
+///
+/// # Arguments
+///
+/// * `data` - The bytes of the stego file.
+/// * `key` - The key the payload was embedded with.
+///
+/// # Returns
+///
+/// A `Result` containing the payload, or a `NotFound` error if the length read with this key
+/// doesn't fit the image, which is what a wrong key usually gives.
+pub fn extract_lsb(data: &[u8], key: &str) -> io::Result<Vec<u8>> {
+    extract_lsb_in(data, key, Channels::default())
+}
+
+/// Extracts a payload hidden by `embed_lsb_with` in the samples of `channels`.
+///
+/// # Returns
+///
+/// A `Result` containing the payload, or a `NotFound` error as `extract_lsb` gives, which is
+/// also what other channels than the payload was embedded in give.
+pub fn extract_lsb_in(data: &[u8], key: &str, channels: Channels) -> io::Result<Vec<u8>> {
+    let image = decode_carrier(data)?;
+    let mut scatter = Scatter::new(&image, key, channels);
+    let layout = read_length(&image, &mut scatter, channels)?;
+    scatter.follow(layout.traversal, &image);
+    read_bytes(&image.samples, &mut scatter, layout.len, layout.bits).ok_or_else(not_found)
+}
+
+/// How an LSB payload was laid out, as recorded in its length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LsbLayout {
+    /// The low bits of each sample the payload takes.
+    pub bits: usize,
+    /// The order its bits were written in.
+    pub traversal: Traversal,
+    /// Its length in bytes.
+    pub len: usize,
+}
+
+/// Reads how an LSB payload was laid out from its length, without reading the payload.
+///
+/// # Returns
+///
+/// A `Result` containing the layout, or a `NotFound` error as `extract_lsb` gives.
+pub fn lsb_layout(data: &[u8], key: &str, channels: Channels) -> io::Result<LsbLayout> {
+    let image = decode_carrier(data)?;
+    read_length(&image, &mut Scatter::new(&image, key, channels), channels)
+}
+
+/// The error of reading an LSB payload with a wrong key, or from a file without one.
+fn not_found() -> io::Error {
+    io::Error::new(ErrorKind::NotFound, "No LSB payload found with this key")
+}
+
+/// Reads the scattered length word of an LSB payload.
+///
+/// # Returns
+///
+/// The layout of the payload, or a `NotFound` error if the word doesn't make sense for the
+/// image.
+fn read_length(
+    image: &PngImage,
+    scatter: &mut Scatter,
+    channels: Channels,
+) -> io::Result<LsbLayout> {
+    let header = read_bytes(&image.samples, scatter, LENGTH_LEN, 1).ok_or_else(not_found)?;
+    let header = u32::from_be_bytes(header.try_into().unwrap());
+    let bits = LSB_PLANES[(header >> 30) as usize];
+    let traversal = *TRAVERSALS
+        .get((header >> TRAVERSAL_SHIFT & 0b111) as usize)
+        .ok_or_else(not_found)?;
+    let len = (header & LENGTH_MASK) as usize;
+    if len > lsb_channels_capacity(image, bits, channels) {
+        return Err(not_found());
+    }
+    Ok(LsbLayout {
+        bits,
+        traversal,
+        len,
+    })
+}
@@ -1,60 +1,1203 @@
-use clap::Parser;
-use crc32_v2::byfour::crc32_little;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches};
 use std::fs::File;
-use std::io::Write;
-use stegano::cli::{Cli, SteganoCommands};
+use std::io::{self, Read, Seek, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use stegano::api::{restores_carrier, verify_restored};
+use stegano::apng::parse_animation;
+use stegano::append::{
+    extract_appended, sha256_prefix, strip_appended, write_appended, write_appended_linked,
+    DIGEST_LEN, TRAILER_LEN,
+};
+use stegano::burn::burn_bytes;
+use stegano::cancel::{
+    install_signal_handler, is_cancelled, patch_output, write_output, CancellationToken,
+    KeepModified, KeepTimes, OutputFile,
+};
+use stegano::chunk_type::ChunkType;
+use stegano::cli::{
+    Cli, DecryptCmd, DecryptMode, EncryptCmd, MetaAction, SteganoCommands, VaultAction,
+    WatermarkAction,
+};
+use stegano::codec::{
+    carrier_mime_type, embed_encrypted, extract_encrypted, nested_carrier_type, CodecOptions,
+};
+use stegano::config::{Config, CONFIG_KEYS};
+use stegano::cover::{fit_dimensions, generate_cover, safe_capacity, CoverStyle};
+use stegano::descriptor::{sha256_range, Descriptor};
+use stegano::diff::{diff_chunks, diff_pixels, print_diff, write_hex_diff, HEX_CONTEXT};
+use stegano::disguise::{extract_disguised, Disguise};
+use stegano::encoding::PayloadEncoding;
+use stegano::envelope::{
+    age_decrypt, age_encrypt, is_age, is_age_algorithm, is_pgp, is_pgp_algorithm, pgp_decrypt,
+    pgp_encrypt,
+};
+use stegano::error::SteganoError;
+#[cfg(feature = "gui")]
+use stegano::explain::explain_gui;
+use stegano::explain::{
+    explain_config, explain_decrypt, explain_diff, explain_encrypt, explain_extract_chunk,
+    explain_generate_carrier, explain_icc, explain_inject_chunk, explain_meta, explain_peek,
+    explain_polyglot, explain_provenance, explain_rekey, explain_remove_chunk, explain_show_meta,
+    explain_soak, explain_vault, explain_watermark,
+};
+#[cfg(feature = "net")]
+use stegano::explain::{explain_fetch, explain_serve, explain_share};
+use stegano::flac::read_flac_blocks;
+use stegano::format::{detect_file_type, extension_type, find_format, same_file_type, SNIFF_LEN};
+#[cfg(feature = "gui")]
+use stegano::gui::GuiOptions;
+use stegano::header::{
+    open_payload, open_precision, payload_tag, precision_tag, unix_now, PayloadHeader,
+};
+use stegano::hint::{hint_frame, split_hint};
+use stegano::i18n::{environment_language, set_language, DEFAULT_LANGUAGE};
+use stegano::icc::{extract_icc, inject_icc};
+use stegano::ico::read_ico_headers;
+use stegano::jpeg::codes::HuffmanReport;
+use stegano::jpeg::markers::MarkerInventory;
+use stegano::jpeg::quant::{write_quant_report, EncoderHints, QuantTable};
 use stegano::jpeg::utils::read_jpeg_headers;
-use stegano::models::MetaChunk;
-use stegano::utils::{encrypt_payload, xor_encrypt_decrypt};
+use stegano::json::rfc3339;
+use stegano::keychain::{fetch_key, read_key_fd, store_key};
+use stegano::lsb::{lsb_layout, Channels};
+use stegano::meta::{read_metadata, remove_metadata, set_metadata};
+use stegano::models::{summarize_chunks, write_listing, write_page, Page};
+use stegano::mp3::read_mp3_frames;
+use stegano::mp4::read_mp4_boxes;
+use stegano::output::{emit, write_payload, Output, OutputFormat, OutputWriter};
+use stegano::png::{
+    check_metadata_kept, chunks, encode_grayscale, insert_chunk, is_interlaced, print_chunk,
+    read_chunks, remove_chunks, validate_chunk_name, ChunkFilter, Ihdr, OffsetSpec, PngImage,
+    RawChunk,
+};
+use stegano::polyglot::{extract_polyglot, write_polyglot, PNG_SIGNATURE};
+use stegano::provenance::{digest_hex, print_provenance, provenance_chain};
+use stegano::qr::{decode_image, QrCode};
+use stegano::quality::{quality_metrics, QualityMetrics};
+use stegano::rekey::rekey_bytes;
+use stegano::report::{diff_report, encrypt_report, show_meta_report, split_report};
+#[cfg(feature = "net")]
+use stegano::serve::{fetch, fetched_payload, serve};
+use stegano::shamir::{combine_shares, split_secret, Share, SHARE_MAGIC};
+use stegano::shard::{join_shards, shard_path, split_payload, Shard, SHARD_MAGIC};
+#[cfg(feature = "net")]
+use stegano::share::{decrypt_command, provider_url, upload};
+use stegano::soak::run_soak;
+use stegano::spill::SpillBuffer;
+use stegano::storage::{is_remote, map_input, open_input, read_input, Input};
+use stegano::strict::check_strict;
+use stegano::style::{color_choice, colors_enabled, set_color_choice, ColorChoice, Stream, Style};
+use stegano::svg::read_svg_tags;
+use stegano::text::read_text_summary;
+use stegano::tiff::scan_tiff_headers;
+use stegano::trailing::find_trailing;
+use stegano::utils::{
+    decrypt_with, encrypt_stream, encrypt_stream_mode, print_hex, strip_padding, CipherMode,
+    Padding,
+};
+use stegano::vault::{vault_get, vault_list, vault_put, vault_remove};
+use stegano::watermark::{detect_watermark, embed_watermark};
+use stegano::wipe::{overwrite, shred, wipe_payload};
+use stegano::{out, outln, tr};
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Cli::parse();
+fn main() {
+    let json_errors = json_errors_requested();
+    set_color_choice(color_requested());
+    if let Err(err) = set_language(&language_requested()) {
+        eprintln!(
+            "{} {}",
+            Style::Yellow.paint_stderr(tr!("label-warning")),
+            err
+        );
+    }
+    // The first Ctrl-C cancels the running operation, which then removes its partial output.
+    let interrupted = || eprintln!("\n{}", Style::Yellow.paint_stderr(tr!("interrupted")));
+    let error = match install_signal_handler(interrupted) {
+        Ok(cancel) => match run(cancel.clone()) {
+            Ok(()) => return,
+            Err(_) if cancel.is_cancelled() => SteganoError::Cancelled,
+            Err(err) => {
+                // Let clap render its own usage errors, unless JSON was asked for.
+                if let Some(clap_err) = err.downcast_ref::<clap::Error>() {
+                    if !json_errors {
+                        clap_err.exit();
+                    }
+                }
+                SteganoError::classify(&*err)
+            }
+        },
+        Err(err) => SteganoError::classify(&err),
+    };
+    if json_errors {
+        eprintln!("{}", error.to_json());
+    } else if error == SteganoError::Cancelled {
+        eprintln!("{}", Style::Yellow.paint_stderr(tr!("cancelled")));
+    } else {
+        eprintln!(
+            "{} {}",
+            Style::BoldRed.paint_stderr(tr!("label-error")),
+            error
+        );
+    }
+    std::process::exit(error.exit_code());
+}
+
+/// Tells whether `--error-format json` was given, from the raw arguments, so that errors
+/// parsing the other arguments are reported in JSON too.
+fn json_errors_requested() -> bool {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter().enumerate().any(|(i, arg)| {
+        arg == "--error-format=json"
+            || (arg == "--error-format" && args.get(i + 1).is_some_and(|value| value == "json"))
+    })
+}
+
+/// Reads `--color` from the raw arguments, so that errors parsing the other arguments are
+/// colored as asked too. Invalid values are left to clap.
+fn color_requested() -> ColorChoice {
+    let args: Vec<String> = std::env::args().collect();
+    let value = args
+        .iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.strip_prefix("--color") {
+            Some(value) if value.starts_with('=') => Some(&value[1..]),
+            Some("") => args.get(i + 1).map(String::as_str),
+            _ => None,
+        });
+    value
+        .and_then(ColorChoice::parse)
+        .unwrap_or(ColorChoice::Auto)
+}
+
+/// Reads `--lang` from the raw arguments, so that errors parsing the other arguments are
+/// reported in that language too, or else the language of the environment.
+fn language_requested() -> String {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.strip_prefix("--lang") {
+            Some(value) if value.starts_with('=') => Some(value[1..].to_string()),
+            Some("") => args.get(i + 1).cloned(),
+            _ => None,
+        })
+        .or_else(environment_language)
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string())
+}
+
+/// Runs the parsed command, stopping early once `cancel` is cancelled.
+fn run(cancel: CancellationToken) -> Result<(), Box<dyn std::error::Error>> {
+    // Help and version requests aren't errors and exit right away.
+    let color = match color_choice() {
+        ColorChoice::Auto => clap::ColorChoice::Auto,
+        ColorChoice::Always => clap::ColorChoice::Always,
+        ColorChoice::Never => clap::ColorChoice::Never,
+    };
+    let matches = Cli::command()
+        .color(color)
+        .try_get_matches()
+        .or_else(|err| {
+            if err.use_stderr() {
+                Err(err)
+            } else {
+                err.exit()
+            }
+        })?;
+    let mut args = Cli::from_arg_matches(&matches)?;
+    // Defaults from the configuration file fill the flags that weren't given. The config
+    // subcommand reads the file itself, so that it can fix a broken one.
+    if let (Some(command), Some((_, sub_matches))) = (&mut args.command, matches.subcommand()) {
+        if !matches!(command, SteganoCommands::Config(_)) {
+            Config::load()?.apply(command, sub_matches);
+        }
+    }
+
+    // extract and remove each do half of what decrypt does.
+    match &mut args.command {
+        Some(SteganoCommands::Extract(cmd)) => cmd.mode = DecryptMode::Extract,
+        Some(SteganoCommands::Remove(cmd)) => cmd.mode = DecryptMode::Remove,
+        Some(SteganoCommands::Decrypt(cmd)) if !cmd.suppress => eprintln!(
+            "{} {}",
+            Style::Yellow.paint_stderr(tr!("label-warning")),
+            tr!("decrypt-deprecated")
+        ),
+        _ => {}
+    }
+
+    // Where the key of encrypt and decrypt came from: typed, STEGANO_KEY or the default.
+    let key_source = matches
+        .subcommand()
+        .filter(|(name, _)| matches!(*name, "encrypt" | "extract" | "remove" | "decrypt"))
+        .and_then(|(_, sub)| sub.value_source("key"));
 
     // Run the CLI.
     match args.command {
         Some(command) => match command {
-            SteganoCommands::Encrypt(encrypt_cmd) => {
-                let mut file = File::open(encrypt_cmd.input.clone())?;
-
-                let mut meta_chunk = MetaChunk::new(&mut file, encrypt_cmd.suppress)
-                    .expect("Error processing the png file!");
-
-                let mut file_writer = File::create(encrypt_cmd.output.clone())?;
-                let encrypted_data: Vec<u8> = match (*encrypt_cmd.algorithm.to_lowercase()).into() {
-                    "aes" => encrypt_payload(&encrypt_cmd.key, &encrypt_cmd.payload),
-                    "xor" => xor_encrypt_decrypt(encrypt_cmd.payload.as_bytes(), &encrypt_cmd.key),
-                    _ => {
-                        return Err("Unsupported algorithm!".into());
+            SteganoCommands::Encrypt(mut encrypt_cmd) => {
+                if encrypt_cmd.robust {
+                    encrypt_cmd.method = String::from("robust");
+                }
+                if encrypt_cmd.disguise.is_some() {
+                    if !matches!(
+                        encrypt_cmd.method.to_lowercase().as_str(),
+                        "auto" | "disguise"
+                    ) {
+                        return Err(SteganoError::Usage(
+                            "--disguise picks the disguise method, it can't be combined with another --method!"
+                                .into(),
+                        )
+                        .into());
+                    }
+                    encrypt_cmd.method = String::from("disguise");
+                }
+                if encrypt_cmd.in_place {
+                    encrypt_cmd.output = encrypt_cmd.input.clone();
+                }
+                if !encrypt_cmd.recipients.is_empty() {
+                    // age1... keys imply age, certificate files OpenPGP.
+                    let age_keys = encrypt_cmd
+                        .recipients
+                        .iter()
+                        .filter(|recipient| recipient.starts_with("age1"))
+                        .count();
+                    encrypt_cmd.algorithm = match encrypt_cmd.algorithm.to_lowercase().as_str() {
+                        "aes" | "age" if age_keys == encrypt_cmd.recipients.len() => {
+                            String::from("age")
+                        }
+                        "aes" | "pgp" | "openpgp" if age_keys == 0 => String::from("pgp"),
+                        _ => {
+                            return Err(SteganoError::Usage(format!(
+                                "--recipient takes age1... public keys with --algo age, or OpenPGP certificate files with --algo pgp, not {}!",
+                                encrypt_cmd.recipients.join(", ")
+                            ))
+                            .into());
+                        }
+                    };
+                }
+                encrypt_cmd.key = resolve_key(
+                    &encrypt_cmd.key,
+                    encrypt_cmd.key_fd,
+                    encrypt_cmd.key_id.as_deref(),
+                    key_source,
+                )?;
+                let _keep_mtime = encrypt_cmd
+                    .keep_mtime
+                    .then(|| KeepModified::new(&encrypt_cmd.input))
+                    .transpose()?;
+                // Read before the carrier is, which may move its access time.
+                let _keep_times = (encrypt_cmd.preserve_times
+                    && encrypt_cmd.split_across.is_empty())
+                .then(|| KeepTimes::new(&encrypt_cmd.input, &encrypt_cmd.output))
+                .transpose()?;
+                let carrier = encrypt_cmd
+                    .split_across
+                    .first()
+                    .unwrap_or(&encrypt_cmd.input);
+                encrypt_cmd.r#type =
+                    resolve_type(&encrypt_cmd.r#type, &encrypt_cmd.method, carrier)?;
+                // JPEG payloads only go in the ICC profile.
+                if matches!(encrypt_cmd.r#type.to_lowercase().as_str(), "jpeg" | "jpg")
+                    && encrypt_cmd.method.eq_ignore_ascii_case("auto")
+                {
+                    encrypt_cmd.method = String::from("icc");
+                }
+                encrypt_cmd.json |= OutputFormat::resolve(&encrypt_cmd.format, OutputFormat::Json)?
+                    == OutputFormat::Json;
+                // Keep stdout clean for the JSON document.
+                if encrypt_cmd.explain && encrypt_cmd.json {
+                    eprintln!("{}", explain_encrypt(&encrypt_cmd));
+                } else if encrypt_cmd.explain {
+                    outln!("{}", explain_encrypt(&encrypt_cmd));
+                }
+                encrypt_cmd.suppress |= encrypt_cmd.json;
+                if encrypt_cmd.strict {
+                    let carriers = if encrypt_cmd.split_across.is_empty() {
+                        std::slice::from_ref(&encrypt_cmd.input)
+                    } else {
+                        &encrypt_cmd.split_across[..]
+                    };
+                    for path in carriers {
+                        check_strict(&map_input(path)?, &encrypt_cmd.r#type)?;
+                    }
+                }
+                if let Some(path) = &encrypt_cmd.payload_qr {
+                    let data = decode_image(&std::fs::read(path)?)?;
+                    encrypt_cmd.payload = String::from_utf8(data).map_err(|_| {
+                        SteganoError::Format(format!(
+                            "The QR code in {} doesn't hold text, which --payload-qr needs!",
+                            path
+                        ))
+                    })?;
+                    if !encrypt_cmd.suppress {
+                        outln!(
+                            "{} {}",
+                            Style::Grey.paint(tr!("label-payload")),
+                            tr!(
+                                "qr-payload-read",
+                                bytes = encrypt_cmd.payload.len(),
+                                path = path
+                            )
+                        );
+                    }
+                }
+                let payload_text = encrypt_cmd.payload_encoding.decode(&encrypt_cmd.payload)?;
+                if encrypt_cmd.method.eq_ignore_ascii_case("apng")
+                    && encrypt_cmd.offset != OffsetSpec::Auto
+                {
+                    return Err(SteganoError::Usage(
+                        "--offset can't be combined with --method apng, which picks the offset!"
+                            .into(),
+                    )
+                    .into());
+                }
+                // Only these methods change pixels, whose distortion is measured.
+                let changes_pixels =
+                    matches!(encrypt_cmd.method.to_lowercase().as_str(), "lsb" | "robust");
+                if encrypt_cmd.preserve_metadata {
+                    if !encrypt_cmd.r#type.eq_ignore_ascii_case("png") {
+                        return Err(SteganoError::Usage(
+                            "--preserve-metadata only applies to PNG carriers!".into(),
+                        )
+                        .into());
+                    }
+                    if encrypt_cmd.method.eq_ignore_ascii_case("precision") {
+                        return Err(SteganoError::Usage(
+                            "--preserve-metadata can't be combined with --method precision, which rewrites the gAMA, cHRM and pHYs chunks!"
+                                .into(),
+                        )
+                        .into());
+                    }
+                    if encrypt_cmd.method.eq_ignore_ascii_case("icc") {
+                        return Err(SteganoError::Usage(
+                            "--preserve-metadata can't be combined with --method icc, which rewrites the iCCP chunk!"
+                                .into(),
+                        )
+                        .into());
+                    }
+                }
+                if encrypt_cmd.deinterlace && !changes_pixels {
+                    return Err(SteganoError::Usage(
+                        "--deinterlace only applies to the lsb and robust methods, the others keep the image data as is!"
+                            .into(),
+                    )
+                    .into());
+                }
+                if encrypt_cmd.traversal != "scatter"
+                    && !encrypt_cmd.method.eq_ignore_ascii_case("lsb")
+                {
+                    return Err(SteganoError::Usage(
+                        "--traversal only applies to the lsb method, the others don't walk the pixels!"
+                            .into(),
+                    )
+                    .into());
+                }
+                if (encrypt_cmd.channels != Channels::default()
+                    || encrypt_cmd.bits_per_channel.is_some())
+                    && !encrypt_cmd.method.eq_ignore_ascii_case("lsb")
+                {
+                    return Err(SteganoError::Usage(
+                        "--channels and --bits-per-channel only apply to the lsb method, the others don't pick samples!"
+                            .into(),
+                    )
+                    .into());
+                }
+                if encrypt_cmd.max_distortion.is_some() && !changes_pixels {
+                    return Err(SteganoError::Usage(
+                        "--max-distortion only applies to the lsb and robust methods, the others leave the pixels alone!"
+                            .into(),
+                    )
+                    .into());
+                }
+                // Encrypt the payload into a buffer that spills to disk above --max-memory.
+                let mut payload = SpillBuffer::new(encrypt_cmd.max_memory.unwrap_or(usize::MAX));
+                let mut payload_len = 0;
+                if let Some(hint) = &encrypt_cmd.hint {
+                    if matches!(
+                        encrypt_cmd.method.to_lowercase().as_str(),
+                        "precision" | "lsb" | "robust"
+                    ) {
+                        return Err(SteganoError::Usage(
+                            "Hints aren't supported by the precision, LSB and robust methods!"
+                                .into(),
+                        )
+                        .into());
                     }
+                    eprintln!(
+                        "{} {}",
+                        Style::Yellow.paint_stderr(tr!("label-warning")),
+                        tr!("hint-plaintext")
+                    );
+                    let frame = hint_frame(hint)?;
+                    payload.write_all(&frame)?;
+                    payload_len += frame.len() as u64;
+                }
+                // The payload tag covers the header and the ciphertext, not the hint.
+                let sealed_from = payload_len;
+                let mut mode = CipherMode::Ecb;
+                let mut padding = Padding::Zeros;
+                // Age files and OpenPGP messages stand on their own, without a header or a tag
+                // of ours.
+                let age = is_age_algorithm(&encrypt_cmd.algorithm);
+                let pgp = is_pgp_algorithm(&encrypt_cmd.algorithm);
+                if age || pgp {
+                    if encrypt_cmd.method.eq_ignore_ascii_case("precision") {
+                        return Err(SteganoError::Usage(
+                            "The precision method has no room for an age file or OpenPGP message!"
+                                .into(),
+                        )
+                        .into());
+                    }
+                    if encrypt_cmd.mime_type.is_some()
+                        || encrypt_cmd.nest
+                        || encrypt_cmd.filename.is_some()
+                        || encrypt_cmd.expires.is_some()
+                        || encrypt_cmd.views.is_some()
+                    {
+                        return Err(SteganoError::Usage(
+                            "Age and OpenPGP payloads have no payload header to record a MIME type, filename, expiry or view counter in!"
+                                .into(),
+                        )
+                        .into());
+                    }
+                } else if encrypt_cmd.method.eq_ignore_ascii_case("precision") {
+                    if encrypt_cmd.algorithm.eq_ignore_ascii_case("chacha") {
+                        return Err(SteganoError::Usage(
+                            "The precision method has no room for the nonce of ChaCha20, use xor!"
+                                .into(),
+                        )
+                        .into());
+                    }
+                    if encrypt_cmd.mime_type.is_some()
+                        || encrypt_cmd.nest
+                        || encrypt_cmd.filename.is_some()
+                        || encrypt_cmd.expires.is_some()
+                        || encrypt_cmd.views.is_some()
+                    {
+                        return Err(SteganoError::Usage(
+                            "The precision method has no room for a payload header!".into(),
+                        )
+                        .into());
+                    }
+                } else {
+                    let plaintext_len = match &encrypt_cmd.payload_file {
+                        Some(path) => std::fs::metadata(path)?.len(),
+                        None => payload_text.len() as u64,
+                    };
+                    let mut header =
+                        PayloadHeader::new(&encrypt_cmd.algorithm, plaintext_len, unix_now())
+                            .with_mode(&encrypt_cmd.block_mode)?;
+                    mode = header.mode;
+                    padding = header.padding;
+                    header.mime_type = encrypt_cmd.mime_type.clone();
+                    if let (true, Some(path)) = (encrypt_cmd.nest, &encrypt_cmd.payload_file) {
+                        let mut magic = Vec::new();
+                        File::open(path)?.take(8).read_to_end(&mut magic)?;
+                        let mime_type = carrier_mime_type(&magic).ok_or_else(|| {
+                            SteganoError::Usage(format!(
+                                "{} isn't a PNG, TIFF or ICO file, which --nest needs!",
+                                path
+                            ))
+                        })?;
+                        header.mime_type = Some(mime_type.to_string());
+                    }
+                    header.filename = encrypt_cmd.filename.clone();
+                    if let Some(expires) = encrypt_cmd.expires {
+                        if expires <= unix_now() {
+                            return Err(
+                                SteganoError::Usage("The expiry is in the past!".into()).into()
+                            );
+                        }
+                        header.expires = expires;
+                    }
+                    let header = header.with_views(encrypt_cmd.views.unwrap_or(0));
+                    let header = header.to_bytes(&encrypt_cmd.key)?;
+                    payload.write_all(&header)?;
+                    payload_len += header.len() as u64;
+                }
+                payload_len += match &encrypt_cmd.payload_file {
+                    Some(path) if age => age_encrypt(
+                        &encrypt_cmd.recipients,
+                        &encrypt_cmd.key,
+                        cancel.reader(File::open(path)?),
+                        &mut payload,
+                    )?,
+                    None if age => age_encrypt(
+                        &encrypt_cmd.recipients,
+                        &encrypt_cmd.key,
+                        payload_text.as_slice(),
+                        &mut payload,
+                    )?,
+                    Some(path) if pgp => pgp_encrypt(
+                        &encrypt_cmd.recipients,
+                        cancel.reader(File::open(path)?),
+                        &mut payload,
+                    )?,
+                    None if pgp => pgp_encrypt(
+                        &encrypt_cmd.recipients,
+                        payload_text.as_slice(),
+                        &mut payload,
+                    )?,
+                    Some(path) => encrypt_stream_mode(
+                        &encrypt_cmd.algorithm,
+                        &encrypt_cmd.key,
+                        &mode,
+                        padding,
+                        cancel.reader(File::open(path)?),
+                        &mut payload,
+                    )?,
+                    None => encrypt_stream_mode(
+                        &encrypt_cmd.algorithm,
+                        &encrypt_cmd.key,
+                        &mode,
+                        padding,
+                        payload_text.as_slice(),
+                        &mut payload,
+                    )?,
+                };
+                if !encrypt_cmd.method.eq_ignore_ascii_case("precision") && !age && !pgp {
+                    payload.seek(io::SeekFrom::Start(sealed_from))?;
+                    let tag = payload_tag(&encrypt_cmd.key, &mut payload)?;
+                    payload.write_all(&tag)?;
+                    payload_len += tag.len() as u64;
+                } else if !age && !pgp {
+                    // Precision payloads only have room for a check byte.
+                    payload.seek(io::SeekFrom::Start(sealed_from))?;
+                    let mut ciphertext = Vec::new();
+                    payload.read_to_end(&mut ciphertext)?;
+                    let check = precision_tag(&encrypt_cmd.key, &ciphertext);
+                    payload.write_all(&check)?;
+                    payload_len += check.len() as u64;
+                }
+                payload.rewind()?;
+                // The descriptor records where the payload lands, checked against this digest.
+                let payload_digest = match &encrypt_cmd.descriptor {
+                    Some(_) if !encrypt_cmd.split_across.is_empty() => {
+                        return Err(SteganoError::Usage(
+                            "Descriptors can't be combined with --split-across!".into(),
+                        )
+                        .into());
+                    }
+                    Some(_)
+                        if matches!(
+                            encrypt_cmd.method.to_lowercase().as_str(),
+                            "precision" | "lsb" | "robust"
+                        ) =>
+                    {
+                        return Err(SteganoError::Usage(
+                            "Descriptors need a method that stores the payload in one piece!"
+                                .into(),
+                        )
+                        .into());
+                    }
+                    Some(_) => Some(sha256_prefix(&mut payload, payload_len)?),
+                    None => None,
                 };
-                // Calculate CRC for the encrypted data
-                let mut bytes_msb = Vec::new();
-                bytes_msb
-                    .write_all(&meta_chunk.chk.r#type.to_be_bytes())
-                    .unwrap();
-                bytes_msb.write_all(&encrypted_data).unwrap();
-                let crc = crc32_little(meta_chunk.chk.crc, &bytes_msb);
 
-                // Update the MetaChunk with the encrypted data and CRC
-                meta_chunk.chk.data = encrypted_data.clone();
-                meta_chunk.chk.crc = crc;
+                if encrypt_cmd.shamir.is_some() && encrypt_cmd.split_across.is_empty() {
+                    return Err(SteganoError::Usage(
+                        "Shamir shares are written to the carriers of --split-across!".into(),
+                    )
+                    .into());
+                }
+                if !encrypt_cmd.split_across.is_empty() {
+                    if encrypt_cmd.provenance {
+                        return Err(SteganoError::Usage(
+                            "Provenance links can't be combined with --split-across!".into(),
+                        )
+                        .into());
+                    }
+                    let opts = encrypt_cmd.codec_options(&cancel);
+                    let payload = payload.to_vec()?;
+                    // (label, data length, serialized piece) for each carrier, in order.
+                    let pieces: Vec<(String, usize, Vec<u8>)> = match encrypt_cmd.shamir {
+                        Some(threshold) => {
+                            if threshold.shares as usize != encrypt_cmd.split_across.len() {
+                                return Err(SteganoError::Usage(format!(
+                                    "--shamir {} needs {} carriers in --split-across, got {}!",
+                                    threshold,
+                                    threshold.shares,
+                                    encrypt_cmd.split_across.len()
+                                ))
+                                .into());
+                            }
+                            split_secret(&payload, threshold)?
+                                .into_iter()
+                                .map(|share| {
+                                    (
+                                        tr!(
+                                            "share-label",
+                                            index = share.index + 1,
+                                            total = share.total,
+                                            needed = share.threshold
+                                        ),
+                                        share.data.len(),
+                                        share.to_bytes(),
+                                    )
+                                })
+                                .collect()
+                        }
+                        None => split_payload(&payload, encrypt_cmd.split_across.len())?
+                            .into_iter()
+                            .map(|shard| {
+                                (
+                                    tr!(
+                                        "shard-label",
+                                        index = shard.index + 1,
+                                        total = shard.total
+                                    ),
+                                    shard.data.len(),
+                                    shard.to_bytes(),
+                                )
+                            })
+                            .collect(),
+                    };
+                    let mut written = Vec::new();
+                    for (index, ((label, data_len, piece), carrier)) in
+                        pieces.iter().zip(&encrypt_cmd.split_across).enumerate()
+                    {
+                        let output = shard_path(&encrypt_cmd.output, index);
+                        let _keep_times = encrypt_cmd
+                            .preserve_times
+                            .then(|| KeepTimes::new(carrier, &output))
+                            .transpose()?;
+                        let data = read_input(carrier)?;
+                        let stego = embed_encrypted(&data, piece, &opts)
+                            .map_err(|err| format!("{}: {}", carrier, err))?;
+                        let quality = changes_pixels
+                            .then(|| check_quality(&encrypt_cmd, &data, &stego))
+                            .transpose()
+                            .map_err(|err| format!("{}: {}", carrier, err))?;
+                        let output_len =
+                            write_stego(&encrypt_cmd, carrier, &output, &stego, &cancel)?;
+                        if !encrypt_cmd.suppress {
+                            outln!(
+                                "{}",
+                                if encrypt_cmd.dry_run {
+                                    tr!(
+                                        "piece-would-be-written",
+                                        label = label,
+                                        bytes = data_len,
+                                        path = output
+                                    )
+                                } else {
+                                    tr!(
+                                        "piece-written",
+                                        label = label,
+                                        bytes = data_len,
+                                        path = output
+                                    )
+                                }
+                            );
+                            if let Some(quality) = quality {
+                                outln!("{} {}", Style::Grey.paint(tr!("label-quality")), quality);
+                            }
+                        }
+                        written.push((carrier.clone(), output, output_len));
+                    }
+                    if encrypt_cmd.json {
+                        outln!("{}", split_report(&encrypt_cmd, payload_len, &written)?);
+                    } else if encrypt_cmd.dry_run {
+                        outln!(
+                            "{} {}",
+                            Style::Grey.paint(tr!("label-dry-run")),
+                            tr!("dry-run-nothing-written")
+                        );
+                    } else if let Some(threshold) = encrypt_cmd.shamir {
+                        outln!(
+                            "{}",
+                            tr!(
+                                "split-shares",
+                                shares = threshold.shares,
+                                needed = threshold.needed
+                            )
+                        );
+                    } else {
+                        outln!("{}", tr!("split-carriers", carriers = written.len()));
+                    }
+                    return Ok(());
+                }
 
-                // Create a new mutable reference to file_reader
-                let mut file_reader = &file;
+                if encrypt_cmd.provenance && encrypt_cmd.method.to_lowercase() != "append" {
+                    return Err(SteganoError::Usage(
+                        "Provenance links are only supported with --method append!".into(),
+                    )
+                    .into());
+                }
+                if encrypt_cmd.method.to_lowercase() == "append" {
+                    let mut file_writer = create_output(&encrypt_cmd, &cancel)?;
+                    let carrier = cancel.reader(open_input(&encrypt_cmd.input)?);
+                    if encrypt_cmd.provenance {
+                        let parent = write_appended_linked(
+                            carrier,
+                            &mut payload,
+                            payload_len,
+                            &mut file_writer,
+                        )?;
+                        if !encrypt_cmd.suppress {
+                            outln!("{}", tr!("provenance-linked", digest = digest_hex(&parent)));
+                        }
+                    } else {
+                        write_appended(carrier, &mut payload, payload_len, &mut file_writer)?;
+                    }
+                    let output_len = file_writer.written();
+                    file_writer.commit()?;
+                    // The carrier is copied whole in front of the frame, which may be remote.
+                    let digest_len = if encrypt_cmd.provenance {
+                        DIGEST_LEN
+                    } else {
+                        0
+                    };
+                    let carrier_len = output_len - payload_len - TRAILER_LEN - digest_len;
+                    write_descriptor(
+                        &encrypt_cmd,
+                        payload_digest,
+                        carrier_len,
+                        payload_len,
+                        &cancel,
+                    )?;
+                    print_encrypt_result(
+                        &encrypt_cmd,
+                        payload_len,
+                        output_len,
+                        Some(carrier_len),
+                        None,
+                        &tr!("encrypt-appended"),
+                    )?;
+                    return Ok(());
+                }
 
-                meta_chunk.write_encrypted_data(&mut file_reader, &encrypt_cmd, &mut file_writer);
+                // Every carrier goes through its format, which a registered one overrides.
+                let format = find_format(&encrypt_cmd.r#type).ok_or_else(|| {
+                    SteganoError::Usage(format!(
+                        "{} carriers aren't supported!",
+                        encrypt_cmd.r#type.to_uppercase()
+                    ))
+                })?;
+                let method = encrypt_cmd.method.to_lowercase();
+                let opts = encrypt_cmd.codec_options(&cancel);
+                let carrier = map_input(&encrypt_cmd.input)?;
+                let chunked =
+                    format.name() == "png" && matches!(method.as_str(), "auto" | "chunk" | "apng");
+                if chunked && !encrypt_cmd.suppress {
+                    print_png_header(&carrier)?;
+                }
+                if changes_pixels {
+                    // The whole image is re-encoded, and measured before it's written.
+                    let stego = deinterlace(
+                        &encrypt_cmd,
+                        format.embed(&carrier, &payload.to_vec()?, &opts)?,
+                    )?;
+                    let quality = check_quality(&encrypt_cmd, &carrier, &stego)?;
+                    let message = if method == "lsb" {
+                        // Reported with the quality it costs, when the fewest bits that fit were used.
+                        let bits = lsb_layout(&stego, &encrypt_cmd.key, encrypt_cmd.channels)?.bits;
+                        encrypt_cmd.bits_per_channel = Some(bits as u8);
+                        tr!("encrypt-lsb", bits = bits)
+                    } else {
+                        tr!("encrypt-dct")
+                    };
+                    let output_len = write_stego(
+                        &encrypt_cmd,
+                        &encrypt_cmd.input,
+                        &encrypt_cmd.output,
+                        &stego,
+                        &cancel,
+                    )?;
+                    print_encrypt_result(
+                        &encrypt_cmd,
+                        payload_len,
+                        output_len,
+                        None,
+                        Some(&quality),
+                        &message,
+                    )?;
+                    return Ok(());
+                }
+                let (output_len, offset) = if encrypt_cmd.preserve_metadata {
+                    // The ancillary chunks are checked before anything is written.
+                    let mut stego = Vec::new();
+                    let offset = format.embed_stream(
+                        &carrier,
+                        &mut payload,
+                        payload_len,
+                        &mut stego,
+                        &opts,
+                    )?;
+                    let output_len = write_stego(
+                        &encrypt_cmd,
+                        &encrypt_cmd.input,
+                        &encrypt_cmd.output,
+                        &stego,
+                        &cancel,
+                    )?;
+                    (output_len, offset)
+                } else {
+                    let mut file_writer = create_output(&encrypt_cmd, &cancel)?;
+                    let offset = format.embed_stream(
+                        &carrier,
+                        &mut payload,
+                        payload_len,
+                        &mut file_writer,
+                        &opts,
+                    )?;
+                    let output_len = file_writer.written();
+                    file_writer.commit()?;
+                    (output_len, offset)
+                };
+                if let Some(offset) = offset {
+                    write_descriptor(&encrypt_cmd, payload_digest, offset, payload_len, &cancel)?;
+                }
+                let message = match (offset, method.as_str()) {
+                    // The chunk data follows its length and type.
+                    (Some(offset), _) if chunked => {
+                        let offset = offset - 8;
+                        if !encrypt_cmd.suppress {
+                            let name = validate_chunk_name(
+                                &encrypt_cmd.chunk_name,
+                                &read_chunks(&carrier)?,
+                            )?;
+                            print_chunk(&name, &payload.to_vec()?, offset as usize);
+                        }
+                        tr!("encrypt-written-at", offset = offset)
+                    }
+                    (_, "precision") => tr!("encrypt-precision"),
+                    (_, "disguise") => tr!(
+                        "encrypt-disguised",
+                        disguise = Disguise::new(&opts.disguise)?.description()
+                    ),
+                    (_, "interleave") => tr!("encrypt-interleaved"),
+                    (_, "icc") => tr!("encrypt-icc"),
+                    _ => tr!("encrypt-written"),
+                };
+                let offset = offset.map(|offset| if chunked { offset - 8 } else { offset });
+                print_encrypt_result(
+                    &encrypt_cmd,
+                    payload_len,
+                    output_len,
+                    offset,
+                    None,
+                    &message,
+                )?;
             }
-            SteganoCommands::Decrypt(decrypt_cmd) => {
-                let mut file = File::open(decrypt_cmd.input.clone())?;
-
-                let mut meta_chunk = MetaChunk::new(&mut file, decrypt_cmd.suppress)
-                    .expect("Error processing the png file!");
+            SteganoCommands::Extract(mut decrypt_cmd)
+            | SteganoCommands::Remove(mut decrypt_cmd)
+            | SteganoCommands::Decrypt(mut decrypt_cmd) => {
+                if decrypt_cmd.robust {
+                    decrypt_cmd.method = String::from("robust");
+                }
+                if decrypt_cmd.in_place {
+                    decrypt_cmd.output = decrypt_cmd.input.clone();
+                }
+                decrypt_cmd.key = resolve_key(
+                    &decrypt_cmd.key,
+                    decrypt_cmd.key_fd,
+                    decrypt_cmd.key_id.as_deref(),
+                    key_source,
+                )?;
+                let _keep_mtime = decrypt_cmd
+                    .keep_mtime
+                    .then(|| KeepModified::new(&decrypt_cmd.input))
+                    .transpose()?;
+                let stego = decrypt_cmd.join.first().unwrap_or(&decrypt_cmd.input);
+                decrypt_cmd.r#type = resolve_type(&decrypt_cmd.r#type, &decrypt_cmd.method, stego)?;
+                if matches!(decrypt_cmd.r#type.to_lowercase().as_str(), "jpeg" | "jpg")
+                    && decrypt_cmd.method.eq_ignore_ascii_case("auto")
+                {
+                    decrypt_cmd.method = String::from("icc");
+                }
+                let format = OutputFormat::resolve(&decrypt_cmd.format, OutputFormat::Raw)?;
+                // Only the payload may reach stdout in raw mode.
+                decrypt_cmd.suppress |= format == OutputFormat::Raw;
+                if decrypt_cmd.explain && format == OutputFormat::Raw {
+                    eprintln!("{}", explain_decrypt(&decrypt_cmd));
+                } else if decrypt_cmd.explain {
+                    outln!("{}", explain_decrypt(&decrypt_cmd));
+                }
+                if decrypt_cmd.strict {
+                    let files = if decrypt_cmd.join.is_empty() {
+                        std::slice::from_ref(&decrypt_cmd.input)
+                    } else {
+                        &decrypt_cmd.join[..]
+                    };
+                    for path in files {
+                        check_strict(&map_input(path)?, &decrypt_cmd.r#type)?;
+                    }
+                }
+                check_mode(&decrypt_cmd)?;
+                check_wipe(&decrypt_cmd)?;
+                let opts = decrypt_cmd.codec_options(&cancel);
+                if let Some(path) = &decrypt_cmd.descriptor {
+                    let descriptor =
+                        Descriptor::open(&std::fs::read_to_string(path)?, &decrypt_cmd.key)?;
+                    let payload = descriptor.read_payload(&mut Input::open(&decrypt_cmd.input)?)?;
+                    if !decrypt_cmd.suppress {
+                        outln!(
+                            "{}",
+                            tr!(
+                                "descriptor-read",
+                                bytes = descriptor.length,
+                                offset = descriptor.offset,
+                                file_type = descriptor.file_type,
+                                method = descriptor.method
+                            )
+                        );
+                    }
+                    let (header, decrypted_data) = open_secret(&payload, &decrypt_cmd, &cancel)?;
+                    emit_secret(&header, decrypted_data, format, &decrypt_cmd, &cancel)?;
+                    return Ok(());
+                }
+                if decrypt_cmd.burn {
+                    let burned = burn_bytes(&read_input(&decrypt_cmd.input)?, &opts).map_err(
+                        |err| match err.kind() {
+                            io::ErrorKind::InvalidInput => {
+                                SteganoError::Usage(err.to_string()).into()
+                            }
+                            _ => err,
+                        },
+                    )?;
+                    // Expired payloads are refused before a view is counted.
+                    let (mut header, decrypted_data) =
+                        open_secret(&burned.embedded, &decrypt_cmd, &cancel)?;
+                    write_output(&decrypt_cmd.input, &burned.stego, &cancel)?;
+                    if let Some(header) = header.as_mut() {
+                        header.views = burned.views;
+                    }
+                    if !decrypt_cmd.suppress {
+                        outln!(
+                            "{}",
+                            match burned.views {
+                                0 => tr!("burn-wiped", path = decrypt_cmd.input),
+                                views => tr!("burn-views-left", views = views),
+                            }
+                        );
+                    }
+                    emit_secret(&header, decrypted_data, format, &decrypt_cmd, &cancel)?;
+                    return Ok(());
+                }
+                if decrypt_cmd.exact {
+                    let stego = read_input(&decrypt_cmd.input)?;
+                    let (payload, restored) = extract_encrypted(&stego, &opts)?;
+                    verify_restored(&stego, &restored, &payload, &opts)?;
+                    let (header, decrypted_data) = open_secret(&payload, &decrypt_cmd, &cancel)?;
+                    write_restored(&decrypt_cmd, &restored, &cancel)?;
+                    if !decrypt_cmd.suppress {
+                        outln!("{}", tr!("restored-identical"));
+                    }
+                    emit_secret(&header, decrypted_data, format, &decrypt_cmd, &cancel)?;
+                    return Ok(());
+                }
+                if !decrypt_cmd.join.is_empty() {
+                    let mut shards = Vec::new();
+                    let mut shares = Vec::new();
+                    let mut carriers = Vec::new();
+                    for path in &decrypt_cmd.join {
+                        let (bytes, carrier) = extract_encrypted(&read_input(path)?, &opts)
+                            .map_err(|err| format!("{}: {}", path, err))?;
+                        if bytes.starts_with(SHARE_MAGIC) {
+                            let share =
+                                Share::parse(&bytes).map_err(|err| format!("{}: {}", path, err))?;
+                            if !decrypt_cmd.suppress {
+                                outln!(
+                                    "{}",
+                                    tr!(
+                                        "found-share",
+                                        index = share.index + 1,
+                                        total = share.total,
+                                        needed = share.threshold,
+                                        path = path
+                                    )
+                                );
+                            }
+                            carriers.push((share.index as usize, carrier));
+                            shares.push(share);
+                            continue;
+                        }
+                        let shard =
+                            Shard::parse(&bytes).map_err(|err| format!("{}: {}", path, err))?;
+                        if !decrypt_cmd.suppress {
+                            outln!(
+                                "{}",
+                                tr!(
+                                    "found-shard",
+                                    index = shard.index + 1,
+                                    total = shard.total,
+                                    path = path
+                                )
+                            );
+                        }
+                        carriers.push((shard.index as usize, carrier));
+                        shards.push(shard);
+                    }
+                    let payload = match (shards.is_empty(), shares.is_empty()) {
+                        (_, true) => join_shards(&shards)?,
+                        (true, false) => combine_shares(&shares)?,
+                        (false, false) => {
+                            return Err(SteganoError::Format(
+                                "The files mix --split-across shards and --shamir shares!".into(),
+                            )
+                            .into());
+                        }
+                    };
+                    let (header, decrypted_data) = open_secret(&payload, &decrypt_cmd, &cancel)?;
+                    // The overwritten bits can't be restored, as with a single carrier.
+                    if decrypt_cmd.mode.restores() && restores_carrier(&decrypt_cmd.method) {
+                        for (index, carrier) in carriers {
+                            write_output(
+                                shard_path(&decrypt_cmd.output, index),
+                                &carrier,
+                                &cancel,
+                            )?;
+                        }
+                    }
+                    emit_secret(&header, decrypted_data, format, &decrypt_cmd, &cancel)?;
+                    return Ok(());
+                }
+                if decrypt_cmd.method.to_lowercase() == "append" {
+                    let mut file = Input::open(&decrypt_cmd.input)?;
+                    let payload = extract_appended(&mut file)?;
+                    let (header, decrypted_data) = open_secret(&payload, &decrypt_cmd, &cancel)?;
+                    if decrypt_cmd.mode.restores() {
+                        let mut file_writer = OutputFile::create(&decrypt_cmd.output, &cancel)?;
+                        strip_appended(&mut file, &mut file_writer)?;
+                        file_writer.commit()?;
+                        restored_note(&decrypt_cmd);
+                    }
+                    emit_secret(&header, decrypted_data, format, &decrypt_cmd, &cancel)?;
+                    return Ok(());
+                }
 
-                let mut file_writer = File::create(decrypt_cmd.output.clone()).unwrap();
-                let mut file_reader = &file;
-                meta_chunk.write_decrypted_data(&mut file_reader, &decrypt_cmd, &mut file_writer);
+                // Every carrier goes through its format, which a registered one overrides.
+                let carrier_format = find_format(&decrypt_cmd.r#type).ok_or_else(|| {
+                    SteganoError::Usage(format!(
+                        "{} carriers aren't supported!",
+                        decrypt_cmd.r#type.to_uppercase()
+                    ))
+                })?;
+                let method = decrypt_cmd.method.to_lowercase();
+                let data = map_input(&decrypt_cmd.input)?;
+                let chunked = carrier_format.name() == "png"
+                    && matches!(method.as_str(), "auto" | "chunk" | "apng");
+                if chunked && !decrypt_cmd.suppress {
+                    print_png_header(&data)?;
+                }
+                let (payload, cleaned) = carrier_format.extract(&data, &opts)?;
+                if method == "precision" {
+                    // Precision payloads are too small to carry a header, only a check byte.
+                    let decrypted_data =
+                        open_precision(&payload, &decrypt_cmd.algorithm, &decrypt_cmd.key)?;
+                    reveal(decrypted_data, format, &decrypt_cmd, &cancel)?;
+                    return Ok(());
+                }
+                let (header, decrypted_data) = open_secret(&payload, &decrypt_cmd, &cancel)?;
+                if !decrypt_cmd.suppress && method == "disguise" {
+                    let (_, _, disguise) = extract_disguised(&data)?;
+                    outln!(
+                        "{} {}",
+                        Style::Grey.paint(tr!("label-disguised")),
+                        disguise.description()
+                    );
+                }
+                // The hexdump shows the secret, which --output-payload-file keeps off stdout.
+                if chunked
+                    && !decrypt_cmd.suppress
+                    && decrypt_cmd.mode.reveals()
+                    && decrypt_cmd.output_payload_file.is_none()
+                {
+                    let chunks = read_chunks(&data)?;
+                    let name: [u8; 4] = decrypt_cmd
+                        .chunk_name
+                        .as_bytes()
+                        .try_into()
+                        .map_err(|_| "Chunk names are exactly four ASCII letters!")?;
+                    let offset = match decrypt_cmd.offset.resolve(&data, &chunks)? {
+                        Some(offset) => offset,
+                        None => chunks
+                            .iter()
+                            .find(|chunk| chunk.r#type == name)
+                            .map_or(0, |chunk| chunk.offset),
+                    };
+                    print_chunk(&name, &decrypted_data, offset);
+                }
+                // The lsb and robust payloads overwrite pixels, leaving no carrier to restore.
+                if !matches!(method.as_str(), "lsb" | "robust") {
+                    write_restored(&decrypt_cmd, &cleaned, &cancel)?;
+                }
+                emit_secret(&header, decrypted_data, format, &decrypt_cmd, &cancel)?;
             }
-            SteganoCommands::ShowMeta(show_meta_cmd) => {
+            SteganoCommands::ShowMeta(mut show_meta_cmd) => {
+                show_meta_cmd.r#type =
+                    resolve_type(&show_meta_cmd.r#type, "auto", &show_meta_cmd.input)?;
+                show_meta_cmd.json |=
+                    OutputFormat::resolve(&show_meta_cmd.format, OutputFormat::Json)?
+                        == OutputFormat::Json;
+                // Keep stdout clean for the JSON document.
+                if show_meta_cmd.explain && show_meta_cmd.json {
+                    eprintln!("{}", explain_show_meta(&show_meta_cmd));
+                } else if show_meta_cmd.explain {
+                    outln!("{}", explain_show_meta(&show_meta_cmd));
+                }
+                if show_meta_cmd.strict {
+                    check_strict(&map_input(&show_meta_cmd.input)?, &show_meta_cmd.r#type)?;
+                }
+                if let Some(plane) = show_meta_cmd.bit_plane {
+                    let image = PngImage::decode(&read_input(&show_meta_cmd.input)?)?;
+                    let pixels = image.bit_plane(&show_meta_cmd.channel, plane)?;
+                    write_output(
+                        &show_meta_cmd.output,
+                        &encode_grayscale(image.width, image.height, &pixels)?,
+                        &cancel,
+                    )?;
+                    if !show_meta_cmd.suppress {
+                        outln!(
+                            "{}",
+                            tr!(
+                                "bit-plane-written",
+                                plane = plane,
+                                channel = show_meta_cmd.channel,
+                                path = show_meta_cmd.output
+                            )
+                        );
+                    }
+                    return Ok(());
+                }
+                if let Some(path) = &show_meta_cmd.extract_trailing {
+                    let data = map_input(&show_meta_cmd.input)?;
+                    let Some(trailing) = find_trailing(&data, &show_meta_cmd.r#type)? else {
+                        return Err(io::Error::new(
+                            io::ErrorKind::NotFound,
+                            tr!("trailing-none", path = show_meta_cmd.input),
+                        )
+                        .into());
+                    };
+                    write_output(path, trailing.data, &cancel)?;
+                    if !show_meta_cmd.suppress {
+                        outln!(
+                            "{}",
+                            tr!(
+                                "trailing-written",
+                                bytes = trailing.data.len(),
+                                terminator = trailing.terminator,
+                                path = path
+                            )
+                        );
+                    }
+                    return Ok(());
+                }
+                let filter = ChunkFilter::new(&show_meta_cmd.filter, show_meta_cmd.grep.as_deref())
+                    .map_err(|err| SteganoError::Usage(err.to_string()))?;
+                if !filter.is_empty() && show_meta_cmd.r#type.to_lowercase() != "png" {
+                    return Err(SteganoError::Usage(
+                        "--filter and --grep only apply to PNG chunks!".into(),
+                    )
+                    .into());
+                }
+                if show_meta_cmd.json {
+                    outln!(
+                        "{}",
+                        show_meta_report(&show_meta_cmd.input, &show_meta_cmd.r#type, &filter)?
+                    );
+                    return Ok(());
+                }
                 if show_meta_cmd.r#type.to_lowercase() == "jpeg" {
                     let _ = read_jpeg_headers(
                         &show_meta_cmd.input.clone(),
@@ -62,16 +1205,1754 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         show_meta_cmd.end_chunk,
                         show_meta_cmd.nb_chunks,
                     );
+                    let data = map_input(&show_meta_cmd.input)?;
+                    let inventory = MarkerInventory::parse(&data)?;
+                    inventory.print();
+                    if !show_meta_cmd.suppress {
+                        // Damaged tables are left to the marker listing.
+                        if let Ok(tables) = QuantTable::read_all(&data, &inventory) {
+                            write_quant_report(
+                                &tables,
+                                &EncoderHints::read(&data, &inventory),
+                                &mut OutputWriter::default(),
+                            )?;
+                        }
+                        match HuffmanReport::analyze(&data, &inventory) {
+                            Ok(report) => report.write_report(&mut OutputWriter::default())?,
+                            Err(err) => {
+                                outln!("{} {}", Style::Yellow.paint(tr!("label-warning")), err)
+                            }
+                        }
+                    }
+                    if let (false, Some(trailing)) =
+                        (show_meta_cmd.suppress, find_trailing(&data, "jpeg")?)
+                    {
+                        trailing.write_report(&mut OutputWriter::default())?;
+                    }
+                } else if show_meta_cmd.r#type.to_lowercase() == "tiff" {
+                    scan_tiff_headers(
+                        &show_meta_cmd.input,
+                        show_meta_cmd.nb_chunks,
+                        show_meta_cmd.suppress,
+                    )?;
+                } else if show_meta_cmd.r#type.to_lowercase() == "ico" {
+                    let _ = read_ico_headers(
+                        &show_meta_cmd.input,
+                        show_meta_cmd.nb_chunks,
+                        show_meta_cmd.suppress,
+                    )?;
+                } else if show_meta_cmd.r#type.to_lowercase() == "mp4" {
+                    read_mp4_boxes(
+                        &show_meta_cmd.input,
+                        show_meta_cmd.nb_chunks,
+                        show_meta_cmd.suppress,
+                    )?;
+                } else if show_meta_cmd.r#type.to_lowercase() == "mp3" {
+                    read_mp3_frames(
+                        &show_meta_cmd.input,
+                        show_meta_cmd.nb_chunks,
+                        show_meta_cmd.suppress,
+                    )?;
+                } else if show_meta_cmd.r#type.to_lowercase() == "flac" {
+                    read_flac_blocks(
+                        &show_meta_cmd.input,
+                        show_meta_cmd.nb_chunks,
+                        show_meta_cmd.suppress,
+                    )?;
+                } else if show_meta_cmd.r#type.to_lowercase() == "svg" {
+                    read_svg_tags(&show_meta_cmd.input, show_meta_cmd.suppress)?;
+                } else if show_meta_cmd.r#type.to_lowercase() == "text" {
+                    read_text_summary(&show_meta_cmd.input, show_meta_cmd.suppress)?;
                 } else if show_meta_cmd.r#type.to_lowercase() == "png" {
-                    let mut file = File::open(show_meta_cmd.input.clone())?;
-                    let mut meta_chunk = MetaChunk::new(&mut file, show_meta_cmd.suppress)
-                        .expect("Error processing the png file!");
-                    meta_chunk.process_image(&mut file, &show_meta_cmd);
+                    let data = map_input(&show_meta_cmd.input)?;
+                    if !show_meta_cmd.suppress {
+                        print_png_header(&data)?;
+                        // A damaged IHDR is left to the listing and the summary.
+                        if let Ok(ihdr) = Ihdr::read(&data) {
+                            ihdr.write_report(&mut OutputWriter::default())?;
+                        }
+                    }
+                    let listed = match show_meta_cmd.page {
+                        Some(page) => write_page(
+                            &data,
+                            &show_meta_cmd,
+                            Page {
+                                number: page as usize,
+                                size: show_meta_cmd.per_page as usize,
+                            },
+                            &filter,
+                            &mut OutputWriter::default(),
+                        ),
+                        None => write_listing(
+                            &data,
+                            &show_meta_cmd,
+                            &filter,
+                            &mut OutputWriter::default(),
+                        ),
+                    };
+                    match listed {
+                        // A closed stdout, such as a pager quit early, isn't worth an error.
+                        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+                        result => result?,
+                    }
+                    if !show_meta_cmd.suppress {
+                        let summary = summarize_chunks(&data)?;
+                        outln!("{} {}", Style::Grey.paint(tr!("label-summary")), summary);
+                        for violation in &summary.order_violations {
+                            outln!(
+                                "{} {}",
+                                Style::Yellow.paint(tr!("label-misplaced")),
+                                violation
+                            );
+                        }
+                    }
+                    // A truncated chunk leaves no end to find, and the summary tells of it.
+                    if let (false, Ok(Some(trailing))) =
+                        (show_meta_cmd.suppress, find_trailing(&data, "png"))
+                    {
+                        trailing.write_report(&mut OutputWriter::default())?;
+                    }
+                    // The summary tells of a truncated chunk, which leaves no animation to read.
+                    let chunks = read_chunks(&data).unwrap_or_default();
+                    if let (false, Some(animation)) =
+                        (show_meta_cmd.suppress, parse_animation(&chunks)?)
+                    {
+                        outln!(
+                            "{} {}",
+                            Style::Grey.paint(tr!("label-animation")),
+                            animation
+                        );
+                    }
                 }
                 return Ok(());
             }
+            SteganoCommands::Provenance(provenance_cmd) => {
+                if provenance_cmd.explain {
+                    outln!("{}", explain_provenance(&provenance_cmd));
+                }
+                let mut file = Input::open(&provenance_cmd.input)?;
+                let links = provenance_chain(&mut file)?;
+                print_provenance(&provenance_cmd.input, &links);
+            }
+            SteganoCommands::Polyglot(polyglot_cmd) => {
+                let format = OutputFormat::resolve(&polyglot_cmd.format, OutputFormat::Raw)?;
+                if polyglot_cmd.explain && polyglot_cmd.extract && format == OutputFormat::Raw {
+                    eprintln!("{}", explain_polyglot(&polyglot_cmd));
+                } else if polyglot_cmd.explain {
+                    outln!("{}", explain_polyglot(&polyglot_cmd));
+                }
+                if polyglot_cmd.extract {
+                    let mut file = Input::open(&polyglot_cmd.input)?;
+                    let entry = extract_polyglot(&mut file)?;
+                    let mut decrypted_data =
+                        decrypt_with(&polyglot_cmd.algorithm, &polyglot_cmd.key, &entry.data)?;
+                    strip_padding(&polyglot_cmd.algorithm, &mut decrypted_data);
+                    let mut file_writer = OutputFile::create(&polyglot_cmd.output, &cancel)?;
+                    io::copy(&mut (&mut file).take(entry.offset), &mut file_writer)?;
+                    file_writer.commit()?;
+                    if format == OutputFormat::Human {
+                        outln!(
+                            "{}",
+                            tr!(
+                                "polyglot-entry",
+                                name = format!("{:?}", entry.name),
+                                offset = entry.offset
+                            )
+                        );
+                    }
+                    write_payload(&decrypted_data, format, polyglot_cmd.force_binary)?;
+                    return Ok(());
+                }
+
+                let mut payload = SpillBuffer::new(polyglot_cmd.max_memory.unwrap_or(usize::MAX));
+                let payload_len = match &polyglot_cmd.payload_file {
+                    Some(path) => encrypt_stream(
+                        &polyglot_cmd.algorithm,
+                        &polyglot_cmd.key,
+                        cancel.reader(File::open(path)?),
+                        &mut payload,
+                    )?,
+                    None => encrypt_stream(
+                        &polyglot_cmd.algorithm,
+                        &polyglot_cmd.key,
+                        polyglot_cmd.payload.as_bytes(),
+                        &mut payload,
+                    )?,
+                };
+                payload.rewind()?;
+                let mut file_writer = OutputFile::create(&polyglot_cmd.output, &cancel)?;
+                write_polyglot(
+                    cancel.reader(Input::open(&polyglot_cmd.input)?),
+                    &mut payload,
+                    payload_len,
+                    &polyglot_cmd.name,
+                    &mut file_writer,
+                )?;
+                file_writer.commit()?;
+                outln!("{}", tr!("polyglot-written"));
+            }
+            #[cfg(feature = "net")]
+            SteganoCommands::Share(share_cmd) => {
+                if share_cmd.explain {
+                    outln!("{}", explain_share(&share_cmd));
+                }
+                let url = provider_url(&share_cmd.provider, share_cmd.url.as_deref())?;
+                let link = upload(&url, &share_cmd.field, &share_cmd.input)?;
+                outln!("{} {}", Style::Green.paint(tr!("label-shared-at")), link);
+                outln!("{}", tr!("share-send-command"));
+                outln!(
+                    "  {}",
+                    decrypt_command(
+                        &link,
+                        &share_cmd.r#type,
+                        &share_cmd.method,
+                        &share_cmd.algorithm
+                    )
+                );
+            }
+            #[cfg(feature = "net")]
+            SteganoCommands::Serve(serve_cmd) => {
+                if serve_cmd.explain {
+                    outln!("{}", explain_serve(&serve_cmd));
+                }
+                let mut queue: std::collections::VecDeque<Vec<u8>> = serve_cmd
+                    .payload
+                    .iter()
+                    .map(|payload| payload.as_bytes().to_vec())
+                    .collect();
+                for path in &serve_cmd.payload_file {
+                    queue.push_back(std::fs::read(path)?);
+                }
+                let opts = CodecOptions {
+                    method: serve_cmd.method.clone(),
+                    algorithm: serve_cmd.algorithm.clone(),
+                    key: serve_cmd.key.clone(),
+                    cancel: cancel.clone(),
+                    ..CodecOptions::default()
+                };
+                let listener = std::net::TcpListener::bind(&serve_cmd.bind)?;
+                if !serve_cmd.suppress {
+                    outln!(
+                        "{}",
+                        tr!(
+                            "serve-listening",
+                            dir = serve_cmd.dir,
+                            address = listener.local_addr()?,
+                            queued = queue.len()
+                        )
+                    );
+                }
+                let mut remaining = queue.len();
+                let requests = serve(
+                    listener,
+                    std::path::Path::new(&serve_cmd.dir),
+                    queue,
+                    &opts,
+                    serve_cmd.max_requests,
+                    |served| {
+                        if serve_cmd.suppress {
+                            return;
+                        }
+                        match served {
+                            Ok(served) => {
+                                remaining -= served.embedded as usize;
+                                let note = match (&served.skipped, served.embedded) {
+                                    (Some(reason), _) => Style::Yellow
+                                        .paint(format!(" (payload kept: {})", reason))
+                                        .to_string(),
+                                    (None, true) => Style::Green
+                                        .paint(format!(" (payload embedded, {} left)", remaining))
+                                        .to_string(),
+                                    (None, false) => String::new(),
+                                };
+                                outln!(
+                                    "{} {} {} {} bytes{}",
+                                    served.method,
+                                    served.target,
+                                    served.status,
+                                    served.len,
+                                    note
+                                );
+                            }
+                            Err(err) => {
+                                eprintln!("{} {}", Style::Red.paint(tr!("label-connection")), err)
+                            }
+                        }
+                    },
+                )?;
+                cancel.check()?;
+                if !serve_cmd.suppress {
+                    outln!("{}", tr!("serve-answered", requests = requests));
+                }
+            }
+            #[cfg(feature = "net")]
+            SteganoCommands::Fetch(fetch_cmd) => {
+                let format = OutputFormat::resolve(&fetch_cmd.format, OutputFormat::Raw)?;
+                if fetch_cmd.explain && format == OutputFormat::Raw {
+                    eprintln!("{}", explain_fetch(&fetch_cmd));
+                } else if fetch_cmd.explain {
+                    outln!("{}", explain_fetch(&fetch_cmd));
+                }
+                let data = fetch(&fetch_cmd.url)?;
+                if let Some(save) = &fetch_cmd.save {
+                    write_output(save, &data, &cancel)?;
+                }
+                let opts = CodecOptions {
+                    file_type: fetch_cmd.r#type.clone(),
+                    method: fetch_cmd.method.clone(),
+                    algorithm: fetch_cmd.algorithm.clone(),
+                    key: fetch_cmd.key.clone(),
+                    cancel: cancel.clone(),
+                    ..CodecOptions::default()
+                };
+                let (file_type, decoded) = fetched_payload(&fetch_cmd.url, &data, &opts)?;
+                if let Some(output) = &fetch_cmd.output {
+                    write_output(output, &decoded.carrier, &cancel)?;
+                }
+                if format == OutputFormat::Human {
+                    outln!(
+                        "{}",
+                        tr!(
+                            "fetch-downloaded",
+                            bytes = data.len(),
+                            file_type = file_type,
+                            url = fetch_cmd.url
+                        )
+                    );
+                }
+                write_payload(&decoded.payload, format, fetch_cmd.force_binary)?;
+            }
+            SteganoCommands::Soak(soak_cmd) => {
+                if soak_cmd.explain {
+                    outln!("{}", explain_soak(&soak_cmd));
+                }
+                let seed = soak_cmd.seed.unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+                });
+                outln!(
+                    "{}",
+                    tr!("soak-start", iterations = soak_cmd.iterations, seed = seed)
+                );
+                let step = (soak_cmd.iterations / 10).max(1);
+                let report = run_soak(soak_cmd.iterations, seed, &cancel, |iteration, report| {
+                    if !soak_cmd.suppress && (iteration + 1) % step == 0 {
+                        let _ = emit(Output::Progress {
+                            done: iteration + 1,
+                            total: soak_cmd.iterations,
+                            text: &format!(
+                                "  {:>8} iterations, {} round trips, {} failures\n",
+                                iteration + 1,
+                                report.round_trips,
+                                report.failures.len()
+                            ),
+                        });
+                    }
+                });
+                if let Some((start, end)) = report.rss {
+                    outln!("{}", tr!("soak-memory", start = start, end = end));
+                }
+                if let Some((start, end)) = report.open_files {
+                    outln!("{}", tr!("soak-open-files", start = start, end = end));
+                }
+                if cancel.is_cancelled() {
+                    eprintln!(
+                        "{}",
+                        tr!(
+                            "soak-interrupted",
+                            done = report.iterations,
+                            iterations = soak_cmd.iterations,
+                            seed = seed
+                        )
+                    );
+                    cancel.check()?;
+                }
+                if !report.failures.is_empty() {
+                    for failure in &report.failures {
+                        outln!("{}", Style::Red.paint(failure));
+                    }
+                    return Err(tr!(
+                        "soak-failed",
+                        failures = report.failures.len(),
+                        round_trips = report.round_trips,
+                        seed = seed
+                    )
+                    .into());
+                }
+                outln!(
+                    "{}",
+                    Style::Green.paint(tr!("soak-succeeded", round_trips = report.round_trips))
+                );
+            }
+            SteganoCommands::Diff(mut diff_cmd) => {
+                diff_cmd.json |= OutputFormat::resolve(&diff_cmd.format, OutputFormat::Json)?
+                    == OutputFormat::Json;
+                // Keep stdout clean for the JSON document.
+                if diff_cmd.explain && diff_cmd.json {
+                    eprintln!("{}", explain_diff(&diff_cmd));
+                } else if diff_cmd.explain {
+                    outln!("{}", explain_diff(&diff_cmd));
+                }
+                let original = read_input(&diff_cmd.original)?;
+                let modified = read_input(&diff_cmd.modified)?;
+                let chunks = diff_chunks(&original, &modified)?;
+                let pixels = if diff_cmd.pixels {
+                    Some(diff_pixels(&original, &modified)?)
+                } else {
+                    None
+                };
+                if diff_cmd.json {
+                    outln!(
+                        "{}",
+                        diff_report(
+                            &diff_cmd.original,
+                            &diff_cmd.modified,
+                            &chunks,
+                            pixels.as_ref()
+                        )?
+                    );
+                } else {
+                    let files = diff_cmd.hex.then_some((&original[..], &modified[..]));
+                    print_diff(&chunks, pixels.as_ref(), files);
+                }
+            }
+            SteganoCommands::Peek(mut peek_cmd) => {
+                peek_cmd.r#type =
+                    resolve_type(&peek_cmd.r#type, &peek_cmd.method, &peek_cmd.input)?;
+                // Keep stdout clean for the hint alone.
+                if peek_cmd.explain && peek_cmd.hint_only {
+                    eprintln!("{}", explain_peek(&peek_cmd));
+                } else if peek_cmd.explain {
+                    outln!("{}", explain_peek(&peek_cmd));
+                }
+                if matches!(peek_cmd.method.to_lowercase().as_str(), "lsb" | "robust") {
+                    return Err(SteganoError::Usage(
+                        "The LSB and robust methods can't be inspected without the key!".into(),
+                    )
+                    .into());
+                }
+                let opts = CodecOptions {
+                    file_type: peek_cmd.r#type.clone(),
+                    method: peek_cmd.method.clone(),
+                    chunk_name: peek_cmd.chunk_name.clone(),
+                    ..CodecOptions::default()
+                };
+                let (embedded, _) = extract_encrypted(&read_input(&peek_cmd.input)?, &opts)?;
+                if embedded.starts_with(SHARE_MAGIC) {
+                    // Shares reveal nothing on their own, not even the hint or the header.
+                    let share = Share::parse(&embedded)?;
+                    outln!(
+                        "{}",
+                        tr!(
+                            "peek-share",
+                            bytes = share.data.len(),
+                            path = peek_cmd.input
+                        )
+                    );
+                    outln!(
+                        "{}",
+                        tr!(
+                            "peek-share-info",
+                            index = share.index + 1,
+                            total = share.total,
+                            needed = share.threshold
+                        )
+                    );
+                    return Ok(());
+                }
+                let shard = if embedded.starts_with(SHARD_MAGIC) {
+                    Some(Shard::parse(&embedded)?)
+                } else {
+                    None
+                };
+                let body = shard
+                    .as_ref()
+                    .map_or(&embedded[..], |shard| &shard.data[..]);
+                // Hints travel in the first shard of a split payload.
+                let (hint, encrypted) = match &shard {
+                    Some(shard) if shard.index > 0 => (None, body),
+                    _ => split_hint(body),
+                };
+                if peek_cmd.hint_only {
+                    let hint = hint.ok_or_else(|| {
+                        SteganoError::PayloadNotFound("No hint is stored with this payload!".into())
+                    })?;
+                    outln!("{}", hint);
+                    return Ok(());
+                }
+                // The header travels in the first shard too, and precision payloads have none.
+                let (header, encrypted) = match PayloadHeader::parse(encrypted) {
+                    Ok((header, ciphertext)) => (Some(header), ciphertext),
+                    Err(_) => (None, encrypted),
+                };
+                outln!(
+                    "{}",
+                    tr!("peek-found", bytes = encrypted.len(), path = peek_cmd.input)
+                );
+                if let Some(header) = header {
+                    outln!(
+                        "{} {}",
+                        Style::Grey.paint(tr!("label-payload-header")),
+                        header
+                    );
+                }
+                if let Some(shard) = &shard {
+                    outln!(
+                        "{}",
+                        tr!(
+                            "peek-shard-info",
+                            index = shard.index + 1,
+                            total = shard.total
+                        )
+                    );
+                }
+                match hint {
+                    Some(hint) => outln!("{} {}", Style::Yellow.paint(tr!("label-hint")), hint),
+                    None if shard.as_ref().is_some_and(|shard| shard.index > 0) => {
+                        outln!("{}", tr!("peek-hint-first-shard"))
+                    }
+                    None => outln!("{}", tr!("peek-no-hint")),
+                }
+            }
+            SteganoCommands::Config(config_cmd) => {
+                if config_cmd.explain {
+                    outln!("{}", explain_config(&config_cmd));
+                }
+                let path = Config::path()
+                    .ok_or("Can't locate the configuration file, set STEGANO_CONFIG or HOME!")?;
+                if config_cmd.path {
+                    outln!("{}", path.display());
+                    return Ok(());
+                }
+                let mut config = Config::load_from(&path)?;
+                if !config_cmd.set.is_empty() || !config_cmd.unset.is_empty() {
+                    for entry in &config_cmd.set {
+                        let (key, value) = entry.split_once('=').ok_or_else(|| {
+                            SteganoError::Usage("Defaults are set as --set KEY=VALUE!".into())
+                        })?;
+                        config.set(key.trim(), value.trim())?;
+                    }
+                    for key in &config_cmd.unset {
+                        config.unset(key)?;
+                    }
+                    config.save(&path)?;
+                    outln!(
+                        "{}",
+                        Style::Green.paint(tr!("config-saved", path = path.display()))
+                    );
+                }
+                if config.entries().is_empty() {
+                    outln!("{}", tr!("config-empty", path = path.display()));
+                    for (key, description) in CONFIG_KEYS {
+                        outln!("  {:<10}  {}", key, description);
+                    }
+                } else {
+                    outln!("# {}", path.display());
+                    out!("{}", config);
+                }
+            }
+            SteganoCommands::Rekey(mut rekey_cmd) => {
+                if rekey_cmd.in_place {
+                    rekey_cmd.output = rekey_cmd.input.clone();
+                }
+                rekey_cmd.r#type =
+                    resolve_type(&rekey_cmd.r#type, &rekey_cmd.method, &rekey_cmd.input)?;
+                if rekey_cmd.explain {
+                    outln!("{}", explain_rekey(&rekey_cmd));
+                }
+                let opts = CodecOptions {
+                    file_type: rekey_cmd.r#type.clone(),
+                    method: rekey_cmd.method.clone(),
+                    algorithm: rekey_cmd.algorithm.clone(),
+                    key: rekey_cmd.key.clone(),
+                    chunk_name: rekey_cmd.chunk_name.clone(),
+                    channels: rekey_cmd.channels.name(),
+                    cancel: cancel.clone(),
+                    ..CodecOptions::default()
+                };
+                let new_algorithm = rekey_cmd
+                    .new_algorithm
+                    .clone()
+                    .unwrap_or_else(|| rekey_cmd.algorithm.clone());
+                let data = map_input(&rekey_cmd.input)?;
+                let stego = rekey_bytes(&data, &opts, &new_algorithm, &rekey_cmd.new_key)?;
+                if !rekey_cmd.in_place {
+                    write_output(&rekey_cmd.output, &stego, &cancel)?;
+                    if !rekey_cmd.suppress {
+                        outln!("{}", tr!("rekey-written", path = rekey_cmd.output));
+                    }
+                } else {
+                    let patched = patch_output(&rekey_cmd.input, &data, &stego, &cancel)?;
+                    if !rekey_cmd.suppress {
+                        match patched {
+                            Some(range) => outln!(
+                                "{}",
+                                tr!(
+                                    "rekey-patched",
+                                    bytes = range.len(),
+                                    path = rekey_cmd.input,
+                                    offset = range.start
+                                )
+                            ),
+                            None => outln!("{}", tr!("rekey-rewritten", path = rekey_cmd.input)),
+                        }
+                    }
+                }
+            }
+            SteganoCommands::InjectChunk(inject_cmd) => {
+                if inject_cmd.explain {
+                    outln!("{}", explain_inject_chunk(&inject_cmd));
+                }
+                let data = read_input(&inject_cmd.input)?;
+                let chunk_data = std::fs::read(&inject_cmd.data_file)?;
+                let offset = inject_cmd.position.offset(&read_chunks(&data)?)?;
+                let (png, offset) =
+                    insert_chunk(&data, &inject_cmd.chunk_type, &chunk_data, Some(offset))?;
+                write_output(&inject_cmd.output, &png, &cancel)?;
+                if !inject_cmd.suppress {
+                    print_chunk(&inject_cmd.chunk_type, &chunk_data, offset);
+                    outln!(
+                        "{}",
+                        tr!("chunk-inserted", offset = offset, path = inject_cmd.output)
+                    );
+                }
+            }
+            SteganoCommands::RemoveChunk(remove_cmd) => {
+                if remove_cmd.explain {
+                    outln!("{}", explain_remove_chunk(&remove_cmd));
+                }
+                if (remove_cmd.wipe || remove_cmd.shred_input)
+                    && (remove_cmd.input == "-" || is_remote(&remove_cmd.input))
+                {
+                    return Err(SteganoError::Usage(
+                        "--wipe and --shred-input only overwrite local files, not stdin or remote objects"
+                            .into(),
+                    )
+                    .into());
+                }
+                let data = read_input(&remove_cmd.input)?;
+                let (png, removed) =
+                    remove_chunks(&data, |index, chunk| match remove_cmd.chunk_type {
+                        Some(r#type) => chunk.r#type == r#type,
+                        None => remove_cmd.index == Some(index),
+                    })?;
+                if removed.is_empty() {
+                    return Err(SteganoError::PayloadNotFound(match remove_cmd.chunk_type {
+                        Some(r#type) => {
+                            format!("No {:?} chunk found", String::from_utf8_lossy(&r#type))
+                        }
+                        None => {
+                            format!("No chunk at index {}", remove_cmd.index.unwrap_or_default())
+                        }
+                    })
+                    .into());
+                }
+                for chunk in &removed {
+                    let name = chunk.r#type;
+                    if matches!(chunk.r#type, ChunkType::Ihdr | ChunkType::Iend) {
+                        return Err(SteganoError::Usage(format!(
+                            "{} can't be removed, the file wouldn't be a PNG without it!",
+                            name
+                        ))
+                        .into());
+                    }
+                    if chunk.r#type.is_critical() && !remove_cmd.force {
+                        return Err(SteganoError::Usage(format!(
+                            "{} is a critical chunk, the image can't be decoded without it; use --force to remove it anyway!",
+                            name
+                        ))
+                        .into());
+                    }
+                }
+                if remove_cmd.wipe {
+                    let path = std::path::Path::new(&remove_cmd.input);
+                    for chunk in &removed {
+                        let start = chunk.offset as u64;
+                        overwrite(
+                            path,
+                            start..start + 12 + chunk.data.len() as u64,
+                            remove_cmd.passes,
+                        )?;
+                    }
+                }
+                write_output(&remove_cmd.output, &png, &cancel)?;
+                if remove_cmd.shred_input {
+                    shred(std::path::Path::new(&remove_cmd.input), remove_cmd.passes)?;
+                }
+                if !remove_cmd.suppress {
+                    for chunk in &removed {
+                        outln!(
+                            "{} {}",
+                            Style::Grey.paint(tr!("label-removed")),
+                            tr!(
+                                "chunk-removed",
+                                chunk_type = chunk.r#type.to_string(),
+                                bytes = chunk.data.len(),
+                                offset = chunk.offset
+                            )
+                        );
+                    }
+                    outln!(
+                        "{}",
+                        tr!(
+                            "chunks-removed",
+                            count = removed.len(),
+                            path = remove_cmd.output
+                        )
+                    );
+                    if remove_cmd.wipe {
+                        outln!(
+                            "{}",
+                            tr!(
+                                "chunks-wiped",
+                                count = removed.len(),
+                                passes = remove_cmd.passes
+                            )
+                        );
+                    }
+                    if remove_cmd.shred_input {
+                        outln!(
+                            "{}",
+                            tr!(
+                                "input-shredded",
+                                path = remove_cmd.input,
+                                passes = remove_cmd.passes
+                            )
+                        );
+                    }
+                }
+            }
+            SteganoCommands::ExtractChunk(extract_cmd) => {
+                if extract_cmd.explain {
+                    outln!("{}", explain_extract_chunk(&extract_cmd));
+                }
+                // Chunks are read lazily, so the file is only read up to the last one taken.
+                let input = io::BufReader::new(open_input(&extract_cmd.input)?);
+                let selected: Vec<(usize, RawChunk)> = chunks(input)
+                    .enumerate()
+                    .map(|(index, chunk)| chunk.map(|chunk| (index, chunk)))
+                    .filter(|chunk| {
+                        chunk
+                            .as_ref()
+                            .map_or(true, |(index, chunk)| match extract_cmd.chunk_type {
+                                Some(r#type) => chunk.r#type == r#type,
+                                None => extract_cmd.index == Some(*index),
+                            })
+                    })
+                    .take(if extract_cmd.all { usize::MAX } else { 1 })
+                    .collect::<io::Result<_>>()?;
+                if selected.is_empty() {
+                    return Err(SteganoError::PayloadNotFound(match extract_cmd.chunk_type {
+                        Some(r#type) => {
+                            format!("No {:?} chunk found", String::from_utf8_lossy(&r#type))
+                        }
+                        None => format!(
+                            "No chunk at index {}",
+                            extract_cmd.index.unwrap_or_default()
+                        ),
+                    })
+                    .into());
+                }
+                for (n, (index, chunk)) in selected.iter().enumerate() {
+                    let path = if extract_cmd.all {
+                        shard_path(&extract_cmd.output, n)
+                    } else {
+                        extract_cmd.output.clone()
+                    };
+                    write_output(&path, &chunk.data, &cancel)?;
+                    if !extract_cmd.suppress {
+                        outln!(
+                            "{} {}",
+                            Style::Grey.paint(tr!("label-extracted")),
+                            tr!(
+                                "chunk-extracted",
+                                chunk_type = chunk.r#type.to_string(),
+                                index = index,
+                                bytes = chunk.data.len(),
+                                offset = chunk.offset,
+                                path = path
+                            )
+                        );
+                    }
+                }
+            }
+            SteganoCommands::Icc(mut icc_cmd) => {
+                icc_cmd.r#type = resolve_type(&icc_cmd.r#type, "auto", &icc_cmd.input)?;
+                if icc_cmd.explain {
+                    outln!("{}", explain_icc(&icc_cmd));
+                }
+                let data = read_input(&icc_cmd.input)?;
+                if let (Some(path), Some(output)) = (&icc_cmd.inject_icc, &icc_cmd.output) {
+                    let profile = std::fs::read(path)?;
+                    let image = inject_icc(&data, &profile, &icc_cmd.r#type)?;
+                    write_output(output, &image, &cancel)?;
+                    if !icc_cmd.suppress {
+                        outln!(
+                            "{}",
+                            tr!("icc-injected", bytes = profile.len(), path = output)
+                        );
+                    }
+                } else if let Some(path) = &icc_cmd.extract_icc {
+                    let profile = extract_icc(&data, &icc_cmd.r#type)?;
+                    write_output(path, &profile, &cancel)?;
+                    if !icc_cmd.suppress {
+                        outln!(
+                            "{} {}",
+                            Style::Grey.paint(tr!("label-extracted")),
+                            tr!("icc-extracted", bytes = profile.len(), path = path)
+                        );
+                    }
+                }
+            }
+            SteganoCommands::Watermark(watermark_cmd) => {
+                if watermark_cmd.explain() {
+                    outln!("{}", explain_watermark(&watermark_cmd));
+                }
+                match watermark_cmd.action {
+                    WatermarkAction::Embed(embed_cmd) => {
+                        let marked = embed_watermark(
+                            &read_input(&embed_cmd.input)?,
+                            &embed_cmd.owner,
+                            &embed_cmd.key,
+                            embed_cmd.strength,
+                        )?;
+                        write_output(&embed_cmd.output, &marked, &cancel)?;
+                        if !embed_cmd.suppress {
+                            outln!(
+                                "{}",
+                                tr!(
+                                    "watermark-embedded",
+                                    owner = format!("{:?}", embed_cmd.owner),
+                                    path = embed_cmd.output
+                                )
+                            );
+                        }
+                    }
+                    WatermarkAction::Verify(verify_cmd) => {
+                        let detection = detect_watermark(
+                            &read_input(&verify_cmd.input)?,
+                            &verify_cmd.key,
+                            verify_cmd.owner.as_deref(),
+                        )?;
+                        if let Some(owner) = &detection.owner {
+                            outln!("{} {:?}", Style::Grey.paint(tr!("label-owner-read")), owner);
+                        }
+                        let owner = verify_cmd.owner.as_ref().or(detection.owner.as_ref());
+                        match owner {
+                            Some(owner) if detection.is_present() => outln!(
+                                "{} ({})",
+                                Style::Green.paint(tr!(
+                                    "watermark-present",
+                                    owner = format!("{:?}", owner)
+                                )),
+                                detection
+                            ),
+                            Some(owner) => {
+                                return Err(SteganoError::PayloadNotFound(format!(
+                                    "No watermark of {:?} found with this key ({})",
+                                    owner, detection
+                                ))
+                                .into())
+                            }
+                            None => {
+                                return Err(SteganoError::PayloadNotFound(
+                                    "No watermark found with this key".into(),
+                                )
+                                .into())
+                            }
+                        }
+                    }
+                }
+            }
+            SteganoCommands::Vault(vault_cmd) => {
+                if vault_cmd.explain() {
+                    outln!("{}", explain_vault(&vault_cmd));
+                }
+                match vault_cmd.action {
+                    VaultAction::Put(put_cmd) => {
+                        let name = match &put_cmd.name {
+                            Some(name) => name.clone(),
+                            None => std::path::Path::new(&put_cmd.file)
+                                .file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                                .ok_or_else(|| {
+                                    SteganoError::Usage(format!(
+                                        "{} has no file name, give one with --name!",
+                                        put_cmd.file
+                                    ))
+                                })?,
+                        };
+                        let contents = std::fs::read(&put_cmd.file)?;
+                        let (png, replaced) = vault_put(
+                            &read_input(&put_cmd.input)?,
+                            &name,
+                            &contents,
+                            &put_cmd.algorithm,
+                            &put_cmd.key,
+                        )?;
+                        let output = put_cmd.output.as_ref().unwrap_or(&put_cmd.input);
+                        write_output(output, &png, &cancel)?;
+                        if !put_cmd.suppress {
+                            outln!(
+                                "{}",
+                                if replaced.is_some() {
+                                    tr!(
+                                        "vault-replaced",
+                                        name = format!("{:?}", name),
+                                        bytes = contents.len(),
+                                        path = output
+                                    )
+                                } else {
+                                    tr!(
+                                        "vault-stored",
+                                        name = format!("{:?}", name),
+                                        bytes = contents.len(),
+                                        path = output
+                                    )
+                                }
+                            );
+                        }
+                    }
+                    VaultAction::Ls(ls_cmd) => {
+                        let entries = vault_list(&read_input(&ls_cmd.input)?, &ls_cmd.key)?;
+                        if entries.is_empty() {
+                            outln!("{}", tr!("vault-empty", path = ls_cmd.input));
+                        }
+                        for entry in &entries {
+                            outln!(
+                                "{:>10}  {}  {}",
+                                entry.size,
+                                Style::Grey
+                                    .paint(rfc3339(UNIX_EPOCH + Duration::from_secs(entry.added))),
+                                entry.name
+                            );
+                        }
+                    }
+                    VaultAction::Get(get_cmd) => {
+                        let contents =
+                            vault_get(&read_input(&get_cmd.input)?, &get_cmd.name, &get_cmd.key)?;
+                        match &get_cmd.output {
+                            Some(output) => {
+                                write_output(output, &contents, &cancel)?;
+                                if !get_cmd.suppress {
+                                    outln!(
+                                        "{}",
+                                        tr!(
+                                            "vault-written",
+                                            name = format!("{:?}", get_cmd.name),
+                                            bytes = contents.len(),
+                                            path = output
+                                        )
+                                    );
+                                }
+                            }
+                            None => {
+                                write_payload(&contents, OutputFormat::Raw, get_cmd.force_binary)?
+                            }
+                        }
+                    }
+                    VaultAction::Rm(rm_cmd) => {
+                        let (png, removed) =
+                            vault_remove(&read_input(&rm_cmd.input)?, &rm_cmd.name, &rm_cmd.key)?;
+                        let output = rm_cmd.output.as_ref().unwrap_or(&rm_cmd.input);
+                        write_output(output, &png, &cancel)?;
+                        if !rm_cmd.suppress {
+                            outln!(
+                                "{}",
+                                tr!(
+                                    "vault-removed",
+                                    name = format!("{:?}", removed.name),
+                                    bytes = removed.size,
+                                    path = output
+                                )
+                            );
+                        }
+                    }
+                }
+            }
+            SteganoCommands::Meta(meta_cmd) => {
+                if meta_cmd.explain() {
+                    outln!("{}", explain_meta(&meta_cmd));
+                }
+                match meta_cmd.action {
+                    MetaAction::Set(set_cmd) => {
+                        let png = set_metadata(
+                            &read_input(&set_cmd.input)?,
+                            &set_cmd.text,
+                            set_cmd.time,
+                        )?;
+                        let output = set_cmd.output.as_ref().unwrap_or(&set_cmd.input);
+                        write_output(output, &png, &cancel)?;
+                        if !set_cmd.suppress {
+                            outln!(
+                                "{}",
+                                tr!(
+                                    "meta-set",
+                                    count =
+                                        set_cmd.text.len() + usize::from(set_cmd.time.is_some()),
+                                    path = output
+                                )
+                            );
+                        }
+                    }
+                    MetaAction::Get(get_cmd) => {
+                        let metadata = read_metadata(&read_input(&get_cmd.input)?)?;
+                        if let Some(key) = &get_cmd.key {
+                            let text = metadata.get(key).ok_or_else(|| {
+                                SteganoError::PayloadNotFound(format!(
+                                    "No {:?} text entry in {}",
+                                    key, get_cmd.input
+                                ))
+                            })?;
+                            outln!("{}", text);
+                        } else {
+                            if metadata.is_empty() {
+                                outln!("{}", tr!("meta-empty", path = get_cmd.input));
+                            }
+                            for (keyword, text) in &metadata.text {
+                                outln!("{} {}", Style::Blue.paint(format!("{}:", keyword)), text);
+                            }
+                            if let Some(time) = metadata.time {
+                                outln!(
+                                    "{} {}",
+                                    Style::Grey.paint(tr!("label-modified")),
+                                    rfc3339(UNIX_EPOCH + Duration::from_secs(time))
+                                );
+                            }
+                        }
+                    }
+                    MetaAction::Remove(remove_cmd) => {
+                        let (png, removed) = remove_metadata(
+                            &read_input(&remove_cmd.input)?,
+                            |keyword| {
+                                remove_cmd.all_text
+                                    || remove_cmd.keys.iter().any(|key| key == keyword)
+                            },
+                            remove_cmd.time,
+                        )?;
+                        if removed.is_empty() {
+                            return Err(SteganoError::PayloadNotFound(format!(
+                                "None of the metadata to remove is in {}",
+                                remove_cmd.input
+                            ))
+                            .into());
+                        }
+                        let output = remove_cmd.output.as_ref().unwrap_or(&remove_cmd.input);
+                        write_output(output, &png, &cancel)?;
+                        if !remove_cmd.suppress {
+                            outln!(
+                                "{}",
+                                tr!(
+                                    "meta-removed",
+                                    count =
+                                        removed.text.len() + usize::from(removed.time.is_some()),
+                                    path = output
+                                )
+                            );
+                        }
+                    }
+                }
+            }
+            SteganoCommands::GenerateCarrier(generate_cmd) => {
+                if generate_cmd.explain {
+                    outln!("{}", explain_generate_carrier(&generate_cmd));
+                }
+                let style = CoverStyle::new(&generate_cmd.style)?;
+                let (width, height) = match &generate_cmd.fit_payload {
+                    Some(path) => fit_dimensions(
+                        generate_cmd.width,
+                        generate_cmd.height,
+                        std::fs::metadata(path)?.len() as usize,
+                    ),
+                    None => (generate_cmd.width, generate_cmd.height),
+                };
+                let seed = generate_cmd.seed.unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+                });
+                let png = generate_cover(width, height, style, seed)?;
+                write_output(&generate_cmd.output, &png, &cancel)?;
+                if !generate_cmd.suppress {
+                    if (width, height) != (generate_cmd.width, generate_cmd.height) {
+                        outln!(
+                            "{}",
+                            Style::Yellow.paint(tr!(
+                                "carrier-enlarged",
+                                width = width,
+                                height = height
+                            ))
+                        );
+                    }
+                    outln!(
+                        "{}",
+                        tr!(
+                            "carrier-generated",
+                            width = width,
+                            height = height,
+                            style = style.name(),
+                            seed = seed,
+                            capacity = safe_capacity(width, height),
+                            path = generate_cmd.output
+                        )
+                    );
+                }
+            }
+            #[cfg(feature = "gui")]
+            SteganoCommands::Gui(gui_cmd) => {
+                if gui_cmd.explain {
+                    outln!("{}", explain_gui(&gui_cmd));
+                }
+                stegano::gui::run(GuiOptions {
+                    carrier: gui_cmd.input.map(std::path::PathBuf::from),
+                    key: gui_cmd.key.unwrap_or_default(),
+                    algorithm: gui_cmd.algorithm,
+                })?;
+            }
         },
-        None => println!("\x1b[1;91mUnknown command. Use 'help' for usage instructions.\x1b[0m"),
+        None => outln!("{}", Style::BoldRed.paint(tr!("unknown-command"))),
+    }
+    Ok(())
+}
+
+/// Prints the signature of a PNG file before its chunks are listed or changed, failing if the
+/// file doesn't start with one.
+fn print_png_header(data: &[u8]) -> io::Result<()> {
+    // Reading the first chunk checks the signature.
+    chunks(data).next().transpose()?;
+    outln!("{} \n", tr!("png-valid"));
+    outln!("{}", Style::Green.paint("---- Header ----"));
+    print_hex(&data[..PNG_SIGNATURE.len()], 0);
+    outln!("{}", Style::Green.paint("----- End ------"));
+    outln!();
+    Ok(())
+}
+
+/// Prints the outcome of the encrypt command, as JSON when `--json` is set.
+fn print_encrypt_result(
+    cmd: &EncryptCmd,
+    payload_len: u64,
+    output_len: u64,
+    offset: Option<u64>,
+    quality: Option<&QualityMetrics>,
+    message: &str,
+) -> io::Result<()> {
+    if cmd.json {
+        outln!(
+            "{}",
+            encrypt_report(cmd, payload_len, output_len, offset, quality)?
+        );
+    } else {
+        if cmd.dry_run {
+            outln!(
+                "{} {}",
+                Style::Grey.paint(tr!("label-dry-run")),
+                tr!("dry-run-nothing-written")
+            );
+            outln!(
+                "{} {}",
+                Style::Grey.paint(tr!("label-output")),
+                tr!("dry-run-output-size", path = cmd.output, bytes = output_len)
+            );
+            match offset {
+                Some(offset) => outln!(
+                    "{} {}",
+                    Style::Grey.paint(tr!("label-payload")),
+                    tr!("payload-at-offset", bytes = payload_len, offset = offset)
+                ),
+                None => outln!(
+                    "{} {}",
+                    Style::Grey.paint(tr!("label-payload")),
+                    tr!("payload-bytes", bytes = payload_len)
+                ),
+            }
+        } else {
+            outln!("{}", message);
+        }
+        if let Some(quality) = quality {
+            outln!("{} {}", Style::Grey.paint(tr!("label-quality")), quality);
+        }
+        if cmd.verbose {
+            match offset {
+                Some(offset) => print_injection(cmd, offset)?,
+                None => outln!(
+                    "{} {}",
+                    Style::Grey.paint(tr!("label-verbose")),
+                    tr!("verbose-no-offset")
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Largest window of the output `--verbose` reads to show what the payload changed.
+const MAX_INJECTION_WINDOW: u64 = 1 << 20;
+
+/// Shows the bytes around the injection offset in the input and the output of `encrypt`, for
+/// `--verbose`.
+fn print_injection(cmd: &EncryptCmd, offset: u64) -> io::Result<()> {
+    if is_remote(&cmd.input) || is_remote(&cmd.output) {
+        outln!(
+            "{} {}",
+            Style::Grey.paint(tr!("label-verbose")),
+            tr!("verbose-remote")
+        );
+        return Ok(());
+    }
+    let input_len = std::fs::metadata(&cmd.input)?.len();
+    let output_len = std::fs::metadata(&cmd.output)?.len();
+    let inserted = output_len.saturating_sub(input_len);
+    if inserted > MAX_INJECTION_WINDOW {
+        outln!(
+            "{} {}",
+            Style::Grey.paint(tr!("label-verbose")),
+            tr!("verbose-too-many", bytes = inserted)
+        );
+        return Ok(());
+    }
+    let start = offset.saturating_sub(HEX_CONTEXT as u64);
+    let end = offset + HEX_CONTEXT as u64;
+    let before = read_window(&cmd.input, start, end)?;
+    let after = read_window(&cmd.output, start, end + inserted)?;
+    write_hex_diff(
+        &mut OutputWriter::default(),
+        &before,
+        start,
+        &after,
+        start,
+        colors_enabled(Stream::Stdout),
+    )
+}
+
+/// Reads the bytes of a file between two offsets, fewer if it ends before.
+fn read_window(path: &str, start: u64, end: u64) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(io::SeekFrom::Start(start))?;
+    let mut window = Vec::new();
+    file.take(end - start).read_to_end(&mut window)?;
+    Ok(window)
+}
+
+/// Opens the output of `encrypt`, which only counts the data with `--dry-run`.
+fn create_output(cmd: &EncryptCmd, cancel: &CancellationToken) -> io::Result<OutputFile> {
+    if cmd.dry_run {
+        Ok(OutputFile::discard(&cmd.output, cancel))
+    } else {
+        OutputFile::create(&cmd.output, cancel)
+    }
+}
+
+/// Resolves the key of `encrypt` and `decrypt` from its sources, first to last: `-k` or
+/// `--key-fd`, the `--key-id` keychain entry, `STEGANO_KEY`, then the default.
+///
+/// A key given with `-k` or `--key-fd` is stored under `--key-id`, which is only read back
+/// without them.
+///
+/// # Arguments
+///
+/// * `key` - The value of `-k`, from the command line, `STEGANO_KEY` or the default.
+/// * `key_fd` - The descriptor of `--key-fd`.
+/// * `key_id` - The keychain entry of `--key-id`.
+/// * `source` - Where the value of `-k` came from.
+fn resolve_key(
+    key: &str,
+    key_fd: Option<u32>,
+    key_id: Option<&str>,
+    source: Option<ValueSource>,
+) -> io::Result<String> {
+    let typed = source == Some(ValueSource::CommandLine);
+    let given = match key_fd {
+        Some(_) if typed => {
+            return Err(
+                SteganoError::Usage("-k and --key-fd both give the key, pick one!".into()).into(),
+            );
+        }
+        Some(fd) => Some(read_key_fd(fd)?),
+        None => typed.then(|| key.to_string()),
+    };
+    match (given, key_id) {
+        (Some(key), Some(id)) => {
+            store_key(id, &key)?;
+            Ok(key)
+        }
+        (Some(key), None) => Ok(key),
+        (None, Some(id)) => fetch_key(id),
+        (None, None) => Ok(key.to_string()),
+    }
+}
+
+/// Resolves `--type auto` from the first bytes of the carrier at `path`, or else from its
+/// extension, and checks that an explicit `--type`, or else the extension, agrees with them.
+/// Appended payloads don't depend on the carrier, so with `--method append` an unknown
+/// carrier stays `auto`.
+fn resolve_type(file_type: &str, method: &str, path: &str) -> io::Result<String> {
+    let mut prefix = Vec::new();
+    open_input(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut prefix)?;
+    let detected = detect_file_type(&prefix);
+    if !file_type.eq_ignore_ascii_case("auto") {
+        return match detected {
+            Some(detected) if !same_file_type(file_type, detected) => {
+                Err(SteganoError::Usage(format!(
+                    "{} holds a {} file, not {}; leave out -t to go by its content!",
+                    path,
+                    detected.to_uppercase(),
+                    file_type.to_uppercase()
+                ))
+                .into())
+            }
+            _ => Ok(file_type.to_string()),
+        };
+    }
+    // URLs may carry a query after the file name.
+    let name = if is_remote(path) {
+        path.split(['?', '#']).next().unwrap_or_default()
+    } else {
+        path
+    };
+    match (detected, extension_type(std::path::Path::new(name))) {
+        (Some(detected), Some(named)) if !same_file_type(named, detected) => {
+            Err(SteganoError::Usage(format!(
+                "{} is named like a {} file but holds a {} one; pass -t {} to go by its content!",
+                path,
+                named.to_uppercase(),
+                detected.to_uppercase(),
+                detected
+            ))
+            .into())
+        }
+        (Some(file_type), _) | (None, Some(file_type)) => Ok(file_type.to_string()),
+        (None, None) if method.eq_ignore_ascii_case("append") => Ok(file_type.to_string()),
+        (None, None) => Err(SteganoError::Usage(format!(
+            "The type of {} can't be told from its first bytes, pass it with -t!",
+            path
+        ))
+        .into()),
+    }
+}
+
+/// Writes a whole output of `encrypt`, unless `--dry-run` was given. With
+/// `--preserve-metadata`, the ancillary chunks of `carrier` are checked first.
+///
+/// # Returns
+///
+/// A `Result` containing the size of the output.
+fn write_stego(
+    cmd: &EncryptCmd,
+    carrier: &str,
+    path: &str,
+    data: &[u8],
+    cancel: &CancellationToken,
+) -> io::Result<u64> {
+    if cmd.preserve_metadata {
+        check_metadata_kept(&read_input(carrier)?, data)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}; nothing was written", err)))?;
+    }
+    if !cmd.dry_run {
+        write_output(path, data, cancel)?;
+    }
+    Ok(data.len() as u64)
+}
+
+/// Re-encodes a stego image from the lsb or robust methods without interlacing if
+/// `--deinterlace` was given; they keep the interlacing of the carrier otherwise.
+fn deinterlace(cmd: &EncryptCmd, stego: Vec<u8>) -> io::Result<Vec<u8>> {
+    if !cmd.deinterlace || !is_interlaced(&stego)? {
+        return Ok(stego);
+    }
+    PngImage::decode(&stego)?.replace_pixels_progressive(&stego)
+}
+
+/// Measures the PSNR and SSIM of a stego image from the lsb or robust methods before it's
+/// written, refusing it past `--max-distortion`.
+///
+/// # Arguments
+///
+/// * `cmd` - The encrypt command.
+/// * `carrier` - The carrier the payload went into.
+/// * `stego` - The carrier with the payload.
+fn check_quality(cmd: &EncryptCmd, carrier: &[u8], stego: &[u8]) -> io::Result<QualityMetrics> {
+    let quality = quality_metrics(carrier, stego)?;
+    match cmd.max_distortion {
+        Some(max) if max.exceeded_by(&quality) => Err(SteganoError::Other(format!(
+            "The payload drops the image below {} ({}); nothing was written. Try a smaller payload or a larger carrier.",
+            max, quality
+        ))
+        .into()),
+        _ => Ok(quality),
+    }
+}
+
+/// Writes the `--descriptor` file of the encrypt command, once the payload is in the output.
+///
+/// # Arguments
+///
+/// * `cmd` - The encrypt command.
+/// * `digest` - The digest of the encrypted payload, `None` when no descriptor was requested.
+/// * `offset` - The offset of the payload in the output.
+/// * `len` - The length of the payload.
+/// * `cancel` - The token cancelling the write.
+fn write_descriptor(
+    cmd: &EncryptCmd,
+    digest: Option<[u8; 32]>,
+    offset: u64,
+    len: u64,
+    cancel: &CancellationToken,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (Some(path), Some(digest)) = (&cmd.descriptor, digest) else {
+        return Ok(());
+    };
+    // A dry run has no output to check the location against.
+    if cmd.dry_run {
+        if !cmd.suppress {
+            outln!("{}", tr!("descriptor-would-be-written", path = path));
+        }
+        return Ok(());
+    }
+    // Check the location against the output as written, rather than trusting the arithmetic.
+    let sha256 = sha256_range(&mut Input::open(&cmd.output)?, offset, len)?;
+    if sha256 != digest {
+        return Err("The payload couldn't be located in the output for the descriptor!".into());
+    }
+    let descriptor = Descriptor {
+        file_type: cmd.r#type.to_lowercase(),
+        method: cmd.method.to_lowercase(),
+        algorithm: cmd.algorithm.to_lowercase(),
+        chunk_name: cmd.chunk_name.clone(),
+        offset,
+        length: len,
+        sha256,
+    };
+    write_output(path, descriptor.seal(&cmd.key)?.as_bytes(), cancel)?;
+    if !cmd.suppress {
+        outln!("{}", tr!("descriptor-written", path = path));
+    }
+    Ok(())
+}
+
+/// Decrypts an embedded payload for `decrypt`, then up to `--depth` layers nested in it with
+/// `encrypt --nest`, each with the carrier type recorded in the header of the layer above.
+fn open_secret(
+    embedded: &[u8],
+    cmd: &DecryptCmd,
+    cancel: &CancellationToken,
+) -> io::Result<(Option<PayloadHeader>, Vec<u8>)> {
+    let (mut header, mut plaintext) = open_layer(embedded, cmd)?;
+    // The payload is only wiped once it is known to open, so a wrong key loses nothing.
+    if cmd.wipe {
+        let range = wipe_payload(std::path::Path::new(&cmd.input), embedded, cmd.passes)?;
+        if !cmd.suppress {
+            outln!(
+                "{}",
+                tr!(
+                    "payload-wiped",
+                    bytes = range.end - range.start,
+                    offset = range.start,
+                    passes = cmd.passes
+                )
+            );
+        }
+    }
+    for layer in 2..=cmd.depth {
+        let Some(file_type) = header
+            .as_ref()
+            .and_then(|header| header.mime_type.as_deref())
+            .and_then(nested_carrier_type)
+        else {
+            break;
+        };
+        // A layer may use another method than the one above it, so the methods finding their
+        // payload by structure are tried next.
+        let mut methods = vec![cmd.method.to_lowercase()];
+        for method in ["auto", "append"] {
+            if !methods.iter().any(|known| known == method) {
+                methods.push(method.to_string());
+            }
+        }
+        let mut inner = None;
+        let mut first_error = None;
+        for method in methods {
+            let opts = CodecOptions {
+                file_type: file_type.to_string(),
+                method,
+                algorithm: cmd.algorithm.clone(),
+                key: cmd.key.clone(),
+                chunk_name: cmd.chunk_name.clone(),
+                channels: cmd.channels.name(),
+                cancel: cancel.clone(),
+                ..CodecOptions::default()
+            };
+            match extract_encrypted(&plaintext, &opts) {
+                Ok((data, _)) => {
+                    inner = Some(data);
+                    break;
+                }
+                Err(err) if is_cancelled(&err) => return Err(err),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        // The first error is the one of the method that was asked for.
+        let inner = match (inner, first_error) {
+            (Some(inner), _) => inner,
+            (None, err) => {
+                let err = err.expect("at least one method was tried");
+                return Err(io::Error::new(
+                    err.kind(),
+                    format!("Layer {}: {}", layer, err),
+                ));
+            }
+        };
+        (header, plaintext) = open_layer(&inner, cmd)?;
+        if !cmd.suppress {
+            outln!(
+                "{}",
+                tr!("unwrapped-layer", layer = layer, file_type = file_type)
+            );
+        }
+    }
+    Ok((header, plaintext))
+}
+
+/// Refuses the options that don't apply to `extract` or `remove`, before anything is read.
+fn check_mode(cmd: &DecryptCmd) -> io::Result<()> {
+    let refused = match cmd.mode {
+        DecryptMode::Both => None,
+        DecryptMode::Extract => [
+            ("--in-place", cmd.in_place),
+            ("--keep-mtime", cmd.keep_mtime),
+            ("--exact", cmd.exact),
+        ]
+        .into_iter()
+        .find(|(_, given)| *given)
+        .map(|(flag, _)| {
+            format!(
+                "{} only applies when the carrier is restored, use remove",
+                flag
+            )
+        }),
+        DecryptMode::Remove => {
+            let flag = [
+                ("--burn", cmd.burn),
+                ("--wipe", cmd.wipe),
+                ("--qr", cmd.qr.is_some()),
+                ("--output-payload-file", cmd.output_payload_file.is_some()),
+                ("--descriptor", cmd.descriptor.is_some()),
+            ]
+            .into_iter()
+            .find(|(_, given)| *given);
+            match flag {
+                Some((flag, _)) => Some(format!(
+                    "{} only applies when the payload is revealed, use extract",
+                    flag
+                )),
+                None if matches!(
+                    cmd.method.to_lowercase().as_str(),
+                    "precision" | "lsb" | "robust"
+                ) =>
+                {
+                    Some(format!(
+                        "{} payloads overwrite the carrier, which can't be restored, use extract",
+                        cmd.method
+                    ))
+                }
+                None => None,
+            }
+        }
+    };
+    match refused {
+        Some(message) => Err(SteganoError::Usage(message).into()),
+        None => Ok(()),
+    }
+}
+
+/// Writes the carrier without its payload to `--output`, unless the command is `extract`.
+fn write_restored(cmd: &DecryptCmd, data: &[u8], cancel: &CancellationToken) -> io::Result<()> {
+    if !cmd.mode.restores() {
+        return Ok(());
+    }
+    write_output(&cmd.output, data, cancel)?;
+    restored_note(cmd);
+    Ok(())
+}
+
+/// Tells where `remove` wrote the carrier, as nothing else is printed.
+fn restored_note(cmd: &DecryptCmd) {
+    if cmd.mode == DecryptMode::Remove && !cmd.suppress {
+        outln!("{}", tr!("carrier-restored", path = cmd.output));
+    }
+}
+
+/// Refuses `--wipe` and `--shred-input` where they can't be honored, before anything is read.
+fn check_wipe(cmd: &DecryptCmd) -> io::Result<()> {
+    if !cmd.wipe && !cmd.shred_input {
+        return Ok(());
+    }
+    if cmd.input == "-" || is_remote(&cmd.input) {
+        return Err(SteganoError::Usage(
+            "--wipe and --shred-input only overwrite local files, not stdin or remote objects"
+                .into(),
+        )
+        .into());
+    }
+    if cmd.wipe
+        && matches!(
+            cmd.method.to_lowercase().as_str(),
+            "precision" | "lsb" | "robust"
+        )
+    {
+        return Err(SteganoError::Usage(format!(
+            "{} payloads are spread over the pixels and can't be wiped on their own, use --shred-input instead",
+            cmd.method
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Opens one layer of a payload for decrypt, refusing it past its expiry unless
+/// `--ignore-expiry` is set.
+fn open_layer(embedded: &[u8], cmd: &DecryptCmd) -> io::Result<(Option<PayloadHeader>, Vec<u8>)> {
+    let body = split_hint(embedded).1;
+    if is_age(body) {
+        return Ok((None, age_decrypt(body, &cmd.identities, &cmd.key)?));
+    }
+    if is_pgp(body) {
+        if cmd.identities.is_empty() {
+            eprintln!(
+                "{} {}",
+                Style::Grey.paint_stderr(tr!("label-note")),
+                tr!("pgp-written-as-is")
+            );
+            return Ok((None, body.to_vec()));
+        }
+        return Ok((None, pgp_decrypt(body, &cmd.identities, &cmd.key)?));
+    }
+    let (header, plaintext) = open_payload(embedded, &cmd.algorithm, &cmd.key)?;
+    if let (Some(header), false) = (&header, cmd.burn) {
+        if header.views > 0 {
+            return Err(SteganoError::Usage(format!(
+                "The payload may be revealed {} more times, pass --burn to reveal it and count the view",
+                header.views
+            ))
+            .into());
+        }
+    }
+    if let Some(header) = header
+        .as_ref()
+        .filter(|header| header.is_expired(unix_now()))
+    {
+        let expired = format!(
+            "The payload expired on {}",
+            rfc3339(UNIX_EPOCH + Duration::from_secs(header.expires))
+        );
+        if !cmd.ignore_expiry {
+            return Err(SteganoError::Expired(format!(
+                "{}, pass --ignore-expiry to reveal it anyway",
+                expired
+            ))
+            .into());
+        }
+        eprintln!(
+            "{} {}.",
+            Style::Yellow.paint_stderr(tr!("label-warning")),
+            expired
+        );
+    }
+    Ok((header, plaintext))
+}
+
+/// Reveals a decrypted payload after a summary of its header in human mode, or a note that it
+/// predates headers.
+fn emit_secret(
+    header: &Option<PayloadHeader>,
+    data: Vec<u8>,
+    format: OutputFormat,
+    cmd: &DecryptCmd,
+    cancel: &CancellationToken,
+) -> io::Result<()> {
+    if format != OutputFormat::Raw && cmd.mode.reveals() {
+        match header {
+            Some(header) => outln!("{} {}", Style::Grey.paint(tr!("label-payload")), header),
+            None => outln!(
+                "{} {}",
+                Style::Grey.paint(tr!("label-payload")),
+                tr!("payload-without-header")
+            ),
+        }
+    }
+    reveal(data, format, cmd, cancel)
+}
+
+/// Writes a decrypted payload to stdout in `--output-encoding` and the resolved format, to
+/// `--output-payload-file`, or shows it as a QR code with `--qr`.
+fn reveal(
+    data: Vec<u8>,
+    format: OutputFormat,
+    cmd: &DecryptCmd,
+    cancel: &CancellationToken,
+) -> io::Result<()> {
+    // Every path reveals the secret last, once the restored carrier is written.
+    if cmd.shred_input {
+        shred(std::path::Path::new(&cmd.input), cmd.passes)?;
+        if !cmd.suppress {
+            outln!(
+                "{}",
+                tr!("input-shredded", path = cmd.input, passes = cmd.passes)
+            );
+        }
+    }
+    if !cmd.mode.reveals() {
+        return Ok(());
+    }
+    let data = match cmd.output_encoding {
+        PayloadEncoding::Raw => data,
+        // A line break ends the text in pipes, like other encoders do.
+        encoding
+            if format == OutputFormat::Raw
+                && cmd.qr.is_none()
+                && cmd.output_payload_file.is_none() =>
+        {
+            let mut text = encoding.encode(&data);
+            text.push(b'\n');
+            text
+        }
+        encoding => encoding.encode(&data),
+    };
+    if let Some(path) = &cmd.output_payload_file {
+        write_output(path, &data, cancel)?;
+        if !cmd.suppress {
+            outln!(
+                "{}",
+                tr!("payload-file-written", bytes = data.len(), path = path)
+            );
+        }
+        return Ok(());
+    }
+    let Some(target) = &cmd.qr else {
+        return write_payload(&data, format, cmd.force_binary);
+    };
+    let code = QrCode::encode(&data)?;
+    if target == "-" {
+        out!("{}", code.to_unicode());
+        return io::stdout().flush();
+    }
+    write_output(target, &code.to_png(8)?, cancel)?;
+    if !cmd.suppress {
+        outln!(
+            "{} {}",
+            Style::Grey.paint(tr!("label-qr-code")),
+            tr!(
+                "qr-written",
+                version = code.version,
+                size = code.size,
+                path = target
+            )
+        );
     }
     Ok(())
 }
@@ -0,0 +1,78 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::ops::Deref;
+use std::path::Path;
+
+/// The bytes of a file opened for scanning, memory-mapped with the `mmap` feature.
+///
+/// Parsers that walk the structure of a carrier, such as `show-meta` over chunk lists, IFD
+/// chains and marker segments, read it as a plain `&[u8]`. With the `mmap` feature the file
+/// is mapped instead of read, so multi-gigabyte files are scanned without copying them into
+/// memory first, and only the pages actually touched are loaded. Without it, or for files
+/// that can't be mapped such as pipes and empty files, the whole file is read.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::mapped::MappedFile;
+///
+/// let path = std::env::temp_dir().join("stegano-mapped-doc.bin");
+/// std::fs::write(&path, b"\x89PNG").unwrap();
+/// let file = MappedFile::open(&path).unwrap();
+/// assert_eq!(&file[..], b"\x89PNG");
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug)]
+pub enum MappedFile {
+    /// A read-only mapping of the file.
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+    /// The contents of the file, read into memory.
+    Read(Vec<u8>),
+}
+
+impl MappedFile {
+    /// Opens a file, mapping it when the `mmap` feature is enabled and the file allows it.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<MappedFile> {
+        let mut file = File::open(path)?;
+        #[cfg(feature = "mmap")]
+        {
+            let metadata = file.metadata()?;
+            if metadata.is_file() && metadata.len() > 0 {
+                // SAFETY: the map is read-only. Another process truncating the file while it
+                // is mapped would fault, as with any mmap-based reader; stegano itself renames
+                // its outputs over its inputs, `rekey --in-place` included, rather than
+                // writing into them.
+                if let Ok(map) = unsafe { memmap2::Mmap::map(&file) } {
+                    return Ok(MappedFile::Mapped(map));
+                }
+            }
+        }
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        Ok(MappedFile::Read(data))
+    }
+
+    /// Tells whether the file is memory-mapped rather than read.
+    pub fn is_mapped(&self) -> bool {
+        !matches!(self, MappedFile::Read(_))
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            #[cfg(feature = "mmap")]
+            MappedFile::Mapped(map) => map,
+            MappedFile::Read(data) => data,
+        }
+    }
+}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
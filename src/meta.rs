@@ -0,0 +1,305 @@
+use crate::chunk_type::ChunkType;
+use crate::header::unix_now;
+use crate::json::{parse_rfc3339, rfc3339};
+use crate::png::{insert_chunk, read_chunks, remove_chunks};
+use std::io::{self, ErrorKind};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// The standard metadata of a PNG file: its `tEXt` entries and its `tIME` timestamp.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PngMetadata {
+    /// The keyword and text of each `tEXt` chunk, in file order.
+    pub text: Vec<(String, String)>,
+    /// The time of the last modification from `tIME`, in seconds since the Unix epoch.
+    pub time: Option<u64>,
+}
+
+impl PngMetadata {
+    /// Tells whether there is neither text nor timestamp.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.time.is_none()
+    }
+
+    /// Returns the text of the first `tEXt` entry with this keyword.
+    pub fn get(&self, keyword: &str) -> Option<&str> {
+        self.text
+            .iter()
+            .find(|(key, _)| key == keyword)
+            .map(|(_, text)| text.as_str())
+    }
+}
+
+/// Builds the error returned for keywords and text `tEXt` can't hold.
+fn invalid(reason: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, reason)
+}
+
+/// Encodes a string in Latin-1, the character set of `tEXt`.
+fn latin1(value: &str) -> Option<Vec<u8>> {
+    value.chars().map(|c| u8::try_from(c).ok()).collect()
+}
+
+/// Builds the data of a `tEXt` chunk: the keyword, a NUL separator and the text, in Latin-1.
+///
+/// The keyword is 1 to 79 printable characters, without leading, trailing or consecutive
+/// spaces, as the PNG specification asks.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::meta::text_chunk;
+///
+/// assert_eq!(text_chunk("Author", "Zoë").unwrap(), b"Author\0Zo\xeb");
+/// assert!(text_chunk(" Author", "Alice").is_err());
+/// assert!(text_chunk("Author", "\u{1F600}").is_err());
+/// ```
+pub fn text_chunk(keyword: &str, text: &str) -> io::Result<Vec<u8>> {
+    let key = latin1(keyword)
+        .filter(|key| {
+            (1..=79).contains(&key.len())
+                && key.iter().all(|&b| matches!(b, 32..=126 | 161..=255))
+                && !keyword.starts_with(' ')
+                && !keyword.ends_with(' ')
+                && !keyword.contains("  ")
+        })
+        .ok_or_else(|| {
+            invalid(format!(
+                "Invalid keyword {:?}: keywords are 1 to 79 printable Latin-1 characters, without leading, trailing or double spaces",
+                keyword
+            ))
+        })?;
+    let text = latin1(text)
+        .filter(|text| !text.contains(&0))
+        .ok_or_else(|| {
+            invalid(format!(
+                "The text of {:?} must be Latin-1, without NUL characters",
+                keyword
+            ))
+        })?;
+    let mut data = key;
+    data.push(0);
+    data.extend_from_slice(&text);
+    Ok(data)
+}
+
+/// Splits the data of a `tEXt` chunk into its keyword and text, or `None` without separator.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::meta::parse_text_chunk;
+///
+/// assert_eq!(
+///     parse_text_chunk(b"Author\0Zo\xeb"),
+///     Some(("Author".into(), "Zoë".into()))
+/// );
+/// assert_eq!(parse_text_chunk(b"Author"), None);
+/// ```
+pub fn parse_text_chunk(data: &[u8]) -> Option<(String, String)> {
+    let nul = data.iter().position(|&b| b == 0)?;
+    let decode = |bytes: &[u8]| bytes.iter().map(|&b| char::from(b)).collect();
+    Some((decode(&data[..nul]), decode(&data[nul + 1..])))
+}
+
+/// Builds the data of a `tIME` chunk: the year as a 16-bit big-endian integer, then the
+/// month, day, hour, minute and second in UTC.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::meta::time_chunk;
+///
+/// assert_eq!(time_chunk(951_782_400 + 3_723), [0x07, 0xd0, 2, 29, 1, 2, 3]);
+/// ```
+pub fn time_chunk(time: u64) -> [u8; 7] {
+    let stamp = rfc3339(UNIX_EPOCH + Duration::from_secs(time));
+    let field = |range: std::ops::Range<usize>| stamp[range].parse::<u16>().unwrap_or(0);
+    let year = field(0..4).to_be_bytes();
+    [
+        year[0],
+        year[1],
+        field(5..7) as u8,
+        field(8..10) as u8,
+        field(11..13) as u8,
+        field(14..16) as u8,
+        field(17..19) as u8,
+    ]
+}
+
+/// Reads the data of a `tIME` chunk back into seconds since the Unix epoch, or `None` when it
+/// isn't a valid date. A leap second of 60 is read as 59.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::meta::parse_time_chunk;
+///
+/// assert_eq!(parse_time_chunk(&[0x07, 0xd0, 2, 29, 1, 2, 3]), Some(951_782_400 + 3_723));
+/// assert_eq!(parse_time_chunk(&[0x07, 0xd1, 2, 29, 1, 2, 3]), None);
+/// ```
+pub fn parse_time_chunk(data: &[u8]) -> Option<u64> {
+    let [year_high, year_low, month, day, hour, minute, second] =
+        *<&[u8; 7]>::try_from(data).ok()?;
+    parse_rfc3339(&format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        u16::from_be_bytes([year_high, year_low]),
+        month,
+        day,
+        hour,
+        minute,
+        second.min(59)
+    ))
+}
+
+/// Parses the value of `meta set --time`: `now`, a `YYYY-MM-DD` date or an RFC 3339 UTC
+/// timestamp such as `2025-12-31T18:00:00Z`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::meta::parse_meta_time;
+///
+/// assert_eq!(parse_meta_time("1970-01-01T00:01:00Z").unwrap(), 60);
+/// assert_eq!(parse_meta_time("1970-01-02").unwrap(), 86_400);
+/// assert!(parse_meta_time("now").unwrap() > 0);
+/// assert!(parse_meta_time("yesterday").is_err());
+/// ```
+pub fn parse_meta_time(value: &str) -> Result<u64, String> {
+    if value == "now" {
+        return Ok(unix_now());
+    }
+    parse_rfc3339(value).ok_or_else(|| {
+        format!(
+            "invalid time {:?}, expected now, YYYY-MM-DD or YYYY-MM-DDTHH:MM:SSZ",
+            value
+        )
+    })
+}
+
+/// Parses the value of `meta set --text`: a keyword and its text, as `KEY=VALUE`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::meta::parse_text_entry;
+///
+/// assert_eq!(
+///     parse_text_entry("Comment=a=b").unwrap(),
+///     ("Comment".into(), "a=b".into())
+/// );
+/// assert!(parse_text_entry("Author").is_err());
+/// ```
+pub fn parse_text_entry(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((keyword, text)) => {
+            text_chunk(keyword, text).map_err(|error| error.to_string())?;
+            Ok((keyword.into(), text.into()))
+        }
+        None => Err(format!("invalid entry {:?}, expected KEY=VALUE", value)),
+    }
+}
+
+/// Reads the `tEXt` entries and `tIME` timestamp of a PNG file, skipping malformed chunks.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::meta::{read_metadata, set_metadata};
+/// use stegano::png::encode_grayscale;
+///
+/// let png = encode_grayscale(1, 1, &[0]).unwrap();
+/// assert!(read_metadata(&png).unwrap().is_empty());
+/// let png = set_metadata(&png, &[("Author".into(), "Alice".into())], Some(60)).unwrap();
+/// let metadata = read_metadata(&png).unwrap();
+/// assert_eq!((metadata.get("Author"), metadata.time), (Some("Alice"), Some(60)));
+/// ```
+pub fn read_metadata(png: &[u8]) -> io::Result<PngMetadata> {
+    let mut metadata = PngMetadata::default();
+    for chunk in read_chunks(png)? {
+        match chunk.r#type {
+            ChunkType::Text => metadata.text.extend(parse_text_chunk(&chunk.data)),
+            ChunkType::Time => metadata.time = metadata.time.or(parse_time_chunk(&chunk.data)),
+            _ => {}
+        }
+    }
+    Ok(metadata)
+}
+
+/// Sets `tEXt` entries and the `tIME` timestamp of a PNG file.
+///
+/// Each entry replaces the `tEXt` chunks of the same keyword, and a timestamp replaces the
+/// `tIME` chunk; the new chunks go before `IEND`. Other chunks, `iTXt` and `zTXt` included,
+/// are kept as they are.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::meta::{read_metadata, set_metadata};
+/// use stegano::png::encode_grayscale;
+///
+/// let png = encode_grayscale(1, 1, &[0]).unwrap();
+/// let png = set_metadata(&png, &[("Author".into(), "Alice".into())], None).unwrap();
+/// let png = set_metadata(&png, &[("Author".into(), "Bob".into())], None).unwrap();
+/// assert_eq!(read_metadata(&png).unwrap().text, [("Author".into(), "Bob".into())]);
+/// ```
+pub fn set_metadata(
+    png: &[u8],
+    text: &[(String, String)],
+    time: Option<u64>,
+) -> io::Result<Vec<u8>> {
+    let chunks = text
+        .iter()
+        .map(|(keyword, value)| text_chunk(keyword, value))
+        .collect::<io::Result<Vec<_>>>()?;
+    let (mut out, _) = remove_chunks(png, |_, chunk| match chunk.r#type {
+        ChunkType::Text => parse_text_chunk(&chunk.data)
+            .is_some_and(|(keyword, _)| text.iter().any(|(key, _)| *key == keyword)),
+        ChunkType::Time => time.is_some(),
+        _ => false,
+    })?;
+    for data in &chunks {
+        out = insert_chunk(&out, b"tEXt", data, None)?.0;
+    }
+    if let Some(time) = time {
+        out = insert_chunk(&out, b"tIME", &time_chunk(time), None)?.0;
+    }
+    Ok(out)
+}
+
+/// Removes the `tEXt` entries whose keyword `keyword` selects, and the `tIME` timestamp if
+/// `time` is set.
+///
+/// # Returns
+///
+/// A `Result` containing the file without them and the metadata that was removed.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::meta::{read_metadata, remove_metadata, set_metadata};
+/// use stegano::png::encode_grayscale;
+///
+/// let png = encode_grayscale(1, 1, &[0]).unwrap();
+/// let stamped = set_metadata(&png, &[("Author".into(), "Alice".into())], Some(60)).unwrap();
+/// let (clean, removed) = remove_metadata(&stamped, |keyword| keyword == "Author", true).unwrap();
+/// assert_eq!((clean, removed.time), (png, Some(60)));
+/// ```
+pub fn remove_metadata<F: Fn(&str) -> bool>(
+    png: &[u8],
+    keyword: F,
+    time: bool,
+) -> io::Result<(Vec<u8>, PngMetadata)> {
+    let (out, removed) = remove_chunks(png, |_, chunk| match chunk.r#type {
+        ChunkType::Text => parse_text_chunk(&chunk.data).is_some_and(|(key, _)| keyword(&key)),
+        ChunkType::Time => time,
+        _ => false,
+    })?;
+    let mut metadata = PngMetadata::default();
+    for chunk in removed {
+        match chunk.r#type {
+            ChunkType::Text => metadata.text.extend(parse_text_chunk(&chunk.data)),
+            _ => metadata.time = metadata.time.or(parse_time_chunk(&chunk.data)),
+        }
+    }
+    Ok((out, metadata))
+}
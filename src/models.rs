@@ -1,27 +1,73 @@
-use crate::cli::{DecryptCmd, EncryptCmd, ShowMetaCmd};
-use crate::utils::{decrypt_data, print_hex, u64_to_u8_array, xor_encrypt_decrypt};
-use std::fs::File;
-use std::io::{copy, Error, ErrorKind, Read, Seek, SeekFrom, Write};
-use std::mem;
+use crate::apng::describe_chunk;
+use crate::chunk_type::{check_order, ChunkType, OrderViolation};
+use crate::cli::ShowMetaCmd;
+use crate::png::{chunks, write_chunk, ChunkFilter, RawChunk};
+use crate::polyglot::PNG_SIGNATURE;
+use crate::style::Style;
+use crate::tr;
+use crate::utils::HexFormatter;
+use crc32_v2::crc32;
+use std::fmt;
+use std::io::{Error, Write};
+use std::ops::Range;
+
+/// The most chunks `show-meta` walks in a PNG file, so that bogus lengths splitting a file
+/// into millions of tiny chunks can't keep it busy.
+pub const MAX_SCANNED_CHUNKS: usize = 1 << 20;
 
 /// Represents the header of a PNG format.
 ///
 /// # Fields
 ///
-/// - `header` - A 64-bit unsigned integer representing the PNG header.
+/// - `header` - The eight bytes of the PNG signature, in file order.
 ///
 /// # Examples
 ///
 /// ```
 /// use stegano::models::Header;
 ///
-/// let png_header = Header { header: 0x8950_4E47_0D0A_1A0A };
-/// println!("PNG Header: {:X}", png_header.header);
+/// let png_header = Header { header: *b"\x89PNG\r\n\x1a\n" };
+/// println!("PNG Header: {:X}", png_header.to_u64());
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Header {
-    /// A 64-bit unsigned integer representing the PNG header.
-    pub header: u64,
+    /// The eight bytes of the PNG signature, in file order.
+    pub header: [u8; 8],
+}
+
+impl Header {
+    /// Builds a header from the signature read as a big-endian integer.
+    ///
+    /// The bytes are laid out most significant first whatever the endianness of the target,
+    /// so a header read from a file and one built from a constant always match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::models::Header;
+    ///
+    /// let header = Header::from_u64(0x8950_4E47_0D0A_1A0A);
+    /// assert_eq!(&header.header, b"\x89PNG\r\n\x1a\n");
+    /// assert_eq!(header.to_u64(), 0x8950_4E47_0D0A_1A0A);
+    ///
+    /// // Round trips through both representations, on little- and big-endian targets alike.
+    /// for value in [0, 1, 0x0102_0304_0506_0708, u64::MAX] {
+    ///     assert_eq!(Header::from_u64(value).to_u64(), value);
+    ///     let bytes = value.to_be_bytes();
+    ///     assert_eq!(Header::from_u64(u64::from_be_bytes(bytes)).header, bytes);
+    /// }
+    /// assert_eq!(Header::from_u64(1).header, [0, 0, 0, 0, 0, 0, 0, 1]);
+    /// ```
+    pub fn from_u64(value: u64) -> Header {
+        Header {
+            header: value.to_be_bytes(),
+        }
+    }
+
+    /// Returns the signature as a big-endian integer.
+    pub fn to_u64(&self) -> u64 {
+        u64::from_be_bytes(self.header)
+    }
 }
 
 /// Represents a generic chunk in the PNG format.
@@ -29,531 +75,438 @@ pub struct Header {
 /// # Fields
 ///
 /// - `size` - The size of the chunk data in bytes.
-/// - `r#type` - A 32-bit unsigned integer representing the chunk type.
+/// - `r#type` - The chunk type.
 /// - `data` - A vector of bytes containing the chunk data.
 /// - `crc` - A 32-bit unsigned integer representing the cyclic redundancy check value for the chunk.
 ///
 /// # Examples
 ///
 /// ```
+/// use stegano::chunk_type::ChunkType;
 /// use stegano::models::Chunk;
 ///
 /// let png_chunk = Chunk {
 ///     size: 13,
-///     r#type: 0x4949_4444,
+///     r#type: ChunkType::Ihdr,
 ///     data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
 ///     crc: 0xABCD_EF01,
 /// };
-/// println!("Chunk Type: {:X}", png_chunk.r#type);
+/// println!("Chunk Type: {} ({})", png_chunk.r#type, png_chunk.r#type.describe());
 /// ```
 #[derive(Debug, Clone)]
 pub struct Chunk {
     /// The size of the chunk data in bytes.
     pub size: u32,
-    /// A 32-bit unsigned integer representing the chunk type.
-    pub r#type: u32,
+    /// The chunk type.
+    pub r#type: ChunkType,
     /// A vector of bytes containing the chunk data.
     pub data: Vec<u8>,
     /// A 32-bit unsigned integer representing the cyclic redundancy check value for the chunk.
     pub crc: u32,
 }
 
-/// Represents a meta chunk in the PNG format, composed of a header and a generic chunk.
+/// Writes a PNG file chunk after chunk, computing lengths and CRCs.
 ///
-/// # Fields
-///
-/// - `header` - The header of the meta chunk.
-/// - `chk` - A generic chunk representing the meta chunk data.
-/// - `offset` - A 64-bit unsigned integer representing the offset of the meta chunk.
+/// The writer owns its output and holds no other state, so it is `Send` whenever its output
+/// is.
 ///
 /// # Examples
 ///
 /// ```
-/// use stegano::models::{MetaChunk, Chunk, Header};
-///
-/// let meta_chunk = MetaChunk {
-///     header: Header { header: 0x8950_4E47_0D0A_1A0A },
-///     chk: Chunk {
-///         size: 13,
-///         r#type: 0x4949_4444,
-///         data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
-///         crc: 0xABCD_EF01,
-///     },
-///     offset: 42,
-/// };
-/// println!("Meta Chunk Offset: {}", meta_chunk.offset);
+/// use stegano::models::PngWriter;
+/// use stegano::png::read_chunks;
+/// use stegano::polyglot::PNG_SIGNATURE;
+///
+/// let mut writer = PngWriter::new(Vec::new()).unwrap();
+/// assert_eq!(writer.write_chunk(b"tEXt", b"k\0v").unwrap(), 8);
+/// assert_eq!(writer.write_chunk(b"IEND", &[]).unwrap(), 23);
+/// let png = writer.into_inner();
+/// assert!(png.starts_with(PNG_SIGNATURE));
+/// assert_eq!(read_chunks(&png).unwrap()[0].data, b"k\0v");
+///
+/// fn assert_send<T: Send>() {}
+/// assert_send::<PngWriter<std::fs::File>>();
 /// ```
-#[derive(Debug, Clone)]
-pub struct MetaChunk {
-    /// The header of the meta chunk.
-    pub header: Header,
-    /// A generic chunk representing the meta chunk data.
-    pub chk: Chunk,
-    /// A 64-bit unsigned integer representing the offset of the meta chunk.
-    pub offset: u64,
+#[derive(Debug)]
+pub struct PngWriter<W> {
+    writer: W,
+    offset: u64,
 }
 
-impl MetaChunk {
-    /// Pre-processes a PNG image file to extract the PNG header and initializes a MetaChunk.
-    ///
-    /// This function reads the PNG header from the provided file, validates it, and creates
-    /// a MetaChunk with an empty Chunk and the offset set to the current position in the file.
-    ///
-    /// # Arguments
-    ///
-    /// - `file` - A mutable reference to a File representing the PNG image file.
-    /// - `suppress`: A boolean to suppress print statements.
-    ///
-    /// # Returns
-    ///
-    /// A Result containing the initialized MetaChunk if successful, or an Error if any
-    /// _errors occur during the process.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the file is not a valid PNG format.
-    pub fn new(file: &mut File, suppress: bool) -> Result<MetaChunk, Error> {
-        let mut header = Header { header: 0 };
-        file.read_exact(unsafe { mem::transmute::<_, &mut [u8; 8]>(&mut header.header) })?;
-        let b_arr = u64_to_u8_array(header.header);
-        let offset = file.stream_position()?;
-        if &b_arr[1..4] != b"PNG" {
-            let _err = Error::new(ErrorKind::Other, "Not a valid PNG file!");
-            return Err(_err);
-        } else if !suppress {
-            println!("It is a valid PNG file. Let's process it! \n");
-            // print header
-            println!("\x1b[92m---- Header ----\x1b[0m");
-            print_hex(&b_arr, 0);
-            print!("\x1b[0m");
-            println!("\x1b[92m----- End ------\x1b[0m");
-            println!();
-        }
-        Ok(MetaChunk {
-            header,
-            chk: Chunk {
-                size: 0,
-                r#type: 0,
-                data: Vec::new(),
-                crc: 0,
-            },
-            offset,
+impl<W: Write> PngWriter<W> {
+    /// Writes the PNG signature.
+    pub fn new(mut writer: W) -> Result<PngWriter<W>, Error> {
+        writer.write_all(PNG_SIGNATURE)?;
+        Ok(PngWriter {
+            writer,
+            offset: PNG_SIGNATURE.len() as u64,
         })
     }
 
-    /// Processes a PNG image file by reading and displaying information about its chunks.
-    ///
-    /// This function iterates through the chunks in the provided file, printing information
-    /// about each chunk, until the 'IEND' chunk is encountered.
-    ///
-    /// # Arguments
-    ///
-    /// - `file` - A mutable reference to a File representing the PNG image file.
-    /// - `c`: A reference to `ShowMetaCmd` containing command-line arguments.
-    pub fn process_image(&mut self, file: &mut File, c: &ShowMetaCmd) {
-        let mut start_position: usize = c.start_chunk;
-        let mut end_position: usize = c.end_chunk;
-        let mut _chunk_type = String::new();
-        let end_chunk_type = "IEND";
-        if c.read_end {
-            file.seek(SeekFrom::End(
-                (-(start_position as isize)).try_into().unwrap(),
-            ))
-            .unwrap();
-            start_position = file.metadata().unwrap().len() as usize - c.nb_chunks;
-            end_position = file.metadata().unwrap().len() as usize - 1;
-        } else {
-            file.seek(SeekFrom::Start((start_position).try_into().unwrap()))
-                .unwrap();
-            if c.start_chunk > 8 {
-                self.offset = start_position as u64;
-            }
-        }
-        for (i, j) in (start_position..end_position).enumerate() {
-            _chunk_type = self.chunk_type_to_string();
-            if i >= c.nb_chunks || _chunk_type == end_chunk_type {
-                break;
-            }
-            self.read_chunk(file);
-            if !c.suppress {
-                println!("\x1b[92m---- Chunk #{} ----\x1b[0m", j);
-                println!("Offset: {:?}", self.offset);
-                println!("Size: {:?}", self.chk.size);
-                println!("CRC: {:x}", self.chk.crc);
-                print_hex(&self.chk.data, self.offset);
-                print!("\x1b[0m");
-                println!("\x1b[92m------- End -------\x1b[0m");
-                println!();
-            }
-            let _offset = self.get_offset(file);
-        }
-    }
-
-    /// Gets the offset from the current position in the file and updates the MetaChunk offset.
-    ///
-    /// This function reads the offset from the current position in the file, updates the offset
-    /// field in the MetaChunk, and returns the obtained offset.
-    ///
-    /// # Arguments
-    ///
-    /// - `file` - A mutable reference to a type implementing Read and Seek.
+    /// Writes a chunk with its length and CRC.
     ///
     /// # Returns
     ///
-    /// The offset obtained from the current position in the file.
-    fn get_offset<T: Read + Seek>(&mut self, file: &mut T) -> u64 {
-        let offset = file.stream_position().unwrap();
-        self.offset = offset;
-        offset
+    /// A `Result` containing the offset the chunk was written at.
+    pub fn write_chunk(&mut self, r#type: &[u8; 4], data: &[u8]) -> Result<u64, Error> {
+        let offset = self.offset;
+        write_chunk(&mut self.writer, r#type, data)?;
+        self.offset += 12 + data.len() as u64;
+        Ok(offset)
     }
 
-    /// Reads a PNG chunk from the provided file and updates the MetaChunk.
-    ///
-    /// This function reads the size, type, data, and CRC of a PNG chunk from the file,
-    /// updates the fields in the MetaChunk, and advances the file cursor accordingly.
-    ///
-    /// # Arguments
-    ///
-    /// - `file` - A mutable reference to a type implementing Read and Seek.
-    fn read_chunk<T: Read + Seek>(&mut self, file: &mut T) {
-        self.read_chunk_size(file);
-        self.read_chunk_type(file);
-        self.read_chunk_bytes(file, self.chk.size);
-        self.read_chunk_crc(file);
+    /// Gives the output back.
+    pub fn into_inner(self) -> W {
+        self.writer
     }
+}
 
-    /// Reads the size of a PNG chunk from the provided file and updates the Chunk size.
-    ///
-    /// This function reads the size field of a PNG chunk from the file and updates the
-    /// size field in the associated Chunk.
-    ///
-    /// # Arguments
-    ///
-    /// - `file` - A mutable reference to a type implementing Read.
-    fn read_chunk_size<R: Read>(&mut self, file: &mut R) {
-        let mut size_bytes = [0; 4];
-
-        match file.read_exact(&mut size_bytes) {
-            Ok(_) => {
-                // Successfully read the expected number of bytes
-                self.chk.size = u32::from_be_bytes(size_bytes);
-                if self.chk.size > 40 {
-                    let min_non_zero_number = *size_bytes
-                        .iter()
-                        .filter(|&&byte| byte != 0)
-                        .min_by(|a, b| a.cmp(b))
-                        .unwrap_or(&0);
-                    self.chk.size = min_non_zero_number as u32;
-                }
-                // self.chk.size = size_bytes[3] as u32;
-            }
-            Err(_err) if _err.kind() == ErrorKind::UnexpectedEof => {
-                // Handle the situation where the file ends before reading the expected bytes
-                // eprintln!("Warning: Reached end of file prematurely while reading chunk size");
-            }
-            Err(_err) => {
-                // eprintln!("Error reading chunk size bytes: {}", _err);
-            }
+/// Formats the chunks of a PNG file as `show-meta` lists them, from chunk `--start` up to
+/// chunk `--end` and at most `--nb-chunks` of them, or the last ones with `--read-end`.
+///
+/// Chunks are walked by their declared lengths with `png::chunks`, numbered from 0 after the
+/// signature, and listed at their real offsets. With `--read-end`, `--start` counts the chunks
+/// skipped back from the last one instead. A chunk running past the end of the file ends the
+/// listing, and the summary tells of it.
+///
+/// # Arguments
+///
+/// - `data` - The whole PNG file.
+/// - `c` - The `ShowMetaCmd` giving the positions and hexdump options.
+/// - `filter` - The chunks to list, from `--filter` and `--grep`.
+/// - `out` - Where the listing goes; nothing is written with `--suppress`, but the chunks are
+///   still read.
+///
+/// # Returns
+///
+/// A Result that is an error if the file isn't a PNG file or can't be written out.
+///
+/// # Examples
+///
+/// ```
+/// use clap::Parser;
+/// use stegano::cli::ShowMetaCmd;
+/// use stegano::models::write_listing;
+/// use stegano::png::{encode_grayscale, ChunkFilter};
+///
+/// let png = encode_grayscale(1, 1, &[0]).unwrap();
+/// let cmd = ShowMetaCmd::parse_from(["show-meta", "-i", "in.png", "-s", "1", "--no-hex"]);
+/// let filter = ChunkFilter::new(&["IDAT".into()], None).unwrap();
+/// let mut out = Vec::new();
+/// write_listing(&png, &cmd, &filter, &mut out).unwrap();
+/// let out = String::from_utf8(out).unwrap();
+/// assert!(out.contains("Chunk #1") && out.contains("Offset: 33") && out.contains("Type: IDAT"));
+/// assert!(!out.contains("IHDR"));
+///
+/// // The last chunk only.
+/// let cmd = ShowMetaCmd::parse_from(["show-meta", "-i", "in.png", "-z", "-n", "1", "--no-hex"]);
+/// let mut out = Vec::new();
+/// write_listing(&png, &cmd, &ChunkFilter::default(), &mut out).unwrap();
+/// let out = String::from_utf8(out).unwrap();
+/// assert!(out.contains("Chunk #2") && out.contains("Name: Image trailer"));
+/// assert!(!out.contains("Chunk #1"));
+/// ```
+pub fn write_listing<W: Write>(
+    data: &[u8],
+    c: &ShowMetaCmd,
+    filter: &ChunkFilter,
+    out: &mut W,
+) -> Result<(), Error> {
+    let range = if c.read_end {
+        let total = listed_chunks(data)?.count();
+        let end = total.saturating_sub(c.start_chunk);
+        end.saturating_sub(c.nb_chunks)..end
+    } else {
+        c.start_chunk..c.end_chunk.min(c.start_chunk.saturating_add(c.nb_chunks))
+    };
+    let mut selected = 0;
+    for (index, chunk) in listed_chunks(data)?.take(range.end).skip(range.start) {
+        let Some(matches) = filter.select(&chunk.r#type.bytes(), &chunk.data) else {
+            continue;
+        };
+        if c.suppress {
+            continue;
         }
+        selected += 1;
+        let offset = chunk.offset as u64;
+        write_entry(out, c, index, offset, &entry(data, chunk), &matches)?;
     }
-
-    /// Reads the type of a PNG chunk from the provided file and updates the Chunk type.
-    ///
-    /// This function reads the type field of a PNG chunk from the file and updates the
-    /// type field in the associated Chunk.
-    ///
-    /// # Arguments
-    ///
-    /// - `file` - A mutable reference to a type implementing Read.
-    fn read_chunk_type<R: Read>(&mut self, file: &mut R) {
-        let mut type_bytes = [0; 4];
-
-        match file.read_exact(&mut type_bytes) {
-            Ok(_) => {
-                // Successfully read the expected number of bytes
-                self.chk.r#type = u32::from_be_bytes(type_bytes);
-            }
-            Err(_err) if _err.kind() == ErrorKind::UnexpectedEof => {
-                // Handle the situation where the file ends before reading the expected bytes
-                // eprintln!("Warning: Reached end of file prematurely while reading chunk type");
-            }
-            Err(_err) => {
-                // eprintln!("Error reading chunk type bytes: {}", _err);
-            }
-        }
+    if !c.suppress && !filter.is_empty() && selected == 0 {
+        writeln!(out, "No chunk matches the filter.")?;
     }
+    Ok(())
+}
 
-    /// Reads the data bytes of a PNG chunk from the provided file and updates the Chunk data.
-    ///
-    /// This function reads the data bytes of a PNG chunk from the file, updates the
-    /// data field in the associated Chunk, and handles the situation where the file ends
-    /// before reading the expected bytes.
-    ///
-    /// # Arguments
-    ///
-    /// - `file` - A mutable reference to a type implementing Read and Seek.
-    /// - `len` - The expected length of the data in bytes.
-    fn read_chunk_bytes<T: Read + Seek>(&mut self, file: &mut T, len: u32) {
-        self.chk.data = vec![0; len as usize];
+/// Numbers the chunks of a PNG file as listings and pages show them: walked by `png::chunks`
+/// up to `MAX_SCANNED_CHUNKS`, so that they are the chunks `summarize_chunks` counts.
+///
+/// # Returns
+///
+/// A `Result` containing the chunks and their indices, or an error if the first chunk can't
+/// be read; a later chunk running past the end of the file ends the walk.
+fn listed_chunks(data: &[u8]) -> Result<impl Iterator<Item = (usize, RawChunk)> + '_, Error> {
+    let mut walk = chunks(data).take(MAX_SCANNED_CHUNKS);
+    let first = walk.next().transpose()?;
+    Ok(first
+        .into_iter()
+        .chain(walk.map_while(Result::ok))
+        .enumerate())
+}
 
-        match file.read_exact(&mut self.chk.data) {
-            Ok(_) => {
-                // Successfully read the expected number of bytes
-            }
-            Err(_err) if _err.kind() == ErrorKind::UnexpectedEof => {
-                // eprintln!("Error reading chunk bytes: Reached end of file prematurely");
-                // Update the length of the Chunk based on the actual number of bytes read
-                self.chk
-                    .data
-                    .truncate(file.stream_position().unwrap() as usize);
-            }
-            Err(_err) => {
-                // eprintln!("Error reading chunk bytes: {}", _err);
-            }
-        }
+/// Turns a chunk found by `png::chunks` into the fields of a listing entry.
+fn entry(data: &[u8], chunk: RawChunk) -> Chunk {
+    Chunk {
+        size: chunk.data.len() as u32,
+        r#type: chunk.r#type,
+        crc: stored_crc(data, &chunk),
+        data: chunk.data,
     }
+}
 
-    /// Reads the CRC field of a PNG chunk from the provided file and updates the Chunk CRC.
-    ///
-    /// This function reads the CRC field of a PNG chunk from the file and updates the
-    /// crc field in the associated Chunk.
-    ///
-    /// # Arguments
-    ///
-    /// - `file` - A mutable reference to a type implementing Read.
-    fn read_chunk_crc<R: Read>(&mut self, file: &mut R) {
-        let mut crc_bytes = [0; 4];
-
-        match file.read_exact(&mut crc_bytes) {
-            Ok(_) => {
-                // Successfully read the expected number of bytes
-                self.chk.crc = u32::from_be_bytes(crc_bytes);
-            }
-            Err(_err) if _err.kind() == ErrorKind::UnexpectedEof => {
-                // Handle the situation where the file ends before reading the expected bytes
-                // eprintln!("Warning: Reached end of file prematurely while reading CRC");
-            }
-            Err(_err) => {
-                // eprintln!("Error reading CRC bytes: {}", _err);
-            }
-        }
+/// Writes one chunk of a listing: its header fields, then its hexdump unless `--no-hex`.
+fn write_entry<W: Write>(
+    out: &mut W,
+    c: &ShowMetaCmd,
+    index: usize,
+    offset: u64,
+    chunk: &Chunk,
+    matches: &[usize],
+) -> Result<(), Error> {
+    let r#type = chunk.r#type.bytes();
+    writeln!(
+        out,
+        "{}",
+        Style::Green.paint(format!("---- Chunk #{} ----", index))
+    )?;
+    writeln!(out, "Offset: {:?}", offset)?;
+    // The code comes first, so that a friendly name is never read without it.
+    writeln!(out, "Type: {}", String::from_utf8_lossy(&r#type))?;
+    writeln!(out, "Name: {}", chunk.r#type.describe())?;
+    writeln!(out, "Size: {:?}", chunk.size)?;
+    writeln!(out, "CRC: {:x}", chunk.crc)?;
+    if let Some(text) = describe_chunk(&r#type, &chunk.data) {
+        writeln!(out, "APNG: {}", text)?;
+    }
+    if !matches.is_empty() {
+        // The data follows the length and type fields.
+        let offsets: Vec<String> = matches
+            .iter()
+            .map(|pos| (offset + 8 + *pos as u64).to_string())
+            .collect();
+        writeln!(out, "Matches at: {}", offsets.join(", "))?;
+    }
+    if !c.no_hex {
+        // The dump is addressed in the file, where the data follows the length and type.
+        HexFormatter::new(offset + 8)
+            .range(c.hex_range.clone())
+            .limit(c.hex_limit)
+            .write(out, &chunk.data)?;
     }
+    writeln!(out, "{}", Style::Green.paint("------- End -------"))?;
+    writeln!(out)
+}
+
+/// A page of a chunk listing, from `--page` and `--per-page`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    /// The page number, counted from 1.
+    pub number: usize,
+    /// The number of chunks per page.
+    pub size: usize,
+}
 
-    /// Converts the type field of the associated Chunk to a string representation.
+impl Page {
+    /// Returns the indices, among the selected chunks, that this page holds.
     ///
-    /// This function converts the type field of the associated Chunk to a string
-    /// representation using utf8_lossy.
+    /// # Examples
     ///
-    /// # Returns
+    /// ```
+    /// use stegano::models::Page;
     ///
-    /// A String representing the type of the associated Chunk.
-    fn chunk_type_to_string(&self) -> String {
-        String::from_utf8_lossy(&self.chk.r#type.to_be_bytes()).to_string()
+    /// assert_eq!(Page { number: 1, size: 50 }.range(), 0..50);
+    /// assert_eq!(Page { number: 3, size: 50 }.range(), 100..150);
+    /// ```
+    pub fn range(&self) -> Range<usize> {
+        let start = self.number.saturating_sub(1).saturating_mul(self.size);
+        start..start.saturating_add(self.size)
     }
 
-    /// Marshals the data of the associated Chunk into a vector of bytes.
-    ///
-    /// This function creates a vector of bytes containing the size, type, data, and CRC
-    /// of the associated Chunk.
-    ///
-    /// # Returns
-    ///
-    /// A vector of bytes containing the marshaled data of the associated Chunk.
-    fn marshal_data(&self) -> Vec<u8> {
-        let mut bytes_msb = Vec::new();
-        bytes_msb.push(self.chk.data.len() as u8);
-        bytes_msb.write_all(&self.chk.r#type.to_be_bytes()).unwrap();
-        bytes_msb.write_all(&self.chk.data).unwrap();
-        bytes_msb.write_all(&self.chk.crc.to_be_bytes()).unwrap();
-        bytes_msb
+    /// Returns how many pages `total` chunks fill, at least one.
+    pub fn count(&self, total: usize) -> usize {
+        total.div_ceil(self.size.max(1)).max(1)
     }
+}
 
-    /// Writes data to a specified writer by encryption.
-    ///
-    /// This function takes a readable and seekable input, command arguments, and a writable output. It performs encryption
-    /// processes based on the provided `EncryptCmd`. It encrypt the data using specific operations. The function uses the
-    /// provided writer to output the processed data.
-    ///
-    /// # Arguments
-    ///
-    /// - `self`: A mutable reference to the instance of the struct containing this method.
-    /// - `r`: A mutable reference to a readable and seekable input implementing `Read` and `Seek` traits.
-    /// - `c`: A reference to `EncryptCmd` containing command-line arguments that determine  the encryption options.
-    /// - `w`: A generic writable output implementing the `Write` trait.
-    pub fn write_encrypted_data<R: Read + Seek, W: Write>(
-        &mut self,
-        r: &mut R,
-        c: &EncryptCmd,
-        mut w: W,
-    ) {
-        let b_arr = u64_to_u8_array(self.header.header);
-        w.write_all(&b_arr).unwrap();
-        let mut offset = c.offset;
+/// Counts of the chunks of a PNG file, for the summary line of `show-meta`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkSummary {
+    /// The number of chunks, up to `IEND`.
+    pub chunks: usize,
+    /// The chunk types outside `REGISTERED_CHUNKS`, each once, in the order they appear.
+    pub unknown_types: Vec<ChunkType>,
+    /// The number of chunks whose CRC doesn't match their type and data.
+    pub crc_errors: usize,
+    /// Whether the last chunk declares more data than the file holds.
+    pub truncated: bool,
+    /// Whether the walk stopped at `MAX_SCANNED_CHUNKS` before the end.
+    pub capped: bool,
+    /// The chunks out of the place the specification gives their type, from `check_order`.
+    pub order_violations: Vec<OrderViolation>,
+}
 
-        let encrypted_data = self.chk.data.clone();
-        let encrypted_data_len = self.chk.data.len();
-        let encrypted_data_crc = self.chk.crc;
-        let init_position = r.stream_position().unwrap();
-        if offset == 9999999999 {
-            // Auto inject at IEND - 11
-            // Read untill IEND
-            offset = self.find_iend_offset(r);
-            r.seek(SeekFrom::Start(init_position)).unwrap();
+impl fmt::Display for ChunkSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            tr!(
+                "summary-counts",
+                chunks = self.chunks,
+                unknown = self.unknown_types.len(),
+                crc_errors = self.crc_errors
+            )
+        )?;
+        if self.truncated {
+            write!(f, "{}", tr!("summary-truncated"))?;
         }
-
-        self.chk.data = encrypted_data.clone();
-        self.chk.size = encrypted_data_len as u32;
-        self.chk.crc = encrypted_data_crc;
-
-        if !c.suppress {
-            println!("\x1b[92m------- Chunk -------\x1b[0m");
-            println!("Offset: {:?}", offset);
-            println!("Size: {:?}", encrypted_data_len);
-            println!("CRC: {:x}", encrypted_data_crc);
-            print_hex(&encrypted_data, offset.try_into().unwrap());
-            print!("\x1b[0m");
-            println!("\x1b[92m-------- End --------\x1b[0m");
-            println!();
+        if self.capped {
+            write!(f, "{}", tr!("summary-capped", chunks = MAX_SCANNED_CHUNKS))?;
         }
-        let mut buff = vec![0; offset - 8];
-        buff.resize(&offset - 8, 0);
-        r.read_exact(&mut buff).unwrap();
-        w.write_all(&buff).unwrap();
-        let data: Vec<u8> = self.marshal_data();
-        w.write_all(&data).unwrap();
-        copy(r, &mut w).unwrap();
-        println!(
-            "Your payload has been encrypted and written at offset {} successfully!",
-            offset
-        );
+        Ok(())
     }
+}
 
-    /// Writes data to a specified writer by decryption.
-    ///
-    /// This function takes a readable and seekable input, command arguments, and a writable output. It performs decryption
-    /// processes based on the provided `DecryptCmd`. It decrypt the data using specific operations. The function uses the
-    /// provided writer to output the processed data.
-    ///
-    /// # Arguments
-    ///
-    /// - `self`: A mutable reference to the instance of the struct containing this method.
-    /// - `r`: A mutable reference to a readable and seekable input implementing `Read` and `Seek` traits.
-    /// - `c`: A reference to `DecryptCmd` containing command-line arguments that determine the decryption options.
-    /// - `w`: A generic writable output implementing the `Write` trait.
-    pub fn write_decrypted_data<R: Read + Seek, W: Write>(
-        &mut self,
-        r: &mut R,
-        c: &DecryptCmd,
-        mut w: W,
-    ) {
-        let b_arr = u64_to_u8_array(self.header.header);
-        w.write_all(&b_arr).unwrap();
-        let mut offset = c.offset;
-        let init_position = r.stream_position().unwrap();
-        if offset == 9999999999 {
-            // Read untill IEND
-            offset = self.find_iend_offset(r);
-            r.seek(SeekFrom::Start(init_position)).unwrap();
-        }
-        let mut buff = vec![0; offset - 8];
-
-        buff.resize(offset - 16, 0);
-        r.read_exact(&mut buff).unwrap();
-        w.write_all(&buff).unwrap();
-        self.offset = r.seek(SeekFrom::Current(5)).unwrap();
-        self.read_chunk(r);
-        let mut decrypted_data: Vec<u8> = vec![0];
-        match (*c.algorithm.to_lowercase()).into() {
-            "aes" => {
-                decrypted_data = decrypt_data(&c.key, &self.chk.data);
-            }
-            "xor" => {
-                decrypted_data = xor_encrypt_decrypt(&self.chk.data, &c.key);
+/// Walks the chunks of a PNG file by their declared lengths and counts them, their unknown
+/// types and their CRC errors.
+///
+/// The lengths are trusted, as long as they fit in the file, so that the counts match the
+/// structure other tools see. At most `MAX_SCANNED_CHUNKS` chunks are walked:
+/// bogus lengths can split a file into millions of 12-byte "chunks".
+///
+/// # Returns
+///
+/// A `Result` containing the counts, or an `InvalidData` error if `data` isn't a PNG file.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::models::summarize_chunks;
+/// use stegano::png::{encode_grayscale, insert_chunk};
+///
+/// let png = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let (mut png, offset) = insert_chunk(&png, b"stEg", b"payload", None).unwrap();
+/// png[offset + 8] ^= 1;
+/// let summary = summarize_chunks(&png).unwrap();
+/// assert_eq!((summary.chunks, summary.crc_errors), (4, 1));
+/// assert_eq!(summary.unknown_types, [*b"stEg"]);
+/// assert!(summary.order_violations.is_empty());
+/// assert_eq!(summary.to_string(), "4 chunks, 1 unknown types, 1 CRC errors");
+/// assert!(summarize_chunks(b"GIF89a..").is_err());
+/// ```
+pub fn summarize_chunks(data: &[u8]) -> Result<ChunkSummary, Error> {
+    let mut summary = ChunkSummary::default();
+    let mut types = Vec::new();
+    let mut walk = chunks(data);
+    for chunk in walk.by_ref().take(MAX_SCANNED_CHUNKS) {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(err) if summary.chunks == 0 => return Err(err),
+            Err(_) => {
+                summary.truncated = true;
+                break;
             }
-            _ => {}
+        };
+        summary.chunks += 1;
+        types.push(chunk.r#type);
+        if !chunk.r#type.is_registered() && !summary.unknown_types.contains(&chunk.r#type) {
+            summary.unknown_types.push(chunk.r#type);
         }
-
-        let decoded_string = String::from_utf8_lossy(&decrypted_data);
-        let unpadded_string = decoded_string.trim_end_matches('\0');
-        if !c.suppress {
-            println!("\x1b[92m------- Chunk -------\x1b[0m");
-            println!("Offset: {:?}", self.offset);
-            println!("Size: {:?}", self.chk.size);
-            println!("CRC: {:x}", self.chk.crc);
-            print_hex(&decrypted_data, offset.try_into().unwrap());
-            print!("\x1b[0m");
-            println!("\x1b[92m-------- End --------\x1b[0m");
-            println!();
+        if stored_crc(data, &chunk) != crc32(crc32(0, &chunk.r#type.bytes()), &chunk.data) {
+            summary.crc_errors += 1;
         }
-        r.seek(SeekFrom::Current(self.chk.data.len().try_into().unwrap()))
-            .expect("Error seeking to offset");
-        println!(
-            "\x1b[38;5;7mYour decrypted secret is:\x1b[0m \x1b[38;5;214m{:?}\x1b[0m",
-            unpadded_string
-        );
-        copy(r, &mut w).unwrap();
-    }
-
-    /// Finds the length of a file given a Read + Seek object.
-    ///
-    /// This function takes a readable and seekable input implementing both the `Read` and `Seek` traits.
-    /// It saves the current position, moves the cursor to the end of the file to determine its length,
-    /// and then restores the cursor to the saved position. The function returns the length of the file.
-    ///
-    /// # Arguments
-    ///
-    /// - `self`: A mutable reference to the instance of the struct containing this method.
-    /// - `file`: A mutable reference to a readable and seekable input.
-    ///
-    /// # Returns
-    ///
-    /// Returns a `Result` containing the length of the file if successful, or an `std::io::Error` if an error occurs.
-    pub fn find_file_length<T>(&mut self, file: &mut T) -> std::io::Result<u64>
-    where
-        T: Read + Seek,
-    {
-        // Save the current position
-        let current_position = file.stream_position()?;
-
-        // Move the cursor to the end of the file
-        let file_length = file.seek(SeekFrom::End(0))?;
-
-        // Move the cursor back to the saved position
-        file.seek(SeekFrom::Start(current_position))?;
-
-        Ok(file_length)
     }
+    summary.capped = summary.chunks == MAX_SCANNED_CHUNKS && walk.next().is_some();
+    summary.order_violations = check_order(&types);
+    Ok(summary)
+}
 
-    /// Finds the offset of the last occurrence of the "IEND" chunk.
-    ///
-    /// This function takes a readable and seekable input implementing both the `Read` and `Seek` traits.
-    /// It iterates through the chunks in the file until it reaches the "IEND" chunk, capturing the offset
-    /// of the last occurrence. The offset is then adjusted for the chunk size, returning the final offset.
-    ///
-    /// # Arguments
-    ///
-    /// - `self`: A mutable reference to the instance of the struct containing this method.
-    /// - `r`: A mutable reference to a readable and seekable input.
-    ///
-    /// # Returns
-    ///
-    /// Returns the offset of the last occurrence of the "IEND" chunk.
-    fn find_iend_offset<R>(&mut self, r: &mut R) -> usize
-    where
-        R: Seek + Read,
-    {
-        let mut iend_offset = 999;
-        let end_chunk_type = "IEND";
+/// Returns the CRC stored after a chunk found by `png::chunks`.
+fn stored_crc(data: &[u8], chunk: &RawChunk) -> u32 {
+    let end = chunk.offset + 12 + chunk.data.len();
+    u32::from_be_bytes(data[end - 4..end].try_into().unwrap())
+}
 
-        while iend_offset < self.find_file_length(r).unwrap() {
-            iend_offset = self.get_offset(r);
-            self.read_chunk(r);
-            let chunk_type = self.chunk_type_to_string();
-            if chunk_type == end_chunk_type {
-                break;
-            }
+/// Formats one page of the chunks of a PNG file, for `show-meta --page`.
+///
+/// Chunks are walked by `png::chunks` as in `write_listing`, so the pages cover the chunks the
+/// summary line counts. They are numbered from 0 and counted after `--filter` and `--grep`,
+/// and the page ends with a line telling which chunks it holds. A chunk running past the end
+/// of the file ends the listing.
+///
+/// # Returns
+///
+/// A `Result` that is an error if the file isn't a PNG file or can't be written out.
+///
+/// # Examples
+///
+/// ```
+/// use clap::Parser;
+/// use stegano::cli::ShowMetaCmd;
+/// use stegano::models::{write_page, Page};
+/// use stegano::png::{encode_grayscale, ChunkFilter};
+///
+/// let png = encode_grayscale(1, 1, &[0]).unwrap();
+/// let cmd = ShowMetaCmd::parse_from(["show-meta", "-i", "in.png", "--page", "2", "--per-page", "2", "--no-hex"]);
+/// let mut out = Vec::new();
+/// write_page(&png, &cmd, Page { number: 2, size: 2 }, &ChunkFilter::default(), &mut out).unwrap();
+/// let out = String::from_utf8(out).unwrap();
+/// assert!(out.contains("Chunk #2") && out.contains("Type: IEND"));
+/// assert!(!out.contains("IHDR"));
+/// assert!(out.ends_with("Page 2 of 2: chunks 3 to 3 of 3\n"));
+/// ```
+pub fn write_page<W: Write>(
+    data: &[u8],
+    c: &ShowMetaCmd,
+    page: Page,
+    filter: &ChunkFilter,
+    out: &mut W,
+) -> Result<(), Error> {
+    let range = page.range();
+    let mut selected = 0;
+    for (index, chunk) in listed_chunks(data)? {
+        let Some(matches) = filter.select(&chunk.r#type.bytes(), &chunk.data) else {
+            continue;
+        };
+        if range.contains(&selected) && !c.suppress {
+            let offset = chunk.offset as u64;
+            write_entry(out, c, index, offset, &entry(data, chunk), &matches)?;
         }
-
-        (iend_offset - 11) as usize
+        selected += 1;
+    }
+    if c.suppress {
+        return Ok(());
+    }
+    if selected == 0 && !filter.is_empty() {
+        return writeln!(out, "No chunk matches the filter.");
+    }
+    let pages = page.count(selected);
+    if range.start >= selected {
+        return writeln!(
+            out,
+            "{}",
+            tr!("page-empty", page = page.number, pages = pages)
+        );
     }
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "page-position",
+            page = page.number,
+            pages = pages,
+            first = range.start + 1,
+            last = range.end.min(selected),
+            total = selected
+        )
+    )
 }
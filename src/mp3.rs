@@ -0,0 +1,559 @@
+use crate::outln;
+use crate::storage::map_input;
+use crate::style::Style;
+use std::error::Error;
+use std::io::{self, ErrorKind, Read, Write};
+
+/// The owner identifier of the `PRIV` frames holding a payload.
+pub const PRIV_OWNER: &[u8] = b"stegano\0";
+
+/// The start of the `GEOB` frames holding a payload: Latin-1 text, a MIME type, no filename
+/// and the content description "stegano".
+pub const GEOB_PREFIX: &[u8] = b"\0application/octet-stream\0\0stegano\0";
+
+/// The largest size an ID3v2 tag can declare, in its 28-bit synchsafe integer.
+const MAX_TAG_SIZE: u64 = (1 << 28) - 1;
+
+/// A frame of an ID3v2 tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Id3Frame {
+    /// The four-character frame ID, such as `TIT2`.
+    pub id: [u8; 4],
+    /// The offset of the frame header in the file.
+    pub offset: usize,
+    /// The size of the frame body, without its 10-byte header.
+    pub size: usize,
+    /// The status and format flags.
+    pub flags: u16,
+}
+
+impl Id3Frame {
+    /// Returns the offset of the frame body.
+    pub fn body(&self) -> usize {
+        self.offset + 10
+    }
+
+    /// Returns the end offset of the frame.
+    pub fn end(&self) -> usize {
+        self.body() + self.size
+    }
+
+    /// Returns the frame ID as text.
+    pub fn id_name(&self) -> String {
+        String::from_utf8_lossy(&self.id).into_owned()
+    }
+}
+
+/// An ID3v2.3 or ID3v2.4 tag at the start of an MP3 file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Id3Tag {
+    /// The major version: 3 or 4.
+    pub version: u8,
+    /// The revision number.
+    pub revision: u8,
+    /// The header flags: unsynchronisation, extended header, experimental and footer.
+    pub flags: u8,
+    /// The size of the tag after its header, without the footer.
+    pub size: usize,
+    /// The frames, in file order.
+    pub frames: Vec<Id3Frame>,
+    /// The offset right after the last frame, where the padding begins.
+    pub frames_end: usize,
+}
+
+impl Id3Tag {
+    /// Tells whether a 10-byte footer follows the tag.
+    pub fn has_footer(&self) -> bool {
+        self.flags & 0x10 != 0
+    }
+
+    /// Returns the offset right after the tag, footer included, where the audio begins.
+    pub fn end(&self) -> usize {
+        10 + self.size + if self.has_footer() { 10 } else { 0 }
+    }
+
+    /// Returns the number of padding bytes after the last frame.
+    pub fn padding(&self) -> usize {
+        10 + self.size - self.frames_end
+    }
+}
+
+/// The kind of frame a payload is hidden in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadFrame {
+    /// A `PRIV` frame, private data of the `PRIV_OWNER` owner.
+    Priv,
+    /// A `GEOB` frame, a general encapsulated object described as "stegano".
+    Geob,
+}
+
+impl PayloadFrame {
+    /// Parses an embedding method: auto or priv, or geob.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::mp3::PayloadFrame;
+    ///
+    /// assert_eq!(PayloadFrame::new("auto").unwrap(), PayloadFrame::Priv);
+    /// assert_eq!(PayloadFrame::new("GEOB").unwrap(), PayloadFrame::Geob);
+    /// assert!(PayloadFrame::new("apic").is_err());
+    /// ```
+    pub fn new(method: &str) -> io::Result<PayloadFrame> {
+        match method.to_lowercase().as_str() {
+            "auto" | "priv" => Ok(PayloadFrame::Priv),
+            "geob" => Ok(PayloadFrame::Geob),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unsupported method {:?} for MP3 files, use priv or geob",
+                    method
+                ),
+            )),
+        }
+    }
+
+    /// Returns the frame ID and the bytes between the frame header and the payload.
+    fn parts(self) -> (&'static [u8; 4], &'static [u8]) {
+        match self {
+            PayloadFrame::Priv => (b"PRIV", PRIV_OWNER),
+            PayloadFrame::Geob => (b"GEOB", GEOB_PREFIX),
+        }
+    }
+}
+
+/// Decodes a 28-bit synchsafe integer, where the high bit of each byte is ignored.
+fn synchsafe(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0, |value, &b| (value << 7) | (b & 0x7F) as usize)
+}
+
+/// Encodes a 28-bit synchsafe integer.
+fn to_synchsafe(value: u64) -> [u8; 4] {
+    std::array::from_fn(|i| ((value >> (21 - 7 * i)) & 0x7F) as u8)
+}
+
+/// Represents an MP3 file and its ID3v2 tag, over its bytes.
+///
+/// The bytes are borrowed, so long recordings can be parsed from a `MappedFile` without
+/// copying them.
+#[derive(Debug, Clone)]
+pub struct Mp3File<'a> {
+    /// The ID3v2 tag at the start of the file, if there is one.
+    pub tag: Option<Id3Tag>,
+    /// Raw bytes of the file.
+    pub data: &'a [u8],
+}
+
+impl<'a> Mp3File<'a> {
+    /// Parses the ID3v2 tag of an MP3 file and lists its frames.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The raw bytes of the MP3 file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed `Mp3File`, or an `InvalidData` error if the tag is
+    /// neither version 2.3 nor 2.4, a frame doesn't fit in the tag, or a file without a tag
+    /// doesn't start with an MPEG audio frame.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::mp3::Mp3File;
+    ///
+    /// let mut mp3 = b"ID3\x03\0\0\0\0\0\x14".to_vec();
+    /// mp3.extend_from_slice(b"TIT2\0\0\0\x06\0\0\0Title");
+    /// mp3.extend_from_slice(&[0; 4]);
+    /// mp3.extend_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+    /// let file = Mp3File::parse(&mp3).unwrap();
+    /// let tag = file.tag.unwrap();
+    /// assert_eq!(tag.frames[0].id_name(), "TIT2");
+    /// assert_eq!((tag.padding(), tag.end()), (4, 30));
+    ///
+    /// // The TIT2 frame declares more bytes than the tag holds.
+    /// mp3[17] = 0x20;
+    /// assert!(Mp3File::parse(&mp3).is_err());
+    /// ```
+    pub fn parse(data: &'a [u8]) -> io::Result<Mp3File<'a>> {
+        let invalid = |msg: String| io::Error::new(ErrorKind::InvalidData, msg);
+        if !data.starts_with(b"ID3") {
+            if data.len() < 2 || data[0] != 0xFF || data[1] & 0xE0 != 0xE0 {
+                return Err(invalid(
+                    "Not an MP3 file: it starts with neither an ID3v2 tag nor an MPEG frame".into(),
+                ));
+            }
+            return Ok(Mp3File { tag: None, data });
+        }
+        let Some(header) = data.get(..10) else {
+            return Err(invalid("Truncated ID3v2 header".into()));
+        };
+        let (version, revision, flags) = (header[3], header[4], header[5]);
+        if version != 3 && version != 4 {
+            return Err(invalid(format!(
+                "Unsupported ID3v2.{} tag, only 2.3 and 2.4 are",
+                version
+            )));
+        }
+        let size = synchsafe(&header[6..10]);
+        let footer = if flags & 0x10 != 0 { 10 } else { 0 };
+        if 10 + size + footer > data.len() {
+            return Err(invalid(format!(
+                "The ID3v2 tag declares {} bytes, past the end of the file",
+                size
+            )));
+        }
+        let tag_end = 10 + size;
+        let mut pos = 10;
+        if flags & 0x40 != 0 {
+            let Some(ext) = data.get(10..14).filter(|_| 14 <= tag_end) else {
+                return Err(invalid("Truncated extended header".into()));
+            };
+            // Version 2.3 counts the bytes after the size, version 2.4 the whole header.
+            let ext_len = match version {
+                3 => 4 + u32::from_be_bytes(ext.try_into().unwrap()) as usize,
+                _ => synchsafe(ext),
+            };
+            if ext_len < 4 || ext_len > tag_end - 10 {
+                return Err(invalid(format!(
+                    "The extended header declares {} bytes, past the end of the tag",
+                    ext_len
+                )));
+            }
+            pos += ext_len;
+        }
+        let mut frames = Vec::new();
+        // Frames end at the first padding byte, or where there is no room for another one.
+        while pos + 10 <= tag_end && data[pos] != 0 {
+            let head = &data[pos..pos + 10];
+            let id: [u8; 4] = head[..4].try_into().unwrap();
+            if !id
+                .iter()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+            {
+                return Err(invalid(format!("Invalid frame ID at offset {}", pos)));
+            }
+            let size = match version {
+                3 => u32::from_be_bytes(head[4..8].try_into().unwrap()) as usize,
+                _ => synchsafe(&head[4..8]),
+            };
+            if size > tag_end - pos - 10 {
+                return Err(invalid(format!(
+                    "The {} frame at offset {} declares {} bytes, past the end of the tag",
+                    String::from_utf8_lossy(&id),
+                    pos,
+                    size
+                )));
+            }
+            frames.push(Id3Frame {
+                id,
+                offset: pos,
+                size,
+                flags: u16::from_be_bytes([head[8], head[9]]),
+            });
+            pos += 10 + size;
+        }
+        Ok(Mp3File {
+            tag: Some(Id3Tag {
+                version,
+                revision,
+                flags,
+                size,
+                frames,
+                frames_end: pos.min(tag_end),
+            }),
+            data,
+        })
+    }
+
+    /// Hides a payload in a `PRIV` or `GEOB` frame, added after the last frame of the tag.
+    ///
+    /// The tag grows by the size of the frame and its padding is kept, so that removing the
+    /// frame gives the original file back. A file without a tag gets an ID3v2.3 one.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The bytes to hide.
+    /// * `kind` - The frame to hide them in.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the new MP3 file, or an `InvalidInput` error if the
+    /// tag would grow past 256 MiB.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::mp3::{Mp3File, PayloadFrame};
+    ///
+    /// let audio = [0xFF, 0xFB, 0x90, 0x00];
+    /// let mut tagged = b"ID3\x04\0\0\0\0\0\x14".to_vec();
+    /// tagged.extend_from_slice(b"TIT2\0\0\0\x06\0\0\x03Title");
+    /// tagged.extend_from_slice(&[0; 4]);
+    /// tagged.extend_from_slice(&audio);
+    /// for mp3 in [audio.to_vec(), tagged] {
+    ///     for kind in [PayloadFrame::Priv, PayloadFrame::Geob] {
+    ///         let stego = Mp3File::parse(&mp3).unwrap().embed_in_frame(b"secret", kind).unwrap();
+    ///         let file = Mp3File::parse(&stego).unwrap();
+    ///         assert_eq!(file.extract_payload().unwrap(), b"secret");
+    ///         assert_eq!(file.remove_payload().unwrap(), mp3);
+    ///     }
+    /// }
+    /// ```
+    pub fn embed_in_frame(&self, payload: &[u8], kind: PayloadFrame) -> io::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(self.data.len() + payload.len() + 64);
+        self.write_frame_embedded(payload, payload.len() as u64, kind, &mut out)?;
+        Ok(out)
+    }
+
+    /// Streams the file with a payload frame added to its tag to `writer`.
+    ///
+    /// This is the streaming counterpart of `embed_in_frame`, used when the payload is too
+    /// big to be held in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The source of the bytes to hide.
+    /// * `len` - The number of bytes to read from `payload`.
+    /// * `kind` - The frame to hide them in.
+    /// * `writer` - The destination of the new MP3 file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the offset of the payload in the new file.
+    pub fn write_frame_embedded<R: Read, W: Write>(
+        &self,
+        payload: R,
+        len: u64,
+        kind: PayloadFrame,
+        mut writer: W,
+    ) -> io::Result<u64> {
+        let (id, prefix) = kind.parts();
+        let body_len = prefix.len() as u64 + len;
+        let (version, size) = match &self.tag {
+            Some(tag) => (tag.version, tag.size as u64),
+            None => (3, 0),
+        };
+        if size + 10 + body_len > MAX_TAG_SIZE {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Payload too large for an ID3v2 tag, which holds up to 256 MiB",
+            ));
+        }
+        if self.tag.as_ref().is_some_and(|tag| tag.flags & 0x80 != 0) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Unsynchronised ID3v2 tags aren't supported",
+            ));
+        }
+        let new_size = to_synchsafe(size + 10 + body_len);
+        let mut frame = id.to_vec();
+        match version {
+            3 => frame.extend_from_slice(&(body_len as u32).to_be_bytes()),
+            _ => frame.extend_from_slice(&to_synchsafe(body_len)),
+        }
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(prefix);
+        let offset = match &self.tag {
+            Some(tag) => {
+                writer.write_all(&self.data[..6])?;
+                writer.write_all(&new_size)?;
+                writer.write_all(&self.data[10..tag.frames_end])?;
+                tag.frames_end
+            }
+            None => {
+                writer.write_all(b"ID3\x03\0\0")?;
+                writer.write_all(&new_size)?;
+                10
+            }
+        };
+        writer.write_all(&frame)?;
+        let copied = io::copy(&mut payload.take(len), &mut writer)?;
+        if copied != len {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Payload shorter than announced",
+            ));
+        }
+        if let Some(tag) = &self.tag {
+            writer.write_all(&self.data[tag.frames_end..10 + tag.size])?;
+            // The footer repeats the header, with the new size too.
+            if tag.has_footer() {
+                writer.write_all(b"3DI")?;
+                writer.write_all(&self.data[3..6])?;
+                writer.write_all(&new_size)?;
+            }
+            writer.write_all(&self.data[tag.end()..])?;
+        } else {
+            writer.write_all(self.data)?;
+        }
+        Ok((offset + frame.len()) as u64)
+    }
+
+    /// Returns the last frame holding a payload, and the offset the payload starts at.
+    pub fn payload_frame(&self) -> Option<(&Id3Frame, usize)> {
+        self.tag.as_ref()?.frames.iter().rev().find_map(|frame| {
+            let prefix = match &frame.id {
+                b"PRIV" => PRIV_OWNER,
+                b"GEOB" => GEOB_PREFIX,
+                _ => return None,
+            };
+            self.data[frame.body()..frame.end()]
+                .starts_with(prefix)
+                .then_some((frame, frame.body() + prefix.len()))
+        })
+    }
+
+    /// Extracts a payload hidden in a `PRIV` or `GEOB` frame.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the payload bytes, or a `NotFound` error if no payload was found.
+    pub fn extract_payload(&self) -> io::Result<Vec<u8>> {
+        let (frame, start) = self.payload_frame().ok_or_else(not_found)?;
+        Ok(self.data[start..frame.end()].to_vec())
+    }
+
+    /// Removes the frame holding a payload, restoring the original file.
+    ///
+    /// A tag left without frames or padding was added along with the payload, and is
+    /// removed too.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the cleaned file, or a `NotFound` error if no
+    /// payload was found.
+    pub fn remove_payload(&self) -> io::Result<Vec<u8>> {
+        let (frame, _) = self.payload_frame().ok_or_else(not_found)?;
+        let tag = self.tag.as_ref().ok_or_else(not_found)?;
+        let size = tag.size - (frame.end() - frame.offset);
+        if tag.frames.len() == 1 && tag.padding() == 0 && tag.flags == 0 && tag.version == 3 {
+            return Ok(self.data[tag.end()..].to_vec());
+        }
+        let mut out = Vec::with_capacity(self.data.len());
+        out.extend_from_slice(&self.data[..6]);
+        out.extend_from_slice(&to_synchsafe(size as u64));
+        out.extend_from_slice(&self.data[10..frame.offset]);
+        out.extend_from_slice(&self.data[frame.end()..10 + tag.size]);
+        if tag.has_footer() {
+            out.extend_from_slice(&self.data[10 + tag.size..16 + tag.size]);
+            out.extend_from_slice(&to_synchsafe(size as u64));
+        }
+        out.extend_from_slice(&self.data[tag.end()..]);
+        Ok(out)
+    }
+}
+
+/// Builds the error returned when a file holds no payload frame.
+fn not_found() -> io::Error {
+    io::Error::new(ErrorKind::NotFound, "No payload found in the MP3 file")
+}
+
+/// Describes the content of a frame in a few words: the text of text frames, the owner of
+/// private frames and the description of objects and comments.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::mp3::describe_frame;
+///
+/// assert_eq!(describe_frame(b"TIT2", b"\0Title"), "Title");
+/// assert_eq!(describe_frame(b"PRIV", b"stegano\0\x01\x02"), "owner stegano");
+/// assert_eq!(describe_frame(b"APIC", b"\0image/png\0\x03\0"), "");
+/// ```
+pub fn describe_frame(id: &[u8; 4], body: &[u8]) -> String {
+    let latin1 = |bytes: &[u8]| -> String {
+        bytes
+            .iter()
+            .take_while(|&&b| b != 0)
+            .map(|&b| b as char)
+            .collect()
+    };
+    match id {
+        b"PRIV" => format!("owner {}", latin1(body)),
+        // Only Latin-1 and UTF-8 text is shown; UTF-16 needs a decoder for little gain.
+        [b'T', ..] if id != b"TXXX" && matches!(body.first(), Some(0 | 3)) => {
+            String::from_utf8_lossy(&body[1..])
+                .trim_end_matches('\0')
+                .to_string()
+        }
+        b"GEOB" if body.first() == Some(&0) => {
+            let mut fields = body[1..].split(|&b| b == 0);
+            let mime = latin1(fields.next().unwrap_or(&[]));
+            let _filename = fields.next();
+            let description = latin1(fields.next().unwrap_or(&[]));
+            format!("{} {:?}", mime, description)
+        }
+        _ => String::new(),
+    }
+}
+
+/// Reads an MP3 file and prints its ID3v2 header and frames.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice representing the path to the MP3 file.
+/// * `nb_frames` - The maximum number of frames to print.
+/// * `suppress` - A boolean to suppress print statements.
+///
+/// # Returns
+///
+/// A `Result` that is an error if the file could not be read or parsed.
+pub fn read_mp3_frames(
+    file_path: &str,
+    nb_frames: usize,
+    suppress: bool,
+) -> Result<(), Box<dyn Error>> {
+    let data = map_input(file_path)?;
+    let file = Mp3File::parse(&data)?;
+    if suppress {
+        return Ok(());
+    }
+
+    outln!("It is a valid MP3 file. Let's process it! \n");
+    let Some(tag) = &file.tag else {
+        outln!("{}", Style::Yellow.paint("The file has no ID3v2 tag."));
+        return Ok(());
+    };
+    outln!("{}", Style::Green.paint("---- Header ----"));
+    outln!("Version: ID3v2.{}.{}", tag.version, tag.revision);
+    outln!("Flags: {:#04x}", tag.flags);
+    outln!("Size: {}", tag.size);
+    outln!("{}", Style::Green.paint("----- End ------"));
+    outln!();
+
+    outln!("{}", Style::Green.paint("---- Frames ----"));
+    outln!(
+        "{}",
+        Style::Grey.paint(format!(
+            "{:<10} {:<4} {:>10}  {}",
+            "Offset", "ID", "Size", "Content"
+        ))
+    );
+    let payload = file.payload_frame().map(|(frame, _)| frame.offset);
+    for frame in tag.frames.iter().take(nb_frames) {
+        let content: String = describe_frame(&frame.id, &data[frame.body()..frame.end()])
+            .chars()
+            .take(60)
+            .collect();
+        let mark = if payload == Some(frame.offset) {
+            Style::Yellow.paint(" (stegano payload)").to_string()
+        } else {
+            String::new()
+        };
+        outln!(
+            "{:<10} {:<4} {:>10}  {}{}",
+            frame.offset,
+            frame.id_name(),
+            frame.size,
+            content,
+            mark
+        );
+    }
+    outln!("{}", Style::Green.paint("----- End ------"));
+    outln!("Padding: {} bytes", tag.padding());
+    outln!("Audio: {} bytes", data.len() - tag.end());
+    Ok(())
+}
@@ -0,0 +1,448 @@
+use crate::outln;
+use crate::storage::map_input;
+use crate::style::Style;
+use std::error::Error;
+use std::io::{self, ErrorKind, Read, Write};
+
+/// Magic marker opening a payload stored in a `free` or `skip` box.
+pub const BOX_MAGIC: &[u8; 4] = b"STGM";
+
+/// The extended type of the `uuid` boxes holding a payload.
+pub const STEGANO_UUID: [u8; 16] = [
+    0x8a, 0x3e, 0x5c, 0x1f, 0x27, 0xd4, 0x4b, 0x96, 0xa0, 0x5b, 0x73, 0x74, 0x65, 0x67, 0x61, 0x6e,
+];
+
+/// The boxes holding other boxes, which are walked into.
+const CONTAINERS: [&[u8; 4]; 14] = [
+    b"moov", b"trak", b"mdia", b"minf", b"stbl", b"udta", b"edts", b"dinf", b"mvex", b"moof",
+    b"traf", b"mfra", b"meta", b"ilst",
+];
+
+/// How deep containers may nest before the file is rejected.
+const MAX_DEPTH: usize = 16;
+
+/// A box (or atom) of an MP4 or QuickTime file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mp4Box {
+    /// The four-character type, such as `moov`.
+    pub r#type: [u8; 4],
+    /// The extended type of a `uuid` box.
+    pub uuid: Option<[u8; 16]>,
+    /// The offset of the box in the file.
+    pub offset: usize,
+    /// The length of the header: 8 bytes, 16 with a 64-bit size, plus 16 for a `uuid` box.
+    pub header_len: usize,
+    /// The size of the box, header included. A box declaring a size of 0 runs to the end of
+    /// the file and gets the size it covers.
+    pub size: usize,
+    /// The boxes inside a container box.
+    pub children: Vec<Mp4Box>,
+}
+
+impl Mp4Box {
+    /// Returns the end offset of the box.
+    pub fn end(&self) -> usize {
+        self.offset + self.size
+    }
+
+    /// Returns the type as text, with bytes outside printable ASCII shown as dots.
+    pub fn type_name(&self) -> String {
+        self.r#type
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+            .collect()
+    }
+}
+
+/// The kind of box a payload is hidden in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadBox {
+    /// A `free` box, the padding players skip.
+    Free,
+    /// A `skip` box, the older name of `free`.
+    Skip,
+    /// A `uuid` box with the `STEGANO_UUID` extended type, the place for vendor data.
+    Uuid,
+}
+
+impl PayloadBox {
+    /// Parses an embedding method: auto or free, skip, or uuid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::mp4::PayloadBox;
+    ///
+    /// assert_eq!(PayloadBox::new("auto").unwrap(), PayloadBox::Free);
+    /// assert_eq!(PayloadBox::new("UUID").unwrap(), PayloadBox::Uuid);
+    /// assert!(PayloadBox::new("mdat").is_err());
+    /// ```
+    pub fn new(method: &str) -> io::Result<PayloadBox> {
+        match method.to_lowercase().as_str() {
+            "auto" | "free" => Ok(PayloadBox::Free),
+            "skip" => Ok(PayloadBox::Skip),
+            "uuid" => Ok(PayloadBox::Uuid),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unsupported method {:?} for MP4 files, use free, skip or uuid",
+                    method
+                ),
+            )),
+        }
+    }
+
+    /// Returns the header of a box of this kind holding `len` bytes of payload, with a 64-bit
+    /// size when a 32-bit one can't hold it.
+    fn header(self, len: u64) -> Vec<u8> {
+        let (r#type, tag): (&[u8; 4], &[u8]) = match self {
+            PayloadBox::Free => (b"free", BOX_MAGIC),
+            PayloadBox::Skip => (b"skip", BOX_MAGIC),
+            PayloadBox::Uuid => (b"uuid", &STEGANO_UUID),
+        };
+        let mut header = Vec::with_capacity(36);
+        let size = 8 + tag.len() as u64 + len;
+        match u32::try_from(size) {
+            Ok(size) => {
+                header.extend_from_slice(&size.to_be_bytes());
+                header.extend_from_slice(r#type);
+            }
+            Err(_) => {
+                header.extend_from_slice(&1u32.to_be_bytes());
+                header.extend_from_slice(r#type);
+                header.extend_from_slice(&(size + 8).to_be_bytes());
+            }
+        }
+        header.extend_from_slice(tag);
+        header
+    }
+}
+
+/// Represents the box structure of an MP4 file, over its bytes.
+///
+/// The bytes are borrowed, so multi-gigabyte videos can be parsed from a `MappedFile`
+/// without copying them.
+#[derive(Debug, Clone)]
+pub struct Mp4File<'a> {
+    /// The top-level boxes, with the boxes of the containers inside them.
+    pub boxes: Vec<Mp4Box>,
+    /// Raw bytes of the file.
+    pub data: &'a [u8],
+}
+
+impl<'a> Mp4File<'a> {
+    /// Parses the boxes of an MP4 file (ISO base media: MP4, M4A, M4V, MOV, 3GP).
+    ///
+    /// Containers such as `moov`, `trak` and `udta` are walked into; the other boxes, `mdat`
+    /// among them, are skipped over.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The raw bytes of the MP4 file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed `Mp4File`, or an `InvalidData` error if a box doesn't
+    /// fit in its parent, or if there is no `ftyp` or `moov` box at the top level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::mp4::Mp4File;
+    ///
+    /// let mut mp4 = Vec::new();
+    /// mp4.extend_from_slice(b"\0\0\0\x10ftypisom\0\0\x02\0");
+    /// mp4.extend_from_slice(b"\0\0\0\x10moov\0\0\0\x08udta");
+    /// mp4.extend_from_slice(b"\0\0\0\x0Cmdat\x01\x02\x03\x04");
+    /// let file = Mp4File::parse(&mp4).unwrap();
+    /// let types: Vec<String> = file.boxes.iter().map(|b| b.type_name()).collect();
+    /// assert_eq!(types, ["ftyp", "moov", "mdat"]);
+    /// assert_eq!(file.boxes[1].children[0].type_name(), "udta");
+    ///
+    /// // The mdat box declares more bytes than the file holds.
+    /// assert!(Mp4File::parse(&mp4[..40]).is_err());
+    /// ```
+    pub fn parse(data: &'a [u8]) -> io::Result<Mp4File<'a>> {
+        let boxes = parse_boxes(data, 0, data.len(), 0)?;
+        if !boxes
+            .iter()
+            .any(|b| &b.r#type == b"ftyp" || &b.r#type == b"moov")
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "Not an MP4 file: it has no ftyp or moov box",
+            ));
+        }
+        Ok(Mp4File { boxes, data })
+    }
+
+    /// Returns the major brand and the compatible brands of the `ftyp` box, if there is one.
+    pub fn brands(&self) -> Option<(String, Vec<String>)> {
+        let ftyp = self.boxes.iter().find(|b| &b.r#type == b"ftyp")?;
+        let body = &self.data[ftyp.offset + ftyp.header_len..ftyp.end()];
+        let text = |b: &[u8]| String::from_utf8_lossy(b).into_owned();
+        let major = text(body.get(..4)?);
+        let compatible = body
+            .get(8..)
+            .unwrap_or(&[])
+            .chunks_exact(4)
+            .map(text)
+            .collect();
+        Some((major, compatible))
+    }
+
+    /// Hides a payload in a box appended after the last top-level box.
+    ///
+    /// Players skip `free`, `skip` and unknown `uuid` boxes, and the box goes after
+    /// everything else, so the sample offsets in `stco` and `co64` stay valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The bytes to hide.
+    /// * `kind` - The box to hide them in.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the new MP4 file, or an `InvalidInput` error if the
+    /// last box runs to the end of the file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::mp4::{Mp4File, PayloadBox};
+    ///
+    /// let mut mp4 = Vec::new();
+    /// mp4.extend_from_slice(b"\0\0\0\x10ftypisom\0\0\x02\0");
+    /// mp4.extend_from_slice(b"\0\0\0\x0Cmdat\x01\x02\x03\x04");
+    /// for kind in [PayloadBox::Free, PayloadBox::Skip, PayloadBox::Uuid] {
+    ///     let stego = Mp4File::parse(&mp4).unwrap().embed_in_box(b"secret", kind).unwrap();
+    ///     let file = Mp4File::parse(&stego).unwrap();
+    ///     assert_eq!(file.boxes.len(), 3);
+    ///     assert_eq!(file.extract_payload().unwrap(), b"secret");
+    ///     assert_eq!(file.remove_payload().unwrap(), mp4);
+    /// }
+    /// ```
+    pub fn embed_in_box(&self, payload: &[u8], kind: PayloadBox) -> io::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(self.data.len() + payload.len() + 32);
+        self.write_box_embedded(payload, payload.len() as u64, kind, &mut out)?;
+        Ok(out)
+    }
+
+    /// Streams the file with a payload box appended to `writer`.
+    ///
+    /// This is the streaming counterpart of `embed_in_box`, used when the payload is too big
+    /// to be held in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The source of the bytes to hide.
+    /// * `len` - The number of bytes to read from `payload`.
+    /// * `kind` - The box to hide them in.
+    /// * `writer` - The destination of the new MP4 file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the offset of the payload in the new file.
+    pub fn write_box_embedded<R: Read, W: Write>(
+        &self,
+        payload: R,
+        len: u64,
+        kind: PayloadBox,
+        mut writer: W,
+    ) -> io::Result<u64> {
+        // A size of 0 makes the last box swallow anything written after it.
+        if let Some(last) = self.boxes.last() {
+            if self.data[last.offset..last.offset + 4] == [0; 4] {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "The {} box at offset {} runs to the end of the file, so no box can follow it",
+                        last.type_name(),
+                        last.offset
+                    ),
+                ));
+            }
+        }
+        let header = kind.header(len);
+        writer.write_all(self.data)?;
+        writer.write_all(&header)?;
+        let copied = io::copy(&mut payload.take(len), &mut writer)?;
+        if copied != len {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Payload shorter than announced",
+            ));
+        }
+        Ok((self.data.len() + header.len()) as u64)
+    }
+
+    /// Returns the last top-level box holding a payload, and the offset the payload starts at.
+    pub fn payload_box(&self) -> Option<(&Mp4Box, usize)> {
+        self.boxes.iter().rev().find_map(|b| {
+            let body = b.offset + b.header_len;
+            match &b.r#type {
+                b"free" | b"skip" if self.data[body..b.end()].starts_with(BOX_MAGIC) => {
+                    Some((b, body + BOX_MAGIC.len()))
+                }
+                b"uuid" if b.uuid == Some(STEGANO_UUID) => Some((b, body)),
+                _ => None,
+            }
+        })
+    }
+
+    /// Extracts a payload hidden in a `free`, `skip` or `uuid` box.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the payload bytes, or a `NotFound` error if no payload was found.
+    pub fn extract_payload(&self) -> io::Result<Vec<u8>> {
+        let (found, start) = self.payload_box().ok_or_else(not_found)?;
+        Ok(self.data[start..found.end()].to_vec())
+    }
+
+    /// Removes the box holding a payload, restoring the original file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the cleaned file, or a `NotFound` error if no
+    /// payload was found.
+    pub fn remove_payload(&self) -> io::Result<Vec<u8>> {
+        let (found, _) = self.payload_box().ok_or_else(not_found)?;
+        Ok([&self.data[..found.offset], &self.data[found.end()..]].concat())
+    }
+}
+
+/// Builds the error returned when a file holds no payload box.
+fn not_found() -> io::Error {
+    io::Error::new(ErrorKind::NotFound, "No payload found in the MP4 file")
+}
+
+/// Parses the boxes between `start` and `end`, walking into the containers.
+fn parse_boxes(data: &[u8], start: usize, end: usize, depth: usize) -> io::Result<Vec<Mp4Box>> {
+    let invalid = |msg: String| io::Error::new(ErrorKind::InvalidData, msg);
+    if depth > MAX_DEPTH {
+        return Err(invalid(format!(
+            "Boxes nested more than {} deep at offset {}",
+            MAX_DEPTH, start
+        )));
+    }
+    let mut boxes = Vec::new();
+    let mut pos = start;
+    while pos < end {
+        let Some(head) = data.get(pos..pos + 8).filter(|_| pos + 8 <= end) else {
+            return Err(invalid(format!("Truncated box header at offset {}", pos)));
+        };
+        let r#type: [u8; 4] = head[4..8].try_into().unwrap();
+        let mut header_len = 8;
+        let size = match u32::from_be_bytes(head[..4].try_into().unwrap()) {
+            // Only a top-level box may run to the end of the file.
+            0 if depth == 0 => end - pos,
+            1 => {
+                let Some(large) = data.get(pos + 8..pos + 16).filter(|_| pos + 16 <= end) else {
+                    return Err(invalid(format!(
+                        "Truncated 64-bit box size at offset {}",
+                        pos
+                    )));
+                };
+                header_len = 16;
+                usize::try_from(u64::from_be_bytes(large.try_into().unwrap())).unwrap_or(usize::MAX)
+            }
+            size => size as usize,
+        };
+        let uuid = if &r#type == b"uuid" {
+            let extended = data
+                .get(pos + header_len..pos + header_len + 16)
+                .and_then(|b| <[u8; 16]>::try_from(b).ok());
+            header_len += 16;
+            extended
+        } else {
+            None
+        };
+        if size < header_len || size > end - pos {
+            return Err(invalid(format!(
+                "The {} box at offset {} declares {} bytes, past the end of its parent",
+                String::from_utf8_lossy(&r#type),
+                pos,
+                size
+            )));
+        }
+        let children = if CONTAINERS.contains(&&r#type) {
+            let mut body = pos + header_len;
+            // The ISO meta box is a full box, with a version and flags before its children;
+            // the QuickTime one isn't.
+            if &r#type == b"meta" && data.get(body + 4..body + 8) != Some(b"hdlr") {
+                body += 4;
+            }
+            parse_boxes(data, body.min(pos + size), pos + size, depth + 1)?
+        } else {
+            Vec::new()
+        };
+        boxes.push(Mp4Box {
+            r#type,
+            uuid,
+            offset: pos,
+            header_len,
+            size,
+            children,
+        });
+        pos += size;
+    }
+    Ok(boxes)
+}
+
+/// Prints a box and its children, indented by their depth, while `budget` lasts.
+fn print_box(file: &Mp4File, b: &Mp4Box, depth: usize, budget: &mut usize) {
+    if *budget == 0 {
+        return;
+    }
+    *budget -= 1;
+    let name = format!("{}{}", "  ".repeat(depth), b.type_name());
+    let payload = file
+        .payload_box()
+        .filter(|(found, _)| found.offset == b.offset)
+        .map(|_| Style::Yellow.paint(" (stegano payload)").to_string())
+        .unwrap_or_default();
+    outln!("{:<12} {:>12}  {}{}", b.offset, b.size, name, payload);
+    for child in &b.children {
+        print_box(file, child, depth + 1, budget);
+    }
+}
+
+/// Reads an MP4 file and prints its box tree.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice representing the path to the MP4 file.
+/// * `nb_boxes` - The maximum number of boxes to print.
+/// * `suppress` - A boolean to suppress print statements.
+///
+/// # Returns
+///
+/// A `Result` that is an error if the file could not be read or parsed.
+pub fn read_mp4_boxes(
+    file_path: &str,
+    nb_boxes: usize,
+    suppress: bool,
+) -> Result<(), Box<dyn Error>> {
+    let data = map_input(file_path)?;
+    let file = Mp4File::parse(&data)?;
+    if suppress {
+        return Ok(());
+    }
+
+    outln!("It is a valid MP4 file. Let's process it! \n");
+    if let Some((major, compatible)) = file.brands() {
+        outln!("Brand: {} (compatible: {})", major, compatible.join(", "));
+    }
+    outln!("{}", Style::Green.paint("---- Boxes ----"));
+    outln!(
+        "{}",
+        Style::Grey.paint(format!("{:<12} {:>12}  {}", "Offset", "Size", "Type"))
+    );
+    let mut budget = nb_boxes;
+    for b in &file.boxes {
+        print_box(&file, b, 0, &mut budget);
+    }
+    outln!("{}", Style::Green.paint("----- End -----"));
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use crate::codec::{decode_bytes, encode_bytes, CodecOptions, Decoded};
+use std::io::{self, SeekFrom};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use tokio::task::spawn_blocking;
+
+/// Reads a whole stream from its start, wherever its cursor was.
+async fn read_from_start<R: AsyncRead + AsyncSeek + Unpin>(reader: &mut R) -> io::Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(0)).await?;
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).await?;
+    Ok(data)
+}
+
+/// Runs a CPU-bound step on the blocking thread pool, so the runtime keeps serving other
+/// tasks meanwhile.
+async fn blocking<T, F>(step: F) -> io::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+{
+    spawn_blocking(step).await.map_err(io::Error::other)?
+}
+
+/// Encrypts a payload and embeds it into a carrier read from an async stream.
+///
+/// This is the async counterpart of `codec::encode_bytes`: the carrier and the stego file
+/// are read and written without blocking, and the embedding itself, which can take a while
+/// with the pixel methods, runs on the blocking thread pool of the current Tokio runtime.
+///
+/// # Arguments
+///
+/// * `carrier` - The carrier file, read from its start.
+/// * `payload` - The secret to hide.
+/// * `opts` - The carrier type, method, algorithm and key.
+/// * `output` - Where the stego file is written; it is flushed before returning.
+///
+/// # Returns
+///
+/// A `Result` containing the number of bytes written to `output`.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use stegano::codec::CodecOptions;
+/// use stegano::nonblocking::{decode_async, encode_async};
+/// use stegano::png::encode_grayscale;
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// runtime.block_on(async {
+///     let carrier = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+///     let opts = CodecOptions::default();
+///     let mut stego = Cursor::new(Vec::new());
+///     encode_async(&mut Cursor::new(carrier.clone()), b"hello", &opts, &mut stego)
+///         .await
+///         .unwrap();
+///
+///     let decoded = decode_async(&mut stego, &opts).await.unwrap();
+///     assert_eq!(decoded.payload, b"hello");
+///     assert_eq!(decoded.carrier, carrier);
+/// });
+/// ```
+pub async fn encode_async<R, W>(
+    carrier: &mut R,
+    payload: &[u8],
+    opts: &CodecOptions,
+    output: &mut W,
+) -> io::Result<u64>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let carrier = read_from_start(carrier).await?;
+    let payload = payload.to_vec();
+    let opts = opts.clone();
+    let stego = blocking(move || encode_bytes(&carrier, &payload, &opts)).await?;
+    output.write_all(&stego).await?;
+    output.flush().await?;
+    Ok(stego.len() as u64)
+}
+
+/// Extracts and decrypts a payload from a stego file read from an async stream.
+///
+/// This is the async counterpart of `codec::decode_bytes`, see `encode_async`.
+///
+/// # Arguments
+///
+/// * `stego` - The stego file, read from its start.
+/// * `opts` - The options the payload was embedded with.
+///
+/// # Returns
+///
+/// A `Result` containing the payload and the restored carrier.
+pub async fn decode_async<R>(stego: &mut R, opts: &CodecOptions) -> io::Result<Decoded>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    let stego = read_from_start(stego).await?;
+    let opts = opts.clone();
+    blocking(move || decode_bytes(&stego, &opts)).await
+}
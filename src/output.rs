@@ -0,0 +1,329 @@
+use crate::utils::print_secret;
+use std::io::{self, ErrorKind, IsTerminal, Write};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// How a subcommand writes its result to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Colored messages for people.
+    Human,
+    /// A single JSON document.
+    Json,
+    /// The payload bytes, untouched.
+    Raw,
+}
+
+impl OutputFormat {
+    /// Returns the `--format` name of the format.
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Human => "human",
+            OutputFormat::Json => "json",
+            OutputFormat::Raw => "raw",
+        }
+    }
+
+    /// Resolves a `--format` value against the current stdout.
+    ///
+    /// See `resolve_for`.
+    pub fn resolve(requested: &str, piped: OutputFormat) -> io::Result<OutputFormat> {
+        OutputFormat::resolve_for(requested, piped, io::stdout().is_terminal())
+    }
+
+    /// Resolves a `--format` value.
+    ///
+    /// `auto` gives `Human` when stdout is a terminal and `piped` otherwise, so that scripts get
+    /// machine-readable output without asking for it. Besides `auto`, only `human` and the name
+    /// of `piped` are accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `requested` - The value of `--format`, case insensitive.
+    /// * `piped` - The machine-readable format of the subcommand, `Json` or `Raw`.
+    /// * `terminal` - Whether stdout is a terminal.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the format, or an `InvalidInput` error for unknown values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::output::OutputFormat;
+    ///
+    /// let auto = |terminal| OutputFormat::resolve_for("auto", OutputFormat::Json, terminal).unwrap();
+    /// assert_eq!(auto(true), OutputFormat::Human);
+    /// assert_eq!(auto(false), OutputFormat::Json);
+    /// let human = OutputFormat::resolve_for("HUMAN", OutputFormat::Json, false).unwrap();
+    /// assert_eq!(human, OutputFormat::Human);
+    /// assert!(OutputFormat::resolve_for("raw", OutputFormat::Json, false).is_err());
+    /// ```
+    pub fn resolve_for(
+        requested: &str,
+        piped: OutputFormat,
+        terminal: bool,
+    ) -> io::Result<OutputFormat> {
+        match requested.to_lowercase().as_str() {
+            "auto" if terminal => Ok(OutputFormat::Human),
+            "auto" => Ok(piped),
+            "human" => Ok(OutputFormat::Human),
+            name if name == piped.name() => Ok(piped),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unsupported format {:?}, expected auto, human or {}",
+                    requested,
+                    piped.name()
+                ),
+            )),
+        }
+    }
+}
+
+/// Checks whether bytes would garble a terminal: invalid UTF-8 or control characters other
+/// than tabs and line breaks.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::output::is_binary;
+///
+/// assert!(!is_binary(b"hello\tworld\n"));
+/// assert!(is_binary(b"\x1b[2J"));
+/// assert!(is_binary(&[0xFF, 0xFE]));
+/// ```
+pub fn is_binary(data: &[u8]) -> bool {
+    match std::str::from_utf8(data) {
+        Ok(text) => text
+            .chars()
+            .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r')),
+        Err(_) => true,
+    }
+}
+
+/// Writes a decrypted payload to stdout in the given format.
+///
+/// Raw binary payloads are refused when stdout is a terminal, where they would garble the
+/// display or inject escape sequences, unless `force_binary` is set.
+///
+/// # Arguments
+///
+/// * `data` - The decrypted payload, without padding.
+/// * `format` - `Human` to print it quoted and escaped, `Raw` to write the bytes as they are.
+/// * `force_binary` - Writes raw binary payloads to a terminal anyway.
+///
+/// # Returns
+///
+/// A `Result` indicating success, or an `InvalidInput` error if the payload was refused.
+pub fn write_payload(data: &[u8], format: OutputFormat, force_binary: bool) -> io::Result<()> {
+    if format != OutputFormat::Raw {
+        print_secret(data);
+        return Ok(());
+    }
+    let mut stdout = io::stdout().lock();
+    if stdout.is_terminal() && !force_binary && is_binary(data) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Refusing to write {} bytes of binary payload to a terminal, redirect stdout or pass --force-binary",
+                data.len()
+            ),
+        ));
+    }
+    stdout.write_all(data)?;
+    stdout.flush()
+}
+
+/// The sink installed with `set_output_sink`, or `None` for stdout.
+static SINK: Mutex<Option<Box<dyn OutputSink>>> = Mutex::new(None);
+
+/// A piece of user-facing output: messages, metadata listings, hexdumps and progress.
+///
+/// Each piece carries the text the console prints, so that a sink only interested in text
+/// can ignore the rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output<'a> {
+    /// Text printed as is, line breaks included.
+    Text(&'a str),
+    /// A hexdump of `data`, whose first byte is at `offset` in its file.
+    Hexdump {
+        /// The address of the first byte.
+        offset: u64,
+        /// The bytes dumped.
+        data: &'a [u8],
+        /// The dump as printed.
+        text: &'a str,
+    },
+    /// Progress of a long operation, `done` of `total` steps.
+    Progress {
+        /// The steps completed.
+        done: u64,
+        /// All the steps.
+        total: u64,
+        /// The progress line as printed.
+        text: &'a str,
+    },
+}
+
+impl Output<'_> {
+    /// Returns the text the console prints for this output.
+    pub fn text(&self) -> &str {
+        match self {
+            Output::Text(text) | Output::Hexdump { text, .. } | Output::Progress { text, .. } => {
+                text
+            }
+        }
+    }
+}
+
+/// Where the output of the subcommands goes, instead of stdout.
+///
+/// Everything the subcommands print goes through `emit`, usually with the `out!` and `outln!`
+/// macros, which hands it to the sink installed with `set_output_sink`. Tests install a
+/// `MemorySink` to assert on what a command printed, and other front-ends, such as a TUI or
+/// GUI, their own sink to show it their way. Payloads written out raw and errors printed to
+/// stderr don't go through the sink.
+pub trait OutputSink: Send {
+    /// Takes a piece of output.
+    ///
+    /// # Returns
+    ///
+    /// An `io::Result`, an error such as `BrokenPipe` stopping listings early.
+    fn emit(&mut self, output: Output<'_>) -> io::Result<()>;
+}
+
+/// The sink of the console: every piece of output is printed to stdout.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn emit(&mut self, output: Output<'_>) -> io::Result<()> {
+        io::stdout().lock().write_all(output.text().as_bytes())
+    }
+}
+
+/// A sink that keeps the printed text in memory. Clones share the text, so a test keeps one
+/// and installs the other.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::output::{reset_output_sink, set_output_sink, MemorySink};
+/// use stegano::png::print_chunk;
+///
+/// let sink = MemorySink::default();
+/// set_output_sink(sink.clone());
+/// print_chunk(b"stEg", b"hi", 33);
+/// reset_output_sink();
+/// assert!(sink.contents().contains("stEg"));
+/// assert!(sink.contents().contains("00000033 | 68 69 | hi"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    text: Arc<Mutex<String>>,
+}
+
+impl MemorySink {
+    /// Returns the text printed so far.
+    pub fn contents(&self) -> String {
+        self.text
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn emit(&mut self, output: Output<'_>) -> io::Result<()> {
+        self.text
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_str(output.text());
+        Ok(())
+    }
+}
+
+/// Sends the output of the whole process to `sink` instead of stdout.
+pub fn set_output_sink<S: OutputSink + 'static>(sink: S) {
+    *SINK.lock().unwrap_or_else(PoisonError::into_inner) = Some(Box::new(sink));
+}
+
+/// Sends the output back to stdout.
+pub fn reset_output_sink() {
+    *SINK.lock().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Hands a piece of output to the installed sink, or prints it to stdout.
+pub fn emit(output: Output<'_>) -> io::Result<()> {
+    match &mut *SINK.lock().unwrap_or_else(PoisonError::into_inner) {
+        Some(sink) => sink.emit(output),
+        None => StdoutSink.emit(output),
+    }
+}
+
+/// A writer whose text goes to the output sink, for listings written with `write!`.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use stegano::output::{reset_output_sink, set_output_sink, MemorySink, OutputWriter};
+///
+/// let sink = MemorySink::default();
+/// set_output_sink(sink.clone());
+/// let mut writer = OutputWriter::default();
+/// writer.write_all(&"caf\u{e9}".as_bytes()[..4]).unwrap();
+/// writer.write_all(&"caf\u{e9}".as_bytes()[4..]).unwrap();
+/// reset_output_sink();
+/// assert_eq!(sink.contents(), "caf\u{e9}");
+/// ```
+#[derive(Debug, Default)]
+pub struct OutputWriter {
+    /// The start of a character split between two writes.
+    pending: Vec<u8>,
+}
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            // Bytes that will never be UTF-8 are shown as replacement characters.
+            Err(_) => self.pending.len(),
+        };
+        let text: Vec<u8> = self.pending.drain(..complete).collect();
+        emit(Output::Text(&String::from_utf8_lossy(&text)))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let text = std::mem::take(&mut self.pending);
+            emit(Output::Text(&String::from_utf8_lossy(&text)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints formatted text through the output sink, like `print!`.
+#[macro_export]
+macro_rules! out {
+    ($($arg:tt)*) => {{
+        // A closed stdout, such as a pager quit early, isn't worth a panic.
+        let _ = $crate::output::emit($crate::output::Output::Text(&format!($($arg)*)));
+    }};
+}
+
+/// Prints formatted text and a line break through the output sink, like `println!`.
+#[macro_export]
+macro_rules! outln {
+    () => {
+        $crate::out!("\n")
+    };
+    ($($arg:tt)*) => {{
+        let mut text = format!($($arg)*);
+        text.push('\n');
+        let _ = $crate::output::emit($crate::output::Output::Text(&text));
+    }};
+}
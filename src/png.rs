@@ -0,0 +1,1616 @@
+use crate::chunk_type::ChunkType;
+use crate::models::PngWriter;
+use crate::outln;
+use crate::polyglot::PNG_SIGNATURE;
+use crate::style::Style;
+use crate::utils::print_hex;
+use crc32_v2::crc32;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::fmt;
+use std::io::{self, ErrorKind, Read, Write};
+
+/// Starting column, starting row, column step and row step of the seven Adam7 passes.
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Builds an `InvalidData` error for malformed PNG streams.
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg.to_string())
+}
+
+/// A PNG chunk as laid out in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawChunk {
+    /// Offset of the chunk, starting at its length field.
+    pub offset: usize,
+    /// The chunk type.
+    pub r#type: ChunkType,
+    /// The chunk data.
+    pub data: Vec<u8>,
+}
+
+/// Splits a PNG file into its chunks, stopping after `IEND`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{encode_grayscale, read_chunks};
+///
+/// let png = encode_grayscale(2, 2, &[0, 255, 255, 0]).unwrap();
+/// let chunks = read_chunks(&png).unwrap();
+/// assert_eq!(&chunks[0].r#type, b"IHDR");
+/// assert_eq!(&chunks.last().unwrap().r#type, b"IEND");
+/// ```
+pub fn read_chunks(data: &[u8]) -> io::Result<Vec<RawChunk>> {
+    chunks(data).collect()
+}
+
+/// Reads the chunks of a PNG stream one at a time, as `chunks` returns them.
+#[derive(Debug)]
+pub struct PngChunks<R> {
+    reader: R,
+    offset: usize,
+    done: bool,
+}
+
+/// Lazily reads the chunks of a PNG stream, checking its signature first.
+///
+/// Each chunk is read only when asked for, so searching a large file for one chunk stops
+/// reading as soon as it is found. Iteration ends after `IEND`, or where fewer than the 12
+/// bytes of an empty chunk remain. A missing signature, or a chunk declaring more data than
+/// the stream holds, is yielded as an `InvalidData` error, after which the iterator ends.
+///
+/// # Arguments
+///
+/// * `reader` - The PNG stream, from its first byte.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{chunks, encode_grayscale, insert_chunk};
+///
+/// let png = encode_grayscale(2, 2, &[0, 255, 255, 0]).unwrap();
+/// let (png, _) = insert_chunk(&png, b"tEXt", b"k\0v", None).unwrap();
+/// let text = chunks(&png[..])
+///     .find(|chunk| chunk.as_ref().is_ok_and(|chunk| &chunk.r#type == b"tEXt"))
+///     .unwrap()
+///     .unwrap();
+/// assert_eq!(text.data, b"k\0v");
+/// assert_eq!(&png[text.offset + 4..text.offset + 8], b"tEXt");
+///
+/// assert!(chunks(&b"GIF89a.."[..]).next().unwrap().is_err());
+/// assert!(chunks(&png[..png.len() - 14]).any(|chunk| chunk.is_err()));
+/// ```
+pub fn chunks<R: Read>(reader: R) -> PngChunks<R> {
+    PngChunks {
+        reader,
+        offset: 0,
+        done: false,
+    }
+}
+
+impl<R: Read> PngChunks<R> {
+    /// Reads up to `len` bytes, fewer only at the end of the stream.
+    fn read_up_to(&mut self, len: u64) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_chunk(&mut self) -> io::Result<Option<RawChunk>> {
+        if self.offset == 0 {
+            if self.read_up_to(8)? != PNG_SIGNATURE {
+                return Err(invalid("Not a valid PNG file!"));
+            }
+            self.offset = 8;
+        }
+        let head = self.read_up_to(8)?;
+        let Ok(head) = <[u8; 8]>::try_from(head) else {
+            return Ok(None);
+        };
+        let len = u32::from_be_bytes(head[..4].try_into().unwrap()) as u64;
+        // The data and CRC are read without trusting the length for an allocation.
+        let mut data = self.read_up_to(len + 4)?;
+        if (data.len() as u64) < len + 4 {
+            // Fewer than the 12 bytes of an empty chunk are trailing bytes, not a chunk.
+            if data.len() < 4 {
+                return Ok(None);
+            }
+            return Err(invalid("Truncated PNG chunk"));
+        }
+        data.truncate(len as usize);
+        let chunk = RawChunk {
+            offset: self.offset,
+            r#type: ChunkType::from(<[u8; 4]>::try_from(&head[4..]).unwrap()),
+            data,
+        };
+        self.offset += 12 + len as usize;
+        Ok(Some(chunk))
+    }
+}
+
+impl<R: Read> Iterator for PngChunks<R> {
+    type Item = io::Result<RawChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let chunk = self.read_chunk().transpose();
+        self.done = !matches!(&chunk, Some(Ok(chunk)) if &chunk.r#type != b"IEND");
+        chunk
+    }
+}
+
+/// Selects PNG chunks by type and by content, for `show-meta --filter` and `--grep`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkFilter {
+    /// The chunk types to keep, all of them when empty.
+    pub types: Vec<[u8; 4]>,
+    /// Bytes the data of kept chunks must contain.
+    pub pattern: Option<Vec<u8>>,
+}
+
+impl ChunkFilter {
+    /// Builds a filter from the values of `--filter` and `--grep`.
+    ///
+    /// # Arguments
+    ///
+    /// * `types` - Four-letter chunk types, matched case-sensitively as in the file.
+    /// * `pattern` - Hex bytes after `0x` (such as `0x89504e47`), or ASCII text otherwise.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the filter, or an `InvalidInput` error for a malformed type or
+    /// pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::png::ChunkFilter;
+    ///
+    /// let filter = ChunkFilter::new(&["tEXt".into(), "iTXt".into()], Some("0x00ff")).unwrap();
+    /// assert_eq!(filter.select(b"tEXt", &[1, 0, 255, 0, 255]), Some(vec![1, 3]));
+    /// assert_eq!(filter.select(b"tEXt", b"none"), None);
+    /// assert_eq!(filter.select(b"IDAT", &[0, 255]), None);
+    ///
+    /// let filter = ChunkFilter::new(&[], Some("Software")).unwrap();
+    /// assert_eq!(filter.select(b"tEXt", b"Software\0GIMP"), Some(vec![0]));
+    /// assert!(ChunkFilter::new(&["text chunk".into()], None).is_err());
+    /// assert!(ChunkFilter::new(&[], Some("0xabc")).is_err());
+    /// ```
+    pub fn new(types: &[String], pattern: Option<&str>) -> io::Result<ChunkFilter> {
+        let bad = |msg: String| io::Error::new(ErrorKind::InvalidInput, msg);
+        let types = types
+            .iter()
+            .map(|name| {
+                let name = name.trim();
+                match <[u8; 4]>::try_from(name.as_bytes()) {
+                    Ok(bytes) if bytes.iter().all(u8::is_ascii_alphabetic) => Ok(bytes),
+                    _ => Err(bad(format!(
+                        "Invalid chunk type {:?}: chunk types are exactly four ASCII letters",
+                        name
+                    ))),
+                }
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let pattern = match pattern {
+            None => None,
+            Some(pattern) => {
+                let bytes = match pattern.strip_prefix("0x") {
+                    Some(hex) => {
+                        let hex: Vec<u8> =
+                            hex.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+                        if !hex.len().is_multiple_of(2) || !hex.iter().all(u8::is_ascii_hexdigit) {
+                            return Err(bad(format!(
+                                "Invalid hex pattern {:?}: expected pairs of hex digits after 0x",
+                                pattern
+                            )));
+                        }
+                        hex.chunks(2)
+                            .map(|pair| {
+                                u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap()
+                            })
+                            .collect()
+                    }
+                    None => pattern.as_bytes().to_vec(),
+                };
+                if bytes.is_empty() {
+                    return Err(bad("The search pattern is empty".into()));
+                }
+                Some(bytes)
+            }
+        };
+        Ok(ChunkFilter { types, pattern })
+    }
+
+    /// Tells whether the filter keeps every chunk.
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty() && self.pattern.is_none()
+    }
+
+    /// Checks a chunk against the filter.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the chunk is filtered out, otherwise the offsets in `data` where the pattern
+    /// starts, empty without a pattern.
+    pub fn select(&self, r#type: &[u8; 4], data: &[u8]) -> Option<Vec<usize>> {
+        if !self.types.is_empty() && !self.types.contains(r#type) {
+            return None;
+        }
+        let Some(pattern) = &self.pattern else {
+            return Some(Vec::new());
+        };
+        let matches: Vec<usize> = data
+            .windows(pattern.len())
+            .enumerate()
+            .filter(|(_, window)| window == pattern)
+            .map(|(pos, _)| pos)
+            .collect();
+        (!matches.is_empty()).then_some(matches)
+    }
+}
+
+/// Most data bytes one chunk holds, as its length is limited to 2^31 - 1.
+pub const MAX_CHUNK_LEN: usize = (1 << 31) - 1;
+
+/// Serializes a chunk with its length and CRC.
+///
+/// # Returns
+///
+/// A `Result` that is an `InvalidInput` error if `data` is longer than `MAX_CHUNK_LEN`.
+pub fn write_chunk<W: Write>(writer: &mut W, r#type: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    if data.len() > MAX_CHUNK_LEN {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The chunk needs {} bytes but a PNG chunk offers {}",
+                data.len(),
+                MAX_CHUNK_LEN
+            ),
+        ));
+    }
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(r#type)?;
+    writer.write_all(data)?;
+    let crc = crc32(crc32(0, r#type), data);
+    writer.write_all(&crc.to_be_bytes())
+}
+
+/// Chunk types registered in the PNG specification and its extensions (APNG, PNGEXT).
+pub const REGISTERED_CHUNKS: [&[u8; 4]; 34] = [
+    b"IHDR", b"PLTE", b"IDAT", b"IEND", b"acTL", b"bKGD", b"cHRM", b"cICP", b"cLLI", b"dSIG",
+    b"eXIf", b"fcTL", b"fdAT", b"fRAc", b"gAMA", b"gIFg", b"gIFt", b"gIFx", b"hIST", b"iCCP",
+    b"iTXt", b"mDCV", b"oFFs", b"pCAL", b"pHYs", b"sBIT", b"sCAL", b"sPLT", b"sRGB", b"sTER",
+    b"tEXt", b"tIME", b"tRNS", b"zTXt",
+];
+
+/// Checks whether a chunk type is registered, ignoring case so that names differing only in
+/// their property bits are rejected too.
+fn is_registered(name: &[u8; 4]) -> bool {
+    REGISTERED_CHUNKS
+        .iter()
+        .any(|registered| registered.eq_ignore_ascii_case(name))
+}
+
+/// Checks that a chunk name can hold a payload without upsetting other PNG tools.
+///
+/// The name must be four ASCII letters with the ancillary and private bits set (first two
+/// letters lowercase) and the reserved bit clear (third letter uppercase). It must not match a
+/// registered chunk type, nor a chunk already present in the carrier. On failure the error
+/// message suggests a safe name, as computed by `suggest_chunk_name`.
+///
+/// # Arguments
+///
+/// * `name` - The requested chunk name.
+/// * `existing` - The chunks of the carrier.
+///
+/// # Returns
+///
+/// A `Result` containing the chunk type, or an `InvalidInput` error explaining the violation.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::validate_chunk_name;
+///
+/// assert_eq!(&validate_chunk_name("stEg", &[]).unwrap(), b"stEg");
+/// let error = validate_chunk_name("STEG", &[]).unwrap_err();
+/// assert!(error.to_string().contains("try \"stEG\""));
+/// assert!(validate_chunk_name("text", &[]).is_err());
+/// assert!(validate_chunk_name("st g", &[]).is_err());
+/// ```
+pub fn validate_chunk_name(name: &str, existing: &[RawChunk]) -> io::Result<[u8; 4]> {
+    let bytes = name.as_bytes();
+    let problem = if bytes.len() != 4 || !bytes.iter().all(u8::is_ascii_alphabetic) {
+        Some("chunk names are exactly four ASCII letters")
+    } else if bytes[0].is_ascii_uppercase() {
+        Some("the first letter must be lowercase (ancillary), or decoders reject the file")
+    } else if bytes[1].is_ascii_uppercase() {
+        Some("the second letter must be lowercase (private), public names are reserved")
+    } else if bytes[2].is_ascii_lowercase() {
+        Some("the third letter must be uppercase (reserved bit)")
+    } else if is_registered(bytes.try_into().unwrap()) {
+        Some("it collides with a registered chunk type")
+    } else if existing.iter().any(|chunk| chunk.r#type.bytes() == bytes) {
+        Some("the carrier already holds a chunk with this name")
+    } else {
+        None
+    };
+    match problem {
+        None => Ok(bytes.try_into().unwrap()),
+        Some(problem) => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid chunk name {:?}: {}; try {:?}",
+                name,
+                problem,
+                String::from_utf8_lossy(&suggest_chunk_name(name, existing))
+            ),
+        )),
+    }
+}
+
+/// Derives a valid chunk name from a requested one by fixing its property bits.
+///
+/// Non-letters are replaced by the letters of `stEg`, and the last letter is cycled until the
+/// name collides with neither a registered chunk nor a chunk of the carrier.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::suggest_chunk_name;
+///
+/// assert_eq!(&suggest_chunk_name("HIDE", &[]), b"hiDE");
+/// assert_eq!(&suggest_chunk_name("x", &[]), b"xtEg");
+/// assert_eq!(&suggest_chunk_name("TEXT", &[]), b"teXa");
+/// ```
+pub fn suggest_chunk_name(name: &str, existing: &[RawChunk]) -> [u8; 4] {
+    let mut candidate = *b"stEg";
+    for (slot, byte) in candidate.iter_mut().zip(name.bytes()) {
+        if byte.is_ascii_alphabetic() {
+            *slot = byte;
+        }
+    }
+    candidate[0] = candidate[0].to_ascii_lowercase();
+    candidate[1] = candidate[1].to_ascii_lowercase();
+    candidate[2] = candidate[2].to_ascii_uppercase();
+    let taken = |candidate: &[u8; 4]| {
+        is_registered(candidate) || existing.iter().any(|chunk| &chunk.r#type == candidate)
+    };
+    let mut letter = b'a';
+    while taken(&candidate) && letter <= b'z' {
+        candidate[3] = letter;
+        letter += 1;
+    }
+    candidate
+}
+
+/// Inserts a chunk into a PNG file.
+///
+/// Data longer than `MAX_CHUNK_LEN` is split over consecutive chunks of the same type, the
+/// way image data is over `IDAT` chunks, which `remove_chunk` joins again.
+///
+/// # Arguments
+///
+/// * `data` - The whole PNG file.
+/// * `name` - The chunk type, usually checked with `validate_chunk_name` beforehand.
+/// * `payload` - The chunk data.
+/// * `offset` - The chunk boundary to insert at, or `None` to insert right before `IEND`.
+///
+/// # Returns
+///
+/// A `Result` containing the new file and the offset the chunk was written at, or an
+/// `InvalidInput` error if `offset` isn't a chunk boundary between `IHDR` and `IEND`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{encode_grayscale, insert_chunk, remove_chunk};
+///
+/// let png = encode_grayscale(1, 1, &[0]).unwrap();
+/// let (stego, offset) = insert_chunk(&png, b"stEg", b"secret", None).unwrap();
+/// assert_eq!(offset, png.len() - 12);
+/// let (clean, chunk) = remove_chunk(&stego, b"stEg").unwrap();
+/// assert_eq!(clean, png);
+/// assert_eq!((chunk.offset, chunk.data), (offset, b"secret".to_vec()));
+/// ```
+pub fn insert_chunk(
+    data: &[u8],
+    name: &[u8; 4],
+    payload: &[u8],
+    offset: Option<usize>,
+) -> io::Result<(Vec<u8>, usize)> {
+    let chunks = read_chunks(data)?;
+    let iend = chunks
+        .iter()
+        .find(|chunk| &chunk.r#type == b"IEND")
+        .ok_or_else(|| invalid("Missing IEND chunk"))?
+        .offset;
+    let offset = match offset {
+        None => iend,
+        Some(offset) if chunks[1..].iter().any(|chunk| chunk.offset == offset) => offset,
+        Some(offset) => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Offset {} is not a chunk boundary between IHDR and IEND (IEND is at {})",
+                    offset, iend
+                ),
+            ))
+        }
+    };
+    let pieces = payload.len().div_ceil(MAX_CHUNK_LEN).max(1);
+    let mut out = Vec::with_capacity(data.len() + payload.len() + 12 * pieces);
+    out.extend_from_slice(&data[..offset]);
+    for index in 0..pieces {
+        let end = ((index + 1) * MAX_CHUNK_LEN).min(payload.len());
+        write_chunk(&mut out, name, &payload[index * MAX_CHUNK_LEN..end])?;
+    }
+    out.extend_from_slice(&data[offset..]);
+    Ok((out, offset))
+}
+
+/// Removes the first chunk of the given type from a PNG file, checking its CRC. Chunks of the
+/// same type right after it, as `insert_chunk` splits long data into, are removed with it.
+///
+/// # Returns
+///
+/// A `Result` containing the file without the chunk and the removed chunk, with the data of
+/// the whole run.
+pub fn remove_chunk(data: &[u8], name: &[u8; 4]) -> io::Result<(Vec<u8>, RawChunk)> {
+    let chunks = read_chunks(data)?;
+    let first = chunks
+        .iter()
+        .position(|chunk| &chunk.r#type == name)
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("No {:?} chunk found", String::from_utf8_lossy(name)),
+            )
+        })?;
+    cut_run(data, chunks, first)
+}
+
+/// Removes the chunk of the given type starting at an offset of a PNG file, checking its CRC.
+///
+/// # Returns
+///
+/// A `Result` containing the file without the chunk and the removed chunk, or a `NotFound`
+/// error if no chunk of that type starts there.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{encode_grayscale, insert_chunk, remove_chunk_at};
+///
+/// let png = encode_grayscale(1, 1, &[0]).unwrap();
+/// let (stego, offset) = insert_chunk(&png, b"stEg", b"secret", Some(33)).unwrap();
+/// assert_eq!(remove_chunk_at(&stego, b"stEg", offset).unwrap().0, png);
+/// assert!(remove_chunk_at(&stego, b"stEg", offset + 1).is_err());
+/// ```
+pub fn remove_chunk_at(
+    data: &[u8],
+    name: &[u8; 4],
+    offset: usize,
+) -> io::Result<(Vec<u8>, RawChunk)> {
+    let chunks = read_chunks(data)?;
+    let first = chunks
+        .iter()
+        .position(|chunk| chunk.offset == offset && &chunk.r#type == name)
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "No {:?} chunk starts at offset {}",
+                    String::from_utf8_lossy(name),
+                    offset
+                ),
+            )
+        })?;
+    cut_run(data, chunks, first)
+}
+
+/// Checks the CRC of a chunk found by `read_chunks`.
+///
+/// # Returns
+///
+/// A `Result` containing the offset right after the chunk.
+fn checked_end(data: &[u8], chunk: &RawChunk) -> io::Result<usize> {
+    let end = chunk.offset + 12 + chunk.data.len();
+    let crc = u32::from_be_bytes(data[end - 4..end].try_into().unwrap());
+    if crc32(crc32(0, &chunk.r#type.bytes()), &chunk.data) != crc {
+        return Err(invalid("The payload chunk failed its CRC check"));
+    }
+    Ok(end)
+}
+
+/// Cuts the chunk at index `first` of `chunks`, and those of the same type right after it,
+/// out of the file, checking their CRCs.
+fn cut_run(data: &[u8], chunks: Vec<RawChunk>, first: usize) -> io::Result<(Vec<u8>, RawChunk)> {
+    let mut run = chunks.into_iter().skip(first);
+    let mut joined = run.next().expect("The index comes from the chunks");
+    let mut end = checked_end(data, &joined)?;
+    for chunk in run {
+        if chunk.r#type != joined.r#type || chunk.offset != end {
+            break;
+        }
+        end = checked_end(data, &chunk)?;
+        joined.data.extend_from_slice(&chunk.data);
+    }
+    let mut out = data[..joined.offset].to_vec();
+    out.extend_from_slice(&data[end..]);
+    Ok((out, joined))
+}
+
+/// Parses a chunk type given on the command line: exactly four ASCII letters.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::parse_chunk_type;
+///
+/// assert_eq!(&parse_chunk_type("teXt").unwrap(), b"teXt");
+/// assert!(parse_chunk_type("te t").is_err());
+/// ```
+pub fn parse_chunk_type(value: &str) -> Result<[u8; 4], String> {
+    value
+        .as_bytes()
+        .try_into()
+        .ok()
+        .filter(|bytes: &[u8; 4]| bytes.iter().all(u8::is_ascii_alphabetic))
+        .ok_or_else(|| {
+            format!(
+                "invalid chunk type '{}', expected four ASCII letters",
+                value
+            )
+        })
+}
+
+/// Where `inject-chunk` puts a new chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkPosition {
+    /// Right after the last chunk of a type, such as after all the `IDAT` chunks.
+    After([u8; 4]),
+    /// Right before the first chunk of a type.
+    Before([u8; 4]),
+    /// At an index of the chunk list, where `IHDR` is 0: the new chunk becomes the N-th.
+    Index(usize),
+}
+
+impl ChunkPosition {
+    /// Finds the offset of the position in a PNG file.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunks` - The chunks of the file, as `read_chunks` returns them.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the chunk boundary to insert at, or an `InvalidInput` error if the
+    /// position doesn't exist or lies outside `IHDR` and `IEND`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::png::{encode_grayscale, read_chunks, ChunkPosition};
+    ///
+    /// let chunks = read_chunks(&encode_grayscale(1, 1, &[0]).unwrap()).unwrap();
+    /// assert_eq!(ChunkPosition::After(*b"IHDR").offset(&chunks).unwrap(), 33);
+    /// assert_eq!(ChunkPosition::Index(1).offset(&chunks).unwrap(), 33);
+    /// assert!(ChunkPosition::Before(*b"IHDR").offset(&chunks).is_err());
+    /// assert!(ChunkPosition::After(*b"tEXt").offset(&chunks).is_err());
+    /// ```
+    pub fn offset(&self, chunks: &[RawChunk]) -> io::Result<usize> {
+        let error = |msg: String| io::Error::new(ErrorKind::InvalidInput, msg);
+        let missing = |r#type: &[u8; 4]| {
+            error(format!(
+                "The file has no {:?} chunk",
+                String::from_utf8_lossy(r#type)
+            ))
+        };
+        let index = match self {
+            ChunkPosition::After(r#type) => {
+                chunks
+                    .iter()
+                    .rposition(|chunk| &chunk.r#type == r#type)
+                    .ok_or_else(|| missing(r#type))?
+                    + 1
+            }
+            ChunkPosition::Before(r#type) => chunks
+                .iter()
+                .position(|chunk| &chunk.r#type == r#type)
+                .ok_or_else(|| missing(r#type))?,
+            ChunkPosition::Index(index) => *index,
+        };
+        // Nothing goes before IHDR or after IEND.
+        let last = chunks.len().saturating_sub(1);
+        if index == 0 || index > last || &chunks[last].r#type != b"IEND" {
+            return Err(error(format!(
+                "Chunks can only be inserted between IHDR and IEND, at indices 1 to {}",
+                last
+            )));
+        }
+        Ok(chunks[index].offset)
+    }
+}
+
+/// Parses the value of `inject-chunk --position`: `after:TYPE`, `before:TYPE` or `index:N`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{parse_chunk_position, ChunkPosition};
+///
+/// assert_eq!(parse_chunk_position("after:IHDR").unwrap(), ChunkPosition::After(*b"IHDR"));
+/// assert_eq!(parse_chunk_position("index:3").unwrap(), ChunkPosition::Index(3));
+/// assert!(parse_chunk_position("middle").is_err());
+/// ```
+pub fn parse_chunk_position(value: &str) -> Result<ChunkPosition, String> {
+    match value.split_once(':') {
+        Some(("after", r#type)) => Ok(ChunkPosition::After(parse_chunk_type(r#type)?)),
+        Some(("before", r#type)) => Ok(ChunkPosition::Before(parse_chunk_type(r#type)?)),
+        Some(("index", index)) => index
+            .parse()
+            .map(ChunkPosition::Index)
+            .map_err(|_| format!("invalid chunk index '{}'", index)),
+        _ => Err(format!(
+            "invalid position '{}', expected after:TYPE, before:TYPE or index:N",
+            value
+        )),
+    }
+}
+
+/// Where `encrypt` puts the payload chunk and `decrypt` looks for it, from `--offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OffsetSpec {
+    /// Right before `IEND` for `encrypt`; `decrypt` looks the chunk up by name.
+    #[default]
+    Auto,
+    /// At an absolute byte offset, which must be a chunk boundary.
+    Absolute(usize),
+    /// At N bytes before the end of the file: `end-12` is right before a plain `IEND`.
+    FromEnd(usize),
+    /// Right after the last chunk of a type, such as `after:IHDR`.
+    After([u8; 4]),
+    /// Right before the first chunk of a type.
+    Before([u8; 4]),
+}
+
+impl OffsetSpec {
+    /// Resolves the spec to a byte offset in a PNG file.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The whole PNG file.
+    /// * `chunks` - Its chunks, as `read_chunks` returns them.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the offset, `None` for `Auto`, or an `InvalidInput` error if it
+    /// lies outside the file or names a chunk the file doesn't have. Whether the offset is a
+    /// chunk boundary is left to the caller.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::png::{encode_grayscale, read_chunks, OffsetSpec};
+    ///
+    /// let png = encode_grayscale(1, 1, &[0]).unwrap();
+    /// let chunks = read_chunks(&png).unwrap();
+    /// assert_eq!(OffsetSpec::Auto.resolve(&png, &chunks).unwrap(), None);
+    /// assert_eq!(OffsetSpec::After(*b"IHDR").resolve(&png, &chunks).unwrap(), Some(33));
+    /// assert_eq!(OffsetSpec::FromEnd(12).resolve(&png, &chunks).unwrap(), Some(png.len() - 12));
+    /// assert!(OffsetSpec::FromEnd(png.len() + 1).resolve(&png, &chunks).is_err());
+    /// ```
+    pub fn resolve(&self, data: &[u8], chunks: &[RawChunk]) -> io::Result<Option<usize>> {
+        let offset = match self {
+            OffsetSpec::Auto => return Ok(None),
+            OffsetSpec::Absolute(offset) => *offset,
+            OffsetSpec::FromEnd(back) => data.len().checked_sub(*back).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "end-{} lies before the start of the file, which is {} bytes long",
+                        back,
+                        data.len()
+                    ),
+                )
+            })?,
+            OffsetSpec::After(r#type) => ChunkPosition::After(*r#type).offset(chunks)?,
+            OffsetSpec::Before(r#type) => ChunkPosition::Before(*r#type).offset(chunks)?,
+        };
+        Ok(Some(offset))
+    }
+}
+
+impl fmt::Display for OffsetSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OffsetSpec::Auto => write!(f, "auto"),
+            OffsetSpec::Absolute(offset) => write!(f, "{}", offset),
+            OffsetSpec::FromEnd(back) => write!(f, "end-{}", back),
+            OffsetSpec::After(r#type) => write!(f, "after:{}", String::from_utf8_lossy(r#type)),
+            OffsetSpec::Before(r#type) => {
+                write!(f, "before:{}", String::from_utf8_lossy(r#type))
+            }
+        }
+    }
+}
+
+/// Parses the value of `--offset`: `auto`, `end-N`, `after:TYPE`, `before:TYPE` or an
+/// absolute offset, in decimal or in hex after `0x`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{parse_offset_spec, OffsetSpec};
+///
+/// assert_eq!(parse_offset_spec("auto").unwrap(), OffsetSpec::Auto);
+/// assert_eq!(parse_offset_spec("end-12").unwrap(), OffsetSpec::FromEnd(12));
+/// assert_eq!(parse_offset_spec("after:IHDR").unwrap(), OffsetSpec::After(*b"IHDR"));
+/// assert_eq!(parse_offset_spec("0x21").unwrap(), OffsetSpec::Absolute(33));
+/// assert_eq!(parse_offset_spec("33").unwrap(), OffsetSpec::Absolute(33));
+/// assert!(parse_offset_spec("end-").is_err());
+/// assert!(parse_offset_spec("after:IHD").is_err());
+/// ```
+pub fn parse_offset_spec(value: &str) -> Result<OffsetSpec, String> {
+    let number = |digits: &str| match digits.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => digits.parse().ok(),
+    };
+    if value == "auto" {
+        return Ok(OffsetSpec::Auto);
+    }
+    if let Some(back) = value.strip_prefix("end-") {
+        return number(back)
+            .map(OffsetSpec::FromEnd)
+            .ok_or_else(|| format!("invalid byte count '{}' in '{}'", back, value));
+    }
+    match value.split_once(':') {
+        Some(("after", r#type)) => Ok(OffsetSpec::After(parse_chunk_type(r#type)?)),
+        Some(("before", r#type)) => Ok(OffsetSpec::Before(parse_chunk_type(r#type)?)),
+        _ => number(value).map(OffsetSpec::Absolute).ok_or_else(|| {
+            format!(
+                "invalid offset '{}', expected auto, end-N, after:TYPE, before:TYPE or a number",
+                value
+            )
+        }),
+    }
+}
+
+/// Removes the chunks a predicate selects from a PNG file, without checking their CRC.
+///
+/// # Arguments
+///
+/// * `data` - The whole PNG file.
+/// * `select` - Tells whether to remove a chunk, given its index (`IHDR` is 0) and itself.
+///
+/// # Returns
+///
+/// A `Result` containing the file without the chunks and the removed chunks, which may be
+/// none.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{encode_grayscale, insert_chunk, remove_chunks};
+///
+/// let png = encode_grayscale(1, 1, &[0]).unwrap();
+/// let (stego, _) = insert_chunk(&png, b"teXt", b"raw", None).unwrap();
+/// let (clean, removed) = remove_chunks(&stego, |_, chunk| &chunk.r#type == b"teXt").unwrap();
+/// assert_eq!((clean, removed.len()), (png, 1));
+/// ```
+pub fn remove_chunks<F: Fn(usize, &RawChunk) -> bool>(
+    data: &[u8],
+    select: F,
+) -> io::Result<(Vec<u8>, Vec<RawChunk>)> {
+    let mut out = data[..8.min(data.len())].to_vec();
+    let mut removed = Vec::new();
+    let chunks = read_chunks(data)?;
+    let mut end = 8;
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        end = chunk.offset + 12 + chunk.data.len();
+        if select(index, &chunk) {
+            removed.push(chunk);
+        } else {
+            out.extend_from_slice(&data[chunk.offset..end]);
+        }
+    }
+    // Whatever follows IEND, such as appended payloads, is kept.
+    out.extend_from_slice(&data[end..]);
+    Ok((out, removed))
+}
+
+/// Checks that a stego file keeps every ancillary chunk of its carrier byte for byte and in
+/// the same order, for `--preserve-metadata`.
+///
+/// Ancillary chunks (`gAMA`, `pHYs`, `iCCP`, `tEXt` and the like) are those whose type starts
+/// with a lowercase letter. New chunks may come between them, but none may be changed, moved
+/// past another or dropped.
+///
+/// # Returns
+///
+/// A `Result` that is an `InvalidData` error naming the first chunk that wasn't kept.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{check_metadata_kept, encode_grayscale, insert_chunk, remove_chunk};
+///
+/// let png = encode_grayscale(1, 1, &[0]).unwrap();
+/// let (carrier, _) = insert_chunk(&png, b"gAMA", &[0, 0, 177, 143], None).unwrap();
+/// let (stego, _) = insert_chunk(&carrier, b"stEg", b"secret", None).unwrap();
+/// assert!(check_metadata_kept(&carrier, &stego).is_ok());
+///
+/// let (stripped, _) = remove_chunk(&stego, b"gAMA").unwrap();
+/// let error = check_metadata_kept(&carrier, &stripped).unwrap_err();
+/// assert!(error.to_string().contains("gAMA"));
+/// ```
+pub fn check_metadata_kept(carrier: &[u8], stego: &[u8]) -> io::Result<()> {
+    let ancillary = |chunk: &RawChunk| !chunk.r#type.is_critical();
+    let chunks = read_chunks(carrier)?;
+    let kept = read_chunks(stego)?;
+    let mut kept = kept.iter().filter(|chunk| ancillary(chunk));
+    for chunk in chunks.iter().filter(|chunk| ancillary(chunk)) {
+        if !kept.any(|other| other.r#type == chunk.r#type && other.data == chunk.data) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "The {} chunk at offset {} of the carrier isn't kept byte for byte",
+                    chunk.r#type, chunk.offset
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Prints a chunk in the same layout as `show-meta`.
+pub fn print_chunk(name: &[u8; 4], data: &[u8], offset: usize) {
+    outln!("{}", Style::Green.paint("------- Chunk -------"));
+    outln!("Offset: {:?}", offset);
+    outln!("Type: {}", String::from_utf8_lossy(name));
+    outln!("Size: {:?}", data.len());
+    outln!("CRC: {:x}", crc32(crc32(0, name), data));
+    print_hex(data, offset as u64);
+    outln!("{}", Style::Green.paint("-------- End --------"));
+    outln!();
+}
+
+/// The image properties of a PNG file, as its `IHDR` chunk records them.
+///
+/// Capacity computations only need these fields, so reading them spares decoding the pixels.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{encode_rgb, Ihdr};
+///
+/// let ihdr = Ihdr::read(&encode_rgb(4, 3, &[0; 36]).unwrap()).unwrap();
+/// assert_eq!((ihdr.width, ihdr.height, ihdr.bit_depth, ihdr.color_type), (4, 3, 8, 2));
+/// assert_eq!(ihdr.channels(), Some(3));
+/// assert_eq!(ihdr.samples(), 36);
+/// assert_eq!(ihdr.to_string(), "4x3, 8-bit truecolour, not interlaced");
+/// assert_eq!(Ihdr::parse(&ihdr.to_bytes()).unwrap(), ihdr);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ihdr {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Bits per sample, or per palette index: 1, 2, 4, 8 or 16.
+    pub bit_depth: u8,
+    /// Colour type: 0 (greyscale), 2 (truecolour), 3 (indexed), 4 (greyscale with alpha) or 6
+    /// (truecolour with alpha).
+    pub color_type: u8,
+    /// Compression method, 0 (deflate) being the only one defined.
+    pub compression: u8,
+    /// Filter method, 0 (adaptive filtering with five filter types) being the only one defined.
+    pub filter: u8,
+    /// Interlace method: 0 (none) or 1 (Adam7).
+    pub interlace: u8,
+}
+
+impl Ihdr {
+    /// Parses the 13 bytes of an `IHDR` chunk, without checking that the values are allowed.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the fields, or an `InvalidData` error if `data` isn't 13 bytes.
+    pub fn parse(data: &[u8]) -> io::Result<Ihdr> {
+        let data: &[u8; 13] = data
+            .try_into()
+            .map_err(|_| invalid("IHDR doesn't hold 13 bytes"))?;
+        Ok(Ihdr {
+            width: u32::from_be_bytes(data[0..4].try_into().unwrap()),
+            height: u32::from_be_bytes(data[4..8].try_into().unwrap()),
+            bit_depth: data[8],
+            color_type: data[9],
+            compression: data[10],
+            filter: data[11],
+            interlace: data[12],
+        })
+    }
+
+    /// Reads the `IHDR` chunk at the start of a PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the fields, or an `InvalidData` error if the file doesn't start
+    /// with a 13-byte `IHDR` chunk.
+    pub fn read(png: &[u8]) -> io::Result<Ihdr> {
+        Ihdr::from_chunks(&read_chunks(png)?)
+    }
+
+    /// Reads the `IHDR` chunk among the chunks of a PNG file, as `read_chunks` returns them.
+    pub fn from_chunks(chunks: &[RawChunk]) -> io::Result<Ihdr> {
+        let ihdr = chunks
+            .first()
+            .filter(|chunk| &chunk.r#type == b"IHDR" && chunk.data.len() == 13)
+            .ok_or_else(|| invalid("Missing IHDR chunk"))?;
+        Ihdr::parse(&ihdr.data)
+    }
+
+    /// Returns the 13 bytes of the chunk data.
+    pub fn to_bytes(&self) -> [u8; 13] {
+        let mut data = [0u8; 13];
+        data[0..4].copy_from_slice(&self.width.to_be_bytes());
+        data[4..8].copy_from_slice(&self.height.to_be_bytes());
+        data[8..].copy_from_slice(&[
+            self.bit_depth,
+            self.color_type,
+            self.compression,
+            self.filter,
+            self.interlace,
+        ]);
+        data
+    }
+
+    /// Returns the number of samples per pixel, one for the index of indexed images, or `None`
+    /// for an unknown colour type.
+    pub fn channels(&self) -> Option<usize> {
+        match self.color_type {
+            0 | 3 => Some(1),
+            2 => Some(3),
+            4 => Some(2),
+            6 => Some(4),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of samples of the image, as `PngImage::decode` gives them: indexed
+    /// images count three per pixel, their palette being expanded to RGB (four with a `tRNS`
+    /// chunk, which the header doesn't tell), and unknown colour types none.
+    pub fn samples(&self) -> u64 {
+        let channels = match self.color_type {
+            3 => 3,
+            _ => self.channels().unwrap_or(0),
+        };
+        u64::from(self.width) * u64::from(self.height) * channels as u64
+    }
+
+    /// Returns whether the image is interlaced with Adam7.
+    pub fn is_interlaced(&self) -> bool {
+        self.interlace == 1
+    }
+
+    /// Returns the name of the colour type.
+    pub fn color_type_name(&self) -> &'static str {
+        match self.color_type {
+            0 => "greyscale",
+            2 => "truecolour",
+            3 => "indexed-colour",
+            4 => "greyscale with alpha",
+            6 => "truecolour with alpha",
+            _ => "unknown colour type",
+        }
+    }
+
+    /// Writes the fields for `show-meta`, one per line, with the meaning of each method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::png::{encode_grayscale, Ihdr};
+    ///
+    /// let ihdr = Ihdr::read(&encode_grayscale(2, 5, &[0; 10]).unwrap()).unwrap();
+    /// let mut out = Vec::new();
+    /// ihdr.write_report(&mut out).unwrap();
+    /// let out = String::from_utf8(out).unwrap();
+    /// assert!(out.contains("Width: 2\nHeight: 5\nBit depth: 8\nColour type: 0 (greyscale)\n"));
+    /// assert!(out.contains("Interlace: 0 (none)"));
+    /// ```
+    pub fn write_report<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let known = |value: u8, name: &'static str| if value == 0 { name } else { "unknown" };
+        writeln!(out, "{}", Style::Green.paint("----- IHDR -----"))?;
+        writeln!(out, "Width: {}", self.width)?;
+        writeln!(out, "Height: {}", self.height)?;
+        writeln!(out, "Bit depth: {}", self.bit_depth)?;
+        writeln!(
+            out,
+            "Colour type: {} ({})",
+            self.color_type,
+            self.color_type_name()
+        )?;
+        writeln!(
+            out,
+            "Compression: {} ({})",
+            self.compression,
+            known(self.compression, "deflate")
+        )?;
+        writeln!(
+            out,
+            "Filter: {} ({})",
+            self.filter,
+            known(self.filter, "adaptive")
+        )?;
+        let interlace = match self.interlace {
+            0 => "none",
+            1 => "Adam7",
+            _ => "unknown",
+        };
+        writeln!(out, "Interlace: {} ({})", self.interlace, interlace)?;
+        writeln!(out, "{}", Style::Green.paint("----- End ------"))?;
+        writeln!(out)
+    }
+}
+
+impl fmt::Display for Ihdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{}, {}-bit {}, {}",
+            self.width,
+            self.height,
+            self.bit_depth,
+            self.color_type_name(),
+            if self.is_interlaced() {
+                "Adam7 interlaced"
+            } else {
+                "not interlaced"
+            }
+        )
+    }
+}
+
+/// A decoded PNG image, with one sample per channel per pixel.
+///
+/// Palette images are expanded to RGB, or RGBA when they carry a `tRNS` chunk, so that
+/// channels always refer to colour components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PngImage {
+    /// Width in pixels.
+    pub width: usize,
+    /// Height in pixels.
+    pub height: usize,
+    /// Bits per sample: 1, 2, 4, 8 or 16.
+    pub bit_depth: u8,
+    /// PNG colour type after palette expansion: 0 (grey), 2 (RGB), 4 (grey + alpha) or 6 (RGBA).
+    pub color_type: u8,
+    /// Samples in row-major order, `channels()` per pixel.
+    pub samples: Vec<u16>,
+}
+
+impl PngImage {
+    /// Decodes the IDAT stream of a PNG file.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The whole PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded image, or an `io::Error` if the file is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::png::{encode_grayscale, PngImage};
+    ///
+    /// let png = encode_grayscale(3, 1, &[0, 128, 255]).unwrap();
+    /// let image = PngImage::decode(&png).unwrap();
+    /// assert_eq!((image.width, image.height), (3, 1));
+    /// assert_eq!(image.samples, vec![0, 128, 255]);
+    /// ```
+    pub fn decode(data: &[u8]) -> io::Result<PngImage> {
+        let chunks = read_chunks(data)?;
+        let ihdr = Ihdr::from_chunks(&chunks)?;
+        let (width, height) = (ihdr.width as usize, ihdr.height as usize);
+        let (bit_depth, color_type) = (ihdr.bit_depth, ihdr.color_type);
+        let interlaced = ihdr.is_interlaced();
+        let channels = ihdr
+            .channels()
+            .ok_or_else(|| invalid("Unknown PNG colour type"))?;
+        if ![1, 2, 4, 8, 16].contains(&bit_depth) {
+            return Err(invalid("Unsupported PNG bit depth"));
+        }
+
+        let passes: &[(usize, usize, usize, usize)] =
+            if interlaced { &ADAM7 } else { &[(0, 0, 1, 1)] };
+        // The declared size is only trusted once that much image data actually inflated, so
+        // crafted dimensions can't make it allocate more than the file holds.
+        let expected = raw_len(width, height, channels * bit_depth as usize, passes)
+            .ok_or_else(|| invalid("PNG dimensions are too large"))?;
+
+        let mut compressed = Vec::new();
+        for chunk in chunks.iter().filter(|chunk| &chunk.r#type == b"IDAT") {
+            compressed.extend_from_slice(&chunk.data);
+        }
+        let mut raw = Vec::new();
+        ZlibDecoder::new(&compressed[..])
+            .take(expected as u64)
+            .read_to_end(&mut raw)?;
+        if raw.len() < expected {
+            return Err(invalid("Truncated image data"));
+        }
+
+        let mut samples = vec![0u16; width * height * channels];
+        let bpp = (channels * bit_depth as usize).div_ceil(8);
+        let mut pos = 0;
+        for &(x0, y0, dx, dy) in passes {
+            if x0 >= width || y0 >= height {
+                continue;
+            }
+            let pass_width = (width - x0).div_ceil(dx);
+            let pass_height = (height - y0).div_ceil(dy);
+            let stride = (pass_width * channels * bit_depth as usize).div_ceil(8);
+            let rows = unfilter(&raw[pos.min(raw.len())..], pass_height, stride, bpp)?;
+            pos += pass_height * (stride + 1);
+            for (row_index, row) in rows.chunks(stride).enumerate() {
+                let y = y0 + row_index * dy;
+                for i in 0..pass_width {
+                    let x = x0 + i * dx;
+                    for c in 0..channels {
+                        samples[(y * width + x) * channels + c] =
+                            unpack_sample(row, i * channels + c, bit_depth);
+                    }
+                }
+            }
+        }
+
+        let image = PngImage {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            samples,
+        };
+        if color_type == 3 {
+            let palette = chunks
+                .iter()
+                .find(|chunk| &chunk.r#type == b"PLTE")
+                .ok_or_else(|| invalid("Missing PLTE chunk"))?;
+            let transparency = chunks.iter().find(|chunk| &chunk.r#type == b"tRNS");
+            return image.expand_palette(&palette.data, transparency.map(|chunk| &chunk.data[..]));
+        }
+        Ok(image)
+    }
+
+    /// Replaces palette indices with their RGB(A) entries.
+    fn expand_palette(self, palette: &[u8], transparency: Option<&[u8]>) -> io::Result<PngImage> {
+        let channels = if transparency.is_some() { 4 } else { 3 };
+        let mut samples = Vec::with_capacity(self.samples.len() * channels);
+        for &index in &self.samples {
+            let index = index as usize;
+            let rgb = palette
+                .get(index * 3..index * 3 + 3)
+                .ok_or_else(|| invalid("Palette index out of range"))?;
+            samples.extend(rgb.iter().map(|&v| v as u16));
+            if let Some(alpha) = transparency {
+                samples.push(*alpha.get(index).unwrap_or(&255) as u16);
+            }
+        }
+        Ok(PngImage {
+            bit_depth: 8,
+            color_type: if transparency.is_some() { 6 } else { 2 },
+            samples,
+            ..self
+        })
+    }
+
+    /// Returns the number of samples per pixel.
+    pub fn channels(&self) -> usize {
+        match self.color_type {
+            0 => 1,
+            2 => 3,
+            4 => 2,
+            _ => 4,
+        }
+    }
+
+    /// Maps a channel name (`r`, `g`, `b` or `a`) to a sample index within a pixel.
+    ///
+    /// Colour channels of greyscale images all map to the grey sample.
+    pub fn channel_index(&self, channel: &str) -> Option<usize> {
+        match (channel.to_lowercase().as_str(), self.color_type) {
+            ("r" | "g" | "b", 0 | 4) => Some(0),
+            ("r", _) => Some(0),
+            ("g", _) => Some(1),
+            ("b", _) => Some(2),
+            ("a", 4) => Some(1),
+            ("a", 6) => Some(3),
+            _ => None,
+        }
+    }
+
+    /// Re-encodes the samples into the image data of `carrier`, keeping its other chunks.
+    ///
+    /// The image is written unfiltered, interlaced with Adam7 if `carrier` is, so `carrier`
+    /// must have the same size, colour type and bit depth, and must not be a palette image.
+    ///
+    /// # Arguments
+    ///
+    /// * `carrier` - The PNG file the image was decoded from.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new PNG file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::png::{encode_grayscale, PngImage};
+    ///
+    /// let png = encode_grayscale(2, 1, &[10, 20]).unwrap();
+    /// let mut image = PngImage::decode(&png).unwrap();
+    /// image.samples[1] = 21;
+    /// let updated = image.replace_pixels(&png).unwrap();
+    /// assert_eq!(PngImage::decode(&updated).unwrap().samples, vec![10, 21]);
+    /// ```
+    pub fn replace_pixels(&self, carrier: &[u8]) -> io::Result<Vec<u8>> {
+        self.encode_into(carrier, None)
+    }
+
+    /// Re-encodes the samples into the image data of `carrier` like `replace_pixels`, but
+    /// always without interlacing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::png::{encode_grayscale, is_interlaced, PngImage};
+    ///
+    /// let pixels: Vec<u8> = (0..100).collect();
+    /// let png = encode_grayscale(10, 10, &pixels).unwrap();
+    /// let image = PngImage::decode(&png).unwrap();
+    /// let interlaced = image.replace_pixels_interlaced(&png).unwrap();
+    /// assert!(is_interlaced(&interlaced).unwrap());
+    /// assert_eq!(PngImage::decode(&interlaced).unwrap(), image);
+    ///
+    /// let progressive = image.replace_pixels_progressive(&interlaced).unwrap();
+    /// assert!(!is_interlaced(&progressive).unwrap());
+    /// assert_eq!(progressive, png);
+    /// ```
+    pub fn replace_pixels_progressive(&self, carrier: &[u8]) -> io::Result<Vec<u8>> {
+        self.encode_into(carrier, Some(false))
+    }
+
+    /// Re-encodes the samples into the image data of `carrier` like `replace_pixels`, but
+    /// always interlaced with Adam7.
+    pub fn replace_pixels_interlaced(&self, carrier: &[u8]) -> io::Result<Vec<u8>> {
+        self.encode_into(carrier, Some(true))
+    }
+
+    /// Writes the samples into the image data of `carrier`, interlaced or not, or like
+    /// `carrier` when `interlaced` is `None`.
+    fn encode_into(&self, carrier: &[u8], interlaced: Option<bool>) -> io::Result<Vec<u8>> {
+        let chunks = read_chunks(carrier)?;
+        let mut ihdr = Ihdr::from_chunks(&chunks)?;
+        if ihdr.bit_depth != self.bit_depth || ihdr.color_type != self.color_type {
+            return Err(invalid("The image doesn't match the carrier format"));
+        }
+        let interlaced = interlaced.unwrap_or(ihdr.is_interlaced());
+        ihdr.interlace = interlaced as u8;
+
+        let channels = self.channels();
+        let passes: &[(usize, usize, usize, usize)] =
+            if interlaced { &ADAM7 } else { &[(0, 0, 1, 1)] };
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for &(x0, y0, dx, dy) in passes {
+            // Empty passes have no scanlines at all, not even filter bytes.
+            if x0 >= self.width || y0 >= self.height {
+                continue;
+            }
+            let pass_width = (self.width - x0).div_ceil(dx);
+            let stride = (pass_width * channels * self.bit_depth as usize).div_ceil(8);
+            let mut row = vec![0u8; stride];
+            for y in (y0..self.height).step_by(dy) {
+                row.fill(0);
+                for i in 0..pass_width {
+                    let pixel = (y * self.width + x0 + i * dx) * channels;
+                    for c in 0..channels {
+                        pack_sample(
+                            &mut row,
+                            i * channels + c,
+                            self.bit_depth,
+                            self.samples[pixel + c],
+                        );
+                    }
+                }
+                encoder.write_all(&[0])?;
+                encoder.write_all(&row)?;
+            }
+        }
+        let idat = encoder.finish()?;
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        let mut idat_written = false;
+        for chunk in &chunks {
+            match chunk.r#type {
+                ChunkType::Ihdr => write_chunk(&mut png, b"IHDR", &ihdr.to_bytes())?,
+                // IDAT chunks are consecutive, so the new data takes the place of the first.
+                ChunkType::Idat if !idat_written => {
+                    write_chunk(&mut png, b"IDAT", &idat)?;
+                    idat_written = true;
+                }
+                ChunkType::Idat => {}
+                r#type => write_chunk(&mut png, &r#type.bytes(), &chunk.data)?,
+            }
+        }
+        Ok(png)
+    }
+
+    /// Extracts one bit plane of one channel as black (0) and white (255) pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel name: `r`, `g`, `b` or `a`.
+    /// * `plane` - The bit to extract, 0 being the least significant.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing one byte per pixel, or an `io::Error` if the channel or plane
+    /// doesn't exist in this image.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::png::{encode_grayscale, PngImage};
+    ///
+    /// let png = encode_grayscale(4, 1, &[0, 1, 2, 3]).unwrap();
+    /// let image = PngImage::decode(&png).unwrap();
+    /// assert_eq!(image.bit_plane("r", 0).unwrap(), vec![0, 255, 0, 255]);
+    /// assert_eq!(image.bit_plane("r", 1).unwrap(), vec![0, 0, 255, 255]);
+    /// assert!(image.bit_plane("a", 0).is_err());
+    /// ```
+    pub fn bit_plane(&self, channel: &str, plane: u8) -> io::Result<Vec<u8>> {
+        let index = self.channel_index(channel).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("The image has no '{}' channel", channel),
+            )
+        })?;
+        if plane >= self.bit_depth {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Bit plane {} is out of range for a {}-bit image",
+                    plane, self.bit_depth
+                ),
+            ));
+        }
+        Ok(self
+            .samples
+            .chunks(self.channels())
+            .map(|pixel| {
+                if pixel[index] >> plane & 1 == 1 {
+                    255
+                } else {
+                    0
+                }
+            })
+            .collect())
+    }
+}
+
+/// Returns the number of bytes of filtered image data a PNG of these dimensions holds, one
+/// filter byte per row of each pass, or `None` if it overflows.
+fn raw_len(
+    width: usize,
+    height: usize,
+    bits_per_pixel: usize,
+    passes: &[(usize, usize, usize, usize)],
+) -> Option<usize> {
+    let mut total: usize = 0;
+    for &(x0, y0, dx, dy) in passes {
+        if x0 >= width || y0 >= height {
+            continue;
+        }
+        let pass_width = (width - x0).div_ceil(dx);
+        let pass_height = (height - y0).div_ceil(dy);
+        let stride = pass_width.checked_mul(bits_per_pixel)?.div_ceil(8);
+        total = total.checked_add(pass_height.checked_mul(stride.checked_add(1)?)?)?;
+    }
+    // The decoded samples, one per channel of each pixel, must be addressable too.
+    width.checked_mul(height)?.checked_mul(bits_per_pixel)?;
+    Some(total)
+}
+
+/// Reverses the per-scanline filters of a PNG image pass.
+///
+/// # Arguments
+///
+/// * `raw` - The inflated data, starting with the filter byte of the first row.
+/// * `rows` - The number of scanlines.
+/// * `stride` - The number of bytes per scanline, without the filter byte.
+/// * `bpp` - The number of bytes per complete pixel, rounded up to one.
+///
+/// # Returns
+///
+/// A `Result` containing the unfiltered scanlines, concatenated.
+fn unfilter(raw: &[u8], rows: usize, stride: usize, bpp: usize) -> io::Result<Vec<u8>> {
+    if raw.len() < rows * (stride + 1) {
+        return Err(invalid("Truncated image data"));
+    }
+    let mut out = vec![0u8; rows * stride];
+    for y in 0..rows {
+        let filter = raw[y * (stride + 1)];
+        let line = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (previous, current) = out.split_at_mut(y * stride);
+        let prior = if y > 0 {
+            &previous[(y - 1) * stride..]
+        } else {
+            &[][..]
+        };
+        let current = &mut current[..stride];
+        for x in 0..stride {
+            let a = if x >= bpp { current[x - bpp] } else { 0 };
+            let b = prior.get(x).copied().unwrap_or(0);
+            let c = if x >= bpp {
+                prior.get(x - bpp).copied().unwrap_or(0)
+            } else {
+                0
+            };
+            current[x] = match filter {
+                0 => line[x],
+                1 => line[x].wrapping_add(a),
+                2 => line[x].wrapping_add(b),
+                3 => line[x].wrapping_add(((a as u16 + b as u16) / 2) as u8),
+                4 => line[x].wrapping_add(paeth(a, b, c)),
+                _ => return Err(invalid("Unknown PNG filter type")),
+            };
+        }
+    }
+    Ok(out)
+}
+
+/// The Paeth predictor from the PNG specification.
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Reads the `index`-th sample of an unfiltered scanline.
+fn unpack_sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    match bit_depth {
+        16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+        8 => row[index] as u16,
+        _ => {
+            let bits = bit_depth as usize;
+            let per_byte = 8 / bits;
+            let shift = 8 - bits * (index % per_byte + 1);
+            ((row[index / per_byte] >> shift) & ((1 << bits) - 1)) as u16
+        }
+    }
+}
+
+/// Writes the `index`-th sample of a scanline, the counterpart of `unpack_sample`.
+fn pack_sample(row: &mut [u8], index: usize, bit_depth: u8, sample: u16) {
+    match bit_depth {
+        16 => row[index * 2..index * 2 + 2].copy_from_slice(&sample.to_be_bytes()),
+        8 => row[index] = sample as u8,
+        _ => {
+            let bits = bit_depth as usize;
+            let per_byte = 8 / bits;
+            let shift = 8 - bits * (index % per_byte + 1);
+            row[index / per_byte] |= ((sample as u8) & ((1 << bits) - 1)) << shift;
+        }
+    }
+}
+
+/// Tells whether a PNG file is interlaced with Adam7, from its `IHDR` chunk.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{encode_grayscale, is_interlaced};
+///
+/// assert!(!is_interlaced(&encode_grayscale(1, 1, &[0]).unwrap()).unwrap());
+/// ```
+pub fn is_interlaced(data: &[u8]) -> io::Result<bool> {
+    Ok(Ihdr::read(data)?.is_interlaced())
+}
+
+/// Encodes 8-bit greyscale pixels as a PNG file.
+///
+/// # Arguments
+///
+/// * `width` - Width in pixels.
+/// * `height` - Height in pixels.
+/// * `pixels` - One byte per pixel, in row-major order.
+///
+/// # Returns
+///
+/// A `Result` containing the PNG file.
+pub fn encode_grayscale(width: usize, height: usize, pixels: &[u8]) -> io::Result<Vec<u8>> {
+    encode_pixels(width, height, 0, pixels)
+}
+
+/// Encodes 8-bit RGB pixels as a PNG file.
+///
+/// # Arguments
+///
+/// * `width` - Width in pixels.
+/// * `height` - Height in pixels.
+/// * `pixels` - Three bytes per pixel, red, green and blue, in row-major order.
+///
+/// # Returns
+///
+/// A `Result` containing the PNG file.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{encode_rgb, PngImage};
+///
+/// let png = encode_rgb(2, 1, &[255, 0, 0, 0, 0, 255]).unwrap();
+/// let image = PngImage::decode(&png).unwrap();
+/// assert_eq!((image.width, image.color_type), (2, 2));
+/// assert_eq!(image.samples, vec![255, 0, 0, 0, 0, 255]);
+/// ```
+pub fn encode_rgb(width: usize, height: usize, pixels: &[u8]) -> io::Result<Vec<u8>> {
+    encode_pixels(width, height, 2, pixels)
+}
+
+/// Encodes 8-bit pixels of a greyscale (0) or RGB (2) colour type as a PNG file, without
+/// filtering.
+fn encode_pixels(
+    width: usize,
+    height: usize,
+    color_type: u8,
+    pixels: &[u8],
+) -> io::Result<Vec<u8>> {
+    let channels = if color_type == 2 { 3 } else { 1 };
+    let ihdr = Ihdr {
+        width: width as u32,
+        height: height as u32,
+        bit_depth: 8,
+        color_type,
+        compression: 0,
+        filter: 0,
+        interlace: 0,
+    };
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in pixels.chunks((width * channels).max(1)).take(height) {
+        encoder.write_all(&[0])?;
+        encoder.write_all(row)?;
+    }
+    let idat = encoder.finish()?;
+
+    let mut png = PngWriter::new(Vec::new())?;
+    png.write_chunk(b"IHDR", &ihdr.to_bytes())?;
+    png.write_chunk(b"IDAT", &idat)?;
+    png.write_chunk(b"IEND", &[])?;
+    Ok(png.into_inner())
+}
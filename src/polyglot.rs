@@ -0,0 +1,210 @@
+use crate::io_ext::{read_vec_at, stream_len};
+use crc32_v2::crc32;
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+
+/// The PNG file signature.
+pub const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// Signature of a ZIP local file header.
+const LOCAL_HEADER_SIG: &[u8; 4] = b"PK\x03\x04";
+
+/// Signature of a ZIP central directory file header.
+const CENTRAL_HEADER_SIG: &[u8; 4] = b"PK\x01\x02";
+
+/// Signature of the ZIP end of central directory record.
+const EOCD_SIG: &[u8; 4] = b"PK\x05\x06";
+
+/// Size of the end of central directory record without its trailing comment.
+const EOCD_LEN: u64 = 22;
+
+/// The EOCD comment is at most 65535 bytes, bounding the backwards search for the record.
+const EOCD_SEARCH: u64 = EOCD_LEN + u16::MAX as u64;
+
+/// "Version needed to extract" for a stored entry without ZIP64 extensions.
+const ZIP_VERSION: u16 = 10;
+
+/// A file entry stored in the ZIP half of a PNG+ZIP polyglot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolyglotEntry {
+    /// Name of the entry in the archive.
+    pub name: String,
+    /// Content of the entry.
+    pub data: Vec<u8>,
+    /// Offset of the entry's local header, which is also the length of the PNG half.
+    pub offset: u64,
+}
+
+/// Writes a file that is both a valid PNG and a valid ZIP archive holding the payload.
+///
+/// PNG decoders stop at `IEND` while ZIP readers start from the end of central directory
+/// record, so the archive is written right after the carrier. Every offset in the archive is
+/// absolute, which keeps the file valid for strict readers that don't tolerate prepended data.
+/// The entry is stored uncompressed.
+///
+/// # Arguments
+///
+/// * `carrier` - The PNG image, copied verbatim.
+/// * `payload` - The content of the archive entry, read from its current position.
+/// * `len` - The number of bytes to read from `payload`.
+/// * `name` - The name of the archive entry.
+/// * `writer` - The destination of the polyglot.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use stegano::polyglot::{extract_polyglot, write_polyglot, PNG_SIGNATURE};
+///
+/// let mut png = PNG_SIGNATURE.to_vec();
+/// png.extend_from_slice(b"rest of the image");
+/// let mut out = Vec::new();
+/// write_polyglot(Cursor::new(&png), Cursor::new(b"secret"), 6, "secret.bin", &mut out).unwrap();
+///
+/// let entry = extract_polyglot(&mut Cursor::new(out)).unwrap();
+/// assert_eq!(entry.name, "secret.bin");
+/// assert_eq!(entry.data, b"secret");
+/// assert_eq!(entry.offset, png.len() as u64);
+/// ```
+pub fn write_polyglot<C: Read + Seek, P: Read + Seek, W: Write>(
+    mut carrier: C,
+    mut payload: P,
+    len: u64,
+    name: &str,
+    mut writer: W,
+) -> io::Result<()> {
+    let mut signature = [0u8; 8];
+    carrier.read_exact(&mut signature)?;
+    if &signature != PNG_SIGNATURE {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Polyglots can only be built from PNG carriers",
+        ));
+    }
+    carrier.seek(SeekFrom::Start(0))?;
+    let carrier_len = stream_len(&mut carrier)?;
+    let name_len = u16::try_from(name.len())
+        .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "Entry name is too long"))?;
+    let too_large = || io::Error::new(ErrorKind::InvalidInput, "Polyglot would exceed 4 GiB");
+    let local_offset = u32::try_from(carrier_len).map_err(|_| too_large())?;
+    let size = u32::try_from(len).map_err(|_| too_large())?;
+    let central_offset =
+        u32::try_from(carrier_len + 30 + name.len() as u64 + len).map_err(|_| too_large())?;
+
+    // The CRC goes before the data, so the payload is read twice rather than buffered.
+    let start = payload.stream_position()?;
+    let mut crc = 0;
+    let mut limited = payload.by_ref().take(len);
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = limited.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        crc = crc32(crc, &buf[..n]);
+    }
+    payload.seek(SeekFrom::Start(start))?;
+
+    io::copy(&mut carrier, &mut writer)?;
+
+    let mut local = Vec::with_capacity(30 + name.len());
+    local.extend_from_slice(LOCAL_HEADER_SIG);
+    local.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+    local.extend_from_slice(&[0; 4]); // flags and compression method (stored)
+    local.extend_from_slice(&[0, 0, 0x21, 0]); // modification time and date, 1980-01-01
+    local.extend_from_slice(&crc.to_le_bytes());
+    local.extend_from_slice(&size.to_le_bytes());
+    local.extend_from_slice(&size.to_le_bytes());
+    local.extend_from_slice(&name_len.to_le_bytes());
+    local.extend_from_slice(&[0; 2]); // extra field length
+    local.extend_from_slice(name.as_bytes());
+    writer.write_all(&local)?;
+
+    let copied = io::copy(&mut payload.take(len), &mut writer)?;
+    if copied != len {
+        return Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "Payload shorter than announced",
+        ));
+    }
+
+    let mut central = Vec::with_capacity(46 + name.len());
+    central.extend_from_slice(CENTRAL_HEADER_SIG);
+    central.extend_from_slice(&ZIP_VERSION.to_le_bytes()); // version made by
+    central.extend_from_slice(&local[4..26]);
+    central.extend_from_slice(&name_len.to_le_bytes());
+    central.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+    central.extend_from_slice(&local_offset.to_le_bytes());
+    central.extend_from_slice(name.as_bytes());
+    writer.write_all(&central)?;
+
+    let mut eocd = Vec::with_capacity(EOCD_LEN as usize);
+    eocd.extend_from_slice(EOCD_SIG);
+    eocd.extend_from_slice(&[0; 4]); // disk numbers
+    eocd.extend_from_slice(&1u16.to_le_bytes());
+    eocd.extend_from_slice(&1u16.to_le_bytes());
+    eocd.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    eocd.extend_from_slice(&central_offset.to_le_bytes());
+    eocd.extend_from_slice(&[0; 2]); // comment length
+    writer.write_all(&eocd)
+}
+
+/// Extracts the first entry of the ZIP half of a PNG+ZIP polyglot.
+///
+/// # Arguments
+///
+/// * `reader` - A readable and seekable input holding the polyglot.
+///
+/// # Returns
+///
+/// A `Result` containing the entry, or an `io::Error` if the archive is missing, uses an
+/// unsupported compression method or fails its CRC check.
+pub fn extract_polyglot<R: Read + Seek>(reader: &mut R) -> io::Result<PolyglotEntry> {
+    let invalid = |msg: &str| io::Error::new(ErrorKind::InvalidData, msg.to_string());
+    let file_len = stream_len(reader)?;
+    if file_len < EOCD_LEN {
+        return Err(invalid("No ZIP archive found"));
+    }
+    let window_start = file_len.saturating_sub(EOCD_SEARCH);
+    let window = read_vec_at(reader, window_start, (file_len - window_start) as usize)?;
+    let eocd_pos = (0..=window.len() - EOCD_LEN as usize)
+        .rev()
+        .find(|&pos| &window[pos..pos + 4] == EOCD_SIG)
+        .ok_or_else(|| invalid("No ZIP archive found"))?;
+    let eocd = &window[eocd_pos..];
+    let entries = u16::from_le_bytes([eocd[10], eocd[11]]);
+    let central_offset = u32::from_le_bytes(eocd[16..20].try_into().unwrap()) as u64;
+    if entries == 0 {
+        return Err(invalid("The ZIP archive is empty"));
+    }
+
+    let central = read_vec_at(reader, central_offset, 46)?;
+    if &central[..4] != CENTRAL_HEADER_SIG {
+        return Err(invalid("Corrupted ZIP central directory"));
+    }
+    let method = u16::from_le_bytes([central[10], central[11]]);
+    let crc = u32::from_le_bytes(central[16..20].try_into().unwrap());
+    let size = u32::from_le_bytes(central[20..24].try_into().unwrap()) as usize;
+    let name_len = u16::from_le_bytes([central[28], central[29]]) as usize;
+    let offset = u32::from_le_bytes(central[42..46].try_into().unwrap()) as u64;
+    if method != 0 {
+        return Err(invalid("Only stored ZIP entries are supported"));
+    }
+    let name = read_vec_at(reader, central_offset + 46, name_len)?;
+
+    let local = read_vec_at(reader, offset, 30)?;
+    if &local[..4] != LOCAL_HEADER_SIG {
+        return Err(invalid("Corrupted ZIP local header"));
+    }
+    let local_name_len = u16::from_le_bytes([local[26], local[27]]) as u64;
+    let extra_len = u16::from_le_bytes([local[28], local[29]]) as u64;
+    let data = read_vec_at(reader, offset + 30 + local_name_len + extra_len, size)?;
+    if crc32(0, &data) != crc {
+        return Err(invalid("ZIP entry failed its CRC check"));
+    }
+
+    Ok(PolyglotEntry {
+        name: String::from_utf8_lossy(&name).into_owned(),
+        data,
+        offset,
+    })
+}
@@ -0,0 +1,170 @@
+use crate::png::{read_chunks, write_chunk, RawChunk};
+use crate::polyglot::PNG_SIGNATURE;
+use std::io::{self, ErrorKind};
+
+/// Number of payload bytes the precision method can hold.
+pub const PRECISION_CAPACITY: usize = 5;
+
+/// Chunks carrying the payload, with the data written when the carrier lacks them: the sRGB
+/// gamma and chromaticities, and 72 DPI square pixels.
+const PRECISION_CHUNKS: [(&[u8; 4], &[u32]); 3] = [
+    (b"gAMA", &[45455]),
+    (
+        b"cHRM",
+        &[31270, 32900, 64000, 33000, 30000, 60000, 15000, 6000],
+    ),
+    (b"pHYs", &[2835, 2835]),
+];
+
+/// Number of 4-bit fields available: one for the length, two per payload byte.
+const PRECISION_FIELDS: usize = 1 + 2 * PRECISION_CAPACITY;
+
+/// Returns the value closest to `value` whose low four bits are `nibble`, never zero.
+fn with_nibble(value: u32, nibble: u8) -> u32 {
+    let base = (value & !0xF) as i64 + nibble as i64;
+    [base - 16, base, base + 16]
+        .into_iter()
+        .filter(|&candidate| candidate > 0 && candidate <= u32::MAX as i64)
+        .min_by_key(|&candidate| (candidate - value as i64).abs())
+        .unwrap_or(value as i64) as u32
+}
+
+/// Returns the big-endian values of a precision chunk.
+fn values(data: &[u8]) -> Vec<u32> {
+    data.chunks_exact(4)
+        .map(|word| u32::from_be_bytes(word.try_into().unwrap()))
+        .collect()
+}
+
+/// Hides a short payload in the low-order bits of the `gAMA`, `cHRM` and `pHYs` values.
+///
+/// Each of the 11 values carries four bits: the first holds the payload length, the others the
+/// payload bytes, high nibble first. Values are moved by at most 8 units of 1/100000 for gamma
+/// and chromaticities, and 8 pixels per unit for the physical size, which is far below what
+/// color management can show. Missing chunks are inserted after `IHDR` with sRGB defaults, and
+/// unused values are left untouched. The payload survives tools that preserve these chunks,
+/// but not a re-encode that drops or rewrites them.
+///
+/// # Arguments
+///
+/// * `data` - The bytes of the PNG carrier.
+/// * `payload` - Up to `PRECISION_CAPACITY` bytes to hide.
+///
+/// # Returns
+///
+/// A `Result` containing the bytes of the stego file, or an `InvalidInput` error if the payload
+/// is too long.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::encode_grayscale;
+/// use stegano::precision::{embed_precision, extract_precision};
+///
+/// let carrier = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let stego = embed_precision(&carrier, b"wm42").unwrap();
+/// assert_eq!(extract_precision(&stego).unwrap(), b"wm42");
+/// assert!(embed_precision(&carrier, b"too long").is_err());
+/// ```
+pub fn embed_precision(data: &[u8], payload: &[u8]) -> io::Result<Vec<u8>> {
+    if payload.len() > PRECISION_CAPACITY {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The payload needs {} bytes but the precision method offers {}; use --algo xor with a short payload",
+                payload.len(),
+                PRECISION_CAPACITY
+            ),
+        ));
+    }
+    let mut nibbles = vec![payload.len() as u8];
+    for byte in payload {
+        nibbles.extend([byte >> 4, byte & 0xF]);
+    }
+
+    let chunks = read_chunks(data)?;
+    let mut out = PNG_SIGNATURE.to_vec();
+    let mut field = 0;
+    for chunk in &chunks {
+        // The precision chunks are all rewritten right after IHDR.
+        if PRECISION_CHUNKS
+            .iter()
+            .any(|(name, _)| **name == chunk.r#type)
+        {
+            continue;
+        }
+        write_chunk(&mut out, &chunk.r#type.bytes(), &chunk.data)?;
+        if &chunk.r#type != b"IHDR" {
+            continue;
+        }
+        for (name, defaults) in PRECISION_CHUNKS {
+            let existing = chunks.iter().find(|c| &c.r#type == name);
+            let mut data = existing.map_or_else(
+                || {
+                    let mut data: Vec<u8> = defaults.iter().flat_map(|v| v.to_be_bytes()).collect();
+                    if name == b"pHYs" {
+                        data.push(1);
+                    }
+                    data
+                },
+                |c| c.data.clone(),
+            );
+            for i in 0..defaults.len() {
+                let word = data
+                    .get_mut(i * 4..i * 4 + 4)
+                    .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Truncated chunk"))?;
+                if let Some(&nibble) = nibbles.get(field) {
+                    let value = u32::from_be_bytes((&*word).try_into().unwrap());
+                    word.copy_from_slice(&with_nibble(value, nibble).to_be_bytes());
+                }
+                field += 1;
+            }
+            write_chunk(&mut out, name, &data)?;
+        }
+    }
+    debug_assert_eq!(field, PRECISION_FIELDS);
+    Ok(out)
+}
+
+/// Extracts a payload hidden by `embed_precision`.
+///
+/// Any PNG with the three chunks yields some bytes, so without a key check this is only
+/// meaningful on files known to carry a precision payload.
+///
+/// # Arguments
+///
+/// * `data` - The bytes of the stego file.
+///
+/// # Returns
+///
+/// A `Result` containing the payload, or a `NotFound` error if a chunk is missing or the
+/// length field is out of range.
+pub fn extract_precision(data: &[u8]) -> io::Result<Vec<u8>> {
+    let chunks = read_chunks(data)?;
+    let not_found = || {
+        io::Error::new(
+            ErrorKind::NotFound,
+            "No precision payload found in the PNG file",
+        )
+    };
+    let mut nibbles = Vec::with_capacity(PRECISION_FIELDS);
+    for (name, defaults) in PRECISION_CHUNKS {
+        let chunk: &RawChunk = chunks
+            .iter()
+            .find(|c| &c.r#type == name)
+            .ok_or_else(not_found)?;
+        let values = values(&chunk.data);
+        if values.len() < defaults.len() {
+            return Err(not_found());
+        }
+        nibbles.extend(values[..defaults.len()].iter().map(|v| (v & 0xF) as u8));
+    }
+    let len = nibbles[0] as usize;
+    if len > PRECISION_CAPACITY {
+        return Err(not_found());
+    }
+    Ok(nibbles[1..1 + 2 * len]
+        .chunks_exact(2)
+        .map(|pair| pair[0] << 4 | pair[1])
+        .collect())
+}
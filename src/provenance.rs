@@ -0,0 +1,106 @@
+use crate::append::{find_frame_before, sha256_prefix, AppendFrame};
+use crate::io_ext::stream_len;
+use crate::outln;
+use crate::style::Style;
+use std::io::{self, Read, Seek};
+
+/// One edit in the chain of custody of a carrier.
+#[derive(Debug, Clone)]
+pub struct ProvenanceLink {
+    /// The payload frame appended by this edit.
+    pub frame: AppendFrame,
+    /// SHA-256 digest of the carrier as it was before this edit.
+    pub actual_parent: [u8; 32],
+}
+
+impl ProvenanceLink {
+    /// Returns `Some(true)` if the recorded parent digest matches the carrier preceding the
+    /// frame, `Some(false)` if it doesn't, and `None` if the frame records no parent.
+    pub fn verified(&self) -> Option<bool> {
+        self.frame.parent.map(|parent| parent == self.actual_parent)
+    }
+}
+
+/// Walks all payload frames appended to a carrier and checks their parent links.
+///
+/// # Arguments
+///
+/// * `reader` - A readable and seekable input holding the carrier.
+///
+/// # Returns
+///
+/// A `Result` containing the links ordered from the oldest edit to the newest.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use stegano::append::write_appended_linked;
+/// use stegano::provenance::provenance_chain;
+///
+/// let mut first = Vec::new();
+/// write_appended_linked(&b"carrier"[..], &b"one"[..], 3, &mut first).unwrap();
+/// let mut second = Vec::new();
+/// write_appended_linked(&first[..], &b"two"[..], 3, &mut second).unwrap();
+///
+/// let chain = provenance_chain(&mut Cursor::new(second)).unwrap();
+/// assert_eq!(chain.len(), 2);
+/// assert!(chain.iter().all(|link| link.verified() == Some(true)));
+/// ```
+pub fn provenance_chain<R: Read + Seek>(reader: &mut R) -> io::Result<Vec<ProvenanceLink>> {
+    let mut links = Vec::new();
+    let mut end = stream_len(reader)?;
+    while let Some(frame) = find_frame_before(reader, end)? {
+        end = frame.offset;
+        links.push(ProvenanceLink {
+            actual_parent: sha256_prefix(reader, frame.offset)?,
+            frame,
+        });
+    }
+    links.reverse();
+    Ok(links)
+}
+
+/// Formats a digest as a lowercase hexadecimal string.
+pub fn digest_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Prints the chain of custody of a carrier as a history tree.
+///
+/// # Arguments
+///
+/// * `file_name` - The name of the carrier, used as the root of the tree.
+/// * `links` - The links returned by `provenance_chain`.
+pub fn print_provenance(file_name: &str, links: &[ProvenanceLink]) {
+    if links.is_empty() {
+        outln!("No embedded payload frames found in {}.", file_name);
+        return;
+    }
+    let root = &links[0];
+    outln!("{} ({} edits)", Style::Green.paint(file_name), links.len());
+    outln!(
+        "original carrier: {} bytes, sha256 {}",
+        root.frame.offset,
+        digest_hex(&root.actual_parent)
+    );
+    for (i, link) in links.iter().enumerate() {
+        let indent = "    ".repeat(i);
+        let status = match link.verified() {
+            Some(true) => Style::Green.paint("verified"),
+            Some(false) => Style::Red.paint("BROKEN LINK"),
+            None => Style::Yellow.paint("no parent link"),
+        };
+        outln!(
+            "{}└─ edit #{}: offset {}, {} bytes payload, {}",
+            indent,
+            i + 1,
+            link.frame.offset,
+            link.frame.len,
+            status
+        );
+        if let Some(parent) = link.frame.parent {
+            outln!("{}   parent sha256 {}", indent, digest_hex(&parent));
+        }
+    }
+}
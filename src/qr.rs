@@ -0,0 +1,1528 @@
+use crate::png::{encode_grayscale, PngImage};
+use std::io::{self, ErrorKind};
+
+/// Powers of 2 in GF(256) under the QR polynomial x^8 + x^4 + x^3 + x^2 + 1, written twice
+/// so that sums of two logarithms index it without reduction.
+const EXP: [u8; 512] = {
+    let mut table = [0; 512];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 512 {
+        table[i] = x as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= 0x11D;
+        }
+        i += 1;
+    }
+    table
+};
+
+/// Logarithms in GF(256), the inverse of `EXP`; the entry for 0 is unused.
+const LOG: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 255 {
+        table[EXP[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+};
+
+/// Error correction codewords per block, by level (L, M, Q, H) and version.
+const ECC_PER_BLOCK: [[u8; 41]; 4] = [
+    [
+        0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28,
+        30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28,
+        28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    ],
+    [
+        0, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30, 28, 30,
+        30, 30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28, 30, 24,
+        30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+];
+
+/// Error correction blocks, by level (L, M, Q, H) and version.
+const BLOCKS: [[u8; 41]; 4] = [
+    [
+        0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13,
+        14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+    ],
+    [
+        0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21,
+        23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+    ],
+    [
+        0, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29,
+        34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68,
+    ],
+    [
+        0, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32,
+        35, 37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81,
+    ],
+];
+
+/// The characters of the alphanumeric mode, by value.
+const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// Modules of light margin around a rendered code, as the standard asks.
+const QUIET_ZONE: usize = 4;
+
+/// The error correction level of a QR code, from the least to the most redundant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EcLevel {
+    /// Recovers about 7% of the codewords.
+    L,
+    /// Recovers about 15% of the codewords.
+    M,
+    /// Recovers about 25% of the codewords.
+    Q,
+    /// Recovers about 30% of the codewords.
+    H,
+}
+
+impl EcLevel {
+    /// Returns the index of the level in the tables.
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the two bits of the level in the format information.
+    fn format_bits(self) -> u32 {
+        match self {
+            EcLevel::L => 1,
+            EcLevel::M => 0,
+            EcLevel::Q => 3,
+            EcLevel::H => 2,
+        }
+    }
+}
+
+/// A QR code: a square of dark and light modules.
+///
+/// `encode` builds one from data, in byte mode, and `decode` reads one back with its error
+/// correction, in any mode. `decode_image` finds and reads a code in a PNG picture.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::qr::{EcLevel, QrCode};
+///
+/// let code = QrCode::encode(b"hello").unwrap();
+/// assert_eq!((code.version, code.size), (1, 21));
+/// assert_eq!(code.level, EcLevel::H);
+/// assert_eq!(code.decode().unwrap(), b"hello");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    /// Version from 1 to 40, which sets the size.
+    pub version: u8,
+    /// Modules per side, 17 + 4 * version.
+    pub size: usize,
+    /// Error correction level.
+    pub level: EcLevel,
+    /// Mask pattern from 0 to 7.
+    pub mask: u8,
+    /// Modules in row-major order, `true` for dark.
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encodes data in byte mode into the smallest code at level M, raising the level as far
+    /// as the data still fits that version.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the code, or an `InvalidInput` error for data beyond the 2331
+    /// bytes of a version 40 code at level M.
+    pub fn encode(data: &[u8]) -> io::Result<QrCode> {
+        let bits_needed = |version: u8| 4 + count_bits(4, version) + 8 * data.len();
+        let version = (1..=40)
+            .find(|&version| bits_needed(version) <= data_codewords(version, EcLevel::M) * 8)
+            .ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "{} bytes don't fit in a QR code, which holds at most {}",
+                        data.len(),
+                        data_codewords(40, EcLevel::M) - 3
+                    ),
+                )
+            })?;
+        let level = [EcLevel::H, EcLevel::Q, EcLevel::M]
+            .into_iter()
+            .find(|&level| bits_needed(version) <= data_codewords(version, level) * 8)
+            .unwrap_or(EcLevel::M);
+
+        let capacity = data_codewords(version, level) * 8;
+        let mut bits = BitWriter::default();
+        bits.push(0b0100, 4);
+        bits.push(data.len() as u32, count_bits(4, version));
+        for &byte in data {
+            bits.push(byte as u32, 8);
+        }
+        bits.push(0, (capacity - bits.len).min(4));
+        bits.push(0, (8 - bits.len % 8) % 8);
+        let mut codewords = bits.bytes;
+        for pad in [0xEC, 0x11].into_iter().cycle() {
+            if codewords.len() * 8 >= capacity {
+                break;
+            }
+            codewords.push(pad);
+        }
+        let codewords = add_ecc_and_interleave(&codewords, version, level);
+
+        let mut template = Grid::new(version);
+        template.draw_codewords(&codewords);
+        let mut best: Option<(usize, Grid, u8)> = None;
+        for mask in 0..8 {
+            let mut grid = template.clone();
+            grid.apply_mask(mask);
+            grid.draw_format(level, mask);
+            let penalty = grid.penalty();
+            if best.as_ref().is_none_or(|(lowest, _, _)| penalty < *lowest) {
+                best = Some((penalty, grid, mask));
+            }
+        }
+        let (_, grid, mask) = best.expect("there are eight masks");
+        Ok(QrCode {
+            version,
+            size: grid.size,
+            level,
+            mask,
+            modules: grid.dark,
+        })
+    }
+
+    /// Builds a code from its modules, as read from a picture, checking only the size.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Modules per side: 21, 25, ... up to 177.
+    /// * `modules` - The modules in row-major order, `true` for dark.
+    pub fn from_modules(size: usize, modules: Vec<bool>) -> io::Result<QrCode> {
+        if !(21..=177).contains(&size) || size % 4 != 1 || modules.len() != size * size {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} modules per side isn't a QR code size", size),
+            ));
+        }
+        let (level, mask) = read_format(size, &modules)?;
+        Ok(QrCode {
+            version: ((size - 17) / 4) as u8,
+            size,
+            level,
+            mask,
+            modules,
+        })
+    }
+
+    /// Returns whether the module at column `x` and row `y` is dark.
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    /// Reads the data of the code, correcting errors up to what its level allows.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the data, with numeric and alphanumeric segments as ASCII and
+    /// kanji as Shift JIS, or an `InvalidData` error if there are too many errors.
+    pub fn decode(&self) -> io::Result<Vec<u8>> {
+        let version = self.version;
+        let mut grid = Grid::new(version);
+        grid.dark.copy_from_slice(&self.modules);
+        grid.apply_mask(self.mask);
+        let codewords = grid.read_codewords();
+
+        // Undo the interleaving, then correct each block.
+        let ecc_len = ECC_PER_BLOCK[self.level.index()][version as usize] as usize;
+        let blocks = BLOCKS[self.level.index()][version as usize] as usize;
+        let raw = raw_data_modules(version) / 8;
+        let short_blocks = blocks - raw % blocks;
+        let short_len = raw / blocks;
+        let mut data_blocks: Vec<Vec<u8>> = (0..blocks)
+            .map(|block| Vec::with_capacity(short_len + usize::from(block >= short_blocks)))
+            .collect();
+        let mut codewords = codewords.into_iter();
+        for i in 0..=short_len {
+            for (block, data) in data_blocks.iter_mut().enumerate() {
+                // Short blocks have no codeword where long ones have their last data one.
+                if i == short_len - ecc_len && block < short_blocks {
+                    continue;
+                }
+                data.push(codewords.next().unwrap_or(0));
+            }
+        }
+        let mut data = Vec::new();
+        for mut block in data_blocks {
+            rs_correct(&mut block, ecc_len)?;
+            data.extend_from_slice(&block[..block.len() - ecc_len]);
+        }
+        parse_segments(&data, version)
+    }
+
+    /// Renders the code as a greyscale PNG with a quiet zone, `scale` pixels per module.
+    pub fn to_png(&self, scale: usize) -> io::Result<Vec<u8>> {
+        let side = (self.size + 2 * QUIET_ZONE) * scale;
+        let mut pixels = vec![255u8; side * side];
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if !self.is_dark(x, y) {
+                    continue;
+                }
+                for dy in 0..scale {
+                    let row = ((y + QUIET_ZONE) * scale + dy) * side;
+                    let start = row + (x + QUIET_ZONE) * scale;
+                    pixels[start..start + scale].fill(0);
+                }
+            }
+        }
+        encode_grayscale(side, side, &pixels)
+    }
+
+    /// Renders the code with Unicode half blocks, two rows of modules per line.
+    ///
+    /// The blocks draw the light modules, so the code reads on terminals with a dark
+    /// background.
+    pub fn to_unicode(&self) -> String {
+        let size = self.size as isize;
+        let quiet = QUIET_ZONE as isize;
+        let light = |x: isize, y: isize| {
+            !(0..size).contains(&x)
+                || !(0..size).contains(&y)
+                || !self.is_dark(x as usize, y as usize)
+        };
+        let mut text = String::new();
+        let mut y = -quiet;
+        while y < size + quiet {
+            for x in -quiet..size + quiet {
+                text.push(
+                    match (light(x, y), light(x, y + 1) && y + 1 < size + quiet) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    },
+                );
+            }
+            text.push('\n');
+            y += 2;
+        }
+        text
+    }
+}
+
+/// Finds a QR code in a PNG picture, such as a photo of a screen or a print, and reads it.
+///
+/// The picture is binarized with a global threshold, then a local one for uneven lighting.
+/// The three finder patterns give the position, size and rotation of the code, and the
+/// bottom-right alignment pattern, when the version has one, corrects for perspective.
+///
+/// # Arguments
+///
+/// * `png` - The whole PNG file.
+///
+/// # Returns
+///
+/// A `Result` containing the data of the code, or an `InvalidData` error if no code could be
+/// read.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::qr::{decode_image, QrCode};
+///
+/// let png = QrCode::encode(b"air-gapped").unwrap().to_png(4).unwrap();
+/// assert_eq!(decode_image(&png).unwrap(), b"air-gapped");
+/// ```
+pub fn decode_image(png: &[u8]) -> io::Result<Vec<u8>> {
+    let image = PngImage::decode(png)?;
+    let grey = greyscale(&image);
+    let mut last_error = None;
+    for binary in [
+        Binary::global(&grey, image.width, image.height),
+        Binary::local(&grey, image.width, image.height),
+    ] {
+        let finders = binary.find_finders();
+        for [top_left, top_right, bottom_left] in pick_finders(&finders).into_iter().take(4) {
+            let estimate = binary.estimate_version(&top_left, &top_right, &bottom_left);
+            for version in [estimate, estimate.saturating_sub(1), estimate + 1] {
+                if !(1..=40).contains(&version) {
+                    continue;
+                }
+                let transform = binary.transform(version, &top_left, &top_right, &bottom_left);
+                let size = 17 + 4 * version as usize;
+                let modules: Vec<bool> = (0..size * size)
+                    .map(|i| {
+                        let (x, y) =
+                            transform.apply((i % size) as f64 + 0.5, (i / size) as f64 + 0.5);
+                        binary.is_dark_at(x, y)
+                    })
+                    .collect();
+                match QrCode::from_modules(size, modules).and_then(|code| code.decode()) {
+                    Ok(data) => return Ok(data),
+                    Err(err) => last_error = Some(err),
+                }
+            }
+        }
+    }
+    Err(match last_error {
+        Some(err) => io::Error::new(
+            ErrorKind::InvalidData,
+            format!("A QR code was found but couldn't be read: {}", err),
+        ),
+        None => io::Error::new(ErrorKind::InvalidData, "No QR code found in the image"),
+    })
+}
+
+/// Returns the number of bits of the character count of a mode, by mode indicator.
+fn count_bits(mode: u32, version: u8) -> usize {
+    let column = match version {
+        1..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    let bits = match mode {
+        0b0001 => [10, 12, 14],
+        0b0010 => [9, 11, 13],
+        0b0100 => [8, 16, 16],
+        _ => [8, 10, 12],
+    };
+    bits[column]
+}
+
+/// Returns the number of modules of a version left for codewords once the function patterns
+/// are drawn.
+fn raw_data_modules(version: u8) -> usize {
+    let version = version as usize;
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        modules -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+/// Returns the number of data codewords of a version at a level.
+fn data_codewords(version: u8, level: EcLevel) -> usize {
+    raw_data_modules(version) / 8
+        - ECC_PER_BLOCK[level.index()][version as usize] as usize
+            * BLOCKS[level.index()][version as usize] as usize
+}
+
+/// Returns the centres of the alignment patterns along each axis of a version.
+fn alignment_positions(version: u8) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let version = version as usize;
+    let alignments = version / 7 + 2;
+    let step = (version * 8 + alignments * 3 + 5) / (alignments * 4 - 4) * 2;
+    let mut positions: Vec<usize> = (0..alignments - 1)
+        .map(|i| 17 + 4 * version - 7 - i * step)
+        .collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+/// Splits data codewords into blocks, appends their error correction and interleaves them.
+fn add_ecc_and_interleave(data: &[u8], version: u8, level: EcLevel) -> Vec<u8> {
+    let ecc_len = ECC_PER_BLOCK[level.index()][version as usize] as usize;
+    let blocks = BLOCKS[level.index()][version as usize] as usize;
+    let raw = raw_data_modules(version) / 8;
+    let short_blocks = blocks - raw % blocks;
+    let short_len = raw / blocks;
+    let divisor = rs_divisor(ecc_len);
+    let mut start = 0;
+    let all: Vec<Vec<u8>> = (0..blocks)
+        .map(|block| {
+            let len = short_len - ecc_len + usize::from(block >= short_blocks);
+            let mut codewords = data[start..start + len].to_vec();
+            start += len;
+            let ecc = rs_remainder(&codewords, &divisor);
+            if block < short_blocks {
+                codewords.push(0);
+            }
+            codewords.extend_from_slice(&ecc);
+            codewords
+        })
+        .collect();
+    let mut result = Vec::with_capacity(raw);
+    for i in 0..=short_len {
+        for (block, codewords) in all.iter().enumerate() {
+            // Short blocks have a placeholder where long ones have their last data one.
+            if i != short_len - ecc_len || block >= short_blocks {
+                result.push(codewords[i]);
+            }
+        }
+    }
+    result
+}
+
+/// Multiplies two elements of GF(256).
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+}
+
+/// Returns the inverse of a non-zero element of GF(256).
+fn gf_inv(a: u8) -> u8 {
+    EXP[255 - LOG[a as usize] as usize]
+}
+
+/// Returns 2 raised to a power, which may be negative, in GF(256).
+fn gf_pow2(power: isize) -> u8 {
+    EXP[power.rem_euclid(255) as usize]
+}
+
+/// Evaluates a polynomial with its lowest-degree coefficient first.
+fn poly_eval(poly: &[u8], x: u8) -> u8 {
+    poly.iter().rev().fold(0, |acc, &c| gf_mul(acc, x) ^ c)
+}
+
+/// Returns the generator polynomial of Reed-Solomon codes of a degree, highest-degree first
+/// without its leading 1.
+fn rs_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0; degree];
+    result[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_mul(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_mul(root, 2);
+    }
+    result
+}
+
+/// Returns the Reed-Solomon error correction codewords of data.
+fn rs_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (r, &d) in result.iter_mut().zip(divisor) {
+            *r ^= gf_mul(d, factor);
+        }
+    }
+    result
+}
+
+/// Corrects a block of codewords in place, ending with `ecc_len` error correction ones.
+///
+/// Uses Berlekamp-Massey for the error locator, a Chien search for the positions and Forney
+/// for the values.
+fn rs_correct(block: &mut [u8], ecc_len: usize) -> io::Result<()> {
+    let n = block.len();
+    // The first codeword is the coefficient of the highest degree.
+    let syndromes: Vec<u8> = (0..ecc_len)
+        .map(|j| {
+            block
+                .iter()
+                .fold(0, |acc, &c| gf_mul(acc, gf_pow2(j as isize)) ^ c)
+        })
+        .collect();
+    if syndromes.iter().all(|&s| s == 0) {
+        return Ok(());
+    }
+    let too_many = || io::Error::new(ErrorKind::InvalidData, "Too many errors in the QR code");
+
+    let mut locator = vec![1u8];
+    let mut previous = vec![1u8];
+    let mut errors = 0;
+    let mut shift = 1;
+    let mut previous_discrepancy = 1u8;
+    for step in 0..ecc_len {
+        let mut discrepancy = syndromes[step];
+        for i in 1..=errors.min(locator.len() - 1) {
+            discrepancy ^= gf_mul(locator[i], syndromes[step - i]);
+        }
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+        let factor = gf_mul(discrepancy, gf_inv(previous_discrepancy));
+        let mut next = locator.clone();
+        if next.len() < previous.len() + shift {
+            next.resize(previous.len() + shift, 0);
+        }
+        for (i, &p) in previous.iter().enumerate() {
+            next[i + shift] ^= gf_mul(factor, p);
+        }
+        if 2 * errors <= step {
+            previous = std::mem::replace(&mut locator, next);
+            errors = step + 1 - errors;
+            previous_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            locator = next;
+            shift += 1;
+        }
+    }
+    locator.truncate(errors + 1);
+    if 2 * errors > ecc_len {
+        return Err(too_many());
+    }
+
+    // Error at degree e when the locator vanishes at 2^-e.
+    let positions: Vec<usize> = (0..n)
+        .filter(|&degree| poly_eval(&locator, gf_pow2(-(degree as isize))) == 0)
+        .collect();
+    if positions.len() != errors {
+        return Err(too_many());
+    }
+    let mut evaluator: Vec<u8> = vec![0; ecc_len];
+    for (i, &s) in syndromes.iter().enumerate() {
+        for (j, &l) in locator.iter().enumerate() {
+            if i + j < ecc_len {
+                evaluator[i + j] ^= gf_mul(s, l);
+            }
+        }
+    }
+    // The formal derivative keeps the odd terms, in characteristic 2.
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &l)| if i % 2 == 1 { l } else { 0 })
+        .collect();
+    for degree in positions {
+        let x = gf_pow2(degree as isize);
+        let x_inv = gf_inv(x);
+        let denominator = poly_eval(&derivative, x_inv);
+        if denominator == 0 {
+            return Err(too_many());
+        }
+        let magnitude = gf_mul(gf_mul(x, poly_eval(&evaluator, x_inv)), gf_inv(denominator));
+        block[n - 1 - degree] ^= magnitude;
+    }
+    Ok(())
+}
+
+/// Returns the 15 format bits of a level and mask, with their BCH code and the XOR mask.
+fn format_bits(level: EcLevel, mask: u8) -> u32 {
+    let data = level.format_bits() << 3 | mask as u32;
+    let mut remainder = data;
+    for _ in 0..10 {
+        remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+    }
+    (data << 10 | remainder) ^ 0x5412
+}
+
+/// Returns the module coordinates of each format bit, for both copies.
+fn format_positions(size: usize) -> [[(usize, usize); 15]; 2] {
+    let mut first = [(0, 0); 15];
+    let mut second = [(0, 0); 15];
+    for (i, (a, b)) in first.iter_mut().zip(second.iter_mut()).enumerate() {
+        *a = match i {
+            0..=5 => (8, i),
+            6 => (8, 7),
+            7 => (8, 8),
+            8 => (7, 8),
+            _ => (14 - i, 8),
+        };
+        *b = if i < 8 {
+            (size - 1 - i, 8)
+        } else {
+            (8, size - 15 + i)
+        };
+    }
+    [first, second]
+}
+
+/// Reads the level and mask of a code from the closest valid format information.
+fn read_format(size: usize, modules: &[bool]) -> io::Result<(EcLevel, u8)> {
+    let mut best = None;
+    for copy in format_positions(size) {
+        let bits = copy.iter().enumerate().fold(0u32, |acc, (i, &(x, y))| {
+            acc | (modules[y * size + x] as u32) << i
+        });
+        for level in [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+            for mask in 0..8 {
+                let distance = (format_bits(level, mask) ^ bits).count_ones();
+                if best.is_none_or(|(lowest, _, _)| distance < lowest) {
+                    best = Some((distance, level, mask));
+                }
+            }
+        }
+    }
+    match best {
+        Some((distance, level, mask)) if distance <= 3 => Ok((level, mask)),
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Unreadable QR format information",
+        )),
+    }
+}
+
+/// Returns whether a mask pattern flips the module at column `x` and row `y`.
+fn mask_flips(mask: u8, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (x + y).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (x + y).is_multiple_of(3),
+        4 => (x / 3 + y / 2).is_multiple_of(2),
+        5 => x * y % 2 + x * y % 3 == 0,
+        6 => (x * y % 2 + x * y % 3).is_multiple_of(2),
+        _ => ((x + y) % 2 + x * y % 3).is_multiple_of(2),
+    }
+}
+
+/// Reads the segments of the data codewords.
+fn parse_segments(data: &[u8], version: u8) -> io::Result<Vec<u8>> {
+    let mut reader = BitReader { data, position: 0 };
+    let mut output = Vec::new();
+    let malformed = || io::Error::new(ErrorKind::InvalidData, "Malformed QR code data");
+    while reader.remaining() >= 4 {
+        let mode = reader.read(4).ok_or_else(malformed)?;
+        match mode {
+            0b0000 => break,
+            // Extended channel interpretation: the designator is skipped, bytes are kept.
+            0b0111 => {
+                let first = reader.read(8).ok_or_else(malformed)?;
+                let extra = match first {
+                    0x00..=0x7F => 0,
+                    0x80..=0xBF => 8,
+                    _ => 16,
+                };
+                reader.read(extra).ok_or_else(malformed)?;
+            }
+            // Structured append and FNC1 only carry parameters.
+            0b0011 => {
+                reader.read(16).ok_or_else(malformed)?;
+            }
+            0b0101 => {}
+            0b1001 => {
+                reader.read(8).ok_or_else(malformed)?;
+            }
+            0b0001 => {
+                let mut count = reader
+                    .read(count_bits(mode, version))
+                    .ok_or_else(malformed)?;
+                while count > 0 {
+                    let digits = count.min(3);
+                    let value = reader
+                        .read([0, 4, 7, 10][digits as usize])
+                        .ok_or_else(malformed)?;
+                    let text = format!("{:0width$}", value, width = digits as usize);
+                    if text.len() != digits as usize {
+                        return Err(malformed());
+                    }
+                    output.extend_from_slice(text.as_bytes());
+                    count -= digits;
+                }
+            }
+            0b0010 => {
+                let mut count = reader
+                    .read(count_bits(mode, version))
+                    .ok_or_else(malformed)?;
+                while count > 0 {
+                    if count >= 2 {
+                        let value = reader.read(11).ok_or_else(malformed)? as usize;
+                        let (high, low) = (value / 45, value % 45);
+                        output.push(*ALPHANUMERIC.get(high).ok_or_else(malformed)?);
+                        output.push(ALPHANUMERIC[low]);
+                        count -= 2;
+                    } else {
+                        let value = reader.read(6).ok_or_else(malformed)? as usize;
+                        output.push(*ALPHANUMERIC.get(value).ok_or_else(malformed)?);
+                        count -= 1;
+                    }
+                }
+            }
+            0b0100 => {
+                let count = reader
+                    .read(count_bits(mode, version))
+                    .ok_or_else(malformed)?;
+                for _ in 0..count {
+                    output.push(reader.read(8).ok_or_else(malformed)? as u8);
+                }
+            }
+            0b1000 => {
+                let count = reader
+                    .read(count_bits(mode, version))
+                    .ok_or_else(malformed)?;
+                for _ in 0..count {
+                    let value = reader.read(13).ok_or_else(malformed)?;
+                    let mut code = ((value / 0xC0) << 8) | (value % 0xC0);
+                    code += if code < 0x1F00 { 0x8140 } else { 0xC140 };
+                    output.extend_from_slice(&(code as u16).to_be_bytes());
+                }
+            }
+            _ => return Err(malformed()),
+        }
+    }
+    Ok(output)
+}
+
+/// Appends bits to bytes, most significant first.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitWriter {
+    /// Appends the `count` low bits of `value`.
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if value >> i & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+/// Reads bits from bytes, most significant first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    /// Returns the number of bits left.
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.position
+    }
+
+    /// Reads `count` bits, or `None` past the end.
+    fn read(&mut self, count: usize) -> Option<u32> {
+        if count > self.remaining() {
+            return None;
+        }
+        let mut value = 0;
+        for _ in 0..count {
+            let bit = self.data[self.position / 8] >> (7 - self.position % 8) & 1;
+            value = value << 1 | bit as u32;
+            self.position += 1;
+        }
+        Some(value)
+    }
+}
+
+/// The modules of a code being drawn or read, with the function patterns marked.
+#[derive(Clone)]
+struct Grid {
+    size: usize,
+    dark: Vec<bool>,
+    function: Vec<bool>,
+}
+
+impl Grid {
+    /// Draws the function patterns of a version: finders, timing, alignment, version
+    /// information, and room for the format information.
+    fn new(version: u8) -> Grid {
+        let size = 17 + 4 * version as usize;
+        let mut grid = Grid {
+            size,
+            dark: vec![false; size * size],
+            function: vec![false; size * size],
+        };
+        for i in 0..size {
+            grid.set_function(6, i, i % 2 == 0);
+            grid.set_function(i, 6, i % 2 == 0);
+        }
+        for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4isize..=4 {
+                for dx in -4isize..=4 {
+                    let (x, y) = (cx as isize + dx, cy as isize + dy);
+                    if (0..size as isize).contains(&x) && (0..size as isize).contains(&y) {
+                        let distance = dx.abs().max(dy.abs());
+                        grid.set_function(x as usize, y as usize, distance != 2 && distance != 4);
+                    }
+                }
+            }
+        }
+        let positions = alignment_positions(version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &cx) in positions.iter().enumerate() {
+            for (j, &cy) in positions.iter().enumerate() {
+                // The corners with finder patterns have no alignment pattern.
+                if (i, j) == (0, 0) || (i, j) == (0, last) || (i, j) == (last, 0) {
+                    continue;
+                }
+                for dy in -2isize..=2 {
+                    for dx in -2isize..=2 {
+                        grid.set_function(
+                            (cx as isize + dx) as usize,
+                            (cy as isize + dy) as usize,
+                            dx.abs().max(dy.abs()) != 1,
+                        );
+                    }
+                }
+            }
+        }
+        grid.draw_format(EcLevel::M, 0);
+        if version >= 7 {
+            let mut remainder = version as u32;
+            for _ in 0..12 {
+                remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+            }
+            let bits = (version as u32) << 12 | remainder;
+            for i in 0..18 {
+                let dark = bits >> i & 1 == 1;
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                grid.set_function(a, b, dark);
+                grid.set_function(b, a, dark);
+            }
+        }
+        grid
+    }
+
+    /// Sets a module of a function pattern.
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.dark[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    /// Draws both copies of the format information, and the dark module next to them.
+    fn draw_format(&mut self, level: EcLevel, mask: u8) {
+        let bits = format_bits(level, mask);
+        for copy in format_positions(self.size) {
+            for (i, (x, y)) in copy.into_iter().enumerate() {
+                self.set_function(x, y, bits >> i & 1 == 1);
+            }
+        }
+        self.set_function(8, self.size - 8, true);
+    }
+
+    /// Returns the data modules in placement order: two-module columns from the right,
+    /// zigzagging up and down, skipping the vertical timing pattern.
+    fn data_modules(&self) -> Vec<usize> {
+        let size = self.size;
+        let mut order = Vec::new();
+        let mut right = size - 1;
+        loop {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    if !self.function[y * size + x] {
+                        order.push(y * size + x);
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+        order
+    }
+
+    /// Places codewords in the data modules, leaving the remainder bits light.
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        for (i, index) in self.data_modules().into_iter().enumerate() {
+            if i < codewords.len() * 8 {
+                self.dark[index] = codewords[i / 8] >> (7 - i % 8) & 1 == 1;
+            }
+        }
+    }
+
+    /// Reads the codewords from the data modules.
+    fn read_codewords(&self) -> Vec<u8> {
+        let mut codewords = vec![0u8; raw_data_modules(((self.size - 17) / 4) as u8) / 8];
+        for (i, index) in self.data_modules().into_iter().enumerate() {
+            if i / 8 < codewords.len() && self.dark[index] {
+                codewords[i / 8] |= 0x80 >> (i % 8);
+            }
+        }
+        codewords
+    }
+
+    /// Flips the data modules selected by a mask pattern.
+    fn apply_mask(&mut self, mask: u8) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if !self.function[y * self.size + x] && mask_flips(mask, x, y) {
+                    self.dark[y * self.size + x] ^= true;
+                }
+            }
+        }
+    }
+
+    /// Scores how hard the code is to scan, with the four penalty rules of the standard.
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let at = |x: usize, y: usize| self.dark[y * size + x];
+        let mut penalty = 0;
+        let finder_like = [true, false, true, true, true, false, true];
+        for transposed in [false, true] {
+            for a in 0..size {
+                let line: Vec<bool> = (0..size)
+                    .map(|b| if transposed { at(a, b) } else { at(b, a) })
+                    .collect();
+                let mut run = 1;
+                for b in 1..=size {
+                    if b < size && line[b] == line[b - 1] {
+                        run += 1;
+                        continue;
+                    }
+                    if run >= 5 {
+                        penalty += run - 2;
+                    }
+                    run = 1;
+                }
+                // A finder-like pattern with four light modules on either side, the margin
+                // counting as light.
+                let light = |i: isize| i < 0 || i >= size as isize || !line[i as usize];
+                for start in 0..=size - 7 {
+                    if line[start..start + 7] != finder_like {
+                        continue;
+                    }
+                    let before = (1..=4).all(|k| light(start as isize - k));
+                    let after = (0..4).all(|k| light((start + 7 + k) as isize));
+                    if before || after {
+                        penalty += 40;
+                    }
+                }
+            }
+        }
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let dark = at(x, y);
+                if at(x + 1, y) == dark && at(x, y + 1) == dark && at(x + 1, y + 1) == dark {
+                    penalty += 3;
+                }
+            }
+        }
+        let dark = self.dark.iter().filter(|&&dark| dark).count();
+        let total = size * size;
+        // Each 5% away from half dark costs 10.
+        let deviation = (dark * 20).abs_diff(total * 10);
+        penalty + deviation.div_ceil(total).saturating_sub(1) * 10
+    }
+}
+
+/// Converts an image to 8-bit grey, compositing transparent pixels over white.
+fn greyscale(image: &PngImage) -> Vec<u8> {
+    let max = ((1u32 << image.bit_depth) - 1) as f64;
+    let channels = image.channels();
+    image
+        .samples
+        .chunks(channels)
+        .map(|pixel| {
+            let unit = |c: usize| pixel[c] as f64 / max;
+            let (grey, alpha) = match channels {
+                1 => (unit(0), 1.0),
+                2 => (unit(0), unit(1)),
+                3 => (0.299 * unit(0) + 0.587 * unit(1) + 0.114 * unit(2), 1.0),
+                _ => (0.299 * unit(0) + 0.587 * unit(1) + 0.114 * unit(2), unit(3)),
+            };
+            ((grey * alpha + 1.0 - alpha) * 255.0).round() as u8
+        })
+        .collect()
+}
+
+/// A finder pattern found in a picture.
+#[derive(Debug, Clone, Copy)]
+struct Finder {
+    x: f64,
+    y: f64,
+    /// Estimated module size in pixels.
+    module: f64,
+    /// Number of scan lines that found it.
+    hits: usize,
+}
+
+impl Finder {
+    /// Returns the distance to another finder.
+    fn distance(&self, other: &Finder) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// Ranks the triples of finder patterns by how well they form the corners of a code, each
+/// in the order top-left, top-right, bottom-left.
+fn pick_finders(finders: &[Finder]) -> Vec<[Finder; 3]> {
+    // The middle square is three modules high, so a real pattern is seen on more scan lines
+    // than it has pixels to a module, and a stray match in the data on far fewer.
+    let mut candidates: Vec<Finder> = finders
+        .iter()
+        .filter(|f| f.hits as f64 >= f.module.max(2.0))
+        .copied()
+        .collect();
+    candidates.sort_by_key(|f| std::cmp::Reverse(f.hits));
+    candidates.truncate(16);
+    let mut ranked: Vec<(f64, [Finder; 3])> = Vec::new();
+    for i in 0..candidates.len() {
+        for j in i + 1..candidates.len() {
+            for k in j + 1..candidates.len() {
+                let trio = [candidates[i], candidates[j], candidates[k]];
+                let modules = trio.map(|f| f.module);
+                let (min, max) = modules
+                    .iter()
+                    .fold((f64::MAX, 0.0f64), |(lo, hi), &m| (lo.min(m), hi.max(m)));
+                if max > min * 2.0 {
+                    continue;
+                }
+                // The corner is opposite the longest side.
+                let sides = [
+                    trio[1].distance(&trio[2]),
+                    trio[0].distance(&trio[2]),
+                    trio[0].distance(&trio[1]),
+                ];
+                let corner = (0..3)
+                    .max_by(|&a, &b| sides[a].total_cmp(&sides[b]))
+                    .unwrap();
+                let hypotenuse = sides[corner];
+                let (a, b) = (sides[(corner + 1) % 3], sides[(corner + 2) % 3]);
+                let modules_across = a.max(b) / (modules.iter().sum::<f64>() / 3.0);
+                if !(10.0..=180.0).contains(&modules_across) {
+                    continue;
+                }
+                let score = (hypotenuse * hypotenuse - a * a - b * b).abs()
+                    / (hypotenuse * hypotenuse)
+                    + (a - b).abs() / a.max(b)
+                    + (max - min) / max;
+                let top_left = trio[corner];
+                let mut top_right = trio[(corner + 1) % 3];
+                let mut bottom_left = trio[(corner + 2) % 3];
+                // With y pointing down, top-right to bottom-left turns clockwise.
+                let cross = (top_right.x - top_left.x) * (bottom_left.y - top_left.y)
+                    - (top_right.y - top_left.y) * (bottom_left.x - top_left.x);
+                if cross < 0.0 {
+                    std::mem::swap(&mut top_right, &mut bottom_left);
+                }
+                ranked.push((score, [top_left, top_right, bottom_left]));
+            }
+        }
+    }
+    ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+    ranked.into_iter().map(|(_, trio)| trio).collect()
+}
+
+/// A projective transform from module coordinates to pixels.
+struct Transform([f64; 8]);
+
+impl Transform {
+    /// Solves the transform mapping four points to four others.
+    fn from_points(from: [(f64, f64); 4], to: [(f64, f64); 4]) -> Option<Transform> {
+        let mut rows = [[0.0f64; 9]; 8];
+        for (i, ((x, y), (u, v))) in from.into_iter().zip(to).enumerate() {
+            rows[2 * i] = [x, y, 1.0, 0.0, 0.0, 0.0, -x * u, -y * u, u];
+            rows[2 * i + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -x * v, -y * v, v];
+        }
+        // Gaussian elimination with partial pivoting.
+        for column in 0..8 {
+            let pivot = (column..8)
+                .max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))?;
+            if rows[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            rows.swap(column, pivot);
+            for row in 0..8 {
+                if row != column {
+                    let factor = rows[row][column] / rows[column][column];
+                    let pivot = rows[column];
+                    for (value, &pivot) in rows[row].iter_mut().zip(&pivot).skip(column) {
+                        *value -= factor * pivot;
+                    }
+                }
+            }
+        }
+        let mut h = [0.0; 8];
+        for (i, value) in h.iter_mut().enumerate() {
+            *value = rows[i][8] / rows[i][i];
+        }
+        Some(Transform(h))
+    }
+
+    /// Maps a point in module coordinates to pixels.
+    fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let h = &self.0;
+        let w = h[6] * x + h[7] * y + 1.0;
+        (
+            (h[0] * x + h[1] * y + h[2]) / w,
+            (h[3] * x + h[4] * y + h[5]) / w,
+        )
+    }
+}
+
+/// A binarized picture, `true` for dark pixels.
+struct Binary {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+impl Binary {
+    /// Binarizes with Otsu's threshold over the whole picture.
+    fn global(grey: &[u8], width: usize, height: usize) -> Binary {
+        let mut histogram = [0usize; 256];
+        for &g in grey {
+            histogram[g as usize] += 1;
+        }
+        let total = grey.len() as f64;
+        let sum: f64 = histogram
+            .iter()
+            .enumerate()
+            .map(|(g, &n)| g as f64 * n as f64)
+            .sum();
+        let (mut below, mut below_sum) = (0.0, 0.0);
+        let (mut threshold, mut best) = (128, -1.0);
+        for (g, &n) in histogram.iter().enumerate() {
+            below += n as f64;
+            below_sum += g as f64 * n as f64;
+            if below == 0.0 || below == total {
+                continue;
+            }
+            let above = total - below;
+            let difference = below_sum / below - (sum - below_sum) / above;
+            let variance = below * above * difference * difference;
+            if variance > best {
+                best = variance;
+                threshold = g;
+            }
+        }
+        Binary {
+            width,
+            height,
+            dark: grey.iter().map(|&g| g as usize <= threshold).collect(),
+        }
+    }
+
+    /// Binarizes against the mean of a window around each pixel, for uneven lighting.
+    fn local(grey: &[u8], width: usize, height: usize) -> Binary {
+        let radius = (width.min(height) / 8).max(8);
+        let mut integral = vec![0u64; (width + 1) * (height + 1)];
+        for y in 0..height {
+            let mut row = 0u64;
+            for x in 0..width {
+                row += grey[y * width + x] as u64;
+                integral[(y + 1) * (width + 1) + x + 1] = integral[y * (width + 1) + x + 1] + row;
+            }
+        }
+        let dark = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(width));
+                let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(height));
+                let sum = integral[y1 * (width + 1) + x1] + integral[y0 * (width + 1) + x0]
+                    - integral[y0 * (width + 1) + x1]
+                    - integral[y1 * (width + 1) + x0];
+                let count = ((x1 - x0) * (y1 - y0)) as u64;
+                // A small margin keeps flat areas light.
+                (grey[i] as u64 + 8) * count < sum
+            })
+            .collect();
+        Binary {
+            width,
+            height,
+            dark,
+        }
+    }
+
+    /// Returns whether a pixel is dark, counting the outside as light.
+    fn is_dark_at(&self, x: f64, y: f64) -> bool {
+        if x < 0.0 || y < 0.0 || x >= self.width as f64 || y >= self.height as f64 {
+            return false;
+        }
+        self.dark[y as usize * self.width + x as usize]
+    }
+
+    /// Finds the finder patterns: runs of dark, light, dark, light and dark pixels in the
+    /// ratio 1:1:3:1:1 across, confirmed down, across again and diagonally through their
+    /// centre.
+    fn find_finders(&self) -> Vec<Finder> {
+        let mut finders: Vec<Finder> = Vec::new();
+        for y in 0..self.height {
+            let row = &self.dark[y * self.width..(y + 1) * self.width];
+            let mut runs: Vec<(bool, usize, usize)> = Vec::new();
+            for (x, &dark) in row.iter().enumerate() {
+                match runs.last_mut() {
+                    Some((colour, _, len)) if *colour == dark => *len += 1,
+                    _ => runs.push((dark, x, 1)),
+                }
+            }
+            for window in runs.windows(5) {
+                if !window[0].0 {
+                    continue;
+                }
+                let lens = [0, 1, 2, 3, 4].map(|i| window[i].2);
+                if !finder_ratio(lens) {
+                    continue;
+                }
+                let total: usize = lens.iter().sum();
+                let cx = window[2].1 as f64 + window[2].2 as f64 / 2.0;
+                let y = y as f64 + 0.5;
+                let Some((offset, vertical)) = self.cross_check(cx, y, (0.0, 1.0), Some(total))
+                else {
+                    continue;
+                };
+                let cy = y + offset;
+                // A ring can blur away on the centre row alone, so fall back to this row.
+                let (offset, horizontal) = self
+                    .cross_check(cx, cy, (1.0, 0.0), Some(total))
+                    .unwrap_or((0.0, total));
+                let cx = cx + offset;
+                // Any line through the centre of the nested squares crosses them in the same
+                // ratio, which a patch of data modules rarely manages on a diagonal too.
+                if self.cross_check(cx, cy, (1.0, 1.0), None).is_none() {
+                    continue;
+                }
+                let module = (vertical + horizontal) as f64 / 14.0;
+                match finders.iter_mut().find(|f| {
+                    (f.x - cx).abs() <= f.module.max(module)
+                        && (f.y - cy).abs() <= f.module.max(module)
+                        && (f.module - module).abs() <= f.module.max(1.0)
+                }) {
+                    Some(f) => {
+                        let n = f.hits as f64;
+                        f.x = (f.x * n + cx) / (n + 1.0);
+                        f.y = (f.y * n + cy) / (n + 1.0);
+                        f.module = (f.module * n + module) / (n + 1.0);
+                        f.hits += 1;
+                    }
+                    None => finders.push(Finder {
+                        x: cx,
+                        y: cy,
+                        module,
+                        hits: 1,
+                    }),
+                }
+            }
+        }
+        finders
+    }
+
+    /// Measures the finder pattern runs through a point along a direction, in steps of it.
+    ///
+    /// # Returns
+    ///
+    /// The offset of the centre of the middle run from the point and the total length of the
+    /// runs, or `None` if they aren't in the finder ratio or differ too much from `expected`.
+    fn cross_check(
+        &self,
+        x: f64,
+        y: f64,
+        (dx, dy): (f64, f64),
+        expected: Option<usize>,
+    ) -> Option<(f64, usize)> {
+        let at = |i: isize| -> Option<bool> {
+            let (px, py) = (x + i as f64 * dx, y + i as f64 * dy);
+            if px < 0.0 || py < 0.0 || px >= self.width as f64 || py >= self.height as f64 {
+                return None;
+            }
+            Some(self.is_dark_at(px, py))
+        };
+        if at(0) != Some(true) {
+            return None;
+        }
+        let max = expected.unwrap_or(self.width.max(self.height));
+        let mut lens = [0usize; 5];
+        let mut i = 0;
+        for (run, dark) in [(2, true), (1, false), (0, true)] {
+            while at(i) == Some(dark) && lens[run] < max {
+                lens[run] += 1;
+                i -= 1;
+            }
+        }
+        // The middle run covers the steps from `1 - behind` to `ahead`.
+        let behind = lens[2];
+        let mut i = 1;
+        for (run, dark) in [(2, true), (3, false), (4, true)] {
+            while at(i) == Some(dark) && lens[run] < max {
+                lens[run] += 1;
+                i += 1;
+            }
+        }
+        let total: usize = lens.iter().sum();
+        if !finder_ratio(lens)
+            || expected.is_some_and(|expected| 5 * total.abs_diff(expected) >= 2 * expected)
+        {
+            return None;
+        }
+        let ahead = lens[2] - behind;
+        Some(((ahead as f64 - behind as f64 + 1.0) / 2.0, total))
+    }
+
+    /// Measures the module size of a finder pattern along the line to another one, which
+    /// crosses its rings square on whatever the rotation of the code.
+    fn module_towards(&self, from: &Finder, to: &Finder) -> f64 {
+        let distance = from.distance(to);
+        let (dx, dy) = ((to.x - from.x) / distance, (to.y - from.y) / distance);
+        // The third edge from the centre is the outer edge of the pattern, 3.5 modules away.
+        let edge = |sign: f64| {
+            let mut dark = true;
+            let mut edges = 0;
+            let mut t = 0.0;
+            while t < from.module * 8.0 {
+                if self.is_dark_at(from.x + sign * t * dx, from.y + sign * t * dy) != dark {
+                    dark = !dark;
+                    edges += 1;
+                    if edges == 3 {
+                        return Some(t);
+                    }
+                }
+                t += 0.5;
+            }
+            None
+        };
+        match (edge(1.0), edge(-1.0)) {
+            (Some(a), Some(b)) => (a + b) / 7.0,
+            (Some(a), None) | (None, Some(a)) => a / 3.5,
+            (None, None) => from.module,
+        }
+    }
+
+    /// Estimates the version of a code from the distance between its finder patterns.
+    fn estimate_version(&self, top_left: &Finder, top_right: &Finder, bottom_left: &Finder) -> u8 {
+        let across = |a: &Finder, b: &Finder| {
+            a.distance(b) / ((self.module_towards(a, b) + self.module_towards(b, a)) / 2.0)
+        };
+        let dimension = (across(top_left, top_right) + across(top_left, bottom_left)) / 2.0 + 7.0;
+        ((dimension - 17.0) / 4.0).round().clamp(1.0, 40.0) as u8
+    }
+
+    /// Builds the transform from module coordinates to pixels for a version, from the
+    /// finder patterns and, where the version has one, the bottom-right alignment pattern.
+    fn transform(
+        &self,
+        version: u8,
+        top_left: &Finder,
+        top_right: &Finder,
+        bottom_left: &Finder,
+    ) -> Transform {
+        let size = 17 + 4 * version as usize;
+        let far = size as f64 - 3.5;
+        let span = size as f64 - 7.0;
+        let step_x = (
+            (top_right.x - top_left.x) / span,
+            (top_right.y - top_left.y) / span,
+        );
+        let step_y = (
+            (bottom_left.x - top_left.x) / span,
+            (bottom_left.y - top_left.y) / span,
+        );
+        let affine = |mx: f64, my: f64| {
+            (
+                top_left.x + (mx - 3.5) * step_x.0 + (my - 3.5) * step_y.0,
+                top_left.y + (mx - 3.5) * step_x.1 + (my - 3.5) * step_y.1,
+            )
+        };
+        let mut fourth = ((far, far), affine(far, far));
+        if version >= 2 {
+            let centre = size as f64 - 6.5;
+            if let Some(found) = self.find_alignment(affine(centre, centre), step_x, step_y) {
+                fourth = ((centre, centre), found);
+            }
+        }
+        let from = [(3.5, 3.5), (far, 3.5), (3.5, far), fourth.0];
+        let to = [
+            (top_left.x, top_left.y),
+            (top_right.x, top_right.y),
+            (bottom_left.x, bottom_left.y),
+            fourth.1,
+        ];
+        Transform::from_points(from, to).unwrap_or(Transform([
+            step_x.0,
+            step_y.0,
+            top_left.x - 3.5 * (step_x.0 + step_y.0),
+            step_x.1,
+            step_y.1,
+            top_left.y - 3.5 * (step_x.1 + step_y.1),
+            0.0,
+            0.0,
+        ]))
+    }
+
+    /// Searches around an estimate for the 5x5 alignment pattern, a dark ring around a light
+    /// ring around a dark module, sampled along the module steps of the code.
+    ///
+    /// Perspective can move the pattern a long way from where the finder patterns alone put
+    /// it, so the search reaches twelve modules out and prefers the best match nearest the
+    /// estimate.
+    fn find_alignment(
+        &self,
+        estimate: (f64, f64),
+        step_x: (f64, f64),
+        step_y: (f64, f64),
+    ) -> Option<(f64, f64)> {
+        let module = step_x.0.hypot(step_x.1).max(step_y.0.hypot(step_y.1));
+        // A quarter module apart is fine enough to land inside the middle module.
+        let stride = (module / 4.0).max(1.0);
+        let radius = (module * 12.0 / stride).ceil() as isize;
+        let mut matches = Vec::new();
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let (dx, dy) = (dx as f64 * stride, dy as f64 * stride);
+                let (cx, cy) = (estimate.0 + dx, estimate.1 + dy);
+                let mut score = 0;
+                for j in -2isize..=2 {
+                    for i in -2isize..=2 {
+                        let x = cx + i as f64 * step_x.0 + j as f64 * step_y.0;
+                        let y = cy + i as f64 * step_x.1 + j as f64 * step_y.1;
+                        if self.is_dark_at(x, y) == (i.abs().max(j.abs()) != 1) {
+                            score += 1;
+                        }
+                    }
+                }
+                if score >= 23 {
+                    matches.push((score, dx.hypot(dy), cx, cy));
+                }
+            }
+        }
+        let &(score, _, x, y) = matches
+            .iter()
+            .max_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)))?;
+        // Average the equally good positions around it for the centre.
+        let centre: Vec<_> = matches
+            .iter()
+            .filter(|m| m.0 == score && (m.2 - x).hypot(m.3 - y) <= module)
+            .collect();
+        let n = centre.len() as f64;
+        Some((
+            centre.iter().map(|m| m.2).sum::<f64>() / n,
+            centre.iter().map(|m| m.3).sum::<f64>() / n,
+        ))
+    }
+}
+
+/// Returns whether run lengths are in the 1:1:3:1:1 ratio of a finder pattern.
+///
+/// Blur and uneven lighting grow the dark runs at the expense of the light ones, so
+/// neighbouring runs are measured in pairs, from one dark-to-light edge to the next, which
+/// the threshold shifts together.
+fn finder_ratio(lens: [usize; 5]) -> bool {
+    let total: usize = lens.iter().sum();
+    if total < 7 || lens.contains(&0) {
+        return false;
+    }
+    let module = total as f64 / 7.0;
+    (lens[2] as f64 - 3.0 * module).abs() < 1.5 * module
+        && lens
+            .windows(2)
+            .zip([2.0, 4.0, 4.0, 2.0])
+            .all(|(pair, ratio)| ((pair[0] + pair[1]) as f64 - ratio * module).abs() < module)
+}
@@ -0,0 +1,224 @@
+use crate::png::PngImage;
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+/// Side of the square windows SSIM is computed over.
+const WINDOW: usize = 8;
+
+/// Step between two SSIM windows, so that they overlap by half.
+const WINDOW_STEP: usize = 4;
+
+/// How far a stego image is from its carrier, as seen by the eye.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityMetrics {
+    /// Peak signal-to-noise ratio of the colour samples, in dB; infinite for identical images.
+    pub psnr: f64,
+    /// Mean structural similarity of the luma, from -1 to 1, where 1 means identical.
+    pub ssim: f64,
+}
+
+impl fmt::Display for QualityMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.psnr.is_finite() {
+            write!(f, "PSNR {:.1} dB, SSIM {:.4}", self.psnr, self.ssim)
+        } else {
+            write!(f, "PSNR infinite (identical pixels), SSIM {:.4}", self.ssim)
+        }
+    }
+}
+
+/// The most distortion `encrypt --max-distortion` accepts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaxDistortion {
+    /// The lowest PSNR accepted, in dB.
+    Psnr(f64),
+    /// The lowest SSIM accepted.
+    Ssim(f64),
+}
+
+impl MaxDistortion {
+    /// Tells whether an image with these metrics is distorted beyond the limit.
+    pub fn exceeded_by(&self, metrics: &QualityMetrics) -> bool {
+        match *self {
+            MaxDistortion::Psnr(min) => metrics.psnr < min,
+            MaxDistortion::Ssim(min) => metrics.ssim < min,
+        }
+    }
+}
+
+impl fmt::Display for MaxDistortion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaxDistortion::Psnr(min) => write!(f, "PSNR {} dB", min),
+            MaxDistortion::Ssim(min) => write!(f, "SSIM {}", min),
+        }
+    }
+}
+
+/// Parses the value of `--max-distortion`: the lowest PSNR accepted, such as `40dB` or `40`,
+/// or the lowest SSIM, such as `ssim:0.99`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::quality::{parse_max_distortion, MaxDistortion};
+///
+/// assert_eq!(parse_max_distortion("40dB").unwrap(), MaxDistortion::Psnr(40.0));
+/// assert_eq!(parse_max_distortion("ssim:0.99").unwrap(), MaxDistortion::Ssim(0.99));
+/// assert!(parse_max_distortion("ssim:2").is_err());
+/// ```
+pub fn parse_max_distortion(value: &str) -> Result<MaxDistortion, String> {
+    let value = value.trim();
+    if let Some(ssim) = value.strip_prefix("ssim:") {
+        return match ssim.parse::<f64>() {
+            Ok(ssim) if (-1.0..=1.0).contains(&ssim) => Ok(MaxDistortion::Ssim(ssim)),
+            _ => Err(format!(
+                "invalid SSIM '{}', expected a number from -1 to 1",
+                ssim
+            )),
+        };
+    }
+    let psnr = value
+        .strip_suffix("dB")
+        .or_else(|| value.strip_suffix("db"))
+        .unwrap_or(value);
+    match psnr.trim().parse::<f64>() {
+        Ok(psnr) if psnr.is_finite() && psnr >= 0.0 => Ok(MaxDistortion::Psnr(psnr)),
+        _ => Err(format!(
+            "invalid distortion '{}', expected a PSNR such as 40dB or an SSIM such as ssim:0.99",
+            value
+        )),
+    }
+}
+
+/// Converts the samples of an image to 8-bit units, dropping alpha.
+fn colour_samples(image: &PngImage) -> Vec<f64> {
+    let scale = 255.0 / ((1u32 << image.bit_depth) - 1) as f64;
+    let channels = image.channels();
+    // Grey + alpha and RGBA end with alpha.
+    let colours = channels - matches!(image.color_type, 4 | 6) as usize;
+    image
+        .samples
+        .chunks(channels)
+        .flat_map(|pixel| pixel[..colours].iter())
+        .map(|&sample| sample as f64 * scale)
+        .collect()
+}
+
+/// Returns the luma plane of colour samples from `colour_samples`, in 8-bit units.
+fn luma_plane(samples: &[f64], colours: usize) -> Vec<f64> {
+    if colours == 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks(colours)
+        .map(|rgb| 0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2])
+        .collect()
+}
+
+/// Returns the SSIM of one window of two luma planes.
+fn window_ssim(
+    a: &[f64],
+    b: &[f64],
+    width: usize,
+    (x0, y0): (usize, usize),
+    size: (usize, usize),
+) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+    let n = (size.0 * size.1) as f64;
+    let (mut sa, mut sb, mut saa, mut sbb, mut sab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for y in y0..y0 + size.1 {
+        for x in x0..x0 + size.0 {
+            let (va, vb) = (a[y * width + x], b[y * width + x]);
+            sa += va;
+            sb += vb;
+            saa += va * va;
+            sbb += vb * vb;
+            sab += va * vb;
+        }
+    }
+    let (ma, mb) = (sa / n, sb / n);
+    let (vara, varb, cov) = (saa / n - ma * ma, sbb / n - mb * mb, sab / n - ma * mb);
+    ((2.0 * ma * mb + C1) * (2.0 * cov + C2)) / ((ma * ma + mb * mb + C1) * (vara + varb + C2))
+}
+
+/// Measures how far a stego image is from its carrier.
+///
+/// The PSNR compares every colour sample, alpha aside, in 8-bit units. The SSIM is the mean
+/// over 8x8 windows of the luma, overlapping by half, with the constants of Wang et al.
+/// Around 40 dB and 0.98 the changes are invisible to most eyes; methods that only add
+/// chunks or bytes leave the pixels alone and score an infinite PSNR and an SSIM of 1.
+///
+/// # Arguments
+///
+/// * `original` - The carrier PNG.
+/// * `stego` - The PNG with the payload.
+///
+/// # Returns
+///
+/// A `Result` containing the metrics, or an `InvalidInput` error if the images differ in
+/// size or colour type.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::lsb::embed_lsb;
+/// use stegano::png::encode_grayscale;
+/// use stegano::quality::quality_metrics;
+///
+/// let pixels: Vec<u8> = (0..64 * 64).map(|i| (i % 64 * 4) as u8).collect();
+/// let carrier = encode_grayscale(64, 64, &pixels).unwrap();
+/// let stego = embed_lsb(&carrier, b"hidden", "key").unwrap();
+///
+/// let metrics = quality_metrics(&carrier, &stego).unwrap();
+/// assert!(metrics.psnr > 50.0 && metrics.ssim > 0.99);
+/// assert!(quality_metrics(&carrier, &carrier).unwrap().psnr.is_infinite());
+/// ```
+pub fn quality_metrics(original: &[u8], stego: &[u8]) -> io::Result<QualityMetrics> {
+    let original = PngImage::decode(original)?;
+    let stego = PngImage::decode(stego)?;
+    if (original.width, original.height, original.color_type)
+        != (stego.width, stego.height, stego.color_type)
+    {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The images differ in size or colour type",
+        ));
+    }
+    let (a, b) = (colour_samples(&original), colour_samples(&stego));
+    if a.is_empty() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The images are empty",
+        ));
+    }
+    let mse = a
+        .iter()
+        .zip(&b)
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f64>()
+        / a.len() as f64;
+    let psnr = if mse == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (255.0 * 255.0 / mse).log10()
+    };
+
+    let colours = a.len() / (original.width * original.height);
+    let (a, b) = (luma_plane(&a, colours), luma_plane(&b, colours));
+    let (width, height) = (original.width, original.height);
+    let size = (WINDOW.min(width), WINDOW.min(height));
+    let starts = |len: usize, size: usize| (0..=len - size).step_by(WINDOW_STEP);
+    let (mut total, mut windows) = (0.0, 0);
+    for y in starts(height, size.1) {
+        for x in starts(width, size.0) {
+            total += window_ssim(&a, &b, width, (x, y), size);
+            windows += 1;
+        }
+    }
+    Ok(QualityMetrics {
+        psnr,
+        ssim: total / windows as f64,
+    })
+}
@@ -0,0 +1,272 @@
+use crate::append::{find_frame_before, write_appended, write_appended_linked};
+use crate::codec::{embed_encrypted, extract_encrypted, CodecOptions};
+use crate::disguise::{embed_disguised, extract_disguised};
+use crate::header::{kdf_for, open_payload, open_precision, payload_tag, precision_tag};
+use crate::hint::{hint_frame, split_hint};
+use crate::lsb::{embed_lsb_with, lsb_layout, Channels, LsbOptions};
+use crate::png::{insert_chunk, read_chunks, PngImage};
+use crate::prng::Prng;
+use crate::robust::{embed_robust, robust_capacity};
+use crate::shamir::SHARE_MAGIC;
+use crate::shard::SHARD_MAGIC;
+use crate::tiff::{TiffFile, PAYLOAD_TAG};
+use crate::utils::{encrypt_stream_mode, CipherMode, Padding, DEFAULT_BLOCK_MODE};
+use sha2::{Digest, Sha256};
+use std::io::{self, Cursor, ErrorKind};
+
+/// Re-encrypts the payload of a stego file with a new key or algorithm and embeds it again
+/// where it was.
+///
+/// The payload is extracted with `opts`, decrypted with the old key, encrypted with the new
+/// one and written back with the same placement: the same chunk name and offset for PNG
+/// chunks, the same tag or strip for TIFF, the same slack space for ICO, and the same frame,
+/// provenance link included, for appended payloads. The plaintext hint and the payload
+/// header are kept, the header naming the new algorithm; legacy payloads written before the
+/// header stay without one. With the LSB and robust methods, the pixels or blocks chosen by
+/// the old key are overwritten with noise first, so the old key no longer reads the payload.
+///
+/// A wrong old key is refused when the payload has a signed header, and by the check byte of
+/// precision payloads, which lets one wrong key in 256 through. Legacy payloads carry no
+/// checksum, so there it re-encrypts garbage and the payload is lost. Keep the original until
+/// the new key has been checked.
+///
+/// # Arguments
+///
+/// * `stego` - The bytes of the stego file.
+/// * `opts` - The options the payload was embedded with, including the old key.
+/// * `new_algorithm` - The algorithm to re-encrypt with, aes or xor.
+/// * `new_key` - The key to re-encrypt with.
+///
+/// # Returns
+///
+/// A `Result` containing the bytes of the re-keyed stego file, or an `InvalidInput` error for
+/// shards, which can only be decrypted once joined.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::codec::{decode_bytes, encode_bytes, CodecOptions};
+/// use stegano::png::{encode_grayscale, read_chunks};
+/// use stegano::rekey::rekey_bytes;
+///
+/// let carrier = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let opts = CodecOptions::default();
+/// let stego = encode_bytes(&carrier, b"hello", &opts).unwrap();
+///
+/// let rekeyed = rekey_bytes(&stego, &opts, "xor", "new key").unwrap();
+/// let new_opts = CodecOptions {
+///     algorithm: "xor".to_string(),
+///     key: "new key".to_string(),
+///     ..CodecOptions::default()
+/// };
+/// assert_eq!(decode_bytes(&rekeyed, &new_opts).unwrap().payload, b"hello");
+/// let offset = |data: &[u8]| read_chunks(data).unwrap().iter().position(|c| &c.r#type == b"stEg");
+/// assert_eq!(offset(&rekeyed), offset(&stego));
+/// ```
+pub fn rekey_bytes(
+    stego: &[u8],
+    opts: &CodecOptions,
+    new_algorithm: &str,
+    new_key: &str,
+) -> io::Result<Vec<u8>> {
+    let (encrypted, carrier) = extract_encrypted(stego, opts)?;
+    let (hint, body) = split_hint(&encrypted);
+    if body.starts_with(SHARD_MAGIC) || encrypted.starts_with(SHARE_MAGIC) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "Shards and shares can't be re-keyed one by one, join them with `decrypt --join` first",
+        ));
+    }
+    let mut reencrypted = match hint {
+        Some(hint) => hint_frame(hint)?,
+        None => Vec::new(),
+    };
+    // The header keeps its creation time, expiry, view counter, MIME type and filename, with
+    // the new algorithm and signed with the new key.
+    let sealed_from = reencrypted.len();
+    let mut sealed = false;
+    let mut mode = CipherMode::Ecb;
+    let mut padding = Padding::Zeros;
+    let payload = if opts.method.eq_ignore_ascii_case("precision") {
+        open_precision(body, &opts.algorithm, &opts.key)?
+    } else {
+        // Legacy payloads stay without header, so they still fit where they were.
+        let (header, payload) = open_payload(body, &opts.algorithm, &opts.key)?;
+        if let Some(mut header) = header {
+            let views = header.views;
+            header = header.with_views(views);
+            header.algorithm = new_algorithm.to_lowercase();
+            header.kdf = kdf_for(new_algorithm);
+            header.mode = CipherMode::Ecb;
+            header.padding = Padding::Pkcs7;
+            let header = header.with_mode(DEFAULT_BLOCK_MODE)?;
+            mode = header.mode;
+            padding = header.padding;
+            reencrypted.extend_from_slice(&header.to_bytes(new_key)?);
+            sealed = true;
+        }
+        payload
+    };
+    encrypt_stream_mode(
+        new_algorithm,
+        new_key,
+        &mode,
+        padding,
+        opts.cancel.reader(&payload[..]),
+        &mut reencrypted,
+    )?;
+    if sealed {
+        let tag = payload_tag(new_key, &reencrypted[sealed_from..])?;
+        reencrypted.extend_from_slice(&tag);
+    } else if opts.method.eq_ignore_ascii_case("precision") {
+        let check = precision_tag(new_key, &reencrypted[sealed_from..]);
+        reencrypted.extend_from_slice(&check);
+    }
+    opts.cancel.check()?;
+
+    let new_opts = CodecOptions {
+        algorithm: new_algorithm.to_string(),
+        key: new_key.to_string(),
+        ..opts.clone()
+    };
+    let rekeyed = put_back(stego, &carrier, &encrypted, &reencrypted, opts, &new_opts)?;
+    // Read the new payload back before handing the file over.
+    if extract_encrypted(&rekeyed, &new_opts)?.0 != reencrypted {
+        return Err(io::Error::other(
+            "The re-keyed payload doesn't read back, the file was left as it was",
+        ));
+    }
+    Ok(rekeyed)
+}
+
+/// Embeds a new payload where the old one was, as `rekey_bytes` and `burn::burn_bytes` do.
+///
+/// # Arguments
+///
+/// * `stego` - The bytes of the stego file.
+/// * `carrier` - The carrier `extract_encrypted` left once the old payload was taken out.
+/// * `encrypted` - The old payload, as extracted.
+/// * `replacement` - The new payload.
+/// * `opts` - The options the old payload was embedded with.
+/// * `new_opts` - The options to embed the new payload with.
+pub(crate) fn put_back(
+    stego: &[u8],
+    carrier: &[u8],
+    encrypted: &[u8],
+    replacement: &[u8],
+    opts: &CodecOptions,
+    new_opts: &CodecOptions,
+) -> io::Result<Vec<u8>> {
+    let method = opts.method.to_lowercase();
+    Ok(
+        match (opts.file_type.to_lowercase().as_str(), method.as_str()) {
+            (_, "append") => reappend(stego, replacement)?,
+            ("png", "auto" | "chunk" | "apng") => {
+                // Put the chunk back at its old offset rather than before IEND.
+                let offset = read_chunks(stego)?
+                    .iter()
+                    .find(|chunk| chunk.r#type.bytes() == opts.chunk_name.as_bytes())
+                    .map(|chunk| chunk.offset);
+                let name = opts.chunk_name.as_bytes().try_into().unwrap();
+                insert_chunk(carrier, &name, replacement, offset)?.0
+            }
+            ("png", "lsb" | "robust") => {
+                // Keep the traversal the payload was found in.
+                let mut new_opts = new_opts.clone();
+                if method == "lsb" {
+                    let channels = Channels::new(&opts.channels)?;
+                    new_opts.traversal = lsb_layout(stego, &opts.key, channels)?
+                        .traversal
+                        .name()
+                        .to_string();
+                }
+                embed_encrypted(
+                    &scrub(carrier, encrypted, replacement, opts)?,
+                    replacement,
+                    &new_opts,
+                )?
+            }
+            ("tiff", _) => {
+                let tiff = TiffFile::parse(stego.to_vec())?;
+                let in_tag = tiff
+                    .ifds
+                    .first()
+                    .is_some_and(|ifd| ifd.entries.iter().any(|entry| entry.tag == PAYLOAD_TAG));
+                let clean = TiffFile::parse(carrier.to_vec())?;
+                if in_tag {
+                    clean.embed_in_tag(replacement)?.0
+                } else {
+                    clean.embed_in_strip(replacement)
+                }
+            }
+            ("png", "disguise") => {
+                // Keep the template the payload was found in.
+                let (_, _, disguise) = extract_disguised(stego)?;
+                embed_disguised(carrier, replacement, disguise)?
+            }
+            _ => embed_encrypted(carrier, replacement, new_opts)?,
+        },
+    )
+}
+
+/// Overwrites the pixels or blocks holding an LSB or robust payload with noise.
+///
+/// Only the key the payload was embedded with knows which ones to scrub, and `extract_encrypted`
+/// leaves them as they are. Other carriers are returned as they are, the payload already taken
+/// out of them.
+///
+/// # Arguments
+///
+/// * `carrier` - The carrier `extract_encrypted` left.
+/// * `encrypted` - The payload, as extracted.
+/// * `seed` - Bytes mixed into the seed of the noise with the payload.
+/// * `opts` - The options the payload was embedded with.
+pub(crate) fn scrub(
+    carrier: &[u8],
+    encrypted: &[u8],
+    seed: &[u8],
+    opts: &CodecOptions,
+) -> io::Result<Vec<u8>> {
+    let seed = Sha256::digest([encrypted, seed].concat());
+    let mut prng = Prng::new(u64::from_be_bytes(seed[..8].try_into().unwrap()));
+    match (
+        opts.file_type.to_lowercase().as_str(),
+        opts.method.to_lowercase().as_str(),
+    ) {
+        ("png", "lsb") => {
+            let mut noise = vec![0u8; encrypted.len()];
+            prng.fill(&mut noise);
+            // The noise must cover the same samples and bits as the payload.
+            let channels = Channels::new(&opts.channels)?;
+            let layout = lsb_layout(carrier, &opts.key, channels)?;
+            let lsb = LsbOptions {
+                traversal: layout.traversal,
+                channels,
+                bits: Some(layout.bits),
+            };
+            embed_lsb_with(carrier, &noise, &opts.key, lsb)
+        }
+        ("png", "robust") => {
+            let mut noise = vec![0u8; robust_capacity(&PngImage::decode(carrier)?)];
+            prng.fill(&mut noise);
+            embed_robust(carrier, &noise, &opts.key)
+        }
+        _ => Ok(carrier.to_vec()),
+    }
+}
+
+/// Replaces the last appended frame of a file, keeping its provenance link and whatever
+/// follows it.
+fn reappend(stego: &[u8], payload: &[u8]) -> io::Result<Vec<u8>> {
+    let frame = find_frame_before(&mut Cursor::new(stego), stego.len() as u64)?
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "No appended payload found"))?;
+    let carrier = &stego[..frame.offset as usize];
+    let mut out = Vec::with_capacity(stego.len() + payload.len());
+    if frame.parent.is_some() {
+        write_appended_linked(carrier, payload, payload.len() as u64, &mut out)?;
+    } else {
+        write_appended(carrier, payload, payload.len() as u64, &mut out)?;
+    }
+    out.extend_from_slice(&stego[frame.end as usize..]);
+    Ok(out)
+}
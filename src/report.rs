@@ -0,0 +1,678 @@
+use crate::apng::{parse_animation, Animation};
+use crate::cli::EncryptCmd;
+use crate::diff::{ChunkDiff, PixelDiff};
+use crate::encoding::PayloadEncoding;
+use crate::flac::FlacFile;
+use crate::ico::IcoFile;
+use crate::jpeg::codes::HuffmanReport;
+use crate::jpeg::markers::MarkerInventory;
+use crate::jpeg::quant::{EncoderHints, QuantSource, QuantTable};
+use crate::json::JsonValue;
+use crate::models::{summarize_chunks, ChunkSummary};
+use crate::mp3::{describe_frame, Mp3File};
+use crate::mp4::{Mp4Box, Mp4File};
+use crate::png::{read_chunks, ChunkFilter, Ihdr};
+use crate::quality::QualityMetrics;
+use crate::storage::{is_remote, map_input};
+use crate::svg::{SvgFile, SvgMethod};
+use crate::text::{TextFile, TextMethod};
+use crate::tiff::{tag_name, type_name, Ifd, TiffFile};
+use crate::trailing::{find_trailing, Trailing, PREVIEW_LEN};
+use std::fs;
+use std::io;
+use std::time::SystemTime;
+
+/// Describes a file on disk: its path, size and last modification time. Remote objects only
+/// get their URL, with a null size and time.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::report::file_info;
+///
+/// std::fs::write("info.bin", [0u8; 12]).unwrap();
+/// let info = file_info("info.bin").unwrap().to_string();
+/// assert!(info.contains(r#""size":{"value":12,"unit":"bytes"}"#));
+/// std::fs::remove_file("info.bin").unwrap();
+/// let remote = file_info("s3://bucket/info.bin").unwrap().to_string();
+/// assert!(remote.contains(r#""size":null"#));
+/// ```
+pub fn file_info(path: &str) -> io::Result<JsonValue> {
+    if is_remote(path) {
+        return Ok(JsonValue::object([
+            ("path", JsonValue::from(path)),
+            ("size", JsonValue::Null),
+            ("modified", JsonValue::Null),
+        ]));
+    }
+    let metadata = fs::metadata(path)?;
+    Ok(JsonValue::object([
+        ("path", JsonValue::from(path)),
+        ("size", JsonValue::size(metadata.len())),
+        (
+            "modified",
+            metadata
+                .modified()
+                .map_or(JsonValue::Null, JsonValue::timestamp),
+        ),
+    ]))
+}
+
+/// Describes an output of `encrypt` like `file_info`, from its expected size when a dry run
+/// didn't write it or it was stored remotely.
+fn output_info(cmd: &EncryptCmd, path: &str, len: u64) -> io::Result<JsonValue> {
+    if !cmd.dry_run && !is_remote(path) {
+        return file_info(path);
+    }
+    Ok(JsonValue::object([
+        ("path", JsonValue::from(path)),
+        ("size", JsonValue::size(len)),
+        ("modified", JsonValue::Null),
+    ]))
+}
+
+/// Builds the JSON document printed by `show-meta --json`.
+///
+/// # Arguments
+///
+/// * `path` - The file to describe.
+/// * `file_type` - The file type: png, jpeg, tiff, ico, mp4, mp3, flac, svg or text.
+/// * `filter` - The PNG chunks to list, from `--filter` and `--grep`.
+///
+/// # Returns
+///
+/// A `Result` containing the document, or an `io::Error` if the file can't be read or parsed.
+pub fn show_meta_report(
+    path: &str,
+    file_type: &str,
+    filter: &ChunkFilter,
+) -> io::Result<JsonValue> {
+    let data = map_input(path)?;
+    let file_type = file_type.to_lowercase();
+    let animation = match file_type.as_str() {
+        "png" => Some(parse_animation(&read_chunks(&data)?)?),
+        _ => None,
+    };
+    let (key, items) = match file_type.as_str() {
+        "png" => ("chunks", png_chunks(&data, filter)?),
+        "jpeg" | "jpg" => ("segments", jpeg_segments(&data)?),
+        "tiff" => ("ifds", tiff_ifds(&TiffFile::parse_ifds(&data)?.1)),
+        "ico" => ("images", ico_images(&IcoFile::parse(data.to_vec())?)),
+        "mp4" => ("boxes", mp4_boxes(&Mp4File::parse(&data)?.boxes)),
+        "mp3" => ("frames", mp3_frames(&Mp3File::parse(&data)?)),
+        "flac" => ("blocks", flac_blocks(&FlacFile::parse(&data)?)),
+        "svg" => ("methods", svg_methods(&SvgFile::parse(&data)?)),
+        "text" => ("methods", text_methods(&TextFile::parse(&data)?)),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unsupported type: {}", file_type),
+            ))
+        }
+    };
+    let mut report = vec![
+        ("schema", JsonValue::from("stegano.show-meta/1")),
+        ("generated_at", JsonValue::timestamp(SystemTime::now())),
+        ("file", file_info(path)?),
+        ("type", JsonValue::from(file_type.clone())),
+        (key, JsonValue::Array(items)),
+    ];
+    if file_type == "png" {
+        report.push(("ihdr", Ihdr::read(&data).map_or(JsonValue::Null, ihdr_info)));
+        report.push(("summary", chunk_summary(&summarize_chunks(&data)?)));
+    }
+    if matches!(file_type.as_str(), "jpeg" | "jpg") {
+        report.push(("quantization", quantization_info(&data)?));
+        report.push(("huffman", huffman_info(&data)?));
+    }
+    // Only PNG and JPEG files end with a terminator, and those ending with it say so with null.
+    if matches!(file_type.as_str(), "png" | "jpeg" | "jpg") {
+        report.push((
+            "trailing",
+            find_trailing(&data, &file_type)?.map_or(JsonValue::Null, trailing_info),
+        ));
+    }
+    // Only PNG files can be animated, and still ones say so with null.
+    if let Some(animation) = animation {
+        report.push((
+            "animation",
+            animation.map_or(JsonValue::Null, animation_info),
+        ));
+    }
+    Ok(JsonValue::object(report))
+}
+
+/// Describes the image properties recorded in the IHDR chunk of a PNG file.
+fn ihdr_info(ihdr: Ihdr) -> JsonValue {
+    JsonValue::object([
+        ("width", JsonValue::from(u64::from(ihdr.width))),
+        ("height", JsonValue::from(u64::from(ihdr.height))),
+        ("bit_depth", JsonValue::from(u64::from(ihdr.bit_depth))),
+        ("color_type", JsonValue::from(u64::from(ihdr.color_type))),
+        ("color_type_name", JsonValue::from(ihdr.color_type_name())),
+        ("compression", JsonValue::from(u64::from(ihdr.compression))),
+        ("filter", JsonValue::from(u64::from(ihdr.filter))),
+        ("interlace", JsonValue::from(u64::from(ihdr.interlace))),
+    ])
+}
+
+/// Describes the counts of the chunks of a PNG file.
+fn chunk_summary(summary: &ChunkSummary) -> JsonValue {
+    let unknown_types = summary
+        .unknown_types
+        .iter()
+        .map(|r#type| JsonValue::from(r#type.to_string()))
+        .collect();
+    let order_violations = summary
+        .order_violations
+        .iter()
+        .map(|violation| {
+            JsonValue::object([
+                ("index", JsonValue::from(violation.index as u64)),
+                ("type", JsonValue::from(violation.r#type.to_string())),
+                ("problem", JsonValue::from(violation.problem)),
+            ])
+        })
+        .collect();
+    JsonValue::object([
+        ("chunks", JsonValue::from(summary.chunks as u64)),
+        ("unknown_types", JsonValue::Array(unknown_types)),
+        ("crc_errors", JsonValue::from(summary.crc_errors as u64)),
+        ("truncated", JsonValue::from(summary.truncated)),
+        ("capped", JsonValue::from(summary.capped)),
+        ("order_violations", JsonValue::Array(order_violations)),
+    ])
+}
+
+/// Describes the bytes after the terminator of an image, with their entropy in thousandths of
+/// a bit per byte and their first bytes in hex.
+fn trailing_info(trailing: Trailing) -> JsonValue {
+    let preview = &trailing.data[..trailing.data.len().min(PREVIEW_LEN)];
+    JsonValue::object([
+        ("terminator", JsonValue::from(trailing.terminator)),
+        ("offset", JsonValue::from(trailing.offset as u64)),
+        ("size", JsonValue::size(trailing.data.len() as u64)),
+        (
+            "entropy",
+            JsonValue::object([
+                (
+                    "value",
+                    JsonValue::from((trailing.entropy() * 1000.0).round() as u64),
+                ),
+                ("unit", JsonValue::from("millibits per byte")),
+            ]),
+        ),
+        (
+            "preview",
+            JsonValue::from(
+                String::from_utf8_lossy(&PayloadEncoding::Hex.encode(preview)).into_owned(),
+            ),
+        ),
+    ])
+}
+
+/// Describes an APNG animation and its frames, with delays in milliseconds.
+fn animation_info(animation: Animation) -> JsonValue {
+    let frames = animation
+        .frames
+        .iter()
+        .map(|frame| {
+            JsonValue::object([
+                ("sequence", JsonValue::from(frame.sequence as u64)),
+                ("offset", JsonValue::from(frame.offset as u64)),
+                ("width", JsonValue::from(frame.width as u64)),
+                ("height", JsonValue::from(frame.height as u64)),
+                ("x_offset", JsonValue::from(frame.x_offset as u64)),
+                ("y_offset", JsonValue::from(frame.y_offset as u64)),
+                ("delay", milliseconds(frame.delay_ms())),
+                ("dispose_op", JsonValue::from(frame.dispose_op as u64)),
+                ("blend_op", JsonValue::from(frame.blend_op as u64)),
+                ("data_chunks", JsonValue::from(frame.data_chunks as u64)),
+            ])
+        })
+        .collect();
+    JsonValue::object([
+        ("num_frames", JsonValue::from(animation.num_frames as u64)),
+        ("num_plays", JsonValue::from(animation.num_plays as u64)),
+        ("duration", milliseconds(animation.duration_ms())),
+        ("frames", JsonValue::Array(frames)),
+    ])
+}
+
+/// Describes a duration in milliseconds.
+fn milliseconds(value: u64) -> JsonValue {
+    JsonValue::object([
+        ("value", JsonValue::from(value)),
+        ("unit", JsonValue::from("milliseconds")),
+    ])
+}
+
+/// Builds the JSON document printed by `encrypt --json` once the output has been written, or
+/// described with `--dry-run`.
+///
+/// # Arguments
+///
+/// * `cmd` - The encrypt command that was run.
+/// * `payload_len` - The size of the encrypted payload.
+/// * `output_len` - The size of the output.
+/// * `offset` - The offset the payload was written at, when the method has a single one.
+/// * `quality` - The PSNR and SSIM of the output, measured for the methods changing pixels.
+pub fn encrypt_report(
+    cmd: &EncryptCmd,
+    payload_len: u64,
+    output_len: u64,
+    offset: Option<u64>,
+    quality: Option<&QualityMetrics>,
+) -> io::Result<JsonValue> {
+    Ok(JsonValue::object([
+        ("schema", JsonValue::from("stegano.encrypt/1")),
+        ("generated_at", JsonValue::timestamp(SystemTime::now())),
+        ("input", file_info(&cmd.input)?),
+        ("output", output_info(cmd, &cmd.output, output_len)?),
+        ("dry_run", JsonValue::from(cmd.dry_run)),
+        ("type", JsonValue::from(cmd.r#type.to_lowercase())),
+        ("method", JsonValue::from(cmd.method.to_lowercase())),
+        ("algorithm", JsonValue::from(cmd.algorithm.to_lowercase())),
+        ("payload_size", JsonValue::size(payload_len)),
+        ("offset", JsonValue::from(offset)),
+        ("quality", quality.map_or(JsonValue::Null, quality_info)),
+        (
+            "bits_per_channel",
+            JsonValue::from(cmd.bits_per_channel.map(u64::from)),
+        ),
+    ]))
+}
+
+/// Describes the PSNR and SSIM of a stego image in integer units: the PSNR in thousandths of
+/// a dB, `null` for identical pixels, and the SSIM in millionths.
+fn quality_info(metrics: &QualityMetrics) -> JsonValue {
+    let psnr = if metrics.psnr.is_finite() {
+        JsonValue::Int((metrics.psnr * 1000.0).round() as i64)
+    } else {
+        JsonValue::Null
+    };
+    JsonValue::object([
+        (
+            "psnr",
+            JsonValue::object([("value", psnr), ("unit", JsonValue::from("millidecibels"))]),
+        ),
+        (
+            "ssim",
+            JsonValue::object([
+                ("value", JsonValue::Int((metrics.ssim * 1e6).round() as i64)),
+                ("unit", JsonValue::from("millionths")),
+            ]),
+        ),
+    ])
+}
+
+/// Builds the JSON document printed by `encrypt --split-across --json`.
+///
+/// # Arguments
+///
+/// * `cmd` - The encrypt command that was run.
+/// * `payload_len` - The size of the whole encrypted payload.
+/// * `shards` - The carrier, output and output size of each shard, in shard order.
+pub fn split_report(
+    cmd: &EncryptCmd,
+    payload_len: u64,
+    shards: &[(String, String, u64)],
+) -> io::Result<JsonValue> {
+    let shards = shards
+        .iter()
+        .enumerate()
+        .map(|(index, (input, output, output_len))| {
+            Ok(JsonValue::object([
+                ("index", JsonValue::from(index as u64)),
+                ("input", file_info(input)?),
+                ("output", output_info(cmd, output, *output_len)?),
+            ]))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok(JsonValue::object([
+        ("schema", JsonValue::from("stegano.split/1")),
+        ("generated_at", JsonValue::timestamp(SystemTime::now())),
+        ("type", JsonValue::from(cmd.r#type.to_lowercase())),
+        ("method", JsonValue::from(cmd.method.to_lowercase())),
+        ("algorithm", JsonValue::from(cmd.algorithm.to_lowercase())),
+        ("payload_size", JsonValue::size(payload_len)),
+        ("dry_run", JsonValue::from(cmd.dry_run)),
+        (
+            "shamir",
+            JsonValue::from(cmd.shamir.map(|threshold| threshold.to_string())),
+        ),
+        ("shards", JsonValue::Array(shards)),
+    ]))
+}
+
+/// Builds the JSON document printed by `diff --json`.
+///
+/// # Arguments
+///
+/// * `a` - The path of the first file.
+/// * `b` - The path of the second file.
+/// * `chunks` - The chunk differences returned by `diff_chunks`.
+/// * `pixels` - The pixel differences returned by `diff_pixels`, if requested.
+pub fn diff_report(
+    a: &str,
+    b: &str,
+    chunks: &[ChunkDiff],
+    pixels: Option<&PixelDiff>,
+) -> io::Result<JsonValue> {
+    let place = |place: Option<(usize, usize)>| {
+        place.map_or(JsonValue::Null, |(offset, size)| {
+            JsonValue::object([
+                ("offset", JsonValue::from(offset as u64)),
+                ("size", JsonValue::size(size as u64)),
+            ])
+        })
+    };
+    let chunks = chunks
+        .iter()
+        .map(|diff| {
+            JsonValue::object([
+                ("type", JsonValue::from(diff.r#type.to_string())),
+                ("change", JsonValue::from(diff.change.name())),
+                ("a", place(diff.a)),
+                ("b", place(diff.b)),
+                ("bytes_changed", JsonValue::from(diff.bytes_changed as u64)),
+                (
+                    "first_difference",
+                    JsonValue::from(diff.first_difference.map(|i| i as u64)),
+                ),
+            ])
+        })
+        .collect();
+    let pixels = pixels.map_or(JsonValue::Null, |pixels| {
+        JsonValue::object([
+            ("width", JsonValue::from(pixels.width as u64)),
+            ("height", JsonValue::from(pixels.height as u64)),
+            (
+                "pixels_changed",
+                JsonValue::from(pixels.pixels_changed as u64),
+            ),
+            (
+                "samples_changed",
+                JsonValue::from(pixels.samples_changed as u64),
+            ),
+            ("lsb_only", JsonValue::from(pixels.lsb_only as u64)),
+            ("max_delta", JsonValue::from(pixels.max_delta as u64)),
+            (
+                "bounds",
+                pixels
+                    .bounds
+                    .map_or(JsonValue::Null, |(left, top, right, bottom)| {
+                        JsonValue::Array(
+                            [left, top, right, bottom]
+                                .into_iter()
+                                .map(|v| JsonValue::from(v as u64))
+                                .collect(),
+                        )
+                    }),
+            ),
+        ])
+    });
+    Ok(JsonValue::object([
+        ("schema", JsonValue::from("stegano.diff/1")),
+        ("generated_at", JsonValue::timestamp(SystemTime::now())),
+        ("a", file_info(a)?),
+        ("b", file_info(b)?),
+        ("chunks", JsonValue::Array(chunks)),
+        ("pixels", pixels),
+    ]))
+}
+
+/// Lists the chunks of a PNG file.
+fn png_chunks(data: &[u8], filter: &ChunkFilter) -> io::Result<Vec<JsonValue>> {
+    Ok(read_chunks(data)?
+        .iter()
+        .filter_map(|chunk| {
+            let matches = filter.select(&chunk.r#type.bytes(), &chunk.data)?;
+            let mut item = vec![
+                ("type", JsonValue::from(chunk.r#type.to_string())),
+                ("offset", JsonValue::from(chunk.offset as u64)),
+                ("size", JsonValue::size(chunk.data.len() as u64)),
+            ];
+            if filter.pattern.is_some() {
+                // File offsets of the matches; the data follows the length and type fields.
+                let offsets = matches
+                    .iter()
+                    .map(|pos| JsonValue::from((chunk.offset + 8 + pos) as u64))
+                    .collect();
+                item.push(("matches", JsonValue::Array(offsets)));
+            }
+            Some(JsonValue::object(item))
+        })
+        .collect())
+}
+
+/// Lists every marker of a JPEG file with its classification, through the entropy-coded scans.
+fn jpeg_segments(data: &[u8]) -> io::Result<Vec<JsonValue>> {
+    Ok(MarkerInventory::parse(data)?
+        .segments
+        .iter()
+        .map(|segment| {
+            let mut item = vec![
+                (
+                    "marker",
+                    JsonValue::from(format!("FF{:02X}", segment.marker)),
+                ),
+                ("class", JsonValue::from(segment.class.clone())),
+                ("offset", JsonValue::from(segment.offset as u64)),
+                ("size", JsonValue::size(segment.length as u64)),
+            ];
+            if segment.scan_len > 0 {
+                item.push(("scan_size", JsonValue::size(segment.scan_len as u64)));
+            }
+            JsonValue::object(item)
+        })
+        .collect())
+}
+
+/// Describes the quantization tables of a JPEG file, in natural order, and the encoder they
+/// likely come from, which is null without a luminance table.
+fn quantization_info(data: &[u8]) -> io::Result<JsonValue> {
+    let inventory = MarkerInventory::parse(data)?;
+    let tables = QuantTable::read_all(data, &inventory)?;
+    let items = tables
+        .iter()
+        .map(|table| {
+            JsonValue::object([
+                ("id", JsonValue::from(u64::from(table.id))),
+                ("bits", JsonValue::from(if table.wide { 16u64 } else { 8 })),
+                (
+                    "values",
+                    JsonValue::Array(
+                        table
+                            .values
+                            .iter()
+                            .map(|&value| JsonValue::from(u64::from(value)))
+                            .collect(),
+                    ),
+                ),
+            ])
+        })
+        .collect();
+    let source = QuantSource::identify(&tables, &EncoderHints::read(data, &inventory)).map_or(
+        JsonValue::Null,
+        |source| {
+            JsonValue::object([
+                ("encoder", JsonValue::from(source.encoder())),
+                ("quality", JsonValue::from(u64::from(source.quality()))),
+                ("exact", JsonValue::from(source.is_exact())),
+                ("description", JsonValue::from(source.to_string())),
+            ])
+        },
+    );
+    Ok(JsonValue::object([
+        ("tables", JsonValue::Array(items)),
+        ("source", source),
+    ]))
+}
+
+/// Describes the Huffman tables of a JPEG file, whether each is one of Annex K and what is
+/// wrong with them and with the scans using them.
+fn huffman_info(data: &[u8]) -> io::Result<JsonValue> {
+    let report = HuffmanReport::analyze(data, &MarkerInventory::parse(data)?)?;
+    let strings =
+        |items: Vec<String>| JsonValue::Array(items.into_iter().map(JsonValue::from).collect());
+    let tables = report
+        .tables
+        .iter()
+        .map(|(offset, table)| {
+            JsonValue::object([
+                ("class", JsonValue::from(if table.ac { "ac" } else { "dc" })),
+                ("id", JsonValue::from(u64::from(table.id))),
+                ("offset", JsonValue::from(*offset as u64)),
+                ("codes", JsonValue::from(table.symbols.len() as u64)),
+                (
+                    "standard",
+                    table.standard().map_or(JsonValue::Null, JsonValue::from),
+                ),
+                ("problems", strings(table.problems(report.progressive))),
+            ])
+        })
+        .collect();
+    Ok(JsonValue::object([
+        ("arithmetic", JsonValue::from(report.arithmetic)),
+        ("progressive", JsonValue::from(report.progressive)),
+        ("tables", JsonValue::Array(tables)),
+        ("scan_problems", strings(report.scan_problems.clone())),
+        ("anomalies", JsonValue::from(report.has_anomalies())),
+    ]))
+}
+
+/// Lists the IFDs of a TIFF file with their entries.
+fn tiff_ifds(parsed: &[Ifd]) -> Vec<JsonValue> {
+    let mut ifds = Vec::new();
+    for ifd in parsed {
+        let entries = ifd
+            .entries
+            .iter()
+            .map(|entry| {
+                JsonValue::object([
+                    ("tag", JsonValue::from(entry.tag as u64)),
+                    ("name", JsonValue::from(tag_name(entry.tag))),
+                    ("type", JsonValue::from(type_name(entry.field_type))),
+                    ("count", JsonValue::from(entry.count as u64)),
+                    ("size", JsonValue::size(entry.data_len() as u64)),
+                ])
+            })
+            .collect();
+        ifds.push(JsonValue::object([
+            ("offset", JsonValue::from(ifd.offset as u64)),
+            ("entries", JsonValue::Array(entries)),
+            ("next", JsonValue::from(ifd.next as u64)),
+        ]));
+    }
+    ifds
+}
+
+/// Lists the images of an ICO file.
+fn ico_images(ico: &IcoFile) -> Vec<JsonValue> {
+    let dimension = |v: u8| if v == 0 { 256u64 } else { v as u64 };
+    ico.entries
+        .iter()
+        .map(|entry| {
+            JsonValue::object([
+                ("format", JsonValue::from(ico.entry_format(entry))),
+                ("width", JsonValue::from(dimension(entry.width))),
+                ("height", JsonValue::from(dimension(entry.height))),
+                ("bits_per_pixel", JsonValue::from(entry.bpp as u64)),
+                ("offset", JsonValue::from(entry.offset as u64)),
+                ("size", JsonValue::size(entry.size as u64)),
+            ])
+        })
+        .collect()
+}
+
+/// Lists the boxes of an MP4 file, with the boxes of the containers nested in them.
+fn mp4_boxes(boxes: &[Mp4Box]) -> Vec<JsonValue> {
+    boxes
+        .iter()
+        .map(|b| {
+            JsonValue::object([
+                ("type", JsonValue::from(b.type_name())),
+                ("offset", JsonValue::from(b.offset as u64)),
+                ("size", JsonValue::size(b.size as u64)),
+                ("children", JsonValue::Array(mp4_boxes(&b.children))),
+            ])
+        })
+        .collect()
+}
+
+/// Lists the frames of the ID3v2 tag of an MP3 file, with a short description of each.
+fn mp3_frames(mp3: &Mp3File) -> Vec<JsonValue> {
+    let Some(tag) = &mp3.tag else {
+        return Vec::new();
+    };
+    tag.frames
+        .iter()
+        .map(|frame| {
+            let body = &mp3.data[frame.body()..frame.end()];
+            JsonValue::object([
+                ("id", JsonValue::from(frame.id_name())),
+                ("offset", JsonValue::from(frame.offset as u64)),
+                ("size", JsonValue::size(frame.size as u64)),
+                ("flags", JsonValue::from(frame.flags as u64)),
+                ("content", JsonValue::from(describe_frame(&frame.id, body))),
+            ])
+        })
+        .collect()
+}
+
+/// Lists the metadata blocks of a FLAC file.
+fn flac_blocks(flac: &FlacFile) -> Vec<JsonValue> {
+    flac.blocks
+        .iter()
+        .map(|block| {
+            JsonValue::object([
+                ("type", JsonValue::from(block.kind_name())),
+                ("offset", JsonValue::from(block.offset as u64)),
+                ("size", JsonValue::size(block.len as u64)),
+                ("last", JsonValue::from(block.last)),
+            ])
+        })
+        .collect()
+}
+
+/// Lists the embedding methods of an SVG file, with their capacity and whether they hold a
+/// payload. The metadata method has no limit, so its capacity is null.
+fn svg_methods(svg: &SvgFile) -> Vec<JsonValue> {
+    SvgMethod::ALL
+        .into_iter()
+        .map(|method| {
+            JsonValue::object([
+                ("method", JsonValue::from(method.name())),
+                (
+                    "capacity",
+                    svg.capacity(method)
+                        .map_or(JsonValue::Null, |bytes| JsonValue::size(bytes as u64)),
+                ),
+                ("payload", JsonValue::from(svg.holds_payload(method))),
+            ])
+        })
+        .collect()
+}
+
+/// Lists the embedding methods of a text file, with their capacity and whether they hold a
+/// payload. The zerowidth method has no limit, so its capacity is null.
+fn text_methods(text: &TextFile) -> Vec<JsonValue> {
+    TextMethod::ALL
+        .into_iter()
+        .map(|method| {
+            JsonValue::object([
+                ("method", JsonValue::from(method.name())),
+                (
+                    "capacity",
+                    text.capacity(method)
+                        .map_or(JsonValue::Null, |bytes| JsonValue::size(bytes as u64)),
+                ),
+                (
+                    "payload",
+                    JsonValue::from(text.extract_payload(method).is_ok()),
+                ),
+            ])
+        })
+        .collect()
+}
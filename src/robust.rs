@@ -0,0 +1,302 @@
+use crate::png::{Ihdr, PngImage};
+use crate::prng::Prng;
+use crc32_v2::crc32;
+use sha2::{Digest, Sha256};
+use std::f64::consts::PI;
+use std::io::{self, ErrorKind};
+
+/// Side of the square pixel blocks, the JPEG block size.
+const BLOCK: usize = 8;
+
+/// Number of blocks carrying each bit.
+pub const ROBUST_REPEAT: usize = 5;
+
+/// Quantization step of the embedding, in 8-bit luma units of the orthonormal DCT.
+///
+/// A coefficient moved by more than a quarter step reads as the other bit. JPEG at quality 75
+/// quantizes the chosen coefficients with steps of 7 or 8, moving them by at most 4; below
+/// that, the votes of the other coefficients and blocks carry the bit. Larger steps survive
+/// harsher compression but show more: this one keeps the image around 45 dB PSNR.
+pub const ROBUST_STEP: f64 = 24.0;
+
+/// The mid-frequency DCT coefficients carrying the bit of a block, as (row, column).
+const COEFFICIENTS: [(usize, usize); 3] = [(1, 2), (2, 1), (2, 2)];
+
+/// Number of bytes framing the payload: its length before it and its CRC-32 after it.
+const FRAME_LEN: usize = 8;
+
+/// Returns the value of a DCT basis function at a pixel of the block.
+fn basis((u, v): (usize, usize), x: usize, y: usize) -> f64 {
+    let alpha = |k: usize| if k == 0 { (0.5f64).sqrt() } else { 1.0 };
+    alpha(u) * alpha(v) / 4.0
+        * ((2 * y + 1) as f64 * u as f64 * PI / 16.0).cos()
+        * ((2 * x + 1) as f64 * v as f64 * PI / 16.0).cos()
+}
+
+/// The luma plane of an image, in 8-bit units, with where its blocks go.
+struct Luma {
+    width: usize,
+    /// Indices of the samples each pixel's luma is made of, with their weights.
+    channels: Vec<(usize, f64)>,
+    /// Factor from 8-bit units to sample units.
+    scale: f64,
+    /// Block origins in pixels, in the order drawn from the key.
+    blocks: Vec<(usize, usize)>,
+    /// Dither of each block and coefficient, so the lattice can't be found without the key.
+    dither: Vec<[f64; 3]>,
+}
+
+impl Luma {
+    /// Lists the blocks of an image in the order derived from `key`.
+    fn new(image: &PngImage, key: &str) -> Self {
+        let channels = match image.color_type {
+            0 | 4 => vec![(0, 1.0)],
+            _ => vec![(0, 0.299), (1, 0.587), (2, 0.114)],
+        };
+        let mut blocks: Vec<(usize, usize)> = (0..image.height / BLOCK)
+            .flat_map(|by| (0..image.width / BLOCK).map(move |bx| (bx * BLOCK, by * BLOCK)))
+            .collect();
+        let digest = Sha256::digest([b"robust:", key.as_bytes()].concat());
+        let mut rng = Prng::new(u64::from_le_bytes(digest[..8].try_into().unwrap()));
+        for i in (1..blocks.len()).rev() {
+            blocks.swap(i, rng.below(i as u64 + 1) as usize);
+        }
+        let mut unit = || rng.next_u64() as f64 / u64::MAX as f64 * ROBUST_STEP;
+        let dither = (0..blocks.len())
+            .map(|_| [unit(), unit(), unit()])
+            .collect();
+        Luma {
+            width: image.width,
+            channels,
+            scale: if image.bit_depth == 16 { 257.0 } else { 1.0 },
+            blocks,
+            dither,
+        }
+    }
+
+    /// Returns the luma of a pixel, in 8-bit units.
+    fn at(&self, image: &PngImage, x: usize, y: usize) -> f64 {
+        let pixel = (y * self.width + x) * image.channels();
+        self.channels
+            .iter()
+            .map(|&(c, weight)| image.samples[pixel + c] as f64 * weight)
+            .sum::<f64>()
+            / self.scale
+    }
+
+    /// Returns the chosen coefficients of a block, less their dither.
+    fn coefficients(&self, image: &PngImage, block: usize) -> [f64; 3] {
+        let (x0, y0) = self.blocks[block];
+        let mut coefficients = [0.0; 3];
+        for y in 0..BLOCK {
+            for x in 0..BLOCK {
+                let luma = self.at(image, x0 + x, y0 + y);
+                for (c, &position) in coefficients.iter_mut().zip(&COEFFICIENTS) {
+                    *c += luma * basis(position, x, y);
+                }
+            }
+        }
+        for (c, dither) in coefficients.iter_mut().zip(self.dither[block]) {
+            *c -= dither;
+        }
+        coefficients
+    }
+
+    /// Moves the coefficients of a block onto the lattice of `bit`, changing every colour
+    /// sample by the same amount so only the luma changes.
+    fn embed(&self, image: &mut PngImage, block: usize, bit: u8) {
+        let offset = bit as f64 * ROBUST_STEP / 2.0;
+        let changes = self
+            .coefficients(image, block)
+            .map(|c| ((c - offset) / ROBUST_STEP).round() * ROBUST_STEP + offset - c);
+        let (x0, y0) = self.blocks[block];
+        let channels = image.channels();
+        let max = if image.bit_depth == 16 {
+            65535.0
+        } else {
+            255.0
+        };
+        let colour = if matches!(image.color_type, 4 | 6) {
+            channels - 1
+        } else {
+            channels
+        };
+        for y in 0..BLOCK {
+            for x in 0..BLOCK {
+                let delta = changes
+                    .iter()
+                    .zip(&COEFFICIENTS)
+                    .map(|(change, &position)| change * basis(position, x, y))
+                    .sum::<f64>()
+                    * self.scale;
+                let pixel = ((y0 + y) * self.width + x0 + x) * channels;
+                for sample in &mut image.samples[pixel..pixel + colour] {
+                    *sample = (*sample as f64 + delta).round().clamp(0.0, max) as u16;
+                }
+            }
+        }
+    }
+
+    /// Returns how strongly a block votes for bit 0 (positive) or bit 1 (negative).
+    fn vote(&self, image: &PngImage, block: usize) -> f64 {
+        self.coefficients(image, block)
+            .iter()
+            .map(|c| (2.0 * PI * c / ROBUST_STEP).cos())
+            .sum()
+    }
+}
+
+/// Decodes a carrier, checking that it can hold robust data.
+fn decode_carrier(data: &[u8]) -> io::Result<PngImage> {
+    let ihdr = Ihdr::read(data)?;
+    if ihdr.color_type == 3 || ihdr.bit_depth < 8 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The robust method needs an 8 or 16-bit greyscale or truecolour PNG",
+        ));
+    }
+    PngImage::decode(data)
+}
+
+/// Returns the number of payload bytes an image can hold with the robust method.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{encode_grayscale, PngImage};
+/// use stegano::robust::robust_capacity;
+///
+/// let png = encode_grayscale(256, 256, &[128; 256 * 256]).unwrap();
+/// assert_eq!(robust_capacity(&PngImage::decode(&png).unwrap()), 17);
+/// ```
+pub fn robust_capacity(image: &PngImage) -> usize {
+    let blocks = (image.width / BLOCK) * (image.height / BLOCK);
+    (blocks / ROBUST_REPEAT / 8).saturating_sub(FRAME_LEN)
+}
+
+/// Hides a payload in the DCT coefficients of 8x8 pixel blocks, so it survives moderate JPEG
+/// re-compression and noise.
+///
+/// Each block carries one bit by quantization index modulation of three mid-frequency
+/// coefficients of its luma, on the block grid JPEG uses. The error correction is a
+/// repetition code: every bit goes into `ROBUST_REPEAT` blocks scattered over the image in an
+/// order drawn from the key, and is read back by a soft majority vote, so a few damaged blocks
+/// don't matter. The payload is framed by its length and a CRC-32 that tells intact payloads
+/// from damaged ones and wrong keys. The key also dithers the quantization lattice.
+///
+/// This trades capacity for robustness: about one byte per 40 blocks, or 94 bytes for a
+/// 512x512 image. JPEG re-compression down to about quality 50, chroma subsampling included,
+/// and mild noise are survived, as long as the result is converted back to PNG at the same
+/// size. Resizing, cropping or rotating the image moves the block grid and loses the payload.
+///
+/// # Arguments
+///
+/// * `carrier` - The bytes of an 8 or 16-bit, non-palette PNG.
+/// * `payload` - The bytes to hide.
+/// * `key` - The key the block order and dither are derived from.
+///
+/// # Returns
+///
+/// A `Result` containing the bytes of the stego file, or an `InvalidInput` error if the
+/// carrier is unsuitable, too small, or too close to black or white to hold the bits.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::{encode_grayscale, PngImage};
+/// use stegano::robust::{embed_robust, extract_robust};
+///
+/// let pixels: Vec<u8> = (0..256 * 256).map(|i| (64 + (i % 256 + i / 256) / 4) as u8).collect();
+/// let carrier = encode_grayscale(256, 256, &pixels).unwrap();
+/// let stego = embed_robust(&carrier, b"secret", "key").unwrap();
+/// assert_eq!(extract_robust(&stego, "key").unwrap(), b"secret");
+///
+/// // Noise of up to two levels on every pixel doesn't reach the payload.
+/// let mut image = PngImage::decode(&stego).unwrap();
+/// for (i, sample) in image.samples.iter_mut().enumerate() {
+///     *sample = (*sample as i32 + [-2, 1, 2, -1, 0][i * 7 % 5]).clamp(0, 255) as u16;
+/// }
+/// let noisy = image.replace_pixels(&stego).unwrap();
+/// assert_eq!(extract_robust(&noisy, "key").unwrap(), b"secret");
+/// assert!(extract_robust(&noisy, "other key").is_err());
+/// ```
+pub fn embed_robust(carrier: &[u8], payload: &[u8], key: &str) -> io::Result<Vec<u8>> {
+    let mut image = decode_carrier(carrier)?;
+    let capacity = robust_capacity(&image);
+    if payload.len() > capacity {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The payload needs {} bytes but the carrier offers {} with the robust method",
+                payload.len(),
+                capacity
+            ),
+        ));
+    }
+    let luma = Luma::new(&image, key);
+    let mut frame = (payload.len() as u32).to_be_bytes().to_vec();
+    frame.extend_from_slice(payload);
+    frame.extend_from_slice(&crc32(0, payload).to_be_bytes());
+    let bits = frame
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |bit| byte >> bit & 1));
+    for (i, bit) in bits.enumerate() {
+        for block in i * ROBUST_REPEAT..(i + 1) * ROBUST_REPEAT {
+            luma.embed(&mut image, block, bit);
+        }
+    }
+    let stego = image.replace_pixels(carrier)?;
+    // Clipping in near black or white blocks can undo the bits.
+    if extract_robust(&stego, key).ok().as_deref() != Some(payload) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The carrier is too close to black or white for the robust method",
+        ));
+    }
+    Ok(stego)
+}
+
+/// Extracts a payload hidden by `embed_robust`.
+///
+/// # Arguments
+///
+/// * `data` - The bytes of the stego file, or of a re-compressed copy converted to PNG.
+/// * `key` - The key the payload was embedded with.
+///
+/// # Returns
+///
+/// A `Result` containing the payload, or a `NotFound` error if the framing doesn't check out,
+/// which a wrong key or too much damage gives.
+pub fn extract_robust(data: &[u8], key: &str) -> io::Result<Vec<u8>> {
+    let image = decode_carrier(data)?;
+    let luma = Luma::new(&image, key);
+    let capacity = robust_capacity(&image);
+    let not_found = || {
+        io::Error::new(
+            ErrorKind::NotFound,
+            "No robust payload found with this key, or it was damaged beyond repair",
+        )
+    };
+    let read_byte = |index: usize| -> u8 {
+        (0..8).fold(0u8, |byte, bit| {
+            let first = (index * 8 + bit) * ROBUST_REPEAT;
+            let vote: f64 = (first..first + ROBUST_REPEAT)
+                .map(|block| luma.vote(&image, block))
+                .sum();
+            byte << 1 | (vote < 0.0) as u8
+        })
+    };
+    if capacity == 0 {
+        return Err(not_found());
+    }
+    let len = u32::from_be_bytes([read_byte(0), read_byte(1), read_byte(2), read_byte(3)]) as usize;
+    if len > capacity {
+        return Err(not_found());
+    }
+    let frame: Vec<u8> = (4..len + FRAME_LEN).map(read_byte).collect();
+    let (payload, crc) = frame.split_at(len);
+    if crc32(0, payload).to_be_bytes() != crc {
+        return Err(not_found());
+    }
+    Ok(payload.to_vec())
+}
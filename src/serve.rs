@@ -0,0 +1,393 @@
+use crate::codec::{decode_bytes, encode_bytes};
+use crate::codec::{CodecOptions, Decoded};
+use crate::format::{detect_format, extension_type};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// Largest request line and headers accepted by `serve`, in bytes.
+const MAX_HEAD: u64 = 8192;
+
+/// Largest carrier `fetch` downloads, in bytes.
+pub const MAX_DOWNLOAD: u64 = 256 << 20;
+
+/// How long a connection may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns the carrier type of a file from its extension, or else from its magic bytes.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use stegano::serve::carrier_type;
+///
+/// assert_eq!(carrier_type(Path::new("covers/cat.PNG"), b""), Some("png"));
+/// assert_eq!(carrier_type(Path::new("notes.txt"), b""), Some("text"));
+/// assert_eq!(carrier_type(Path::new("download"), b"fLaC"), Some("flac"));
+/// assert_eq!(carrier_type(Path::new("download"), &[0xFF, 0xD8, 0xFF]), Some("jpeg"));
+/// assert_eq!(carrier_type(Path::new("archive.zip"), b"PK"), None);
+/// ```
+pub fn carrier_type(path: &Path, data: &[u8]) -> Option<&'static str> {
+    extension_type(path).or_else(|| detect_format(data).map(|format| format.name()))
+}
+
+/// Returns the `Content-Type` a carrier type is served with.
+pub fn content_type(file_type: &str) -> &'static str {
+    match file_type {
+        "png" => "image/png",
+        "jpeg" => "image/jpeg",
+        "tiff" => "image/tiff",
+        "ico" => "image/vnd.microsoft.icon",
+        "mp4" => "video/mp4",
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "svg" => "image/svg+xml",
+        "text" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Maps a request target to a file under `dir`, decoding `%XX` escapes and ignoring the
+/// query. Targets that climb out of `dir` or name a directory give `None`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use stegano::serve::resolve_target;
+///
+/// let dir = std::env::temp_dir();
+/// let path = dir.join("stegano-resolve-doc.png");
+/// std::fs::write(&path, b"").unwrap();
+/// assert_eq!(resolve_target(&dir, "/stegano-resolve%2Ddoc.png?v=2"), Some(path.clone()));
+/// assert_eq!(resolve_target(&dir, "/../etc/passwd"), None);
+/// assert_eq!(resolve_target(&dir, "/"), None);
+/// std::fs::remove_file(path).unwrap();
+/// ```
+pub fn resolve_target(dir: &Path, target: &str) -> Option<PathBuf> {
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    let decoded = String::from_utf8(decoded).ok()?;
+    let mut resolved = dir.to_path_buf();
+    for segment in decoded.split('/').filter(|segment| !segment.is_empty()) {
+        if segment == "." || segment == ".." || segment.contains('\\') || segment.contains('\0') {
+            return None;
+        }
+        resolved.push(segment);
+    }
+    resolved.is_file().then_some(resolved)
+}
+
+/// What `serve` answered a request with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Served {
+    /// The request method, such as `GET`.
+    pub method: String,
+    /// The request target, such as `/cat.png`.
+    pub target: String,
+    /// The HTTP status code of the response.
+    pub status: u16,
+    /// The size of the response body.
+    pub len: usize,
+    /// Whether a queued payload was embedded into the response.
+    pub embedded: bool,
+    /// Why a queued payload couldn't be embedded into the requested carrier, which was served
+    /// as it is.
+    pub skipped: Option<String>,
+}
+
+/// Writes a response, with its body unless the request was `HEAD`.
+fn respond<W: Write>(
+    writer: &mut W,
+    status: u16,
+    reason: &str,
+    content_type: &str,
+    body: &[u8],
+    head: bool,
+) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        reason,
+        content_type,
+        body.len()
+    )?;
+    if status == 405 {
+        writer.write_all(b"Allow: GET, HEAD\r\n")?;
+    }
+    writer.write_all(b"\r\n")?;
+    if !head {
+        writer.write_all(body)?;
+    }
+    writer.flush()
+}
+
+/// Answers one HTTP request for a file of `dir`.
+///
+/// A `GET` of a carrier embeds the next queued payload into it on the fly, with the key,
+/// algorithm and method of `opts`, and takes it off the queue; the file on disk is left
+/// untouched. Once the queue is empty, or when the payload doesn't fit the carrier, the file
+/// is served as it is, so the traffic looks like any other static server.
+///
+/// # Arguments
+///
+/// * `reader` - The connection, to read the request from.
+/// * `writer` - The connection, to write the response to.
+/// * `dir` - The directory of cover files.
+/// * `queue` - The payloads waiting to be sent.
+/// * `opts` - The key, algorithm and method; the carrier type comes from each file.
+///
+/// # Returns
+///
+/// A `Result` containing what was served, or an `io::Error` if the connection failed.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::VecDeque;
+/// use std::io::Cursor;
+/// use stegano::codec::{decode_bytes, CodecOptions};
+/// use stegano::png::encode_grayscale;
+/// use stegano::serve::handle_request;
+///
+/// let dir = std::env::temp_dir().join("stegano-serve-doc");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let cover = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// std::fs::write(dir.join("cat.png"), &cover).unwrap();
+/// let opts = CodecOptions::default();
+/// let mut queue = VecDeque::from([b"meet at noon".to_vec()]);
+///
+/// let mut response = Vec::new();
+/// let request = Cursor::new("GET /cat.png HTTP/1.1\r\nHost: localhost\r\n\r\n");
+/// let served = handle_request(request, &mut response, &dir, &mut queue, &opts).unwrap();
+/// assert!(served.embedded && queue.is_empty());
+/// let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+/// assert!(response.starts_with(b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n"));
+/// assert_eq!(decode_bytes(&response[split..], &opts).unwrap().payload, b"meet at noon");
+///
+/// // The queue is empty: the cover goes out untouched.
+/// let mut response = Vec::new();
+/// let request = Cursor::new("GET /cat.png HTTP/1.1\r\n\r\n");
+/// handle_request(request, &mut response, &dir, &mut queue, &opts).unwrap();
+/// assert!(response.ends_with(&cover));
+///
+/// let request = Cursor::new("GET /../secret HTTP/1.1\r\n\r\n");
+/// let served = handle_request(request, &mut Vec::new(), &dir, &mut queue, &opts).unwrap();
+/// assert_eq!(served.status, 404);
+/// std::fs::remove_dir_all(dir).unwrap();
+/// ```
+pub fn handle_request<R: BufRead, W: Write>(
+    reader: R,
+    mut writer: W,
+    dir: &Path,
+    queue: &mut VecDeque<Vec<u8>>,
+    opts: &CodecOptions,
+) -> io::Result<Served> {
+    let mut reader = reader.take(MAX_HEAD);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers aren't needed, but must be read before answering.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && header.trim_end() != "" {
+        header.clear();
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/") => {
+            (method.to_string(), target.to_string())
+        }
+        _ => {
+            respond(&mut writer, 400, "Bad Request", "text/plain", b"", false)?;
+            return Ok(Served {
+                method: String::new(),
+                target: request_line.trim().chars().take(60).collect(),
+                status: 400,
+                len: 0,
+                embedded: false,
+                skipped: None,
+            });
+        }
+    };
+    let mut served = Served {
+        method,
+        target,
+        status: 200,
+        len: 0,
+        embedded: false,
+        skipped: None,
+    };
+    let head = served.method == "HEAD";
+    if !head && served.method != "GET" {
+        served.status = 405;
+        respond(
+            &mut writer,
+            405,
+            "Method Not Allowed",
+            "text/plain",
+            b"",
+            false,
+        )?;
+        return Ok(served);
+    }
+    let Some(path) = resolve_target(dir, &served.target) else {
+        served.status = 404;
+        respond(&mut writer, 404, "Not Found", "text/plain", b"", head)?;
+        return Ok(served);
+    };
+    let mut body = std::fs::read(&path)?;
+    let file_type = carrier_type(&path, &body);
+    if let (Some(file_type), Some(payload), false) = (file_type, queue.front(), head) {
+        let opts = CodecOptions {
+            file_type: file_type.to_string(),
+            ..opts.clone()
+        };
+        match encode_bytes(&body, payload, &opts) {
+            Ok(stego) => {
+                body = stego;
+                queue.pop_front();
+                served.embedded = true;
+            }
+            Err(err) => served.skipped = Some(err.to_string()),
+        }
+    }
+    served.len = body.len();
+    let content_type = content_type(file_type.unwrap_or_default());
+    respond(&mut writer, 200, "OK", content_type, &body, head)?;
+    Ok(served)
+}
+
+/// Serves the files of `dir` over HTTP, embedding the queued payloads into the carriers
+/// requested, one payload per request, until cancelled or after `max_requests` requests.
+///
+/// # Arguments
+///
+/// * `listener` - The bound socket.
+/// * `dir` - The directory of cover files.
+/// * `queue` - The payloads to send, in order.
+/// * `opts` - The key, algorithm and method, and the token that stops the server.
+/// * `max_requests` - Stops after this many requests, if given.
+/// * `on_request` - Called after each request, with what was served or the error of the
+///   connection.
+///
+/// # Returns
+///
+/// A `Result` containing the number of requests answered.
+pub fn serve(
+    listener: TcpListener,
+    dir: &Path,
+    mut queue: VecDeque<Vec<u8>>,
+    opts: &CodecOptions,
+    max_requests: Option<u64>,
+    mut on_request: impl FnMut(&io::Result<Served>),
+) -> io::Result<u64> {
+    // Polling lets Ctrl-C stop the server between connections.
+    listener.set_nonblocking(true)?;
+    let mut requests = 0;
+    while max_requests.is_none_or(|max| requests < max) && !opts.cancel.is_cancelled() {
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(50));
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let reader = io::BufReader::new(&stream);
+        let served = handle_request(reader, &stream, dir, &mut queue, opts);
+        requests += 1;
+        on_request(&served);
+    }
+    Ok(requests)
+}
+
+/// Downloads a stego file, up to `MAX_DOWNLOAD` bytes.
+///
+/// # Returns
+///
+/// A `Result` containing the bytes, or an `io::Error` if the request failed.
+pub fn fetch(url: &str) -> io::Result<Vec<u8>> {
+    let mut response = ureq::get(url)
+        .header("User-Agent", concat!("stegano/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .map_err(io::Error::other)?;
+    response
+        .body_mut()
+        .with_config()
+        .limit(MAX_DOWNLOAD)
+        .read_to_vec()
+        .map_err(io::Error::other)
+}
+
+/// Extracts and decrypts the payload of a downloaded stego file.
+///
+/// # Arguments
+///
+/// * `url` - Where the file came from, whose extension names the carrier type.
+/// * `data` - The downloaded bytes.
+/// * `opts` - The key, algorithm and method; a file type of `auto` is taken from the URL, or
+///   else from the magic bytes.
+///
+/// # Returns
+///
+/// A `Result` containing the carrier type and the decoded payload and carrier.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::codec::{encode_bytes, CodecOptions};
+/// use stegano::png::encode_grayscale;
+/// use stegano::serve::fetched_payload;
+///
+/// let carrier = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let stego = encode_bytes(&carrier, b"hello", &CodecOptions::default()).unwrap();
+/// let opts = CodecOptions {
+///     file_type: "auto".into(),
+///     ..CodecOptions::default()
+/// };
+/// let (file_type, decoded) = fetched_payload("http://host/img?id=4", &stego, &opts).unwrap();
+/// assert_eq!((file_type.as_str(), decoded.payload.as_slice()), ("png", &b"hello"[..]));
+/// ```
+pub fn fetched_payload(
+    url: &str,
+    data: &[u8],
+    opts: &CodecOptions,
+) -> io::Result<(String, Decoded)> {
+    let file_type = if opts.file_type.eq_ignore_ascii_case("auto") {
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        let name = path.rsplit('/').next().unwrap_or_default();
+        carrier_type(Path::new(name), data)
+            .ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "Can't tell the carrier type of the download, give it with -t",
+                )
+            })?
+            .to_string()
+    } else {
+        opts.file_type.to_lowercase()
+    };
+    let opts = CodecOptions {
+        file_type: file_type.clone(),
+        ..opts.clone()
+    };
+    Ok((file_type, decode_bytes(data, &opts)?))
+}
@@ -0,0 +1,275 @@
+use crate::shard::list_numbers;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+/// Magic bytes opening every share.
+pub const SHARE_MAGIC: &[u8; 4] = b"STss";
+
+/// Number of bytes before the share data: magic, set id, threshold, number and total.
+pub const SHARE_HEADER_LEN: usize = 11;
+
+/// How many shares a payload is split into and how many of them rebuild it, as in `3-of-5`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threshold {
+    /// The number of shares needed to rebuild the payload.
+    pub needed: u8,
+    /// The number of shares written.
+    pub shares: u8,
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-of-{}", self.needed, self.shares)
+    }
+}
+
+/// Parses the value of `--shamir`: `K-of-N`, with 1 <= K <= N <= 255.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::shamir::{parse_threshold, Threshold};
+///
+/// assert_eq!(parse_threshold("3-of-5").unwrap(), Threshold { needed: 3, shares: 5 });
+/// assert!(parse_threshold("6-of-5").is_err());
+/// assert!(parse_threshold("3/5").is_err());
+/// ```
+pub fn parse_threshold(value: &str) -> Result<Threshold, String> {
+    let (needed, shares) = value
+        .to_lowercase()
+        .split_once("-of-")
+        .and_then(|(k, n)| Some((k.trim().parse::<u8>().ok()?, n.trim().parse::<u8>().ok()?)))
+        .ok_or_else(|| {
+            format!(
+                "invalid threshold '{}', expected K-of-N such as 3-of-5",
+                value
+            )
+        })?;
+    if needed == 0 || needed > shares {
+        return Err(format!(
+            "invalid threshold '{}', K must be between 1 and N",
+            value
+        ));
+    }
+    Ok(Threshold { needed, shares })
+}
+
+/// One Shamir share of a payload: any `threshold` shares of a set rebuild it, fewer tell
+/// nothing about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    /// The first four bytes of the SHA-256 of the whole payload, shared by every share of a set.
+    pub set: [u8; 4],
+    /// The number of shares needed to rebuild the payload.
+    pub threshold: u8,
+    /// The position of the share, starting at 0.
+    pub index: u8,
+    /// The number of shares in the set.
+    pub total: u8,
+    /// The share itself, as long as the payload.
+    pub data: Vec<u8>,
+}
+
+impl Share {
+    /// Serializes the share: `STss`, the set id, then the threshold, index and total as single
+    /// bytes, followed by the data.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SHARE_HEADER_LEN + self.data.len());
+        bytes.extend_from_slice(SHARE_MAGIC);
+        bytes.extend_from_slice(&self.set);
+        bytes.extend_from_slice(&[self.threshold, self.index, self.total]);
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// Parses a share serialized with `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the share, or an `InvalidData` error if the bytes aren't a share.
+    pub fn parse(bytes: &[u8]) -> io::Result<Share> {
+        let invalid = |message: &str| io::Error::new(ErrorKind::InvalidData, message.to_string());
+        if bytes.len() < SHARE_HEADER_LEN || &bytes[..4] != SHARE_MAGIC {
+            return Err(invalid(
+                "Not a share, was the payload embedded with --shamir?",
+            ));
+        }
+        let (threshold, index, total) = (bytes[8], bytes[9], bytes[10]);
+        if threshold == 0 || threshold > total || index >= total {
+            return Err(invalid("Corrupted share header"));
+        }
+        Ok(Share {
+            set: bytes[4..8].try_into().unwrap(),
+            threshold,
+            index,
+            total,
+            data: bytes[SHARE_HEADER_LEN..].to_vec(),
+        })
+    }
+
+    /// The point the share was evaluated at; 0 is where the payload itself sits.
+    fn x(&self) -> u8 {
+        self.index + 1
+    }
+}
+
+/// Multiplies two elements of GF(256), reduced by the AES polynomial.
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = (a << 1) ^ if a & 0x80 != 0 { 0x1B } else { 0 };
+        b >>= 1;
+    }
+    product
+}
+
+/// Returns the multiplicative inverse of a non-zero element of GF(256), as a^254.
+fn inverse(a: u8) -> u8 {
+    let mut result = 1;
+    let mut power = a;
+    let mut exponent = 254u8;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            result = mul(result, power);
+        }
+        power = mul(power, power);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// Splits a payload into Shamir shares over GF(256), byte by byte.
+///
+/// # Arguments
+///
+/// * `payload` - The encrypted payload.
+/// * `threshold` - How many shares are written and how many rebuild the payload.
+///
+/// # Returns
+///
+/// A `Result` containing the shares in order, or an error if the system has no randomness
+/// to draw the polynomials from.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::shamir::{combine_shares, split_secret, Threshold};
+///
+/// let threshold = Threshold { needed: 3, shares: 5 };
+/// let shares = split_secret(b"any three of five", threshold).unwrap();
+/// assert_eq!(shares.len(), 5);
+/// let quorum = [shares[4].clone(), shares[0].clone(), shares[2].clone()];
+/// assert_eq!(combine_shares(&quorum).unwrap(), b"any three of five");
+///
+/// let err = combine_shares(&shares[1..3]).unwrap_err();
+/// assert_eq!(err.to_string(), "Need 3 shares of 5, found 2 (shares 2 and 3)");
+/// ```
+pub fn split_secret(payload: &[u8], threshold: Threshold) -> io::Result<Vec<Share>> {
+    let set = Sha256::digest(payload)[..4].try_into().unwrap();
+    let degree = threshold.needed as usize - 1;
+    let mut coefficients = vec![0u8; payload.len() * degree];
+    getrandom::getrandom(&mut coefficients).map_err(|err| io::Error::other(err.to_string()))?;
+    Ok((0..threshold.shares)
+        .map(|index| {
+            let x = index + 1;
+            let data = payload
+                .iter()
+                .enumerate()
+                .map(|(i, &byte)| {
+                    let y = coefficients[i * degree..(i + 1) * degree]
+                        .iter()
+                        .rev()
+                        .fold(0, |y, &c| mul(y, x) ^ c);
+                    mul(y, x) ^ byte
+                })
+                .collect();
+            Share {
+                set,
+                threshold: threshold.needed,
+                index,
+                total: threshold.shares,
+                data,
+            }
+        })
+        .collect())
+}
+
+/// Rebuilds a payload from any `threshold` of its shares, given in any order.
+///
+/// # Arguments
+///
+/// * `shares` - The shares gathered from the carriers; duplicates are ignored.
+///
+/// # Returns
+///
+/// A `Result` containing the payload, a `NotFound` error if too few shares were found, or an
+/// `InvalidData` error if the shares come from different payloads or don't rebuild it.
+pub fn combine_shares(shares: &[Share]) -> io::Result<Vec<u8>> {
+    let first = shares
+        .first()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "No shares to combine"))?;
+    if shares.iter().any(|share| {
+        share.set != first.set
+            || share.threshold != first.threshold
+            || share.total != first.total
+            || share.data.len() != first.data.len()
+    }) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "The shares come from different payloads",
+        ));
+    }
+    let mut unique: Vec<&Share> = Vec::new();
+    for share in shares {
+        if unique.iter().all(|kept| kept.index != share.index) {
+            unique.push(share);
+        }
+    }
+    unique.sort_by_key(|share| share.index);
+    if unique.len() < first.threshold as usize {
+        let found: Vec<u16> = unique.iter().map(|share| share.index as u16).collect();
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "Need {} shares of {}, found {} (share{} {})",
+                first.threshold,
+                first.total,
+                found.len(),
+                if found.len() > 1 { "s" } else { "" },
+                list_numbers(&found)
+            ),
+        ));
+    }
+    let used = &unique[..first.threshold as usize];
+    // The Lagrange basis polynomials evaluated at 0, one weight per share.
+    let weights: Vec<u8> = used
+        .iter()
+        .map(|share| {
+            let (numerator, denominator) = used
+                .iter()
+                .filter(|other| other.index != share.index)
+                .fold((1, 1), |(num, den), other| {
+                    (mul(num, other.x()), mul(den, other.x() ^ share.x()))
+                });
+            mul(numerator, inverse(denominator))
+        })
+        .collect();
+    let payload: Vec<u8> = (0..first.data.len())
+        .map(|i| {
+            used.iter().zip(&weights).fold(0, |byte, (share, &weight)| {
+                byte ^ mul(share.data[i], weight)
+            })
+        })
+        .collect();
+    if Sha256::digest(&payload)[..4] != first.set {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "The shares don't rebuild the payload they were split from",
+        ));
+    }
+    Ok(payload)
+}
@@ -0,0 +1,198 @@
+use sha2::{Digest, Sha256};
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+/// Magic bytes opening every shard.
+pub const SHARD_MAGIC: &[u8; 4] = b"STsh";
+
+/// Number of bytes before the shard data: magic, set id, index and total.
+pub const SHARD_HEADER_LEN: usize = 12;
+
+/// One part of a payload split across several carriers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shard {
+    /// The first four bytes of the SHA-256 of the whole payload, shared by every shard of a set.
+    pub set: [u8; 4],
+    /// The position of the shard, starting at 0.
+    pub index: u16,
+    /// The number of shards in the set.
+    pub total: u16,
+    /// The bytes of the payload carried by this shard.
+    pub data: Vec<u8>,
+}
+
+impl Shard {
+    /// Serializes the shard: `STsh`, the set id, then the index and total as 16-bit
+    /// big-endian integers, followed by the data.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SHARD_HEADER_LEN + self.data.len());
+        bytes.extend_from_slice(SHARD_MAGIC);
+        bytes.extend_from_slice(&self.set);
+        bytes.extend_from_slice(&self.index.to_be_bytes());
+        bytes.extend_from_slice(&self.total.to_be_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// Parses a shard serialized with `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the shard, or an `InvalidData` error if the bytes aren't a shard.
+    pub fn parse(bytes: &[u8]) -> io::Result<Shard> {
+        let invalid = |message: &str| io::Error::new(ErrorKind::InvalidData, message.to_string());
+        if bytes.len() < SHARD_HEADER_LEN || &bytes[..4] != SHARD_MAGIC {
+            return Err(invalid(
+                "Not a shard, was the payload embedded with --split-across?",
+            ));
+        }
+        let index = u16::from_be_bytes([bytes[8], bytes[9]]);
+        let total = u16::from_be_bytes([bytes[10], bytes[11]]);
+        if index >= total {
+            return Err(invalid("Corrupted shard header"));
+        }
+        Ok(Shard {
+            set: bytes[4..8].try_into().unwrap(),
+            index,
+            total,
+            data: bytes[SHARD_HEADER_LEN..].to_vec(),
+        })
+    }
+}
+
+/// Splits an encrypted payload into `parts` shards of nearly equal size.
+///
+/// # Arguments
+///
+/// * `payload` - The encrypted payload.
+/// * `parts` - The number of shards, between 1 and 65535.
+///
+/// # Returns
+///
+/// A `Result` containing the shards in order, or an `InvalidInput` error if `parts` is out of
+/// range.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::shard::{join_shards, split_payload};
+///
+/// let mut shards = split_payload(b"a payload in three parts", 3).unwrap();
+/// assert_eq!(shards.len(), 3);
+/// shards.reverse();
+/// assert_eq!(join_shards(&shards).unwrap(), b"a payload in three parts");
+///
+/// let err = join_shards(&shards[..1]).unwrap_err();
+/// assert_eq!(err.to_string(), "Missing shards 1 and 2 of 3");
+/// ```
+pub fn split_payload(payload: &[u8], parts: usize) -> io::Result<Vec<Shard>> {
+    let total = u16::try_from(parts)
+        .ok()
+        .filter(|&total| total > 0)
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "A payload can be split across 1 to 65535 carriers, got {}",
+                    parts
+                ),
+            )
+        })?;
+    let set = Sha256::digest(payload)[..4].try_into().unwrap();
+    let size = payload.len().div_ceil(parts);
+    Ok((0..total)
+        .map(|index| {
+            let start = (index as usize * size).min(payload.len());
+            let end = (start + size).min(payload.len());
+            Shard {
+                set,
+                index,
+                total,
+                data: payload[start..end].to_vec(),
+            }
+        })
+        .collect())
+}
+
+/// Names the output of a shard after the `--output` path: `out.png` gives `out.1.png`,
+/// `out.2.png` and so on.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::shard::shard_path;
+///
+/// assert_eq!(shard_path("dir/out.png", 0), "dir/out.1.png");
+/// assert_eq!(shard_path("out", 2), "out.3");
+/// ```
+pub fn shard_path(output: &str, index: usize) -> String {
+    let path = Path::new(output);
+    let stem = path
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, index + 1, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, index + 1),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Lists shard numbers, counted from 1, as "1, 2 and 4".
+pub(crate) fn list_numbers(indices: &[u16]) -> String {
+    let numbers: Vec<String> = indices.iter().map(|i| (i + 1).to_string()).collect();
+    match numbers.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => numbers.concat(),
+    }
+}
+
+/// Reassembles a payload from its shards, given in any order.
+///
+/// # Arguments
+///
+/// * `shards` - The shards gathered from the carriers; duplicates are ignored.
+///
+/// # Returns
+///
+/// A `Result` containing the payload, a `NotFound` error listing the missing shards, or an
+/// `InvalidData` error if the shards come from different payloads.
+pub fn join_shards(shards: &[Shard]) -> io::Result<Vec<u8>> {
+    let first = shards
+        .first()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "No shards to join"))?;
+    if shards
+        .iter()
+        .any(|shard| shard.set != first.set || shard.total != first.total)
+    {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "The shards come from different payloads",
+        ));
+    }
+    let mut parts: Vec<Option<&[u8]>> = vec![None; first.total as usize];
+    for shard in shards {
+        parts[shard.index as usize] = Some(&shard.data);
+    }
+    let missing: Vec<u16> = (0..first.total)
+        .filter(|&index| parts[index as usize].is_none())
+        .collect();
+    if !missing.is_empty() {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "Missing shard{} {} of {}",
+                if missing.len() > 1 { "s" } else { "" },
+                list_numbers(&missing),
+                first.total
+            ),
+        ));
+    }
+    let payload: Vec<u8> = parts.into_iter().flatten().flatten().copied().collect();
+    if Sha256::digest(&payload)[..4] != first.set {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "The joined shards don't match the payload they were split from",
+        ));
+    }
+    Ok(payload)
+}
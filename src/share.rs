@@ -0,0 +1,138 @@
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Upload endpoint of the 0x0.st file host.
+pub const ZERO_X_ZERO_URL: &str = "https://0x0.st";
+
+/// Resolves a provider name to its upload endpoint.
+///
+/// # Arguments
+///
+/// * `provider` - `0x0.st`, or `custom` to use `custom_url`.
+/// * `custom_url` - The endpoint used by the `custom` provider.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::share::provider_url;
+///
+/// assert_eq!(provider_url("0x0.st", None).unwrap(), "https://0x0.st");
+/// assert_eq!(
+///     provider_url("custom", Some("https://paste.example/upload")).unwrap(),
+///     "https://paste.example/upload"
+/// );
+/// assert!(provider_url("custom", None).is_err());
+/// ```
+pub fn provider_url(provider: &str, custom_url: Option<&str>) -> io::Result<String> {
+    match (provider.to_lowercase().as_str(), custom_url) {
+        ("0x0.st" | "0x0", _) => Ok(ZERO_X_ZERO_URL.to_string()),
+        ("custom", Some(url)) => Ok(url.to_string()),
+        ("custom", None) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The custom provider needs an upload URL (--url)",
+        )),
+        (other, _) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Unknown share provider: {}", other),
+        )),
+    }
+}
+
+/// Builds a `multipart/form-data` body holding a single file field.
+///
+/// # Returns
+///
+/// The boundary, to be announced in the `Content-Type` header, and the body.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::share::multipart_body;
+///
+/// let (boundary, body) = multipart_body("file", "stego.png", b"data");
+/// let body = String::from_utf8(body).unwrap();
+/// assert!(body.starts_with(&format!("--{}\r\n", boundary)));
+/// assert!(body.contains("name=\"file\"; filename=\"stego.png\""));
+/// assert!(body.ends_with(&format!("\r\ndata\r\n--{}--\r\n", boundary)));
+/// ```
+pub fn multipart_body(field: &str, file_name: &str, data: &[u8]) -> (String, Vec<u8>) {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let boundary = format!("----stegano{:x}", nanos);
+    let mut body = Vec::with_capacity(data.len() + 256);
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n",
+            boundary,
+            field,
+            file_name.replace('"', "")
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    (boundary, body)
+}
+
+/// Uploads a file to a paste/file host and returns the link it answered with.
+///
+/// The file is sent as is: a stego file already carries its payload encrypted, and the key
+/// never leaves the machine.
+///
+/// # Arguments
+///
+/// * `url` - The upload endpoint.
+/// * `field` - The form field the host expects the file in.
+/// * `path` - The file to upload.
+///
+/// # Returns
+///
+/// A `Result` containing the link, or an `io::Error` if the upload failed.
+pub fn upload(url: &str, field: &str, path: &str) -> io::Result<String> {
+    let data = std::fs::read(path)?;
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "stego.bin".to_string());
+    let (boundary, body) = multipart_body(field, &file_name, &data);
+    let mut response = ureq::post(url)
+        .header("User-Agent", concat!("stegano/", env!("CARGO_PKG_VERSION")))
+        .content_type(format!("multipart/form-data; boundary={}", boundary))
+        .send(&body[..])
+        .map_err(io::Error::other)?;
+    let link = response
+        .body_mut()
+        .read_to_string()
+        .map_err(io::Error::other)?;
+    Ok(link.trim().to_string())
+}
+
+/// Formats the command a recipient runs to fetch and decrypt a shared stego file.
+///
+/// The key is left as a placeholder so that it can be sent over a separate channel.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::share::decrypt_command;
+///
+/// assert_eq!(
+///     decrypt_command("https://0x0.st/abc.png", "png", "auto", "aes"),
+///     "curl -sSLo stego.png 'https://0x0.st/abc.png' && \
+///      stegano decrypt -i stego.png -o restored.png -t png -m auto -a aes -k '<KEY>'"
+/// );
+/// ```
+pub fn decrypt_command(link: &str, file_type: &str, method: &str, algorithm: &str) -> String {
+    format!(
+        "curl -sSLo stego.png '{}' && \
+         stegano decrypt -i stego.png -o restored.png -t {} -m {} -a {} -k '<KEY>'",
+        link.replace('\'', "%27"),
+        file_type.to_lowercase(),
+        method.to_lowercase(),
+        algorithm.to_lowercase()
+    )
+}
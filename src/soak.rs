@@ -0,0 +1,452 @@
+use crate::append::write_appended_linked;
+use crate::cancel::{register_temp_file, unregister_temp_file, CancellationToken};
+use crate::codec::{decode_bytes, encode_bytes, CodecOptions};
+use crate::header::PRECISION_TAG_LEN;
+use crate::png::{encode_grayscale, read_chunks};
+use crate::polyglot::{extract_polyglot, write_polyglot};
+use crate::precision::PRECISION_CAPACITY;
+use crate::prng::Prng;
+use crate::provenance::provenance_chain;
+use crate::spill::SpillBuffer;
+use crate::svg::SvgFile;
+use crate::tiff::TiffFile;
+use crate::utils::encrypt_stream;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+/// Carrier types and methods exercised by the soak test.
+pub const SOAK_CASES: [(&str, &str); 20] = [
+    ("png", "auto"),
+    ("png", "append"),
+    ("png", "precision"),
+    ("png", "lsb"),
+    ("tiff", "tag"),
+    ("tiff", "strip"),
+    ("tiff", "append"),
+    ("ico", "slack"),
+    ("ico", "append"),
+    ("mp4", "free"),
+    ("mp4", "uuid"),
+    ("mp3", "priv"),
+    ("mp3", "geob"),
+    ("flac", "application"),
+    ("flac", "padding"),
+    ("svg", "metadata"),
+    ("svg", "whitespace"),
+    ("svg", "attributes"),
+    ("text", "zerowidth"),
+    ("text", "whitespace"),
+];
+
+/// Resident memory allowed to accumulate after the warm-up before a leak is reported.
+const RSS_GROWTH_LIMIT: u64 = 64 << 20;
+
+/// Outcome of a soak run.
+#[derive(Debug, Clone, Default)]
+pub struct SoakReport {
+    /// Number of completed iterations.
+    pub iterations: u64,
+    /// Number of embed/extract round trips performed.
+    pub round_trips: u64,
+    /// Descriptions of the failed round trips, panics and leaks.
+    pub failures: Vec<String>,
+    /// Resident memory after the warm-up and at the end, in bytes, where available.
+    pub rss: Option<(u64, u64)>,
+    /// Open file descriptors after the warm-up and at the end, where available.
+    pub open_files: Option<(u64, u64)>,
+}
+
+/// Reads the resident set size of the process, on Linux.
+fn resident_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Counts the open file descriptors of the process, on Linux.
+fn open_files() -> Option<u64> {
+    Some(fs::read_dir("/proc/self/fd").ok()?.count() as u64)
+}
+
+/// Builds a random grayscale PNG, at least `min_side` pixels wide and high.
+fn synthetic_png(rng: &mut Prng, min_side: usize) -> Vec<u8> {
+    let width = min_side.max(1) + rng.below(64) as usize;
+    let height = min_side.max(1) + rng.below(64) as usize;
+    let mut pixels = vec![0u8; width * height];
+    rng.fill(&mut pixels);
+    encode_grayscale(width, height, &pixels).expect("Encoding to a Vec never fails")
+}
+
+/// Builds a random single-strip, 8-bit grayscale TIFF in either byte order.
+fn synthetic_tiff(rng: &mut Prng) -> Vec<u8> {
+    let big_endian = rng.below(2) == 1;
+    let u16_bytes = |v: u16| {
+        if big_endian {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+    let u32_bytes = |v: u32| {
+        if big_endian {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+    let (width, height) = (1 + rng.below(32) as u32, 1 + rng.below(32) as u32);
+    let strip_len = width * height;
+    // (tag, type, value): SHORT values are left-aligned in the 4-byte field.
+    let entries: [(u16, u16, u32); 6] = [
+        (256, 4, width),
+        (257, 4, height),
+        (258, 3, 8),
+        (273, 4, 8 + 2 + 6 * 12 + 4),
+        (278, 4, height),
+        (279, 4, strip_len),
+    ];
+    let mut data = Vec::new();
+    data.extend_from_slice(if big_endian { b"MM" } else { b"II" });
+    data.extend_from_slice(&u16_bytes(42));
+    data.extend_from_slice(&u32_bytes(8));
+    data.extend_from_slice(&u16_bytes(entries.len() as u16));
+    for (tag, field_type, value) in entries {
+        data.extend_from_slice(&u16_bytes(tag));
+        data.extend_from_slice(&u16_bytes(field_type));
+        data.extend_from_slice(&u32_bytes(1));
+        if field_type == 3 {
+            data.extend_from_slice(&u16_bytes(value as u16));
+            data.extend_from_slice(&[0, 0]);
+        } else {
+            data.extend_from_slice(&u32_bytes(value));
+        }
+    }
+    data.extend_from_slice(&u32_bytes(0));
+    let start = data.len();
+    data.resize(start + strip_len as usize, 0);
+    rng.fill(&mut data[start..]);
+    data
+}
+
+/// Builds a random single-image ICO.
+fn synthetic_ico(rng: &mut Prng) -> Vec<u8> {
+    let size = 1 + rng.below(256) as u32;
+    let mut data = vec![0, 0, 1, 0, 1, 0, 16, 16, 0, 0, 1, 0, 32, 0];
+    data.extend_from_slice(&size.to_le_bytes());
+    data.extend_from_slice(&22u32.to_le_bytes());
+    data.resize(22 + size as usize, 0);
+    rng.fill(&mut data[22..]);
+    data
+}
+
+/// Builds a random MP4 made of an `ftyp` box and an `mdat` box.
+fn synthetic_mp4(rng: &mut Prng) -> Vec<u8> {
+    let size = 1 + rng.below(256) as u32;
+    let mut data = b"\0\0\0\x10ftypisom\0\0\x02\0".to_vec();
+    data.extend_from_slice(&(8 + size).to_be_bytes());
+    data.extend_from_slice(b"mdat");
+    data.resize(24 + size as usize, 0);
+    rng.fill(&mut data[24..]);
+    data
+}
+
+/// Builds a random MP3: an ID3v2.3 tag with a title and padding, then random audio behind a
+/// frame sync.
+fn synthetic_mp3(rng: &mut Prng) -> Vec<u8> {
+    let padding = rng.below(64) as u8;
+    let mut data = b"ID3\x03\0\0\0\0\0".to_vec();
+    data.push(16 + padding);
+    data.extend_from_slice(b"TIT2\0\0\0\x06\0\0\0Title");
+    data.resize(26 + padding as usize, 0);
+    data.extend_from_slice(&[0xFF, 0xFB]);
+    let audio = data.len();
+    data.resize(audio + 1 + rng.below(256) as usize, 0);
+    rng.fill(&mut data[audio..]);
+    data
+}
+
+/// Builds a random FLAC: a STREAMINFO block, padding of random length, then random audio
+/// behind a frame sync.
+fn synthetic_flac(rng: &mut Prng) -> Vec<u8> {
+    let padding = rng.below(8192) as usize;
+    let mut data = b"fLaC\0\0\0\x22".to_vec();
+    data.resize(42, 0);
+    rng.fill(&mut data[8..]);
+    data.push(0x81);
+    data.extend_from_slice(&(padding as u32).to_be_bytes()[1..]);
+    data.resize(46 + padding, 0);
+    data.extend_from_slice(&[0xFF, 0xF8]);
+    let audio = data.len();
+    data.resize(audio + 1 + rng.below(256) as usize, 0);
+    rng.fill(&mut data[audio..]);
+    data
+}
+
+/// Builds a random SVG: enough lines, with CRLF or LF endings, and elements with enough
+/// attributes for the largest payloads with every method.
+fn synthetic_svg(rng: &mut Prng) -> Vec<u8> {
+    let newline = if rng.below(2) == 1 { "\r\n" } else { "\n" };
+    let mut data = format!("<svg xmlns=\"http://www.w3.org/2000/svg\">{}", newline);
+    for _ in 0..4352 + rng.below(256) {
+        data.push_str("<rect");
+        for attribute in 0..12 {
+            data.push_str(&format!(" a{}=\"{:x}\"", attribute, rng.next_u64()));
+        }
+        data.push_str("/>");
+        data.push_str(newline);
+    }
+    data.push_str("</svg>");
+    data.into_bytes()
+}
+
+/// Builds a random text: enough lines of random words, with CRLF or LF endings, for the
+/// largest payloads in trailing whitespace.
+fn synthetic_text(rng: &mut Prng) -> Vec<u8> {
+    const WORDS: [&str; 8] = [
+        "the", "quick", "brown", "fox", "jumps", "über", "lazy", "dog",
+    ];
+    let newline = if rng.below(2) == 1 { "\r\n" } else { "\n" };
+    let mut text = String::new();
+    for _ in 0..4352 + rng.below(256) {
+        let words: Vec<&str> = (0..1 + rng.below(12))
+            .map(|_| WORDS[rng.below(WORDS.len() as u64) as usize])
+            .collect();
+        text.push_str(&words.join(" "));
+        text.push_str(newline);
+    }
+    text.into_bytes()
+}
+
+/// Returns a random payload that doesn't end with a NUL byte, which AES padding would eat.
+fn random_payload(rng: &mut Prng) -> Vec<u8> {
+    let mut payload = vec![0u8; 1 + rng.below(4096) as usize];
+    rng.fill(&mut payload);
+    if let Some(last) = payload.last_mut() {
+        *last |= 1;
+    }
+    payload
+}
+
+/// Checks the carrier left after removing a payload.
+///
+/// TIFF tag and strip payloads relocate the IFD or pad the file to a word boundary, so the
+/// restored file only has to be a clean TIFF. Precision and LSB payloads overwrite the
+/// original low bits, so the file only has to stay a valid PNG, and SVG attribute payloads
+/// keep the reordered attributes, so the file only has to stay a valid SVG. Every other method
+/// must give the original bytes back.
+fn restored(opts: &CodecOptions, original: &[u8], carrier: &[u8]) -> bool {
+    match (opts.file_type.as_str(), opts.method.as_str()) {
+        ("tiff", "tag" | "strip") => {
+            TiffFile::parse(carrier.to_vec()).is_ok_and(|tiff| tiff.extract_payload().is_err())
+        }
+        ("png", "precision" | "lsb") => read_chunks(carrier).is_ok(),
+        ("svg", "attributes") => SvgFile::parse(carrier).is_ok(),
+        _ => carrier == original,
+    }
+}
+
+/// Runs one iteration: every case of `SOAK_CASES` in memory, a polyglot, a provenance chain
+/// and a round trip through the file system with a spilling buffer.
+fn soak_iteration(rng: &mut Prng, iteration: u64, report: &mut SoakReport) {
+    for (file_type, method) in SOAK_CASES {
+        let carrier = match file_type {
+            // 32x32 pixels leave room for 124 bytes of LSB payload.
+            "png" if method == "lsb" => synthetic_png(rng, 32),
+            "png" => synthetic_png(rng, 1),
+            "tiff" => synthetic_tiff(rng),
+            "mp4" => synthetic_mp4(rng),
+            "mp3" => synthetic_mp3(rng),
+            "flac" => synthetic_flac(rng),
+            "svg" => synthetic_svg(rng),
+            "text" => synthetic_text(rng),
+            _ => synthetic_ico(rng),
+        };
+        let mut payload = random_payload(rng);
+        // The precision method only fits a few bytes of length-preserving XOR and its check byte.
+        let micro = method == "precision";
+        if micro {
+            payload.drain(
+                ..payload
+                    .len()
+                    .saturating_sub(PRECISION_CAPACITY - PRECISION_TAG_LEN),
+            );
+        } else if method == "lsb" {
+            payload.drain(..payload.len().saturating_sub(96));
+        }
+        let opts = CodecOptions {
+            file_type: file_type.to_string(),
+            method: method.to_string(),
+            algorithm: String::from(if micro || rng.below(2) == 1 {
+                "xor"
+            } else {
+                "aes"
+            }),
+            key: format!("key-{}", rng.next_u64()),
+            ..CodecOptions::default()
+        };
+        let outcome =
+            encode_bytes(&carrier, &payload, &opts).and_then(|stego| decode_bytes(&stego, &opts));
+        report.round_trips += 1;
+        match outcome {
+            Ok(decoded)
+                if decoded.payload == payload && restored(&opts, &carrier, &decoded.carrier) => {}
+            Ok(_) => report.failures.push(format!(
+                "iteration {}: {}/{}/{} round trip mismatch",
+                iteration, file_type, method, opts.algorithm
+            )),
+            Err(err) => report.failures.push(format!(
+                "iteration {}: {}/{}/{}: {}",
+                iteration, file_type, method, opts.algorithm, err
+            )),
+        }
+    }
+
+    let carrier = synthetic_png(rng, 1);
+    let payload = random_payload(rng);
+    let mut stego = Vec::new();
+    let polyglot = write_polyglot(
+        Cursor::new(&carrier),
+        Cursor::new(&payload),
+        payload.len() as u64,
+        "secret.bin",
+        &mut stego,
+    )
+    .and_then(|_| extract_polyglot(&mut Cursor::new(&stego)));
+    report.round_trips += 1;
+    if !matches!(polyglot, Ok(entry) if entry.data == payload) {
+        report.failures.push(format!(
+            "iteration {}: polyglot round trip failed",
+            iteration
+        ));
+    }
+
+    let mut chained = synthetic_png(rng, 1);
+    let edits = 1 + rng.below(3) as usize;
+    for _ in 0..edits {
+        let payload = random_payload(rng);
+        let mut next = Vec::new();
+        let linked =
+            write_appended_linked(&chained[..], &payload[..], payload.len() as u64, &mut next);
+        if linked.is_err() {
+            break;
+        }
+        chained = next;
+    }
+    report.round_trips += 1;
+    match provenance_chain(&mut Cursor::new(&chained)) {
+        Ok(links) if links.len() == edits && links.iter().all(|l| l.verified() == Some(true)) => {}
+        _ => report
+            .failures
+            .push(format!("iteration {}: provenance chain broken", iteration)),
+    }
+
+    // Go through real files and a spilling buffer to catch handle and temp file leaks.
+    let path = std::env::temp_dir().join(format!("stegano-soak-{}.bin", std::process::id()));
+    let payload = random_payload(rng);
+    let mut spill = SpillBuffer::new(rng.below(2048) as usize);
+    register_temp_file(&path);
+    let outcome = encrypt_stream("xor", "soak", &payload[..], &mut spill)
+        .and_then(|_| spill.rewind())
+        .and_then(|_| {
+            let mut file = fs::File::create(&path)?;
+            std::io::copy(&mut spill, &mut file)?;
+            file.flush()
+        })
+        .and_then(|_| fs::read(&path));
+    let _ = fs::remove_file(&path);
+    unregister_temp_file(&path);
+    report.round_trips += 1;
+    match outcome {
+        Ok(data) if data.len() == payload.len() => {}
+        _ => report
+            .failures
+            .push(format!("iteration {}: file round trip failed", iteration)),
+    }
+}
+
+/// Repeatedly embeds and extracts random payloads into random synthetic carriers.
+///
+/// Each iteration runs every case of `SOAK_CASES` plus a polyglot, a provenance chain and a
+/// file round trip. Panics are caught and reported as failures. After a warm-up of a tenth of
+/// the run, resident memory and open file descriptors are sampled, and growth beyond a small
+/// allowance at the end is reported as a leak.
+///
+/// # Arguments
+///
+/// * `iterations` - The number of iterations to run.
+/// * `seed` - The seed of the random generator, so that failures can be replayed.
+/// * `cancel` - Stops the run after the current iteration when cancelled.
+/// * `progress` - Called after each iteration with the iteration number and the report so far.
+///
+/// # Returns
+///
+/// The `SoakReport` of the run.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::cancel::CancellationToken;
+/// use stegano::soak::run_soak;
+///
+/// let report = run_soak(3, 7, &CancellationToken::new(), |_, _| {});
+/// assert_eq!(report.iterations, 3);
+/// assert!(report.failures.is_empty(), "{:?}", report.failures);
+/// ```
+pub fn run_soak<F: FnMut(u64, &SoakReport)>(
+    iterations: u64,
+    seed: u64,
+    cancel: &CancellationToken,
+    mut progress: F,
+) -> SoakReport {
+    let mut rng = Prng::new(seed);
+    let mut report = SoakReport::default();
+    let warm_up = iterations / 10;
+    let mut baseline = None;
+    for iteration in 0..iterations {
+        if cancel.is_cancelled() {
+            break;
+        }
+        if iteration == warm_up {
+            baseline = Some((resident_memory(), open_files()));
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            soak_iteration(&mut rng, iteration, &mut report)
+        }));
+        if let Err(cause) = result {
+            let message = cause
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| cause.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            report
+                .failures
+                .push(format!("iteration {}: panic: {}", iteration, message));
+        }
+        report.iterations = iteration + 1;
+        progress(iteration, &report);
+    }
+
+    if let Some((rss, files)) = baseline {
+        report.rss = rss.zip(resident_memory());
+        report.open_files = files.zip(open_files());
+    }
+    if let Some((start, end)) = report.rss {
+        if end > start + RSS_GROWTH_LIMIT {
+            report.failures.push(format!(
+                "resident memory grew from {} to {} bytes",
+                start, end
+            ));
+        }
+    }
+    if let Some((start, end)) = report.open_files {
+        if end > start {
+            report.failures.push(format!(
+                "open file descriptors grew from {} to {}",
+                start, end
+            ));
+        }
+    }
+    report
+}
@@ -0,0 +1,188 @@
+use crate::cancel::{register_temp_file, unregister_temp_file};
+use std::fs::{remove_file, File, OpenOptions};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SPILL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A read/write buffer that keeps its content in memory up to a cap, then spills to a temp file.
+///
+/// Payload transforms write their output into a `SpillBuffer` so that huge payloads never have
+/// to be held in memory at once. The temp file, if any, is removed when the buffer is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{Read, Write};
+/// use stegano::spill::SpillBuffer;
+///
+/// let mut buffer = SpillBuffer::new(4);
+/// buffer.write_all(b"hello world").unwrap();
+/// assert!(buffer.is_spilled());
+///
+/// buffer.rewind().unwrap();
+/// let mut content = String::new();
+/// buffer.read_to_string(&mut content).unwrap();
+/// assert_eq!(content, "hello world");
+/// ```
+#[derive(Debug)]
+pub struct SpillBuffer {
+    /// Maximum number of bytes kept in memory.
+    limit: usize,
+    /// In-memory storage, used until the limit is exceeded.
+    memory: Cursor<Vec<u8>>,
+    /// Temp file storage and its path, used once the limit is exceeded.
+    file: Option<(File, PathBuf)>,
+}
+
+impl SpillBuffer {
+    /// Creates an empty buffer holding at most `limit` bytes in memory.
+    pub fn new(limit: usize) -> Self {
+        SpillBuffer {
+            limit,
+            memory: Cursor::new(Vec::new()),
+            file: None,
+        }
+    }
+
+    /// Returns true if the content has been moved to a temp file.
+    pub fn is_spilled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Returns the total number of bytes stored in the buffer.
+    pub fn len(&self) -> io::Result<u64> {
+        match &self.file {
+            Some((file, _)) => Ok(file.metadata()?.len()),
+            None => Ok(self.memory.get_ref().len() as u64),
+        }
+    }
+
+    /// Returns true if the buffer holds no bytes.
+    pub fn is_empty(&self) -> io::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Moves the cursor back to the start of the buffer.
+    pub fn rewind(&mut self) -> io::Result<()> {
+        self.seek(SeekFrom::Start(0)).map(|_| ())
+    }
+
+    /// Reads the whole content of the buffer into memory, regardless of the limit.
+    pub fn to_vec(&mut self) -> io::Result<Vec<u8>> {
+        match &mut self.file {
+            Some((file, _)) => {
+                let position = file.stream_position()?;
+                let mut data = Vec::new();
+                file.seek(SeekFrom::Start(0))?;
+                file.read_to_end(&mut data)?;
+                file.seek(SeekFrom::Start(position))?;
+                Ok(data)
+            }
+            None => Ok(self.memory.get_ref().clone()),
+        }
+    }
+
+    /// Moves the in-memory content to a fresh temp file.
+    fn spill(&mut self) -> io::Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "stegano-{}-{}.spill",
+            std::process::id(),
+            SPILL_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        register_temp_file(&path);
+        file.write_all(self.memory.get_ref())?;
+        file.seek(SeekFrom::Start(self.memory.position()))?;
+        self.memory = Cursor::new(Vec::new());
+        self.file = Some((file, path));
+        Ok(())
+    }
+}
+
+impl Write for SpillBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.file.is_none() && self.memory.get_ref().len() + buf.len() > self.limit {
+            self.spill()?;
+        }
+        match &mut self.file {
+            Some((file, _)) => file.write(buf),
+            None => self.memory.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.file {
+            Some((file, _)) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Read for SpillBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.file {
+            Some((file, _)) => file.read(buf),
+            None => self.memory.read(buf),
+        }
+    }
+}
+
+impl Seek for SpillBuffer {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match &mut self.file {
+            Some((file, _)) => file.seek(pos),
+            None => self.memory.seek(pos),
+        }
+    }
+}
+
+impl Drop for SpillBuffer {
+    fn drop(&mut self) {
+        if let Some((_, path)) = self.file.take() {
+            let _ = remove_file(&path);
+            unregister_temp_file(&path);
+        }
+    }
+}
+
+/// Parses a human readable memory size such as `512`, `64K`, `512M` or `2G` into bytes.
+///
+/// Suffixes are binary multiples (K = 1024) and case insensitive; an optional trailing `B` or
+/// `iB` is accepted.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::spill::parse_size;
+///
+/// assert_eq!(parse_size("512").unwrap(), 512);
+/// assert_eq!(parse_size("64k").unwrap(), 64 * 1024);
+/// assert_eq!(parse_size("2GiB").unwrap(), 2 * 1024 * 1024 * 1024);
+/// assert!(parse_size("lots").is_err());
+/// ```
+pub fn parse_size(value: &str) -> Result<usize, String> {
+    let upper = value.trim().to_uppercase();
+    let trimmed = upper
+        .strip_suffix("IB")
+        .or_else(|| upper.strip_suffix('B'))
+        .unwrap_or(&upper);
+    let (digits, multiplier) = match trimmed.chars().last() {
+        Some('K') => (&trimmed[..trimmed.len() - 1], 1usize << 10),
+        Some('M') => (&trimmed[..trimmed.len() - 1], 1 << 20),
+        Some('G') => (&trimmed[..trimmed.len() - 1], 1 << 30),
+        Some('T') => (&trimmed[..trimmed.len() - 1], 1 << 40),
+        _ => (trimmed, 1),
+    };
+    digits
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("Invalid memory size: {}", value))
+}
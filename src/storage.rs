@@ -0,0 +1,469 @@
+use crate::mapped::MappedFile;
+use std::fs::File;
+use std::io::{self, Cursor, ErrorKind, Read, Seek, SeekFrom};
+#[cfg(feature = "http")]
+use {
+    crate::json::rfc3339,
+    crate::provenance::digest_hex,
+    sha2::{Digest, Sha256},
+    std::time::SystemTime,
+};
+
+/// The `User-Agent` of the requests made by the `http` and `s3` backends.
+#[cfg(feature = "http")]
+const USER_AGENT: &str = concat!("stegano/", env!("CARGO_PKG_VERSION"));
+
+/// A store that carriers are read from and outputs written to, for inputs and outputs named
+/// by a URL such as `https://example.com/cover.png` or `s3://bucket/key` rather than a path.
+///
+/// `backend` picks the implementation of a URL scheme. Other stores plug in by implementing
+/// the trait: the parsers only need the `Read` of `open`, and outputs are finished in memory
+/// before `store` is called, so a backend never sees a partial file.
+pub trait Storage {
+    /// Opens the object at `location` for reading, streaming it rather than downloading it
+    /// first.
+    fn open(&self, location: &str) -> io::Result<Box<dyn Read + Send>>;
+
+    /// Writes `data` to `location`, replacing the object stored there.
+    fn store(&self, location: &str, data: &[u8]) -> io::Result<()>;
+}
+
+/// Tells whether a path names a remote object, a URL with a scheme such as `https://` or
+/// `s3://`, rather than a local file. Single letter schemes are Windows drives.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::storage::is_remote;
+///
+/// assert!(is_remote("https://example.com/cover.png"));
+/// assert!(is_remote("s3://bucket/covers/cat.png"));
+/// assert!(!is_remote("covers/cat.png"));
+/// assert!(!is_remote("C://covers/cat.png"));
+/// ```
+pub fn is_remote(path: &str) -> bool {
+    path.split_once("://").is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Returns the backend of a remote location, by its URL scheme.
+///
+/// `http://` and `https://` URLs are read with GET and written with PUT. `s3://bucket/key`
+/// objects are signed with the `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY` of the
+/// environment; see `S3Storage::from_env`.
+///
+/// # Returns
+///
+/// A `Result` containing the backend, an `InvalidInput` error for other schemes, or an
+/// `Unsupported` error without the `http` feature.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use stegano::storage::backend;
+///
+/// let result = backend("https://example.com/cover.png");
+/// assert_eq!(result.is_ok(), cfg!(feature = "http"));
+/// let result = backend("ftp://example.com/cover.png");
+/// assert_eq!(result.err().map(|err| err.kind()), Some(ErrorKind::InvalidInput));
+/// ```
+pub fn backend(location: &str) -> io::Result<Box<dyn Storage>> {
+    let scheme = location
+        .split_once("://")
+        .map_or("", |(scheme, _)| scheme)
+        .to_lowercase();
+    match scheme.as_str() {
+        #[cfg(feature = "http")]
+        "http" | "https" => Ok(Box::new(HttpStorage)),
+        #[cfg(feature = "http")]
+        "s3" => Ok(Box::new(S3Storage::from_env()?)),
+        #[cfg(not(feature = "http"))]
+        "http" | "https" | "s3" => Err(unsupported(location)),
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Unsupported location {}, expected a path or an http, https or s3 URL",
+                location
+            ),
+        )),
+    }
+}
+
+/// Opens an input for streaming: a local file, or a remote object read as it downloads.
+pub fn open_input(path: &str) -> io::Result<Box<dyn Read + Send>> {
+    if is_remote(path) {
+        backend(path)?.open(path)
+    } else {
+        Ok(Box::new(File::open(path)?))
+    }
+}
+
+/// Reads a whole input, a local file or a remote object, into memory.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::storage::read_input;
+///
+/// let path = std::env::temp_dir().join("stegano-read-input-doc.bin");
+/// std::fs::write(&path, b"\x89PNG").unwrap();
+/// assert_eq!(read_input(path.to_str().unwrap()).unwrap(), b"\x89PNG");
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn read_input(path: &str) -> io::Result<Vec<u8>> {
+    if !is_remote(path) {
+        return std::fs::read(path);
+    }
+    let mut data = Vec::new();
+    backend(path)?.open(path)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Opens an input for scanning, mapping local files as `MappedFile::open` does. Remote
+/// objects are read into memory.
+pub fn map_input(path: &str) -> io::Result<MappedFile> {
+    if is_remote(path) {
+        read_input(path).map(MappedFile::Read)
+    } else {
+        MappedFile::open(path)
+    }
+}
+
+/// An input read at arbitrary offsets, such as the trailer of an appended payload.
+///
+/// Local files are read in place; remote objects, whose streams can't seek, are read into
+/// memory first.
+#[derive(Debug)]
+pub enum Input {
+    /// A local file.
+    File(File),
+    /// The contents of a remote object.
+    Memory(Cursor<Vec<u8>>),
+}
+
+impl Input {
+    /// Opens a local file or reads a remote object.
+    pub fn open(path: &str) -> io::Result<Input> {
+        if is_remote(path) {
+            read_input(path).map(|data| Input::Memory(Cursor::new(data)))
+        } else {
+            File::open(path).map(Input::File)
+        }
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::File(file) => file.read(buf),
+            Input::Memory(cursor) => cursor.read(buf),
+        }
+    }
+}
+
+impl Seek for Input {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Input::File(file) => file.seek(pos),
+            Input::Memory(cursor) => cursor.seek(pos),
+        }
+    }
+}
+
+/// The backend of `http://` and `https://` URLs: objects are read with GET and written with
+/// PUT, which WebDAV shares and presigned upload URLs accept.
+#[cfg(feature = "http")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpStorage;
+
+#[cfg(feature = "http")]
+impl Storage for HttpStorage {
+    fn open(&self, location: &str) -> io::Result<Box<dyn Read + Send>> {
+        let response = ureq::get(location)
+            .header("User-Agent", USER_AGENT)
+            .call()
+            .map_err(|err| request_error(location, err))?;
+        Ok(Box::new(response.into_body().into_reader()))
+    }
+
+    fn store(&self, location: &str, data: &[u8]) -> io::Result<()> {
+        ureq::put(location)
+            .header("User-Agent", USER_AGENT)
+            .send(data)
+            .map_err(|err| request_error(location, err))?;
+        Ok(())
+    }
+}
+
+/// The backend of `s3://bucket/key` URLs, for Amazon S3 and compatible services, with
+/// requests signed by AWS Signature Version 4.
+#[cfg(feature = "http")]
+#[derive(Clone)]
+pub struct S3Storage {
+    /// The endpoint of an S3-compatible service such as MinIO, addressed path-style, or
+    /// `None` for Amazon S3 itself.
+    pub endpoint: Option<String>,
+    /// The region requests are signed for.
+    pub region: String,
+    /// The access key ID.
+    pub access_key: String,
+    /// The secret access key.
+    pub secret_key: String,
+    /// The session token of temporary credentials.
+    pub session_token: Option<String>,
+}
+
+#[cfg(feature = "http")]
+impl S3Storage {
+    /// Reads the credentials from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+    /// `AWS_SESSION_TOKEN`, the region from `AWS_REGION` or `AWS_DEFAULT_REGION` (default is
+    /// `us-east-1`), and the endpoint of a compatible service from `AWS_ENDPOINT_URL_S3` or
+    /// `AWS_ENDPOINT_URL`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the backend, or an `InvalidInput` error if the credentials are
+    /// missing.
+    pub fn from_env() -> io::Result<S3Storage> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let (Some(access_key), Some(secret_key)) =
+            (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY"))
+        else {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "s3:// locations need the AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY environment variables",
+            ));
+        };
+        Ok(S3Storage {
+            endpoint: var("AWS_ENDPOINT_URL_S3").or_else(|| var("AWS_ENDPOINT_URL")),
+            region: var("AWS_REGION")
+                .or_else(|| var("AWS_DEFAULT_REGION"))
+                .unwrap_or_else(|| String::from("us-east-1")),
+            access_key,
+            secret_key,
+            session_token: var("AWS_SESSION_TOKEN"),
+        })
+    }
+
+    /// Returns the HTTPS URL of an `s3://bucket/key` location, virtual-hosted on Amazon S3
+    /// and path-style on a custom endpoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::storage::S3Storage;
+    ///
+    /// let mut s3 = S3Storage {
+    ///     endpoint: None,
+    ///     region: String::from("eu-west-1"),
+    ///     access_key: String::from("AKIDEXAMPLE"),
+    ///     secret_key: String::from("secret"),
+    ///     session_token: None,
+    /// };
+    /// assert_eq!(
+    ///     s3.url("s3://covers/2024/cat 1.png").unwrap(),
+    ///     "https://covers.s3.eu-west-1.amazonaws.com/2024/cat%201.png"
+    /// );
+    /// s3.endpoint = Some(String::from("http://localhost:9000/"));
+    /// assert_eq!(s3.url("s3://covers/cat.png").unwrap(), "http://localhost:9000/covers/cat.png");
+    /// assert!(s3.url("s3://covers").is_err());
+    /// ```
+    pub fn url(&self, location: &str) -> io::Result<String> {
+        let (scheme, host, path) = self.address(location)?;
+        Ok(format!("{}://{}{}", scheme, host, path))
+    }
+
+    /// Splits a location into the scheme, host and encoded path of its URL.
+    fn address(&self, location: &str) -> io::Result<(String, String, String)> {
+        let (bucket, key) = location
+            .split_once("://")
+            .and_then(|(_, rest)| rest.split_once('/'))
+            .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+            .ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid S3 location {}, expected s3://bucket/key", location),
+                )
+            })?;
+        let key = uri_encode(key);
+        Ok(match &self.endpoint {
+            Some(endpoint) => {
+                let (scheme, host) = endpoint.split_once("://").unwrap_or(("https", endpoint));
+                (
+                    scheme.to_string(),
+                    host.trim_end_matches('/').to_string(),
+                    format!("/{}/{}", uri_encode(bucket), key),
+                )
+            }
+            None => (
+                String::from("https"),
+                format!("{}.s3.{}.amazonaws.com", bucket, self.region),
+                format!("/{}", key),
+            ),
+        })
+    }
+
+    /// Returns the URL of a request and the headers that sign it.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The HTTP method, `GET` or `PUT`.
+    /// * `location` - The `s3://bucket/key` location.
+    /// * `body` - The body of the request, empty for GET.
+    /// * `time` - The time of the request, which S3 allows to be 15 minutes off.
+    fn sign(
+        &self,
+        method: &str,
+        location: &str,
+        body: &[u8],
+        time: SystemTime,
+    ) -> io::Result<(String, Vec<(&'static str, String)>)> {
+        let (scheme, host, path) = self.address(location)?;
+        let timestamp: String = rfc3339(time)
+            .chars()
+            .filter(|c| !matches!(c, '-' | ':'))
+            .collect();
+        let date = &timestamp[..8];
+        let mut headers = vec![
+            ("host", host.clone()),
+            ("x-amz-content-sha256", digest_hex(&Sha256::digest(body))),
+            ("x-amz-date", timestamp.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method, path, canonical_headers, signed_headers, headers[1].1
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            digest_hex(&Sha256::digest(canonical_request))
+        );
+        let signing_key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+            hmac_sha256(
+                format!("AWS4{}", self.secret_key).as_bytes(),
+                date.as_bytes(),
+            ),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+        let signature = digest_hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+        headers.remove(0);
+        headers.push((
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                self.access_key, scope, signed_headers, signature
+            ),
+        ));
+        Ok((format!("{}://{}{}", scheme, host, path), headers))
+    }
+}
+
+#[cfg(feature = "http")]
+impl Storage for S3Storage {
+    fn open(&self, location: &str) -> io::Result<Box<dyn Read + Send>> {
+        let (url, headers) = self.sign("GET", location, &[], SystemTime::now())?;
+        let request = headers
+            .into_iter()
+            .fold(ureq::get(&url), |request, (name, value)| {
+                request.header(name, value)
+            });
+        let response = request
+            .header("User-Agent", USER_AGENT)
+            .call()
+            .map_err(|err| request_error(location, err))?;
+        Ok(Box::new(response.into_body().into_reader()))
+    }
+
+    fn store(&self, location: &str, data: &[u8]) -> io::Result<()> {
+        let (url, headers) = self.sign("PUT", location, data, SystemTime::now())?;
+        let request = headers
+            .into_iter()
+            .fold(ureq::put(&url), |request, (name, value)| {
+                request.header(name, value)
+            });
+        request
+            .header("User-Agent", USER_AGENT)
+            .send(data)
+            .map_err(|err| request_error(location, err))?;
+        Ok(())
+    }
+}
+
+/// Percent-encodes a key for the path of an S3 request, keeping its slashes.
+#[cfg(feature = "http")]
+fn uri_encode(key: &str) -> String {
+    key.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Computes the HMAC-SHA256 of a message.
+#[cfg(feature = "http")]
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// Turns a failed request into an `io::Error`, keeping missing objects and refused
+/// credentials apart.
+#[cfg(feature = "http")]
+fn request_error(location: &str, err: ureq::Error) -> io::Error {
+    let kind = match err {
+        ureq::Error::StatusCode(404) => ErrorKind::NotFound,
+        ureq::Error::StatusCode(401 | 403) => ErrorKind::PermissionDenied,
+        ureq::Error::Io(ref err) => err.kind(),
+        _ => ErrorKind::Other,
+    };
+    io::Error::new(kind, format!("{}: {}", location, err))
+}
+
+/// The error for a URL in a build without the `http` feature.
+#[cfg(not(feature = "http"))]
+fn unsupported(location: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::Unsupported,
+        format!(
+            "{} is a URL, which this build can't read or write; rebuild with --features http",
+            location
+        ),
+    )
+}
@@ -0,0 +1,474 @@
+use crate::chunk_type::ChunkType;
+use crate::flac::FlacFile;
+use crate::ico::IcoFile;
+use crate::mp3::Mp3File;
+use crate::mp4::Mp4File;
+use crate::png::read_chunks;
+use crate::svg::{SvgFile, TagKind};
+use crate::text::TextFile;
+use crate::tiff::TiffFile;
+use crc32_v2::crc32;
+use std::io::{self, ErrorKind};
+
+/// Builds the error returned for spec violations.
+fn violation(format: &str, reason: String) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("Strict mode: {} {}", format, reason),
+    )
+}
+
+/// Checks that a carrier follows the specification of its format, for `--strict`.
+///
+/// The parsers only reject what they can't make sense of, and tolerate a lot that readers
+/// following the specification refuse: bad CRCs, misplaced chunks, data after the end. Strict
+/// mode rejects those too, so crafted or damaged files are caught before anything is done
+/// with them.
+///
+/// # Arguments
+///
+/// * `data` - The bytes of the file.
+/// * `file_type` - The type of the file: PNG, JPEG, TIFF, ICO, MP4, MP3 or FLAC, in any case.
+///
+/// # Returns
+///
+/// A `Result` that is an `InvalidData` error naming the first violation found, or an
+/// `InvalidInput` error for other types.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::encode_grayscale;
+/// use stegano::strict::check_strict;
+///
+/// let mut png = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// assert!(check_strict(&png, "png").is_ok());
+///
+/// // Data after IEND is ignored by readers, but isn't part of the file.
+/// png.extend_from_slice(b"trailing");
+/// assert!(check_strict(&png, "PNG").is_err());
+/// ```
+pub fn check_strict(data: &[u8], file_type: &str) -> io::Result<()> {
+    match file_type.to_lowercase().as_str() {
+        "png" => check_png(data),
+        "jpeg" | "jpg" => check_jpeg(data),
+        "tiff" => check_tiff(data),
+        "ico" => check_ico(data),
+        "mp4" => check_mp4(data),
+        "mp3" => check_mp3(data),
+        "flac" => check_flac(data),
+        "svg" => check_svg(data),
+        "text" => check_text(data),
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("--strict doesn't support {} files", file_type),
+        )),
+    }
+}
+
+/// Checks a PNG file against the PNG specification.
+///
+/// IHDR must come first with valid dimensions and a valid colour type and bit depth pair,
+/// every chunk must have a valid type and CRC, critical chunks must appear in order with the
+/// IDAT chunks consecutive, and the file must end with an empty IEND.
+pub fn check_png(data: &[u8]) -> io::Result<()> {
+    let chunks = read_chunks(data)?;
+    let fail = |reason: String| Err(violation("PNG", reason));
+
+    let Some(ihdr) = chunks.first().filter(|chunk| &chunk.r#type == b"IHDR") else {
+        return fail("doesn't start with IHDR".into());
+    };
+    if ihdr.data.len() != 13 {
+        return fail(format!(
+            "IHDR holds {} bytes instead of 13",
+            ihdr.data.len()
+        ));
+    }
+    let width = u32::from_be_bytes(ihdr.data[0..4].try_into().unwrap());
+    let height = u32::from_be_bytes(ihdr.data[4..8].try_into().unwrap());
+    if width == 0 || height == 0 || width > i32::MAX as u32 || height > i32::MAX as u32 {
+        return fail(format!("dimensions {}x{} are out of range", width, height));
+    }
+    let (bit_depth, color_type) = (ihdr.data[8], ihdr.data[9]);
+    let depths: &[u8] = match color_type {
+        0 => &[1, 2, 4, 8, 16],
+        3 => &[1, 2, 4, 8],
+        2 | 4 | 6 => &[8, 16],
+        _ => return fail(format!("colour type {} is unknown", color_type)),
+    };
+    if !depths.contains(&bit_depth) {
+        return fail(format!(
+            "bit depth {} isn't allowed with colour type {}",
+            bit_depth, color_type
+        ));
+    }
+    if ihdr.data[10] != 0 || ihdr.data[11] != 0 || ihdr.data[12] > 1 {
+        return fail("IHDR names an unknown compression, filter or interlace method".into());
+    }
+
+    let mut seen_plte = false;
+    let mut seen_idat = false;
+    let mut idat_ended = false;
+    for (index, chunk) in chunks.iter().enumerate() {
+        let name = chunk.r#type.to_string();
+        let bytes = chunk.r#type.bytes();
+        if !bytes.iter().all(u8::is_ascii_alphabetic) {
+            return fail(format!("chunk type {:?} isn't four letters", name));
+        }
+        if bytes[2].is_ascii_lowercase() {
+            return fail(format!("chunk type {:?} sets the reserved bit", name));
+        }
+        let end = chunk.offset + 12 + chunk.data.len();
+        let crc = u32::from_be_bytes(data[end - 4..end].try_into().unwrap());
+        if crc32(crc32(0, &bytes), &chunk.data) != crc {
+            return fail(format!(
+                "chunk {:?} at offset {} has a bad CRC",
+                name, chunk.offset
+            ));
+        }
+        match chunk.r#type {
+            ChunkType::Ihdr if index > 0 => return fail("holds a second IHDR".into()),
+            ChunkType::Plte if seen_plte => return fail("holds a second PLTE".into()),
+            ChunkType::Plte if seen_idat => return fail("has PLTE after IDAT".into()),
+            ChunkType::Plte if color_type == 0 || color_type == 4 => {
+                return fail(format!("has PLTE with colour type {}", color_type))
+            }
+            ChunkType::Plte if chunk.data.is_empty() || chunk.data.len() % 3 != 0 => {
+                return fail(format!("PLTE holds {} bytes", chunk.data.len()))
+            }
+            ChunkType::Plte => seen_plte = true,
+            ChunkType::Idat if idat_ended => return fail("IDAT chunks aren't consecutive".into()),
+            ChunkType::Idat if color_type == 3 && !seen_plte => {
+                return fail("IDAT comes before the PLTE colour type 3 needs".into())
+            }
+            ChunkType::Idat => seen_idat = true,
+            ChunkType::Iend if !chunk.data.is_empty() => return fail("IEND isn't empty".into()),
+            _ => idat_ended |= seen_idat,
+        }
+    }
+    if !seen_idat {
+        return fail("has no IDAT chunk".into());
+    }
+    let last = chunks.last().unwrap();
+    if &last.r#type != b"IEND" {
+        return fail("doesn't end with IEND".into());
+    }
+    let end = last.offset + 12;
+    if end != data.len() {
+        return fail(format!("has {} bytes after IEND", data.len() - end));
+    }
+    Ok(())
+}
+
+/// Checks a JPEG file's marker structure.
+///
+/// The file must start with SOI, every segment up to SOS must start with a marker and declare
+/// a length that fits, and the entropy-coded data must end with EOI at the end of the file.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::strict::check_jpeg;
+///
+/// let jpeg = [0xFF, 0xD8, 0xFF, 0xFE, 0x00, 0x04, b'h', b'i', 0xFF, 0xDA, 0x00, 0x02, 0x12, 0xFF, 0xD9];
+/// assert!(check_jpeg(&jpeg).is_ok());
+///
+/// // The comment declares more bytes than the file holds.
+/// assert!(check_jpeg(&[0xFF, 0xD8, 0xFF, 0xFE, 0x40, 0x00, b'h', b'i']).is_err());
+/// ```
+pub fn check_jpeg(data: &[u8]) -> io::Result<()> {
+    let fail = |reason: String| Err(violation("JPEG", reason));
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return fail("doesn't start with SOI".into());
+    }
+    let mut pos = 2;
+    let mut scans = 0;
+    loop {
+        let Some(&[prefix, marker]) = data.get(pos..pos + 2) else {
+            return fail("doesn't end with EOI".into());
+        };
+        if prefix != 0xFF {
+            return fail(format!("expected a marker at offset {}", pos));
+        }
+        match marker {
+            0xD8 => return fail(format!("holds a second SOI at offset {}", pos)),
+            0xD9 if scans == 0 => return fail(format!("ends at offset {} without a scan", pos)),
+            0xD9 if pos + 2 != data.len() => {
+                return fail(format!("has {} bytes after EOI", data.len() - pos - 2))
+            }
+            0xD9 => return Ok(()),
+            // Standalone markers carry no length.
+            0x01 | 0xD0..=0xD7 => {
+                pos += 2;
+                continue;
+            }
+            // Fill bytes before a marker.
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let Some(&[high, low]) = data.get(pos + 2..pos + 4) else {
+            return fail(format!("segment at offset {} is truncated", pos));
+        };
+        let len = u16::from_be_bytes([high, low]) as usize;
+        if len < 2 || pos + 2 + len > data.len() {
+            return fail(format!(
+                "segment at offset {} declares {} bytes, which don't fit",
+                pos, len
+            ));
+        }
+        pos += 2 + len;
+        if marker == 0xDA {
+            scans += 1;
+            // In the entropy-coded data, 0xFF is only followed by stuffing or restart markers.
+            while pos + 1 < data.len()
+                && (data[pos] != 0xFF || matches!(data[pos + 1], 0x00 | 0xD0..=0xD7))
+            {
+                pos += 1;
+            }
+        }
+    }
+}
+
+/// Checks a TIFF file's IFD chain: IFDs start on a word boundary, hold at least one entry,
+/// and list their tags in ascending order.
+pub fn check_tiff(data: &[u8]) -> io::Result<()> {
+    let (_, ifds) = TiffFile::parse_ifds(data)?;
+    let fail = |reason: String| Err(violation("TIFF", reason));
+    if ifds.is_empty() {
+        return fail("has no IFD".into());
+    }
+    for ifd in &ifds {
+        if ifd.offset % 2 != 0 {
+            return fail(format!("IFD at offset {} isn't word-aligned", ifd.offset));
+        }
+        if ifd.entries.is_empty() {
+            return fail(format!("IFD at offset {} is empty", ifd.offset));
+        }
+        if ifd
+            .entries
+            .windows(2)
+            .any(|pair| pair[0].tag >= pair[1].tag)
+        {
+            return fail(format!(
+                "IFD at offset {} doesn't list its tags in ascending order",
+                ifd.offset
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks an ICO file's directory: every entry has one colour plane, and its image starts
+/// after the directory without overlapping another.
+pub fn check_ico(data: &[u8]) -> io::Result<()> {
+    let ico = IcoFile::parse(data.to_vec())?;
+    let fail = |reason: String| Err(violation("ICO", reason));
+    if ico.entries.is_empty() {
+        return fail("has no image".into());
+    }
+    let directory_end = 6 + ico.entries.len() * 16;
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (index, entry) in ico.entries.iter().enumerate() {
+        if ico.kind == 1 && entry.planes > 1 {
+            return fail(format!(
+                "entry {} has {} colour planes",
+                index, entry.planes
+            ));
+        }
+        let range = (entry.offset as usize, entry.end());
+        if range.0 < directory_end {
+            return fail(format!("image of entry {} overlaps the directory", index));
+        }
+        if ranges
+            .iter()
+            .any(|other| range.0 < other.1 && other.0 < range.1)
+        {
+            return fail(format!("image of entry {} overlaps another", index));
+        }
+        ranges.push(range);
+    }
+    Ok(())
+}
+
+/// Checks an MP4 file's top-level boxes: an `ftyp` box with a major brand and minor version
+/// comes first, and there is exactly one `moov`.
+pub fn check_mp4(data: &[u8]) -> io::Result<()> {
+    let mp4 = Mp4File::parse(data)?;
+    let fail = |reason: String| Err(violation("MP4", reason));
+    let Some(ftyp) = mp4.boxes.first().filter(|b| &b.r#type == b"ftyp") else {
+        return fail("doesn't start with an ftyp box".into());
+    };
+    if ftyp.size < ftyp.header_len + 8 {
+        return fail(format!(
+            "ftyp box holds {} bytes instead of at least 8",
+            ftyp.size - ftyp.header_len
+        ));
+    }
+    let movies = mp4.boxes.iter().filter(|b| &b.r#type == b"moov").count();
+    if movies != 1 {
+        return fail(format!("has {} moov boxes instead of 1", movies));
+    }
+    Ok(())
+}
+
+/// Checks an MP3 file's ID3v2 tag: sizes are synchsafe integers where the version asks for
+/// them, the padding is made of zeros, and the audio after the tag opens with a frame sync.
+pub fn check_mp3(data: &[u8]) -> io::Result<()> {
+    let mp3 = Mp3File::parse(data)?;
+    let fail = |reason: String| Err(violation("MP3", reason));
+    let Some(tag) = &mp3.tag else {
+        return Ok(());
+    };
+    if data[6..10].iter().any(|b| b & 0x80 != 0) {
+        return fail("tag size isn't a synchsafe integer".into());
+    }
+    if tag.version == 4 {
+        if let Some(frame) = tag.frames.iter().find(|frame| {
+            data[frame.offset + 4..frame.offset + 8]
+                .iter()
+                .any(|b| b & 0x80 != 0)
+        }) {
+            return fail(format!(
+                "{} frame at offset {} has a size that isn't a synchsafe integer",
+                frame.id_name(),
+                frame.offset
+            ));
+        }
+    }
+    if let Some(position) = data[tag.frames_end..10 + tag.size]
+        .iter()
+        .position(|&b| b != 0)
+    {
+        return fail(format!(
+            "padding holds a non-zero byte at offset {}",
+            tag.frames_end + position
+        ));
+    }
+    let audio = &data[tag.end()..];
+    if !audio.is_empty() && (audio.len() < 2 || audio[0] != 0xFF || audio[1] & 0xE0 != 0xE0) {
+        return fail(format!(
+            "audio at offset {} doesn't open with a frame sync",
+            tag.end()
+        ));
+    }
+    Ok(())
+}
+
+/// Checks a FLAC file's metadata blocks: a 34-byte `STREAMINFO` comes first and only once,
+/// there is at most one `VORBIS_COMMENT` and `SEEKTABLE`, no block has the invalid type 127,
+/// padding is made of zeros, and the audio opens with a frame sync.
+pub fn check_flac(data: &[u8]) -> io::Result<()> {
+    let flac = FlacFile::parse(data)?;
+    let fail = |reason: String| Err(violation("FLAC", reason));
+    if flac.blocks[0].kind != 0 || flac.blocks[0].len != 34 {
+        return fail("doesn't start with a 34-byte STREAMINFO block".into());
+    }
+    for (kind, name) in [(0, "STREAMINFO"), (3, "SEEKTABLE"), (4, "VORBIS_COMMENT")] {
+        if flac.blocks.iter().filter(|b| b.kind == kind).count() > 1 {
+            return fail(format!("has more than one {} block", name));
+        }
+    }
+    for block in &flac.blocks {
+        if block.kind == 127 {
+            return fail(format!(
+                "block at offset {} has the invalid type 127",
+                block.offset
+            ));
+        }
+        if block.kind == 1 && data[block.body()..block.end()].iter().any(|&b| b != 0) {
+            return fail(format!(
+                "PADDING block at offset {} isn't zeroed",
+                block.offset
+            ));
+        }
+    }
+    let audio = &data[flac.audio_offset()..];
+    if !audio.is_empty() && (audio.len() < 2 || audio[0] != 0xFF || audio[1] & 0xFE != 0xF8) {
+        return fail(format!(
+            "audio at offset {} doesn't open with a frame sync",
+            flac.audio_offset()
+        ));
+    }
+    Ok(())
+}
+
+/// Checks an SVG file's tags: every start tag is closed by a matching end tag, the root `svg`
+/// element declares the SVG namespace and is the only one, and nothing but whitespace
+/// follows it.
+pub fn check_svg(data: &[u8]) -> io::Result<()> {
+    let svg = SvgFile::parse(data)?;
+    let fail = |reason: String| Err(violation("SVG", reason));
+    let root = &svg.tags[0];
+    let namespace = root.attributes.iter().any(|attribute| {
+        let text = &data[attribute.clone()];
+        text.starts_with(b"xmlns")
+            && (text.ends_with(b"\"http://www.w3.org/2000/svg\"")
+                || text.ends_with(b"'http://www.w3.org/2000/svg'"))
+    });
+    if !namespace {
+        return fail("root element doesn't declare the SVG namespace".into());
+    }
+    let mut open = Vec::new();
+    for tag in &svg.tags {
+        let name = &data[tag.name.clone()];
+        if open.is_empty() && tag.offset != root.offset {
+            return fail(format!(
+                "element at offset {} is outside the root",
+                tag.offset
+            ));
+        }
+        match tag.kind {
+            TagKind::Start => open.push(tag),
+            TagKind::Empty => {}
+            TagKind::End => match open.pop() {
+                Some(start) if &data[start.name.clone()] == name => {}
+                Some(start) => {
+                    return fail(format!(
+                        "end tag at offset {} doesn't close the {} element at offset {}",
+                        tag.offset,
+                        String::from_utf8_lossy(&data[start.name.clone()]),
+                        start.offset
+                    ))
+                }
+                None => unreachable!("The root is open"),
+            },
+        }
+    }
+    if let Some(start) = open.last() {
+        return fail(format!(
+            "{} element at offset {} isn't closed",
+            String::from_utf8_lossy(&data[start.name.clone()]),
+            start.offset
+        ));
+    }
+    let root_end = svg.tags.last().map_or(0, |tag| tag.end);
+    if let Some(position) = data[root_end..]
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+    {
+        return fail(format!(
+            "content at offset {} follows the root element",
+            root_end + position
+        ));
+    }
+    Ok(())
+}
+
+/// Checks a text file: it must be UTF-8 without NUL bytes, other control characters than
+/// tabs and line breaks, or a byte order mark after the start.
+pub fn check_text(data: &[u8]) -> io::Result<()> {
+    let text = TextFile::parse(data)?.text;
+    let fail = |reason: String| Err(violation("text", reason));
+    for (offset, c) in text.char_indices() {
+        if c.is_control() && !matches!(c, '\t' | '\n' | '\r') {
+            return fail(format!("control character {:?} at offset {}", c, offset));
+        }
+        if c == '\u{FEFF}' && offset > 0 {
+            return fail(format!("byte order mark at offset {}", offset));
+        }
+    }
+    Ok(())
+}
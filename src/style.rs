@@ -0,0 +1,182 @@
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// When to color the human output, from `--color`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colors on terminals, unless `NO_COLOR` is set.
+    Auto,
+    /// Colors everywhere, even in files and pipes.
+    Always,
+    /// No colors at all.
+    Never,
+}
+
+impl ColorChoice {
+    /// Parses a `--color` value: auto, always or never.
+    pub fn parse(value: &str) -> Option<ColorChoice> {
+        match value.to_lowercase().as_str() {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    /// Tells whether a stream gets colors.
+    ///
+    /// `Auto` colors terminals only, and nothing when the `NO_COLOR` environment variable is
+    /// set to a non-empty value, as <https://no-color.org> asks; `Always` overrides it.
+    ///
+    /// # Arguments
+    ///
+    /// * `no_color` - Whether `NO_COLOR` is set to a non-empty value.
+    /// * `terminal` - Whether the stream is a terminal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::style::ColorChoice;
+    ///
+    /// assert!(ColorChoice::Auto.colors(false, true));
+    /// assert!(!ColorChoice::Auto.colors(true, true));
+    /// assert!(!ColorChoice::Auto.colors(false, false));
+    /// assert!(ColorChoice::Always.colors(true, false));
+    /// assert!(!ColorChoice::Never.colors(false, true));
+    /// ```
+    pub fn colors(self, no_color: bool, terminal: bool) -> bool {
+        match self {
+            ColorChoice::Auto => !no_color && terminal,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// The choice of the running command, `Auto` until `set_color_choice` is called.
+static CHOICE: AtomicU8 = AtomicU8::new(0);
+
+/// Sets when the output of this process is colored.
+pub fn set_color_choice(choice: ColorChoice) {
+    let value = match choice {
+        ColorChoice::Auto => 0,
+        ColorChoice::Always => 1,
+        ColorChoice::Never => 2,
+    };
+    CHOICE.store(value, Ordering::Relaxed);
+}
+
+/// Returns the choice set with `set_color_choice`.
+pub fn color_choice() -> ColorChoice {
+    match CHOICE.load(Ordering::Relaxed) {
+        1 => ColorChoice::Always,
+        2 => ColorChoice::Never,
+        _ => ColorChoice::Auto,
+    }
+}
+
+/// The standard streams the CLI prints to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Tells whether what is printed to a stream gets colors, given the current choice, the
+/// `NO_COLOR` environment variable and whether the stream is a terminal.
+pub fn colors_enabled(stream: Stream) -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let terminal = match stream {
+        Stream::Stdout => io::stdout().is_terminal(),
+        Stream::Stderr => io::stderr().is_terminal(),
+    };
+    color_choice().colors(no_color, terminal)
+}
+
+/// The colors and weights of the human output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Section frames and successes.
+    Green,
+    /// Warnings and secondary headings.
+    Yellow,
+    /// Failures.
+    Red,
+    /// Every other byte of hexdumps.
+    Blue,
+    /// Labels.
+    Grey,
+    /// Secrets and the tagline.
+    Orange,
+    /// Error prefixes.
+    BoldRed,
+    /// Plan headings.
+    BoldCyan,
+}
+
+impl Style {
+    /// Returns the ANSI SGR parameters of the style.
+    fn code(self) -> &'static str {
+        match self {
+            Style::Green => "92",
+            Style::Yellow => "93",
+            Style::Red => "91",
+            Style::Blue => "94",
+            Style::Grey => "38;5;7",
+            Style::Orange => "38;5;214",
+            Style::BoldRed => "1;91",
+            Style::BoldCyan => "1;96",
+        }
+    }
+
+    /// Styles a value printed to stdout.
+    pub fn paint<T: fmt::Display>(self, value: T) -> Painted<T> {
+        self.paint_for(Stream::Stdout, value)
+    }
+
+    /// Styles a value printed to stderr.
+    pub fn paint_stderr<T: fmt::Display>(self, value: T) -> Painted<T> {
+        self.paint_for(Stream::Stderr, value)
+    }
+
+    /// Styles a value printed to a stream, in plain text if it doesn't get colors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::style::{set_color_choice, ColorChoice, Stream, Style};
+    ///
+    /// set_color_choice(ColorChoice::Always);
+    /// assert_eq!(Style::Green.paint_for(Stream::Stdout, "ok").to_string(), "\x1b[92mok\x1b[0m");
+    /// set_color_choice(ColorChoice::Never);
+    /// assert_eq!(Style::Green.paint_for(Stream::Stdout, "ok").to_string(), "ok");
+    /// ```
+    pub fn paint_for<T: fmt::Display>(self, stream: Stream, value: T) -> Painted<T> {
+        self.paint_if(colors_enabled(stream), value)
+    }
+
+    /// Styles a value if `enabled`, for writers other than the standard streams.
+    pub fn paint_if<T: fmt::Display>(self, enabled: bool, value: T) -> Painted<T> {
+        Painted {
+            style: enabled.then_some(self),
+            value,
+        }
+    }
+}
+
+/// A value with the style it is displayed in, if any.
+#[derive(Debug, Clone)]
+pub struct Painted<T> {
+    style: Option<Style>,
+    value: T,
+}
+
+impl<T: fmt::Display> fmt::Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            Some(style) => write!(f, "\x1b[{}m{}\x1b[0m", style.code(), self.value),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
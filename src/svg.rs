@@ -0,0 +1,586 @@
+use crate::encoding::PayloadEncoding;
+use crate::header::is_current;
+use crate::hint::split_hint;
+use crate::outln;
+use crate::storage::read_input;
+use crate::style::Style;
+use crate::text::{
+    embed_trailing, extract_trailing, frame, line_ends, remove_trailing, trailing_capacity, unframe,
+};
+use std::error::Error;
+use std::io::{self, ErrorKind};
+use std::ops::Range;
+
+/// The opening tag of the `metadata` element holding a payload, in base64.
+pub const METADATA_OPEN: &[u8] = b"<metadata id=\"stegano\">";
+
+/// The closing tag of the `metadata` element holding a payload.
+const METADATA_CLOSE: &[u8] = b"</metadata>";
+
+/// The number of attributes of an element that are reordered; 20! still fits in a `u64`.
+const MAX_PERMUTED: usize = 20;
+
+/// The kind of a tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagKind {
+    /// A start tag, such as `<g>`.
+    Start,
+    /// An end tag, such as `</g>`.
+    End,
+    /// An empty-element tag, such as `<path/>`.
+    Empty,
+}
+
+/// A tag of an SVG file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    /// Whether the tag opens, closes or is an empty element.
+    pub kind: TagKind,
+    /// The range of the element name in the file.
+    pub name: Range<usize>,
+    /// The ranges of the attributes in the file, from their name to their closing quote.
+    pub attributes: Vec<Range<usize>>,
+    /// The offset of the `<` of the tag.
+    pub offset: usize,
+    /// The offset right after the `>` of the tag.
+    pub end: usize,
+}
+
+/// The way a payload is hidden in an SVG file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvgMethod {
+    /// In base64 in a `metadata` element opening the root element.
+    Metadata,
+    /// As spaces and tabs at the end of the lines, one byte per line.
+    Whitespace,
+    /// In the order of the attributes of the elements.
+    Attributes,
+}
+
+impl SvgMethod {
+    /// Every method, in the order `show-meta` lists them.
+    pub const ALL: [SvgMethod; 3] = [
+        SvgMethod::Metadata,
+        SvgMethod::Whitespace,
+        SvgMethod::Attributes,
+    ];
+
+    /// Returns the name of the method, as given to `--method`.
+    pub fn name(self) -> &'static str {
+        match self {
+            SvgMethod::Metadata => "metadata",
+            SvgMethod::Whitespace => "whitespace",
+            SvgMethod::Attributes => "attributes",
+        }
+    }
+
+    /// Parses an embedding method: auto or metadata, whitespace, or attributes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::svg::SvgMethod;
+    ///
+    /// assert_eq!(SvgMethod::new("auto").unwrap(), SvgMethod::Metadata);
+    /// assert_eq!(SvgMethod::new("Attributes").unwrap(), SvgMethod::Attributes);
+    /// assert!(SvgMethod::new("comment").is_err());
+    /// ```
+    pub fn new(method: &str) -> io::Result<SvgMethod> {
+        match method.to_lowercase().as_str() {
+            "auto" | "metadata" => Ok(SvgMethod::Metadata),
+            "whitespace" => Ok(SvgMethod::Whitespace),
+            "attributes" => Ok(SvgMethod::Attributes),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unsupported method {:?} for SVG files, use metadata, whitespace or attributes",
+                    method
+                ),
+            )),
+        }
+    }
+}
+
+/// Returns the position of `needle` in `data` from `from`, if it is there.
+fn find(data: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    data.get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| from + position)
+}
+
+/// Returns `n!`.
+fn factorial(n: usize) -> u64 {
+    (1..=n as u64).product()
+}
+
+/// Returns the number of bits a permutation of `n` attributes holds: the base-2 logarithm of
+/// `n!`, rounded down.
+fn permutation_bits(n: usize) -> usize {
+    let n = n.min(MAX_PERMUTED);
+    if n < 2 {
+        0
+    } else {
+        factorial(n).ilog2() as usize
+    }
+}
+
+/// Represents the tags of an SVG file, over its bytes.
+#[derive(Debug, Clone)]
+pub struct SvgFile<'a> {
+    /// The tags, in file order; comments, CDATA sections, processing instructions and the
+    /// document type are skipped.
+    pub tags: Vec<Tag>,
+    /// Raw bytes of the file.
+    pub data: &'a [u8],
+}
+
+impl<'a> SvgFile<'a> {
+    /// Parses the tags of an SVG file.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The raw bytes of the SVG file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed `SvgFile`, or an `InvalidData` error if a tag,
+    /// comment or attribute isn't closed, or if the root element isn't `svg`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::svg::{SvgFile, TagKind};
+    ///
+    /// let svg = br#"<?xml version="1.0"?>
+    /// <!-- a square -->
+    /// <svg xmlns="http://www.w3.org/2000/svg" width="4" height='4'>
+    ///   <rect x="0" y="0" width="4" height="4"/>
+    /// </svg>
+    /// "#;
+    /// let file = SvgFile::parse(svg).unwrap();
+    /// let kinds: Vec<TagKind> = file.tags.iter().map(|tag| tag.kind).collect();
+    /// assert_eq!(kinds, [TagKind::Start, TagKind::Empty, TagKind::End]);
+    /// assert_eq!(file.tags[1].attributes.len(), 4);
+    ///
+    /// assert!(SvgFile::parse(b"<svg width=\"4></svg>").is_err());
+    /// assert!(SvgFile::parse(b"<html></html>").is_err());
+    /// ```
+    pub fn parse(data: &'a [u8]) -> io::Result<SvgFile<'a>> {
+        let invalid = |msg: String| io::Error::new(ErrorKind::InvalidData, msg);
+        let unterminated = |what: &str, offset: usize| {
+            invalid(format!("Unterminated {} at offset {}", what, offset))
+        };
+        let is_space = |b: u8| b.is_ascii_whitespace();
+        let mut tags = Vec::new();
+        let mut pos = 0;
+        while let Some(lt) = find(data, pos, b"<") {
+            let rest = &data[lt..];
+            if rest.starts_with(b"<!--") {
+                pos = find(data, lt + 4, b"-->").ok_or_else(|| unterminated("comment", lt))? + 3;
+                continue;
+            }
+            if rest.starts_with(b"<![CDATA[") {
+                pos = find(data, lt + 9, b"]]>")
+                    .ok_or_else(|| unterminated("CDATA section", lt))?
+                    + 3;
+                continue;
+            }
+            if rest.starts_with(b"<?") {
+                pos = find(data, lt + 2, b"?>")
+                    .ok_or_else(|| unterminated("processing instruction", lt))?
+                    + 2;
+                continue;
+            }
+            if rest.starts_with(b"<!") {
+                // The document type may hold an internal subset in brackets.
+                let mut depth = 0;
+                let end = rest.iter().position(|&b| {
+                    depth += (b == b'[') as i32 - (b == b']') as i32;
+                    b == b'>' && depth == 0
+                });
+                pos = lt + end.ok_or_else(|| unterminated("declaration", lt))? + 1;
+                continue;
+            }
+            let closing = rest.starts_with(b"</");
+            let mut i = lt + if closing { 2 } else { 1 };
+            let name_start = i;
+            while i < data.len() && !is_space(data[i]) && data[i] != b'/' && data[i] != b'>' {
+                i += 1;
+            }
+            if i == name_start {
+                return Err(invalid(format!("Invalid tag at offset {}", lt)));
+            }
+            let name = name_start..i;
+            let mut attributes = Vec::new();
+            let kind = loop {
+                while i < data.len() && is_space(data[i]) {
+                    i += 1;
+                }
+                match data.get(i..).unwrap_or(&[]) {
+                    [] => return Err(unterminated("tag", lt)),
+                    [b'>', ..] if closing => break TagKind::End,
+                    [b'>', ..] => break TagKind::Start,
+                    [b'/', b'>', ..] if !closing => {
+                        i += 1;
+                        break TagKind::Empty;
+                    }
+                    _ if closing => {
+                        return Err(invalid(format!("Malformed end tag at offset {}", lt)))
+                    }
+                    _ => {}
+                }
+                let attribute = i;
+                while i < data.len() && !is_space(data[i]) && !b"=/>".contains(&data[i]) {
+                    i += 1;
+                }
+                while i < data.len() && is_space(data[i]) {
+                    i += 1;
+                }
+                if i == attribute || data.get(i) != Some(&b'=') {
+                    return Err(invalid(format!(
+                        "Malformed attribute at offset {}",
+                        attribute
+                    )));
+                }
+                i += 1;
+                while i < data.len() && is_space(data[i]) {
+                    i += 1;
+                }
+                let quote = match data.get(i) {
+                    Some(&quote @ (b'"' | b'\'')) => quote,
+                    _ => {
+                        return Err(invalid(format!(
+                            "Unquoted attribute value at offset {}",
+                            attribute
+                        )))
+                    }
+                };
+                let close = find(data, i + 1, &[quote])
+                    .ok_or_else(|| unterminated("attribute value", attribute))?;
+                i = close + 1;
+                attributes.push(attribute..i);
+            };
+            tags.push(Tag {
+                kind,
+                name,
+                attributes,
+                offset: lt,
+                end: i + 1,
+            });
+            pos = i + 1;
+        }
+        match tags.first() {
+            Some(root) if &data[root.name.clone()] == b"svg" && root.kind != TagKind::End => {
+                Ok(SvgFile { tags, data })
+            }
+            _ => Err(invalid(
+                "Not an SVG file: the root element isn't svg".into(),
+            )),
+        }
+    }
+
+    /// Returns the name of an attribute, without the `=` and value.
+    fn attribute_name(&self, attribute: &Range<usize>) -> &[u8] {
+        let text = &self.data[attribute.clone()];
+        let end = text
+            .iter()
+            .position(|&b| b == b'=' || b.is_ascii_whitespace())
+            .unwrap_or(text.len());
+        &text[..end]
+    }
+
+    /// Returns the tags whose attributes can be reordered, with the order of their
+    /// attributes sorted by name; tags with a repeated attribute name are left out.
+    fn permutable(&self) -> Vec<(&Tag, Vec<usize>)> {
+        self.tags
+            .iter()
+            .filter(|tag| permutation_bits(tag.attributes.len()) > 0)
+            .filter_map(|tag| {
+                let n = tag.attributes.len().min(MAX_PERMUTED);
+                let mut sorted: Vec<usize> = (0..n).collect();
+                sorted.sort_by_key(|&i| self.attribute_name(&tag.attributes[i]));
+                let unique = sorted.windows(2).all(|pair| {
+                    self.attribute_name(&tag.attributes[pair[0]])
+                        != self.attribute_name(&tag.attributes[pair[1]])
+                });
+                unique.then_some((tag, sorted))
+            })
+            .collect()
+    }
+
+    /// Returns the number of payload bytes a method can hide, or `None` if it is unlimited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::svg::{SvgFile, SvgMethod};
+    ///
+    /// let svg = b"<svg a=\"1\" b=\"2\" c=\"3\" d=\"4\">\n</svg>\n";
+    /// let file = SvgFile::parse(svg).unwrap();
+    /// assert_eq!(file.capacity(SvgMethod::Metadata), None);
+    /// assert_eq!(file.capacity(SvgMethod::Whitespace), Some(0));
+    /// // The 24 orders of four attributes hold 4 bits, short of the 32-bit length.
+    /// assert_eq!(file.capacity(SvgMethod::Attributes), Some(0));
+    /// ```
+    pub fn capacity(&self, method: SvgMethod) -> Option<usize> {
+        match method {
+            SvgMethod::Metadata => None,
+            SvgMethod::Whitespace => Some(trailing_capacity(self.data)),
+            SvgMethod::Attributes => {
+                let bits: usize = self
+                    .permutable()
+                    .iter()
+                    .map(|(tag, _)| permutation_bits(tag.attributes.len()))
+                    .sum();
+                Some((bits / 8).saturating_sub(4))
+            }
+        }
+    }
+
+    /// Hides a payload in the SVG file.
+    ///
+    /// * `Metadata` inserts a `metadata` element holding the payload in base64 right after
+    ///   the root `svg` tag; renderers don't draw metadata.
+    /// * `Whitespace` writes the 4-byte length and the payload as spaces (0) and tabs (1) at
+    ///   the end of the lines, one byte per line; XML ignores whitespace between tags. Any
+    ///   trailing whitespace the file already had is removed first.
+    /// * `Attributes` writes the length and the payload in the order of the attributes of
+    ///   the elements, each element with `n` attributes holding `log2(n!)` bits; attribute
+    ///   order has no meaning in XML. The original order can't be restored.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The bytes to hide.
+    /// * `method` - Where to hide them.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the new SVG file, or an `InvalidInput` error if the
+    /// payload doesn't fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::svg::{SvgFile, SvgMethod};
+    ///
+    /// let mut svg = b"<svg xmlns=\"http://www.w3.org/2000/svg\">\n".to_vec();
+    /// for i in 0..16 {
+    ///     let line = format!("<circle r=\"{}\" cx=\"1\" cy=\"2\" fill=\"red\" id=\"c{}\"/>\n", i, i);
+    ///     svg.extend_from_slice(line.as_bytes());
+    /// }
+    /// svg.extend_from_slice(b"</svg>\n");
+    /// for method in [SvgMethod::Metadata, SvgMethod::Whitespace, SvgMethod::Attributes] {
+    ///     let stego = SvgFile::parse(&svg).unwrap().embed(b"secret", method).unwrap();
+    ///     let file = SvgFile::parse(&stego).unwrap();
+    ///     assert_eq!(file.extract_payload(method).unwrap(), b"secret");
+    ///     if method != SvgMethod::Attributes {
+    ///         assert_eq!(file.remove_payload(method).unwrap(), svg);
+    ///     }
+    /// }
+    /// ```
+    pub fn embed(&self, payload: &[u8], method: SvgMethod) -> io::Result<Vec<u8>> {
+        if let Some(capacity) = self.capacity(method).filter(|&c| payload.len() > c) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "The payload is {} bytes, but the {:?} method only holds {} bytes in this SVG",
+                    payload.len(),
+                    method,
+                    capacity
+                ),
+            ));
+        }
+        let frame = frame(payload);
+        match method {
+            SvgMethod::Metadata => {
+                let root = &self.tags[0];
+                if root.kind != TagKind::Start {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        "The root svg element is empty, so it can't hold a metadata element",
+                    ));
+                }
+                let mut out = Vec::with_capacity(self.data.len() + payload.len() * 4 / 3 + 40);
+                out.extend_from_slice(&self.data[..root.end]);
+                out.extend_from_slice(METADATA_OPEN);
+                out.extend_from_slice(&PayloadEncoding::Base64.encode(payload));
+                out.extend_from_slice(METADATA_CLOSE);
+                out.extend_from_slice(&self.data[root.end..]);
+                Ok(out)
+            }
+            SvgMethod::Whitespace => embed_trailing(self.data, payload),
+            SvgMethod::Attributes => {
+                let bits = frame.len() * 8;
+                let bit = |i: usize| {
+                    frame
+                        .get(i / 8)
+                        .is_some_and(|byte| byte >> (7 - i % 8) & 1 == 1)
+                };
+                let mut out = Vec::with_capacity(self.data.len());
+                let mut copied = 0;
+                let mut used = 0;
+                for (tag, sorted) in self.permutable() {
+                    if used >= bits {
+                        break;
+                    }
+                    let k = permutation_bits(tag.attributes.len());
+                    let value = (used..used + k).fold(0u64, |value, i| value << 1 | bit(i) as u64);
+                    used += k;
+                    // Unranks the value into an order of the sorted attributes.
+                    let mut remaining = sorted;
+                    let mut rest = value;
+                    let n = remaining.len();
+                    for (slot, attribute) in tag.attributes.iter().take(n).enumerate() {
+                        let f = factorial(n - 1 - slot);
+                        let chosen = remaining.remove((rest / f) as usize);
+                        rest %= f;
+                        out.extend_from_slice(&self.data[copied..attribute.start]);
+                        out.extend_from_slice(&self.data[tag.attributes[chosen].clone()]);
+                        copied = attribute.end;
+                    }
+                }
+                out.extend_from_slice(&self.data[copied..]);
+                Ok(out)
+            }
+        }
+    }
+
+    /// Returns the range of the `metadata` element holding a payload, and of the base64 in it.
+    fn metadata_element(&self) -> Option<(Range<usize>, Range<usize>)> {
+        let open = find(self.data, 0, METADATA_OPEN)?;
+        let start = open + METADATA_OPEN.len();
+        let close = find(self.data, start, METADATA_CLOSE)?;
+        Some((open..close + METADATA_CLOSE.len(), start..close))
+    }
+
+    /// Reads the bits written by the attributes method, in the order of the attributes.
+    fn attribute_bits(&self) -> Vec<bool> {
+        let mut bits = Vec::new();
+        for (tag, sorted) in self.permutable() {
+            let n = sorted.len();
+            let k = permutation_bits(n);
+            let mut remaining = sorted;
+            let mut value = 0u64;
+            for (slot, attribute) in tag.attributes.iter().take(n).enumerate() {
+                let name = self.attribute_name(attribute);
+                let index = remaining
+                    .iter()
+                    .position(|&i| self.attribute_name(&tag.attributes[i]) == name)
+                    .unwrap_or(0);
+                remaining.remove(index);
+                value += index as u64 * factorial(n - 1 - slot);
+            }
+            // Orders past the 2^k first ones are never written.
+            if value >> k != 0 {
+                break;
+            }
+            bits.extend((0..k).rev().map(|i| value >> i & 1 == 1));
+        }
+        bits
+    }
+
+    /// Extracts a payload hidden with a method.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the payload bytes, or a `NotFound` error if no payload was found.
+    pub fn extract_payload(&self, method: SvgMethod) -> io::Result<Vec<u8>> {
+        let payload = match method {
+            SvgMethod::Metadata => self.metadata_element().and_then(|(_, base64)| {
+                PayloadEncoding::Base64
+                    .decode(&String::from_utf8_lossy(&self.data[base64]))
+                    .ok()
+            }),
+            SvgMethod::Whitespace => extract_trailing(self.data),
+            SvgMethod::Attributes => unframe(
+                &self
+                    .attribute_bits()
+                    .chunks_exact(8)
+                    .map(|bits| bits.iter().fold(0, |byte, &bit| byte << 1 | bit as u8))
+                    .collect::<Vec<u8>>(),
+            ),
+        };
+        payload
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "No payload found in the SVG file"))
+    }
+
+    /// Tells whether a method holds a payload.
+    ///
+    /// Any order of attributes reads as some bytes, so a payload of the attributes method
+    /// only counts when it opens with a payload header.
+    pub fn holds_payload(&self, method: SvgMethod) -> bool {
+        match self.extract_payload(method) {
+            Ok(payload) if method == SvgMethod::Attributes => is_current(split_hint(&payload).1),
+            result => result.is_ok(),
+        }
+    }
+
+    /// Removes a payload, restoring the original file.
+    ///
+    /// The attributes method can't restore the original order, so the file is returned as
+    /// it is, like the pixel methods of PNG files do.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the cleaned file, or a `NotFound` error if no
+    /// payload was found.
+    pub fn remove_payload(&self, method: SvgMethod) -> io::Result<Vec<u8>> {
+        self.extract_payload(method)?;
+        match method {
+            SvgMethod::Metadata => {
+                let (element, _) = self.metadata_element().expect("The payload was found");
+                Ok([&self.data[..element.start], &self.data[element.end..]].concat())
+            }
+            SvgMethod::Whitespace => Ok(remove_trailing(self.data).expect("The payload was found")),
+            SvgMethod::Attributes => Ok(self.data.to_vec()),
+        }
+    }
+}
+
+/// Reads an SVG file and prints its root element, tag counts and the capacity of each method.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice representing the path to the SVG file.
+/// * `suppress` - A boolean to suppress print statements.
+///
+/// # Returns
+///
+/// A `Result` that is an error if the file could not be read or parsed.
+pub fn read_svg_tags(file_path: &str, suppress: bool) -> Result<(), Box<dyn Error>> {
+    let data = read_input(file_path)?;
+    let file = SvgFile::parse(&data)?;
+    if suppress {
+        return Ok(());
+    }
+
+    outln!("It is a valid SVG file. Let's process it! \n");
+    let root = &file.tags[0];
+    outln!("{}", Style::Green.paint("---- Root ----"));
+    outln!("{}", String::from_utf8_lossy(&data[root.offset..root.end]));
+    outln!("{}", Style::Green.paint("---- End -----"));
+    let elements = file
+        .tags
+        .iter()
+        .filter(|tag| tag.kind != TagKind::End)
+        .count();
+    outln!("Elements: {}", elements);
+    outln!("Lines: {}", line_ends(&data).len());
+    outln!();
+
+    outln!("{}", Style::Green.paint("---- Capacity ----"));
+    for method in SvgMethod::ALL {
+        let capacity = file
+            .capacity(method)
+            .map_or("unlimited".to_string(), |bytes| format!("{} bytes", bytes));
+        let found = if file.holds_payload(method) {
+            Style::Yellow.paint(" (stegano payload)").to_string()
+        } else {
+            String::new()
+        };
+        outln!("{:<11} {}{}", method.name(), capacity, found);
+    }
+    outln!("{}", Style::Green.paint("------ End -------"));
+    Ok(())
+}
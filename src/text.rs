@@ -0,0 +1,426 @@
+use crate::outln;
+use crate::storage::read_input;
+use crate::style::Style;
+use std::error::Error;
+use std::io::{self, ErrorKind};
+use std::ops::Range;
+
+/// The zero-width characters that carry two bits each, from 00 to 11: zero width space,
+/// zero width non-joiner, zero width joiner and word joiner.
+pub const ZERO_WIDTH: [char; 4] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}'];
+
+/// The way a payload is hidden in a text file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextMethod {
+    /// As zero-width characters after the first visible character, four per byte.
+    ZeroWidth,
+    /// As spaces and tabs at the end of the lines, one byte per line.
+    Whitespace,
+}
+
+impl TextMethod {
+    /// Every method, in the order `show-meta` lists them.
+    pub const ALL: [TextMethod; 2] = [TextMethod::ZeroWidth, TextMethod::Whitespace];
+
+    /// Returns the name of the method, as given to `--method`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TextMethod::ZeroWidth => "zerowidth",
+            TextMethod::Whitespace => "whitespace",
+        }
+    }
+
+    /// Parses an embedding method: auto or zerowidth, or whitespace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::text::TextMethod;
+    ///
+    /// assert_eq!(TextMethod::new("auto").unwrap(), TextMethod::ZeroWidth);
+    /// assert_eq!(TextMethod::new("Whitespace").unwrap(), TextMethod::Whitespace);
+    /// assert!(TextMethod::new("homoglyph").is_err());
+    /// ```
+    pub fn new(method: &str) -> io::Result<TextMethod> {
+        match method.to_lowercase().as_str() {
+            "auto" | "zerowidth" => Ok(TextMethod::ZeroWidth),
+            "whitespace" => Ok(TextMethod::Whitespace),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unsupported method {:?} for text files, use zerowidth or whitespace",
+                    method
+                ),
+            )),
+        }
+    }
+}
+
+/// Prefixes a payload with its length, as a 4-byte big-endian integer.
+pub(crate) fn frame(payload: &[u8]) -> Vec<u8> {
+    let mut frame = (payload.len() as u32).to_be_bytes().to_vec();
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Returns the payload of a frame, if the bytes hold a whole one.
+pub(crate) fn unframe(bytes: &[u8]) -> Option<Vec<u8>> {
+    let len = u32::from_be_bytes(bytes.get(..4)?.try_into().unwrap()) as usize;
+    bytes.get(4..4 + len).map(<[u8]>::to_vec)
+}
+
+/// Returns the offsets of the line ends that can carry trailing whitespace: the offset of
+/// each `\n`, or of the `\r` before it.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::text::line_ends;
+///
+/// assert_eq!(line_ends(b"a\nbc\r\nd"), [1, 4]);
+/// ```
+pub fn line_ends(data: &[u8]) -> Vec<usize> {
+    data.iter()
+        .enumerate()
+        .filter(|&(_, &b)| b == b'\n')
+        .map(|(i, _)| {
+            if i > 0 && data[i - 1] == b'\r' {
+                i - 1
+            } else {
+                i
+            }
+        })
+        .collect()
+}
+
+/// Returns the number of payload bytes the trailing whitespace of a file can hide: one per
+/// line, less the 4-byte length.
+pub fn trailing_capacity(data: &[u8]) -> usize {
+    line_ends(data).len().saturating_sub(4)
+}
+
+/// Hides a payload as spaces (0) and tabs (1) at the end of the lines, 8 per line for each
+/// byte of the 4-byte length and the payload. Any trailing whitespace the file already had is
+/// removed first, so files without any are restored byte for byte.
+///
+/// # Returns
+///
+/// A `Result` containing the bytes of the new file, or an `InvalidInput` error if there
+/// aren't enough lines.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::text::{embed_trailing, extract_trailing, remove_trailing};
+///
+/// let text = "line\n".repeat(8);
+/// let stego = embed_trailing(text.as_bytes(), b"hi").unwrap();
+/// assert!(stego.starts_with(b"line        \nline"));
+/// assert_eq!(extract_trailing(&stego).unwrap(), b"hi");
+/// assert_eq!(remove_trailing(&stego).unwrap(), text.as_bytes());
+///
+/// assert!(embed_trailing(text.as_bytes(), b"too long").is_err());
+/// ```
+pub fn embed_trailing(data: &[u8], payload: &[u8]) -> io::Result<Vec<u8>> {
+    let capacity = trailing_capacity(data);
+    if payload.len() > capacity {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The payload is {} bytes, but trailing whitespace only holds {} bytes in this file",
+                payload.len(),
+                capacity
+            ),
+        ));
+    }
+    let frame = frame(payload);
+    let mut out = Vec::with_capacity(data.len() + frame.len() * 8);
+    let mut start = 0;
+    for (index, end) in line_ends(data).into_iter().enumerate() {
+        let line = &data[start..end];
+        let kept = line
+            .iter()
+            .rposition(|&b| b != b' ' && b != b'\t')
+            .map_or(0, |i| i + 1);
+        out.extend_from_slice(&line[..kept]);
+        if let Some(&byte) = frame.get(index) {
+            out.extend((0..8).map(|bit| {
+                if byte >> (7 - bit) & 1 == 1 {
+                    b'\t'
+                } else {
+                    b' '
+                }
+            }));
+        }
+        start = end;
+    }
+    out.extend_from_slice(&data[start..]);
+    Ok(out)
+}
+
+/// Reads the bytes encoded at the end of the lines, as long as each line ends with exactly
+/// 8 spaces or tabs.
+fn trailing_bytes(data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for end in line_ends(data) {
+        let run = data[..end]
+            .iter()
+            .rev()
+            .take_while(|&&b| b == b' ' || b == b'\t')
+            .count();
+        if run != 8 {
+            break;
+        }
+        bytes.push(
+            data[end - 8..end]
+                .iter()
+                .fold(0, |byte, &b| byte << 1 | (b == b'\t') as u8),
+        );
+    }
+    bytes
+}
+
+/// Extracts a payload hidden by `embed_trailing`, or `None` if the lines don't hold one.
+pub fn extract_trailing(data: &[u8]) -> Option<Vec<u8>> {
+    unframe(&trailing_bytes(data))
+}
+
+/// Removes a payload hidden by `embed_trailing`, or returns `None` if the lines don't hold
+/// one.
+pub fn remove_trailing(data: &[u8]) -> Option<Vec<u8>> {
+    let len = extract_trailing(data)?.len();
+    let mut out = Vec::with_capacity(data.len());
+    let mut start = 0;
+    for end in line_ends(data).into_iter().take(4 + len) {
+        out.extend_from_slice(&data[start..end - 8]);
+        start = end;
+    }
+    out.extend_from_slice(&data[start..]);
+    Some(out)
+}
+
+/// Represents a cover text, which must be UTF-8.
+#[derive(Debug, Clone)]
+pub struct TextFile<'a> {
+    /// The text.
+    pub text: &'a str,
+}
+
+impl<'a> TextFile<'a> {
+    /// Checks that a file is UTF-8 text.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TextFile`, or an `InvalidData` error naming the offset of
+    /// the first byte that isn't UTF-8, or of a NUL byte, which text files don't have.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::text::TextFile;
+    ///
+    /// assert!(TextFile::parse("Dear reader,\n".as_bytes()).is_ok());
+    /// assert!(TextFile::parse(b"\x89PNG\r\n").is_err());
+    /// ```
+    pub fn parse(data: &'a [u8]) -> io::Result<TextFile<'a>> {
+        let text = std::str::from_utf8(data).map_err(|err| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Not a text file: invalid UTF-8 at offset {}",
+                    err.valid_up_to()
+                ),
+            )
+        })?;
+        if let Some(offset) = data.iter().position(|&b| b == 0) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Not a text file: NUL byte at offset {}", offset),
+            ));
+        }
+        Ok(TextFile { text })
+    }
+
+    /// Returns the number of lines, counting a last line without a line break.
+    pub fn lines(&self) -> usize {
+        self.text.lines().count()
+    }
+
+    /// Returns the number of payload bytes a method can hide, or `None` if it is unlimited.
+    pub fn capacity(&self, method: TextMethod) -> Option<usize> {
+        match method {
+            TextMethod::ZeroWidth => None,
+            TextMethod::Whitespace => Some(trailing_capacity(self.text.as_bytes())),
+        }
+    }
+
+    /// Returns the byte range of the first run of zero-width characters from `ZERO_WIDTH`
+    /// that holds a whole frame.
+    fn zero_width_run(&self) -> Option<(Range<usize>, Vec<u8>)> {
+        let mut chars = self.text.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if !ZERO_WIDTH.contains(&c) {
+                continue;
+            }
+            let mut symbols = vec![ZERO_WIDTH.iter().position(|&z| z == c).unwrap() as u8];
+            let mut end = start + c.len_utf8();
+            while let Some(&(offset, c)) = chars.peek() {
+                match ZERO_WIDTH.iter().position(|&z| z == c) {
+                    Some(symbol) => symbols.push(symbol as u8),
+                    None => break,
+                }
+                end = offset + c.len_utf8();
+                chars.next();
+            }
+            let bytes: Vec<u8> = symbols
+                .chunks_exact(4)
+                .map(|chunk| chunk.iter().fold(0, |byte, &symbol| byte << 2 | symbol))
+                .collect();
+            if let Some(payload) = unframe(&bytes) {
+                if symbols.len() == (4 + payload.len()) * 4 {
+                    return Some((start..end, payload));
+                }
+            }
+        }
+        None
+    }
+
+    /// Hides a payload in the text.
+    ///
+    /// * `ZeroWidth` writes the 4-byte length and the payload as zero-width characters, two
+    ///   bits each, right after the first visible character; they don't show when the text
+    ///   is displayed.
+    /// * `Whitespace` writes them as spaces and tabs at the end of the lines, one byte per
+    ///   line, like `embed_trailing`.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The bytes to hide.
+    /// * `method` - Where to hide them.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the new file, or an `InvalidInput` error if the
+    /// payload doesn't fit or the text has no visible character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::text::{TextFile, TextMethod};
+    ///
+    /// let text = "Dear reader,\nthe meeting moved to Friday.\nSee you there.\n".repeat(4);
+    /// for method in [TextMethod::ZeroWidth, TextMethod::Whitespace] {
+    ///     let stego = TextFile::parse(text.as_bytes()).unwrap().embed(b"secret", method).unwrap();
+    ///     let file = TextFile::parse(&stego).unwrap();
+    ///     assert_eq!(file.extract_payload(method).unwrap(), b"secret");
+    ///     assert_eq!(file.remove_payload(method).unwrap(), text.as_bytes());
+    /// }
+    /// ```
+    pub fn embed(&self, payload: &[u8], method: TextMethod) -> io::Result<Vec<u8>> {
+        if method == TextMethod::Whitespace {
+            return embed_trailing(self.text.as_bytes(), payload);
+        }
+        let at = self
+            .text
+            .char_indices()
+            .find(|(_, c)| !c.is_whitespace() && !ZERO_WIDTH.contains(c))
+            .map(|(offset, c)| offset + c.len_utf8())
+            .ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "The cover text has no visible character to hide the payload after",
+                )
+            })?;
+        if self.text[at..].starts_with(ZERO_WIDTH) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "The cover text already has zero-width characters where the payload would go",
+            ));
+        }
+        let mut out = String::with_capacity(self.text.len() + (payload.len() + 4) * 12);
+        out.push_str(&self.text[..at]);
+        for byte in frame(payload) {
+            out.extend(
+                (0..4)
+                    .rev()
+                    .map(|i| ZERO_WIDTH[(byte >> (i * 2) & 3) as usize]),
+            );
+        }
+        out.push_str(&self.text[at..]);
+        Ok(out.into_bytes())
+    }
+
+    /// Extracts a payload hidden with a method.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the payload bytes, or a `NotFound` error if no payload was found.
+    pub fn extract_payload(&self, method: TextMethod) -> io::Result<Vec<u8>> {
+        let payload = match method {
+            TextMethod::ZeroWidth => self.zero_width_run().map(|(_, payload)| payload),
+            TextMethod::Whitespace => extract_trailing(self.text.as_bytes()),
+        };
+        payload.ok_or_else(not_found)
+    }
+
+    /// Removes a payload hidden with a method, restoring the original file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the cleaned file, or a `NotFound` error if no
+    /// payload was found.
+    pub fn remove_payload(&self, method: TextMethod) -> io::Result<Vec<u8>> {
+        let cleaned = match method {
+            TextMethod::ZeroWidth => self.zero_width_run().map(|(run, _)| {
+                [&self.text[..run.start], &self.text[run.end..]]
+                    .concat()
+                    .into_bytes()
+            }),
+            TextMethod::Whitespace => remove_trailing(self.text.as_bytes()),
+        };
+        cleaned.ok_or_else(not_found)
+    }
+}
+
+/// The error returned when a text file holds no payload.
+fn not_found() -> io::Error {
+    io::Error::new(ErrorKind::NotFound, "No payload found in the text file")
+}
+
+/// Reads a text file and prints its size and the capacity of each method.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice representing the path to the text file.
+/// * `suppress` - A boolean to suppress print statements.
+///
+/// # Returns
+///
+/// A `Result` that is an error if the file could not be read or isn't text.
+pub fn read_text_summary(file_path: &str, suppress: bool) -> Result<(), Box<dyn Error>> {
+    let data = read_input(file_path)?;
+    let file = TextFile::parse(&data)?;
+    if suppress {
+        return Ok(());
+    }
+
+    outln!("It is a valid text file. Let's process it! \n");
+    outln!("Lines: {}", file.lines());
+    outln!("Characters: {}", file.text.chars().count());
+    outln!();
+
+    outln!("{}", Style::Green.paint("---- Capacity ----"));
+    for method in TextMethod::ALL {
+        let capacity = file
+            .capacity(method)
+            .map_or("unlimited".to_string(), |bytes| format!("{} bytes", bytes));
+        let found = if file.extract_payload(method).is_ok() {
+            Style::Yellow.paint(" (stegano payload)").to_string()
+        } else {
+            String::new()
+        };
+        outln!("{:<11} {}{}", method.name(), capacity, found);
+    }
+    outln!("{}", Style::Green.paint("------ End -------"));
+    Ok(())
+}
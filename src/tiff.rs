@@ -0,0 +1,727 @@
+use crate::outln;
+use crate::storage::{map_input, read_input};
+use crate::style::Style;
+use crate::utils::print_hex;
+use std::error::Error;
+use std::io::{self, ErrorKind, Read, Write};
+
+/// Private tag number used to carry a payload inside an IFD.
+///
+/// Tags in the 32768..=65535 range are reserved for private use by the TIFF 6.0 specification,
+/// so readers that don't know about it simply skip the entry.
+pub const PAYLOAD_TAG: u16 = 65000;
+
+/// Magic marker written in front of a payload stored in an unreferenced strip.
+pub const STRIP_MAGIC: &[u8; 4] = b"STGS";
+
+/// Byte order of a TIFF file, as declared by the first two bytes of the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// `II` - Intel byte order.
+    LittleEndian,
+    /// `MM` - Motorola byte order.
+    BigEndian,
+}
+
+impl ByteOrder {
+    /// Reads a 16-bit unsigned integer at `pos` in this byte order.
+    pub fn read_u16(&self, data: &[u8], pos: usize) -> io::Result<u16> {
+        let bytes: [u8; 2] = data
+            .get(pos..pos + 2)
+            .ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "Truncated TIFF data"))?
+            .try_into()
+            .unwrap();
+        Ok(match self {
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+        })
+    }
+
+    /// Reads a 32-bit unsigned integer at `pos` in this byte order.
+    pub fn read_u32(&self, data: &[u8], pos: usize) -> io::Result<u32> {
+        let bytes: [u8; 4] = data
+            .get(pos..pos + 4)
+            .ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "Truncated TIFF data"))?
+            .try_into()
+            .unwrap();
+        Ok(match self {
+            ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+        })
+    }
+
+    /// Encodes a 16-bit unsigned integer in this byte order.
+    pub fn u16_bytes(&self, value: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::LittleEndian => value.to_le_bytes(),
+            ByteOrder::BigEndian => value.to_be_bytes(),
+        }
+    }
+
+    /// Encodes a 32-bit unsigned integer in this byte order.
+    pub fn u32_bytes(&self, value: u32) -> [u8; 4] {
+        match self {
+            ByteOrder::LittleEndian => value.to_le_bytes(),
+            ByteOrder::BigEndian => value.to_be_bytes(),
+        }
+    }
+}
+
+/// Represents a single 12-byte entry of an Image File Directory.
+#[derive(Debug, Clone)]
+pub struct IfdEntry {
+    /// The tag identifying the field.
+    pub tag: u16,
+    /// The field type (BYTE, ASCII, SHORT, LONG, ...).
+    pub field_type: u16,
+    /// The number of values of the given type.
+    pub count: u32,
+    /// The raw value/offset field, kept in file byte order.
+    pub value: [u8; 4],
+}
+
+impl IfdEntry {
+    /// Returns the size in bytes of the values referenced by this entry.
+    pub fn data_len(&self) -> usize {
+        type_size(self.field_type) * self.count as usize
+    }
+
+    /// Returns true if the values fit into the 4-byte value field.
+    pub fn is_inline(&self) -> bool {
+        self.data_len() <= 4
+    }
+
+    /// Returns the raw bytes referenced by this entry, either inline or from the file body.
+    pub fn data<'a>(&'a self, order: ByteOrder, file: &'a [u8]) -> io::Result<&'a [u8]> {
+        let len = self.data_len();
+        if self.is_inline() {
+            return Ok(&self.value[..len]);
+        }
+        let offset = order.read_u32(&self.value, 0)? as usize;
+        file.get(offset..offset + len)
+            .ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "TIFF tag data out of bounds"))
+    }
+}
+
+/// Represents an Image File Directory (IFD) of a TIFF file.
+#[derive(Debug, Clone)]
+pub struct Ifd {
+    /// Offset of the IFD in the file.
+    pub offset: u32,
+    /// Entries of the IFD.
+    pub entries: Vec<IfdEntry>,
+    /// Offset of the next IFD, or 0 if this is the last one.
+    pub next: u32,
+}
+
+/// Represents a parsed TIFF file together with its raw bytes.
+#[derive(Debug, Clone)]
+pub struct TiffFile {
+    /// Byte order of the file.
+    pub byte_order: ByteOrder,
+    /// Image File Directories in file order.
+    pub ifds: Vec<Ifd>,
+    /// Raw bytes of the file.
+    pub data: Vec<u8>,
+}
+
+/// Returns the size in bytes of a single value of the given TIFF field type.
+///
+/// Unknown types are treated as one byte wide.
+pub fn type_size(field_type: u16) -> usize {
+    match field_type {
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => 1,
+    }
+}
+
+/// Returns the name of a TIFF field type.
+pub fn type_name(field_type: u16) -> &'static str {
+    match field_type {
+        1 => "BYTE",
+        2 => "ASCII",
+        3 => "SHORT",
+        4 => "LONG",
+        5 => "RATIONAL",
+        6 => "SBYTE",
+        7 => "UNDEFINED",
+        8 => "SSHORT",
+        9 => "SLONG",
+        10 => "SRATIONAL",
+        11 => "FLOAT",
+        12 => "DOUBLE",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Returns a human readable name for well-known TIFF tags.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::tiff::tag_name;
+///
+/// assert_eq!(tag_name(256), "ImageWidth");
+/// assert_eq!(tag_name(12345), "Unknown");
+/// ```
+pub fn tag_name(tag: u16) -> &'static str {
+    match tag {
+        254 => "NewSubfileType",
+        255 => "SubfileType",
+        256 => "ImageWidth",
+        257 => "ImageLength",
+        258 => "BitsPerSample",
+        259 => "Compression",
+        262 => "PhotometricInterpretation",
+        266 => "FillOrder",
+        269 => "DocumentName",
+        270 => "ImageDescription",
+        271 => "Make",
+        272 => "Model",
+        273 => "StripOffsets",
+        274 => "Orientation",
+        277 => "SamplesPerPixel",
+        278 => "RowsPerStrip",
+        279 => "StripByteCounts",
+        282 => "XResolution",
+        283 => "YResolution",
+        284 => "PlanarConfiguration",
+        296 => "ResolutionUnit",
+        305 => "Software",
+        306 => "DateTime",
+        315 => "Artist",
+        316 => "HostComputer",
+        317 => "Predictor",
+        320 => "ColorMap",
+        322 => "TileWidth",
+        323 => "TileLength",
+        324 => "TileOffsets",
+        325 => "TileByteCounts",
+        330 => "SubIFDs",
+        338 => "ExtraSamples",
+        339 => "SampleFormat",
+        700 => "XMP",
+        33432 => "Copyright",
+        33723 => "IPTC",
+        34665 => "ExifIFD",
+        34675 => "ICCProfile",
+        34853 => "GPSInfo",
+        PAYLOAD_TAG => "SteganoPayload",
+        _ => "Unknown",
+    }
+}
+
+impl TiffFile {
+    /// Parses a TIFF file from its raw bytes.
+    ///
+    /// The header is validated, then every IFD in the chain is read. Loops in the IFD chain are
+    /// detected and reported as an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The raw bytes of the TIFF file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed `TiffFile`, or an `io::Error` if the data is not a valid TIFF.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::tiff::{ByteOrder, TiffFile};
+    ///
+    /// // Little-endian header followed by an IFD holding a single ImageWidth entry.
+    /// let data: Vec<u8> = vec![
+    ///     0x49, 0x49, 0x2A, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x03, 0x00,
+    ///     0x01, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    /// ];
+    /// let tiff = TiffFile::parse(data).unwrap();
+    /// assert_eq!(tiff.byte_order, ByteOrder::LittleEndian);
+    /// assert_eq!(tiff.ifds.len(), 1);
+    /// assert_eq!(tiff.ifds[0].entries[0].tag, 256);
+    /// ```
+    pub fn parse(data: Vec<u8>) -> io::Result<TiffFile> {
+        let (byte_order, ifds) = Self::parse_ifds(&data)?;
+        Ok(TiffFile {
+            byte_order,
+            ifds,
+            data,
+        })
+    }
+
+    /// Parses the header and the IFD chain of a TIFF file without taking ownership of it, so
+    /// that memory-mapped files can be scanned without a copy.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the byte order and the IFDs in file order.
+    pub fn parse_ifds(data: &[u8]) -> io::Result<(ByteOrder, Vec<Ifd>)> {
+        let byte_order = match data.get(0..2) {
+            Some(b"II") => ByteOrder::LittleEndian,
+            Some(b"MM") => ByteOrder::BigEndian,
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "Not a valid TIFF file!",
+                ))
+            }
+        };
+        if byte_order.read_u16(data, 2)? != 42 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "Not a valid TIFF file!",
+            ));
+        }
+
+        let mut ifds = Vec::new();
+        let mut next = byte_order.read_u32(data, 4)?;
+        while next != 0 {
+            if ifds.iter().any(|ifd: &Ifd| ifd.offset == next) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "Loop detected in the TIFF IFD chain",
+                ));
+            }
+            let ifd = Self::read_ifd(byte_order, data, next)?;
+            next = ifd.next;
+            ifds.push(ifd);
+        }
+        Ok((byte_order, ifds))
+    }
+
+    /// Reads a single IFD located at `offset`.
+    fn read_ifd(order: ByteOrder, data: &[u8], offset: u32) -> io::Result<Ifd> {
+        let mut pos = offset as usize;
+        let count = order.read_u16(data, pos)?;
+        pos += 2;
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let value: [u8; 4] = data
+                .get(pos + 8..pos + 12)
+                .ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "Truncated TIFF IFD"))?
+                .try_into()
+                .unwrap();
+            entries.push(IfdEntry {
+                tag: order.read_u16(data, pos)?,
+                field_type: order.read_u16(data, pos + 2)?,
+                count: order.read_u32(data, pos + 4)?,
+                value,
+            });
+            pos += 12;
+        }
+        let next = order.read_u32(data, pos)?;
+        Ok(Ifd {
+            offset,
+            entries,
+            next,
+        })
+    }
+
+    /// Serializes an IFD with the given entries in this file's byte order.
+    fn encode_ifd(&self, entries: &[IfdEntry], next: u32) -> Vec<u8> {
+        let order = self.byte_order;
+        let mut out = Vec::with_capacity(2 + entries.len() * 12 + 4);
+        out.extend_from_slice(&order.u16_bytes(entries.len() as u16));
+        for entry in entries {
+            out.extend_from_slice(&order.u16_bytes(entry.tag));
+            out.extend_from_slice(&order.u16_bytes(entry.field_type));
+            out.extend_from_slice(&order.u32_bytes(entry.count));
+            out.extend_from_slice(&entry.value);
+        }
+        out.extend_from_slice(&order.u32_bytes(next));
+        out
+    }
+
+    /// Returns the first IFD, or an error if the file has none.
+    fn first_ifd(&self) -> io::Result<&Ifd> {
+        self.ifds
+            .first()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "TIFF file has no IFD"))
+    }
+
+    /// Hides a payload in a private tag of the first IFD.
+    ///
+    /// A copy of the first IFD extended with a `PAYLOAD_TAG` entry is appended to the file,
+    /// followed by the payload itself, and the header is updated to point at the new IFD.
+    /// No existing byte is moved, so all offsets in the file stay valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The bytes to hide.
+    ///
+    /// # Returns
+    ///
+    /// The bytes of the new TIFF file and the offset the payload was written at: after the new
+    /// IFD, or in the value field of its entry for payloads of 4 bytes or fewer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::tiff::TiffFile;
+    ///
+    /// let data: Vec<u8> = vec![
+    ///     0x4D, 0x4D, 0x00, 0x2A, 0x00, 0x00, 0x00, 0x08, 0x00, 0x01, 0x01, 0x00, 0x00, 0x03,
+    ///     0x00, 0x00, 0x00, 0x01, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    /// ];
+    /// let tiff = TiffFile::parse(data).unwrap();
+    /// let (stego, offset) = tiff.embed_in_tag(b"secret").unwrap();
+    /// assert_eq!(&stego[offset..offset + 6], b"secret");
+    /// assert_eq!(TiffFile::parse(stego).unwrap().extract_payload().unwrap(), b"secret");
+    ///
+    /// // A short payload sits in the entry itself.
+    /// let (stego, offset) = tiff.embed_in_tag(b"hi").unwrap();
+    /// assert_eq!((&stego[offset..offset + 2], offset), (&b"hi"[..], 48));
+    /// ```
+    pub fn embed_in_tag(&self, payload: &[u8]) -> io::Result<(Vec<u8>, usize)> {
+        let order = self.byte_order;
+        let ifd = self.first_ifd()?;
+        let mut entries: Vec<IfdEntry> = ifd
+            .entries
+            .iter()
+            .filter(|entry| entry.tag != PAYLOAD_TAG)
+            .cloned()
+            .collect();
+
+        let mut out = self.data.clone();
+        if out.len() % 2 == 1 {
+            out.push(0);
+        }
+        let ifd_offset = out.len();
+        let ifd_len = 2 + (entries.len() + 1) * 12 + 4;
+        let payload_offset = ifd_offset + ifd_len;
+
+        let mut value = [0u8; 4];
+        if payload.len() <= 4 {
+            value[..payload.len()].copy_from_slice(payload);
+        } else {
+            value = order.u32_bytes(payload_offset as u32);
+        }
+        entries.push(IfdEntry {
+            tag: PAYLOAD_TAG,
+            field_type: 7,
+            count: payload.len() as u32,
+            value,
+        });
+        // IFD entries must be sorted in ascending tag order.
+        entries.sort_by_key(|entry| entry.tag);
+
+        out.extend_from_slice(&self.encode_ifd(&entries, ifd.next));
+        let offset = if payload.len() > 4 {
+            out.extend_from_slice(payload);
+            payload_offset
+        } else {
+            // The value field follows the tag, type and count of the entry.
+            let index = entries.iter().position(|entry| entry.tag == PAYLOAD_TAG);
+            ifd_offset + 2 + index.unwrap_or(0) * 12 + 8
+        };
+        out[4..8].copy_from_slice(&order.u32_bytes(ifd_offset as u32));
+        Ok((out, offset))
+    }
+
+    /// Hides a payload in an unreferenced strip appended at the end of the file.
+    ///
+    /// The strip starts with `STRIP_MAGIC` followed by the payload length, so it can be found
+    /// again without any offset. TIFF readers ignore it since no IFD references it.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The bytes to hide.
+    ///
+    /// # Returns
+    ///
+    /// The bytes of the new TIFF file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::tiff::TiffFile;
+    ///
+    /// let data: Vec<u8> = vec![
+    ///     0x49, 0x49, 0x2A, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x03, 0x00,
+    ///     0x01, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    /// ];
+    /// let tiff = TiffFile::parse(data).unwrap();
+    /// let stego = TiffFile::parse(tiff.embed_in_strip(b"secret")).unwrap();
+    /// assert_eq!(stego.extract_payload().unwrap(), b"secret");
+    /// ```
+    pub fn embed_in_strip(&self, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.data.len() + payload.len() + 9);
+        self.write_strip_embedded(payload, payload.len() as u64, &mut out)
+            .expect("Writing to a Vec never fails");
+        out
+    }
+
+    /// Streams the file with a payload strip appended to `writer`.
+    ///
+    /// This is the streaming counterpart of `embed_in_strip`, used when the payload is too big
+    /// to be held in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The source of the bytes to hide.
+    /// * `len` - The number of bytes to read from `payload`.
+    /// * `writer` - The destination of the new TIFF file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the offset of the payload in the new file.
+    pub fn write_strip_embedded<R: Read, W: Write>(
+        &self,
+        payload: R,
+        len: u64,
+        mut writer: W,
+    ) -> io::Result<u64> {
+        let len32 = u32::try_from(len)
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "Payload too large for TIFF"))?;
+        writer.write_all(&self.data)?;
+        if self.data.len() % 2 == 1 {
+            writer.write_all(&[0])?;
+        }
+        writer.write_all(STRIP_MAGIC)?;
+        writer.write_all(&self.byte_order.u32_bytes(len32))?;
+        let copied = io::copy(&mut payload.take(len), &mut writer)?;
+        if copied != len {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Payload shorter than announced",
+            ));
+        }
+        // After the padding byte, the magic and the length.
+        Ok((self.data.len() + self.data.len() % 2 + 8) as u64)
+    }
+
+    /// Returns the entry holding the payload in the first IFD, if any.
+    fn payload_entry(&self) -> Option<&IfdEntry> {
+        self.ifds
+            .first()?
+            .entries
+            .iter()
+            .find(|entry| entry.tag == PAYLOAD_TAG)
+    }
+
+    /// Restores the file `embed_in_tag` was given, if the IFD it copied is still in the file.
+    ///
+    /// The copy holds the entries of the original IFD plus the payload entry, so the original
+    /// is found by its encoding. The result is only returned if embedding the payload into it
+    /// again gives this file back, byte for byte.
+    fn restore_copied_ifd(&self, entries: &[IfdEntry], ifd: &Ifd) -> Option<Vec<u8>> {
+        let original = self.encode_ifd(entries, ifd.next);
+        let end = ifd.offset as usize;
+        let last = end.checked_sub(original.len())?;
+        let offset = (8..=last)
+            .step_by(2)
+            .find(|&pos| self.data[pos..pos + original.len()] == original[..])?;
+        let mut data = self.data[..end].to_vec();
+        data[4..8].copy_from_slice(&self.byte_order.u32_bytes(offset as u32));
+        let restored = TiffFile::parse(data).ok()?;
+        let payload = self.extract_payload().ok()?;
+        (restored.embed_in_tag(&payload).ok()?.0 == self.data).then_some(restored.data)
+    }
+
+    /// Returns the offset of a payload strip appended at the end of the file, if any.
+    fn strip_offset(&self) -> Option<usize> {
+        let data = &self.data;
+        (0..data.len().saturating_sub(8)).rev().find(|&pos| {
+            if &data[pos..pos + 4] != STRIP_MAGIC {
+                return false;
+            }
+            match self.byte_order.read_u32(data, pos + 4) {
+                Ok(len) => pos + 8 + len as usize == data.len(),
+                Err(_) => false,
+            }
+        })
+    }
+
+    /// Extracts a hidden payload from either the private tag or an appended strip.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the payload bytes, or an `io::Error` if no payload was found.
+    pub fn extract_payload(&self) -> io::Result<Vec<u8>> {
+        if let Some(entry) = self.payload_entry() {
+            return Ok(entry.data(self.byte_order, &self.data)?.to_vec());
+        }
+        if let Some(pos) = self.strip_offset() {
+            return Ok(self.data[pos + 8..].to_vec());
+        }
+        Err(io::Error::new(
+            ErrorKind::NotFound,
+            "No payload found in the TIFF file",
+        ))
+    }
+
+    /// Removes a hidden payload, restoring a clean TIFF file.
+    ///
+    /// For the tag method, the file is restored byte for byte when the IFD `embed_in_tag`
+    /// copied is still there: the header points back at it and the copy and the payload are
+    /// cut off. Otherwise the first IFD is rewritten in place without the private entry and
+    /// the payload bytes are dropped if they sit at the end of the file, or zeroed otherwise.
+    /// For the strip method, the file is truncated at the start of the strip.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the bytes of the cleaned file, or an `io::Error` if no payload was found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::tiff::TiffFile;
+    ///
+    /// let data: Vec<u8> = vec![
+    ///     0x49, 0x49, 0x2A, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x03, 0x00,
+    ///     0x01, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    /// ];
+    /// let tiff = TiffFile::parse(data).unwrap();
+    /// let stego = TiffFile::parse(tiff.embed_in_tag(b"secret payload").unwrap().0).unwrap();
+    /// let clean = stego.remove_payload().unwrap();
+    /// assert_eq!(clean, tiff.data);
+    /// assert!(TiffFile::parse(clean).unwrap().extract_payload().is_err());
+    ///
+    /// // A payload tag pointing past the end of the file is a format error.
+    /// let crafted: Vec<u8> = vec![
+    ///     0x49, 0x49, 0x2A, 0x00, 0x08, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x01, 0x03, 0x00,
+    ///     0x01, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0xE8, 0xFD, 0x07, 0x00, 0x10, 0x00,
+    ///     0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00,
+    /// ];
+    /// let error = TiffFile::parse(crafted).unwrap().remove_payload().unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    /// ```
+    pub fn remove_payload(&self) -> io::Result<Vec<u8>> {
+        let order = self.byte_order;
+        if let Some(entry) = self.payload_entry() {
+            let ifd = self.first_ifd()?;
+            let entries: Vec<IfdEntry> = ifd
+                .entries
+                .iter()
+                .filter(|e| e.tag != PAYLOAD_TAG)
+                .cloned()
+                .collect();
+            if let Some(original) = self.restore_copied_ifd(&entries, ifd) {
+                return Ok(original);
+            }
+            let mut out = self.data.clone();
+            let start = ifd.offset as usize;
+            let old_len = 2 + ifd.entries.len() * 12 + 4;
+            let encoded = self.encode_ifd(&entries, ifd.next);
+            out[start..start + encoded.len()].copy_from_slice(&encoded);
+            out[start + encoded.len()..start + old_len].fill(0);
+
+            if !entry.is_inline() {
+                let offset = order.read_u32(&entry.value, 0)? as usize;
+                let end = offset.checked_add(entry.data_len());
+                if end == Some(out.len()) {
+                    out.truncate(offset.max(start + old_len));
+                } else {
+                    end.and_then(|end| out.get_mut(offset..end))
+                        .ok_or_else(|| {
+                            io::Error::new(
+                                ErrorKind::InvalidData,
+                                "The payload tag points past the end of the TIFF file",
+                            )
+                        })?
+                        .fill(0);
+                }
+            }
+            return Ok(out);
+        }
+        if let Some(pos) = self.strip_offset() {
+            return Ok(self.data[..pos].to_vec());
+        }
+        Err(io::Error::new(
+            ErrorKind::NotFound,
+            "No payload found in the TIFF file",
+        ))
+    }
+}
+
+/// Reads a TIFF file and prints its header and Image File Directories.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice representing the path to the TIFF file.
+/// * `nb_entries` - The maximum number of IFD entries to print.
+/// * `suppress` - A boolean to suppress print statements.
+///
+/// # Returns
+///
+/// A `Result` containing the parsed `TiffFile` or an error if the file could not be read or parsed.
+pub fn read_tiff_headers(
+    file_path: &str,
+    nb_entries: usize,
+    suppress: bool,
+) -> Result<TiffFile, Box<dyn Error>> {
+    let data = read_input(file_path)?;
+    let tiff = TiffFile::parse(data)?;
+    if !suppress {
+        print_tiff_headers(&tiff.data, tiff.byte_order, &tiff.ifds, nb_entries)?;
+    }
+    Ok(tiff)
+}
+
+/// Prints the header and Image File Directories of a TIFF file without copying it, for
+/// `show-meta` over large files; see `mapped::MappedFile`.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice representing the path to the TIFF file.
+/// * `nb_entries` - The maximum number of IFD entries to print.
+/// * `suppress` - A boolean to suppress print statements, only checking the file.
+pub fn scan_tiff_headers(
+    file_path: &str,
+    nb_entries: usize,
+    suppress: bool,
+) -> Result<(), Box<dyn Error>> {
+    let data = map_input(file_path)?;
+    let (byte_order, ifds) = TiffFile::parse_ifds(&data)?;
+    if !suppress {
+        print_tiff_headers(&data, byte_order, &ifds, nb_entries)?;
+    }
+    Ok(())
+}
+
+/// Prints the header and the IFDs parsed from `data`.
+fn print_tiff_headers(
+    data: &[u8],
+    byte_order: ByteOrder,
+    ifds: &[Ifd],
+    nb_entries: usize,
+) -> io::Result<()> {
+    outln!("It is a valid TIFF file. Let's process it! \n");
+    outln!("{}", Style::Green.paint("---- Header ----"));
+    print_hex(&data[..8], 0);
+    outln!("Byte order: {:?}", byte_order);
+    outln!("{}", Style::Green.paint("----- End ------"));
+    outln!();
+
+    let mut printed = 0;
+    for (i, ifd) in ifds.iter().enumerate() {
+        outln!("{}", Style::Green.paint(format!("---- IFD #{} ----", i)));
+        outln!("Offset: {}", ifd.offset);
+        outln!("Entries: {}", ifd.entries.len());
+        for entry in &ifd.entries {
+            if printed >= nb_entries {
+                break;
+            }
+            let value = if entry.is_inline() {
+                format!("{:02X?}", &entry.value[..entry.data_len()])
+            } else {
+                format!("@{}", byte_order.read_u32(&entry.value, 0)?)
+            };
+            outln!(
+                "{} {:<26} {:<9} count={} value={}",
+                Style::Yellow.paint(format!("{:>5}", entry.tag)),
+                tag_name(entry.tag),
+                type_name(entry.field_type),
+                entry.count,
+                value
+            );
+            printed += 1;
+        }
+        outln!("Next IFD: {}", ifd.next);
+        outln!("{}", Style::Green.paint("------ End ------"));
+        outln!();
+    }
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use crate::jpeg::markers::MarkerInventory;
+use crate::png::chunks;
+use crate::style::Style;
+use crate::tr;
+use crate::utils::HexFormatter;
+use std::io::{self, ErrorKind, Write};
+
+/// The number of trailing bytes `show-meta` dumps as a preview.
+pub const PREVIEW_LEN: usize = 64;
+
+/// The share of the highest entropy its length allows above which trailing data looks
+/// encrypted or compressed. A few hundred random bytes can't reach 8 bits per byte, as they
+/// don't hold every byte value the same number of times.
+pub const HIGH_ENTROPY_RATIO: f64 = 0.9;
+
+/// Bytes found after the terminator of an image, where many tools append their payloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trailing<'a> {
+    /// The name of the terminator, `IEND` or `EOI`.
+    pub terminator: &'static str,
+    /// The offset of the first byte after the terminator.
+    pub offset: usize,
+    /// The bytes after the terminator.
+    pub data: &'a [u8],
+}
+
+/// Finds the bytes following the `IEND` chunk of a PNG file or the EOI marker of a JPEG file.
+///
+/// PNG chunks are walked by their declared lengths, and JPEG markers through the
+/// entropy-coded data of their scans, so bytes that merely look like a terminator inside the
+/// image aren't mistaken for its end.
+///
+/// # Arguments
+///
+/// * `data` - The file.
+/// * `file_type` - The type of the file, `png` or `jpeg`.
+///
+/// # Returns
+///
+/// A `Result` containing the trailing bytes, or `None` if the file ends with its terminator
+/// or has none. It is an error if the file isn't of that type or the type has no terminator.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::encode_grayscale;
+/// use stegano::trailing::find_trailing;
+///
+/// let mut png = encode_grayscale(1, 1, &[0]).unwrap();
+/// assert!(find_trailing(&png, "png").unwrap().is_none());
+/// let end = png.len();
+/// png.extend_from_slice(b"appended");
+/// let trailing = find_trailing(&png, "png").unwrap().unwrap();
+/// assert_eq!((trailing.terminator, trailing.offset), ("IEND", end));
+/// assert_eq!(trailing.data, b"appended");
+///
+/// let jpeg = [0xFF, 0xD8, 0xFF, 0xDA, 0x00, 0x02, 0x12, 0xFF, 0xD9, b'!'];
+/// assert_eq!(find_trailing(&jpeg, "jpeg").unwrap().unwrap().data, b"!");
+/// assert!(find_trailing(b"GIF89a", "gif").is_err());
+/// ```
+pub fn find_trailing<'a>(data: &'a [u8], file_type: &str) -> io::Result<Option<Trailing<'a>>> {
+    let (terminator, end) = match file_type.to_lowercase().as_str() {
+        "png" => {
+            let mut end = None;
+            for chunk in chunks(data) {
+                let chunk = chunk?;
+                if chunk.r#type == *b"IEND" {
+                    end = Some(chunk.offset + 12 + chunk.data.len());
+                }
+            }
+            ("IEND", end)
+        }
+        "jpeg" | "jpg" => {
+            let inventory = MarkerInventory::parse(data)?;
+            let ended = inventory
+                .segments
+                .last()
+                .is_some_and(|segment| segment.marker == 0xD9);
+            ("EOI", ended.then(|| data.len() - inventory.trailing))
+        }
+        other => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Only PNG and JPEG files have trailing data, not {}", other),
+            ))
+        }
+    };
+    Ok(end.filter(|&end| end < data.len()).map(|offset| Trailing {
+        terminator,
+        offset,
+        data: &data[offset..],
+    }))
+}
+
+/// Returns the Shannon entropy of bytes, from 0 (a single value repeated) to 8 bits per byte
+/// (uniformly random, as encrypted or well compressed data is).
+///
+/// # Examples
+///
+/// ```
+/// use stegano::trailing::entropy;
+///
+/// assert_eq!(entropy(b""), 0.0);
+/// assert_eq!(entropy(b"aaaa"), 0.0);
+/// assert_eq!(entropy(b"abab"), 1.0);
+/// let all: Vec<u8> = (0..=255).collect();
+/// assert_eq!(entropy(&all), 8.0);
+/// ```
+pub fn entropy(data: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum::<f64>()
+        .abs()
+}
+
+impl Trailing<'_> {
+    /// Returns the entropy of the trailing bytes, in bits per byte.
+    pub fn entropy(&self) -> f64 {
+        entropy(self.data)
+    }
+
+    /// Tells whether the trailing bytes look encrypted or compressed: their entropy comes close
+    /// to the highest their length allows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::trailing::Trailing;
+    ///
+    /// let text = Trailing { terminator: "EOI", offset: 0, data: b"a plain text comment, repeated, repeated" };
+    /// assert!(!text.looks_random());
+    /// let bytes: Vec<u8> = (0..=255).rev().collect();
+    /// assert!(Trailing { terminator: "EOI", offset: 0, data: &bytes }.looks_random());
+    /// ```
+    pub fn looks_random(&self) -> bool {
+        let highest = (self.data.len().min(256) as f64).log2();
+        self.data.len() > 1 && self.entropy() > HIGH_ENTROPY_RATIO * highest
+    }
+
+    /// Writes what `show-meta` tells of the trailing bytes: their length, offset and entropy,
+    /// then a hexdump of the first `PREVIEW_LEN` of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stegano::trailing::Trailing;
+    ///
+    /// let trailing = Trailing { terminator: "IEND", offset: 57, data: b"abab" };
+    /// let mut out = Vec::new();
+    /// trailing.write_report(&mut out).unwrap();
+    /// let out = String::from_utf8(out).unwrap();
+    /// assert!(out.contains("4 bytes follow IEND at offset 57, with 1.00 bits of entropy per byte"));
+    /// assert!(out.contains("61 62 61 62"));
+    /// ```
+    pub fn write_report<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let entropy = self.entropy();
+        writeln!(
+            out,
+            "{} {}",
+            Style::Yellow.paint(tr!("label-trailing")),
+            tr!(
+                "trailing-found",
+                bytes = self.data.len(),
+                terminator = self.terminator,
+                offset = self.offset,
+                entropy = format!("{:.2}", entropy)
+            )
+        )?;
+        if self.looks_random() {
+            writeln!(out, "{}", tr!("trailing-high-entropy"))?;
+        }
+        HexFormatter::new(self.offset as u64)
+            .limit(Some(PREVIEW_LEN))
+            .write(out, self.data)
+    }
+}
@@ -1,6 +1,17 @@
+use crate::output::{emit, Output};
+use crate::style::{colors_enabled, Stream, Style};
+use crate::{outln, tr};
 use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit};
-use aes::Aes128;
-use std::mem;
+use aes::{Aes128, Aes192, Aes256};
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20::ChaCha20;
+use sha2::{Digest, Sha256};
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::ops::Range;
+
+/// The error of XOR with an empty key, which has nothing to cycle through.
+const EMPTY_XOR_KEY: &str = "The XOR key can't be empty!";
+
 /// Performs XOR encrypting or decrypting on the provided byte slice using the specified key.
 ///
 /// # Arguments
@@ -10,7 +21,8 @@ use std::mem;
 ///
 /// # Returns
 ///
-/// A `Vec<u8>` containing the result of the XOR operation.
+/// A `Result` containing the result of the XOR operation, or an error message if the key is
+/// empty.
 ///
 /// # Examples
 ///
@@ -19,47 +31,20 @@ use std::mem;
 ///
 /// let input = b"Hello, World!";
 /// let key = "secret_key";
-/// let encrypted = xor_encrypt_decrypt(input, key);
-/// let decrypted = xor_encrypt_decrypt(&encrypted, key);
+/// let encrypted = xor_encrypt_decrypt(input, key).unwrap();
+/// let decrypted = xor_encrypt_decrypt(&encrypted, key).unwrap();
 /// assert_eq!(input, decrypted.as_slice());
+/// assert!(xor_encrypt_decrypt(input, "").is_err());
 /// ```
-pub fn xor_encrypt_decrypt(input: &[u8], key: &str) -> Vec<u8> {
+pub fn xor_encrypt_decrypt(input: &[u8], key: &str) -> Result<Vec<u8>, &'static str> {
+    if key.is_empty() {
+        return Err(EMPTY_XOR_KEY);
+    }
     let mut b_arr = Vec::with_capacity(input.len());
     for (i, &byte) in input.iter().enumerate() {
         b_arr.push(byte ^ key.as_bytes()[i % key.len()]);
     }
-    b_arr
-}
-
-/// Converts a 64-bit unsigned integer to an array of 8 bytes.
-///
-/// # Arguments
-///
-/// * `value` - The 64-bit unsigned integer to be converted.
-///
-/// # Returns
-///
-/// An array of 8 bytes representing the input value.
-///
-/// # Examples
-///
-/// ```
-/// use stegano::utils::u64_to_u8_array;
-///
-/// let value = 1234567890u64;
-/// let byte_array = u64_to_u8_array(value);
-/// assert_eq!(value.to_ne_bytes(), byte_array);
-/// ```
-pub fn u64_to_u8_array(value: u64) -> [u8; 8] {
-    let bytes = value.to_ne_bytes();
-    let mut _result = [0; 8];
-
-    unsafe {
-        // Transmute the byte array into an array of unsigned 8-bit integers
-        _result = mem::transmute_copy(&bytes);
-    }
-
-    _result
+    Ok(b_arr)
 }
 
 /// Prints a hexadecimal representation of the input data with ASCII interpretation.
@@ -81,9 +66,11 @@ pub fn u64_to_u8_array(value: u64) -> [u8; 8] {
 ///
 /// # Output
 ///
-/// The function prints the hexadecimal representation of the input data in chunks of 20 bytes.
+/// The function prints the hexadecimal representation of the input data in chunks of 20 bytes,
+/// as an `Output::Hexdump` of the output sink.
 /// Each chunk is displayed with an address offset, hexadecimal values, ASCII interpretation,
-/// and alternating colors (blue and green) for better visibility.
+/// and alternating colors (blue and green) for better visibility, when stdout gets colors
+/// (see `style::colors_enabled`).
 ///
 /// Hexadecimal values are printed in the following format:
 ///
@@ -94,31 +81,180 @@ pub fn u64_to_u8_array(value: u64) -> [u8; 8] {
 /// The ASCII interpretation is displayed on the right, and non-printable ASCII characters
 /// are represented as dots ('.').
 pub fn print_hex(data: &[u8], offset: u64) {
-    for (i, chunk) in data.chunks(20).enumerate() {
-        print!("{:08} | ", offset + 20 * i as u64);
-
-        for (j, &byte) in chunk.iter().enumerate() {
-            // Alternating colors (blue and green)
-            let color = if j % 2 == 0 { "\x1b[94m" } else { "\x1b[92m" };
-            print!("{}{:02X} \x1b[0m", color, byte);
-        }
-
-        print!("| ");
-
-        for byte_chunk in chunk.chunks(4) {
-            for byte in byte_chunk {
-                print!(
-                    "{}",
-                    if byte.is_ascii() && byte.is_ascii_graphic() {
-                        *byte as char
-                    } else {
-                        '.'
-                    }
-                );
+    let mut text = Vec::new();
+    let _ = HexFormatter::new(offset).write(&mut text, data);
+    // A closed stdout, such as a pager quit early, isn't worth a panic.
+    let _ = emit(Output::Hexdump {
+        offset,
+        data,
+        text: &String::from_utf8_lossy(&text),
+    });
+}
+
+/// Number of bytes per hexdump line.
+pub(crate) const HEX_LINE: usize = 20;
+
+/// Formats hexdumps, as `print_hex` prints them, into any writer.
+///
+/// A dump can be cut down to a byte range of the data and to a number of bytes, so that large
+/// chunks don't flood the terminal; what is left out is summarized on a last line.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::utils::HexFormatter;
+///
+/// let data: Vec<u8> = (0..100).collect();
+/// let mut out = Vec::new();
+/// HexFormatter::new(1000)
+///     .color(false)
+///     .range(Some(40..100))
+///     .limit(Some(20))
+///     .write(&mut out, &data)
+///     .unwrap();
+/// let out = String::from_utf8(out).unwrap();
+/// assert!(out.starts_with("00001040 | 28 29 2A"));
+/// assert!(out.ends_with("... 80 more bytes of 100 not shown\n"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct HexFormatter {
+    offset: u64,
+    range: Option<Range<usize>>,
+    limit: Option<usize>,
+    color: bool,
+    highlight: Option<(Range<usize>, Style)>,
+}
+
+impl HexFormatter {
+    /// Creates a formatter of whole dumps, coloured if stdout gets colors.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The address of the first byte of the data.
+    pub fn new(offset: u64) -> Self {
+        HexFormatter {
+            offset,
+            range: None,
+            limit: None,
+            color: colors_enabled(Stream::Stdout),
+            highlight: None,
+        }
+    }
+
+    /// Dumps only this byte range of the data, clamped to its length.
+    pub fn range(mut self, range: Option<Range<usize>>) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// Dumps at most this many bytes.
+    pub fn limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Alternates blue and green bytes with ANSI escapes, or not.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Paints the bytes of this range of the data in one style instead of alternating colors,
+    /// such as the bytes a payload inserted.
+    pub fn highlight(mut self, range: Range<usize>, style: Style) -> Self {
+        self.highlight = Some((range, style));
+        self
+    }
+
+    /// Writes the dump of `data`.
+    ///
+    /// # Returns
+    ///
+    /// An `io::Result` reporting errors of the writer.
+    pub fn write<W: Write>(&self, writer: &mut W, data: &[u8]) -> io::Result<()> {
+        let range = self.range.clone().unwrap_or(0..data.len());
+        let start = range.start.min(data.len());
+        let end = range.end.clamp(start, data.len());
+        let shown = end - start;
+        let end = start + self.limit.map_or(shown, |limit| limit.min(shown));
+        for (i, chunk) in data[start..end].chunks(HEX_LINE).enumerate() {
+            write!(
+                writer,
+                "{:08} | ",
+                self.offset + (start + HEX_LINE * i) as u64
+            )?;
+            for (j, &byte) in chunk.iter().enumerate() {
+                let position = start + HEX_LINE * i + j;
+                // Alternating colors (blue and green)
+                let style = match &self.highlight {
+                    Some((range, style)) if range.contains(&position) => *style,
+                    _ if j % 2 == 0 => Style::Blue,
+                    _ => Style::Green,
+                };
+                write!(
+                    writer,
+                    "{} ",
+                    style.paint_if(self.color, format!("{:02X}", byte))
+                )?;
+            }
+            write!(writer, "| ")?;
+            for &byte in chunk {
+                let shown = if byte.is_ascii_graphic() {
+                    byte as char
+                } else {
+                    '.'
+                };
+                write!(writer, "{}", shown)?;
             }
+            writeln!(writer)?;
         }
-        println!();
+        let hidden = data.len() - (end - start);
+        if hidden > 0 {
+            writeln!(
+                writer,
+                "... {} more bytes of {} not shown",
+                hidden,
+                data.len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the value of `--hex-range`: `start..end`, with either bound left out for the start
+/// or the end of the data, in decimal or in hex after `0x`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::utils::parse_hex_range;
+///
+/// assert_eq!(parse_hex_range("16..64").unwrap(), 16..64);
+/// assert_eq!(parse_hex_range("0x10..").unwrap(), 16..usize::MAX);
+/// assert_eq!(parse_hex_range("..8").unwrap(), 0..8);
+/// assert!(parse_hex_range("64..16").is_err());
+/// ```
+pub fn parse_hex_range(value: &str) -> Result<Range<usize>, String> {
+    let bound = |text: &str, default: usize| -> Result<usize, String> {
+        let text = text.trim();
+        let parsed = match text.strip_prefix("0x") {
+            _ if text.is_empty() => return Ok(default),
+            Some(hex) => usize::from_str_radix(hex, 16),
+            None => text.parse(),
+        };
+        parsed.map_err(|_| format!("invalid bound '{}'", text))
+    };
+    let (start, end) = value
+        .split_once("..")
+        .ok_or_else(|| format!("invalid range '{}', expected start..end", value))?;
+    let (start, end) = (bound(start, 0)?, bound(end, usize::MAX)?);
+    if start > end {
+        return Err(format!(
+            "invalid range '{}', the start is past the end",
+            value
+        ));
     }
+    Ok(start..end)
 }
 
 /// Pad the input slice with zeros to create a fixed-size array of 16 bytes.
@@ -149,6 +285,9 @@ pub fn pad_with_zeros(slice: &[u8]) -> [u8; 16] {
 
 /// Encrypts the payload using AES-128 encryption algorithm with zero-padding.
 ///
+/// This is the format of payloads without a header. Payloads with one are padded with
+/// PKCS#7 by `encrypt_stream_mode`, so that trailing zero bytes aren't taken for padding.
+///
 /// # Arguments
 ///
 /// * `key` - A string representing the encryption key.
@@ -166,38 +305,41 @@ pub fn pad_with_zeros(slice: &[u8]) -> [u8; 16] {
 /// let key = "secret_key";
 /// let payload = "confidential_data";
 /// let encrypted_data = encrypt_payload(key, payload);
-/// assert_eq!(encrypted_data.len(), 16);
+/// assert_eq!(encrypted_data.len(), 32);
+/// ```
+///
+/// The last block is zero-padded rather than dropped, whatever the payload length:
+///
+/// ```
+/// use stegano::utils::{decrypt_data, encrypt_payload, strip_padding};
+///
+/// for len in 1..100 {
+///     let payload = "x".repeat(len);
+///     let encrypted_data = encrypt_payload("key", &payload);
+///     assert_eq!(encrypted_data.len(), (len + 15) / 16 * 16);
+///     let mut decrypted_data = decrypt_data("key", &encrypted_data);
+///     strip_padding("aes", &mut decrypted_data);
+///     assert_eq!(decrypted_data, payload.as_bytes());
+/// }
 /// ```
 pub fn encrypt_payload(key: &str, payload: &str) -> Vec<u8> {
     let in_key: &[u8; 16] = &pad_with_zeros(key.as_bytes());
     let key = GenericArray::clone_from_slice(in_key);
+    let cipher = Aes128::new(&key);
 
-    if payload.len() <= 16 {
-        let in_payload: &[u8; 16] = &pad_with_zeros(payload.as_bytes());
-        let mut block = GenericArray::clone_from_slice(in_payload);
+    // An empty payload still takes a block, as it always has.
+    let blocks = payload.as_bytes().chunks(16).len().max(1);
+    let mut encrypted_data: Vec<u8> = Vec::with_capacity(blocks * 16);
 
-        let cipher = Aes128::new(&key);
+    for i in 0..blocks {
+        let chunk = payload.as_bytes().get(i * 16..).unwrap_or_default();
+        let in_payload: &[u8; 16] = &pad_with_zeros(chunk);
+        let mut block = GenericArray::clone_from_slice(in_payload);
         cipher.encrypt_block(&mut block);
-        block.to_vec()
-    } else {
-        let mut encrypted_data: Vec<u8> = Vec::new();
-
-        for (i, chunk) in payload.as_bytes().chunks_exact(16).enumerate() {
-            let in_payload: &[u8; 16] = &pad_with_zeros(chunk);
-            let mut block = GenericArray::clone_from_slice(in_payload);
-
-            let cipher = Aes128::new(&key);
-            cipher.encrypt_block(&mut block);
-
-            if i > 0 {
-                encrypted_data.extend_from_slice(&block);
-            } else {
-                encrypted_data = block.to_vec();
-            }
-        }
-
-        encrypted_data
+        encrypted_data.extend_from_slice(&block);
     }
+
+    encrypted_data
 }
 
 /// Decrypts the data using AES-128 decryption algorithm with zero-padding.
@@ -243,3 +385,615 @@ pub fn decrypt_data(key: &str, data: &[u8]) -> Vec<u8> {
 
     decrypted_data
 }
+
+/// Tells whether an algorithm is one of the AES variants: aes (AES-128), aes192 or aes256.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::utils::is_aes;
+///
+/// assert!(is_aes("AES256"));
+/// assert!(!is_aes("xor"));
+/// ```
+pub fn is_aes(algorithm: &str) -> bool {
+    matches!(
+        algorithm.to_lowercase().as_str(),
+        "aes" | "aes192" | "aes256"
+    )
+}
+
+/// Derives the cipher key of an AES variant from a key string.
+///
+/// AES-128 keeps the historical derivation, the key cut or zero-padded to 16 bytes, so that
+/// older payloads still decrypt. AES-192 and AES-256 take the SHA-256 of the key, cut to 24 or
+/// 32 bytes, so that every character of a long key counts and short keys aren't mostly zeros.
+///
+/// # Returns
+///
+/// The key bytes, or `None` if the algorithm isn't an AES variant.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::utils::derive_aes_key;
+///
+/// assert_eq!(derive_aes_key("aes", "key").unwrap()[..4], *b"key\0");
+/// assert_eq!(derive_aes_key("aes192", "key").unwrap().len(), 24);
+/// assert_eq!(derive_aes_key("aes256", "key").unwrap().len(), 32);
+/// assert!(derive_aes_key("xor", "key").is_none());
+/// ```
+pub fn derive_aes_key(algorithm: &str, key: &str) -> Option<Vec<u8>> {
+    let len = match algorithm.to_lowercase().as_str() {
+        "aes" => return Some(pad_with_zeros(key.as_bytes()).to_vec()),
+        "aes192" => 24,
+        "aes256" => 32,
+        _ => return None,
+    };
+    Some(Sha256::digest(key.as_bytes())[..len].to_vec())
+}
+
+/// An AES block cipher of any of the supported key sizes.
+enum AesCipher {
+    Aes128(Aes128),
+    Aes192(Aes192),
+    Aes256(Aes256),
+}
+
+impl AesCipher {
+    /// Creates the cipher of an AES variant, with the key derived by `derive_aes_key`.
+    fn new(algorithm: &str, key: &str) -> Option<AesCipher> {
+        let bytes = derive_aes_key(algorithm, key)?;
+        Some(match bytes.len() {
+            16 => AesCipher::Aes128(Aes128::new(GenericArray::from_slice(&bytes))),
+            24 => AesCipher::Aes192(Aes192::new(GenericArray::from_slice(&bytes))),
+            _ => AesCipher::Aes256(Aes256::new(GenericArray::from_slice(&bytes))),
+        })
+    }
+
+    /// Encrypts a block in place.
+    fn encrypt(&self, block: &mut [u8; 16]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            AesCipher::Aes128(cipher) => cipher.encrypt_block(block),
+            AesCipher::Aes192(cipher) => cipher.encrypt_block(block),
+            AesCipher::Aes256(cipher) => cipher.encrypt_block(block),
+        }
+    }
+
+    /// Decrypts a block in place.
+    fn decrypt(&self, block: &mut [u8; 16]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            AesCipher::Aes128(cipher) => cipher.decrypt_block(block),
+            AesCipher::Aes192(cipher) => cipher.decrypt_block(block),
+            AesCipher::Aes256(cipher) => cipher.decrypt_block(block),
+        }
+    }
+}
+
+/// Creates the ChaCha20 stream cipher of a key string, as the "chacha" algorithm runs it.
+///
+/// The 32-byte key is the SHA-256 of the key, and the 12-byte nonce the first bytes of the IV
+/// of the mode, which `PayloadHeader::with_mode` always draws for ChaCha payloads.
+///
+/// # Returns
+///
+/// A `Result` containing the cipher, or an `InvalidInput` error for ECB, which has no IV: a
+/// fixed nonce would encrypt every payload under the same key with the same keystream.
+fn chacha_cipher(key: &str, mode: &CipherMode) -> io::Result<ChaCha20> {
+    let iv = mode
+        .iv()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, CHACHA_NEEDS_NONCE))?;
+    let key = Sha256::digest(key.as_bytes());
+    Ok(ChaCha20::new(&key, GenericArray::from_slice(&iv[..12])))
+}
+
+/// Why ChaCha20 is refused without an IV to take its nonce from.
+const CHACHA_NEEDS_NONCE: &str =
+    "ChaCha20 takes a random nonce from the payload header, which payloads without one lack; use aes or xor";
+
+/// The block mode new AES payloads are written in.
+pub const DEFAULT_BLOCK_MODE: &str = "ctr";
+
+/// The block mode the AES variants run in, with its IV.
+///
+/// ECB encrypts each block on its own, so identical plaintext blocks give identical
+/// ciphertext blocks; it is kept to decrypt payloads written before the modes existed. CBC
+/// chains each block with the ciphertext of the one before, starting from the IV. CTR
+/// encrypts a counter starting at the IV and XORs it with the plaintext, so the ciphertext
+/// is as long as the plaintext. XOR payloads ignore the mode.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::utils::{decrypt_with_mode, encrypt_stream_mode, CipherMode, Padding};
+///
+/// let payload = b"sixteen byte blk".repeat(2);
+/// for mode in [CipherMode::Ecb, CipherMode::new("cbc").unwrap(), CipherMode::new("ctr").unwrap()] {
+///     let mut encrypted = Vec::new();
+///     encrypt_stream_mode("aes256", "key", &mode, Padding::Pkcs7, &payload[..], &mut encrypted)
+///         .unwrap();
+///     // Only ECB gives the repeated block away.
+///     assert_eq!(encrypted[..16] == encrypted[16..32], mode == CipherMode::Ecb);
+///     let decrypted = decrypt_with_mode("aes256", "key", &mode, Padding::Pkcs7, &encrypted);
+///     assert_eq!(decrypted.unwrap(), payload);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherMode {
+    /// Each block encrypted on its own, without IV.
+    Ecb,
+    /// Cipher block chaining from the IV.
+    Cbc([u8; 16]),
+    /// Counter mode, the IV being the first counter block.
+    Ctr([u8; 16]),
+}
+
+impl CipherMode {
+    /// Creates a mode by name, ecb, cbc or ctr in any case, with a random IV.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the mode, or an `InvalidInput` error for other names.
+    pub fn new(name: &str) -> io::Result<CipherMode> {
+        let mut iv = [0u8; 16];
+        let mode = match name.to_lowercase().as_str() {
+            "ecb" => return Ok(CipherMode::Ecb),
+            "cbc" => CipherMode::Cbc,
+            "ctr" => CipherMode::Ctr,
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unsupported block mode {:?}, use ecb, cbc or ctr", name),
+                ))
+            }
+        };
+        getrandom::getrandom(&mut iv).map_err(|err| io::Error::other(err.to_string()))?;
+        Ok(mode(iv))
+    }
+
+    /// Returns the lowercase name of the mode.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CipherMode::Ecb => "ecb",
+            CipherMode::Cbc(_) => "cbc",
+            CipherMode::Ctr(_) => "ctr",
+        }
+    }
+
+    /// Returns the IV of the mode, none for ECB.
+    pub fn iv(&self) -> Option<&[u8; 16]> {
+        match self {
+            CipherMode::Ecb => None,
+            CipherMode::Cbc(iv) | CipherMode::Ctr(iv) => Some(iv),
+        }
+    }
+}
+
+/// How ECB and CBC fill the last block of a payload.
+///
+/// Zero padding can't tell padding from plaintext ending in zero bytes, so only the length in
+/// the payload header, or trimming every trailing zero for legacy payloads, recovers the
+/// plaintext. PKCS#7 fills the last block with the number of padding bytes, adding a whole
+/// block when the plaintext fills the last one, so it always comes off exactly. CTR and XOR
+/// don't pad and ignore it.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::utils::Padding;
+///
+/// assert_eq!(Padding::Pkcs7.pad(b"secret"), *b"secret\x0a\x0a\x0a\x0a\x0a\x0a\x0a\x0a\x0a\x0a");
+/// assert_eq!(Padding::Zeros.pad(b"secret"), *b"secret\0\0\0\0\0\0\0\0\0\0");
+///
+/// // Trailing zero bytes of the plaintext survive PKCS#7.
+/// let mut block = Padding::Pkcs7.pad(b"bin\0\0").to_vec();
+/// Padding::Pkcs7.unpad(&mut block).unwrap();
+/// assert_eq!(block, b"bin\0\0");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    /// Zero bytes, as payloads written before PKCS#7.
+    Zeros,
+    /// PKCS#7: n bytes of value n.
+    Pkcs7,
+}
+
+impl Padding {
+    /// Returns the lowercase name of the padding scheme.
+    pub fn name(self) -> &'static str {
+        match self {
+            Padding::Zeros => "zeros",
+            Padding::Pkcs7 => "pkcs7",
+        }
+    }
+
+    /// Pads the last, partial, block of a payload; PKCS#7 turns an empty one into a whole
+    /// block of padding.
+    pub fn pad(self, tail: &[u8]) -> [u8; 16] {
+        let mut block = pad_with_zeros(tail);
+        if self == Padding::Pkcs7 {
+            let len = 16 - tail.len().min(16);
+            block[16 - len..].fill(len as u8);
+        }
+        block
+    }
+
+    /// Removes PKCS#7 padding from decrypted data; zero padding is left for the caller to
+    /// trim.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is an error if the padding isn't valid PKCS#7.
+    pub fn unpad(self, data: &mut Vec<u8>) -> Result<(), &'static str> {
+        if self == Padding::Zeros {
+            return Ok(());
+        }
+        let len = *data.last().ok_or("Invalid PKCS#7 padding")? as usize;
+        if len == 0 || len > 16 || len > data.len() {
+            return Err("Invalid PKCS#7 padding");
+        }
+        if data[data.len() - len..]
+            .iter()
+            .any(|&byte| byte as usize != len)
+        {
+            return Err("Invalid PKCS#7 padding");
+        }
+        data.truncate(data.len() - len);
+        Ok(())
+    }
+}
+
+/// Size of the buffers `encrypt_stream_mode` reads and writes through.
+const STREAM_BUFFER_LEN: usize = 64 * 1024;
+
+/// Increments a CTR counter block, as a 128-bit big-endian integer.
+fn next_counter(counter: &[u8; 16]) -> [u8; 16] {
+    u128::from_be_bytes(*counter).wrapping_add(1).to_be_bytes()
+}
+
+/// XORs a block with another, in place.
+fn xor_block(block: &mut [u8; 16], other: &[u8; 16]) {
+    block
+        .iter_mut()
+        .zip(other)
+        .for_each(|(byte, other)| *byte ^= other);
+}
+
+/// Decrypts data with an AES cipher in a block mode.
+///
+/// ECB and CBC decrypt whole blocks and ignore a trailing partial block; CTR decrypts every
+/// byte.
+fn aes_decrypt(cipher: &AesCipher, mode: &CipherMode, data: &[u8]) -> Vec<u8> {
+    let mut decrypted = Vec::with_capacity(data.len());
+    match mode {
+        CipherMode::Ecb => {
+            for chunk in data.chunks_exact(16) {
+                let mut block = pad_with_zeros(chunk);
+                cipher.decrypt(&mut block);
+                decrypted.extend_from_slice(&block);
+            }
+        }
+        CipherMode::Cbc(iv) => {
+            let mut previous = *iv;
+            for chunk in data.chunks_exact(16) {
+                let mut block = pad_with_zeros(chunk);
+                cipher.decrypt(&mut block);
+                xor_block(&mut block, &previous);
+                previous = pad_with_zeros(chunk);
+                decrypted.extend_from_slice(&block);
+            }
+        }
+        CipherMode::Ctr(iv) => {
+            let mut counter = *iv;
+            for chunk in data.chunks(16) {
+                let mut keystream = counter;
+                cipher.encrypt(&mut keystream);
+                decrypted.extend(chunk.iter().zip(keystream).map(|(byte, key)| byte ^ key));
+                counter = next_counter(&counter);
+            }
+        }
+    }
+    decrypted
+}
+
+/// Encrypts a payload with the algorithm selected by name ("aes", "aes192", "aes256" or "xor").
+///
+/// The payload has no header to store a nonce in, so ChaCha20 is refused, here as by
+/// `decrypt_with` and `encrypt_stream`.
+///
+/// # Arguments
+///
+/// * `algorithm` - The name of the algorithm, case insensitive.
+/// * `key` - A string representing the encryption key.
+/// * `payload` - A string representing the payload to be encrypted.
+///
+/// # Returns
+///
+/// A `Result` containing the encrypted payload, or an error message if the algorithm is not supported.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::utils::{decrypt_with, encrypt_with};
+///
+/// let encrypted = encrypt_with("xor", "key", "hello").unwrap();
+/// assert_eq!(decrypt_with("xor", "key", &encrypted).unwrap(), b"hello");
+/// assert!(encrypt_with("rot13", "key", "hello").is_err());
+/// assert!(encrypt_with("chacha", "key", "hello").is_err());
+/// assert!(encrypt_with("xor", "", "hello").is_err());
+/// ```
+pub fn encrypt_with(algorithm: &str, key: &str, payload: &str) -> Result<Vec<u8>, &'static str> {
+    match algorithm.to_lowercase().as_str() {
+        "aes" => Ok(encrypt_payload(key, payload)),
+        "xor" => xor_encrypt_decrypt(payload.as_bytes(), key),
+        "aes192" | "aes256" => {
+            let mut encrypted = Vec::new();
+            encrypt_stream(algorithm, key, payload.as_bytes(), &mut encrypted)
+                .map_err(|_| "Unsupported algorithm!")?;
+            Ok(encrypted)
+        }
+        "chacha" => Err(CHACHA_NEEDS_NONCE),
+        _ => Err("Unsupported algorithm!"),
+    }
+}
+
+/// Decrypts data with the algorithm selected by name ("aes", "aes192", "aes256" or "xor").
+///
+/// # Arguments
+///
+/// * `algorithm` - The name of the algorithm, case insensitive.
+/// * `key` - A string representing the decryption key.
+/// * `data` - A slice of u8 representing the data to be decrypted.
+///
+/// # Returns
+///
+/// A `Result` containing the decrypted data, or an error message if the algorithm is not supported.
+pub fn decrypt_with(algorithm: &str, key: &str, data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    decrypt_with_mode(algorithm, key, &CipherMode::Ecb, Padding::Zeros, data)
+}
+
+/// Decrypts data with the algorithm selected by name, in a block mode and padding scheme.
+///
+/// This is `decrypt_with` for payloads whose header records a mode other than ECB or
+/// PKCS#7 padding, which is removed here; XOR ignores both, CTR the padding, and ChaCha20
+/// takes its nonce from the IV.
+///
+/// # Returns
+///
+/// A `Result` containing the decrypted data, or an error message if the algorithm is not
+/// supported or the padding is invalid.
+pub fn decrypt_with_mode(
+    algorithm: &str,
+    key: &str,
+    mode: &CipherMode,
+    padding: Padding,
+    data: &[u8],
+) -> Result<Vec<u8>, &'static str> {
+    if algorithm.eq_ignore_ascii_case("xor") {
+        return xor_encrypt_decrypt(data, key);
+    }
+    if algorithm.eq_ignore_ascii_case("chacha") {
+        let mut decrypted = data.to_vec();
+        chacha_cipher(key, mode)
+            .map_err(|_| CHACHA_NEEDS_NONCE)?
+            .apply_keystream(&mut decrypted);
+        return Ok(decrypted);
+    }
+    let cipher = AesCipher::new(algorithm, key).ok_or("Unsupported algorithm!")?;
+    let mut decrypted = aes_decrypt(&cipher, mode, data);
+    if !matches!(mode, CipherMode::Ctr(_)) {
+        padding.unpad(&mut decrypted)?;
+    }
+    Ok(decrypted)
+}
+
+/// Removes the zero-padding the AES variants add to the last block of a decrypted payload.
+///
+/// XOR payloads keep their length, so they are left as they are.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::utils::strip_padding;
+///
+/// let mut data = b"secret\0\0".to_vec();
+/// strip_padding("xor", &mut data);
+/// assert_eq!(data, b"secret\0\0");
+/// strip_padding("AES", &mut data);
+/// assert_eq!(data, b"secret");
+/// ```
+pub fn strip_padding(algorithm: &str, data: &mut Vec<u8>) {
+    if is_aes(algorithm) {
+        while data.last() == Some(&0) {
+            data.pop();
+        }
+    }
+}
+
+/// Prints a decrypted secret, trimming the zero-padding added by block ciphers.
+///
+/// # Arguments
+///
+/// * `data` - A slice of u8 representing the decrypted data.
+pub fn print_secret(data: &[u8]) {
+    let decoded_string = String::from_utf8_lossy(data);
+    outln!(
+        "{} {}",
+        Style::Grey.paint(tr!("decrypted-secret")),
+        Style::Orange.paint(format!("{:?}", decoded_string.trim_end_matches('\0')))
+    );
+}
+
+/// Encrypts a stream with the algorithm selected by name ("aes", "aes192", "aes256" or "xor"),
+/// block by block.
+///
+/// The payload is never held in memory as a whole, which makes it suitable for huge
+/// payloads. For the AES variants, the final partial block is zero-padded.
+///
+/// # Arguments
+///
+/// * `algorithm` - The name of the algorithm, case insensitive.
+/// * `key` - A string representing the encryption key.
+/// * `reader` - The source of the plaintext.
+/// * `writer` - The destination of the ciphertext.
+///
+/// # Returns
+///
+/// A `Result` containing the number of bytes written, or an `io::Error`.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::utils::{decrypt_data, encrypt_stream};
+///
+/// let mut encrypted = Vec::new();
+/// let written = encrypt_stream("aes", "key", &b"a payload longer than one block"[..], &mut encrypted).unwrap();
+/// assert_eq!(written, 32);
+///
+/// let decrypted = decrypt_data("key", &encrypted);
+/// assert_eq!(&decrypted[..31], b"a payload longer than one block");
+/// ```
+pub fn encrypt_stream<R: Read, W: Write>(
+    algorithm: &str,
+    key: &str,
+    reader: R,
+    writer: W,
+) -> io::Result<u64> {
+    encrypt_stream_mode(
+        algorithm,
+        key,
+        &CipherMode::Ecb,
+        Padding::Zeros,
+        reader,
+        writer,
+    )
+}
+
+/// Encrypts a stream like `encrypt_stream`, in a block mode and padding scheme.
+///
+/// ECB and CBC pad the final block with `padding`; CTR writes as many bytes as it reads.
+/// XOR ignores both, and ChaCha20 the padding, its nonce coming from the IV of the mode.
+///
+/// # Returns
+///
+/// A `Result` containing the number of bytes written, or an `InvalidInput` error for an
+/// unsupported algorithm, an empty XOR key or ChaCha20 without an IV.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::utils::{decrypt_with_mode, encrypt_stream_mode, CipherMode, Padding};
+///
+/// // ChaCha20 keeps the length, and another IV gives another keystream.
+/// let mode = CipherMode::new("ctr").unwrap();
+/// let mut chacha = Vec::new();
+/// encrypt_stream_mode("chacha", "key", &mode, Padding::Pkcs7, &b"hello"[..], &mut chacha).unwrap();
+/// assert_eq!(chacha.len(), 5);
+/// assert_eq!(decrypt_with_mode("chacha", "key", &mode, Padding::Pkcs7, &chacha).unwrap(), b"hello");
+/// let other = CipherMode::new("ctr").unwrap();
+/// assert_ne!(decrypt_with_mode("chacha", "key", &other, Padding::Pkcs7, &chacha).unwrap(), b"hello");
+///
+/// // ECB has no IV to take a nonce from.
+/// let mut sink = Vec::new();
+/// assert!(encrypt_stream_mode("chacha", "key", &CipherMode::Ecb, Padding::Pkcs7, &b"hello"[..], &mut sink).is_err());
+///
+/// // XOR has nothing to cycle through with an empty key.
+/// let empty = encrypt_stream_mode("xor", "", &mode, Padding::Zeros, &b"hello"[..], &mut sink);
+/// assert_eq!(empty.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+/// ```
+pub fn encrypt_stream_mode<R: Read, W: Write>(
+    algorithm: &str,
+    key: &str,
+    mode: &CipherMode,
+    padding: Padding,
+    reader: R,
+    writer: W,
+) -> io::Result<u64> {
+    // The payload goes through 16 bytes at a time, too few for a read or write of their own.
+    let mut reader = BufReader::with_capacity(STREAM_BUFFER_LEN, reader);
+    let mut writer = BufWriter::with_capacity(STREAM_BUFFER_LEN, writer);
+    // The previous ciphertext block for CBC, the counter for CTR.
+    let mut chain = mode.iv().copied().unwrap_or_default();
+    if algorithm.eq_ignore_ascii_case("chacha") {
+        // A stream cipher, which neither pads nor needs whole blocks.
+        let mut cipher = chacha_cipher(key, mode)?;
+        let mut buffer = vec![0u8; STREAM_BUFFER_LEN];
+        let mut written = 0u64;
+        loop {
+            let len = reader.read(&mut buffer)?;
+            if len == 0 {
+                break;
+            }
+            cipher.apply_keystream(&mut buffer[..len]);
+            writer.write_all(&buffer[..len])?;
+            written += len as u64;
+        }
+        writer.flush()?;
+        return Ok(written);
+    }
+    let cipher = AesCipher::new(algorithm, key);
+    if cipher.is_none() && !algorithm.eq_ignore_ascii_case("xor") {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "Unsupported algorithm!",
+        ));
+    }
+    if cipher.is_none() && key.is_empty() {
+        return Err(io::Error::new(ErrorKind::InvalidInput, EMPTY_XOR_KEY));
+    }
+    let mut block = [0u8; 16];
+    let mut written = 0u64;
+    loop {
+        // Fill a whole block, since `read` may return less than requested.
+        let mut filled = 0;
+        while filled < block.len() {
+            match reader.read(&mut block[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        let ctr = matches!(mode, CipherMode::Ctr(_));
+        // PKCS#7 closes a payload filling its last block with a block of padding.
+        if filled == 0 && (cipher.is_none() || ctr || padding == Padding::Zeros) {
+            break;
+        }
+        let (output, len) = if let Some(cipher) = &cipher {
+            let mut padded = padding.pad(&block[..filled]);
+            match mode {
+                CipherMode::Ecb => cipher.encrypt(&mut padded),
+                CipherMode::Cbc(_) => {
+                    xor_block(&mut padded, &chain);
+                    cipher.encrypt(&mut padded);
+                    chain = padded;
+                }
+                CipherMode::Ctr(_) => {
+                    let mut keystream = chain;
+                    cipher.encrypt(&mut keystream);
+                    xor_block(&mut padded, &keystream);
+                    chain = next_counter(&chain);
+                }
+            }
+            (padded, if ctr { filled } else { padded.len() })
+        } else {
+            let mut masked = block;
+            let key = key
+                .as_bytes()
+                .iter()
+                .cycle()
+                .skip(written as usize % key.len());
+            for (byte, key) in masked[..filled].iter_mut().zip(key) {
+                *byte ^= key;
+            }
+            (masked, filled)
+        };
+        writer.write_all(&output[..len])?;
+        written += len as u64;
+        if filled < block.len() {
+            break;
+        }
+    }
+    writer.flush()?;
+    Ok(written)
+}
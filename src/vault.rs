@@ -0,0 +1,284 @@
+use crate::header::{open_payload, payload_tag, unix_now, PayloadHeader};
+use crate::png::{insert_chunk, read_chunks, remove_chunks};
+use crate::utils::{encrypt_stream_mode, DEFAULT_BLOCK_MODE};
+use sha2::{Digest, Sha256};
+use std::io::{self, ErrorKind};
+
+/// The PNG chunk holding the encrypted index of a vault.
+pub const VAULT_INDEX_CHUNK: &[u8; 4] = b"stVx";
+
+/// The PNG chunk type holding the encrypted contents of each file of a vault.
+pub const VAULT_FILE_CHUNK: &[u8; 4] = b"stVf";
+
+/// Magic bytes opening the decrypted index.
+const INDEX_MAGIC: &[u8; 4] = b"STvx";
+
+/// Number of bytes of an index entry before its name: id, size, time added and digest.
+const ENTRY_HEADER_LEN: usize = 4 + 8 + 8 + 32 + 2;
+
+/// A file stored in a vault, as recorded in its index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultEntry {
+    /// The name the file was stored under.
+    pub name: String,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The time the file was stored, in seconds since the Unix epoch.
+    pub added: u64,
+    /// The id of the `stVf` chunk holding the file.
+    pub id: u32,
+    /// The SHA-256 of the file, checked when it is read back.
+    pub digest: [u8; 32],
+}
+
+/// Builds the error returned for malformed vaults.
+fn malformed(reason: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("Malformed vault index: {}", reason),
+    )
+}
+
+/// Encrypts data behind a signed `PayloadHeader`, as `encode_bytes` does for payloads.
+fn seal(data: &[u8], algorithm: &str, key: &str) -> io::Result<Vec<u8>> {
+    let header = PayloadHeader::new(algorithm, data.len() as u64, unix_now())
+        .with_mode(DEFAULT_BLOCK_MODE)?;
+    let mut sealed = header.to_bytes(key)?;
+    encrypt_stream_mode(
+        algorithm,
+        key,
+        &header.mode,
+        header.padding,
+        data,
+        &mut sealed,
+    )?;
+    let tag = payload_tag(key, &sealed[..])?;
+    sealed.extend_from_slice(&tag);
+    Ok(sealed)
+}
+
+/// Serializes the index: `STvx` and the number of entries as a 32-bit big-endian integer,
+/// then for each entry its id, size, time added, digest and the length of its name, followed
+/// by the name in UTF-8.
+fn index_to_bytes(entries: &[VaultEntry]) -> io::Result<Vec<u8>> {
+    let mut bytes = INDEX_MAGIC.to_vec();
+    bytes.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+    for entry in entries {
+        let name_len = u16::try_from(entry.name.len()).map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                "File names are at most 65535 bytes",
+            )
+        })?;
+        bytes.extend_from_slice(&entry.id.to_be_bytes());
+        bytes.extend_from_slice(&entry.size.to_be_bytes());
+        bytes.extend_from_slice(&entry.added.to_be_bytes());
+        bytes.extend_from_slice(&entry.digest);
+        bytes.extend_from_slice(&name_len.to_be_bytes());
+        bytes.extend_from_slice(entry.name.as_bytes());
+    }
+    Ok(bytes)
+}
+
+/// Parses an index serialized with `index_to_bytes`.
+fn parse_index(bytes: &[u8]) -> io::Result<Vec<VaultEntry>> {
+    if bytes.len() < 8 || &bytes[..4] != INDEX_MAGIC {
+        return Err(malformed("bad magic"));
+    }
+    let count = u32::from_be_bytes(bytes[4..8].try_into().unwrap());
+    let mut entries = Vec::new();
+    let mut pos = 8;
+    for _ in 0..count {
+        let header = bytes
+            .get(pos..pos + ENTRY_HEADER_LEN)
+            .ok_or_else(|| malformed("truncated entry"))?;
+        let name_len = u16::from_be_bytes(header[52..54].try_into().unwrap()) as usize;
+        let name = bytes
+            .get(pos + ENTRY_HEADER_LEN..pos + ENTRY_HEADER_LEN + name_len)
+            .ok_or_else(|| malformed("truncated name"))?;
+        entries.push(VaultEntry {
+            name: String::from_utf8(name.to_vec()).map_err(|_| malformed("name isn't UTF-8"))?,
+            id: u32::from_be_bytes(header[..4].try_into().unwrap()),
+            size: u64::from_be_bytes(header[4..12].try_into().unwrap()),
+            added: u64::from_be_bytes(header[12..20].try_into().unwrap()),
+            digest: header[20..52].try_into().unwrap(),
+        });
+        pos += ENTRY_HEADER_LEN + name_len;
+    }
+    Ok(entries)
+}
+
+/// Lists the files stored in the vault of a PNG file.
+///
+/// # Arguments
+///
+/// * `png` - The bytes of the carrier.
+/// * `key` - The key of the vault.
+///
+/// # Returns
+///
+/// A `Result` containing the entries in the order they were stored, none if the carrier has
+/// no vault yet, or a `WrongKey` error if the key doesn't open the index.
+pub fn vault_list(png: &[u8], key: &str) -> io::Result<Vec<VaultEntry>> {
+    let Some(index) = read_chunks(png)?
+        .into_iter()
+        .find(|chunk| &chunk.r#type == VAULT_INDEX_CHUNK)
+    else {
+        return Ok(Vec::new());
+    };
+    // The algorithm is read from the header, the one given here is only a fallback.
+    let (_, plaintext) = open_payload(&index.data, "aes", key)?;
+    parse_index(&plaintext)
+}
+
+/// Replaces the index chunk of a vault, dropping the file chunks it no longer lists.
+fn write_index(
+    png: &[u8],
+    entries: &[VaultEntry],
+    algorithm: &str,
+    key: &str,
+) -> io::Result<Vec<u8>> {
+    let (clean, _) = remove_chunks(png, |_, chunk| {
+        &chunk.r#type == VAULT_INDEX_CHUNK
+            || (&chunk.r#type == VAULT_FILE_CHUNK
+                && !entries
+                    .iter()
+                    .any(|entry| chunk.data.get(..4) == Some(&entry.id.to_be_bytes()[..])))
+    })?;
+    let sealed = seal(&index_to_bytes(entries)?, algorithm, key)?;
+    Ok(insert_chunk(&clean, VAULT_INDEX_CHUNK, &sealed, None)?.0)
+}
+
+/// Stores a file in the vault of a PNG file, creating the vault if there is none.
+///
+/// The file goes into a chunk of its own, encrypted, and the index listing the names is
+/// rewritten, encrypted too. A file stored under the same name is replaced.
+///
+/// # Arguments
+///
+/// * `png` - The bytes of the carrier.
+/// * `name` - The name to store the file under.
+/// * `contents` - The contents of the file.
+/// * `algorithm` - The encryption algorithm: aes or xor.
+/// * `key` - The key of the vault; every file of a vault shares it.
+///
+/// # Returns
+///
+/// A `Result` containing the new carrier and the entry that was replaced, if any.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::encode_grayscale;
+/// use stegano::vault::{vault_get, vault_list, vault_put, vault_remove};
+///
+/// let png = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+/// let (png, _) = vault_put(&png, "notes.txt", b"buy milk", "aes", "key").unwrap();
+/// let (png, _) = vault_put(&png, "todo.txt", b"call back", "aes", "key").unwrap();
+/// let names: Vec<String> = vault_list(&png, "key").unwrap().into_iter().map(|e| e.name).collect();
+/// assert_eq!(names, ["notes.txt", "todo.txt"]);
+/// assert_eq!(vault_get(&png, "notes.txt", "key").unwrap(), b"buy milk");
+/// assert!(vault_list(&png, "wrong key").is_err());
+///
+/// let (png, removed) = vault_remove(&png, "notes.txt", "key").unwrap();
+/// assert_eq!(removed.size, 8);
+/// assert!(vault_get(&png, "notes.txt", "key").is_err());
+/// ```
+pub fn vault_put(
+    png: &[u8],
+    name: &str,
+    contents: &[u8],
+    algorithm: &str,
+    key: &str,
+) -> io::Result<(Vec<u8>, Option<VaultEntry>)> {
+    if name.is_empty() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "Vault file names can't be empty",
+        ));
+    }
+    let mut entries = vault_list(png, key)?;
+    let replaced = entries
+        .iter()
+        .position(|entry| entry.name == name)
+        .map(|position| entries.remove(position));
+    let id = read_chunks(png)?
+        .iter()
+        .filter(|chunk| &chunk.r#type == VAULT_FILE_CHUNK)
+        .filter_map(|chunk| Some(u32::from_be_bytes(chunk.data.get(..4)?.try_into().ok()?)))
+        .max()
+        .map_or(0, |id| id.wrapping_add(1));
+    let mut data = id.to_be_bytes().to_vec();
+    data.extend_from_slice(&seal(contents, algorithm, key)?);
+    let (png, _) = insert_chunk(png, VAULT_FILE_CHUNK, &data, None)?;
+    entries.push(VaultEntry {
+        name: name.to_string(),
+        size: contents.len() as u64,
+        added: unix_now(),
+        id,
+        digest: Sha256::digest(contents).into(),
+    });
+    Ok((write_index(&png, &entries, algorithm, key)?, replaced))
+}
+
+/// Reads a file back from the vault of a PNG file.
+///
+/// # Returns
+///
+/// A `Result` containing the contents of the file, a `NotFound` error if the vault has no
+/// file of that name, or an `InvalidData` error if its chunk is missing or damaged.
+pub fn vault_get(png: &[u8], name: &str, key: &str) -> io::Result<Vec<u8>> {
+    let entry = find_entry(&vault_list(png, key)?, name)?;
+    let chunk = read_chunks(png)?
+        .into_iter()
+        .find(|chunk| {
+            &chunk.r#type == VAULT_FILE_CHUNK
+                && chunk.data.get(..4) == Some(&entry.id.to_be_bytes()[..])
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("The chunk holding {:?} is missing from the vault", name),
+            )
+        })?;
+    let (_, contents) = open_payload(&chunk.data[4..], "aes", key)?;
+    if Sha256::digest(&contents)[..] != entry.digest {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("{:?} doesn't match the digest in the vault index", name),
+        ));
+    }
+    Ok(contents)
+}
+
+/// Removes a file from the vault of a PNG file, along with its chunk.
+///
+/// # Returns
+///
+/// A `Result` containing the new carrier and the removed entry, or a `NotFound` error if
+/// the vault has no file of that name.
+pub fn vault_remove(png: &[u8], name: &str, key: &str) -> io::Result<(Vec<u8>, VaultEntry)> {
+    let mut entries = vault_list(png, key)?;
+    let removed = find_entry(&entries, name)?;
+    entries.retain(|entry| entry.name != name);
+    let algorithm = read_chunks(png)?
+        .into_iter()
+        .find(|chunk| &chunk.r#type == VAULT_INDEX_CHUNK)
+        .and_then(|chunk| Some(PayloadHeader::parse(&chunk.data).ok()?.0.algorithm))
+        .unwrap_or_else(|| String::from("aes"));
+    Ok((write_index(png, &entries, &algorithm, key)?, removed))
+}
+
+/// Looks a file up in the index by name.
+fn find_entry(entries: &[VaultEntry], name: &str) -> io::Result<VaultEntry> {
+    entries
+        .iter()
+        .find(|entry| entry.name == name)
+        .cloned()
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("No file named {:?} in the vault", name),
+            )
+        })
+}
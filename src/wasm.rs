@@ -0,0 +1,65 @@
+use crate::codec::{self, CodecOptions};
+use wasm_bindgen::prelude::*;
+
+/// Options passed from JavaScript, see `CodecOptions`.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    inner: CodecOptions,
+}
+
+#[wasm_bindgen]
+impl Options {
+    /// Creates options with the CLI defaults: png, auto, aes, key "key", chunk "stEg".
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Options {
+        Options::default()
+    }
+
+    #[wasm_bindgen(setter, js_name = fileType)]
+    pub fn set_file_type(&mut self, value: String) {
+        self.inner.file_type = value;
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_method(&mut self, value: String) {
+        self.inner.method = value;
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_algorithm(&mut self, value: String) {
+        self.inner.algorithm = value;
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_key(&mut self, value: String) {
+        self.inner.key = value;
+    }
+
+    #[wasm_bindgen(setter, js_name = chunkName)]
+    pub fn set_chunk_name(&mut self, value: String) {
+        self.inner.chunk_name = value;
+    }
+}
+
+/// Encrypts `payload` and embeds it into `carrier`, returning the stego file as a `Uint8Array`.
+#[wasm_bindgen(js_name = encodeBytes)]
+pub fn encode_bytes(carrier: &[u8], payload: &[u8], opts: &Options) -> Result<Vec<u8>, JsError> {
+    codec::encode_bytes(carrier, payload, &opts.inner).map_err(|err| JsError::new(&err.to_string()))
+}
+
+/// Extracts and decrypts the payload of a stego file, returning it as a `Uint8Array`.
+#[wasm_bindgen(js_name = decodeBytes)]
+pub fn decode_bytes(stego: &[u8], opts: &Options) -> Result<Vec<u8>, JsError> {
+    codec::decode_bytes(stego, &opts.inner)
+        .map(|decoded| decoded.payload)
+        .map_err(|err| JsError::new(&err.to_string()))
+}
+
+/// Removes the payload of a stego file, returning the restored carrier as a `Uint8Array`.
+#[wasm_bindgen(js_name = restoreCarrier)]
+pub fn restore_carrier(stego: &[u8], opts: &Options) -> Result<Vec<u8>, JsError> {
+    codec::decode_bytes(stego, &opts.inner)
+        .map(|decoded| decoded.carrier)
+        .map_err(|err| JsError::new(&err.to_string()))
+}
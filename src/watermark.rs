@@ -0,0 +1,332 @@
+use crate::png::{read_chunks, PngImage};
+use crate::prng::Prng;
+use crc32_v2::crc32;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+/// Maximum length of an owner ID, in bytes.
+pub const MAX_OWNER_LEN: usize = 16;
+
+/// Number of bits of the mark: the owner ID, zero-padded, and its CRC-32.
+const MARK_BITS: usize = (MAX_OWNER_LEN + 4) * 8;
+
+/// Side of the square pixel cells the pattern is made of.
+const CELL: usize = 2;
+
+/// Detection score from which a watermark counts as present.
+///
+/// Without a watermark the score follows a standard normal distribution, so an unmarked image
+/// reaches it about once in three million tries.
+pub const WATERMARK_THRESHOLD: f64 = 5.0;
+
+/// The default amplitude of the pattern, in 8-bit luma levels.
+pub const DEFAULT_STRENGTH: f64 = 2.0;
+
+/// The cells of an image, with the bit and pseudo-random sign the key gives each of them.
+struct Pattern {
+    /// Cells per row and per column.
+    cols: usize,
+    rows: usize,
+    /// The bit of the mark each cell carries.
+    bits: Vec<u16>,
+    /// The sign each cell spreads its bit with.
+    chips: Vec<f64>,
+}
+
+impl Pattern {
+    /// Derives the pattern of an image from the key.
+    fn new(image: &PngImage, key: &str) -> Self {
+        let (cols, rows) = (image.width / CELL, image.height / CELL);
+        let digest = Sha256::digest([b"watermark:", key.as_bytes()].concat());
+        let mut rng = Prng::new(u64::from_le_bytes(digest[..8].try_into().unwrap()));
+        // Every bit gets the same number of cells, scattered over the image.
+        let mut bits: Vec<u16> = (0..cols * rows).map(|i| (i % MARK_BITS) as u16).collect();
+        for i in (1..bits.len()).rev() {
+            bits.swap(i, rng.below(i as u64 + 1) as usize);
+        }
+        let chips = (0..bits.len())
+            .map(|_| if rng.next_u64() & 1 == 1 { 1.0 } else { -1.0 })
+            .collect();
+        Pattern {
+            cols,
+            rows,
+            bits,
+            chips,
+        }
+    }
+}
+
+/// Returns the signs of the pattern proving an owner ID, one per cell.
+///
+/// It is drawn from the key and the ID together, so the patterns of two owners are unrelated
+/// however alike their IDs are.
+fn owner_chips(key: &str, owner: &str, cells: usize) -> Vec<f64> {
+    let digest = Sha256::digest([b"owner:", key.as_bytes(), b":", owner.as_bytes()].concat());
+    let mut rng = Prng::new(u64::from_le_bytes(digest[..8].try_into().unwrap()));
+    (0..cells)
+        .map(|_| if rng.next_u64() & 1 == 1 { 1.0 } else { -1.0 })
+        .collect()
+}
+
+/// Returns the luma of every cell, in 8-bit units.
+fn cell_luma(image: &PngImage, pattern: &Pattern) -> Vec<f64> {
+    let channels = image.channels();
+    let weights: &[f64] = match image.color_type {
+        0 | 4 => &[1.0],
+        _ => &[0.299, 0.587, 0.114],
+    };
+    let scale = if image.bit_depth == 16 { 257.0 } else { 1.0 };
+    let mut cells = vec![0.0; pattern.cols * pattern.rows];
+    for (i, cell) in cells.iter_mut().enumerate() {
+        let (cx, cy) = (i % pattern.cols * CELL, i / pattern.cols * CELL);
+        for y in cy..cy + CELL {
+            for x in cx..cx + CELL {
+                let pixel = (y * image.width + x) * channels;
+                *cell += weights
+                    .iter()
+                    .enumerate()
+                    .map(|(c, weight)| image.samples[pixel + c] as f64 * weight)
+                    .sum::<f64>();
+            }
+        }
+        *cell /= (CELL * CELL) as f64 * scale;
+    }
+    cells
+}
+
+/// Decodes a carrier, checking that it can hold a watermark.
+fn decode_carrier(data: &[u8]) -> io::Result<PngImage> {
+    let chunks = read_chunks(data)?;
+    let ihdr = chunks
+        .first()
+        .filter(|chunk| &chunk.r#type == b"IHDR" && chunk.data.len() == 13)
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Missing IHDR chunk"))?;
+    if ihdr.data[9] == 3 || ihdr.data[8] < 8 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "Watermarks need an 8 or 16-bit greyscale or truecolour PNG",
+        ));
+    }
+    let image = PngImage::decode(data)?;
+    if (image.width / CELL) * (image.height / CELL) < MARK_BITS * 16 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The image is too small to hold a watermark, it needs at least 128x128 pixels",
+        ));
+    }
+    Ok(image)
+}
+
+/// Returns the bits of the mark of an owner ID.
+fn mark_bits(owner: &str) -> io::Result<Vec<f64>> {
+    if owner.is_empty() || owner.len() > MAX_OWNER_LEN {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Owner IDs are 1 to {} bytes long", MAX_OWNER_LEN),
+        ));
+    }
+    let mut mark = [0u8; MAX_OWNER_LEN + 4];
+    mark[..owner.len()].copy_from_slice(owner.as_bytes());
+    let crc = crc32(0, &mark[..MAX_OWNER_LEN]);
+    mark[MAX_OWNER_LEN..].copy_from_slice(&crc.to_be_bytes());
+    Ok(mark
+        .iter()
+        .flat_map(|byte| {
+            (0..8)
+                .rev()
+                .map(move |bit| (byte >> bit & 1) as f64 * 2.0 - 1.0)
+        })
+        .collect())
+}
+
+/// Embeds an owner ID as a watermark spread over the whole image.
+///
+/// Two patterns of 2x2 pixel cells are added, each raising or lowering every cell by half of
+/// `strength` luma levels. The first spreads every bit of the ID and of its CRC-32 over
+/// hundreds of cells scattered by the key, so the ID can be read back. The second is drawn
+/// from the key and the ID, and proves that owner: its correlation with the image is the
+/// score. The change is far below what the eye sees, and it needs no original to detect: see
+/// `detect_watermark`. The mark survives noise and JPEG re-compression down to about
+/// quality 50 once converted back to PNG, but not resizing or cropping. Unlike a payload it isn't
+/// encrypted: anyone with the key can read the owner ID.
+///
+/// # Arguments
+///
+/// * `carrier` - The bytes of an 8 or 16-bit, non-palette PNG of at least 128x128 pixels.
+/// * `owner` - The owner ID, up to `MAX_OWNER_LEN` bytes.
+/// * `key` - The key the pattern is derived from.
+/// * `strength` - The amplitude of the pattern, in 8-bit luma levels.
+///
+/// # Returns
+///
+/// A `Result` containing the bytes of the watermarked image.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::png::encode_grayscale;
+/// use stegano::watermark::{detect_watermark, embed_watermark, DEFAULT_STRENGTH};
+///
+/// let pixels: Vec<u8> = (0..256 * 256).map(|i| (64 + (i % 256 + i / 256) / 4) as u8).collect();
+/// let image = encode_grayscale(256, 256, &pixels).unwrap();
+/// let marked = embed_watermark(&image, "alice", "key", DEFAULT_STRENGTH).unwrap();
+///
+/// let detection = detect_watermark(&marked, "key", None).unwrap();
+/// assert_eq!(detection.owner.as_deref(), Some("alice"));
+/// assert!(detection.is_present());
+/// assert!(!detect_watermark(&marked, "key", Some("mallory")).unwrap().is_present());
+/// assert!(!detect_watermark(&image, "key", Some("alice")).unwrap().is_present());
+/// ```
+pub fn embed_watermark(
+    carrier: &[u8],
+    owner: &str,
+    key: &str,
+    strength: f64,
+) -> io::Result<Vec<u8>> {
+    let bits = mark_bits(owner)?;
+    let mut image = decode_carrier(carrier)?;
+    let pattern = Pattern::new(&image, key);
+    let proof = owner_chips(key, owner, pattern.bits.len());
+    let channels = image.channels();
+    let colour = if matches!(image.color_type, 4 | 6) {
+        channels - 1
+    } else {
+        channels
+    };
+    let (scale, max) = if image.bit_depth == 16 {
+        (257.0, 65535.0)
+    } else {
+        (1.0, 255.0)
+    };
+    for (i, (&bit, &chip)) in pattern.bits.iter().zip(&pattern.chips).enumerate() {
+        let delta = strength / 2.0 * (chip * bits[bit as usize] + proof[i]) * scale;
+        let (cx, cy) = (i % pattern.cols * CELL, i / pattern.cols * CELL);
+        for y in cy..cy + CELL {
+            for x in cx..cx + CELL {
+                let pixel = (y * image.width + x) * channels;
+                for sample in &mut image.samples[pixel..pixel + colour] {
+                    *sample = (*sample as f64 + delta).round().clamp(0.0, max) as u16;
+                }
+            }
+        }
+    }
+    image.replace_pixels(carrier)
+}
+
+/// The result of looking for a watermark.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// The owner ID read from the image, if its CRC checks out.
+    pub owner: Option<String>,
+    /// The detection score of the expected or read owner ID: how many standard deviations the
+    /// correlation with its proof pattern lies above chance. 0 when there is nothing to score.
+    pub score: f64,
+}
+
+impl Detection {
+    /// Tells whether the score reaches `WATERMARK_THRESHOLD`.
+    pub fn is_present(&self) -> bool {
+        self.score >= WATERMARK_THRESHOLD
+    }
+
+    /// Returns an upper bound of the probability that an unmarked image scores this high.
+    pub fn false_positive(&self) -> f64 {
+        if self.score <= 1.0 {
+            return 1.0;
+        }
+        // The Mills ratio bound of the normal tail, kept above zero where it underflows.
+        let bound = (-self.score * self.score / 2.0).exp()
+            / (self.score * (2.0 * std::f64::consts::PI).sqrt());
+        bound.max(f64::MIN_POSITIVE)
+    }
+}
+
+impl fmt::Display for Detection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "score {:.1}, false positive probability below {:.1e}",
+            self.score,
+            self.false_positive()
+        )
+    }
+}
+
+/// Looks for a watermark embedded by `embed_watermark`, without the original image.
+///
+/// The image is high-pass filtered, each cell minus the mean of its neighbours, to take the
+/// picture itself out, then correlated with the pattern of every bit, whose signs give the
+/// owner ID back. The score is the correlation with the proof pattern of the expected owner
+/// ID, or of the one read, normalized by the energy of the filtered image. It follows a
+/// standard normal distribution on images without that owner's mark, other owners' marks
+/// included, and grows with the square root of the image size on marked ones.
+///
+/// # Arguments
+///
+/// * `data` - The bytes of the image.
+/// * `key` - The key the watermark was embedded with.
+/// * `owner` - The owner ID to check for, or `None` to only read it.
+///
+/// # Returns
+///
+/// A `Result` containing the owner ID read, if any, and the score.
+pub fn detect_watermark(data: &[u8], key: &str, owner: Option<&str>) -> io::Result<Detection> {
+    let image = decode_carrier(data)?;
+    let pattern = Pattern::new(&image, key);
+    let cells = cell_luma(&image, &pattern);
+    let (cols, rows) = (pattern.cols as isize, pattern.rows as isize);
+    let mut correlations = vec![0.0; MARK_BITS];
+    let mut residuals = Vec::with_capacity(cells.len());
+    for (i, &cell) in cells.iter().enumerate() {
+        let (x, y) = ((i % pattern.cols) as isize, (i / pattern.cols) as isize);
+        let neighbours: Vec<f64> = [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ]
+        .iter()
+        .map(|(dx, dy)| (x + dx, y + dy))
+        .filter(|&(nx, ny)| (0..cols).contains(&nx) && (0..rows).contains(&ny))
+        .map(|(nx, ny)| cells[(ny * cols + nx) as usize])
+        .collect();
+        let residual = cell - neighbours.iter().sum::<f64>() / neighbours.len() as f64;
+        correlations[pattern.bits[i] as usize] += residual * pattern.chips[i];
+        residuals.push(residual);
+    }
+    let energy: f64 = residuals.iter().map(|r| r * r).sum();
+    let read: Vec<u8> = correlations
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0u8, |acc, &c| acc << 1 | (c > 0.0) as u8))
+        .collect();
+    let (id, crc) = read.split_at(MAX_OWNER_LEN);
+    let read_owner = (crc32(0, id).to_be_bytes() == crc)
+        .then(|| {
+            let len = id.iter().position(|&b| b == 0).unwrap_or(MAX_OWNER_LEN);
+            String::from_utf8(id[..len].to_vec()).ok()
+        })
+        .flatten()
+        .filter(|owner| !owner.is_empty());
+    let score = match owner.or(read_owner.as_deref()) {
+        Some(owner) if energy > 0.0 => {
+            mark_bits(owner)?;
+            let proof = owner_chips(key, owner, residuals.len());
+            residuals
+                .iter()
+                .zip(&proof)
+                .map(|(r, c)| r * c)
+                .sum::<f64>()
+                / energy.sqrt()
+        }
+        _ => 0.0,
+    };
+    Ok(Detection {
+        owner: read_owner,
+        score,
+    })
+}
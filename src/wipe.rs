@@ -0,0 +1,134 @@
+use crate::storage::is_remote;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// The number of bytes of random data written at a time.
+const WIPE_BLOCK: usize = 64 * 1024;
+
+/// Opens a local file for overwriting in place, refusing remote objects and special files.
+fn open_for_wipe(path: &Path) -> io::Result<File> {
+    if path.to_str().is_some_and(is_remote) || !fs::metadata(path)?.is_file() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} isn't a local file that can be wiped", path.display()),
+        ));
+    }
+    OpenOptions::new().write(true).open(path)
+}
+
+/// Overwrites a byte range of a file with random data, in place, syncing it to disk after each
+/// pass.
+///
+/// Each pass draws fresh random bytes, so no pattern of an earlier pass is left to tell the
+/// passes apart. On copy-on-write and journaling file systems, and on SSDs that remap their
+/// blocks, the old bytes may survive elsewhere on the disk: wiping makes them unreadable
+/// through the file, not through the device.
+///
+/// # Arguments
+///
+/// * `path` - The file, which must be a local regular file.
+/// * `range` - The bytes to overwrite, cut to the length of the file.
+/// * `passes` - The number of times to overwrite them.
+///
+/// # Returns
+///
+/// A `Result` that is an error if the file can't be written.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::wipe::overwrite;
+///
+/// let path = std::env::temp_dir().join("stegano-wipe-doc.bin");
+/// std::fs::write(&path, b"keep SECRET keep").unwrap();
+/// overwrite(&path, 5..11, 3).unwrap();
+/// let data = std::fs::read(&path).unwrap();
+/// assert_eq!((&data[..5], &data[11..]), (&b"keep "[..], &b" keep"[..]));
+/// assert_ne!(&data[5..11], b"SECRET");
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn overwrite(path: &Path, range: Range<u64>, passes: u32) -> io::Result<()> {
+    let mut file = open_for_wipe(path)?;
+    let end = range.end.min(file.metadata()?.len());
+    let mut block = vec![0u8; WIPE_BLOCK];
+    for _ in 0..passes {
+        file.seek(SeekFrom::Start(range.start))?;
+        let mut pos = range.start;
+        while pos < end {
+            let len = (end - pos).min(WIPE_BLOCK as u64) as usize;
+            getrandom::getrandom(&mut block[..len])
+                .map_err(|err| io::Error::other(err.to_string()))?;
+            file.write_all(&block[..len])?;
+            pos += len as u64;
+        }
+        file.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Overwrites the bytes of an extracted payload where they are stored in a file, for
+/// `decrypt --wipe`.
+///
+/// The payload is looked for as one run of bytes, as the chunk, appended, tag, box and frame
+/// methods store it. Payloads spread over pixels, coefficients or header fields aren't found.
+///
+/// # Returns
+///
+/// A `Result` containing the range overwritten, or a `NotFound` error if the payload isn't
+/// stored in one piece in the file.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::wipe::wipe_payload;
+///
+/// let path = std::env::temp_dir().join("stegano-wipe-payload-doc.bin");
+/// std::fs::write(&path, b"carrier|payload|carrier").unwrap();
+/// assert_eq!(wipe_payload(&path, b"payload", 1).unwrap(), 8..15);
+/// assert!(!std::fs::read(&path).unwrap().windows(7).any(|w| w == b"payload"));
+/// assert!(wipe_payload(&path, b"payload", 1).is_err());
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn wipe_payload(path: &Path, payload: &[u8], passes: u32) -> io::Result<Range<u64>> {
+    let data = fs::read(path)?;
+    let start = (!payload.is_empty())
+        .then(|| {
+            data.windows(payload.len())
+                .position(|window| window == payload)
+        })
+        .flatten()
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "The payload isn't stored in one piece in {}, so it can't be wiped on its own",
+                    path.display()
+                ),
+            )
+        })?;
+    let range = start as u64..(start + payload.len()) as u64;
+    overwrite(path, range.clone(), passes)?;
+    Ok(range)
+}
+
+/// Overwrites a whole file with random data, then removes it, for `--shred-input`.
+///
+/// The same limits as `overwrite` apply.
+///
+/// # Examples
+///
+/// ```
+/// use stegano::wipe::shred;
+///
+/// let path = std::env::temp_dir().join("stegano-shred-doc.bin");
+/// std::fs::write(&path, b"secret").unwrap();
+/// shred(&path, 2).unwrap();
+/// assert!(!path.exists());
+/// ```
+pub fn shred(path: &Path, passes: u32) -> io::Result<()> {
+    let len = open_for_wipe(path)?.metadata()?.len();
+    overwrite(path, 0..len, passes)?;
+    fs::remove_file(path)
+}
@@ -0,0 +1,150 @@
+//! `encrypt` embeds into every carrier through its `CarrierFormat`, and records where the
+//! payload landed in a descriptor when it is stored in one piece.
+
+mod common;
+
+use common::Scratch;
+use std::process::Output;
+
+/// Runs stegano in `scratch` and checks that it succeeds.
+fn run(scratch: &Scratch, args: &[&str]) -> Output {
+    let output = scratch.run(args);
+    assert!(output.status.success(), "{:?}", output);
+    output
+}
+
+/// Returns a carrier of each type with the methods it is tried with, the same small files
+/// `api::roundtrip_check` is documented with.
+fn carriers() -> Vec<(&'static str, Vec<&'static str>, Vec<u8>)> {
+    let tiff = vec![
+        0x49, 0x49, 0x2A, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x03, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let ico = vec![
+        0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x10, 0x10, 0x00, 0x00, 0x01, 0x00, 0x20, 0x00, 0x04,
+        0x00, 0x00, 0x00, 0x16, 0x00, 0x00, 0x00, 1, 2, 3, 4,
+    ];
+    let mp4 = b"\0\0\0\x10ftypisom\0\0\x02\0\0\0\0\x0Cmdat\x01\x02\x03\x04".to_vec();
+    let mp3 = [0xFF, 0xFB, 0x90, 0x00].to_vec();
+    let mut flac = b"fLaC\0\0\0\x22".to_vec();
+    flac.extend_from_slice(&[0; 34]);
+    flac.extend_from_slice(&[0x81, 0, 1, 0]);
+    flac.extend_from_slice(&[0; 256]);
+    let svg = format!("<svg>\n{}</svg>\n", "<g/>\n".repeat(128)).into_bytes();
+    let text = "The quick brown fox\n".repeat(128).into_bytes();
+    vec![
+        ("tiff", vec!["tag", "strip"], tiff),
+        ("ico", vec!["slack"], ico),
+        ("mp4", vec!["free", "uuid"], mp4),
+        ("mp3", vec!["priv", "geob"], mp3),
+        ("flac", vec!["application", "padding"], flac),
+        ("svg", vec!["metadata", "whitespace"], svg),
+        ("text", vec!["zerowidth", "whitespace"], text),
+    ]
+}
+
+#[test]
+fn encrypt_embeds_in_every_carrier_format() {
+    let scratch = Scratch::new("carrier-formats");
+    for (file_type, methods, carrier) in carriers() {
+        let input = scratch.path(&format!("carrier.{}", file_type));
+        std::fs::write(&input, &carrier).unwrap();
+        for method in methods {
+            let stego = scratch.path(&format!("stego-{}.{}", method, file_type));
+            let descriptor = scratch.path(&format!("{}-{}.json", file_type, method));
+            let mut args = vec![
+                "encrypt", "-i", &input, "-o", &stego, "-t", file_type, "-m", method, "-p",
+                "secret", "-s",
+            ];
+            // SVG and text payloads are spread over the file, with nothing to point at.
+            let located = !matches!(file_type, "svg" | "text");
+            if located {
+                args.extend(["--descriptor", &descriptor]);
+            }
+            run(&scratch, &args);
+            let output = run(
+                &scratch,
+                &[
+                    "extract", "-i", &stego, "-t", file_type, "-m", method, "--format", "raw",
+                ],
+            );
+            assert_eq!(output.stdout, b"secret", "{} {}", file_type, method);
+            if located {
+                let output = run(
+                    &scratch,
+                    &[
+                        "extract",
+                        "-i",
+                        &stego,
+                        "--descriptor",
+                        &descriptor,
+                        "--format",
+                        "raw",
+                    ],
+                );
+                assert_eq!(output.stdout, b"secret", "{} {}", file_type, method);
+            }
+        }
+    }
+}
+
+#[test]
+fn unknown_methods_are_usage_errors() {
+    let scratch = Scratch::new("carrier-format-methods");
+    let (_, _, flac) = carriers().remove(4);
+    let input = scratch.path("carrier.flac");
+    std::fs::write(&input, flac).unwrap();
+    let output = scratch.run(&[
+        "encrypt",
+        "-i",
+        &input,
+        "-o",
+        &scratch.path("stego.flac"),
+        "-t",
+        "flac",
+        "-m",
+        "slack",
+        "-p",
+        "secret",
+    ]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    assert!(!std::path::Path::new(&scratch.path("stego.flac")).exists());
+}
+
+#[test]
+fn png_chunks_go_through_the_format_at_the_given_offset() {
+    let scratch = Scratch::new("carrier-format-png");
+    let input = scratch.path("carrier.png");
+    std::fs::write(
+        &input,
+        stegano::png::encode_grayscale(2, 2, &[0; 4]).unwrap(),
+    )
+    .unwrap();
+    let (stego, descriptor) = (scratch.path("stego.png"), scratch.path("stego.json"));
+    let output = run(
+        &scratch,
+        &[
+            "encrypt",
+            "-i",
+            &input,
+            "-o",
+            &stego,
+            "-f",
+            "after:IHDR",
+            "-p",
+            "secret",
+            "--descriptor",
+            &descriptor,
+            "--json",
+        ],
+    );
+    // The chunk follows the signature and the 25 bytes of IHDR.
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\"offset\":33"));
+    for args in [["-f", "33"], ["--descriptor", &descriptor]] {
+        let output = run(
+            &scratch,
+            &["extract", "-i", &stego, args[0], args[1], "--format", "raw"],
+        );
+        assert_eq!(output.stdout, b"secret");
+    }
+}
@@ -0,0 +1,99 @@
+//! The message catalogs: every built-in translation matches the English one, and `--lang`,
+//! the locale variables and `STEGANO_LOCALE_DIR` select the language of the messages.
+
+mod common;
+
+use common::Scratch;
+use std::collections::BTreeSet;
+use std::process::{Command, Output};
+use stegano::i18n::{languages, load_catalog, Catalog, DEFAULT_LANGUAGE};
+
+/// Returns the `{ $name }` placeables of every message of a catalog.
+fn placeables(catalog: &Catalog) -> BTreeSet<(String, String)> {
+    let mut found = BTreeSet::new();
+    for id in catalog.ids() {
+        let text = catalog.format(id, &[]).unwrap();
+        for part in text.split('{').skip(1) {
+            let name = part.split('}').next().unwrap().trim();
+            found.insert((id.to_string(), name.to_string()));
+        }
+    }
+    found
+}
+
+/// Runs `encrypt --dry-run` on a fresh carrier with the extra `args` and environment.
+fn dry_run(name: &str, args: &[&str], env: &[(&str, &str)]) -> Output {
+    let scratch = Scratch::with_carrier(name);
+    let mut command = Command::new(env!("CARGO_BIN_EXE_stegano"));
+    command
+        .args(["encrypt", "-i", &scratch.path("carrier.png")])
+        .args([
+            "-o",
+            &scratch.path("stego.png"),
+            "--dry-run",
+            "--format",
+            "human",
+        ])
+        .args(args)
+        .env("STEGANO_CONFIG", scratch.0.join("missing.toml"))
+        .env_remove("STEGANO_LOCALE_DIR")
+        .env_remove("LC_ALL")
+        .env_remove("LC_MESSAGES")
+        .env_remove("LANG");
+    for (key, value) in env {
+        command.env(key, value);
+    }
+    command.output().unwrap()
+}
+
+#[test]
+fn translations_have_the_english_messages_and_arguments() {
+    let english = load_catalog(DEFAULT_LANGUAGE).unwrap().unwrap();
+    for language in languages() {
+        let catalog = load_catalog(language).unwrap().unwrap();
+        assert_eq!(catalog.ids(), english.ids(), "{}", language);
+        assert_eq!(placeables(&catalog), placeables(&english), "{}", language);
+    }
+}
+
+#[test]
+fn lang_flag_overrides_the_environment() {
+    let output = dry_run("lang-flag", &["--lang", "fr"], &[("LANG", "en_US.UTF-8")]);
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("Essai à blanc : rien n'a été écrit."),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn environment_selects_the_language_and_falls_back_to_english() {
+    let french = dry_run("lang-env", &[], &[("LC_ALL", "fr_CA.UTF-8")]);
+    assert!(String::from_utf8(french.stdout)
+        .unwrap()
+        .contains("rien n'a été écrit."));
+    let unknown = dry_run("lang-unknown", &[], &[("LANG", "xx_YY")]);
+    assert!(String::from_utf8(unknown.stdout)
+        .unwrap()
+        .contains("Dry run: nothing was written."));
+}
+
+#[test]
+fn locale_dir_adds_a_language() {
+    let locales = Scratch::new("locales");
+    std::fs::write(locales.0.join("de.ftl"), "label-dry-run = Probelauf:\n").unwrap();
+    let output = dry_run(
+        "lang-dir",
+        &["--lang", "de"],
+        &[("STEGANO_LOCALE_DIR", locales.0.to_str().unwrap())],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // Messages the catalog lacks are still printed, in English.
+    assert!(
+        stdout.contains("Probelauf: nothing was written."),
+        "{}",
+        stdout
+    );
+}
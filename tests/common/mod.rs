@@ -0,0 +1,50 @@
+//! The scratch directory the integration tests run stegano in.
+
+// Each test file uses its own share of the helpers.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use stegano::png::encode_grayscale;
+
+/// A scratch directory, removed when dropped.
+pub struct Scratch(pub PathBuf);
+
+impl Scratch {
+    /// Creates an empty directory named after the test and the process.
+    pub fn new(name: &str) -> Scratch {
+        let dir = std::env::temp_dir().join(format!("stegano-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Scratch(dir)
+    }
+
+    /// Creates a directory holding `carrier.png`, a 4×4 grey image.
+    pub fn with_carrier(name: &str) -> Scratch {
+        let scratch = Scratch::new(name);
+        let carrier = encode_grayscale(4, 4, &[128; 16]).unwrap();
+        std::fs::write(scratch.0.join("carrier.png"), carrier).unwrap();
+        scratch
+    }
+
+    /// Returns the path of `name` in the directory.
+    pub fn path(&self, name: &str) -> String {
+        self.0.join(name).to_string_lossy().into_owned()
+    }
+
+    /// Runs stegano with `args`, English messages, no `STEGANO_KEY` and no configuration file.
+    pub fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_stegano"))
+            .args(args)
+            .args(["--lang", "en"])
+            .env_remove("STEGANO_KEY")
+            .env("STEGANO_CONFIG", self.0.join("missing.toml"))
+            .output()
+            .unwrap()
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,166 @@
+//! `extract` reveals the payload and writes nothing, `remove` writes the carrier without it and
+//! reveals nothing, and the deprecated `decrypt` still does both.
+
+mod common;
+
+use common::Scratch;
+
+/// Creates a scratch directory holding a carrier and a stego file hiding "secret".
+fn embedded(name: &str) -> Scratch {
+    let scratch = Scratch::with_carrier(name);
+    let output = scratch.run(&[
+        "encrypt",
+        "-i",
+        &scratch.path("carrier.png"),
+        "-o",
+        &scratch.path("stego.png"),
+        "-p",
+        "secret",
+        "-s",
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    scratch
+}
+
+#[test]
+fn extract_reveals_without_writing() {
+    let scratch = embedded("extract-only");
+    let stego = scratch.path("stego.png");
+    let cleaned = scratch.path("cleaned.png");
+    let output = scratch.run(&["extract", "-i", &stego, "-o", &cleaned, "--format", "raw"]);
+    assert_eq!(
+        (output.status.code(), &output.stdout[..]),
+        (Some(0), &b"secret"[..])
+    );
+    assert!(output.stderr.is_empty());
+    assert!(!std::path::Path::new(&cleaned).exists());
+}
+
+#[test]
+fn remove_restores_without_revealing() {
+    let scratch = embedded("remove-only");
+    let stego = scratch.path("stego.png");
+    let cleaned = scratch.path("cleaned.png");
+    let output = scratch.run(&["restore", "-i", &stego, "-o", &cleaned, "--format", "raw"]);
+    assert_eq!(
+        (output.status.code(), &output.stdout[..]),
+        (Some(0), &b""[..])
+    );
+    assert_eq!(
+        std::fs::read(&cleaned).unwrap(),
+        std::fs::read(scratch.path("carrier.png")).unwrap()
+    );
+    let wrong_key = scratch.run(&["remove", "-i", &stego, "-o", &cleaned, "-k", "wrong"]);
+    assert_eq!(wrong_key.status.code(), Some(6));
+}
+
+#[test]
+fn decrypt_does_both_with_a_warning() {
+    let scratch = embedded("decrypt-both");
+    let stego = scratch.path("stego.png");
+    let cleaned = scratch.path("cleaned.png");
+    let output = scratch.run(&["decrypt", "-i", &stego, "-o", &cleaned, "--format", "raw"]);
+    assert_eq!(
+        (output.status.code(), &output.stdout[..]),
+        (Some(0), &b"secret"[..])
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("deprecated"));
+    assert!(std::path::Path::new(&cleaned).exists());
+}
+
+#[test]
+fn options_of_the_other_half_are_usage_errors() {
+    let scratch = embedded("mode-usage");
+    let stego = scratch.path("stego.png");
+    let extract = scratch.run(&["extract", "-i", &stego, "--exact"]);
+    assert_eq!(extract.status.code(), Some(2));
+    let remove = scratch.run(&["remove", "-i", &stego, "--qr"]);
+    assert_eq!(remove.status.code(), Some(2));
+}
+
+#[test]
+fn output_payload_file_keeps_the_secret_off_stdout() {
+    let scratch = embedded("payload-file");
+    let stego = scratch.path("binary.png");
+    let output = scratch.run(&[
+        "encrypt",
+        "-i",
+        &scratch.path("carrier.png"),
+        "-o",
+        &stego,
+        "-p",
+        "00ff0a68696464656e0d00",
+        "--payload-encoding",
+        "hex",
+        "-s",
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    let secret = scratch.path("secret.bin");
+    let output = scratch.run(&[
+        "extract",
+        "-i",
+        &stego,
+        "--output-payload-file",
+        &secret,
+        "--format",
+        "human",
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("hidden"));
+    assert_eq!(std::fs::read(&secret).unwrap(), b"\x00\xff\nhidden\r\x00");
+}
+
+#[test]
+fn precision_payloads_refuse_a_wrong_key() {
+    let scratch = embedded("precision-key");
+    let stego = scratch.path("precision.png");
+    let args = [
+        "-i",
+        &stego,
+        "-m",
+        "precision",
+        "-a",
+        "xor",
+        "--format",
+        "raw",
+    ];
+    let output = scratch.run(&[
+        "encrypt",
+        "-i",
+        &scratch.path("carrier.png"),
+        "-o",
+        &stego,
+        "-m",
+        "precision",
+        "-a",
+        "xor",
+        "-p",
+        "wm42",
+        "-s",
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    let output = scratch.run(&[&["extract"][..], &args].concat());
+    assert_eq!(
+        (output.status.code(), &output.stdout[..]),
+        (Some(0), &b"wm42"[..])
+    );
+    let wrong_key = scratch.run(&[&["extract", "-k", "wrong"][..], &args].concat());
+    assert_eq!(
+        (wrong_key.status.code(), &wrong_key.stdout[..]),
+        (Some(6), &b""[..])
+    );
+    let too_long = scratch.run(&[
+        "encrypt",
+        "-i",
+        &scratch.path("carrier.png"),
+        "-o",
+        &stego,
+        "-m",
+        "precision",
+        "-a",
+        "xor",
+        "-p",
+        "wm420",
+    ]);
+    assert!(!too_long.status.success());
+}
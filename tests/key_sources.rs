@@ -0,0 +1,139 @@
+//! The sources `encrypt` and `decrypt` take the key from, and their precedence: `-k` or
+//! `--key-fd`, then `--key-id`, then `STEGANO_KEY`, then the default key.
+
+mod common;
+
+use common::Scratch;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+/// Runs stegano with `args`, `STEGANO_KEY` set to `env_key` if given, and `stdin` piped in.
+fn stegano(args: &[&str], env_key: Option<&str>, stdin: &[u8], dir: &Path) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_stegano"));
+    command
+        .args(args)
+        .env_remove("STEGANO_KEY")
+        .env("STEGANO_CONFIG", dir.join("missing.toml"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(key) = env_key {
+        command.env("STEGANO_KEY", key);
+    }
+    let mut child = command.spawn().unwrap();
+    // Commands failing before they read stdin close the pipe early.
+    let _ = child.stdin.take().unwrap().write_all(stdin);
+    child.wait_with_output().unwrap()
+}
+
+/// Hides "secret" in the carrier with the extra `args` and environment key.
+fn encrypt(scratch: &Scratch, args: &[&str], env_key: Option<&str>, stdin: &[u8]) -> String {
+    let stego = scratch.path("stego.png");
+    let carrier = scratch.path("carrier.png");
+    let mut all = vec![
+        "encrypt", "-i", &carrier, "-o", &stego, "-p", "secret", "-s",
+    ];
+    all.extend_from_slice(args);
+    let output = stegano(&all, env_key, stdin, &scratch.0);
+    assert!(output.status.success(), "{:?}", output);
+    stego
+}
+
+/// Extracts the payload of `stego` with the extra `args`, returning the exit code and payload.
+fn decrypt(
+    scratch: &Scratch,
+    stego: &str,
+    args: &[&str],
+    env_key: Option<&str>,
+    stdin: &[u8],
+) -> (i32, Vec<u8>) {
+    let cleaned = scratch.path("cleaned.png");
+    let mut all = vec!["decrypt", "-i", stego, "-o", &cleaned, "--format", "raw"];
+    all.extend_from_slice(args);
+    let output = stegano(&all, env_key, stdin, &scratch.0);
+    (output.status.code().unwrap(), output.stdout)
+}
+
+#[test]
+fn environment_key_is_used_without_k() {
+    let scratch = Scratch::with_carrier("env-key");
+    let stego = encrypt(&scratch, &[], Some("from-env"), b"");
+    assert_eq!(
+        decrypt(&scratch, &stego, &["-k", "from-env"], None, b""),
+        (0, b"secret".to_vec())
+    );
+    assert_eq!(decrypt(&scratch, &stego, &[], None, b"").0, 6);
+    assert_eq!(
+        decrypt(&scratch, &stego, &[], Some("from-env"), b""),
+        (0, b"secret".to_vec())
+    );
+}
+
+#[test]
+fn k_overrides_the_environment() {
+    let scratch = Scratch::with_carrier("k-over-env");
+    let stego = encrypt(&scratch, &["-k", "typed"], Some("from-env"), b"");
+    assert_eq!(
+        decrypt(&scratch, &stego, &["-k", "typed"], Some("from-env"), b""),
+        (0, b"secret".to_vec())
+    );
+    assert_eq!(decrypt(&scratch, &stego, &[], Some("from-env"), b"").0, 6);
+}
+
+#[cfg(unix)]
+#[test]
+fn key_fd_overrides_the_environment() {
+    let scratch = Scratch::with_carrier("fd-over-env");
+    let stego = encrypt(&scratch, &["--key-fd", "0"], Some("from-env"), b"piped\n");
+    assert_eq!(
+        decrypt(
+            &scratch,
+            &stego,
+            &["--key-fd", "0"],
+            Some("from-env"),
+            b"piped"
+        ),
+        (0, b"secret".to_vec())
+    );
+    assert_eq!(
+        decrypt(&scratch, &stego, &["-k", "piped"], None, b""),
+        (0, b"secret".to_vec())
+    );
+}
+
+#[test]
+fn k_and_key_fd_together_are_a_usage_error() {
+    let scratch = Scratch::with_carrier("k-and-fd");
+    let stego = encrypt(&scratch, &[], None, b"");
+    let (code, _) = decrypt(
+        &scratch,
+        &stego,
+        &["-k", "key", "--key-fd", "0"],
+        None,
+        b"key",
+    );
+    assert_eq!(code, 2);
+}
+
+#[test]
+fn unopened_key_fd_is_a_usage_error() {
+    let scratch = Scratch::with_carrier("closed-fd");
+    let stego = encrypt(&scratch, &[], None, b"");
+    let (code, payload) = decrypt(&scratch, &stego, &["--key-fd", "987654"], None, b"");
+    assert!(payload.is_empty());
+    assert_eq!(code, if cfg!(unix) { 2 } else { 1 });
+}
+
+#[test]
+fn empty_xor_key_is_a_usage_error() {
+    let scratch = Scratch::with_carrier("empty-xor");
+    let (carrier, stego) = (scratch.path("carrier.png"), scratch.path("stego.png"));
+    let args = [
+        "encrypt", "-i", &carrier, "-o", &stego, "-p", "secret", "-s", "-k", "", "-a", "xor",
+    ];
+    let output = stegano(&args, None, b"", &scratch.0);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("The XOR key can't be empty!"));
+    assert!(!Path::new(&stego).exists());
+}
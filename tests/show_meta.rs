@@ -0,0 +1,126 @@
+//! `show-meta` lists the chunks of a PNG file where they really are, walking them by their
+//! declared lengths from the end of the signature.
+
+mod common;
+
+use common::Scratch;
+use std::process::Command;
+use stegano::png::{chunks, encode_grayscale, insert_chunk, read_chunks};
+
+/// Writes a PNG with a `tEXt` chunk into `scratch` and returns its bytes.
+fn write_png(scratch: &Scratch) -> Vec<u8> {
+    let png = encode_grayscale(2, 2, &[0, 64, 128, 255]).unwrap();
+    let (png, _) = insert_chunk(&png, b"tEXt", b"Comment\0hello", None).unwrap();
+    std::fs::write(scratch.0.join("image.png"), &png).unwrap();
+    png
+}
+
+/// Runs `show-meta` on the PNG with `args`, English messages and no configuration file.
+fn show_meta(scratch: &Scratch, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_stegano"))
+        .arg("show-meta")
+        .arg("-i")
+        .arg(scratch.0.join("image.png"))
+        .args(["--format", "human", "--no-hex", "--lang", "en"])
+        .args(args)
+        .env("NO_COLOR", "1")
+        .env("STEGANO_CONFIG", scratch.0.join("missing.toml"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Returns the offsets listed, in order.
+fn offsets(listing: &str) -> Vec<usize> {
+    listing
+        .lines()
+        .filter_map(|line| line.strip_prefix("Offset: "))
+        .map(|offset| offset.parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn listing_gives_the_offsets_and_types_of_the_chunks() {
+    let scratch = Scratch::new("show-meta-listing");
+    let png = write_png(&scratch);
+    let listing = show_meta(&scratch, &["--filter", "IHDR,IDAT,tEXt,IEND"]);
+    let chunks = read_chunks(&png).unwrap();
+    let expected: Vec<usize> = chunks.iter().map(|chunk| chunk.offset).collect();
+    assert_eq!(offsets(&listing), expected);
+    assert_eq!(expected[0], 8);
+    let types: Vec<&str> = listing
+        .lines()
+        .filter_map(|line| line.strip_prefix("Type: "))
+        .collect();
+    assert_eq!(types, ["IHDR", "IDAT", "tEXt", "IEND"]);
+    assert!(listing.contains("Summary: 4 chunks, 0 unknown types, 0 CRC errors"));
+}
+
+#[test]
+fn start_and_read_end_count_chunks() {
+    let scratch = Scratch::new("show-meta-positions");
+    let png = write_png(&scratch);
+    let chunks = read_chunks(&png).unwrap();
+    let listing = show_meta(&scratch, &["-s", "1", "-n", "2"]);
+    assert_eq!(offsets(&listing), [chunks[1].offset, chunks[2].offset]);
+    assert!(listing.contains("---- Chunk #1 ----") && !listing.contains("Chunk #0"));
+    let listing = show_meta(&scratch, &["-z", "-n", "1"]);
+    assert_eq!(offsets(&listing), [chunks[3].offset]);
+    assert!(listing.contains("---- Chunk #3 ----"));
+}
+
+#[test]
+fn listing_names_the_chunk_types() {
+    let scratch = Scratch::new("show-meta-names");
+    let png = write_png(&scratch);
+    let (png, _) = insert_chunk(&png, b"stEg", b"payload", None).unwrap();
+    std::fs::write(scratch.0.join("image.png"), png).unwrap();
+    let listing = show_meta(&scratch, &[]);
+    let lines: Vec<&str> = listing.lines().collect();
+    let named: Vec<(&str, &str)> = lines
+        .windows(2)
+        .filter_map(|pair| {
+            Some((
+                pair[0].strip_prefix("Type: ")?,
+                pair[1].strip_prefix("Name: ")?,
+            ))
+        })
+        .collect();
+    assert_eq!(
+        named,
+        [
+            ("IHDR", "Image header (critical, public)"),
+            ("IDAT", "Image data (critical, public)"),
+            ("tEXt", "Text (ancillary, public, safe to copy)"),
+            ("stEg", "unknown (ancillary, private, safe to copy)"),
+            ("IEND", "Image trailer (critical, public)"),
+        ]
+    );
+    assert!(listing.contains("Summary: 5 chunks, 1 unknown types, 0 CRC errors"));
+}
+
+#[test]
+fn pages_cover_the_chunks_of_the_summary() {
+    let scratch = Scratch::new("show-meta-pages");
+    let mut png = write_png(&scratch);
+    for _ in 0..119 {
+        png = insert_chunk(&png, b"tEXt", b"k\0v", None).unwrap().0;
+    }
+    // A last chunk running past the end of the file is left out of both.
+    let iend = png.len() - 12;
+    png.splice(iend.., *b"\0\0\0\x40tEXtComment\0hi");
+    std::fs::write(scratch.0.join("image.png"), &png).unwrap();
+    let listing = show_meta(&scratch, &["--page", "3", "--per-page", "50"]);
+    assert!(listing.contains("---- Chunk #100 ----") && listing.contains("---- Chunk #121 ----"));
+    assert!(!listing.contains("Chunk #99 ") && !listing.contains("Chunk #122 "));
+    assert!(listing.contains("Page 3 of 3: chunks 101 to 122 of 122"));
+    assert!(listing
+        .contains("Summary: 122 chunks, 0 unknown types, 0 CRC errors, the last one truncated"));
+    let expected: Vec<usize> = chunks(&png[..])
+        .map_while(Result::ok)
+        .skip(100)
+        .map(|chunk| chunk.offset)
+        .collect();
+    assert_eq!(offsets(&listing), expected);
+}